@@ -0,0 +1,162 @@
+// src/activity.rs
+
+use chrono::Utc;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::filter::filter_fn;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::config::JobsConfig;
+
+const DEFAULT_IDLE_THRESHOLD_MS: u64 = 2_000;
+/// How often a paused task checks again while a completion is in flight.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Tauri's span around each command, carrying its name.
+const IPC_HANDLE_SPAN: &str = "ipc::request::handle";
+const IPC_TARGET: &str = "tauri::ipc";
+/// Commands the frontend sends as the user types or edits, so calling one
+/// counts as activity. Polling and background commands are left out.
+const INTERACTIVE_COMMANDS: &[&str] = &[
+    "write_file",
+    "write_to_terminal",
+    "run_in_terminal",
+    "format_document",
+    "stage_edit",
+    "ai_edit_range",
+    "rename_symbol",
+    "search",
+    "search_symbols",
+    "search_similar_code",
+];
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+static IDLE_THRESHOLD_MS: AtomicU64 = AtomicU64::new(DEFAULT_IDLE_THRESHOLD_MS);
+/// When the user last did something, in milliseconds since the Unix epoch.
+static LAST_ACTIVITY: AtomicI64 = AtomicI64::new(0);
+static COMPLETIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Notes that the user just did something.
+pub fn note() {
+    LAST_ACTIVITY.store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+}
+
+/// When the user last did something, if they have since the app started.
+pub fn last_activity() -> Option<i64> {
+    Some(LAST_ACTIVITY.load(Ordering::Relaxed)).filter(|at| *at > 0)
+}
+
+/// Completions in flight.
+pub fn completions() -> usize {
+    COMPLETIONS.load(Ordering::Relaxed)
+}
+
+/// Counts a completion as in flight until dropped.
+pub struct CompletionGuard(());
+
+impl Drop for CompletionGuard {
+    fn drop(&mut self) {
+        COMPLETIONS.fetch_sub(1, Ordering::Relaxed);
+        // The reply is still arriving on screen
+        note();
+    }
+}
+
+pub fn completion_started() -> CompletionGuard {
+    COMPLETIONS.fetch_add(1, Ordering::Relaxed);
+    CompletionGuard(())
+}
+
+/// How long background work should wait before checking again, or `None`
+/// if the user has been idle for the threshold.
+fn busy_for() -> Option<Duration> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    if completions() > 0 {
+        return Some(POLL_INTERVAL);
+    }
+    let idle_for = Utc::now().timestamp_millis() - LAST_ACTIVITY.load(Ordering::Relaxed);
+    let threshold = IDLE_THRESHOLD_MS.load(Ordering::Relaxed) as i64;
+    (idle_for < threshold).then(|| Duration::from_millis((threshold - idle_for) as u64))
+}
+
+/// Whether the user is typing or editing, or a completion is in flight,
+/// so heavy background work should hold off.
+pub fn is_active() -> bool {
+    busy_for().is_some()
+}
+
+/// Waits until the user has been idle for the threshold; returns at once
+/// when they already are, or when pausing is turned off.
+pub async fn wait_until_idle() {
+    while let Some(wait) = busy_for() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Applies the pausing settings from `[jobs]`.
+pub fn configure(config: Option<&JobsConfig>) {
+    ENABLED.store(
+        config.and_then(|c| c.pause_while_active).unwrap_or(true),
+        Ordering::Relaxed,
+    );
+    IDLE_THRESHOLD_MS.store(
+        config
+            .and_then(|c| c.idle_threshold_ms)
+            .unwrap_or(DEFAULT_IDLE_THRESHOLD_MS),
+        Ordering::Relaxed,
+    );
+}
+
+/// Notes activity whenever the frontend calls one of the
+/// `INTERACTIVE_COMMANDS`, from Tauri's IPC spans.
+pub fn layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    // Only depends on the callsite, so it is checked once per callsite
+    let filter = filter_fn(|metadata| {
+        metadata.target().starts_with(IPC_TARGET) && metadata.name() == IPC_HANDLE_SPAN
+    });
+    CommandActivity.with_filter(filter)
+}
+
+struct CommandActivity;
+
+impl<S> Layer<S> for CommandActivity
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+        let mut command = CommandName(None);
+        attrs.record(&mut command);
+        if command
+            .0
+            .is_some_and(|name| INTERACTIVE_COMMANDS.contains(&name.as_str()))
+        {
+            note();
+        }
+    }
+}
+
+struct CommandName(Option<String>);
+
+impl Visit for CommandName {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "cmd" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "cmd" {
+            self.0 = Some(format!("{:?}", value).trim_matches('"').to_string());
+        }
+    }
+}
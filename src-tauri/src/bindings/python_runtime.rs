@@ -1,16 +1,16 @@
 use anyhow::{Context, Result};
-use once_cell::sync::OnceCell;
-use parking_lot::Mutex;
 use pyo3::prelude::*;
 use std::{env, fs, path::PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use tokio::sync::Mutex as AsyncMutex;
+use std::time::Instant;
+use tracing::{debug, error, info, warn};
 
-// Global initialization guards
-static INIT_GUARD: OnceCell<Arc<AsyncMutex<()>>> = OnceCell::new();
+use crate::commands::startup::{self, Subsystem};
+use crate::error::MightyError;
+use crate::state::app_state;
+
+// Global initialization guard
 static IS_INITIALIZED: AtomicBool = AtomicBool::new(false);
-static PYTHON_RUNTIME: OnceCell<Mutex<Option<PythonRuntime>>> = OnceCell::new();
 
 pub struct PythonRuntime {
     python_dir: PathBuf,
@@ -57,8 +57,8 @@ impl PythonRuntime {
         let pythonpath_str = pythonpath.to_string_lossy().into_owned();
 
         env::set_var("PYTHONPATH", pythonpath.clone());
-        println!("Python directory: {}", self.python_dir.display());
-        println!("PYTHONPATH set to: {}", pythonpath_str);
+        debug!("Python directory: {}", self.python_dir.display());
+        debug!("PYTHONPATH set to: {}", pythonpath_str);
 
         Python::with_gil(|py| -> PyResult<()> {
             // Set up sys.path
@@ -83,22 +83,22 @@ impl PythonRuntime {
     fn verify_package<'py>(&self, py: Python<'py>, package: &str) -> PyResult<()> {
         match py.import(package) {
             Ok(_) => {
-                println!("Successfully imported {}", package);
+                debug!("Successfully imported {}", package);
                 Ok(())
             }
             Err(e) => {
-                println!("Failed to import {}: {}", package, e);
+                error!("Failed to import {}: {}", package, e);
                 if let Ok(modname) = e.get_type(py).name() {
                     match modname.to_string_lossy().as_ref() {
-                        "ModuleNotFoundError" => println!(
+                        "ModuleNotFoundError" => warn!(
                             "Module not found. Please ensure {} is installed correctly.",
                             package
                         ),
-                        "ImportError" => println!(
+                        "ImportError" => warn!(
                             "Import error. This might be due to missing dependencies for {}.",
                             package
                         ),
-                        _ => println!("Unexpected error type: {}", modname.to_string_lossy())
+                        _ => warn!("Unexpected error type: {}", modname.to_string_lossy())
                     }
                 }
                 Err(e)
@@ -144,64 +144,63 @@ fn cleanup_python_locks() -> Result<()> {
     Ok(())
 }
 
-pub async fn initialize_python_runtime() -> Result<(), String> {
-    // Get or initialize the guard
-    let guard = INIT_GUARD.get_or_init(|| Arc::new(AsyncMutex::new(())));
-    
-    // Acquire the lock to ensure only one initialization happens at a time
-    let _lock = guard.lock().await;
-    
-    // Check if already initialized
+/// Sets Python up if it isn't yet. It starts on first use rather than at
+/// startup; the runtime's lock makes concurrent first users wait for one
+/// setup.
+fn ensure_runtime() -> Result<(), String> {
     if IS_INITIALIZED.load(Ordering::SeqCst) {
         return Ok(());
     }
+    let mut runtime_slot = app_state().python.lock();
+    if runtime_slot.is_none() {
+        let started = Instant::now();
+        startup::mark_starting(Subsystem::Python);
+        info!("Initializing the Python environment");
 
-    println!("=== Python Environment Initialization ===");
-
-    // Initialize Python runtime
-    PYTHON_RUNTIME.get_or_try_init::<_, String>(|| {
         // Initialize Python once at the start
         pyo3::prepare_freethreaded_python();
 
-        let runtime = PythonRuntime::new().map_err(|e| e.to_string())?;
-        runtime.setup_python_environment().map_err(|e| e.to_string())?;
-
-        println!("=== Python Environment Successfully Initialized ===");
-        Ok(Mutex::new(Some(runtime)))
-    })
-    .map_err(|e| format!("Failed to initialize Python runtime: {}", e))?;
-
+        let runtime = PythonRuntime::new()
+            .and_then(|runtime| runtime.setup_python_environment().map(|_| runtime))
+            .map_err(|e| {
+                let error = format!("Failed to initialize Python runtime: {}", e);
+                startup::mark_failed(Subsystem::Python, error.clone());
+                error
+            })?;
+
+        info!("Python environment initialized");
+        *runtime_slot = Some(runtime);
+        startup::mark_ready(Subsystem::Python, started);
+    }
     IS_INITIALIZED.store(true, Ordering::SeqCst);
     Ok(())
 }
 
 #[tauri::command]
-pub async fn cleanup_all_systems() -> Result<(), String> {
+pub async fn cleanup_all_systems() -> Result<(), MightyError> {
     if IS_INITIALIZED.load(Ordering::SeqCst) {
-        // Acquire initialization lock
-        if let Some(guard) = INIT_GUARD.get() {
-            let _lock = guard.lock().await;
-            
-            // Clean up Python runtime
-            if let Some(runtime_mutex) = PYTHON_RUNTIME.get() {
-                if let Some(runtime) = runtime_mutex.lock().as_ref() {
-                    if let Err(e) = runtime.cleanup() {
-                        eprintln!("Error cleaning up Python runtime: {}", e);
-                    }
-                }
+        // Clean up Python runtime
+        if let Some(runtime) = app_state().python.lock().as_ref() {
+            if let Err(e) = runtime.cleanup() {
+                error!("Error cleaning up Python runtime: {}", e);
             }
-            
-            // Reset initialization flag
-            IS_INITIALIZED.store(false, Ordering::SeqCst);
         }
+
+        // Reset initialization flag
+        IS_INITIALIZED.store(false, Ordering::SeqCst);
     }
     Ok(())
 }
 
+pub fn is_initialized() -> bool {
+    IS_INITIALIZED.load(Ordering::SeqCst)
+}
+
 // Helper function for Python commands
 pub fn run_python<F, R>(f: F) -> Result<R, String>
 where
     F: FnOnce(Python<'_>) -> PyResult<R>,
 {
+    ensure_runtime()?;
     Python::with_gil(f).map_err(|e| e.to_string())
 }
\ No newline at end of file
@@ -0,0 +1,507 @@
+// src/commands/actions.rs
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use tauri::ipc::Request;
+use tauri::{command, AppHandle, Manager};
+
+use super::api::{anthropic_completion, AnthropicRequest};
+use super::deps::dependency_report;
+use super::dev_server::{list_dev_servers, start_dev_server, stop_dev_server, DevServerTask};
+use super::dry_run::{self, DRY_RUN_CODE};
+use super::encoding::LineEnding;
+use super::fs::{read_directory, read_file, write_file_as};
+use super::permissions::{authorize, Actor, Capability};
+use super::search::{search, SearchSource};
+use crate::context::context::get_context;
+use crate::error::MightyError;
+use crate::state::AppState;
+
+type Handler = Arc<
+    dyn Fn(AppHandle, Actor, Value) -> BoxFuture<'static, Result<Value, MightyError>> + Send + Sync,
+>;
+
+/// An invokable action, shown in the command palette and, unless
+/// `agent_tool` is off, offered to the agent as a tool.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActionSpec {
+    /// Dotted, e.g. `fs.read`; also the local API server's method name.
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    /// JSON Schema for the arguments.
+    pub params: Value,
+    /// What the action needs the agent to be allowed. The handler checks it
+    /// against the caller, since only it knows the file or command involved.
+    pub capability: Option<Capability>,
+    pub agent_tool: bool,
+}
+
+impl ActionSpec {
+    pub(crate) fn new(id: &str, title: &str, description: &str, params: Value) -> Self {
+        Self {
+            id: id.to_string(),
+            title: title.to_string(),
+            description: description.to_string(),
+            params,
+            capability: None,
+            agent_tool: true,
+        }
+    }
+
+    pub(crate) fn capability(mut self, capability: Capability) -> Self {
+        self.capability = Some(capability);
+        self
+    }
+
+    /// Keeps the action out of the agent's tools.
+    pub(crate) fn user_only(mut self) -> Self {
+        self.agent_tool = false;
+        self
+    }
+}
+
+struct Action {
+    spec: ActionSpec,
+    handler: Handler,
+}
+
+static ACTIONS: Lazy<RwLock<BTreeMap<String, Action>>> = Lazy::new(|| RwLock::new(BTreeMap::new()));
+
+/// A tool definition in the shape the Anthropic API takes.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentTool {
+    pub name: String,
+    pub description: String,
+    pub input_schema: Value,
+}
+
+/// Adds an action, replacing any registered under the same id.
+pub(crate) fn register<F, Fut>(spec: ActionSpec, handler: F)
+where
+    F: Fn(AppHandle, Actor, Value) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Value, MightyError>> + Send + 'static,
+{
+    let handler: Handler = Arc::new(move |app, actor, params| handler(app, actor, params).boxed());
+    ACTIONS
+        .write()
+        .insert(spec.id.clone(), Action { spec, handler });
+}
+
+pub(crate) fn parse<T: DeserializeOwned>(params: Value) -> Result<T, MightyError> {
+    // Actions whose params are all optional can be called without any
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params)
+        .map_err(|e| MightyError::request("INVALID_PARAMS", &e.to_string()))
+}
+
+pub(crate) fn to_value<T: Serialize>(value: T) -> Result<Value, MightyError> {
+    serde_json::to_value(value).map_err(|e| MightyError::from(e.to_string()))
+}
+
+/// Runs action `id` on behalf of `actor`.
+pub(crate) async fn invoke(
+    app: &AppHandle,
+    actor: Actor,
+    id: &str,
+    params: Value,
+) -> Result<Value, MightyError> {
+    let action = ACTIONS.read().get(id).map(|action| {
+        let allowed = actor == Actor::User || action.spec.agent_tool;
+        (allowed, action.handler.clone())
+    });
+    match action {
+        Some((true, handler)) => handler(app.clone(), actor, params).await,
+        Some((false, _)) => Err(MightyError::request(
+            "USER_ONLY_ACTION",
+            &format!("Only the user can run {}", id),
+        )),
+        None => Err(MightyError::request(
+            "UNKNOWN_ACTION",
+            &format!("No action named {}", id),
+        )),
+    }
+}
+
+/// Tool names allow letters, digits, `_` and `-`, so dots become `__`.
+fn tool_name(id: &str) -> String {
+    id.replace('.', "__")
+}
+
+/// The agent's tools: every action it may run.
+pub(crate) fn agent_tools() -> Vec<AgentTool> {
+    ACTIONS
+        .read()
+        .values()
+        .filter(|action| action.spec.agent_tool)
+        .map(|action| AgentTool {
+            name: tool_name(&action.spec.id),
+            description: action.spec.description.clone(),
+            input_schema: action.spec.params.clone(),
+        })
+        .collect()
+}
+
+/// Runs the action behind an agent tool call.
+pub(crate) async fn invoke_tool(
+    app: &AppHandle,
+    name: &str,
+    input: Value,
+) -> Result<Value, MightyError> {
+    let id = ACTIONS
+        .read()
+        .keys()
+        .find(|id| tool_name(id) == name)
+        .cloned()
+        .ok_or_else(|| {
+            MightyError::request("UNKNOWN_ACTION", &format!("No tool named {}", name))
+        })?;
+    invoke(app, Actor::Agent, &id, input).await
+}
+
+/// Every registered action, by id.
+#[command]
+pub async fn list_actions() -> Result<Vec<ActionSpec>, String> {
+    Ok(ACTIONS
+        .read()
+        .values()
+        .map(|action| action.spec.clone())
+        .collect())
+}
+
+/// Runs an action from the palette, or from the agent when the call
+/// carries its actor header.
+#[command]
+pub async fn invoke_action(
+    app: AppHandle,
+    request: Request<'_>,
+    id: String,
+    args: Option<Value>,
+) -> Result<Value, MightyError> {
+    invoke(&app, Actor::of(&request), &id, args.unwrap_or(Value::Null)).await
+}
+
+/// The actions offered to the agent, as tool definitions for a model
+/// request.
+#[command]
+pub async fn list_agent_tools() -> Result<Vec<AgentTool>, String> {
+    Ok(agent_tools())
+}
+
+/// Runs the action behind a tool call the model made, as the agent.
+#[command]
+pub async fn call_agent_tool(
+    app: AppHandle,
+    name: String,
+    input: Option<Value>,
+) -> Result<Value, MightyError> {
+    invoke_tool(&app, &name, input.unwrap_or(Value::Null)).await
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    query: String,
+    limit: Option<usize>,
+    sources: Option<Vec<SearchSource>>,
+}
+
+#[derive(Deserialize)]
+struct QueryParams {
+    query: String,
+}
+
+#[derive(Deserialize)]
+struct ReadParams {
+    path: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListParams {
+    path: String,
+    include_metadata: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WriteParams {
+    path: String,
+    content: String,
+    encoding: Option<String>,
+    line_ending: Option<LineEnding>,
+    with_bom: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct RunTaskParams {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct StopTaskParams {
+    id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DependencyReportParams {
+    refresh: Option<bool>,
+    outdated_only: Option<bool>,
+}
+
+/// Registers the core actions. Params use the same names as the matching
+/// commands' arguments.
+pub(crate) fn register_builtin() {
+    register(
+        ActionSpec::new(
+            "search",
+            "Search",
+            "Search the workspace's text, its indexed code and Greptile.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "limit": { "type": "integer", "minimum": 1 },
+                    "sources": {
+                        "type": "array",
+                        "items": { "type": "string", "enum": ["semantic", "text", "greptile"] }
+                    }
+                },
+                "required": ["query"]
+            }),
+        ),
+        |app, _, params| async move {
+            let params: SearchParams = parse(params)?;
+            let state = app.state::<AppState>();
+            to_value(search(params.query, params.limit, params.sources, state).await?)
+        },
+    );
+    register(
+        ActionSpec::new(
+            "context.query",
+            "Query code context",
+            "Find the indexed code most relevant to a question.",
+            json!({
+                "type": "object",
+                "properties": { "query": { "type": "string" } },
+                "required": ["query"]
+            }),
+        ),
+        |_, _, params| async move {
+            let params: QueryParams = parse(params)?;
+            to_value(get_context(params.query).await?)
+        },
+    );
+    register(
+        ActionSpec::new(
+            "complete",
+            "Complete",
+            "Send messages to the configured model and return its reply.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string" },
+                    "model": { "type": "string" },
+                    "max_tokens": { "type": "integer", "minimum": 1 },
+                    "messages": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "role": { "type": "string" },
+                                "content": { "type": "string" }
+                            },
+                            "required": ["role", "content"]
+                        }
+                    },
+                    "conversation_id": { "type": "string" }
+                },
+                "required": ["id", "model", "max_tokens", "messages"]
+            }),
+        )
+        .user_only(),
+        |app, _, params| async move {
+            let request: AnthropicRequest = parse(params)?;
+            to_value(anthropic_completion(app.clone(), request, app.state()).await?)
+        },
+    );
+    register(
+        ActionSpec::new(
+            "fs.read",
+            "Read file",
+            "Read a file in the workspace.",
+            json!({
+                "type": "object",
+                "properties": { "path": { "type": "string" } },
+                "required": ["path"]
+            }),
+        ),
+        |_, _, params| async move {
+            let params: ReadParams = parse(params)?;
+            to_value(read_file(params.path).await?)
+        },
+    );
+    register(
+        ActionSpec::new(
+            "fs.list",
+            "List directory",
+            "List the entries of a directory in the workspace.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string" },
+                    "includeMetadata": { "type": "boolean" }
+                },
+                "required": ["path"]
+            }),
+        ),
+        |_, _, params| async move {
+            let params: ListParams = parse(params)?;
+            to_value(read_directory(params.path, params.include_metadata).await?)
+        },
+    );
+    register(
+        ActionSpec::new(
+            "fs.write",
+            "Write file",
+            "Write a file in the workspace, creating it and its directories as needed.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string" },
+                    "content": { "type": "string" },
+                    "encoding": { "type": "string" },
+                    "lineEnding": { "type": "string", "enum": ["lf", "crlf"] },
+                    "withBom": { "type": "boolean" }
+                },
+                "required": ["path", "content"]
+            }),
+        )
+        .capability(Capability::WriteFile),
+        |app, actor, params| async move {
+            let params: WriteParams = parse(params)?;
+            write_file_as(
+                &app,
+                actor,
+                params.path,
+                params.content,
+                params.encoding,
+                params.line_ending,
+                params.with_bom,
+            )
+            .await?;
+            Ok(Value::Null)
+        },
+    );
+    register(
+        ActionSpec::new(
+            "tasks.list",
+            "List tasks",
+            "List the workspace's configured tasks and those running.",
+            json!({ "type": "object", "properties": {} }),
+        ),
+        |app, _, _| async move {
+            let tasks = app.state::<AppState>().config.lock().await.tasks.clone();
+            let running = list_dev_servers().await?;
+            Ok(json!({ "tasks": tasks, "running": running }))
+        },
+    );
+    register(
+        ActionSpec::new(
+            "tasks.run",
+            "Run task",
+            "Start one of the workspace's configured tasks by name.",
+            json!({
+                "type": "object",
+                "properties": { "name": { "type": "string" } },
+                "required": ["name"]
+            }),
+        )
+        .capability(Capability::RunCommand),
+        |app, actor, params| async move {
+            let params: RunTaskParams = parse(params)?;
+            let task = app
+                .state::<AppState>()
+                .config
+                .lock()
+                .await
+                .tasks
+                .get(&params.name)
+                .cloned();
+            let Some(task) = task else {
+                return Err(MightyError::request(
+                    "UNKNOWN_TASK",
+                    &format!("No task named {}", params.name),
+                ));
+            };
+            if dry_run::is_active(actor) {
+                let details = json!({ "command": task.command, "args": task.args });
+                dry_run::plan_step("tasks.run", &params.name, None, details);
+                return Err(MightyError::request(
+                    DRY_RUN_CODE,
+                    &dry_run::planned_error("tasks.run"),
+                ));
+            }
+            authorize(&app, actor, Capability::RunCommand, &params.name).await?;
+            let task = DevServerTask {
+                name: Some(params.name),
+                command: task.command,
+                args: Some(task.args),
+                cwd: task.cwd,
+                env: Some(task.env),
+                restart: None,
+                max_restarts: None,
+            };
+            to_value(start_dev_server(app.clone(), task).await?)
+        },
+    );
+    register(
+        ActionSpec::new(
+            "tasks.stop",
+            "Stop task",
+            "Stop a running task by the id it was started with.",
+            json!({
+                "type": "object",
+                "properties": { "id": { "type": "string" } },
+                "required": ["id"]
+            }),
+        ),
+        |_, _, params| async move {
+            let params: StopTaskParams = parse(params)?;
+            stop_dev_server(params.id).await?;
+            Ok(Value::Null)
+        },
+    );
+    register(
+        ActionSpec::new(
+            "deps.report",
+            "Dependency report",
+            "List the workspace's dependencies with the versions in use, the latest releases \
+             and known advisories, to ground upgrade suggestions.",
+            json!({
+                "type": "object",
+                "properties": {
+                    "refresh": { "type": "boolean" },
+                    "outdatedOnly": { "type": "boolean" }
+                }
+            }),
+        ),
+        |_, _, params| async move {
+            let params: DependencyReportParams = parse(params)?;
+            let report = dependency_report(params.refresh.unwrap_or(false)).await?;
+            if params.outdated_only.unwrap_or(false) {
+                to_value(report.actionable())
+            } else {
+                to_value(report)
+            }
+        },
+    );
+}
@@ -0,0 +1,35 @@
+// src/commands/activity.rs
+
+use serde::Serialize;
+use tauri::command;
+
+use crate::activity;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityStatus {
+    /// Heavy background work is holding off for the user.
+    pub active: bool,
+    pub completions_in_flight: usize,
+    /// Milliseconds since the Unix epoch.
+    pub last_activity: Option<i64>,
+}
+
+/// Tells the backend the user is typing or otherwise at work in the editor,
+/// so indexing, embedding and git fetches pause until they stop. The
+/// frontend calls this at most every few hundred milliseconds while keys
+/// or the pointer are in use.
+#[command]
+pub async fn report_activity() -> Result<(), String> {
+    activity::note();
+    Ok(())
+}
+
+/// Whether background work is paused for the user, and since when.
+#[command]
+pub async fn get_activity_status() -> Result<ActivityStatus, String> {
+    Ok(ActivityStatus {
+        active: activity::is_active(),
+        completions_in_flight: activity::completions(),
+        last_activity: activity::last_activity(),
+    })
+}
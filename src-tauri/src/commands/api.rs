@@ -1,22 +1,37 @@
 // src-tauri/src/commands/api.rs
 
 use serde::{Deserialize, Serialize};
-use tauri::State;
-use tokio::sync::Mutex;
-use std::sync::Arc;
-use crate::config::AppConfig;
-use log::{error, info};
+use std::collections::HashMap;
+use tauri::{AppHandle, State};
+use crate::state::AppState;
+use crate::activity;
+use crate::context::chat_memory;
+use crate::error::MightyError;
+use crate::http_client;
+use crate::secrets::Redactor;
+use super::auth::vault;
+use super::conversation::{self, MAX_COMPACTIONS};
+use super::notifications::{notify, NotificationLevel};
+use super::offline::ensure_online;
+use super::redaction;
+use super::recorder::{self, RecordedRequest};
+use log::{debug, error, info};
 use reqwest;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnthropicRequest {
     pub id: String,
     pub model: String,
     pub max_tokens: i32,
     pub messages: Vec<AnthropicMessage>,
+    /// The chat the messages belong to. When the history outgrows the
+    /// model's context window its start is summarized and the summary kept
+    /// with this id for later requests.
+    #[serde(default)]
+    pub conversation_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnthropicMessage {
     pub role: String,
     pub content: String,
@@ -54,31 +69,84 @@ struct ApiResponse {
     usage: Option<AnthropicUsage>,
 }
 
-#[tauri::command]
-pub async fn anthropic_completion(
-    request: AnthropicRequest,
-    config: State<'_, Arc<Mutex<AppConfig>>>,
-) -> Result<String, String> {
-    info!("=== Starting Anthropic completion ===");
-    info!("Incoming request ID: {}", request.id);
-    
-    let config_guard = config.lock().await;
-    let api_key = match &config_guard.anthropic {
-        Some(anthropic) => anthropic.api_key.as_str(),
-        None => {
-            error!("Anthropic config missing in AppConfig");
-            return Err("Anthropic API key not configured.".to_string());
-        }
+/// Raises a notification when a request failed because the account is out
+/// of quota, which otherwise only shows up as a failed completion.
+fn notify_quota_error(app: &AppHandle, status: reqwest::StatusCode, body: &str) {
+    let (title, detail) = if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        (
+            "Anthropic rate limit reached",
+            "Requests are being throttled; try again shortly.",
+        )
+    } else if body.contains("credit balance") {
+        (
+            "Anthropic credit balance is too low",
+            "Add credits to your Anthropic account to keep using AI features.",
+        )
+    } else {
+        return;
     };
+    notify(
+        app,
+        NotificationLevel::Warning,
+        "providers",
+        title,
+        Some(detail.to_string()),
+    );
+}
 
-    let client = reqwest::Client::new();
+/// The Anthropic API key. A key saved in the vault wins over one in
+/// config.toml.
+pub(crate) async fn anthropic_api_key(state: &AppState) -> Option<String> {
+    let vault_key = vault().get("anthropic").unwrap_or_else(|e| {
+        error!("{}", e);
+        None
+    });
+    match vault_key {
+        Some(key) => Some(key),
+        None => state
+            .config
+            .lock()
+            .await
+            .anthropic
+            .as_ref()
+            .and_then(|anthropic| anthropic.api_key.clone()),
+    }
+}
+
+/// Sends `messages` for `request` and returns the status and body of the
+/// response, recording the exchange.
+async fn send_messages(
+    api_key: &str,
+    request: &AnthropicRequest,
+    messages: &[AnthropicMessage],
+    redactor: &mut Redactor,
+) -> Result<(reqwest::StatusCode, String), String> {
+    let client = http_client::client("anthropic")?;
+
+    let mut messages = serde_json::to_value(messages).map_err(|e| e.to_string())?;
+    redactor.redact_json(&mut messages);
 
     let anthropic_api_request = serde_json::json!({
         "model": request.model,
         "max_tokens": request.max_tokens,
-        "messages": request.messages,
+        "messages": messages,
     });
+    redaction::remember(&request.id, "anthropic", &anthropic_api_request, redactor.redactions());
 
+    let headers = HashMap::from([
+        ("x-api-key".to_string(), api_key.to_string()),
+        ("content-type".to_string(), "application/json".to_string()),
+        ("anthropic-version".to_string(), "2023-06-01".to_string()),
+    ]);
+    let mut recording = RecordedRequest::new(
+        "anthropic",
+        "POST",
+        "/v1/messages",
+        &headers,
+        Some(&anthropic_api_request),
+    );
+
+    let _turn = http_client::acquire("anthropic").await;
     info!("Sending request to Anthropic API");
     let response = client
         .post("https://api.anthropic.com/v1/messages")
@@ -87,25 +155,99 @@ pub async fn anthropic_completion(
         .header("anthropic-version", "2023-06-01")
         .json(&anthropic_api_request)
         .send()
-        .await
-        .map_err(|e| {
+        .await;
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => {
             error!("API request failed: {}", e);
-            e.to_string()
-        })?;
+            recording.error = Some(e.to_string());
+            recorder::record(recording, &[api_key]);
+            return Err(e.to_string());
+        }
+    };
 
     let status = response.status();
-    let response_text = response.text().await.map_err(|e| {
+    recording.status = Some(status.as_u16());
+    let response_text = response.text().await;
+    match &response_text {
+        Ok(text) => recording.response_body = Some(text.clone()),
+        Err(e) => recording.error = Some(e.to_string()),
+    }
+    recorder::record(recording, &[api_key]);
+    let response_text = response_text.map_err(|e| {
         error!("Failed to get response text: {}", e);
         e.to_string()
     })?;
+    Ok((status, response_text))
+}
+
+#[tauri::command]
+pub async fn anthropic_completion(
+    app: AppHandle,
+    request: AnthropicRequest,
+    state: State<'_, AppState>,
+) -> Result<String, MightyError> {
+    info!("=== Starting Anthropic completion ===");
+    info!("Incoming request ID: {}", request.id);
+    ensure_online()?;
+    // Background indexing holds off until the reply is in
+    let _completion = activity::completion_started();
+    
+    let api_key = match anthropic_api_key(&state).await {
+        Some(key) => key,
+        None => {
+            error!("Anthropic API key missing from the vault and AppConfig");
+            return Err(MightyError::auth(
+                "MISSING_API_KEY",
+                "Anthropic API key not configured.",
+            ));
+        }
+    };
+
+    let conversation_id = request.conversation_id.as_deref();
+    // Secrets and the redaction policy's paths and names stay here; the
+    // reply gets the secrets back
+    let mut redactor = Redactor::new();
+    let mut messages = conversation::apply_summary(conversation_id, &request.messages);
+    let mut attempt = 0;
+    let (status, response_text) = loop {
+        let (status, text) = send_messages(&api_key, &request, &messages, &mut redactor).await?;
+        if attempt == MAX_COMPACTIONS || !conversation::is_context_overflow(status, &text) {
+            break (status, text);
+        }
+        // Too long for the model: summarize older turns and try again
+        info!("Request {} overflowed the context window; summarizing earlier messages", request.id);
+        messages = conversation::compact(
+            &app,
+            &api_key,
+            &request.id,
+            conversation_id,
+            &request.messages,
+            attempt,
+            &mut redactor,
+        )
+        .await?;
+        attempt += 1;
+    };
 
     if !status.is_success() {
         error!("API request failed with status {}: {}", status, response_text);
-        return Err(format!(
-            "API request failed with status {}: {}",
-            status,
-            response_text
-        ));
+        notify_quota_error(&app, status, &response_text);
+        if conversation::is_context_overflow(status, &response_text) {
+            return Err(MightyError::Service {
+                code: "CONTEXT_OVERFLOW".to_string(),
+                message: format!(
+                    "The conversation is still too long for {} after summarizing earlier messages",
+                    request.model
+                ),
+                details: None,
+            });
+        }
+        return Err(MightyError::Service {
+            code: "API_ERROR".to_string(),
+            message: format!("API request failed with status {}", status),
+            details: Some(response_text),
+        });
     }
 
     info!("Received response from Anthropic API");
@@ -120,12 +262,37 @@ pub async fn anthropic_completion(
         id: request.id,
         text: anthropic_response.content
             .first()
-            .map(|c| c.text.clone())
+            .map(|c| redactor.restore(&c.text))
             .unwrap_or_default(),
         model: anthropic_response.model,
         usage: anthropic_response.usage,
     };
 
+    // The exchange is kept for recalling in later chats, without holding up the reply
+    if let (Some(conversation_id), Some(question)) =
+        (request.conversation_id, request.messages.last())
+    {
+        if question.role == "user" {
+            let turns = vec![
+                question.clone(),
+                AnthropicMessage {
+                    role: "assistant".to_string(),
+                    content: api_response.text.clone(),
+                },
+            ];
+            let first_turn = request.messages.len() - 1;
+            tauri::async_runtime::spawn(async move {
+                // Embedding can wait until the user pauses
+                activity::wait_until_idle().await;
+                let remembered =
+                    chat_memory::remember_turns(&conversation_id, first_turn, &turns).await;
+                if let Err(e) = remembered {
+                    debug!("Failed to remember turns of {}: {}", conversation_id, e);
+                }
+            });
+        }
+    }
+
     let response_json = serde_json::to_string(&api_response).map_err(|e| {
         error!("Failed to serialize response: {}", e);
         e.to_string()
@@ -0,0 +1,490 @@
+// src/commands/api_server.rs
+
+use axum::body::Bytes;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, State as RouterState};
+use axum::http::{header::AUTHORIZATION, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::ipc::Request;
+use tauri::{command, AppHandle, EventId, Listener};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, watch};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::actions::{invoke, parse};
+use super::auth::vault;
+use super::permissions::Actor;
+use crate::config::{ApiServerConfig, APP_IDENTIFIER};
+use crate::error::MightyError;
+
+const DEFAULT_PORT: u16 = 7331;
+/// Vault entry holding the token clients must present.
+pub(crate) const TOKEN_SECRET: &str = "api_server_token";
+/// Written next to the config file while the server is listening, so editor
+/// plugins can find the port and token without being configured.
+const DISCOVERY_FILE: &str = "api-server.json";
+/// Events a WebSocket client may subscribe to.
+const FORWARDED_EVENTS: [&str; 11] = [
+    "file-watch-event",
+    "diagnostics-updated",
+    "dev-server-status",
+    "dev-server-url",
+    "job-status",
+    "job-progress",
+    "test-result",
+    "test-run-finished",
+    "subsystem-ready",
+    "subsystem-failed",
+    "config-updated",
+];
+
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    enabled: bool,
+    port: u16,
+}
+
+struct Running {
+    port: u16,
+    /// Set to stop the server and close its WebSocket connections.
+    stop: watch::Sender<bool>,
+}
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static SETTINGS: Mutex<Settings> = Mutex::new(Settings {
+    enabled: false,
+    port: DEFAULT_PORT,
+});
+static RUNNING: Mutex<Option<Running>> = Mutex::new(None);
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+/// The token from the vault, loaded when the server starts so requests
+/// don't each go to the keychain.
+static TOKEN: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Serialize)]
+pub struct ApiServerStatus {
+    pub enabled: bool,
+    pub running: bool,
+    /// e.g. `http://127.0.0.1:7331`.
+    pub url: Option<String>,
+    /// For pasting into an editor plugin's settings. Only shown to the user.
+    pub token: Option<String>,
+    /// Why the server last failed to start.
+    pub error: Option<String>,
+}
+
+#[derive(Clone)]
+struct Server {
+    app: AppHandle,
+    stop: watch::Receiver<bool>,
+}
+
+/// Applies the `[api_server]` config, starting, restarting or stopping the
+/// server to match.
+pub(crate) fn configure(config: Option<&ApiServerConfig>) {
+    *SETTINGS.lock() = Settings {
+        enabled: config.and_then(|config| config.enabled).unwrap_or(false),
+        port: config
+            .and_then(|config| config.port)
+            .unwrap_or(DEFAULT_PORT),
+    };
+    apply();
+}
+
+/// Starts the server if it is enabled. Called once the app is set up.
+pub(crate) fn initialize_api_server(app: AppHandle) {
+    if APP.set(app).is_ok() {
+        apply();
+    }
+}
+
+/// Stops the server on exit.
+pub fn stop_api_server() {
+    if let Some(running) = RUNNING.lock().take() {
+        let _ = running.stop.send(true);
+    }
+    remove_discovery();
+}
+
+fn apply() {
+    let Some(app) = APP.get() else {
+        return;
+    };
+    let settings = *SETTINGS.lock();
+    let wanted = settings.enabled.then_some(settings.port);
+    let mut running = RUNNING.lock();
+    if running.as_ref().map(|running| running.port) == wanted {
+        return;
+    }
+    if let Some(previous) = running.take() {
+        let _ = previous.stop.send(true);
+    }
+    if let Some(port) = wanted {
+        let (stop, stopped) = watch::channel(false);
+        *running = Some(Running { port, stop });
+        let server = Server {
+            app: app.clone(),
+            stop: stopped,
+        };
+        tauri::async_runtime::spawn(serve(server, port));
+    }
+}
+
+async fn serve(server: Server, port: u16) {
+    if let Err(e) = listen(server, port).await {
+        warn!("Local API server on port {} failed: {}", port, e);
+        *LAST_ERROR.lock() = Some(e);
+        let mut running = RUNNING.lock();
+        if running.as_ref().is_some_and(|running| running.port == port) {
+            *running = None;
+        }
+    }
+    // A server restarted on another port has written its own file
+    if RUNNING.lock().is_none() {
+        remove_discovery();
+    }
+}
+
+async fn listen(server: Server, port: u16) -> Result<(), String> {
+    let token = token()?;
+    let listener = bind_listener(port).await?;
+    *LAST_ERROR.lock() = None;
+    if let Err(e) = write_discovery(port, &token) {
+        warn!("Failed to write the API server discovery file: {}", e);
+    }
+    info!("Local API server listening on {}", url(port));
+
+    let mut stop = server.stop.clone();
+    let router = Router::new()
+        .route("/v1/health", get(health))
+        .route("/v1/ws", get(websocket))
+        .route("/v1/:method", post(call))
+        .with_state(server);
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async move {
+            let _ = stop.changed().await;
+        })
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn bind_listener(port: u16) -> Result<TcpListener, String> {
+    // The server being replaced may not have let go of the port yet
+    let mut last_error = None;
+    for _ in 0..10 {
+        match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => return Ok(listener),
+            Err(e) => last_error = Some(e),
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    Err(format!(
+        "Failed to listen on port {}: {}",
+        port,
+        last_error.map(|e| e.to_string()).unwrap_or_default()
+    ))
+}
+
+fn url(port: u16) -> String {
+    format!("http://127.0.0.1:{}", port)
+}
+
+fn new_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// The token clients must present, created on first use.
+fn token() -> Result<String, String> {
+    if let Some(token) = TOKEN.lock().clone() {
+        return Ok(token);
+    }
+    let token = match vault().get(TOKEN_SECRET)? {
+        Some(token) => token,
+        None => {
+            let token = new_token();
+            vault().set(TOKEN_SECRET, &token)?;
+            token
+        }
+    };
+    *TOKEN.lock() = Some(token.clone());
+    Ok(token)
+}
+
+/// Compares in constant time, so the token can't be guessed byte by byte
+/// from response times.
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn authorized(headers: &HeaderMap, query_token: Option<&str>) -> bool {
+    let Some(expected) = TOKEN.lock().clone() else {
+        return false;
+    };
+    headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or(query_token)
+        .is_some_and(|given| tokens_match(given, &expected))
+}
+
+fn discovery_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_IDENTIFIER).join(DISCOVERY_FILE))
+}
+
+fn write_discovery(port: u16, token: &str) -> std::io::Result<()> {
+    let Some(path) = discovery_path() else {
+        return Ok(());
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Recreated rather than truncated so the mode below always applies
+    let _ = std::fs::remove_file(&path);
+    let contents = json!({
+        "url": url(port),
+        "port": port,
+        "token": token,
+        "pid": std::process::id(),
+    });
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&path)?
+        .write_all(contents.to_string().as_bytes())
+}
+
+fn remove_discovery() {
+    if let Some(path) = discovery_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+fn unauthorized() -> Response {
+    let error = MightyError::request("UNAUTHORIZED", "Missing or wrong API token");
+    (StatusCode::UNAUTHORIZED, Json(error)).into_response()
+}
+
+async fn health() -> Json<Value> {
+    Json(json!({ "status": "ok", "version": env!("CARGO_PKG_VERSION") }))
+}
+
+/// `POST /v1/<method>` with the params as the JSON body.
+async fn call(
+    RouterState(server): RouterState<Server>,
+    Path(method): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if !authorized(&headers, None) {
+        return unauthorized();
+    }
+    let params = if body.is_empty() {
+        Value::Null
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(params) => params,
+            Err(e) => {
+                let error = MightyError::request("INVALID_PARAMS", &e.to_string());
+                return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+            }
+        }
+    };
+    let actor = Actor::from_headers(&headers);
+    match invoke(&server.app, actor, &method, params).await {
+        Ok(result) => Json(result).into_response(),
+        Err(error) => {
+            let status = match error.code() {
+                "UNKNOWN_ACTION" => StatusCode::NOT_FOUND,
+                "INVALID_PARAMS" => StatusCode::BAD_REQUEST,
+                _ => StatusCode::UNPROCESSABLE_ENTITY,
+            };
+            (status, Json(error)).into_response()
+        }
+    }
+}
+
+/// `GET /v1/ws`: JSON-RPC style calls, `{ id, method, params }` answered
+/// with `{ id, result }` or `{ id, error }`, plus `subscribe` to have
+/// events pushed as `{ event, payload }`.
+async fn websocket(
+    RouterState(server): RouterState<Server>,
+    Query(query): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    // Browser WebSocket clients can't set headers, so the query may carry it
+    if !authorized(&headers, query.get("token").map(String::as_str)) {
+        return unauthorized();
+    }
+    let actor = Actor::from_headers(&headers);
+    upgrade.on_upgrade(move |socket| serve_socket(server, actor, socket))
+}
+
+#[derive(Deserialize)]
+struct RpcCall {
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+async fn serve_socket(server: Server, actor: Actor, mut socket: WebSocket) {
+    let (outgoing, mut queued) = mpsc::unbounded_channel::<String>();
+    let mut listeners: HashMap<String, EventId> = HashMap::new();
+    let mut stop = server.stop.clone();
+    loop {
+        tokio::select! {
+            received = socket.recv() => {
+                let text = match received {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    // Pings are answered by axum
+                    Some(Ok(_)) => continue,
+                };
+                let call: RpcCall = match serde_json::from_str(&text) {
+                    Ok(call) => call,
+                    Err(e) => {
+                        let error = MightyError::request("INVALID_REQUEST", &e.to_string());
+                        let _ = outgoing.send(reply(Value::Null, Err(error)));
+                        continue;
+                    }
+                };
+                if call.method == "subscribe" {
+                    let result = subscribe(&server.app, call.params, &outgoing, &mut listeners);
+                    let _ = outgoing.send(reply(call.id, result));
+                    continue;
+                }
+                // Calls run side by side; a slow completion doesn't hold up a read
+                let app = server.app.clone();
+                let outgoing = outgoing.clone();
+                tokio::spawn(async move {
+                    let result = invoke(&app, actor, &call.method, call.params).await;
+                    let _ = outgoing.send(reply(call.id, result));
+                });
+            }
+            Some(text) = queued.recv() => {
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            _ = stop.changed() => {
+                let _ = socket.close().await;
+                break;
+            }
+        }
+    }
+    for (_, id) in listeners {
+        server.app.unlisten(id);
+    }
+}
+
+fn reply(id: Value, result: Result<Value, MightyError>) -> String {
+    match result {
+        Ok(result) => json!({ "id": id, "result": result }),
+        Err(error) => json!({ "id": id, "error": error }),
+    }
+    .to_string()
+}
+
+#[derive(Deserialize)]
+struct SubscribeParams {
+    events: Vec<String>,
+}
+
+fn subscribe(
+    app: &AppHandle,
+    params: Value,
+    outgoing: &mpsc::UnboundedSender<String>,
+    listeners: &mut HashMap<String, EventId>,
+) -> Result<Value, MightyError> {
+    let params: SubscribeParams = parse(params)?;
+    if let Some(event) = params
+        .events
+        .iter()
+        .find(|event| !FORWARDED_EVENTS.contains(&event.as_str()))
+    {
+        return Err(MightyError::request(
+            "UNKNOWN_EVENT",
+            &format!("{} can't be subscribed to", event),
+        ));
+    }
+    for event in params.events {
+        if listeners.contains_key(&event) {
+            continue;
+        }
+        let outgoing = outgoing.clone();
+        let name = event.clone();
+        let id = app.listen_any(event.clone(), move |e| {
+            let payload: Value = serde_json::from_str(e.payload()).unwrap_or(Value::Null);
+            let _ = outgoing.send(json!({ "event": name, "payload": payload }).to_string());
+        });
+        listeners.insert(event, id);
+    }
+    Ok(json!({ "events": listeners.keys().collect::<Vec<_>>() }))
+}
+
+/// Whether the server is on and where, with the token for the user to give
+/// their editor.
+#[command]
+pub async fn get_api_server_status(request: Request<'_>) -> Result<ApiServerStatus, String> {
+    let settings = *SETTINGS.lock();
+    let port = RUNNING.lock().as_ref().map(|running| running.port);
+    let token = if settings.enabled && Actor::of(&request) == Actor::User {
+        Some(token()?)
+    } else {
+        None
+    };
+    Ok(ApiServerStatus {
+        enabled: settings.enabled,
+        running: port.is_some(),
+        url: port.map(url),
+        token,
+        error: LAST_ERROR.lock().clone(),
+    })
+}
+
+/// Replaces the token. Requests with the old one are refused from now on;
+/// WebSocket connections already open stay open. The agent can't: with the
+/// token its calls would pass for the user's.
+#[command]
+pub async fn regenerate_api_server_token(request: Request<'_>) -> Result<String, MightyError> {
+    if Actor::of(&request) == Actor::Agent {
+        return Err(MightyError::auth(
+            "AGENT_NOT_ALLOWED",
+            "Only the user can regenerate the API server token",
+        ));
+    }
+    let token = new_token();
+    vault()
+        .set(TOKEN_SECRET, &token)
+        .map_err(|e| MightyError::auth("VAULT_ERROR", &e))?;
+    *TOKEN.lock() = Some(token.clone());
+    if let Some(port) = RUNNING.lock().as_ref().map(|running| running.port) {
+        if let Err(e) = write_discovery(port, &token) {
+            warn!("Failed to write the API server discovery file: {}", e);
+        }
+    }
+    Ok(token)
+}
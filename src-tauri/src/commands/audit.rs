@@ -0,0 +1,271 @@
+// src/commands/audit.rs
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::ipc::Request;
+use tauri::{command, AppHandle};
+use tracing::warn;
+
+use super::fs::get_project_root;
+use super::permissions::{authorize, Actor, Capability};
+use crate::config::APP_IDENTIFIER;
+
+const AUDIT_FILE_NAME: &str = "audit.jsonl";
+/// Entries returned by `get_audit_log` when no limit is given.
+const DEFAULT_LIMIT: usize = 500;
+
+/// Sequence number and hash of the last entry, read from the file on the
+/// first write.
+static CHAIN: Lazy<Mutex<Option<(u64, String)>>> = Lazy::new(|| Mutex::new(None));
+
+/// One mutating operation. Each entry's hash covers the previous entry's,
+/// so editing or removing a line breaks the chain after it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub seq: u64,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: i64,
+    /// "user" or "agent".
+    pub actor: String,
+    pub workspace: String,
+    pub operation: String,
+    /// The file, command, ref or key acted on.
+    pub target: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+    /// SHA-256 of a file's content before and after the change.
+    pub before_hash: Option<String>,
+    pub after_hash: Option<String>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditFilter {
+    /// "user" or "agent".
+    pub actor: Option<String>,
+    pub operation: Option<String>,
+    /// Matches targets containing this text.
+    pub target: Option<String>,
+    /// Milliseconds since the Unix epoch, inclusive.
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+impl AuditFilter {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.actor
+            .as_ref()
+            .is_none_or(|actor| &entry.actor == actor)
+            && self
+                .operation
+                .as_ref()
+                .is_none_or(|operation| &entry.operation == operation)
+            && self
+                .target
+                .as_ref()
+                .is_none_or(|target| entry.target.contains(target.as_str()))
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditVerification {
+    pub entries: u64,
+    pub valid: bool,
+    /// Position of the first entry that was altered or can't be read.
+    pub broken_at: Option<u64>,
+}
+
+pub fn audit_log_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(APP_IDENTIFIER)
+        .join(AUDIT_FILE_NAME)
+}
+
+pub(crate) fn hash_bytes(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// The entry's hash, over every field but the hash itself.
+fn entry_hash(entry: &AuditEntry) -> String {
+    let mut unhashed = entry.clone();
+    unhashed.hash = String::new();
+    hash_bytes(&serde_json::to_vec(&unhashed).unwrap_or_default())
+}
+
+fn read_lines() -> Result<Vec<String>, String> {
+    let path = audit_log_path();
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(String::from)
+        .collect())
+}
+
+fn read_entries() -> Result<Vec<AuditEntry>, String> {
+    read_lines()?
+        .iter()
+        .map(|line| serde_json::from_str(line).map_err(|e| format!("Corrupt audit entry: {}", e)))
+        .collect()
+}
+
+fn append(entry_for: impl FnOnce(u64, String) -> AuditEntry) -> Result<(), String> {
+    let mut chain = CHAIN.lock();
+    if chain.is_none() {
+        let last = read_entries()?.pop();
+        *chain = Some(last.map_or((0, String::new()), |entry| (entry.seq, entry.hash)));
+    }
+    let (seq, prev_hash) = chain.clone().expect("loaded above");
+    let mut entry = entry_for(seq + 1, prev_hash);
+    entry.hash = entry_hash(&entry);
+
+    let path = audit_log_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut line = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    *chain = Some((entry.seq, entry.hash));
+    Ok(())
+}
+
+/// Records a completed operation. A failure to record is logged rather than
+/// failing the operation, which has already happened.
+pub(crate) fn record(actor: Actor, operation: &str, target: &str, details: Value) {
+    record_change(actor, operation, target, None, None, details);
+}
+
+/// Records a change to a file's content; `before` is `None` for a new file
+/// and `after` is `None` for a deleted one.
+pub(crate) fn record_change(
+    actor: Actor,
+    operation: &str,
+    target: &str,
+    before: Option<&[u8]>,
+    after: Option<&[u8]>,
+    details: Value,
+) {
+    let result = append(|seq, prev_hash| AuditEntry {
+        seq,
+        timestamp: Utc::now().timestamp_millis(),
+        actor: match actor {
+            Actor::User => "user",
+            Actor::Agent => "agent",
+        }
+        .to_string(),
+        workspace: get_project_root().to_string_lossy().to_string(),
+        operation: operation.to_string(),
+        target: target.to_string(),
+        details,
+        before_hash: before.map(hash_bytes),
+        after_hash: after.map(hash_bytes),
+        prev_hash,
+        hash: String::new(),
+    });
+    if let Err(e) = result {
+        warn!(
+            "Failed to record {} of {} in the audit log: {}",
+            operation, target, e
+        );
+    }
+}
+
+/// Matching entries, newest first.
+#[command]
+pub async fn get_audit_log(filter: Option<AuditFilter>) -> Result<Vec<AuditEntry>, String> {
+    let filter = filter.unwrap_or_default();
+    Ok(read_entries()?
+        .into_iter()
+        .rev()
+        .filter(|entry| filter.matches(entry))
+        .take(filter.limit.unwrap_or(DEFAULT_LIMIT))
+        .collect())
+}
+
+/// Writes matching entries, oldest first, as JSON lines to `destination`
+/// and returns how many were written. Without a limit everything matching is
+/// exported.
+#[command]
+pub async fn export_audit_log(
+    app: AppHandle,
+    request: Request<'_>,
+    destination: String,
+    filter: Option<AuditFilter>,
+) -> Result<usize, String> {
+    let actor = Actor::of(&request);
+    authorize(&app, actor, Capability::WriteFile, &destination).await?;
+    let filter = filter.unwrap_or_default();
+    let entries: Vec<AuditEntry> = read_entries()?
+        .into_iter()
+        .filter(|entry| filter.matches(entry))
+        .collect();
+    let skip = filter
+        .limit
+        .map_or(0, |limit| entries.len().saturating_sub(limit));
+    let mut output = String::new();
+    for entry in &entries[skip..] {
+        output.push_str(&serde_json::to_string(entry).map_err(|e| e.to_string())?);
+        output.push('\n');
+    }
+    fs::write(&destination, output)
+        .map_err(|e| format!("Failed to write {}: {}", destination, e))?;
+    let exported = entries.len() - skip;
+    record(
+        actor,
+        "export_audit_log",
+        &destination,
+        json!({ "entries": exported }),
+    );
+    Ok(exported)
+}
+
+/// Checks that no entry was changed, removed or reordered.
+#[command]
+pub async fn verify_audit_log() -> Result<AuditVerification, String> {
+    let lines = read_lines()?;
+    let mut prev_hash = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        let seq = index as u64 + 1;
+        let intact = serde_json::from_str::<AuditEntry>(line).is_ok_and(|entry| {
+            let intact = entry.seq == seq
+                && entry.prev_hash == prev_hash
+                && entry.hash == entry_hash(&entry);
+            prev_hash = entry.hash;
+            intact
+        });
+        if !intact {
+            return Ok(AuditVerification {
+                entries: lines.len() as u64,
+                valid: false,
+                broken_at: Some(seq),
+            });
+        }
+    }
+    Ok(AuditVerification {
+        entries: lines.len() as u64,
+        valid: true,
+        broken_at: None,
+    })
+}
@@ -1,26 +1,192 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
 use std::sync::Mutex;
-use tauri::State;
+use tauri::ipc::Request;
+use tauri::{AppHandle, State};
+use tracing::warn;
 
-// Define our AppState to hold the authentication token
-pub struct AppState {
-    auth_token: Mutex<Option<String>>,
+use super::api_server;
+use super::oauth;
+use super::permissions::Actor;
+use crate::error::MightyError;
+use crate::state::AppState;
+
+// Keychain service that every secret is stored under
+const VAULT_SERVICE: &str = "com.mighty.ide";
+// Keychains can't be enumerated portably, so the vault keeps its own list of names
+const NAME_INDEX: &str = ".names";
+// Secret holding the app's own auth token
+const AUTH_TOKEN_SECRET: &str = "auth_token";
+
+// Named secrets (e.g. "anthropic", "greptile", "openai") kept in the OS
+// keychain: macOS Keychain, Windows Credential Manager or Secret Service
+pub struct CredentialVault {
+    // Serializes updates to the name index
+    index_lock: Mutex<()>,
+}
+
+impl CredentialVault {
+    fn entry(&self, name: &str) -> Result<keyring::Entry, String> {
+        keyring::Entry::new(VAULT_SERVICE, name)
+            .map_err(|e| format!("Failed to open keychain entry {}: {}", name, e))
+    }
+
+    fn validate_name(name: &str) -> Result<(), String> {
+        let valid = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphanumeric())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("Invalid secret name: {:?}", name))
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<String>, String> {
+        Self::validate_name(name)?;
+        match self.entry(name)?.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(format!("Failed to read secret {}: {}", name, e)),
+        }
+    }
+
+    pub fn set(&self, name: &str, value: &str) -> Result<(), String> {
+        Self::validate_name(name)?;
+        let _guard = self.index_lock.lock().unwrap();
+        self.entry(name)?
+            .set_password(value)
+            .map_err(|e| format!("Failed to save secret {}: {}", name, e))?;
+        let mut names = self.read_index()?;
+        if !names.iter().any(|existing| existing == name) {
+            names.push(name.to_string());
+            names.sort();
+            self.write_index(&names)?;
+        }
+        Ok(())
+    }
+
+    // Returns whether there was a secret to delete
+    pub fn delete(&self, name: &str) -> Result<bool, String> {
+        Self::validate_name(name)?;
+        let _guard = self.index_lock.lock().unwrap();
+        let existed = match self.entry(name)?.delete_credential() {
+            Ok(()) => true,
+            Err(keyring::Error::NoEntry) => false,
+            Err(e) => return Err(format!("Failed to delete secret {}: {}", name, e)),
+        };
+        let mut names = self.read_index()?;
+        let before = names.len();
+        names.retain(|existing| existing != name);
+        if names.len() != before {
+            self.write_index(&names)?;
+        }
+        Ok(existed)
+    }
+
+    pub fn names(&self) -> Result<Vec<String>, String> {
+        let _guard = self.index_lock.lock().unwrap();
+        self.read_index()
+    }
+
+    fn read_index(&self) -> Result<Vec<String>, String> {
+        match self.entry(NAME_INDEX)?.get_password() {
+            Ok(json) => Ok(serde_json::from_str(&json).unwrap_or_default()),
+            Err(keyring::Error::NoEntry) => Ok(Vec::new()),
+            Err(e) => Err(format!("Failed to read the secret index: {}", e)),
+        }
+    }
+
+    fn write_index(&self, names: &[String]) -> Result<(), String> {
+        let json = serde_json::to_string(names).map_err(|e| e.to_string())?;
+        self.entry(NAME_INDEX)?
+            .set_password(&json)
+            .map_err(|e| format!("Failed to save the secret index: {}", e))
+    }
+}
+
+static VAULT: Lazy<CredentialVault> = Lazy::new(|| CredentialVault {
+    index_lock: Mutex::new(()),
+});
+
+// The keychain is shared by the whole app, so the vault is too
+pub fn vault() -> &'static CredentialVault {
+    &VAULT
+}
+
+fn vault_error(message: String) -> MightyError {
+    MightyError::auth("VAULT_ERROR", &message)
+}
+
+// Secrets the app keeps for itself; the secret commands won't hand them out
+// or let them be replaced
+fn is_internal(name: &str) -> bool {
+    name == AUTH_TOKEN_SECRET
+        || name == api_server::TOKEN_SECRET
+        || name.starts_with(oauth::TOKEN_SECRET_PREFIX)
+}
+
+fn check_not_internal(name: &str) -> Result<(), MightyError> {
+    if is_internal(name) {
+        return Err(MightyError::auth(
+            "RESERVED_SECRET",
+            &format!(
+                "{} is managed by the app and can't be accessed directly",
+                name
+            ),
+        ));
+    }
+    Ok(())
+}
+
+// Username/password (or token) pair used for HTTPS git remotes
+#[derive(Debug, Clone)]
+pub struct GitCredential {
+    pub username: String,
+    pub password: String,
+}
+
+// Holds the git credentials for the app state; the auth token lives in the vault
+pub struct AuthState {
+    git_credentials: Mutex<HashMap<String, GitCredential>>,
 }
 
-impl AppState {
+impl AuthState {
     pub fn new() -> Self {
         Self {
-            auth_token: Mutex::new(None),
+            git_credentials: Mutex::new(HashMap::new()),
         }
     }
 
-    pub fn store_token(&self, token: String) {
-        let mut token_guard = self.auth_token.lock().unwrap();
-        *token_guard = Some(token);
+    pub fn store_token(&self, token: String) -> Result<(), String> {
+        vault().set(AUTH_TOKEN_SECRET, &token)
     }
 
     pub fn get_token(&self) -> Option<String> {
-        let token_guard = self.auth_token.lock().unwrap();
-        token_guard.clone()
+        vault().get(AUTH_TOKEN_SECRET).unwrap_or_else(|e| {
+            warn!("{}", e);
+            None
+        })
+    }
+
+    pub fn store_git_credential(&self, host: String, credential: GitCredential) {
+        let mut credentials = self.git_credentials.lock().unwrap();
+        credentials.insert(host, credential);
+    }
+
+    pub fn remove_git_credential(&self, host: &str) {
+        let mut credentials = self.git_credentials.lock().unwrap();
+        credentials.remove(host);
+    }
+
+    // Snapshot of the stored git credentials keyed by host
+    pub fn git_credentials(&self) -> HashMap<String, GitCredential> {
+        let credentials = self.git_credentials.lock().unwrap();
+        credentials.clone()
     }
 }
 
@@ -29,24 +195,99 @@ impl AppState {
 pub async fn store_auth_token(
     token: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    state.store_token(token);
-    Ok(())
+) -> Result<(), MightyError> {
+    state
+        .auth
+        .store_token(token)
+        .map_err(|e| MightyError::auth("VAULT_ERROR", &e))
 }
 
 // Command to check if we have an auth token
 #[tauri::command]
-pub async fn has_auth_token(state: State<'_, AppState>) -> Result<bool, String> {
-    Ok(state.get_token().is_some())
+pub async fn has_auth_token(state: State<'_, AppState>) -> Result<bool, MightyError> {
+    Ok(state.auth.get_token().is_some())
+}
+
+// Command to get the current auth token, or with a provider, that provider's OAuth
+// access token, refreshed first if it is about to expire
+#[tauri::command]
+pub async fn get_auth_token(
+    app: AppHandle,
+    provider: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<String>, MightyError> {
+    let Some(provider) = provider else {
+        return Ok(state.auth.get_token());
+    };
+    let provider_config = oauth::provider_config(&*state.config.lock().await, &provider)
+        .map_err(|e| MightyError::config("UNKNOWN_PROVIDER", &e))?;
+    oauth::access_token(&app, &provider, &provider_config).await
+}
+
+// Command to store credentials for an HTTPS git host (e.g. "github.com")
+#[tauri::command]
+pub async fn store_git_credentials(
+    host: String,
+    username: String,
+    password: String,
+    state: State<'_, AppState>,
+) -> Result<(), MightyError> {
+    state
+        .auth
+        .store_git_credential(host, GitCredential { username, password });
+    Ok(())
 }
 
-// Command to get the current auth token
+// Command to forget the credentials stored for a git host
 #[tauri::command]
-pub async fn get_auth_token(state: State<'_, AppState>) -> Result<Option<String>, String> {
-    Ok(state.get_token())
+pub async fn clear_git_credentials(
+    host: String,
+    state: State<'_, AppState>,
+) -> Result<(), MightyError> {
+    state.auth.remove_git_credential(&host);
+    Ok(())
 }
 
 // Helper function to get a token for other commands
 pub fn get_token_from_state(state: &State<AppState>) -> Option<String> {
-    state.get_token()
-}
\ No newline at end of file
+    state.auth.get_token()
+}
+
+// Command to save a named secret (e.g. "anthropic", "greptile", "openai") to the keychain
+#[tauri::command]
+pub async fn set_secret(name: String, value: String) -> Result<(), MightyError> {
+    check_not_internal(&name)?;
+    if value.trim().is_empty() {
+        return Err(MightyError::auth("EMPTY_SECRET", "The secret is empty"));
+    }
+    vault().set(&name, value.trim()).map_err(vault_error)
+}
+
+// Command to read a named secret. Only the user's calls get an answer: the
+// agent uses the keys through the backend without ever seeing them
+#[tauri::command]
+pub async fn get_secret(request: Request<'_>, name: String) -> Result<Option<String>, MightyError> {
+    if Actor::of(&request) == Actor::Agent {
+        return Err(MightyError::auth(
+            "SECRET_DENIED",
+            "The agent can't read secrets",
+        ));
+    }
+    check_not_internal(&name)?;
+    vault().get(&name).map_err(vault_error)
+}
+
+// Command to delete a named secret; returns whether it existed
+#[tauri::command]
+pub async fn delete_secret(name: String) -> Result<bool, MightyError> {
+    check_not_internal(&name)?;
+    vault().delete(&name).map_err(vault_error)
+}
+
+// Command to list the names of the stored secrets, never their values
+#[tauri::command]
+pub async fn list_secret_names() -> Result<Vec<String>, MightyError> {
+    let mut names = vault().names().map_err(vault_error)?;
+    names.retain(|name| !is_internal(name));
+    Ok(names)
+}
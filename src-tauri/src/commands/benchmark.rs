@@ -0,0 +1,406 @@
+// src/commands/benchmark.rs
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tauri::command;
+use tracing::{info, warn};
+
+use super::storage::{delete_record, get_record, put_record};
+use crate::context::context::context_manager;
+use crate::context::context_manager::{ChunkInfo, EMBEDDING_DIM};
+
+const BASELINE_PREFIX: &str = "benchmark:baseline:";
+/// Keys the storage benchmark writes and deletes again.
+const SCRATCH_PREFIX: &str = "benchmark:scratch:";
+/// A median this much slower than the baseline's counts as a regression, in
+/// percent; timings on a desktop vary too much for a tighter bound.
+const REGRESSION_PERCENT: f64 = 20.0;
+
+/// Lines in the generated source file that is chunked and embedded.
+const SOURCE_LINES: usize = 2_000;
+const CHUNKING_RUNS: usize = 20;
+/// Chunks embedded per call, and calls timed.
+const EMBEDDING_BATCH: usize = 32;
+const EMBEDDING_RUNS: usize = 5;
+const SINGLE_EMBEDDING_RUNS: usize = 10;
+/// Chunks written to the scratch table per run, and searches per run.
+const INDEX_CHUNKS: usize = 1_000;
+const INDEX_RUNS: usize = 3;
+const INDEX_SEARCHES: usize = 20;
+const STORAGE_RECORDS: usize = 1_000;
+const STORAGE_VALUE_BYTES: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BenchmarkSuite {
+    All,
+    /// Splitting a file into chunks and extracting its symbols.
+    Chunking,
+    /// The embedding model, one text and a batch at a time.
+    Embedding,
+    /// LanceDB writes and vector searches, on a scratch table.
+    Index,
+    /// RocksDB puts, gets and deletes.
+    Storage,
+}
+
+impl BenchmarkSuite {
+    fn includes(self, suite: BenchmarkSuite) -> bool {
+        self == BenchmarkSuite::All || self == suite
+    }
+}
+
+/// How long one operation took over its runs, in milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Timing {
+    /// `<suite>.<operation>`, e.g. `index.search`.
+    pub name: String,
+    pub runs: usize,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+    /// Items (chunks, records) per second, where a run handles several.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub per_second: Option<f64>,
+}
+
+impl Timing {
+    fn new(name: &str, durations: &[Duration], items_per_run: Option<usize>) -> Self {
+        let mut ms: Vec<f64> = durations.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+        ms.sort_by(f64::total_cmp);
+        let percentile = |p: f64| {
+            let rank = ((ms.len() as f64 * p).ceil() as usize).clamp(1, ms.len().max(1));
+            ms.get(rank - 1).copied().unwrap_or(0.0)
+        };
+        let mean_ms = ms.iter().sum::<f64>() / ms.len().max(1) as f64;
+        Self {
+            name: name.to_string(),
+            runs: ms.len(),
+            mean_ms,
+            p50_ms: percentile(0.5),
+            p95_ms: percentile(0.95),
+            max_ms: ms.last().copied().unwrap_or(0.0),
+            per_second: items_per_run
+                .filter(|_| mean_ms > 0.0)
+                .map(|items| items as f64 * 1000.0 / mean_ms),
+        }
+    }
+}
+
+/// A stored timing later runs are compared against.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    /// App version that recorded it.
+    pub version: String,
+    pub recorded_at: i64,
+    pub timing: Timing,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkResult {
+    #[serde(flatten)]
+    pub timing: Timing,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<Baseline>,
+    /// Change of the median against the baseline's, in percent; positive
+    /// is slower.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change_percent: Option<f64>,
+    /// Whether the median is more than `REGRESSION_PERCENT` slower.
+    pub regressed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkReport {
+    pub suite: BenchmarkSuite,
+    pub version: String,
+    pub started_at: i64,
+    pub results: Vec<BenchmarkResult>,
+    /// Suites that could not run, with why.
+    pub failures: Vec<String>,
+    /// Whether these timings were stored as the new baselines.
+    pub saved_baseline: bool,
+}
+
+/// Deterministic Rust-like source, so every run and release chunks and
+/// embeds the same text.
+fn sample_source(lines: usize) -> String {
+    let mut source = String::new();
+    let mut line = 0;
+    let mut item = 0;
+    while line < lines {
+        source.push_str(&format!(
+            "/// Returns the weighted total of the entries in bucket {item}.\n\
+             pub fn bucket_total_{item}(entries: &[Entry], weight: f64) -> Option<f64> {{\n\
+             \x20   let mut total = 0.0;\n\
+             \x20   for entry in entries.iter().filter(|e| e.bucket == {item}) {{\n\
+             \x20       total += entry.value?;\n\
+             \x20   }}\n\
+             \x20   Some(total * weight)\n\
+             }}\n\n"
+        ));
+        line += 9;
+        item += 1;
+    }
+    source
+}
+
+/// A pseudo-random unit vector of the embedding size, the same for the same
+/// `seed`.
+fn sample_embedding(seed: u64) -> Vec<f32> {
+    let mut state = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    let mut vector: Vec<f32> = (0..EMBEDDING_DIM)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 40) as f32 / (1u64 << 24) as f32) - 0.5
+        })
+        .collect();
+    let norm = vector
+        .iter()
+        .map(|v| v * v)
+        .sum::<f32>()
+        .sqrt()
+        .max(f32::EPSILON);
+    vector.iter_mut().for_each(|v| *v /= norm);
+    vector
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let started = Instant::now();
+    let value = f();
+    (value, started.elapsed())
+}
+
+async fn chunking() -> Result<Vec<Timing>, String> {
+    let manager = context_manager().await?;
+    let source = sample_source(SOURCE_LINES);
+    tokio::task::spawn_blocking(move || {
+        let mut durations = Vec::new();
+        let mut chunks = 0;
+        for _ in 0..CHUNKING_RUNS {
+            let (result, elapsed) = time(|| manager.chunk_file("benchmark.rs", &source));
+            chunks = result.map_err(|e| e.to_string())?.len();
+            durations.push(elapsed);
+        }
+        Ok(vec![Timing::new("chunking.file", &durations, Some(chunks))])
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+async fn sample_chunks() -> Result<Vec<ChunkInfo>, String> {
+    let manager = context_manager().await?;
+    let source = sample_source(SOURCE_LINES);
+    manager
+        .chunk_file("benchmark.rs", &source)
+        .map_err(|e| e.to_string())
+}
+
+async fn embedding() -> Result<Vec<Timing>, String> {
+    let manager = context_manager().await?;
+    let texts: Vec<String> = sample_chunks()
+        .await?
+        .into_iter()
+        .map(|chunk| chunk.content)
+        .cycle()
+        .take(EMBEDDING_BATCH)
+        .collect();
+
+    let mut single = Vec::new();
+    for text in texts.iter().cycle().take(SINGLE_EMBEDDING_RUNS) {
+        let started = Instant::now();
+        manager
+            .generate_embedding(text)
+            .await
+            .map_err(|e| e.to_string())?;
+        single.push(started.elapsed());
+    }
+    let mut batch = Vec::new();
+    for _ in 0..EMBEDDING_RUNS {
+        let started = Instant::now();
+        manager
+            .generate_embeddings(texts.clone())
+            .await
+            .map_err(|e| e.to_string())?;
+        batch.push(started.elapsed());
+    }
+    Ok(vec![
+        Timing::new("embedding.single", &single, Some(1)),
+        Timing::new("embedding.batch", &batch, Some(EMBEDDING_BATCH)),
+    ])
+}
+
+/// LanceDB on its own: embeddings are generated rather than computed by the
+/// model, which `embedding` measures.
+async fn index() -> Result<Vec<Timing>, String> {
+    let manager = context_manager().await?;
+    let chunks: Vec<ChunkInfo> = sample_chunks()
+        .await?
+        .into_iter()
+        .cycle()
+        .take(INDEX_CHUNKS)
+        .collect();
+    let embeddings: Vec<Vec<f32>> = (0..chunks.len() as u64).map(sample_embedding).collect();
+    let queries: Vec<Vec<f32>> = (0..INDEX_SEARCHES as u64)
+        .map(|i| sample_embedding(u64::MAX - i))
+        .collect();
+
+    let mut writes = Vec::new();
+    let mut searches = Vec::new();
+    for _ in 0..INDEX_RUNS {
+        let (write, run_searches) = manager
+            .time_scratch_table(&chunks, &embeddings, &queries)
+            .await
+            .map_err(|e| e.to_string())?;
+        writes.push(write);
+        searches.extend(run_searches);
+    }
+    Ok(vec![
+        Timing::new("index.insert", &writes, Some(chunks.len())),
+        Timing::new("index.search", &searches, None),
+    ])
+}
+
+fn storage() -> Result<Vec<Timing>, String> {
+    let value = "x".repeat(STORAGE_VALUE_BYTES);
+    let keys: Vec<String> = (0..STORAGE_RECORDS)
+        .map(|i| format!("{}{}", SCRATCH_PREFIX, i))
+        .collect();
+    let mut puts = Vec::new();
+    let mut gets = Vec::new();
+    let mut deletes = Vec::new();
+    for key in &keys {
+        let (result, elapsed) = time(|| put_record(key, &value));
+        result.map_err(|e| e.to_string())?;
+        puts.push(elapsed);
+    }
+    for key in &keys {
+        let (result, elapsed) = time(|| get_record(key));
+        result.map_err(|e| e.to_string())?;
+        gets.push(elapsed);
+    }
+    for key in &keys {
+        let (result, elapsed) = time(|| delete_record(key));
+        result.map_err(|e| e.to_string())?;
+        deletes.push(elapsed);
+    }
+    Ok(vec![
+        Timing::new("storage.put", &puts, None),
+        Timing::new("storage.get", &gets, None),
+        Timing::new("storage.delete", &deletes, None),
+    ])
+}
+
+fn load_baseline(name: &str) -> Option<Baseline> {
+    let json = get_record(&format!("{}{}", BASELINE_PREFIX, name)).ok()??;
+    serde_json::from_str(&json).ok()
+}
+
+fn store_baseline(baseline: &Baseline) -> Result<(), String> {
+    let json = serde_json::to_string(baseline).map_err(|e| e.to_string())?;
+    put_record(
+        &format!("{}{}", BASELINE_PREFIX, baseline.timing.name),
+        &json,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn compare(timing: Timing) -> BenchmarkResult {
+    let baseline = load_baseline(&timing.name);
+    let change_percent = baseline
+        .as_ref()
+        .filter(|baseline| baseline.timing.p50_ms > 0.0)
+        .map(|baseline| (timing.p50_ms / baseline.timing.p50_ms - 1.0) * 100.0);
+    BenchmarkResult {
+        timing,
+        baseline,
+        change_percent,
+        regressed: change_percent.is_some_and(|change| change > REGRESSION_PERCENT),
+    }
+}
+
+/// Times the core pipelines and compares each timing with its stored
+/// baseline. With `save_baseline` the timings become the new baselines;
+/// timings without a baseline yet always become one. The index and
+/// embedding suites need the context manager.
+#[command]
+pub async fn run_benchmark(
+    suite: BenchmarkSuite,
+    save_baseline: Option<bool>,
+) -> Result<BenchmarkReport, String> {
+    let version = env!("CARGO_PKG_VERSION").to_string();
+    let started_at = Utc::now().timestamp_millis();
+    let mut timings = Vec::new();
+    let mut failures = Vec::new();
+    let mut record = |name: &str, result: Result<Vec<Timing>, String>| match result {
+        Ok(result) => timings.extend(result),
+        Err(e) => {
+            warn!("The {} benchmark failed: {}", name, e);
+            failures.push(format!("{}: {}", name, e));
+        }
+    };
+
+    if suite.includes(BenchmarkSuite::Chunking) {
+        record("chunking", chunking().await);
+    }
+    if suite.includes(BenchmarkSuite::Embedding) {
+        record("embedding", embedding().await);
+    }
+    if suite.includes(BenchmarkSuite::Index) {
+        record("index", index().await);
+    }
+    if suite.includes(BenchmarkSuite::Storage) {
+        let result = tokio::task::spawn_blocking(storage)
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result);
+        record("storage", result);
+    }
+
+    let save = save_baseline.unwrap_or(false);
+    let results: Vec<BenchmarkResult> = timings.into_iter().map(compare).collect();
+    for result in &results {
+        if save || result.baseline.is_none() {
+            let baseline = Baseline {
+                version: version.clone(),
+                recorded_at: started_at,
+                timing: result.timing.clone(),
+            };
+            if let Err(e) = store_baseline(&baseline) {
+                warn!("Failed to store the {} baseline: {}", result.timing.name, e);
+            }
+        }
+    }
+    let regressed: Vec<&str> = results
+        .iter()
+        .filter(|result| result.regressed)
+        .map(|result| result.timing.name.as_str())
+        .collect();
+    if !regressed.is_empty() {
+        warn!(
+            "Slower than baseline by over {}%: {}",
+            REGRESSION_PERCENT,
+            regressed.join(", ")
+        );
+    }
+    info!(
+        "Ran {} benchmarks, {} failed",
+        results.len(),
+        failures.len()
+    );
+
+    Ok(BenchmarkReport {
+        suite,
+        version,
+        started_at,
+        results,
+        failures,
+        saved_baseline: save,
+    })
+}
@@ -0,0 +1,252 @@
+// src/commands/bookmarks.rs
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::command;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::fs::{get_project_root, read_file, resolve_workspace_path, workspace_relative_path};
+use super::storage::{delete_record, get_record, put_record, records_with_prefix};
+
+const STORAGE_PREFIX: &str = "bookmarks:";
+
+/// What a bookmark's lines looked like when it was placed, to find them
+/// again after the file is edited.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Anchor {
+    /// SHA-256 of the lines with surrounding whitespace trimmed.
+    pub hash: String,
+    /// The first non-blank line, trimmed; a cheap check before hashing.
+    pub first_line: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub id: String,
+    /// Relative to the workspace root.
+    pub path: String,
+    /// 1-based and inclusive; updated as the anchored lines move.
+    pub start_line: usize,
+    pub end_line: usize,
+    /// The user's annotation; empty for a plain bookmark.
+    #[serde(default)]
+    pub note: String,
+    pub anchor: Anchor,
+    /// The anchored lines were changed or removed, so the range is only
+    /// where they last were.
+    #[serde(default)]
+    pub orphaned: bool,
+    /// Milliseconds since the Unix epoch.
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+fn workspace_prefix() -> String {
+    format!(
+        "{}{}|",
+        STORAGE_PREFIX,
+        get_project_root().to_string_lossy()
+    )
+}
+
+fn storage_key(id: &str) -> String {
+    format!("{}{}", workspace_prefix(), id)
+}
+
+fn save(bookmark: &Bookmark) -> Result<(), String> {
+    let json = serde_json::to_string(bookmark).map_err(|e| e.to_string())?;
+    put_record(&storage_key(&bookmark.id), &json).map_err(|e| e.to_string())
+}
+
+fn load(id: &str) -> Result<Bookmark, String> {
+    let json = get_record(&storage_key(id))
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("No bookmark with id {}", id))?;
+    serde_json::from_str(&json).map_err(|e| e.to_string())
+}
+
+fn load_all() -> Result<Vec<Bookmark>, String> {
+    Ok(records_with_prefix(&workspace_prefix())
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|(_, json)| serde_json::from_str(&json).ok())
+        .collect())
+}
+
+fn anchor_of(lines: &[&str]) -> Anchor {
+    let mut hasher = Sha256::new();
+    for line in lines {
+        hasher.update(line.trim().as_bytes());
+        hasher.update(b"\n");
+    }
+    Anchor {
+        hash: format!("{:x}", hasher.finalize()),
+        first_line: lines
+            .iter()
+            .map(|line| line.trim())
+            .find(|line| !line.is_empty())
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+/// Where the bookmark's anchored lines are in `lines`: where they were if
+/// they haven't changed, otherwise the match nearest to it. `None` when
+/// they are gone.
+fn locate(bookmark: &Bookmark, lines: &[&str]) -> Option<usize> {
+    let length = bookmark.end_line + 1 - bookmark.start_line;
+    if length > lines.len() {
+        return None;
+    }
+    let matches_at = |start: usize| {
+        let window = &lines[start..start + length];
+        let first = window
+            .iter()
+            .map(|line| line.trim())
+            .find(|line| !line.is_empty());
+        first.unwrap_or_default() == bookmark.anchor.first_line
+            && anchor_of(window).hash == bookmark.anchor.hash
+    };
+
+    let previous = bookmark.start_line - 1;
+    let last_start = lines.len() - length;
+    (0..=last_start)
+        .filter(|&start| matches_at(start))
+        .min_by_key(|&start| start.abs_diff(previous))
+}
+
+/// Moves `bookmarks`, all of one file, to where their lines are in
+/// `content` now, saving those that changed.
+fn resolve(bookmarks: &mut [Bookmark], content: &str) {
+    let lines: Vec<&str> = content.lines().collect();
+    for bookmark in bookmarks.iter_mut() {
+        let (start_line, orphaned) = match locate(bookmark, &lines) {
+            Some(start) => (start + 1, false),
+            None => (bookmark.start_line, true),
+        };
+        if start_line == bookmark.start_line && orphaned == bookmark.orphaned {
+            continue;
+        }
+        bookmark.end_line = start_line + (bookmark.end_line - bookmark.start_line);
+        bookmark.start_line = start_line;
+        bookmark.orphaned = orphaned;
+        if let Err(e) = save(bookmark) {
+            warn!("Failed to update bookmark {}: {}", bookmark.id, e);
+        }
+    }
+}
+
+/// The bookmarks of `path`, workspace-relative, resolved against its
+/// `content`, in line order. Annotated ones are given to the model as
+/// context when it is asked about the file.
+pub(crate) fn bookmarks_for_file(path: &str, content: &str) -> Vec<Bookmark> {
+    let mut bookmarks: Vec<Bookmark> = match load_all() {
+        Ok(bookmarks) => bookmarks.into_iter().filter(|b| b.path == path).collect(),
+        Err(e) => {
+            warn!("Failed to load bookmarks: {}", e);
+            return Vec::new();
+        }
+    };
+    resolve(&mut bookmarks, content);
+    bookmarks.sort_by_key(|bookmark| bookmark.start_line);
+    bookmarks
+}
+
+/// Bookmarks lines `start_line` to `end_line` (1-based, inclusive) of
+/// `path`, with an optional note.
+#[command]
+pub async fn add_bookmark(
+    path: String,
+    start_line: usize,
+    end_line: usize,
+    note: Option<String>,
+) -> Result<Bookmark, String> {
+    let full_path = resolve_workspace_path(&path).map_err(|e| e.message().to_string())?;
+    let path = workspace_relative_path(&full_path);
+    let content = read_file(path.clone())
+        .await
+        .map_err(|e| e.message().to_string())?
+        .content;
+    let lines: Vec<&str> = content.lines().collect();
+    if start_line == 0 || start_line > end_line || end_line > lines.len() {
+        return Err(format!(
+            "Lines {}-{} are outside of {}, which has {} lines",
+            start_line,
+            end_line,
+            path,
+            lines.len()
+        ));
+    }
+
+    let now = Utc::now().timestamp_millis();
+    let bookmark = Bookmark {
+        id: Uuid::new_v4().to_string(),
+        path,
+        start_line,
+        end_line,
+        note: note.unwrap_or_default().trim().to_string(),
+        anchor: anchor_of(&lines[start_line - 1..end_line]),
+        orphaned: false,
+        created_at: now,
+        updated_at: now,
+    };
+    save(&bookmark)?;
+    Ok(bookmark)
+}
+
+/// The workspace's bookmarks, or only `path`'s, moved to where their lines
+/// are now. Bookmarks of deleted files are dropped.
+#[command]
+pub async fn list_bookmarks(path: Option<String>) -> Result<Vec<Bookmark>, String> {
+    let path = match path {
+        Some(path) => Some(workspace_relative_path(
+            &resolve_workspace_path(&path).map_err(|e| e.message().to_string())?,
+        )),
+        None => None,
+    };
+    let mut bookmarks: Vec<Bookmark> = load_all()?
+        .into_iter()
+        .filter(|bookmark| path.as_ref().is_none_or(|path| bookmark.path == *path))
+        .collect();
+    bookmarks.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut resolved = Vec::new();
+    for file in bookmarks.chunk_by_mut(|a, b| a.path == b.path) {
+        match read_file(file[0].path.clone()).await {
+            Ok(text) => {
+                resolve(file, &text.content);
+                resolved.extend(file.iter().cloned());
+            }
+            Err(e) if e.code() == "FILE_NOT_FOUND" => {
+                for bookmark in file.iter() {
+                    delete_record(&storage_key(&bookmark.id)).map_err(|e| e.to_string())?;
+                }
+            }
+            Err(_) => resolved.extend(file.iter().cloned()),
+        }
+    }
+    resolved.sort_by(|a, b| {
+        a.path
+            .cmp(&b.path)
+            .then_with(|| a.start_line.cmp(&b.start_line))
+    });
+    Ok(resolved)
+}
+
+/// Changes a bookmark's note.
+#[command]
+pub async fn update_bookmark(id: String, note: String) -> Result<Bookmark, String> {
+    let mut bookmark = load(&id)?;
+    bookmark.note = note.trim().to_string();
+    bookmark.updated_at = Utc::now().timestamp_millis();
+    save(&bookmark)?;
+    Ok(bookmark)
+}
+
+#[command]
+pub async fn delete_bookmark(id: String) -> Result<(), String> {
+    load(&id)?;
+    delete_record(&storage_key(&id)).map_err(|e| e.to_string())
+}
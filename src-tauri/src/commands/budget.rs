@@ -0,0 +1,305 @@
+// src/commands/budget.rs
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::{command, AppHandle};
+use tracing::{info, warn};
+
+use super::notifications::{notify, NotificationLevel};
+use super::storage::{compact_storage, storage_dir};
+use crate::activity;
+use crate::config::BudgetConfig;
+use crate::context::{context, pruning};
+
+const MB: u64 = 1024 * 1024;
+/// How often disk usage is measured and the budgets enforced.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Share of the indexed files evicted per round while the index is over
+/// budget, in percent.
+const EVICT_PERCENT: usize = 10;
+/// Rounds of eviction before the index gives up and refuses new files.
+const MAX_EVICTION_ROUNDS: usize = 5;
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static LIMITS: Lazy<RwLock<Limits>> = Lazy::new(|| RwLock::new(Limits::default()));
+/// The error new index writes get while the index stays over budget.
+static INDEX_REFUSAL: Lazy<RwLock<Option<String>>> = Lazy::new(|| RwLock::new(None));
+/// The error `store_value` gets while the database stays over budget.
+static STORAGE_REFUSAL: Lazy<RwLock<Option<String>>> = Lazy::new(|| RwLock::new(None));
+/// One enforcement pass at a time.
+static ENFORCING: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+/// Budgets in bytes; `None` is unlimited.
+#[derive(Debug, Clone, Copy, Default)]
+struct Limits {
+    index: Option<u64>,
+    storage: Option<u64>,
+    cache: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetKind {
+    /// The LanceDB code index and conversation memory.
+    Index,
+    /// The RocksDB database.
+    Storage,
+    /// File contents cached in memory for the code context.
+    Cache,
+}
+
+impl BudgetKind {
+    fn label(self) -> &'static str {
+        match self {
+            BudgetKind::Index => "code index",
+            BudgetKind::Storage => "database",
+            BudgetKind::Cache => "file cache",
+        }
+    }
+
+    fn setting(self) -> &'static str {
+        match self {
+            BudgetKind::Index => "budget.max_index_mb",
+            BudgetKind::Storage => "budget.max_storage_mb",
+            BudgetKind::Cache => "budget.max_cache_mb",
+        }
+    }
+
+    fn refusal(self) -> Option<&'static Lazy<RwLock<Option<String>>>> {
+        match self {
+            BudgetKind::Index => Some(&INDEX_REFUSAL),
+            BudgetKind::Storage => Some(&STORAGE_REFUSAL),
+            // The cache evicts instead
+            BudgetKind::Cache => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetUsage {
+    pub kind: BudgetKind,
+    /// On disk, or in memory for the cache; `None` while it isn't running.
+    pub used_bytes: Option<u64>,
+    /// `None` when there is no budget.
+    pub limit_bytes: Option<u64>,
+    /// Whether new writes are refused until usage is back under the limit.
+    pub refusing_writes: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map_or(0, |metadata| metadata.len()),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+async fn measure(path: &Path) -> u64 {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || dir_size(&path))
+        .await
+        .unwrap_or(0)
+}
+
+/// Starts or stops refusing writes to `kind`, telling the user when it
+/// starts.
+fn set_refusal(kind: BudgetKind, used: u64, limit: Option<u64>) {
+    let Some(refusal) = kind.refusal() else {
+        return;
+    };
+    let message = limit.filter(|limit| used > *limit).map(|limit| {
+        format!(
+            "The {} takes {} MB, over its budget of {} MB even after freeing space, so \
+             nothing more is added to it; raise {} to allow more",
+            kind.label(),
+            used / MB,
+            limit / MB,
+            kind.setting()
+        )
+    });
+    let was_refusing = std::mem::replace(&mut *refusal.write(), message.clone()).is_some();
+    match message {
+        Some(message) if !was_refusing => {
+            warn!("{}", message);
+            if let Some(app) = APP.get() {
+                let title = format!("The {} is over its budget", kind.label());
+                notify(
+                    app,
+                    NotificationLevel::Warning,
+                    "storage",
+                    title,
+                    Some(message),
+                );
+            }
+        }
+        None if was_refusing => info!("The {} is back under its budget", kind.label()),
+        _ => {}
+    }
+}
+
+/// Brings the index under `limit`: first by dropping old versions and
+/// compacting, then by evicting the files changed least recently.
+async fn enforce_index(limit: Option<u64>) -> Result<(), String> {
+    let Some((dir, _)) = context::index_usage().await else {
+        return Ok(());
+    };
+    let mut used = measure(&dir).await;
+    let Some(limit) = limit.filter(|limit| used > *limit) else {
+        set_refusal(BudgetKind::Index, used, limit);
+        return Ok(());
+    };
+
+    activity::wait_until_idle().await;
+    info!(
+        "The code index takes {} MB, over its {} MB budget; freeing space",
+        used / MB,
+        limit / MB
+    );
+    context::reclaim_index_space().await?;
+    used = measure(&dir).await;
+    for _ in 0..MAX_EVICTION_ROUNDS {
+        if used <= limit {
+            break;
+        }
+        let files = context::indexed_files().await?.len();
+        let evicted = pruning::evict_coldest((files * EVICT_PERCENT / 100).max(1)).await?;
+        if evicted == 0 {
+            break;
+        }
+        context::reclaim_index_space().await?;
+        used = measure(&dir).await;
+    }
+    set_refusal(BudgetKind::Index, used, Some(limit));
+    Ok(())
+}
+
+/// Brings the database under `limit` by compacting it; records are the
+/// user's, so none are deleted.
+async fn enforce_storage(limit: Option<u64>) -> Result<(), String> {
+    let Some(dir) = storage_dir() else {
+        return Ok(());
+    };
+    let mut used = measure(&dir).await;
+    if limit.is_some_and(|limit| used > limit) {
+        activity::wait_until_idle().await;
+        tokio::task::spawn_blocking(compact_storage)
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+        used = measure(&dir).await;
+    }
+    set_refusal(BudgetKind::Storage, used, limit);
+    Ok(())
+}
+
+async fn enforce() {
+    let _guard = ENFORCING.lock().await;
+    let limits = *LIMITS.read();
+    context::limit_cache(limits.cache.map(|limit| limit as usize)).await;
+    if let Err(e) = enforce_index(limits.index).await {
+        warn!("Failed to enforce the code index budget: {}", e);
+    }
+    if let Err(e) = enforce_storage(limits.storage).await {
+        warn!("Failed to enforce the database budget: {}", e);
+    }
+}
+
+/// Fails while the code index is over budget; checked before anything is
+/// embedded into it.
+pub(crate) fn check_index_write() -> Result<(), String> {
+    match INDEX_REFUSAL.read().clone() {
+        Some(message) => Err(message),
+        None => Ok(()),
+    }
+}
+
+/// Why `store_value` is refused, while the database is over budget.
+/// Backend records aren't refused; they are small and the app needs them.
+pub(crate) fn storage_refusal() -> Option<String> {
+    STORAGE_REFUSAL.read().clone()
+}
+
+/// The file cache budget in bytes, if there is one.
+pub(crate) fn cache_limit() -> Option<usize> {
+    LIMITS.read().cache.map(|limit| limit as usize)
+}
+
+/// Applies the `[budget]` settings, enforcing them at once when the
+/// backend is running.
+pub(crate) fn configure(config: Option<&BudgetConfig>) {
+    let megabytes = |value: Option<u64>| value.map(|mb| mb * MB);
+    *LIMITS.write() = Limits {
+        index: megabytes(config.and_then(|c| c.max_index_mb)),
+        storage: megabytes(config.and_then(|c| c.max_storage_mb)),
+        cache: megabytes(config.and_then(|c| c.max_cache_mb)),
+    };
+    if APP.get().is_some() {
+        tauri::async_runtime::spawn(enforce());
+    }
+}
+
+/// Measures usage and enforces the budgets now and every `CHECK_INTERVAL`;
+/// called once storage is up.
+pub(crate) fn start(app: AppHandle) {
+    if APP.set(app).is_err() {
+        return;
+    }
+    tauri::async_runtime::spawn(async {
+        loop {
+            enforce().await;
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// How much of each budget is in use, measured now.
+#[command]
+pub async fn get_budget_usage() -> Result<Vec<BudgetUsage>, String> {
+    let limits = *LIMITS.read();
+    let index = context::index_usage().await;
+    let storage = storage_dir();
+
+    let index_bytes = match &index {
+        Some((dir, _)) => Some(measure(dir).await),
+        None => None,
+    };
+    let storage_bytes = match &storage {
+        Some(dir) => Some(measure(dir).await),
+        None => None,
+    };
+    Ok(vec![
+        BudgetUsage {
+            kind: BudgetKind::Index,
+            used_bytes: index_bytes,
+            limit_bytes: limits.index,
+            refusing_writes: INDEX_REFUSAL.read().is_some(),
+            path: index.as_ref().map(|(dir, _)| dir.clone()),
+        },
+        BudgetUsage {
+            kind: BudgetKind::Storage,
+            used_bytes: storage_bytes,
+            limit_bytes: limits.storage,
+            refusing_writes: STORAGE_REFUSAL.read().is_some(),
+            path: storage,
+        },
+        BudgetUsage {
+            kind: BudgetKind::Cache,
+            used_bytes: index.map(|(_, cache_bytes)| cache_bytes as u64),
+            limit_bytes: limits.cache,
+            refusing_writes: false,
+            path: None,
+        },
+    ])
+}
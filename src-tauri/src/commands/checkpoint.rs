@@ -0,0 +1,191 @@
+// src/commands/checkpoint.rs
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::ipc::Request;
+use tauri::{command, AppHandle};
+
+use super::audit;
+use super::dry_run;
+use super::fs::{
+    get_project_root, resolve_workspace_path, workspace_relative_path, FileSystemError,
+};
+use super::permissions::{authorize, Actor, Capability};
+use crate::secrets;
+
+/// Oldest checkpoints beyond this count are dropped from a file's history.
+const MAX_CHECKPOINTS_PER_FILE: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileCheckpoint {
+    /// Content hash of the saved version; doubles as the checkpoint id.
+    pub id: String,
+    pub path: String,
+    pub created_at: i64,
+    pub size: u64,
+    pub reason: String,
+}
+
+fn history_dir() -> PathBuf {
+    get_project_root().join(".mighty").join("history")
+}
+
+fn hash_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn object_path(id: &str) -> PathBuf {
+    history_dir().join("objects").join(id)
+}
+
+fn index_path(relative_path: &str) -> PathBuf {
+    history_dir()
+        .join("index")
+        .join(format!("{}.json", hash_hex(relative_path.as_bytes())))
+}
+
+fn read_index(relative_path: &str) -> std::io::Result<Vec<FileCheckpoint>> {
+    let path = index_path(relative_path);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path)?;
+    serde_json::from_str(&content)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
+fn write_index(relative_path: &str, checkpoints: &[FileCheckpoint]) -> std::io::Result<()> {
+    let path = index_path(relative_path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(checkpoints)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    fs::write(path, content)
+}
+
+/// Saves the current contents of `full_path` into the checkpoint store before
+/// it gets overwritten. Returns `None` when the file doesn't exist yet or is
+/// identical to the most recent checkpoint.
+pub(crate) fn create_checkpoint(
+    full_path: &Path,
+    reason: &str,
+) -> std::io::Result<Option<FileCheckpoint>> {
+    if !full_path.is_file() {
+        return Ok(None);
+    }
+
+    let relative_path = workspace_relative_path(full_path);
+    let relative_path = relative_path.as_str();
+    let bytes = fs::read(full_path)?;
+    let id = hash_hex(&bytes);
+
+    let mut checkpoints = read_index(relative_path)?;
+    if checkpoints.first().map(|c| c.id == id).unwrap_or(false) {
+        return Ok(None);
+    }
+
+    let object = object_path(&id);
+    if !object.exists() {
+        if let Some(parent) = object.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&object, &bytes)?;
+    }
+
+    let checkpoint = FileCheckpoint {
+        id,
+        path: relative_path.to_string(),
+        created_at: Utc::now().timestamp_millis(),
+        size: bytes.len() as u64,
+        reason: reason.to_string(),
+    };
+
+    // Newest first
+    checkpoints.insert(0, checkpoint.clone());
+    checkpoints.truncate(MAX_CHECKPOINTS_PER_FILE);
+    write_index(relative_path, &checkpoints)?;
+
+    Ok(Some(checkpoint))
+}
+
+/// Lists saved versions of a file, newest first.
+#[command]
+pub async fn list_file_checkpoints(path: String) -> Result<Vec<FileCheckpoint>, FileSystemError> {
+    let full_path = resolve_workspace_path(&path)?;
+    read_index(&workspace_relative_path(&full_path))
+        .map_err(|e| FileSystemError::with_path("CHECKPOINT_ERROR", &e.to_string(), &full_path))
+}
+
+/// Restores a file to a saved version. The current contents are checkpointed
+/// first so the restore itself can be undone.
+#[command]
+pub async fn restore_checkpoint(
+    app: AppHandle,
+    request: Request<'_>,
+    path: String,
+    id: String,
+) -> Result<FileCheckpoint, FileSystemError> {
+    let full_path = resolve_workspace_path(&path)?;
+
+    let checkpoint = read_index(&workspace_relative_path(&full_path))
+        .map_err(|e| FileSystemError::with_path("CHECKPOINT_ERROR", &e.to_string(), &full_path))?
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| {
+            FileSystemError::with_path(
+                "CHECKPOINT_NOT_FOUND",
+                &format!("Checkpoint {} not found", id),
+                &full_path,
+            )
+        })?;
+
+    let object = object_path(&checkpoint.id);
+    let bytes = fs::read(&object)
+        .map_err(|e| FileSystemError::with_path("CHECKPOINT_ERROR", &e.to_string(), &object))?;
+
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        let before = fs::read_to_string(&full_path).ok();
+        let after = String::from_utf8_lossy(&bytes);
+        let diff = dry_run::file_diff(&path, before.as_deref(), Some(&after));
+        let details = json!({ "checkpoint": checkpoint.id });
+        dry_run::plan_step("restore_checkpoint", &path, Some(diff), details);
+        return Ok(checkpoint);
+    }
+    authorize(&app, actor, Capability::WriteFile, &path)
+        .await
+        .map_err(|e| FileSystemError::with_path("PERMISSION_DENIED", &e, &full_path))?;
+
+    let before = fs::read(&full_path).ok();
+    let before_text = before.as_deref().map(String::from_utf8_lossy);
+    let after = String::from_utf8_lossy(&bytes);
+    secrets::check_agent_write(&app, actor, &path, before_text.as_deref(), &after)
+        .map_err(|e| FileSystemError::with_path("SECRET_DETECTED", &e, &full_path))?;
+    create_checkpoint(&full_path, "restore")
+        .map_err(|e| FileSystemError::with_path("CHECKPOINT_ERROR", &e.to_string(), &full_path))?;
+
+    if let Some(parent) = full_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| FileSystemError::with_path("CREATE_DIR_ERROR", &e.to_string(), parent))?;
+    }
+    fs::write(&full_path, &bytes)
+        .map_err(|e| FileSystemError::with_path("WRITE_ERROR", &e.to_string(), &full_path))?;
+    audit::record_change(
+        actor,
+        "restore_checkpoint",
+        &path,
+        before.as_deref(),
+        Some(&bytes),
+        json!({ "checkpoint": checkpoint.id }),
+    );
+
+    Ok(checkpoint)
+}
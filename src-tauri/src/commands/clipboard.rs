@@ -0,0 +1,259 @@
+// src/commands/clipboard.rs
+
+use chrono::Local;
+use serde::Serialize;
+use serde_json::json;
+use std::fs;
+use tauri::ipc::Request;
+use tauri::{command, AppHandle};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use super::audit;
+use super::dry_run;
+use super::fs::{resolve_workspace_path, write_file, FileSystemError};
+use super::permissions::{authorize, Actor, Capability};
+
+/// Clipboard text returned by `read_clipboard` is cut off after this.
+const MAX_TEXT_BYTES: usize = 1024 * 1024;
+/// Pasted images are scaled down to fit this many pixels on their longer
+/// side unless the caller asks for another size.
+const DEFAULT_MAX_DIMENSION: u32 = 2048;
+/// Images are refused outright beyond this, before any scaling.
+const MAX_SOURCE_PIXELS: u64 = 100_000_000;
+/// Encoded PNGs larger than this are not written.
+const MAX_PNG_BYTES: usize = 20 * 1024 * 1024;
+/// Where pasted images go when no path is given, relative to the
+/// workspace root.
+const ATTACHMENTS_DIR: &str = ".mighty/attachments";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardKind {
+    Text,
+    Image,
+    Empty,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClipboardContent {
+    pub kind: ClipboardKind,
+    pub text: Option<String>,
+    /// Whether `text` was cut off at the size limit.
+    pub truncated: bool,
+    pub image_width: Option<u32>,
+    pub image_height: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PastedImage {
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+    /// Size of the PNG file.
+    pub bytes: usize,
+    /// Whether the image was scaled down to fit the size limit.
+    pub scaled: bool,
+}
+
+fn clipboard_error(e: impl ToString) -> FileSystemError {
+    FileSystemError::new("CLIPBOARD_ERROR", &e.to_string())
+}
+
+/// Averages each `factor`×`factor` block of an RGBA image into one pixel.
+fn downscale(rgba: &[u8], width: u32, height: u32, factor: u32) -> (Vec<u8>, u32, u32) {
+    let (new_width, new_height) = (width.div_ceil(factor), height.div_ceil(factor));
+    let mut scaled = Vec::with_capacity((new_width * new_height * 4) as usize);
+    for y in 0..new_height {
+        for x in 0..new_width {
+            let mut sum = [0u64; 4];
+            let mut count = 0;
+            for source_y in (y * factor)..((y + 1) * factor).min(height) {
+                for source_x in (x * factor)..((x + 1) * factor).min(width) {
+                    let offset = ((source_y * width + source_x) * 4) as usize;
+                    for (channel, total) in sum.iter_mut().enumerate() {
+                        *total += rgba[offset + channel] as u64;
+                    }
+                    count += 1;
+                }
+            }
+            scaled.extend(sum.iter().map(|total| (total / count) as u8));
+        }
+    }
+    (scaled, new_width, new_height)
+}
+
+fn encode_png(rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>, png::EncodingError> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    writer.finish()?;
+    Ok(png)
+}
+
+/// What the system clipboard holds: text, or an image's size.
+#[command]
+pub async fn read_clipboard(app: AppHandle) -> Result<ClipboardContent, String> {
+    let clipboard = app.clipboard();
+    if let Ok(mut text) = clipboard.read_text() {
+        if !text.is_empty() {
+            let truncated = text.len() > MAX_TEXT_BYTES;
+            if truncated {
+                let mut end = MAX_TEXT_BYTES;
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                text.truncate(end);
+            }
+            return Ok(ClipboardContent {
+                kind: ClipboardKind::Text,
+                text: Some(text),
+                truncated,
+                image_width: None,
+                image_height: None,
+            });
+        }
+    }
+    if let Ok(image) = clipboard.read_image() {
+        return Ok(ClipboardContent {
+            kind: ClipboardKind::Image,
+            text: None,
+            truncated: false,
+            image_width: Some(image.width()),
+            image_height: Some(image.height()),
+        });
+    }
+    Ok(ClipboardContent {
+        kind: ClipboardKind::Empty,
+        text: None,
+        truncated: false,
+        image_width: None,
+        image_height: None,
+    })
+}
+
+/// Writes the clipboard's text to a new workspace file. An existing file is
+/// only replaced with `overwrite`.
+#[command]
+pub async fn paste_as_file(
+    app: AppHandle,
+    request: Request<'_>,
+    path: String,
+    overwrite: Option<bool>,
+) -> Result<String, FileSystemError> {
+    let full_path = resolve_workspace_path(&path)?;
+    if full_path.exists() && !overwrite.unwrap_or(false) {
+        return Err(FileSystemError::with_path(
+            "ALREADY_EXISTS",
+            "File already exists",
+            &full_path,
+        ));
+    }
+    let text = app.clipboard().read_text().map_err(clipboard_error)?;
+    if text.is_empty() {
+        return Err(FileSystemError::new(
+            "CLIPBOARD_EMPTY",
+            "The clipboard holds no text",
+        ));
+    }
+    write_file(app, request, path.clone(), text, None, None, None).await?;
+    Ok(path)
+}
+
+/// Saves the clipboard's image as a PNG in the workspace, by default under
+/// `.mighty/attachments`, so it can be attached to a prompt. Images larger
+/// than `max_dimension` pixels on a side are scaled down.
+#[command]
+pub async fn paste_image(
+    app: AppHandle,
+    request: Request<'_>,
+    path: Option<String>,
+    max_dimension: Option<u32>,
+) -> Result<PastedImage, FileSystemError> {
+    let image = app
+        .clipboard()
+        .read_image()
+        .map_err(|_| FileSystemError::new("CLIPBOARD_EMPTY", "The clipboard holds no image"))?;
+    let (width, height) = (image.width(), image.height());
+    if width == 0 || height == 0 {
+        return Err(FileSystemError::new(
+            "CLIPBOARD_EMPTY",
+            "The clipboard holds no image",
+        ));
+    }
+    if width as u64 * height as u64 > MAX_SOURCE_PIXELS {
+        return Err(FileSystemError::new(
+            "IMAGE_TOO_LARGE",
+            &format!("The clipboard image is too large ({}×{})", width, height),
+        ));
+    }
+
+    let max_dimension = max_dimension.unwrap_or(DEFAULT_MAX_DIMENSION).max(1);
+    let factor = width.max(height).div_ceil(max_dimension);
+    let (rgba, width, height) = if factor > 1 {
+        downscale(image.rgba(), width, height, factor)
+    } else {
+        (image.rgba().to_vec(), width, height)
+    };
+    let png = encode_png(&rgba, width, height)
+        .map_err(|e| FileSystemError::new("ENCODING_ERROR", &e.to_string()))?;
+    if png.len() > MAX_PNG_BYTES {
+        return Err(FileSystemError::new(
+            "IMAGE_TOO_LARGE",
+            &format!(
+                "The image is {} MB as a PNG; the limit is {} MB",
+                png.len() / (1024 * 1024),
+                MAX_PNG_BYTES / (1024 * 1024)
+            ),
+        ));
+    }
+
+    let path = path.unwrap_or_else(|| {
+        format!(
+            "{}/paste-{}.png",
+            ATTACHMENTS_DIR,
+            Local::now().format("%Y%m%d-%H%M%S%3f")
+        )
+    });
+    let full_path = resolve_workspace_path(&path)?;
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        let details = json!({ "width": width, "height": height });
+        dry_run::plan_step("paste_image", &path, None, details);
+        return Ok(PastedImage {
+            path,
+            width,
+            height,
+            bytes: png.len(),
+            scaled: factor > 1,
+        });
+    }
+    authorize(&app, actor, Capability::WriteFile, &path)
+        .await
+        .map_err(|e| FileSystemError::with_path("PERMISSION_DENIED", &e, &full_path))?;
+    if let Some(parent) = full_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| FileSystemError::with_path("CREATE_DIR_ERROR", &e.to_string(), parent))?;
+    }
+    let before = fs::read(&full_path).ok();
+    fs::write(&full_path, &png)
+        .map_err(|e| FileSystemError::with_path("WRITE_ERROR", &e.to_string(), &full_path))?;
+    audit::record_change(
+        actor,
+        "paste_image",
+        &path,
+        before.as_deref(),
+        Some(&png),
+        json!({ "width": width, "height": height }),
+    );
+
+    Ok(PastedImage {
+        path,
+        width,
+        height,
+        bytes: png.len(),
+        scaled: factor > 1,
+    })
+}
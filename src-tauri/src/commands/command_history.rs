@@ -0,0 +1,229 @@
+// src/commands/command_history.rs
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tauri::command;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::storage::{delete_record, put_record, records_with_prefix};
+
+/// Keys sort by start time, so a prefix scan returns entries oldest first.
+const STORAGE_PREFIX: &str = "command_history:";
+/// Entries kept across all sessions before the oldest are dropped.
+const MAX_ENTRIES: usize = 10_000;
+/// New entries between prunes, so a write doesn't scan the whole history.
+const PRUNE_EVERY: usize = 100;
+const DEFAULT_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandHistoryEntry {
+    pub session_id: String,
+    pub command: String,
+    pub cwd: String,
+    pub exit_code: Option<i32>,
+    pub started_at: i64,
+    pub duration_ms: u64,
+}
+
+struct PendingCommand {
+    command: String,
+    cwd: String,
+    started_at: i64,
+}
+
+/// Follows the OSC 633 markers a session's shell integration prints: E
+/// carries the command line, C marks where it starts running and D its exit
+/// status.
+#[derive(Default)]
+pub(crate) struct CommandTracker {
+    pending: Option<PendingCommand>,
+}
+
+impl CommandTracker {
+    /// Takes the text of one OSC 633 sequence and returns the command it
+    /// completes, if any.
+    pub(crate) fn observe(
+        &mut self,
+        session_id: &str,
+        cwd: &str,
+        marker: &str,
+    ) -> Option<CommandHistoryEntry> {
+        let mut fields = marker.split(';');
+        match fields.next()? {
+            "E" => {
+                let command = decode_command_line(fields.next().unwrap_or_default());
+                let command = command.trim();
+                // run_in_terminal records its own commands, not their wrappers
+                self.pending =
+                    (!command.is_empty() && !command.contains("]633;A;")).then(|| PendingCommand {
+                        command: command.to_string(),
+                        cwd: cwd.to_string(),
+                        started_at: Utc::now().timestamp_millis(),
+                    });
+                None
+            }
+            "C" => {
+                if let Some(pending) = self.pending.as_mut() {
+                    pending.started_at = Utc::now().timestamp_millis();
+                }
+                None
+            }
+            "D" => {
+                let pending = self.pending.take()?;
+                Some(CommandHistoryEntry {
+                    session_id: session_id.to_string(),
+                    command: pending.command,
+                    cwd: pending.cwd,
+                    exit_code: fields.next().and_then(|code| code.parse().ok()),
+                    duration_ms: (Utc::now().timestamp_millis() - pending.started_at).max(0) as u64,
+                    started_at: pending.started_at,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Undoes the escaping of an OSC 633 E command line, where `\\` is a
+/// backslash and `\xAB` a byte that can't appear literally (`;`, newlines,
+/// control characters).
+fn decode_command_line(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            if bytes.get(i + 1) == Some(&b'\\') {
+                decoded.push(b'\\');
+                i += 2;
+                continue;
+            }
+            if bytes.get(i + 1) == Some(&b'x') && i + 3 < bytes.len() {
+                let hex = std::str::from_utf8(&bytes[i + 2..i + 4]).unwrap_or_default();
+                if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                    decoded.push(byte);
+                    i += 4;
+                    continue;
+                }
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+static RECORDED: AtomicUsize = AtomicUsize::new(0);
+
+/// Saves a finished command. Failures are logged rather than returned, since
+/// history is never worth interrupting a terminal over.
+pub(crate) fn record_command(entry: &CommandHistoryEntry) {
+    let key = format!(
+        "{}{:013}:{}",
+        STORAGE_PREFIX,
+        entry.started_at,
+        Uuid::new_v4().simple()
+    );
+    let result = serde_json::to_string(entry)
+        .map_err(|e| e.to_string())
+        .and_then(|json| put_record(&key, &json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("Failed to record terminal command: {}", e);
+        return;
+    }
+
+    if RECORDED
+        .fetch_add(1, Ordering::Relaxed)
+        .is_multiple_of(PRUNE_EVERY)
+    {
+        prune_history();
+    }
+}
+
+fn prune_history() {
+    let records = match records_with_prefix(STORAGE_PREFIX) {
+        Ok(records) => records,
+        Err(e) => {
+            warn!("Failed to load command history: {}", e);
+            return;
+        }
+    };
+    let excess = records.len().saturating_sub(MAX_ENTRIES);
+    for (key, _) in records.into_iter().take(excess) {
+        if let Err(e) = delete_record(&key) {
+            warn!("Failed to prune command history: {}", e);
+            return;
+        }
+    }
+}
+
+/// Scores `text` against `query` as a case-insensitive subsequence, or
+/// `None` when it doesn't match. Substring matches rank above scattered
+/// ones, and matches at word starts or in runs score higher.
+pub(crate) fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let query = query.trim().to_lowercase();
+    let text = text.to_lowercase();
+    if query.is_empty() {
+        return Some(0);
+    }
+    if let Some(position) = text.find(&query) {
+        return Some(10_000 - position.min(1_000) as i64);
+    }
+
+    let wanted: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut score = 0;
+    let mut matched = 0;
+    let mut previous: Option<char> = None;
+    let mut run = false;
+    for c in text.chars() {
+        if matched < wanted.len() && c == wanted[matched] {
+            matched += 1;
+            score += 1;
+            if run {
+                score += 5;
+            }
+            if !previous.is_some_and(|p| p.is_alphanumeric()) {
+                score += 10;
+            }
+            run = true;
+        } else {
+            run = false;
+        }
+        previous = Some(c);
+    }
+    (matched == wanted.len()).then_some(score)
+}
+
+/// Returns recorded commands, newest first and without repeats, for one
+/// session or across all of them when `session_id` is omitted. With a
+/// `query`, only fuzzy matches are returned, best first.
+#[command]
+pub async fn get_command_history(
+    session_id: Option<String>,
+    query: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<CommandHistoryEntry>, String> {
+    let records = records_with_prefix(STORAGE_PREFIX).map_err(|e| e.to_string())?;
+    let query = query.unwrap_or_default();
+
+    let mut seen = HashSet::new();
+    let mut matches: Vec<(i64, CommandHistoryEntry)> = records
+        .into_iter()
+        .rev()
+        .filter_map(|(_, json)| serde_json::from_str::<CommandHistoryEntry>(&json).ok())
+        .filter(|entry| session_id.as_ref().is_none_or(|id| &entry.session_id == id))
+        .filter(|entry| seen.insert(entry.command.clone()))
+        .filter_map(|entry| fuzzy_score(&query, &entry.command).map(|score| (score, entry)))
+        .collect();
+
+    // Stable, so equal scores stay newest first
+    matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    Ok(matches
+        .into_iter()
+        .take(limit.unwrap_or(DEFAULT_LIMIT))
+        .map(|(_, entry)| entry)
+        .collect())
+}
@@ -0,0 +1,309 @@
+// src/commands/conversation.rs
+
+use chrono::Utc;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+use tracing::{info, warn};
+
+use super::api::AnthropicMessage;
+use super::redaction;
+use super::storage::{delete_record, get_record, put_record};
+use crate::http_client;
+use crate::secrets::Redactor;
+use crate::state::app_state;
+
+const STORAGE_PREFIX: &str = "conversation_summary:";
+const ANTHROPIC_URL: &str = "https://api.anthropic.com/v1/messages";
+const DEFAULT_SUMMARY_MODEL: &str = "claude-3-5-haiku-latest";
+/// Times a request that overflows the context window is retried with more
+/// of the conversation summarized.
+pub(crate) const MAX_COMPACTIONS: usize = 3;
+/// Recent messages kept word for word by the first compaction; each retry
+/// keeps half as many.
+const KEEP_RECENT: usize = 8;
+/// Characters of each message sent to be summarized.
+const MAX_MESSAGE_CHARS: usize = 8_000;
+/// Characters of messages sent to be summarized at once, well inside the
+/// summary model's own context window; the oldest are left out past it.
+const MAX_PROMPT_CHARS: usize = 300_000;
+const SUMMARY_MAX_TOKENS: u32 = 1_024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+const INSTRUCTIONS: &str = "Summarize the conversation below between a developer and a coding \
+assistant so it can continue without the original messages. Keep decisions made, requirements, \
+file and symbol names, open questions and anything the assistant promised to do. Answer with the \
+summary only, in at most 300 words.";
+
+/// The summary standing in for the start of a conversation that outgrew
+/// the model's context window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    pub conversation_id: String,
+    pub summary: String,
+    /// How many of the conversation's first messages it stands for.
+    pub summarized_messages: usize,
+    /// Hash of those messages, so an edited history isn't summarized wrong.
+    digest: String,
+    pub model: String,
+    /// Milliseconds since the Unix epoch.
+    pub updated_at: i64,
+}
+
+fn storage_key(conversation_id: &str) -> String {
+    format!("{}{}", STORAGE_PREFIX, conversation_id)
+}
+
+fn digest(messages: &[AnthropicMessage]) -> String {
+    let mut hasher = Sha256::new();
+    for message in messages {
+        hasher.update(message.role.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(message.content.as_bytes());
+        hasher.update(b"\0");
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+fn load(conversation_id: &str) -> Option<ConversationSummary> {
+    match get_record(&storage_key(conversation_id)) {
+        Ok(json) => json.and_then(|json| serde_json::from_str(&json).ok()),
+        Err(e) => {
+            warn!("Failed to read the summary of {}: {}", conversation_id, e);
+            None
+        }
+    }
+}
+
+/// The stored summary of `conversation_id` if it still matches the start of
+/// `messages` and leaves some of them to send.
+fn matching_summary(
+    conversation_id: Option<&str>,
+    messages: &[AnthropicMessage],
+) -> Option<ConversationSummary> {
+    let summary = load(conversation_id?)?;
+    let split = summary.summarized_messages;
+    let matches = split < messages.len()
+        && messages[split].role == "user"
+        && digest(&messages[..split]) == summary.digest;
+    matches.then_some(summary)
+}
+
+/// Whether a failed request was rejected for not fitting in the model's
+/// context window.
+pub(crate) fn is_context_overflow(status: StatusCode, body: &str) -> bool {
+    if status != StatusCode::BAD_REQUEST {
+        return false;
+    }
+    let body = body.to_lowercase();
+    [
+        "prompt is too long",
+        "exceed context limit",
+        "context window",
+        "maximum context length",
+    ]
+    .iter()
+    .any(|message| body.contains(message))
+}
+
+/// `recent` with `summary` in front of its first message, which is the
+/// user's.
+fn with_summary(summary: &str, mut recent: Vec<AnthropicMessage>) -> Vec<AnthropicMessage> {
+    if let Some(first) = recent.first_mut() {
+        first.content = format!(
+            "<earlier_conversation_summary>\n{}\n</earlier_conversation_summary>\n\n{}",
+            summary, first.content
+        );
+    }
+    recent
+}
+
+/// `messages` with the start replaced by its stored summary, when
+/// `conversation_id` was summarized before, so an overflowing history
+/// isn't sent in full every time.
+pub(crate) fn apply_summary(
+    conversation_id: Option<&str>,
+    messages: &[AnthropicMessage],
+) -> Vec<AnthropicMessage> {
+    match matching_summary(conversation_id, messages) {
+        Some(summary) => with_summary(
+            &summary.summary,
+            messages[summary.summarized_messages..].to_vec(),
+        ),
+        None => messages.to_vec(),
+    }
+}
+
+/// Where the kept messages start on compaction `attempt`: at a user message
+/// among the last `KEEP_RECENT`, halved per attempt, or `None` when only
+/// the latest exchange is left.
+fn split_point(messages: &[AnthropicMessage], attempt: usize) -> Option<usize> {
+    let keep = (KEEP_RECENT >> attempt).max(1);
+    // At least the first message is summarized
+    let start = messages.len().saturating_sub(keep).max(1);
+    let is_user = |i: &usize| messages[*i].role == "user";
+    (start..messages.len())
+        .find(is_user)
+        .or_else(|| (1..start).rev().find(is_user))
+}
+
+fn prompt(previous: Option<&str>, messages: &[AnthropicMessage]) -> String {
+    let mut prompt = format!("{}\n\n", INSTRUCTIONS);
+    if let Some(previous) = previous {
+        prompt.push_str(&format!(
+            "Summary of the conversation before these messages:\n{}\n\n",
+            previous
+        ));
+    }
+    let mut budget = MAX_PROMPT_CHARS;
+    let mut turns = Vec::new();
+    for message in messages.iter().rev() {
+        let content: String = message.content.chars().take(MAX_MESSAGE_CHARS).collect();
+        let cut = if content.len() < message.content.len() {
+            " [...]"
+        } else {
+            ""
+        };
+        let turn = format!("{}: {}{}\n\n", message.role, content, cut);
+        if turn.len() > budget {
+            break;
+        }
+        budget -= turn.len();
+        turns.push(turn);
+    }
+    if turns.len() < messages.len() {
+        prompt.push_str(&format!(
+            "[{} earlier messages left out]\n\n",
+            messages.len() - turns.len()
+        ));
+    }
+    turns.iter().rev().for_each(|turn| prompt.push_str(turn));
+    prompt
+}
+
+async fn summarize(
+    api_key: &str,
+    model: &str,
+    request_id: &str,
+    prompt: String,
+    redactor: &mut Redactor,
+) -> Result<String, String> {
+    let client = http_client::client("anthropic")?;
+    let body = json!({
+        "model": model,
+        "max_tokens": SUMMARY_MAX_TOKENS,
+        "messages": [{ "role": "user", "content": redactor.redact(&prompt) }],
+    });
+    redaction::remember(request_id, "anthropic", &body, redactor.redactions());
+
+    let _turn = http_client::acquire("anthropic").await;
+    let response = client
+        .post(ANTHROPIC_URL)
+        .header("x-api-key", api_key)
+        .header("anthropic-version", "2023-06-01")
+        .timeout(REQUEST_TIMEOUT)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    let text = response.text().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("Request failed with status {}: {}", status, text));
+    }
+    let body: Value = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+    body["content"][0]["text"]
+        .as_str()
+        .map(|summary| redactor.restore(summary.trim()))
+        .ok_or_else(|| "The response had no text".to_string())
+}
+
+/// Shortens `messages`, the conversation as the caller sent it, after the
+/// model said it doesn't fit: older messages are summarized by the
+/// `models.summary` model and the recent ones kept. The summary is stored
+/// with `conversation_id`, when there is one, for the conversation's next
+/// requests.
+pub(crate) async fn compact(
+    app: &AppHandle,
+    api_key: &str,
+    request_id: &str,
+    conversation_id: Option<&str>,
+    messages: &[AnthropicMessage],
+    attempt: usize,
+    redactor: &mut Redactor,
+) -> Result<Vec<AnthropicMessage>, String> {
+    // Only the messages since the stored summary need summarizing
+    let previous = matching_summary(conversation_id, messages);
+    let from = previous.as_ref().map_or(0, |s| s.summarized_messages);
+    let Some(split) = split_point(&messages[from..], attempt).map(|split| from + split) else {
+        return Err(
+            "The latest message is too long for the model's context window on its own".to_string(),
+        );
+    };
+
+    let model = app_state()
+        .config
+        .lock()
+        .await
+        .models
+        .as_ref()
+        .and_then(|models| models.summary.clone())
+        .unwrap_or_else(|| DEFAULT_SUMMARY_MODEL.to_string());
+    let prompt = prompt(
+        previous.as_ref().map(|s| s.summary.as_str()),
+        &messages[from..split],
+    );
+    let summary_request_id = format!("{}:summary:{}", request_id, attempt + 1);
+    let summary = summarize(api_key, &model, &summary_request_id, prompt, redactor)
+        .await
+        .map_err(|e| format!("Failed to summarize the earlier conversation: {}", e))?;
+    info!(
+        "Summarized {} of {} messages to fit the context window",
+        split,
+        messages.len()
+    );
+
+    let record = ConversationSummary {
+        conversation_id: conversation_id.unwrap_or_default().to_string(),
+        summary,
+        summarized_messages: split,
+        digest: digest(&messages[..split]),
+        model,
+        updated_at: Utc::now().timestamp_millis(),
+    };
+    if let Some(conversation_id) = conversation_id {
+        match serde_json::to_string(&record) {
+            Ok(json) => {
+                if let Err(e) = put_record(&storage_key(conversation_id), &json) {
+                    warn!("Failed to store the summary of {}: {}", conversation_id, e);
+                }
+            }
+            Err(e) => warn!(
+                "Failed to serialize the summary of {}: {}",
+                conversation_id, e
+            ),
+        }
+    }
+    if let Err(e) = app.emit("conversation-summarized", &record) {
+        warn!("Failed to emit conversation-summarized: {}", e);
+    }
+    Ok(with_summary(&record.summary, messages[split..].to_vec()))
+}
+
+/// The summary standing in for the start of a conversation, if it ever
+/// outgrew the context window.
+#[command]
+pub async fn get_conversation_summary(
+    conversation_id: String,
+) -> Result<Option<ConversationSummary>, String> {
+    Ok(load(&conversation_id))
+}
+
+/// Forgets a conversation's summary, e.g. when it is deleted; the next
+/// request that overflows summarizes it afresh.
+#[command]
+pub async fn clear_conversation_summary(conversation_id: String) -> Result<(), String> {
+    delete_record(&storage_key(&conversation_id)).map_err(|e| e.to_string())
+}
@@ -0,0 +1,169 @@
+// src/commands/deep_link.rs
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use reqwest::Url;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{command, AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+pub(crate) const SCHEME: &str = "mighty";
+
+/// Requests that arrived before the UI could listen, handed over by
+/// `take_open_requests`.
+static PENDING: Lazy<Mutex<Vec<OpenPathRequest>>> = Lazy::new(|| Mutex::new(Vec::new()));
+/// Set once the UI has taken the pending requests, and so listens for more.
+static UI_READY: AtomicBool = AtomicBool::new(false);
+
+/// Payload of the `open-path-requested` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenPathRequest {
+    /// Absolute.
+    pub path: String,
+    /// Directories are opened as the workspace, files in an editor.
+    pub is_dir: bool,
+    /// 1-based.
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+impl OpenPathRequest {
+    fn new(path: &Path, line: Option<u32>, column: Option<u32>) -> Self {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        Self {
+            is_dir: path.is_dir(),
+            path: path.to_string_lossy().to_string(),
+            line,
+            column,
+        }
+    }
+}
+
+/// Reads `file.rs:12:4` as `file.rs` at line 12, column 4, unless a file
+/// by the whole name exists.
+fn parse_location(target: &str, cwd: &Path) -> Option<OpenPathRequest> {
+    let whole = cwd.join(target);
+    if whole.exists() {
+        return Some(OpenPathRequest::new(&whole, None, None));
+    }
+
+    let mut parts = target.rsplitn(3, ':');
+    let last = parts.next()?.parse::<u32>().ok()?;
+    let (path, line, column) = match (parts.next(), parts.next()) {
+        (Some(line), Some(path)) => match line.parse::<u32>() {
+            Ok(line) => (path, line, Some(last)),
+            Err(_) => (&target[..target.rfind(':')?], last, None),
+        },
+        (Some(path), None) => (path, last, None),
+        _ => return None,
+    };
+    let path = cwd.join(path);
+    path.exists()
+        .then(|| OpenPathRequest::new(&path, Some(line), column))
+}
+
+/// Reads `mighty://open?path=<path>&line=<n>&column=<n>`.
+pub(crate) fn parse_link(link: &str) -> Option<OpenPathRequest> {
+    let url = Url::parse(link).ok()?;
+    if url.scheme() != SCHEME || url.host_str() != Some("open") {
+        return None;
+    }
+    let mut path = None;
+    let mut line = None;
+    let mut column = None;
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "path" => path = Some(PathBuf::from(value.as_ref())),
+            "line" => line = value.parse().ok(),
+            "column" => column = value.parse().ok(),
+            _ => {}
+        }
+    }
+    let path = path.filter(|path| path.is_absolute())?;
+    Some(OpenPathRequest::new(&path, line, column))
+}
+
+/// The paths a command line asks to open, relative ones taken from `cwd`:
+/// positional arguments and `--workspace`. Links are left to the deep-link
+/// plugin, which is handed them too.
+pub(crate) fn parse_args(args: &[String], cwd: &Path) -> Vec<OpenPathRequest> {
+    let mut requests = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let target = match arg.split_once('=') {
+            Some(("--workspace", value)) => value,
+            _ if arg == "--workspace" => match args.next() {
+                Some(value) => value.as_str(),
+                None => continue,
+            },
+            Some(("--config", _)) => continue,
+            _ if arg == "--config" => {
+                args.next();
+                continue;
+            }
+            // Flags of our own or the platform's, and links
+            _ if arg.starts_with('-') || arg.contains("://") => continue,
+            _ => arg.as_str(),
+        };
+        match parse_location(target, cwd) {
+            Some(request) => requests.push(request),
+            None => warn!("Not opening {}: no such file or directory", target),
+        }
+    }
+    requests
+}
+
+fn focus_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Brings the window to the front and emits `open-path-requested` for each
+/// request, or holds them for `take_open_requests` while the UI loads.
+pub(crate) fn request_open(app: &AppHandle, requests: Vec<OpenPathRequest>) {
+    focus_main_window(app);
+    {
+        // Under the lock, so none slip in as the UI takes the rest
+        let mut pending = PENDING.lock();
+        if !UI_READY.load(Ordering::SeqCst) {
+            pending.extend(requests);
+            return;
+        }
+    }
+    for request in requests {
+        info!("Open requested for {}", request.path);
+        if let Err(e) = app.emit("open-path-requested", &request) {
+            warn!("Failed to emit open request: {}", e);
+        }
+    }
+}
+
+/// Holds what the app was started to open until the UI asks for it.
+pub(crate) fn defer_open(requests: Vec<OpenPathRequest>) {
+    PENDING.lock().extend(requests);
+}
+
+/// What the app was started to open: files and folders from the command
+/// line, or the link it was launched from. Later requests, such as from
+/// `mighty .` while the app is running, arrive as `open-path-requested`.
+#[command]
+pub async fn take_open_requests() -> Result<Vec<OpenPathRequest>, String> {
+    let mut pending = PENDING.lock();
+    UI_READY.store(true, Ordering::SeqCst);
+    Ok(std::mem::take(&mut *pending))
+}
+
+/// Reads the `file://` URLs macOS opens the app with, from Finder or
+/// "Open With".
+#[cfg(target_os = "macos")]
+pub(crate) fn parse_file_urls(urls: &[Url]) -> Vec<OpenPathRequest> {
+    urls.iter()
+        .filter_map(|url| url.to_file_path().ok())
+        .map(|path| OpenPathRequest::new(&path, None, None))
+        .collect()
+}
@@ -0,0 +1,907 @@
+// src/commands/deps.rs
+
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use tauri::command;
+use tracing::{debug, warn};
+
+use super::fs::{get_project_root, should_ignore_path, workspace_relative_path};
+use super::offline::is_offline;
+use super::storage::{get_record, put_record};
+use crate::http_client;
+
+const STORAGE_PREFIX: &str = "deps:";
+/// Registry answers are reused for this long, and past it only while the
+/// registry can't be reached.
+const CACHE_TTL_MS: i64 = 12 * 60 * 60 * 1000;
+/// How deep under the workspace root manifests are looked for.
+const MAX_DEPTH: usize = 4;
+const MAX_MANIFESTS: usize = 50;
+/// Registry requests in flight at once.
+const CONCURRENT_REQUESTS: usize = 8;
+/// crates.io turns away requests without one.
+const USER_AGENT: &str = concat!("MightyDev/", env!("CARGO_PKG_VERSION"));
+const OSV_QUERY_URL: &str = "https://api.osv.dev/v1/query";
+
+/// `>=1.2, <2` or `^1.2.3`: the version a requirement starts from.
+static BASE_VERSION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(?:\^|~=|~|==|>=|=|v)?\s*(\d+(?:\.\d+)*)").expect("the pattern is valid")
+});
+static VERSION_NUMBERS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^v?(\d+(?:\.\d+)*)").expect("the pattern is valid"));
+/// A PEP 508 requirement: `name[extras] (specifier) ; marker`.
+static PEP_508: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*(?P<name>[A-Za-z0-9][A-Za-z0-9._-]*)\s*(?:\[[^\]]*\])?\s*\(?(?P<specifier>[^;@)]*)\)?\s*(?P<url>@)?",
+    )
+    .expect("the pattern is valid")
+});
+static PYPI_NAME_SEPARATORS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[-_.]+").expect("the pattern is valid"));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ecosystem {
+    Cargo,
+    Npm,
+    PyPI,
+}
+
+impl Ecosystem {
+    fn for_manifest(file_name: &str) -> Option<Self> {
+        match file_name {
+            "Cargo.toml" => Some(Ecosystem::Cargo),
+            "package.json" => Some(Ecosystem::Npm),
+            "pyproject.toml" => Some(Ecosystem::PyPI),
+            _ => None,
+        }
+    }
+
+    /// The name OSV knows the ecosystem by.
+    fn osv_name(self) -> &'static str {
+        match self {
+            Ecosystem::Cargo => "crates.io",
+            Ecosystem::Npm => "npm",
+            Ecosystem::PyPI => "PyPI",
+        }
+    }
+
+    fn lockfiles(self) -> &'static [&'static str] {
+        match self {
+            Ecosystem::Cargo => &["Cargo.lock"],
+            Ecosystem::Npm => &["package-lock.json"],
+            Ecosystem::PyPI => &["poetry.lock", "uv.lock"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    Normal,
+    Dev,
+    Build,
+    Peer,
+    Optional,
+}
+
+/// How far behind the latest release a dependency is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateKind {
+    /// Breaking: the major version changes, or the minor one below 1.0.
+    Major,
+    Minor,
+    Patch,
+}
+
+/// A known vulnerability affecting the version in use, from OSV.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Advisory {
+    pub id: String,
+    pub summary: Option<String>,
+    /// Other ids for it, such as its CVE.
+    pub aliases: Vec<String>,
+    pub severity: Option<String>,
+    /// Versions that fix it.
+    pub fixed_in: Vec<String>,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyInfo {
+    /// As published, which may differ from the key a manifest renames it to.
+    pub name: String,
+    pub kind: DependencyKind,
+    /// As written in the manifest.
+    pub requirement: String,
+    /// From the lockfile, or else where the requirement starts.
+    pub current: Option<String>,
+    pub latest: Option<String>,
+    pub update: Option<UpdateKind>,
+    pub advisories: Vec<Advisory>,
+    /// Whether the registry couldn't be reached and an older answer was
+    /// used instead.
+    pub stale: bool,
+    /// Why nothing is known about the latest version or advisories.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestReport {
+    /// Relative to the workspace root.
+    pub path: String,
+    pub ecosystem: Ecosystem,
+    /// The lockfile versions were taken from, when there is one.
+    pub lockfile: Option<String>,
+    pub dependencies: Vec<DependencyInfo>,
+    /// Why the manifest couldn't be read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyReport {
+    pub manifests: Vec<ManifestReport>,
+    pub outdated: usize,
+    pub vulnerable: usize,
+    /// Whether any answer came from an out-of-date cache, or is missing,
+    /// because a registry couldn't be reached or offline mode is on.
+    pub incomplete: bool,
+    /// Milliseconds since the Unix epoch.
+    pub generated_at: i64,
+}
+
+impl DependencyReport {
+    /// Only the dependencies that are outdated or have advisories, for
+    /// handing to the agent when it suggests upgrades.
+    pub(crate) fn actionable(mut self) -> Self {
+        for manifest in &mut self.manifests {
+            manifest.dependencies.retain(|dependency| {
+                dependency.update.is_some() || !dependency.advisories.is_empty()
+            });
+        }
+        self.manifests
+            .retain(|manifest| !manifest.dependencies.is_empty() || manifest.error.is_some());
+        self
+    }
+}
+
+/// A dependency as a manifest declares it.
+#[derive(Debug, Clone)]
+struct Declared {
+    name: String,
+    kind: DependencyKind,
+    requirement: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Cached<T> {
+    value: T,
+    fetched_at: i64,
+}
+
+/// A registry answer, maybe from the cache.
+struct Lookup<T> {
+    value: Option<T>,
+    stale: bool,
+    error: Option<String>,
+}
+
+/// PyPI treats `Foo_Bar`, `foo-bar` and `foo.bar` as one project.
+fn normalize_pypi(name: &str) -> String {
+    PYPI_NAME_SEPARATORS
+        .replace_all(&name.to_lowercase(), "-")
+        .to_string()
+}
+
+fn key_name(ecosystem: Ecosystem, name: &str) -> String {
+    match ecosystem {
+        Ecosystem::PyPI => normalize_pypi(name),
+        Ecosystem::Cargo | Ecosystem::Npm => name.to_string(),
+    }
+}
+
+/// Manifests under `root`, shallowest first, skipping ignored and hidden
+/// directories such as `node_modules` and `.venv`.
+fn find_manifests(root: &Path) -> Vec<(PathBuf, Ecosystem)> {
+    let mut manifests = Vec::new();
+    let mut level = vec![root.to_path_buf()];
+    for _ in 0..=MAX_DEPTH {
+        let mut next = Vec::new();
+        for dir in level {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            let mut entries: Vec<_> = entries.flatten().collect();
+            entries.sort_by_key(|entry| entry.file_name());
+            for entry in entries {
+                let path = entry.path();
+                let name = entry.file_name().to_string_lossy().to_string();
+                if path.is_dir() {
+                    if !name.starts_with('.') && !should_ignore_path(&path.join("")) {
+                        next.push(path);
+                    }
+                } else if let Some(ecosystem) = Ecosystem::for_manifest(&name) {
+                    manifests.push((path, ecosystem));
+                    if manifests.len() >= MAX_MANIFESTS {
+                        return manifests;
+                    }
+                }
+            }
+        }
+        level = next;
+    }
+    manifests
+}
+
+/// The dependency tables of a Cargo manifest or of one of its targets.
+fn cargo_sections(table: &toml::Table) -> Vec<(&toml::Table, DependencyKind)> {
+    [
+        ("dependencies", DependencyKind::Normal),
+        ("dev-dependencies", DependencyKind::Dev),
+        ("build-dependencies", DependencyKind::Build),
+    ]
+    .into_iter()
+    .filter_map(|(key, kind)| Some((table.get(key)?.as_table()?, kind)))
+    .collect()
+}
+
+fn parse_cargo(content: &str) -> Result<Vec<Declared>, String> {
+    let manifest: toml::Table = content
+        .parse()
+        .map_err(|e| format!("Invalid TOML: {}", e))?;
+    let mut tables = cargo_sections(&manifest);
+    for target in manifest
+        .get("target")
+        .and_then(|targets| targets.as_table())
+        .into_iter()
+        .flat_map(|targets| targets.values())
+        .filter_map(|target| target.as_table())
+    {
+        tables.extend(cargo_sections(target));
+    }
+    if let Some(section) = manifest
+        .get("workspace")
+        .and_then(|workspace| workspace.get("dependencies"))
+        .and_then(|section| section.as_table())
+    {
+        tables.push((section, DependencyKind::Normal));
+    }
+
+    let mut declared = Vec::new();
+    for (table, kind) in tables {
+        for (key, value) in table {
+            let (requirement, name) = match value {
+                toml::Value::String(version) => (version.clone(), key.clone()),
+                toml::Value::Table(detail) => {
+                    // Path, git and inherited dependencies aren't from the registry
+                    if ["path", "git", "workspace"]
+                        .iter()
+                        .any(|source| detail.contains_key(*source))
+                    {
+                        continue;
+                    }
+                    let Some(version) = detail.get("version").and_then(|v| v.as_str()) else {
+                        continue;
+                    };
+                    let name = detail
+                        .get("package")
+                        .and_then(|package| package.as_str())
+                        .unwrap_or(key);
+                    (version.to_string(), name.to_string())
+                }
+                _ => continue,
+            };
+            declared.push(Declared {
+                name,
+                kind,
+                requirement,
+            });
+        }
+    }
+    Ok(declared)
+}
+
+fn parse_package_json(content: &str) -> Result<Vec<Declared>, String> {
+    let manifest: Value =
+        serde_json::from_str(content).map_err(|e| format!("Invalid JSON: {}", e))?;
+    let sections = [
+        ("dependencies", DependencyKind::Normal),
+        ("devDependencies", DependencyKind::Dev),
+        ("peerDependencies", DependencyKind::Peer),
+        ("optionalDependencies", DependencyKind::Optional),
+    ];
+    let mut declared = Vec::new();
+    for (key, kind) in sections {
+        let Some(section) = manifest[key].as_object() else {
+            continue;
+        };
+        for (name, spec) in section {
+            let Some(spec) = spec.as_str() else {
+                continue;
+            };
+            // `npm:real-name@^1.0` installs another package under this name
+            let (name, spec) = match spec.strip_prefix("npm:") {
+                Some(aliased) => match aliased.char_indices().skip(1).find(|(_, c)| *c == '@') {
+                    Some((at, _)) => (aliased[..at].to_string(), &aliased[at + 1..]),
+                    None => (aliased.to_string(), "*"),
+                },
+                None => (name.clone(), spec),
+            };
+            // Files, links, workspaces, git and URLs aren't from the registry
+            if spec.contains(':') || spec.contains('/') {
+                continue;
+            }
+            declared.push(Declared {
+                name,
+                kind,
+                requirement: spec.to_string(),
+            });
+        }
+    }
+    Ok(declared)
+}
+
+/// A PEP 508 requirement string, unless it points at a URL.
+fn parse_pep_508(requirement: &str, kind: DependencyKind) -> Option<Declared> {
+    let captures = PEP_508.captures(requirement)?;
+    if captures.name("url").is_some() {
+        return None;
+    }
+    Some(Declared {
+        name: captures["name"].to_string(),
+        kind,
+        requirement: captures["specifier"].trim().to_string(),
+    })
+}
+
+/// A Poetry dependency table: `name = "^1.2"` or `name = { version = ... }`.
+fn poetry_dependencies(table: &toml::Table, kind: DependencyKind, declared: &mut Vec<Declared>) {
+    for (name, value) in table {
+        if name.eq_ignore_ascii_case("python") {
+            continue;
+        }
+        let requirement = match value {
+            toml::Value::String(version) => version.as_str(),
+            toml::Value::Table(detail) => {
+                if ["path", "git", "url"]
+                    .iter()
+                    .any(|source| detail.contains_key(*source))
+                {
+                    continue;
+                }
+                detail
+                    .get("version")
+                    .and_then(|version| version.as_str())
+                    .unwrap_or("*")
+            }
+            _ => continue,
+        };
+        declared.push(Declared {
+            name: name.clone(),
+            kind,
+            requirement: requirement.to_string(),
+        });
+    }
+}
+
+fn parse_pyproject(content: &str) -> Result<Vec<Declared>, String> {
+    let manifest: toml::Table = content
+        .parse()
+        .map_err(|e| format!("Invalid TOML: {}", e))?;
+    let strings = |value: Option<&toml::Value>| -> Vec<String> {
+        value
+            .and_then(|value| value.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|item| item.as_str().map(String::from))
+            .collect()
+    };
+
+    let mut declared = Vec::new();
+    let project = manifest.get("project");
+    for requirement in strings(project.and_then(|project| project.get("dependencies"))) {
+        declared.extend(parse_pep_508(&requirement, DependencyKind::Normal));
+    }
+    let optional = project
+        .and_then(|project| project.get("optional-dependencies"))
+        .and_then(|optional| optional.as_table());
+    for group in optional.into_iter().flat_map(|groups| groups.values()) {
+        for requirement in strings(Some(group)) {
+            declared.extend(parse_pep_508(&requirement, DependencyKind::Optional));
+        }
+    }
+    // PEP 735 groups, which hold development tools
+    let groups = manifest
+        .get("dependency-groups")
+        .and_then(|groups| groups.as_table());
+    for group in groups.into_iter().flat_map(|groups| groups.values()) {
+        for requirement in strings(Some(group)) {
+            declared.extend(parse_pep_508(&requirement, DependencyKind::Dev));
+        }
+    }
+
+    let poetry = manifest
+        .get("tool")
+        .and_then(|tool| tool.get("poetry"))
+        .and_then(|poetry| poetry.as_table());
+    if let Some(poetry) = poetry {
+        if let Some(table) = poetry.get("dependencies").and_then(|t| t.as_table()) {
+            poetry_dependencies(table, DependencyKind::Normal, &mut declared);
+        }
+        if let Some(table) = poetry.get("dev-dependencies").and_then(|t| t.as_table()) {
+            poetry_dependencies(table, DependencyKind::Dev, &mut declared);
+        }
+        let groups = poetry.get("group").and_then(|groups| groups.as_table());
+        for group in groups.into_iter().flat_map(|groups| groups.values()) {
+            if let Some(table) = group.get("dependencies").and_then(|t| t.as_table()) {
+                poetry_dependencies(table, DependencyKind::Dev, &mut declared);
+            }
+        }
+    }
+    Ok(declared)
+}
+
+fn parse_manifest(ecosystem: Ecosystem, content: &str) -> Result<Vec<Declared>, String> {
+    match ecosystem {
+        Ecosystem::Cargo => parse_cargo(content),
+        Ecosystem::Npm => parse_package_json(content),
+        Ecosystem::PyPI => parse_pyproject(content),
+    }
+}
+
+/// Locked versions by package name. Cargo.lock, poetry.lock and uv.lock
+/// list `[[package]]` entries; package-lock.json keys them by install path.
+fn parse_lockfile(ecosystem: Ecosystem, content: &str) -> HashMap<String, String> {
+    let mut versions: HashMap<String, String> = HashMap::new();
+    match ecosystem {
+        Ecosystem::Cargo | Ecosystem::PyPI => {
+            let Ok(lock) = content.parse::<toml::Table>() else {
+                return versions;
+            };
+            let packages = lock.get("package").and_then(|packages| packages.as_array());
+            for package in packages.into_iter().flatten() {
+                let name = package.get("name").and_then(|name| name.as_str());
+                let version = package.get("version").and_then(|version| version.as_str());
+                let (Some(name), Some(version)) = (name, version) else {
+                    continue;
+                };
+                // With several versions locked, report the newest
+                let newer = versions
+                    .get(&key_name(ecosystem, name))
+                    .is_none_or(|locked| version_numbers(version) > version_numbers(locked));
+                if newer {
+                    versions.insert(key_name(ecosystem, name), version.to_string());
+                }
+            }
+        }
+        Ecosystem::Npm => {
+            let Ok(lock) = serde_json::from_str::<Value>(content) else {
+                return versions;
+            };
+            if let Some(packages) = lock["packages"].as_object() {
+                for (path, package) in packages {
+                    // Only top-level installs; nested ones belong to other packages
+                    let Some(name) = path.strip_prefix("node_modules/") else {
+                        continue;
+                    };
+                    if name.contains("/node_modules/") {
+                        continue;
+                    }
+                    if let Some(version) = package["version"].as_str() {
+                        versions.insert(name.to_string(), version.to_string());
+                    }
+                }
+            } else if let Some(dependencies) = lock["dependencies"].as_object() {
+                // lockfileVersion 1
+                for (name, package) in dependencies {
+                    if let Some(version) = package["version"].as_str() {
+                        versions.insert(name.clone(), version.to_string());
+                    }
+                }
+            }
+        }
+    }
+    versions
+}
+
+/// The lockfile next to `manifest` or in a directory above it within the
+/// workspace, as workspaces keep one lockfile at their root.
+fn find_lockfile(manifest: &Path, ecosystem: Ecosystem, root: &Path) -> Option<PathBuf> {
+    manifest
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(root))
+        .flat_map(|dir| ecosystem.lockfiles().iter().map(move |name| dir.join(name)))
+        .find(|path| path.is_file())
+}
+
+fn version_numbers(version: &str) -> Vec<u64> {
+    VERSION_NUMBERS
+        .captures(version.trim())
+        .map(|captures| {
+            captures[1]
+                .split('.')
+                .filter_map(|part| part.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The version a requirement starts from, e.g. `1.2` for `>=1.2,<2`, or
+/// `None` for `*` and upper bounds.
+fn base_version(requirement: &str) -> Option<String> {
+    let first = requirement.split(',').next()?;
+    BASE_VERSION
+        .captures(first)
+        .map(|captures| captures[1].to_string())
+}
+
+fn update_kind(current: &str, latest: &str) -> Option<UpdateKind> {
+    let mut current = version_numbers(current);
+    let mut latest = version_numbers(latest);
+    if current.is_empty() || latest.is_empty() {
+        return None;
+    }
+    let len = current.len().max(latest.len()).max(3);
+    current.resize(len, 0);
+    latest.resize(len, 0);
+    if latest <= current {
+        return None;
+    }
+    let changed = (0..len).find(|&i| current[i] != latest[i])?;
+    Some(match changed {
+        0 => UpdateKind::Major,
+        1 if current[0] == 0 => UpdateKind::Major,
+        1 => UpdateKind::Minor,
+        _ => UpdateKind::Patch,
+    })
+}
+
+fn storage_key(kind: &str, ecosystem: Ecosystem, name: &str) -> String {
+    format!(
+        "{}{}:{}:{}",
+        STORAGE_PREFIX,
+        kind,
+        ecosystem.osv_name(),
+        key_name(ecosystem, name)
+    )
+}
+
+/// The cached answer under `key` while it's fresh, otherwise `fetch`'s,
+/// falling back to the outdated cached answer when fetching fails.
+async fn cached<T, F, Fut>(key: &str, refresh: bool, fetch: F) -> Lookup<T>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    let cached: Option<Cached<T>> = match get_record(key) {
+        Ok(Some(json)) => serde_json::from_str(&json).ok(),
+        Ok(None) => None,
+        Err(e) => {
+            warn!("Failed to read the cached {}: {}", key, e);
+            None
+        }
+    };
+    let now = Utc::now().timestamp_millis();
+    let fresh = cached
+        .as_ref()
+        .is_some_and(|cached| now - cached.fetched_at < CACHE_TTL_MS);
+    if fresh && !refresh {
+        return Lookup {
+            value: cached.map(|cached| cached.value),
+            stale: false,
+            error: None,
+        };
+    }
+
+    let fetched = if is_offline() {
+        Err("Offline mode is on".to_string())
+    } else {
+        fetch().await
+    };
+    match fetched {
+        Ok(value) => {
+            let entry = Cached {
+                value,
+                fetched_at: now,
+            };
+            match serde_json::to_string(&entry) {
+                Ok(json) => {
+                    if let Err(e) = put_record(key, &json) {
+                        warn!("Failed to cache {}: {}", key, e);
+                    }
+                }
+                Err(e) => warn!("Failed to cache {}: {}", key, e),
+            }
+            Lookup {
+                value: Some(entry.value),
+                stale: false,
+                error: None,
+            }
+        }
+        Err(e) => {
+            debug!("Using the cached {}: {}", key, e);
+            match cached {
+                Some(cached) => Lookup {
+                    value: Some(cached.value),
+                    stale: true,
+                    error: None,
+                },
+                None => Lookup {
+                    value: None,
+                    stale: true,
+                    error: Some(e),
+                },
+            }
+        }
+    }
+}
+
+async fn fetch_json(request: reqwest::RequestBuilder) -> Result<Value, String> {
+    let response = request
+        .header("User-Agent", USER_AGENT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach the registry: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("The registry answered {}", status));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse the registry's answer: {}", e))
+}
+
+async fn fetch_latest(ecosystem: Ecosystem, name: &str) -> Result<String, String> {
+    let url = match ecosystem {
+        Ecosystem::Cargo => format!("https://crates.io/api/v1/crates/{}", name),
+        // Scoped names keep their `@` but escape the slash
+        Ecosystem::Npm => format!(
+            "https://registry.npmjs.org/{}/latest",
+            name.replace('/', "%2F")
+        ),
+        Ecosystem::PyPI => format!("https://pypi.org/pypi/{}/json", name),
+    };
+    let body = fetch_json(http_client::client("registry")?.get(&url)).await?;
+    let version = match ecosystem {
+        Ecosystem::Cargo => body["crate"]["max_stable_version"]
+            .as_str()
+            .or_else(|| body["crate"]["max_version"].as_str()),
+        Ecosystem::Npm => body["version"].as_str(),
+        Ecosystem::PyPI => body["info"]["version"].as_str(),
+    };
+    version
+        .map(String::from)
+        .ok_or_else(|| format!("The registry listed no version of {}", name))
+}
+
+async fn fetch_advisories(
+    ecosystem: Ecosystem,
+    name: &str,
+    version: &str,
+) -> Result<Vec<Advisory>, String> {
+    let query = json!({
+        "version": version,
+        "package": { "name": name, "ecosystem": ecosystem.osv_name() }
+    });
+    let body = fetch_json(
+        http_client::client("registry")?
+            .post(OSV_QUERY_URL)
+            .json(&query),
+    )
+    .await?;
+    let advisories = body["vulns"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|vuln| {
+            let id = vuln["id"].as_str().unwrap_or_default().to_string();
+            let mut fixed_in: Vec<String> = vuln["affected"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .flat_map(|affected| affected["ranges"].as_array().into_iter().flatten())
+                .flat_map(|range| range["events"].as_array().into_iter().flatten())
+                .filter_map(|event| event["fixed"].as_str().map(String::from))
+                .collect();
+            fixed_in.sort_by_key(|version| version_numbers(version));
+            fixed_in.dedup();
+            Advisory {
+                url: format!("https://osv.dev/vulnerability/{}", id),
+                id,
+                summary: vuln["summary"].as_str().map(String::from),
+                aliases: vuln["aliases"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|alias| alias.as_str().map(String::from))
+                    .collect(),
+                severity: vuln["database_specific"]["severity"]
+                    .as_str()
+                    .map(String::from),
+                fixed_in,
+            }
+        })
+        .collect();
+    Ok(advisories)
+}
+
+struct ReadManifest {
+    path: String,
+    ecosystem: Ecosystem,
+    lockfile: Option<String>,
+    dependencies: Result<Vec<(Declared, Option<String>)>, String>,
+}
+
+/// Reads every manifest in the workspace, each dependency with the version
+/// in use when the lockfile or requirement tells it.
+fn read_manifests(root: &Path) -> Vec<ReadManifest> {
+    find_manifests(root)
+        .into_iter()
+        .map(|(path, ecosystem)| {
+            let lockfile = find_lockfile(&path, ecosystem, root);
+            let locked = lockfile
+                .as_ref()
+                .and_then(|lockfile| fs::read_to_string(lockfile).ok())
+                .map(|content| parse_lockfile(ecosystem, &content))
+                .unwrap_or_default();
+            let dependencies = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read the manifest: {}", e))
+                .and_then(|content| parse_manifest(ecosystem, &content))
+                .map(|declared| {
+                    declared
+                        .into_iter()
+                        .map(|dependency| {
+                            let current = locked
+                                .get(&key_name(ecosystem, &dependency.name))
+                                .cloned()
+                                .or_else(|| base_version(&dependency.requirement));
+                            (dependency, current)
+                        })
+                        .collect()
+                });
+            ReadManifest {
+                path: workspace_relative_path(&path),
+                ecosystem,
+                lockfile: lockfile.map(|lockfile| workspace_relative_path(&lockfile)),
+                dependencies,
+            }
+        })
+        .collect()
+}
+
+/// Builds the report, asking registries only about what the cache doesn't
+/// answer or, with `refresh`, about everything.
+pub(crate) async fn dependency_report(refresh: bool) -> Result<DependencyReport, String> {
+    let root = get_project_root();
+    let manifests = tokio::task::spawn_blocking(move || read_manifests(&root))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Each package is looked up once, however many manifests use it
+    let mut packages: Vec<(Ecosystem, String)> = Vec::new();
+    let mut versions: Vec<(Ecosystem, String, String)> = Vec::new();
+    for manifest in &manifests {
+        let Ok(dependencies) = &manifest.dependencies else {
+            continue;
+        };
+        for (dependency, current) in dependencies {
+            let package = (manifest.ecosystem, dependency.name.clone());
+            if !packages.contains(&package) {
+                packages.push(package);
+            }
+            if let Some(current) = current {
+                let version = (manifest.ecosystem, dependency.name.clone(), current.clone());
+                if !versions.contains(&version) {
+                    versions.push(version);
+                }
+            }
+        }
+    }
+
+    let latest: HashMap<(Ecosystem, String), Lookup<String>> = stream::iter(packages)
+        .map(|(ecosystem, name)| async move {
+            let key = storage_key("latest", ecosystem, &name);
+            let lookup = cached(&key, refresh, || fetch_latest(ecosystem, &name)).await;
+            ((ecosystem, name), lookup)
+        })
+        .buffer_unordered(CONCURRENT_REQUESTS)
+        .collect()
+        .await;
+    let advisories: HashMap<(Ecosystem, String, String), Lookup<Vec<Advisory>>> =
+        stream::iter(versions)
+            .map(|(ecosystem, name, version)| async move {
+                let key = storage_key("osv", ecosystem, &format!("{}@{}", name, version));
+                let lookup = cached(&key, refresh, || {
+                    fetch_advisories(ecosystem, &name, &version)
+                })
+                .await;
+                ((ecosystem, name, version), lookup)
+            })
+            .buffer_unordered(CONCURRENT_REQUESTS)
+            .collect()
+            .await;
+
+    let mut report = DependencyReport {
+        manifests: Vec::new(),
+        outdated: 0,
+        vulnerable: 0,
+        incomplete: false,
+        generated_at: Utc::now().timestamp_millis(),
+    };
+    for manifest in manifests {
+        let (dependencies, error) = match manifest.dependencies {
+            Ok(dependencies) => (dependencies, None),
+            Err(e) => (Vec::new(), Some(e)),
+        };
+        let dependencies: Vec<DependencyInfo> = dependencies
+            .into_iter()
+            .map(|(dependency, current)| {
+                let found = &latest[&(manifest.ecosystem, dependency.name.clone())];
+                let known = current.as_ref().and_then(|current| {
+                    advisories.get(&(manifest.ecosystem, dependency.name.clone(), current.clone()))
+                });
+                let update = match (&current, &found.value) {
+                    (Some(current), Some(latest)) => update_kind(current, latest),
+                    _ => None,
+                };
+                DependencyInfo {
+                    latest: found.value.clone(),
+                    update,
+                    advisories: known
+                        .and_then(|known| known.value.clone())
+                        .unwrap_or_default(),
+                    stale: found.stale || known.is_some_and(|known| known.stale),
+                    error: found
+                        .error
+                        .clone()
+                        .or_else(|| known.and_then(|known| known.error.clone())),
+                    name: dependency.name,
+                    kind: dependency.kind,
+                    requirement: dependency.requirement,
+                    current,
+                }
+            })
+            .collect();
+        report.outdated += dependencies
+            .iter()
+            .filter(|dependency| dependency.update.is_some())
+            .count();
+        report.vulnerable += dependencies
+            .iter()
+            .filter(|dependency| !dependency.advisories.is_empty())
+            .count();
+        report.incomplete |= dependencies.iter().any(|dependency| dependency.stale);
+        report.manifests.push(ManifestReport {
+            path: manifest.path,
+            ecosystem: manifest.ecosystem,
+            lockfile: manifest.lockfile,
+            dependencies,
+            error,
+        });
+    }
+    Ok(report)
+}
+
+/// Reports the workspace's dependencies from its Cargo.toml, package.json
+/// and pyproject.toml files: the version each uses, the latest release and
+/// known advisories against it. Registry answers are cached for hours and
+/// reused past that while offline; `refresh` asks the registries again.
+#[command]
+pub async fn get_dependency_report(refresh: Option<bool>) -> Result<DependencyReport, String> {
+    dependency_report(refresh.unwrap_or(false)).await
+}
@@ -0,0 +1,529 @@
+// src/commands/dev_server.rs
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::exec::{find_executable, ExecError};
+use super::fs::{get_project_root, resolve_workspace_path};
+use super::recent::{record_use, RecentKind};
+use super::trust::ensure_trusted;
+
+/// Lines kept per process; older output is dropped.
+const LOG_CAPACITY: usize = 5000;
+const DEFAULT_MAX_RESTARTS: u32 = 5;
+/// A process that stays up this long is considered healthy again and its
+/// restart count is reset.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// How long a stopped process gets to exit after SIGTERM before it is killed.
+const STOP_GRACE: Duration = Duration::from_secs(5);
+const OUTPUT_DRAIN: Duration = Duration::from_secs(1);
+
+static DEV_SERVERS: Lazy<Mutex<HashMap<String, Arc<ManagedProcess>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static ANSI_ESCAPE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").unwrap());
+static URL_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"https?://(?:localhost|127\.0\.0\.1|0\.0\.0\.0|\[::1?\]|[\w.-]+):\d{2,5}[^\s]*")
+        .unwrap()
+});
+static PORT_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)\b(?:listening|running|started|serving|available)\b.*?\bport\s*:?\s*(\d{2,5})\b",
+    )
+    .unwrap()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    OnFailure,
+    Always,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DevServerTask {
+    /// Display name; defaults to the command line.
+    pub name: Option<String>,
+    pub command: String,
+    pub args: Option<Vec<String>>,
+    /// Working directory relative to the workspace root.
+    pub cwd: Option<String>,
+    pub env: Option<HashMap<String, String>>,
+    pub restart: Option<RestartPolicy>,
+    pub max_restarts: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessStatus {
+    Running,
+    Restarting,
+    Exited,
+    Failed,
+    Stopped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+    System,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogLine {
+    pub stream: LogStream,
+    pub line: String,
+    pub timestamp: i64,
+}
+
+/// Payload of the `dev-server-log` event.
+#[derive(Debug, Clone, Serialize)]
+struct LogEvent<'a> {
+    id: &'a str,
+    #[serde(flatten)]
+    line: &'a LogLine,
+}
+
+/// Payload of the `dev-server-url` event.
+#[derive(Debug, Clone, Serialize)]
+struct UrlEvent<'a> {
+    id: &'a str,
+    url: &'a str,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DevServerInfo {
+    pub id: String,
+    pub name: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub cwd: String,
+    pub status: ProcessStatus,
+    pub pid: Option<u32>,
+    pub exit_code: Option<i32>,
+    pub restarts: u32,
+    pub restart_policy: RestartPolicy,
+    /// URLs the process announced in its output, in the order seen.
+    pub urls: Vec<String>,
+    pub started_at: i64,
+}
+
+struct ManagedProcess {
+    info: Mutex<DevServerInfo>,
+    logs: Mutex<VecDeque<LogLine>>,
+    stop: Notify,
+}
+
+impl ManagedProcess {
+    fn snapshot(&self) -> DevServerInfo {
+        self.info.lock().clone()
+    }
+
+    fn set_status(&self, app: &AppHandle, status: ProcessStatus) {
+        let info = {
+            let mut info = self.info.lock();
+            info.status = status;
+            info.clone()
+        };
+        if let Err(e) = app.emit("dev-server-status", &info) {
+            warn!("Failed to emit dev-server-status: {}", e);
+        }
+    }
+
+    fn push_log(&self, app: &AppHandle, stream: LogStream, line: &str) {
+        let line = LogLine {
+            stream,
+            line: line.to_string(),
+            timestamp: Utc::now().timestamp_millis(),
+        };
+        let id = self.info.lock().id.clone();
+        if let Err(e) = app.emit(
+            "dev-server-log",
+            &LogEvent {
+                id: &id,
+                line: &line,
+            },
+        ) {
+            warn!("Failed to emit dev-server-log: {}", e);
+        }
+
+        let mut logs = self.logs.lock();
+        if logs.len() == LOG_CAPACITY {
+            logs.pop_front();
+        }
+        logs.push_back(line);
+    }
+
+    fn record_url(&self, app: &AppHandle, url: String) {
+        let id = {
+            let mut info = self.info.lock();
+            if info.urls.contains(&url) {
+                return;
+            }
+            info.urls.push(url.clone());
+            info.id.clone()
+        };
+        if let Err(e) = app.emit("dev-server-url", &UrlEvent { id: &id, url: &url }) {
+            warn!("Failed to emit dev-server-url: {}", e);
+        }
+    }
+}
+
+/// Finds the address a server says it is listening on, e.g. vite's
+/// "Local: http://localhost:5173/" or "Listening on port 3000".
+fn detect_url(line: &str) -> Option<String> {
+    let line = ANSI_ESCAPE.replace_all(line, "");
+    if let Some(found) = URL_PATTERN.find(&line) {
+        let url = found
+            .as_str()
+            .trim_end_matches(['.', ',', ')', '\''])
+            .replace("0.0.0.0", "localhost")
+            .replace("[::]", "localhost");
+        return Some(url);
+    }
+    PORT_PATTERN
+        .captures(&line)
+        .map(|captures| format!("http://localhost:{}", &captures[1]))
+}
+
+fn spawn_child(
+    info: &DevServerInfo,
+    program: &Path,
+    env: &HashMap<String, String>,
+) -> std::io::Result<Child> {
+    let mut command = Command::new(program);
+    command
+        .args(&info.args)
+        .envs(env)
+        .current_dir(&info.cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // Own process group, so stopping also reaches the workers the server forks
+    #[cfg(unix)]
+    command.process_group(0);
+    command.spawn()
+}
+
+fn forward_output<R>(
+    app: AppHandle,
+    process: Arc<ManagedProcess>,
+    stream: LogStream,
+    pipe: R,
+) -> JoinHandle<()>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(pipe).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Some(url) = detect_url(&line) {
+                process.record_url(&app, url);
+            }
+            process.push_log(&app, stream, &line);
+        }
+    })
+}
+
+/// Asks the process group to exit, then kills it if it is still around
+/// after the grace period.
+async fn terminate(child: &mut Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        unsafe {
+            nix::libc::kill(-(pid as i32), nix::libc::SIGTERM);
+        }
+        if tokio::time::timeout(STOP_GRACE, child.wait()).await.is_ok() {
+            return;
+        }
+        unsafe {
+            nix::libc::kill(-(pid as i32), nix::libc::SIGKILL);
+        }
+    }
+    let _ = child.kill().await;
+}
+
+/// Runs the process until it is stopped or exits for good, restarting it
+/// according to its policy with exponential backoff.
+async fn supervise(
+    app: AppHandle,
+    process: Arc<ManagedProcess>,
+    program: PathBuf,
+    env: HashMap<String, String>,
+    max_restarts: u32,
+    mut child: Child,
+) {
+    loop {
+        let started = Instant::now();
+        let mut readers = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            readers.push(forward_output(
+                app.clone(),
+                process.clone(),
+                LogStream::Stdout,
+                stdout,
+            ));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(forward_output(
+                app.clone(),
+                process.clone(),
+                LogStream::Stderr,
+                stderr,
+            ));
+        }
+
+        let status = tokio::select! {
+            status = child.wait() => status,
+            _ = process.stop.notified() => {
+                terminate(&mut child).await;
+                process.info.lock().pid = None;
+                process.push_log(&app, LogStream::System, "Process stopped");
+                process.set_status(&app, ProcessStatus::Stopped);
+                return;
+            }
+        };
+
+        // Let the readers drain what the process printed before exiting. A
+        // forked worker may keep the pipes open, so don't wait on it forever.
+        let _ = tokio::time::timeout(OUTPUT_DRAIN, futures::future::join_all(readers)).await;
+
+        let exit_code = status.as_ref().ok().and_then(|s| s.code());
+        let failed = !matches!(&status, Ok(s) if s.success());
+        process.push_log(
+            &app,
+            LogStream::System,
+            &match &status {
+                Ok(status) => format!("Process exited with {}", status),
+                Err(e) => format!("Failed to wait for process: {}", e),
+            },
+        );
+
+        let (policy, restarts) = {
+            let mut info = process.info.lock();
+            info.pid = None;
+            info.exit_code = exit_code;
+            if started.elapsed() >= STABLE_AFTER {
+                info.restarts = 0;
+            }
+            (info.restart_policy, info.restarts)
+        };
+        let wants_restart = match policy {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => failed,
+            RestartPolicy::Always => true,
+        };
+        if !wants_restart || restarts >= max_restarts {
+            if wants_restart {
+                process.push_log(
+                    &app,
+                    LogStream::System,
+                    &format!("Giving up after {} restarts", restarts),
+                );
+            }
+            let final_status = if failed {
+                ProcessStatus::Failed
+            } else {
+                ProcessStatus::Exited
+            };
+            process.set_status(&app, final_status);
+            return;
+        }
+
+        process.set_status(&app, ProcessStatus::Restarting);
+        let backoff = Duration::from_secs(1 << restarts.min(5)).min(MAX_BACKOFF);
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = process.stop.notified() => {
+                process.set_status(&app, ProcessStatus::Stopped);
+                return;
+            }
+        }
+
+        let info = process.snapshot();
+        child = match spawn_child(&info, &program, &env) {
+            Ok(child) => child,
+            Err(e) => {
+                process.push_log(
+                    &app,
+                    LogStream::System,
+                    &format!("Failed to restart: {}", e),
+                );
+                process.set_status(&app, ProcessStatus::Failed);
+                return;
+            }
+        };
+        {
+            let mut info = process.info.lock();
+            info.restarts += 1;
+            info.pid = child.id();
+            info.exit_code = None;
+            info.urls.clear();
+        }
+        process.push_log(&app, LogStream::System, "Process restarted");
+        process.set_status(&app, ProcessStatus::Running);
+    }
+}
+
+fn managed(id: &str) -> Result<Arc<ManagedProcess>, ExecError> {
+    DEV_SERVERS
+        .lock()
+        .get(id)
+        .cloned()
+        .ok_or_else(|| ExecError::new("NOT_FOUND", &format!("No dev server with id {}", id)))
+}
+
+/// Starts a long-running process such as `vite` or `cargo watch` under
+/// supervision. Its output is kept in a ring buffer and streamed as
+/// `dev-server-log` events; URLs it announces are reported through
+/// `dev-server-url`, and status changes through `dev-server-status`.
+#[command]
+pub async fn start_dev_server(
+    app: AppHandle,
+    task: DevServerTask,
+) -> Result<DevServerInfo, ExecError> {
+    ensure_trusted("running tasks").map_err(|e| ExecError::new("WORKSPACE_UNTRUSTED", &e))?;
+    let program = find_executable(&task.command)
+        .or_else(|| {
+            let path = resolve_workspace_path(&task.command).ok()?;
+            path.is_file().then_some(path)
+        })
+        .ok_or_else(|| {
+            ExecError::new(
+                "TOOL_NOT_FOUND",
+                &format!("{} was not found on PATH", task.command),
+            )
+        })?;
+    let cwd = match &task.cwd {
+        Some(cwd) => resolve_workspace_path(cwd)?,
+        None => get_project_root(),
+    };
+    if !cwd.is_dir() {
+        return Err(ExecError::new(
+            "INVALID_ARGUMENT",
+            &format!("{} is not a directory", cwd.display()),
+        ));
+    }
+
+    let args = task.args.unwrap_or_default();
+    let command_line = std::iter::once(task.command.as_str())
+        .chain(args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+    record_use(RecentKind::Task, &command_line, task.name.clone());
+    let mut info = DevServerInfo {
+        id: Uuid::new_v4().to_string(),
+        name: task.name.unwrap_or(command_line),
+        command: task.command,
+        args,
+        cwd: cwd.to_string_lossy().to_string(),
+        status: ProcessStatus::Running,
+        pid: None,
+        exit_code: None,
+        restarts: 0,
+        restart_policy: task.restart.unwrap_or_default(),
+        urls: Vec::new(),
+        started_at: Utc::now().timestamp_millis(),
+    };
+
+    let env = task.env.unwrap_or_default();
+    let child = spawn_child(&info, &program, &env).map_err(|e| {
+        ExecError::new(
+            "SPAWN_ERROR",
+            &format!("Failed to start {}: {}", program.display(), e),
+        )
+    })?;
+    info.pid = child.id();
+
+    let process = Arc::new(ManagedProcess {
+        info: Mutex::new(info.clone()),
+        logs: Mutex::new(VecDeque::new()),
+        stop: Notify::new(),
+    });
+    DEV_SERVERS.lock().insert(info.id.clone(), process.clone());
+
+    tokio::spawn(supervise(
+        app,
+        process,
+        program,
+        env,
+        task.max_restarts.unwrap_or(DEFAULT_MAX_RESTARTS),
+        child,
+    ));
+    Ok(info)
+}
+
+/// Stops a dev server and its child processes. The server stays listed,
+/// with its logs, until it is removed.
+#[command]
+pub async fn stop_dev_server(id: String) -> Result<(), ExecError> {
+    managed(&id)?.stop.notify_one();
+    Ok(())
+}
+
+/// Stops a dev server if it is running and forgets it.
+#[command]
+pub async fn remove_dev_server(id: String) -> Result<(), ExecError> {
+    let process = DEV_SERVERS
+        .lock()
+        .remove(&id)
+        .ok_or_else(|| ExecError::new("NOT_FOUND", &format!("No dev server with id {}", id)))?;
+    process.stop.notify_one();
+    Ok(())
+}
+
+#[command]
+pub async fn list_dev_servers() -> Result<Vec<DevServerInfo>, ExecError> {
+    let mut servers: Vec<DevServerInfo> = DEV_SERVERS
+        .lock()
+        .values()
+        .map(|process| process.snapshot())
+        .collect();
+    servers.sort_by_key(|server| server.started_at);
+    Ok(servers)
+}
+
+/// Returns the last `lines` lines of output (all buffered lines if omitted).
+#[command]
+pub async fn get_process_logs(id: String, lines: Option<usize>) -> Result<Vec<LogLine>, ExecError> {
+    let process = managed(&id)?;
+    let logs = process.logs.lock();
+    let skip = lines.map_or(0, |lines| logs.len().saturating_sub(lines));
+    Ok(logs.iter().skip(skip).cloned().collect())
+}
+
+/// Stops every supervised process; called when the app shuts down. The
+/// process groups are signalled directly as the runtime may not get to run
+/// the supervisors before exiting.
+pub fn stop_all_dev_servers() {
+    for process in DEV_SERVERS.lock().values() {
+        #[cfg(unix)]
+        if let Some(pid) = process.info.lock().pid {
+            unsafe {
+                nix::libc::kill(-(pid as i32), nix::libc::SIGTERM);
+            }
+        }
+        process.stop.notify_one();
+    }
+}
@@ -0,0 +1,57 @@
+// src/commands/diagnostics.rs
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+    Info,
+    Hint,
+}
+
+/// A problem reported by a linter, compiler or test run. Lines and columns
+/// are 1-based; `path` is relative to the workspace when possible.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub end_line: Option<usize>,
+    pub end_column: Option<usize>,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+    /// Rule or error code, e.g. "no-unused-vars", "E0308" or "F401".
+    pub code: Option<String>,
+    /// Tool that produced the diagnostic.
+    pub source: String,
+}
+
+/// Payload of the `diagnostics-updated` event. Diagnostics replace whatever
+/// `source` previously reported for `scope` (a path, or the whole workspace
+/// when `None`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsUpdate {
+    pub source: String,
+    pub scope: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+pub(crate) fn publish_diagnostics(
+    app: &AppHandle,
+    source: &str,
+    scope: Option<&str>,
+    diagnostics: &[Diagnostic],
+) {
+    let update = DiagnosticsUpdate {
+        source: source.to_string(),
+        scope: scope.map(String::from),
+        diagnostics: diagnostics.to_vec(),
+    };
+    if let Err(e) = app.emit("diagnostics-updated", &update) {
+        warn!("Failed to emit diagnostics: {}", e);
+    }
+}
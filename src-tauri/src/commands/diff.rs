@@ -0,0 +1,546 @@
+// src/commands/diff.rs
+
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
+use serde_json::json;
+use std::fs;
+use tauri::ipc::Request;
+use tauri::{command, AppHandle};
+
+use super::audit;
+use super::checkpoint::create_checkpoint;
+use super::dry_run as agent_dry_run;
+use super::fs::{resolve_workspace_path, FileSystemError};
+use super::permissions::{authorize, Actor, Capability};
+use crate::secrets;
+
+/// Number of unchanged lines emitted around each change in a unified diff.
+const CONTEXT_RADIUS: usize = 3;
+
+/// Maximum number of leading/trailing context lines that may be ignored
+/// when a hunk does not match exactly (same default as GNU patch).
+const MAX_FUZZ: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub content: String,
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub header: String,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextDiffResult {
+    pub unified_diff: String,
+    pub hunks: Vec<DiffHunk>,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HunkStatus {
+    Applied,
+    Conflict,
+}
+
+/// Outcome of applying a single hunk of a patch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HunkResult {
+    pub index: usize,
+    pub status: HunkStatus,
+    /// Line where the hunk header said the change should go (1-based).
+    pub expected_line: usize,
+    /// Line where the hunk was actually applied (1-based), if it was.
+    pub applied_line: Option<usize>,
+    /// Distance in lines between the expected and the actual location.
+    pub offset: isize,
+    /// Number of context lines that had to be ignored to find a match.
+    pub fuzz: usize,
+    /// Lines the hunk expected to find in the file (context + removals).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub expected: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchResult {
+    pub path: String,
+    pub dry_run: bool,
+    /// True when every hunk applied (and, unless dry_run, the file was written).
+    pub applied: bool,
+    pub conflicts: usize,
+    pub hunks: Vec<HunkResult>,
+    /// The patched content, only returned for dry runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patched_content: Option<String>,
+}
+
+/// Result of applying a patch to an in-memory string.
+#[derive(Debug, Clone)]
+pub struct PatchOutcome {
+    pub content: String,
+    pub hunks: Vec<HunkResult>,
+}
+
+impl PatchOutcome {
+    pub fn conflicts(&self) -> usize {
+        self.hunks
+            .iter()
+            .filter(|h| h.status == HunkStatus::Conflict)
+            .count()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PatchLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+#[derive(Debug, Clone)]
+struct PatchHunk {
+    old_start: usize,
+    lines: Vec<PatchLine>,
+    /// The new side of the hunk ends without a trailing newline.
+    no_newline_at_end: bool,
+}
+
+impl PatchHunk {
+    fn leading_context(&self) -> usize {
+        self.lines
+            .iter()
+            .take_while(|l| matches!(l, PatchLine::Context(_)))
+            .count()
+    }
+
+    fn trailing_context(&self) -> usize {
+        self.lines
+            .iter()
+            .rev()
+            .take_while(|l| matches!(l, PatchLine::Context(_)))
+            .count()
+    }
+
+    /// Returns the (old, new) line blocks with `fuzz` context lines dropped
+    /// from each end, along with how many lines were dropped from the front.
+    fn blocks(&self, fuzz: usize) -> (Vec<&str>, Vec<&str>, usize) {
+        let front = fuzz.min(self.leading_context());
+        let back = fuzz.min(self.trailing_context());
+        let end = self.lines.len().saturating_sub(back).max(front);
+
+        let mut old = Vec::new();
+        let mut new = Vec::new();
+        for line in &self.lines[front..end] {
+            match line {
+                PatchLine::Context(s) => {
+                    old.push(s.as_str());
+                    new.push(s.as_str());
+                }
+                PatchLine::Remove(s) => old.push(s.as_str()),
+                PatchLine::Add(s) => new.push(s.as_str()),
+            }
+        }
+        (old, new, front)
+    }
+}
+
+/// Computes a line-based unified diff between two texts.
+pub fn compute_diff(old: &str, new: &str) -> TextDiffResult {
+    let diff = TextDiff::from_lines(old, new);
+    let mut hunks = Vec::new();
+    let mut additions = 0;
+    let mut deletions = 0;
+    let mut unified = String::new();
+
+    if old != new {
+        unified.push_str("--- a\n+++ b\n");
+    }
+
+    for group in diff.grouped_ops(CONTEXT_RADIUS) {
+        let (first, last) = match (group.first(), group.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => continue,
+        };
+        let old_range = first.old_range().start..last.old_range().end;
+        let new_range = first.new_range().start..last.new_range().end;
+
+        let old_lines = old_range.len();
+        let new_lines = new_range.len();
+        // Unified diff convention: an empty range points at the line before it.
+        let old_start = if old_lines == 0 {
+            old_range.start
+        } else {
+            old_range.start + 1
+        };
+        let new_start = if new_lines == 0 {
+            new_range.start
+        } else {
+            new_range.start + 1
+        };
+        let header = format!(
+            "@@ -{},{} +{},{} @@",
+            old_start, old_lines, new_start, new_lines
+        );
+
+        unified.push_str(&header);
+        unified.push('\n');
+
+        let mut lines = Vec::new();
+        for op in &group {
+            for change in diff.iter_changes(op) {
+                let (kind, prefix) = match change.tag() {
+                    ChangeTag::Equal => (DiffLineKind::Context, ' '),
+                    ChangeTag::Insert => {
+                        additions += 1;
+                        (DiffLineKind::Added, '+')
+                    }
+                    ChangeTag::Delete => {
+                        deletions += 1;
+                        (DiffLineKind::Removed, '-')
+                    }
+                };
+                let value = change.value();
+                let content = value.trim_end_matches(['\n', '\r']).to_string();
+
+                unified.push(prefix);
+                unified.push_str(&content);
+                unified.push('\n');
+                if !value.ends_with('\n') {
+                    unified.push_str("\\ No newline at end of file\n");
+                }
+
+                lines.push(DiffLine {
+                    kind,
+                    content,
+                    old_line: change.old_index().map(|i| i + 1),
+                    new_line: change.new_index().map(|i| i + 1),
+                });
+            }
+        }
+
+        hunks.push(DiffHunk {
+            old_start,
+            old_lines,
+            new_start,
+            new_lines,
+            header,
+            lines,
+        });
+    }
+
+    TextDiffResult {
+        unified_diff: unified,
+        hunks,
+        additions,
+        deletions,
+    }
+}
+
+fn parse_range(range: &str) -> Result<(usize, usize), String> {
+    let mut parts = range.splitn(2, ',');
+    let start = parts
+        .next()
+        .unwrap_or_default()
+        .parse::<usize>()
+        .map_err(|e| format!("Invalid hunk range '{}': {}", range, e))?;
+    let count = match parts.next() {
+        Some(count) => count
+            .parse::<usize>()
+            .map_err(|e| format!("Invalid hunk range '{}': {}", range, e))?,
+        None => 1,
+    };
+    Ok((start, count))
+}
+
+/// Parses the hunks out of a unified diff, ignoring file headers.
+fn parse_unified_diff(diff: &str) -> Result<Vec<PatchHunk>, String> {
+    let mut hunks: Vec<PatchHunk> = Vec::new();
+
+    for raw in diff.lines() {
+        let line = raw.strip_suffix('\r').unwrap_or(raw);
+
+        if let Some(rest) = line.strip_prefix("@@") {
+            let spec = rest
+                .split("@@")
+                .next()
+                .ok_or_else(|| format!("Malformed hunk header: {}", line))?;
+            let mut old_range = None;
+            for part in spec.split_whitespace() {
+                if let Some(range) = part.strip_prefix('-') {
+                    old_range = Some(parse_range(range)?);
+                }
+            }
+            let (old_start, _) =
+                old_range.ok_or_else(|| format!("Malformed hunk header: {}", line))?;
+            hunks.push(PatchHunk {
+                old_start,
+                lines: Vec::new(),
+                no_newline_at_end: false,
+            });
+            continue;
+        }
+
+        let hunk = match hunks.last_mut() {
+            Some(hunk) => hunk,
+            // Anything before the first hunk is a file header (diff/index/---/+++)
+            None => continue,
+        };
+
+        if line.starts_with('\\') {
+            // "\ No newline at end of file" only matters for the new side
+            hunk.no_newline_at_end = !matches!(hunk.lines.last(), Some(PatchLine::Remove(_)));
+            continue;
+        }
+
+        match line.chars().next() {
+            Some(' ') => hunk.lines.push(PatchLine::Context(line[1..].to_string())),
+            Some('-') if !line.starts_with("--- ") => {
+                hunk.lines.push(PatchLine::Remove(line[1..].to_string()))
+            }
+            Some('+') if !line.starts_with("+++ ") => {
+                hunk.lines.push(PatchLine::Add(line[1..].to_string()))
+            }
+            // Some generators drop the leading space on blank context lines
+            None => hunk.lines.push(PatchLine::Context(String::new())),
+            _ => {}
+        }
+    }
+
+    if hunks.is_empty() {
+        return Err("Patch does not contain any hunks".to_string());
+    }
+
+    Ok(hunks)
+}
+
+fn matches_at(buffer: &[String], pos: usize, block: &[&str]) -> bool {
+    pos + block.len() <= buffer.len()
+        && buffer[pos..pos + block.len()]
+            .iter()
+            .zip(block)
+            .all(|(have, want)| have == want)
+}
+
+/// Finds the position closest to `expected` (and not before `min_pos`) where
+/// `block` matches the buffer.
+fn find_block(buffer: &[String], block: &[&str], expected: usize, min_pos: usize) -> Option<usize> {
+    let last = buffer.len().saturating_sub(block.len());
+    if min_pos > last {
+        return None;
+    }
+    let expected = expected.clamp(min_pos, last);
+
+    for distance in 0..=buffer.len() {
+        let after = expected + distance;
+        if after <= last && matches_at(buffer, after, block) {
+            return Some(after);
+        }
+        if distance > 0 && expected >= min_pos + distance {
+            let before = expected - distance;
+            if matches_at(buffer, before, block) {
+                return Some(before);
+            }
+        }
+        if after > last && expected < min_pos + distance {
+            break;
+        }
+    }
+    None
+}
+
+/// Applies a unified diff to `original`, tolerating shifted line numbers and
+/// up to `MAX_FUZZ` lines of mismatched context. Hunks that cannot be placed
+/// are reported as conflicts and left unapplied.
+pub fn apply_unified_diff(original: &str, diff: &str) -> Result<PatchOutcome, String> {
+    let hunks = parse_unified_diff(diff)?;
+
+    let line_ending = if original.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut trailing_newline = original.is_empty() || original.ends_with('\n');
+    let mut buffer: Vec<String> = original.lines().map(|l| l.to_string()).collect();
+
+    let mut results = Vec::with_capacity(hunks.len());
+    // Lines added minus lines removed by the hunks applied so far
+    let mut line_delta: isize = 0;
+    // Hunks may not overlap with the region touched by the previous one
+    let mut min_pos = 0;
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let (old_block, _, _) = hunk.blocks(0);
+        // Pure insertions point at the line *after* which to insert
+        let header_pos = if old_block.is_empty() {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let expected = (header_pos as isize + line_delta).max(0) as usize;
+
+        let mut placed = None;
+        for fuzz in 0..=MAX_FUZZ {
+            let (old, new, dropped_front) = hunk.blocks(fuzz);
+            if old.is_empty() && fuzz > 0 {
+                break;
+            }
+            let search_from = expected + dropped_front;
+            if let Some(pos) = find_block(&buffer, &old, search_from, min_pos) {
+                placed = Some((pos, fuzz, old.len(), new));
+                break;
+            }
+            if hunk.leading_context() <= fuzz && hunk.trailing_context() <= fuzz {
+                // Nothing left to trim
+                break;
+            }
+        }
+
+        match placed {
+            Some((pos, fuzz, old_len, new)) => {
+                let new_len = new.len();
+                buffer.splice(pos..pos + old_len, new.into_iter().map(String::from));
+                line_delta += new_len as isize - old_len as isize;
+                min_pos = pos + new_len;
+                if min_pos == buffer.len() && new_len > 0 {
+                    trailing_newline = !hunk.no_newline_at_end;
+                }
+
+                let (_, _, dropped_front) = hunk.blocks(fuzz);
+                let hunk_start = pos.saturating_sub(dropped_front);
+                results.push(HunkResult {
+                    index,
+                    status: HunkStatus::Applied,
+                    expected_line: expected + 1,
+                    applied_line: Some(hunk_start + 1),
+                    offset: hunk_start as isize - expected as isize,
+                    fuzz,
+                    expected: Vec::new(),
+                    message: None,
+                });
+            }
+            None => {
+                results.push(HunkResult {
+                    index,
+                    status: HunkStatus::Conflict,
+                    expected_line: expected + 1,
+                    applied_line: None,
+                    offset: 0,
+                    fuzz: 0,
+                    expected: old_block.iter().map(|s| s.to_string()).collect(),
+                    message: Some(format!(
+                        "Could not find matching context for hunk {} near line {}",
+                        index + 1,
+                        expected + 1
+                    )),
+                });
+            }
+        }
+    }
+
+    let mut content = buffer.join(line_ending);
+    if trailing_newline && !buffer.is_empty() {
+        content.push_str(line_ending);
+    }
+
+    Ok(PatchOutcome {
+        content,
+        hunks: results,
+    })
+}
+
+#[command]
+pub async fn diff_text(old: String, new: String) -> Result<TextDiffResult, String> {
+    Ok(compute_diff(&old, &new))
+}
+
+#[command]
+pub async fn apply_patch(
+    app: AppHandle,
+    request: Request<'_>,
+    path: String,
+    unified_diff: String,
+    dry_run: Option<bool>,
+) -> Result<PatchResult, FileSystemError> {
+    let actor = Actor::of(&request);
+    // The agent's patches only go into its plan while it is in dry run
+    let planned = agent_dry_run::is_active(actor);
+    let dry_run = dry_run.unwrap_or(false) || planned;
+    let full_path = resolve_workspace_path(&path)?;
+
+    // A missing file is only acceptable when the patch creates it
+    let existed = full_path.exists();
+    let original = if existed {
+        fs::read_to_string(&full_path)
+            .map_err(|e| FileSystemError::with_path("READ_ERROR", &e.to_string(), &full_path))?
+    } else {
+        String::new()
+    };
+
+    let outcome = apply_unified_diff(&original, &unified_diff)
+        .map_err(|e| FileSystemError::with_path("INVALID_PATCH", &e, &full_path))?;
+    let conflicts = outcome.conflicts();
+
+    if conflicts == 0 && planned {
+        let before = existed.then_some(original.as_str());
+        let diff = agent_dry_run::file_diff(&path, before, Some(&outcome.content));
+        let details = json!({ "hunks": outcome.hunks.len() });
+        agent_dry_run::plan_step("apply_patch", &path, Some(diff), details);
+    }
+
+    if conflicts == 0 && !dry_run {
+        authorize(&app, actor, Capability::WriteFile, &path)
+            .await
+            .map_err(|e| FileSystemError::with_path("PERMISSION_DENIED", &e, &full_path))?;
+        let before = existed.then_some(original.as_str());
+        secrets::check_agent_write(&app, actor, &path, before, &outcome.content)
+            .map_err(|e| FileSystemError::with_path("SECRET_DETECTED", &e, &full_path))?;
+        create_checkpoint(&full_path, "apply_patch").map_err(|e| {
+            FileSystemError::with_path("CHECKPOINT_ERROR", &e.to_string(), &full_path)
+        })?;
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                FileSystemError::with_path("CREATE_DIR_ERROR", &e.to_string(), parent)
+            })?;
+        }
+        fs::write(&full_path, &outcome.content)
+            .map_err(|e| FileSystemError::with_path("WRITE_ERROR", &e.to_string(), &full_path))?;
+        audit::record_change(
+            actor,
+            "apply_patch",
+            &path,
+            existed.then_some(original.as_bytes()),
+            Some(outcome.content.as_bytes()),
+            json!({ "hunks": outcome.hunks.len() }),
+        );
+    }
+
+    Ok(PatchResult {
+        path,
+        dry_run,
+        applied: conflicts == 0,
+        conflicts,
+        hunks: outcome.hunks,
+        patched_content: if dry_run { Some(outcome.content) } else { None },
+    })
+}
@@ -0,0 +1,148 @@
+// src/commands/dry_run.rs
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use tauri::ipc::Request;
+use tauri::{command, AppHandle, Emitter};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::audit;
+use super::diff::compute_diff;
+use super::permissions::Actor;
+
+/// Error code for calls whose result only exists once they have run.
+pub(crate) const DRY_RUN_CODE: &str = "DRY_RUN";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Set when dry run is first turned on, which is before any step can be
+/// planned.
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static PLAN: Lazy<Mutex<Vec<PlannedStep>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// A mutating call the agent made while in dry run, recorded instead of
+/// carried out.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedStep {
+    pub id: String,
+    /// The command, e.g. "write_file" or "git_commit".
+    pub operation: String,
+    /// The file, command or ref it would have acted on.
+    pub target: String,
+    /// What it would have done to a file, as a unified diff.
+    pub diff: Option<String>,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+    /// Milliseconds since the Unix epoch.
+    pub planned_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentPlan {
+    pub dry_run: bool,
+    pub steps: Vec<PlannedStep>,
+}
+
+fn plan() -> AgentPlan {
+    AgentPlan {
+        dry_run: ENABLED.load(Ordering::Relaxed),
+        steps: PLAN.lock().clone(),
+    }
+}
+
+/// The change from `before` to `after` as a unified diff of `path`; `None`
+/// stands for a missing file.
+pub(crate) fn file_diff(path: &str, before: Option<&str>, after: Option<&str>) -> String {
+    let diff = compute_diff(before.unwrap_or_default(), after.unwrap_or_default()).unified_diff;
+    match diff.strip_prefix("--- a\n+++ b\n") {
+        Some(hunks) => format!(
+            "--- {}\n+++ {}\n{}",
+            before.map_or("/dev/null".to_string(), |_| format!("a/{}", path)),
+            after.map_or("/dev/null".to_string(), |_| format!("b/{}", path)),
+            hunks
+        ),
+        None => diff,
+    }
+}
+
+/// Whether the call should be planned rather than made: `actor` is the
+/// agent and dry run is on. The user's own calls always go ahead.
+pub(crate) fn is_active(actor: Actor) -> bool {
+    actor == Actor::Agent && ENABLED.load(Ordering::Relaxed)
+}
+
+/// Records a call skipped because of `is_active`. The caller then answers
+/// as if it had succeeded where it can, or with `planned_error`.
+pub(crate) fn plan_step(operation: &str, target: &str, diff: Option<String>, details: Value) {
+    let step = PlannedStep {
+        id: Uuid::new_v4().to_string(),
+        operation: operation.to_string(),
+        target: target.to_string(),
+        diff,
+        details,
+        planned_at: Utc::now().timestamp_millis(),
+    };
+    info!("Dry run: planned {} on {}", operation, target);
+    if let Some(app) = APP.get() {
+        if let Err(e) = app.emit("agent-plan-step", &step) {
+            warn!("Failed to emit agent-plan-step: {}", e);
+        }
+    }
+    PLAN.lock().push(step);
+}
+
+/// The error message for a planned call that has no result to give
+/// without running, such as a commit or a command's output.
+pub(crate) fn planned_error(operation: &str) -> String {
+    format!(
+        "Dry run: {} was added to the plan but not run, so it has no result",
+        operation
+    )
+}
+
+/// Turns dry run on or off for the agent. While it is on, the agent's file
+/// writes, git operations, terminal input and tasks are recorded as a plan,
+/// with the diffs writes would make, instead of being carried out. Turning
+/// it on starts a new plan; turning it off keeps the plan for review. Only
+/// the user can do this.
+#[command]
+pub async fn set_agent_dry_run(
+    app: AppHandle,
+    request: Request<'_>,
+    enabled: bool,
+) -> Result<AgentPlan, String> {
+    let actor = Actor::of(&request);
+    if actor == Actor::Agent {
+        return Err("Only the user can change the agent's dry run".to_string());
+    }
+    let _ = APP.set(app);
+    if enabled && !ENABLED.load(Ordering::Relaxed) {
+        PLAN.lock().clear();
+    }
+    ENABLED.store(enabled, Ordering::Relaxed);
+    info!("Agent dry run {}", if enabled { "on" } else { "off" });
+    audit::record(
+        actor,
+        "set_agent_dry_run",
+        "agent",
+        json!({ "enabled": enabled }),
+    );
+    Ok(plan())
+}
+
+/// Whether the agent is in dry run, and the steps planned so far.
+#[command]
+pub async fn get_agent_plan() -> Result<AgentPlan, String> {
+    Ok(plan())
+}
+
+#[command]
+pub async fn clear_agent_plan() -> Result<(), String> {
+    PLAN.lock().clear();
+    Ok(())
+}
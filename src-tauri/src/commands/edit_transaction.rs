@@ -0,0 +1,393 @@
+// src/commands/edit_transaction.rs
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::ipc::Request;
+use tauri::{command, AppHandle, Emitter};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use super::audit;
+use super::checkpoint::create_checkpoint;
+use super::diff::{apply_unified_diff, HunkResult};
+use super::dry_run;
+use super::format::format_for_write;
+use super::fs::{resolve_workspace_path, FileSystemError};
+use super::permissions::{authorize, Actor, Capability};
+use crate::secrets;
+
+static EDIT_TRANSACTIONS: Lazy<Mutex<HashMap<String, EditTransaction>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone)]
+struct StagedEdit {
+    path: String,
+    patch: String,
+}
+
+#[derive(Debug, Clone)]
+struct EditTransaction {
+    created_at: i64,
+    edits: Vec<StagedEdit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StagedEditResult {
+    pub transaction_id: String,
+    pub path: String,
+    pub staged_edits: usize,
+    pub hunks: Vec<HunkResult>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    Committed,
+    RolledBack,
+    /// Nothing was written: the agent is in dry run, so the changes went
+    /// into its plan.
+    Planned,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEditSummary {
+    pub path: String,
+    pub created: bool,
+    pub conflicts: usize,
+    /// The patched content was run through the language's formatter.
+    pub formatted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionSummary {
+    pub transaction_id: String,
+    pub status: TransactionStatus,
+    pub files: Vec<FileEditSummary>,
+    pub started_at: i64,
+    pub finished_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A file's contents before the transaction touched it.
+struct Snapshot {
+    full_path: PathBuf,
+    original: Option<String>,
+}
+
+impl Snapshot {
+    fn restore(&self) -> std::io::Result<()> {
+        match &self.original {
+            Some(content) => fs::write(&self.full_path, content),
+            None if self.full_path.exists() => fs::remove_file(&self.full_path),
+            None => Ok(()),
+        }
+    }
+}
+
+fn transaction_not_found(transaction_id: &str) -> FileSystemError {
+    FileSystemError::new(
+        "TRANSACTION_NOT_FOUND",
+        &format!("Edit transaction {} not found", transaction_id),
+    )
+}
+
+fn read_original(full_path: &Path) -> Result<Option<String>, FileSystemError> {
+    if !full_path.exists() {
+        return Ok(None);
+    }
+    fs::read_to_string(full_path)
+        .map(Some)
+        .map_err(|e| FileSystemError::with_path("READ_ERROR", &e.to_string(), full_path))
+}
+
+/// Groups staged edits by path, keeping the order in which paths were first staged.
+fn group_edits(edits: &[StagedEdit]) -> Vec<(String, Vec<&str>)> {
+    let mut grouped: Vec<(String, Vec<&str>)> = Vec::new();
+    for edit in edits {
+        match grouped.iter_mut().find(|(path, _)| *path == edit.path) {
+            Some((_, patches)) => patches.push(&edit.patch),
+            None => grouped.push((edit.path.clone(), vec![&edit.patch])),
+        }
+    }
+    grouped
+}
+
+fn finish(app: &AppHandle, summary: TransactionSummary) -> TransactionSummary {
+    if let Err(e) = app.emit("edit-transaction-finished", &summary) {
+        warn!("Failed to emit edit transaction summary: {}", e);
+    }
+    summary
+}
+
+/// Starts a new edit transaction and returns its id.
+#[command]
+pub async fn begin_edit_transaction() -> Result<String, FileSystemError> {
+    let transaction_id = Uuid::new_v4().to_string();
+    EDIT_TRANSACTIONS.lock().insert(
+        transaction_id.clone(),
+        EditTransaction {
+            created_at: Utc::now().timestamp_millis(),
+            edits: Vec::new(),
+        },
+    );
+    Ok(transaction_id)
+}
+
+/// Stages a unified diff against `path`. The patch is dry-run against the
+/// current file (plus any edits already staged for it) so conflicts surface
+/// immediately rather than at commit time.
+#[command]
+pub async fn stage_edit(
+    transaction_id: String,
+    path: String,
+    patch: String,
+) -> Result<StagedEditResult, FileSystemError> {
+    let full_path = resolve_workspace_path(&path)?;
+
+    let previous: Vec<String> = {
+        let transactions = EDIT_TRANSACTIONS.lock();
+        let transaction = transactions
+            .get(&transaction_id)
+            .ok_or_else(|| transaction_not_found(&transaction_id))?;
+        transaction
+            .edits
+            .iter()
+            .filter(|edit| edit.path == path)
+            .map(|edit| edit.patch.clone())
+            .collect()
+    };
+
+    let mut content = read_original(&full_path)?.unwrap_or_default();
+    for earlier in &previous {
+        content = apply_unified_diff(&content, earlier)
+            .map_err(|e| FileSystemError::with_path("INVALID_PATCH", &e, &full_path))?
+            .content;
+    }
+
+    let outcome = apply_unified_diff(&content, &patch)
+        .map_err(|e| FileSystemError::with_path("INVALID_PATCH", &e, &full_path))?;
+    if outcome.conflicts() > 0 {
+        return Err(FileSystemError::with_path(
+            "PATCH_CONFLICT",
+            &format!(
+                "{} hunk(s) do not apply cleanly; edit was not staged",
+                outcome.conflicts()
+            ),
+            &full_path,
+        ));
+    }
+
+    let mut transactions = EDIT_TRANSACTIONS.lock();
+    let transaction = transactions
+        .get_mut(&transaction_id)
+        .ok_or_else(|| transaction_not_found(&transaction_id))?;
+    transaction.edits.push(StagedEdit {
+        path: path.clone(),
+        patch,
+    });
+
+    Ok(StagedEditResult {
+        transaction_id,
+        path,
+        staged_edits: transaction.edits.len(),
+        hunks: outcome.hunks,
+    })
+}
+
+/// Applies every staged edit. Nothing is written unless all patches apply
+/// cleanly, and if any write fails every file already written is restored.
+/// Patched files are formatted first unless `format_ai_edits` is disabled.
+#[command]
+pub async fn commit_edit_transaction(
+    app: AppHandle,
+    request: Request<'_>,
+    transaction_id: String,
+) -> Result<TransactionSummary, FileSystemError> {
+    commit_transaction(&app, Actor::of(&request), transaction_id).await
+}
+
+/// `commit_edit_transaction` for callers that know who is committing
+/// without an IPC request, recording the changes as `actor`'s.
+pub(crate) async fn commit_transaction(
+    app: &AppHandle,
+    actor: Actor,
+    transaction_id: String,
+) -> Result<TransactionSummary, FileSystemError> {
+    let transaction = EDIT_TRANSACTIONS
+        .lock()
+        .remove(&transaction_id)
+        .ok_or_else(|| transaction_not_found(&transaction_id))?;
+
+    let mut snapshots = Vec::new();
+    let mut pending = Vec::new();
+    let mut files = Vec::new();
+    let mut failure = None;
+
+    // Phase 1: compute every new file content in memory
+    for (path, patches) in group_edits(&transaction.edits) {
+        let full_path = resolve_workspace_path(&path)?;
+        let original = read_original(&full_path)?;
+
+        let mut content = original.clone().unwrap_or_default();
+        let mut conflicts = 0;
+        let mut error = None;
+        for patch in patches {
+            match apply_unified_diff(&content, patch) {
+                Ok(outcome) => {
+                    conflicts += outcome.conflicts();
+                    content = outcome.content;
+                }
+                Err(e) => error = Some(e),
+            }
+        }
+
+        if (conflicts > 0 || error.is_some()) && failure.is_none() {
+            failure = Some(format!("Edits to {} no longer apply cleanly", path));
+        }
+
+        let mut formatted = false;
+        if failure.is_none() {
+            (content, formatted) = format_for_write(&path, content).await;
+        }
+
+        files.push(FileEditSummary {
+            path,
+            created: original.is_none(),
+            conflicts,
+            formatted,
+            error,
+        });
+        snapshots.push(Snapshot {
+            full_path: full_path.clone(),
+            original,
+        });
+        pending.push((full_path, content));
+    }
+
+    let planned = failure.is_none() && dry_run::is_active(actor);
+    if planned {
+        for ((file, snapshot), (_, content)) in files.iter().zip(&snapshots).zip(&pending) {
+            let diff = dry_run::file_diff(&file.path, snapshot.original.as_deref(), Some(content));
+            let details = json!({ "transaction_id": transaction_id });
+            dry_run::plan_step("commit_edit_transaction", &file.path, Some(diff), details);
+        }
+    }
+
+    if failure.is_none() && !planned {
+        for file in &files {
+            if let Err(e) = authorize(app, actor, Capability::WriteFile, &file.path).await {
+                failure = Some(e);
+                break;
+            }
+        }
+    }
+
+    if failure.is_none() && !planned {
+        for ((file, snapshot), (_, content)) in files.iter().zip(&snapshots).zip(&pending) {
+            let original = snapshot.original.as_deref();
+            if let Err(e) = secrets::check_agent_write(app, actor, &file.path, original, content) {
+                failure = Some(e);
+                break;
+            }
+        }
+    }
+
+    if failure.is_none() && !planned {
+        // Phase 2: write everything, undoing earlier writes on the first failure
+        let reason = format!("edit_transaction:{}", transaction_id);
+        for (index, (full_path, content)) in pending.iter().enumerate() {
+            let result = create_checkpoint(full_path, &reason)
+                .and_then(|_| full_path.parent().map(fs::create_dir_all).unwrap_or(Ok(())))
+                .and_then(|_| fs::write(full_path, content));
+
+            if let Err(e) = result {
+                files[index].error = Some(e.to_string());
+                failure = Some(format!("Failed to write {}: {}", files[index].path, e));
+
+                for snapshot in snapshots[..=index].iter().rev() {
+                    if let Err(e) = snapshot.restore() {
+                        error!(
+                            "Failed to restore {} during rollback: {}",
+                            snapshot.full_path.display(),
+                            e
+                        );
+                    }
+                }
+                break;
+            }
+        }
+    }
+
+    if failure.is_none() && !planned {
+        for ((file, snapshot), (_, content)) in files.iter().zip(&snapshots).zip(&pending) {
+            audit::record_change(
+                actor,
+                "commit_edit_transaction",
+                &file.path,
+                snapshot.original.as_deref().map(str::as_bytes),
+                Some(content.as_bytes()),
+                json!({ "transaction_id": transaction_id }),
+            );
+        }
+    }
+
+    let summary = TransactionSummary {
+        transaction_id,
+        status: if planned {
+            TransactionStatus::Planned
+        } else if failure.is_none() {
+            TransactionStatus::Committed
+        } else {
+            TransactionStatus::RolledBack
+        },
+        files,
+        started_at: transaction.created_at,
+        finished_at: Utc::now().timestamp_millis(),
+        error: failure,
+    };
+
+    Ok(finish(app, summary))
+}
+
+/// Discards a transaction without touching any files.
+#[command]
+pub async fn rollback_edit_transaction(
+    app: AppHandle,
+    transaction_id: String,
+) -> Result<TransactionSummary, FileSystemError> {
+    let transaction = EDIT_TRANSACTIONS
+        .lock()
+        .remove(&transaction_id)
+        .ok_or_else(|| transaction_not_found(&transaction_id))?;
+
+    let files = group_edits(&transaction.edits)
+        .into_iter()
+        .map(|(path, _)| FileEditSummary {
+            path,
+            created: false,
+            conflicts: 0,
+            formatted: false,
+            error: None,
+        })
+        .collect();
+
+    let summary = TransactionSummary {
+        transaction_id,
+        status: TransactionStatus::RolledBack,
+        files,
+        started_at: transaction.created_at,
+        finished_at: Utc::now().timestamp_millis(),
+        error: None,
+    };
+
+    Ok(finish(&app, summary))
+}
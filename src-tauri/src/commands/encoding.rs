@@ -0,0 +1,144 @@
+// src/commands/encoding.rs
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+
+/// How many leading bytes are inspected when sniffing an encoding.
+const SNIFF_LEN: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+/// Text decoded from disk along with what's needed to write it back unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecodedText {
+    pub content: String,
+    /// WHATWG encoding name, e.g. "UTF-8", "UTF-16LE" or "windows-1252".
+    pub encoding: String,
+    pub has_bom: bool,
+    pub line_ending: LineEnding,
+    /// True when some bytes were invalid for the detected encoding and replaced.
+    pub lossy: bool,
+}
+
+/// Guesses UTF-16 for BOM-less files where most bytes on one side are NUL.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN)];
+    if sample.len() < 4 || sample.len() % 2 != 0 {
+        return None;
+    }
+
+    let pairs = sample.len() / 2;
+    let even_zeros = sample.iter().step_by(2).filter(|b| **b == 0).count();
+    let odd_zeros = sample
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|b| **b == 0)
+        .count();
+
+    if odd_zeros * 10 > pairs * 4 && even_zeros * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even_zeros * 10 > pairs * 4 && odd_zeros * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+fn detect_line_ending(content: &str) -> LineEnding {
+    let crlf = content.matches("\r\n").count();
+    let lf = content.matches('\n').count() - crlf;
+    if crlf > lf {
+        LineEnding::Crlf
+    } else {
+        LineEnding::Lf
+    }
+}
+
+/// Detects the encoding of `bytes` (BOM first, then UTF-8 validity, then a
+/// UTF-16 heuristic, falling back to Latin-1) and decodes it to UTF-8.
+/// Returns `None` for content that looks binary.
+pub fn decode_text(bytes: &[u8]) -> Option<DecodedText> {
+    let (encoding, bom_len) = match Encoding::for_bom(bytes) {
+        Some((encoding, bom_len)) => (encoding, bom_len),
+        None if std::str::from_utf8(bytes).is_ok() => (UTF_8, 0),
+        None => match sniff_utf16(bytes) {
+            Some(encoding) => (encoding, 0),
+            None => (WINDOWS_1252, 0),
+        },
+    };
+
+    let body = &bytes[bom_len..];
+    let is_utf16 = encoding == UTF_16LE || encoding == UTF_16BE;
+    if !is_utf16 && body[..body.len().min(SNIFF_LEN)].contains(&0) {
+        return None;
+    }
+
+    let (content, lossy) = encoding.decode_without_bom_handling(body);
+    let content = content.into_owned();
+
+    Some(DecodedText {
+        line_ending: detect_line_ending(&content),
+        content,
+        encoding: encoding.name().to_string(),
+        has_bom: bom_len > 0,
+        lossy,
+    })
+}
+
+/// Rewrites every line break in `content` to the requested style.
+pub fn convert_line_endings(content: &str, line_ending: LineEnding) -> String {
+    let normalized = content.replace("\r\n", "\n");
+    match line_ending {
+        LineEnding::Lf => normalized,
+        LineEnding::Crlf => normalized.replace('\n', "\r\n"),
+    }
+}
+
+/// Encodes UTF-8 text into the encoding named by `label`.
+pub fn encode_text(content: &str, label: &str, with_bom: bool) -> Result<Vec<u8>, String> {
+    let encoding = Encoding::for_label(label.trim().as_bytes())
+        .ok_or_else(|| format!("Unknown encoding: {}", label))?;
+
+    let mut bytes = Vec::with_capacity(content.len() + 3);
+
+    // encoding_rs only decodes UTF-16, so encode it by hand
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let little_endian = encoding == UTF_16LE;
+        let units = std::iter::once(0xFEFF)
+            .filter(|_| with_bom)
+            .chain(content.encode_utf16());
+        for unit in units {
+            let pair = if little_endian {
+                unit.to_le_bytes()
+            } else {
+                unit.to_be_bytes()
+            };
+            bytes.extend_from_slice(&pair);
+        }
+        return Ok(bytes);
+    }
+
+    if encoding == UTF_8 {
+        if with_bom {
+            bytes.extend_from_slice(b"\xEF\xBB\xBF");
+        }
+        bytes.extend_from_slice(content.as_bytes());
+        return Ok(bytes);
+    }
+
+    let (encoded, _, unmappable) = encoding.encode(content);
+    if unmappable {
+        return Err(format!(
+            "Content contains characters that cannot be represented in {}",
+            encoding.name()
+        ));
+    }
+    bytes.extend_from_slice(&encoded);
+    Ok(bytes)
+}
@@ -0,0 +1,156 @@
+// src/commands/environment.rs
+
+use chrono::Utc;
+use futures::future::join_all;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::command;
+use tracing::warn;
+
+use super::exec::{find_system_executable, run_tool, ExecError};
+use super::fs::get_project_root;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Last report, along with the workspace root it was taken for.
+static ENVIRONMENT_CACHE: Lazy<Mutex<Option<(PathBuf, EnvironmentReport)>>> =
+    Lazy::new(|| Mutex::new(None));
+
+/// Tools probed, as (name, candidate executables, version arguments).
+const TOOLS: &[(&str, &[&str], &[&str])] = &[
+    ("node", &["node"], &["--version"]),
+    ("npm", &["npm"], &["--version"]),
+    ("pnpm", &["pnpm"], &["--version"]),
+    ("yarn", &["yarn"], &["--version"]),
+    ("cargo", &["cargo"], &["--version"]),
+    ("rustc", &["rustc"], &["--version"]),
+    ("python", &["python3", "python"], &["--version"]),
+    ("pip", &["pip3", "pip"], &["--version"]),
+    ("go", &["go"], &["version"]),
+    ("docker", &["docker"], &["--version"]),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolInfo {
+    pub name: String,
+    pub available: bool,
+    pub path: Option<String>,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentReport {
+    pub os: String,
+    pub arch: String,
+    pub tools: Vec<ToolInfo>,
+    /// Ecosystems the workspace uses, from its manifest files.
+    pub project_types: Vec<String>,
+    /// Node package manager implied by the lockfile, if any.
+    pub package_manager: Option<String>,
+    pub detected_at: i64,
+}
+
+async fn probe_tool(name: &str, candidates: &[&str], args: &[&str]) -> ToolInfo {
+    let Some(path) = candidates.iter().find_map(|c| find_system_executable(c)) else {
+        return ToolInfo {
+            name: name.to_string(),
+            available: false,
+            path: None,
+            version: None,
+        };
+    };
+
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    let version = match run_tool(&path, &args, &get_project_root(), None, PROBE_TIMEOUT).await {
+        // Some tools (older pythons) print their version on stderr
+        Ok(output) => {
+            let version = Regex::new(r"\d+\.\d+(?:\.\d+)?").unwrap();
+            version
+                .find(&output.stdout)
+                .or_else(|| version.find(&output.stderr))
+                .map(|m| m.as_str().to_string())
+        }
+        Err(e) => {
+            warn!("Failed to probe {}: {}", name, e);
+            None
+        }
+    };
+
+    ToolInfo {
+        name: name.to_string(),
+        available: true,
+        path: Some(path.to_string_lossy().to_string()),
+        version,
+    }
+}
+
+fn project_types(root: &Path) -> Vec<String> {
+    let markers: &[(&str, &[&str])] = &[
+        ("rust", &["Cargo.toml"]),
+        ("node", &["package.json"]),
+        (
+            "python",
+            &["pyproject.toml", "requirements.txt", "setup.py", "Pipfile"],
+        ),
+        ("go", &["go.mod"]),
+        (
+            "docker",
+            &["Dockerfile", "docker-compose.yml", "compose.yaml"],
+        ),
+    ];
+    markers
+        .iter()
+        .filter(|(_, files)| files.iter().any(|file| root.join(file).exists()))
+        .map(|(kind, _)| kind.to_string())
+        .collect()
+}
+
+fn package_manager(root: &Path) -> Option<String> {
+    [
+        ("pnpm-lock.yaml", "pnpm"),
+        ("yarn.lock", "yarn"),
+        ("bun.lockb", "bun"),
+        ("package-lock.json", "npm"),
+    ]
+    .iter()
+    .find(|(lockfile, _)| root.join(lockfile).exists())
+    .map(|(_, manager)| manager.to_string())
+}
+
+/// Reports which toolchains are installed (with versions and paths) and what
+/// kind of project the workspace is. The result is cached per workspace;
+/// pass `refresh` to probe again, e.g. after installing a tool.
+#[command]
+pub async fn detect_environment(refresh: Option<bool>) -> Result<EnvironmentReport, ExecError> {
+    let root = get_project_root();
+    if !refresh.unwrap_or(false) {
+        if let Some((cached_root, report)) = ENVIRONMENT_CACHE.lock().as_ref() {
+            if *cached_root == root {
+                return Ok(report.clone());
+            }
+        }
+    }
+
+    let tools = join_all(
+        TOOLS
+            .iter()
+            .map(|(name, candidates, args)| probe_tool(name, candidates, args)),
+    )
+    .await;
+
+    let report = EnvironmentReport {
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        tools,
+        project_types: project_types(&root),
+        package_manager: package_manager(&root),
+        detected_at: Utc::now().timestamp_millis(),
+    };
+
+    *ENVIRONMENT_CACHE.lock() = Some((root, report.clone()));
+    Ok(report)
+}
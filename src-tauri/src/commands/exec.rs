@@ -0,0 +1,259 @@
+// src/commands/exec.rs
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+
+use super::fs::{get_project_root, FileSystemError};
+use super::trust::is_trusted;
+use crate::error::MightyError;
+
+/// Error returned when running an external tool fails.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "MightyError")]
+pub struct ExecError {
+    code: String,
+    message: String,
+    /// Tool output explaining the failure, when there is any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<String>,
+}
+
+impl ExecError {
+    pub(crate) fn new(code: &str, message: &str) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.to_string(),
+            details: None,
+        }
+    }
+
+    pub(crate) fn with_details(code: &str, message: &str, details: &str) -> Self {
+        Self {
+            details: Some(details.to_string()),
+            ..Self::new(code, message)
+        }
+    }
+}
+
+impl std::fmt::Display for ExecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for ExecError {}
+
+impl From<ExecError> for MightyError {
+    fn from(e: ExecError) -> Self {
+        MightyError::Exec {
+            code: e.code,
+            message: e.message,
+            details: e.details,
+        }
+    }
+}
+
+impl From<FileSystemError> for ExecError {
+    fn from(e: FileSystemError) -> Self {
+        Self::new(e.code(), e.message())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExecOutput {
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration: Duration,
+}
+
+impl ExecOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Looks `name` up in the workspace's `node_modules/.bin` and then on PATH,
+/// for project tools such as formatters and linters. The workspace's own
+/// binaries are only used once it is trusted.
+pub(crate) fn find_executable(name: &str) -> Option<PathBuf> {
+    let local_bin = get_project_root().join("node_modules").join(".bin");
+    if is_trusted() {
+        if let Some(found) = find_in(&local_bin, name) {
+            return Some(found);
+        }
+    }
+    find_system_executable(name)
+}
+
+/// Looks `name` up on PATH only, for system tools that a workspace must not
+/// be able to substitute.
+pub(crate) fn find_system_executable(name: &str) -> Option<PathBuf> {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .iter()
+        .find_map(|dir| find_in(dir, name))
+}
+
+fn find_in(dir: &Path, name: &str) -> Option<PathBuf> {
+    let extensions: &[&str] = if cfg!(windows) {
+        &[".exe", ".cmd", ".bat", ""]
+    } else {
+        &[""]
+    };
+    extensions
+        .iter()
+        .map(|ext| dir.join(format!("{}{}", name, ext)))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Runs `program` to completion, feeding it `stdin` and killing it once
+/// `timeout` elapses. A non-zero exit status is not an error here; callers
+/// decide what a failure means for their tool.
+pub(crate) async fn run_tool(
+    program: &Path,
+    args: &[String],
+    cwd: &Path,
+    stdin: Option<&str>,
+    timeout: Duration,
+) -> Result<ExecOutput, ExecError> {
+    let started = Instant::now();
+    let mut child = Command::new(program)
+        .args(args)
+        .current_dir(cwd)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            ExecError::new(
+                "SPAWN_ERROR",
+                &format!("Failed to start {}: {}", program.display(), e),
+            )
+        })?;
+
+    // Write stdin from a separate task so a tool that fills its stdout pipe
+    // before reading all input can't deadlock us
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        let input = input.to_string();
+        tokio::spawn(async move {
+            let _ = pipe.write_all(input.as_bytes()).await;
+        });
+    }
+
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => output.map_err(|e| ExecError::new("IO_ERROR", &e.to_string()))?,
+        Err(_) => {
+            return Err(ExecError::new(
+                "TIMEOUT",
+                &format!(
+                    "{} did not finish within {} ms",
+                    program.display(),
+                    timeout.as_millis()
+                ),
+            ))
+        }
+    };
+
+    Ok(ExecOutput {
+        exit_code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        duration: started.elapsed(),
+    })
+}
+
+/// Like `run_tool`, but hands each stdout line to `on_line` as soon as it is
+/// printed, for tools whose progress should be reported while they run.
+pub(crate) async fn run_tool_streaming<F>(
+    program: &Path,
+    args: &[String],
+    cwd: &Path,
+    envs: &[(&str, &str)],
+    timeout: Duration,
+    mut on_line: F,
+) -> Result<ExecOutput, ExecError>
+where
+    F: FnMut(&str),
+{
+    let started = Instant::now();
+    let mut child = Command::new(program)
+        .args(args)
+        .envs(envs.iter().copied())
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            ExecError::new(
+                "SPAWN_ERROR",
+                &format!("Failed to start {}: {}", program.display(), e),
+            )
+        })?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| ExecError::new("IO_ERROR", "Failed to capture stdout"))?;
+    let mut stderr_pipe = child
+        .stderr
+        .take()
+        .ok_or_else(|| ExecError::new("IO_ERROR", "Failed to capture stderr"))?;
+    let stderr_task = tokio::spawn(async move {
+        let mut stderr = Vec::new();
+        let _ = stderr_pipe.read_to_end(&mut stderr).await;
+        String::from_utf8_lossy(&stderr).to_string()
+    });
+
+    let run = async {
+        let mut lines = BufReader::new(stdout).lines();
+        let mut stdout = String::new();
+        while let Some(line) = lines
+            .next_line()
+            .await
+            .map_err(|e| ExecError::new("IO_ERROR", &e.to_string()))?
+        {
+            on_line(&line);
+            stdout.push_str(&line);
+            stdout.push('\n');
+        }
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| ExecError::new("IO_ERROR", &e.to_string()))?;
+        Ok::<_, ExecError>((status, stdout))
+    };
+
+    let (status, stdout) = match tokio::time::timeout(timeout, run).await {
+        Ok(result) => result?,
+        Err(_) => {
+            return Err(ExecError::new(
+                "TIMEOUT",
+                &format!(
+                    "{} did not finish within {} ms",
+                    program.display(),
+                    timeout.as_millis()
+                ),
+            ))
+        }
+    };
+
+    Ok(ExecOutput {
+        exit_code: status.code(),
+        stdout,
+        stderr: stderr_task.await.unwrap_or_default(),
+        duration: started.elapsed(),
+    })
+}
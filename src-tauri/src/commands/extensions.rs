@@ -0,0 +1,697 @@
+// src/commands/extensions.rs
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::ipc::Request;
+use tauri::{command, AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use super::audit;
+use super::dry_run;
+use super::fs::get_project_root;
+use super::permissions::{authorize, Actor, Capability};
+use super::storage::{delete_record, get_record, put_record};
+use super::trust::{ensure_trusted, is_trusted};
+use crate::config::{ExtensionsConfig, APP_IDENTIFIER};
+
+const MANIFEST_FILE_NAME: &str = "extension.toml";
+/// Extensions live in this directory under the config directory, and under
+/// `.mighty` in a workspace.
+const EXTENSIONS_DIR: &str = "extensions";
+const STORAGE_PREFIX: &str = "extension:";
+const DEFAULT_WASM_RUNNER: &str = "wasmtime";
+/// Where a WASM extension sees the workspace.
+const WASM_WORKSPACE: &str = "/workspace";
+const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+static WASM_RUNNER: Mutex<Option<String>> = Mutex::new(None);
+/// Running extension processes, keyed by extension directory.
+static RUNNING: Lazy<Mutex<HashMap<String, Arc<ExtensionProcess>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// What an extension may do, declared in its manifest and granted by
+/// enabling it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtensionCapability {
+    ReadFiles,
+    WriteFiles,
+    Network,
+    RunCommands,
+}
+
+impl ExtensionCapability {
+    fn name(self) -> &'static str {
+        match self {
+            ExtensionCapability::ReadFiles => "read_files",
+            ExtensionCapability::WriteFiles => "write_files",
+            ExtensionCapability::Network => "network",
+            ExtensionCapability::RunCommands => "run_commands",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExtensionRuntime {
+    /// A program speaking the tool protocol on stdin and stdout. It runs
+    /// with the user's privileges, so its capabilities are only a promise.
+    Process {
+        /// Looked up on `PATH`, or relative to the extension's directory
+        /// when it contains a path separator.
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        env: HashMap<String, String>,
+    },
+    /// A WASI module run by the configured runner, which only gets the
+    /// workspace and the network when those capabilities are granted.
+    Wasm {
+        /// Relative to the extension's directory.
+        module: String,
+    },
+}
+
+impl ExtensionRuntime {
+    fn kind(&self) -> &'static str {
+        match self {
+            ExtensionRuntime::Process { .. } => "process",
+            ExtensionRuntime::Wasm { .. } => "wasm",
+        }
+    }
+}
+
+/// A tool an extension offers the agent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtensionTool {
+    pub name: String,
+    pub description: String,
+    /// JSON Schema for the tool's input.
+    #[serde(default = "object_schema")]
+    pub input_schema: Value,
+    pub timeout_secs: Option<u64>,
+}
+
+fn object_schema() -> Value {
+    json!({ "type": "object" })
+}
+
+/// The `extension.toml` in an extension's directory.
+#[derive(Debug, Clone, Deserialize)]
+struct ExtensionManifest {
+    id: String,
+    name: Option<String>,
+    version: Option<String>,
+    description: Option<String>,
+    runtime: ExtensionRuntime,
+    #[serde(default)]
+    capabilities: Vec<ExtensionCapability>,
+    #[serde(default)]
+    tools: Vec<ExtensionTool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtensionSource {
+    Global,
+    Workspace,
+}
+
+/// What the user agreed to when enabling an extension.
+#[derive(Debug, Serialize, Deserialize)]
+struct Grant {
+    capabilities: Vec<ExtensionCapability>,
+    /// Milliseconds since the Unix epoch.
+    enabled_at: i64,
+}
+
+struct Extension {
+    source: ExtensionSource,
+    dir: PathBuf,
+    manifest: Result<ExtensionManifest, String>,
+}
+
+impl Extension {
+    /// Grants are per directory, so a workspace can't take over one given to
+    /// a global extension by reusing its id.
+    fn key(&self) -> String {
+        self.dir.to_string_lossy().to_string()
+    }
+
+    fn id(&self) -> String {
+        match &self.manifest {
+            Ok(manifest) => manifest.id.clone(),
+            Err(_) => self
+                .dir
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn grant(&self) -> Option<Grant> {
+        let json = get_record(&format!("{}{}", STORAGE_PREFIX, self.key()))
+            .ok()
+            .flatten()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// The manifest, if the extension is enabled for everything it asks for.
+    fn usable(&self) -> Result<&ExtensionManifest, String> {
+        let manifest = self.manifest.as_ref().map_err(|e| e.clone())?;
+        let grant = self
+            .grant()
+            .ok_or_else(|| format!("Extension {} is not enabled", manifest.id))?;
+        let ungranted: Vec<&str> = manifest
+            .capabilities
+            .iter()
+            .filter(|capability| !grant.capabilities.contains(capability))
+            .map(|capability| capability.name())
+            .collect();
+        if !ungranted.is_empty() {
+            return Err(format!(
+                "Extension {} now asks for {}; enable it again to grant them",
+                manifest.id,
+                ungranted.join(", ")
+            ));
+        }
+        Ok(manifest)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExtensionInfo {
+    pub id: String,
+    pub name: String,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub source: ExtensionSource,
+    pub path: String,
+    /// "process" or "wasm".
+    pub runtime: Option<&'static str>,
+    pub capabilities: Vec<ExtensionCapability>,
+    pub tools: Vec<ExtensionTool>,
+    pub enabled: bool,
+    pub running: bool,
+    /// Why the extension can't be used although it is enabled, or why its
+    /// manifest can't be read.
+    pub error: Option<String>,
+}
+
+impl From<&Extension> for ExtensionInfo {
+    fn from(extension: &Extension) -> Self {
+        let enabled = extension.grant().is_some();
+        let error = match extension.usable() {
+            Err(e) if enabled || extension.manifest.is_err() => Some(e),
+            _ => None,
+        };
+        let manifest = extension.manifest.as_ref().ok();
+        let id = extension.id();
+        ExtensionInfo {
+            name: manifest
+                .and_then(|manifest| manifest.name.clone())
+                .unwrap_or_else(|| id.clone()),
+            id,
+            version: manifest.and_then(|manifest| manifest.version.clone()),
+            description: manifest.and_then(|manifest| manifest.description.clone()),
+            source: extension.source,
+            path: extension.key(),
+            runtime: manifest.map(|manifest| manifest.runtime.kind()),
+            capabilities: manifest
+                .map(|manifest| manifest.capabilities.clone())
+                .unwrap_or_default(),
+            tools: manifest
+                .map(|manifest| manifest.tools.clone())
+                .unwrap_or_default(),
+            enabled,
+            running: RUNNING.lock().contains_key(&extension.key()),
+            error,
+        }
+    }
+}
+
+/// A tool of an enabled extension, as offered to the agent.
+#[derive(Debug, Serialize)]
+pub struct AvailableTool {
+    pub extension: String,
+    #[serde(flatten)]
+    pub tool: ExtensionTool,
+}
+
+/// Sent as an `extension-status` event when an extension starts or stops.
+#[derive(Debug, Clone, Serialize)]
+struct ExtensionStatus {
+    id: String,
+    running: bool,
+    error: Option<String>,
+}
+
+fn emit_status(app: &AppHandle, id: &str, running: bool, error: Option<String>) {
+    let status = ExtensionStatus {
+        id: id.to_string(),
+        running,
+        error,
+    };
+    if let Err(e) = app.emit("extension-status", &status) {
+        warn!("Failed to emit extension-status: {}", e);
+    }
+}
+
+fn extension_roots() -> Vec<(ExtensionSource, PathBuf)> {
+    let mut roots = Vec::new();
+    if let Some(config_dir) = dirs::config_dir() {
+        roots.push((
+            ExtensionSource::Global,
+            config_dir.join(APP_IDENTIFIER).join(EXTENSIONS_DIR),
+        ));
+    }
+    roots.push((
+        ExtensionSource::Workspace,
+        get_project_root().join(".mighty").join(EXTENSIONS_DIR),
+    ));
+    roots
+}
+
+/// Every extension directory with a manifest. A workspace extension replaces
+/// a global one with the same id.
+fn discover() -> Vec<Extension> {
+    let mut extensions: Vec<Extension> = Vec::new();
+    for (source, root) in extension_roots() {
+        let Ok(entries) = fs::read_dir(&root) else {
+            continue;
+        };
+        let mut dirs: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|dir| dir.join(MANIFEST_FILE_NAME).is_file())
+            .collect();
+        dirs.sort();
+        for dir in dirs {
+            let manifest_path = dir.join(MANIFEST_FILE_NAME);
+            let manifest = fs::read_to_string(&manifest_path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| {
+                    toml::from_str::<ExtensionManifest>(&content).map_err(|e| e.to_string())
+                })
+                .map_err(|e| format!("Failed to read {}: {}", manifest_path.display(), e));
+            let extension = Extension {
+                source,
+                dir,
+                manifest,
+            };
+            let id = extension.id();
+            extensions.retain(|other| other.id() != id);
+            extensions.push(extension);
+        }
+    }
+    extensions
+}
+
+fn find(id: &str) -> Result<Extension, String> {
+    discover()
+        .into_iter()
+        .find(|extension| extension.id() == id)
+        .ok_or_else(|| format!("No extension with id {}", id))
+}
+
+/// Applies the `[extensions]` settings.
+pub(crate) fn configure(config: Option<&ExtensionsConfig>) {
+    *WASM_RUNNER.lock() = config.and_then(|extensions| extensions.wasm_runner.clone());
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolResponse {
+    id: u64,
+    result: Option<Value>,
+    error: Option<String>,
+}
+
+/// A running extension. Calls are sent as JSON lines,
+/// `{"id": 1, "tool": "name", "input": {...}}`, and answered with
+/// `{"id": 1, "result": ...}` or `{"id": 1, "error": "message"}`.
+struct ExtensionProcess {
+    key: String,
+    id: String,
+    child: Mutex<Child>,
+    stdin: tokio::sync::Mutex<ChildStdin>,
+    pending: Mutex<HashMap<u64, oneshot::Sender<Result<Value, String>>>>,
+    next_call: AtomicU64,
+}
+
+impl ExtensionProcess {
+    async fn call(&self, tool: &str, input: Value, timeout: Duration) -> Result<Value, String> {
+        let call_id = self.next_call.fetch_add(1, Ordering::Relaxed);
+        let (respond, response) = oneshot::channel();
+        self.pending.lock().insert(call_id, respond);
+
+        let mut line = json!({ "id": call_id, "tool": tool, "input": input }).to_string();
+        line.push('\n');
+        let written = {
+            let mut stdin = self.stdin.lock().await;
+            match stdin.write_all(line.as_bytes()).await {
+                Ok(()) => stdin.flush().await,
+                Err(e) => Err(e),
+            }
+        };
+        if let Err(e) = written {
+            self.pending.lock().remove(&call_id);
+            return Err(format!("Failed to send the call to {}: {}", self.id, e));
+        }
+
+        match tokio::time::timeout(timeout, response).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(format!("Extension {} stopped before answering", self.id)),
+            Err(_) => {
+                self.pending.lock().remove(&call_id);
+                Err(format!(
+                    "{} did not answer within {} s",
+                    tool,
+                    timeout.as_secs()
+                ))
+            }
+        }
+    }
+}
+
+fn build_command(extension: &Extension, manifest: &ExtensionManifest) -> Command {
+    let workspace = get_project_root();
+    let capabilities = manifest
+        .capabilities
+        .iter()
+        .map(|capability| capability.name())
+        .collect::<Vec<_>>()
+        .join(",");
+    let mut env = vec![
+        ("MIGHTY_EXTENSION_ID".to_string(), manifest.id.clone()),
+        ("MIGHTY_CAPABILITIES".to_string(), capabilities),
+    ];
+
+    match &manifest.runtime {
+        ExtensionRuntime::Process {
+            command: program,
+            args,
+            env: extra_env,
+        } => {
+            let program = if program.contains('/') || program.contains('\\') {
+                extension.dir.join(program)
+            } else {
+                PathBuf::from(program)
+            };
+            env.push((
+                "MIGHTY_WORKSPACE".to_string(),
+                workspace.to_string_lossy().to_string(),
+            ));
+            let mut command = Command::new(program);
+            command.args(args).envs(extra_env).envs(env);
+            command
+        }
+        ExtensionRuntime::Wasm { module } => {
+            let runner = WASM_RUNNER
+                .lock()
+                .clone()
+                .unwrap_or_else(|| DEFAULT_WASM_RUNNER.to_string());
+            let mut command = Command::new(runner);
+            command.arg("run");
+            let granted = |capability| manifest.capabilities.contains(&capability);
+            if granted(ExtensionCapability::ReadFiles) || granted(ExtensionCapability::WriteFiles) {
+                command.arg(format!("--dir={}::{}", workspace.display(), WASM_WORKSPACE));
+                env.push(("MIGHTY_WORKSPACE".to_string(), WASM_WORKSPACE.to_string()));
+            }
+            if granted(ExtensionCapability::Network) {
+                command.args(["-S", "inherit-network"]);
+            }
+            for (key, value) in env {
+                command.arg(format!("--env={}={}", key, value));
+            }
+            command.arg(extension.dir.join(module));
+            command
+        }
+    }
+}
+
+/// The extension's process, started if it isn't running.
+fn ensure_running(
+    app: &AppHandle,
+    extension: &Extension,
+    manifest: &ExtensionManifest,
+) -> Result<Arc<ExtensionProcess>, String> {
+    if extension.source == ExtensionSource::Workspace {
+        ensure_trusted("running the workspace's extensions")?;
+    }
+    let mut running = RUNNING.lock();
+    let vacant = match running.entry(extension.key()) {
+        Entry::Occupied(entry) => return Ok(entry.get().clone()),
+        Entry::Vacant(entry) => entry,
+    };
+
+    let mut command = build_command(extension, manifest);
+    command
+        .current_dir(&extension.dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start extension {}: {}", manifest.id, e))?;
+    let (Some(stdin), Some(stdout), Some(stderr)) =
+        (child.stdin.take(), child.stdout.take(), child.stderr.take())
+    else {
+        return Err(format!("Extension {} has no stdio", manifest.id));
+    };
+
+    let process = Arc::new(ExtensionProcess {
+        key: extension.key(),
+        id: manifest.id.clone(),
+        child: Mutex::new(child),
+        stdin: tokio::sync::Mutex::new(stdin),
+        pending: Mutex::new(HashMap::new()),
+        next_call: AtomicU64::new(1),
+    });
+    vacant.insert(process.clone());
+
+    tauri::async_runtime::spawn(read_responses(app.clone(), process.clone(), stdout));
+    let id = manifest.id.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            info!("[extension {}] {}", id, line);
+        }
+    });
+
+    info!("Started extension {}", manifest.id);
+    emit_status(app, &manifest.id, true, None);
+    Ok(process)
+}
+
+async fn read_responses(app: AppHandle, process: Arc<ExtensionProcess>, stdout: ChildStdout) {
+    let mut lines = BufReader::new(stdout).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let Ok(response) = serde_json::from_str::<ToolResponse>(&line) else {
+            // Anything that isn't a response is treated as logging
+            info!("[extension {}] {}", process.id, line);
+            continue;
+        };
+        if let Some(respond) = process.pending.lock().remove(&response.id) {
+            let result = match response.error {
+                Some(error) => Err(error),
+                None => Ok(response.result.unwrap_or(Value::Null)),
+            };
+            let _ = respond.send(result);
+        }
+    }
+
+    // Unless it was stopped on purpose, the extension exited by itself
+    let exited = {
+        let mut running = RUNNING.lock();
+        let current = running
+            .get(&process.key)
+            .is_some_and(|running| Arc::ptr_eq(running, &process));
+        if current {
+            running.remove(&process.key);
+        }
+        current
+    };
+    process.pending.lock().clear();
+    if exited {
+        warn!("Extension {} exited", process.id);
+        emit_status(
+            &app,
+            &process.id,
+            false,
+            Some("The extension exited".to_string()),
+        );
+    }
+}
+
+fn stop(key: &str) -> bool {
+    let Some(process) = RUNNING.lock().remove(key) else {
+        return false;
+    };
+    if let Err(e) = process.child.lock().start_kill() {
+        warn!("Failed to stop extension {}: {}", process.id, e);
+    }
+    // Calls still waiting fail rather than time out
+    process.pending.lock().clear();
+    true
+}
+
+/// Stops the extensions that came with the workspace, leaving global ones
+/// running.
+pub(crate) fn stop_workspace_extensions() {
+    for extension in discover() {
+        if extension.source == ExtensionSource::Workspace {
+            stop(&extension.key());
+        }
+    }
+}
+
+pub fn stop_all_extensions() {
+    let keys: Vec<String> = RUNNING.lock().keys().cloned().collect();
+    for key in keys {
+        stop(&key);
+    }
+}
+
+/// Extensions installed globally and in the workspace.
+#[command]
+pub async fn list_extensions() -> Result<Vec<ExtensionInfo>, String> {
+    Ok(discover().iter().map(ExtensionInfo::from).collect())
+}
+
+/// Enables an extension, granting the capabilities its manifest declares, or
+/// disables it. Either way a running process is stopped; an enabled
+/// extension starts on its first call. Only the user can do this.
+#[command]
+pub async fn enable_extension(
+    app: AppHandle,
+    request: Request<'_>,
+    id: String,
+    enabled: bool,
+) -> Result<ExtensionInfo, String> {
+    let actor = Actor::of(&request);
+    if actor == Actor::Agent {
+        return Err("Only the user can enable or disable extensions".to_string());
+    }
+    let extension = find(&id)?;
+    let key = format!("{}{}", STORAGE_PREFIX, extension.key());
+    if enabled {
+        let manifest = extension.manifest.as_ref().map_err(|e| e.clone())?;
+        let grant = Grant {
+            capabilities: manifest.capabilities.clone(),
+            enabled_at: Utc::now().timestamp_millis(),
+        };
+        let json = serde_json::to_string(&grant).map_err(|e| e.to_string())?;
+        put_record(&key, &json).map_err(|e| e.to_string())?;
+    } else {
+        delete_record(&key).map_err(|e| e.to_string())?;
+    }
+
+    if stop(&extension.key()) {
+        emit_status(&app, &id, false, None);
+    }
+    info!(
+        "Extension {} {}",
+        id,
+        if enabled { "enabled" } else { "disabled" }
+    );
+    audit::record(
+        actor,
+        "enable_extension",
+        &id,
+        json!({ "enabled": enabled, "path": extension.key() }),
+    );
+    Ok(ExtensionInfo::from(&extension))
+}
+
+/// Tools offered by enabled extensions, for the agent to call with
+/// `call_extension_tool`. The workspace's own extensions are left out until
+/// it is trusted.
+#[command]
+pub async fn list_extension_tools() -> Result<Vec<AvailableTool>, String> {
+    let trusted = is_trusted();
+    let mut tools = Vec::new();
+    for extension in discover() {
+        if extension.source == ExtensionSource::Workspace && !trusted {
+            continue;
+        }
+        if let Ok(manifest) = extension.usable() {
+            tools.extend(manifest.tools.iter().map(|tool| AvailableTool {
+                extension: manifest.id.clone(),
+                tool: tool.clone(),
+            }));
+        }
+    }
+    Ok(tools)
+}
+
+/// Calls a tool of an enabled extension. When the agent calls a tool of an
+/// extension that can write files or run commands, the workspace's
+/// permission policy for those applies.
+#[command]
+pub async fn call_extension_tool(
+    app: AppHandle,
+    request: Request<'_>,
+    extension: String,
+    tool: String,
+    input: Option<Value>,
+) -> Result<Value, String> {
+    let actor = Actor::of(&request);
+    let found = find(&extension)?;
+    let manifest = found.usable()?;
+    let definition = manifest
+        .tools
+        .iter()
+        .find(|definition| definition.name == tool)
+        .ok_or_else(|| format!("Extension {} has no tool {}", extension, tool))?;
+
+    let target = format!("{}/{}", extension, tool);
+    let mutating: Vec<Capability> = manifest
+        .capabilities
+        .iter()
+        .filter_map(|capability| match capability {
+            ExtensionCapability::WriteFiles => Some(Capability::WriteFile),
+            ExtensionCapability::RunCommands => Some(Capability::RunCommand),
+            _ => None,
+        })
+        .collect();
+    if !mutating.is_empty() && dry_run::is_active(actor) {
+        dry_run::plan_step(
+            "call_extension_tool",
+            &target,
+            None,
+            json!({ "input": input }),
+        );
+        return Err(dry_run::planned_error("call_extension_tool"));
+    }
+    for capability in &mutating {
+        authorize(&app, actor, *capability, &target).await?;
+    }
+
+    let timeout = definition
+        .timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_CALL_TIMEOUT);
+    let process = ensure_running(&app, &found, manifest)?;
+    let result = process
+        .call(&tool, input.unwrap_or_else(|| json!({})), timeout)
+        .await;
+    if !mutating.is_empty() && result.is_ok() {
+        audit::record(actor, "call_extension_tool", &target, json!(null));
+    }
+    result
+}
@@ -0,0 +1,240 @@
+// src/commands/fanout.rs
+
+use futures::future::join_all;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{command, AppHandle, Manager};
+use tracing::{info, warn};
+
+use super::api::{anthropic_completion, AnthropicMessage, AnthropicRequest};
+use super::offline::ensure_online;
+use crate::error::MightyError;
+
+/// Candidates one call may ask for.
+const MAX_CANDIDATES: usize = 8;
+/// Characters of each candidate, and of the question, shown to the judge.
+const MAX_JUDGED_CHARS: usize = 12_000;
+const JUDGE_MAX_TOKENS: i32 = 256;
+const JUDGE_INSTRUCTIONS: &str = "Below is the last message of a conversation with a coding \
+assistant and several numbered candidate replies to it. Pick the reply that answers it best: \
+correct first, then complete, then clear and concise. Answer with one line in the form \
+`<number>: <reason in at most 20 words>` and nothing else.";
+
+/// The judge's answer, e.g. "2: Handles the empty case the others miss".
+static PICK: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?im)^\W*(?:candidate\s*)?(\d+)\W*[:.)-][\s*]*(.*)$")
+        .expect("the pattern is valid")
+});
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FanoutStrategy {
+    /// Return the candidates as they are.
+    #[default]
+    All,
+    /// Also ask the model which candidate is best.
+    Judge,
+}
+
+/// One of the sampled replies.
+#[derive(Debug, Clone, Serialize)]
+pub struct Candidate {
+    /// 1-based, in the order the candidates were requested.
+    pub index: usize,
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Value>,
+    /// Why this candidate has no text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BestPick {
+    /// The `index` of the chosen candidate.
+    pub index: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FanoutResult {
+    pub id: String,
+    pub strategy: FanoutStrategy,
+    pub candidates: Vec<Candidate>,
+    /// Set with the `judge` strategy when the judge gave a usable answer.
+    pub best: Option<BestPick>,
+}
+
+fn truncate(text: &str) -> String {
+    match text.char_indices().nth(MAX_JUDGED_CHARS) {
+        Some((end, _)) => format!("{} [...]", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+async fn generate(app: AppHandle, request: AnthropicRequest, index: usize) -> Candidate {
+    let id = request.id.clone();
+    let response = anthropic_completion(app.clone(), request, app.state())
+        .await
+        .map_err(|e| e.message().to_string())
+        .and_then(|response| serde_json::from_str::<Value>(&response).map_err(|e| e.to_string()));
+    match response {
+        Ok(response) => Candidate {
+            index,
+            id,
+            text: response["text"].as_str().map(str::to_string),
+            usage: Some(response["usage"].clone()).filter(|usage| !usage.is_null()),
+            error: None,
+        },
+        Err(e) => Candidate {
+            index,
+            id,
+            text: None,
+            usage: None,
+            error: Some(e),
+        },
+    }
+}
+
+fn judge_prompt(question: &str, candidates: &[Candidate]) -> String {
+    let mut prompt = format!(
+        "{}\n\nLast message:\n{}\n",
+        JUDGE_INSTRUCTIONS,
+        truncate(question)
+    );
+    for candidate in candidates {
+        if let Some(text) = &candidate.text {
+            prompt.push_str(&format!(
+                "\nCandidate {}:\n{}\n",
+                candidate.index,
+                truncate(text)
+            ));
+        }
+    }
+    prompt
+}
+
+/// Asks `model` which of `candidates` answers `question` best.
+async fn judge(
+    app: AppHandle,
+    id: &str,
+    model: &str,
+    question: &str,
+    candidates: &[Candidate],
+) -> Result<BestPick, String> {
+    let request = AnthropicRequest {
+        id: format!("{}:judge", id),
+        model: model.to_string(),
+        max_tokens: JUDGE_MAX_TOKENS,
+        messages: vec![AnthropicMessage {
+            role: "user".to_string(),
+            content: judge_prompt(question, candidates),
+        }],
+        conversation_id: None,
+    };
+    let response = anthropic_completion(app.clone(), request, app.state())
+        .await
+        .map_err(|e| e.message().to_string())?;
+    let response: Value = serde_json::from_str(&response).map_err(|e| e.to_string())?;
+    let answer = response["text"].as_str().unwrap_or_default();
+    let captures = PICK
+        .captures(answer)
+        .ok_or_else(|| format!("Unexpected answer: {}", answer))?;
+    let index: usize = captures[1].parse().map_err(|_| "Unexpected number")?;
+    if !candidates
+        .iter()
+        .any(|candidate| candidate.index == index && candidate.text.is_some())
+    {
+        return Err(format!("Picked candidate {}, which has no reply", index));
+    }
+    Ok(BestPick {
+        index,
+        reason: captures[2].trim().to_string(),
+    })
+}
+
+/// Samples `n` replies to `request` at once for the "regenerate variations"
+/// view. They share the provider's request limit with everything else, so
+/// a large `n` may queue. With the `judge` strategy the model then picks
+/// the best; if that fails the candidates are still returned, without a
+/// pick. Fails only when no candidate could be generated.
+#[command]
+pub async fn completion_fanout(
+    app: AppHandle,
+    request: AnthropicRequest,
+    n: usize,
+    strategy: Option<FanoutStrategy>,
+) -> Result<FanoutResult, MightyError> {
+    if n == 0 || n > MAX_CANDIDATES {
+        return Err(MightyError::request(
+            "INVALID_FANOUT",
+            &format!("Ask for 1 to {} candidates, not {}", MAX_CANDIDATES, n),
+        ));
+    }
+    // Every candidate would fail the same way
+    ensure_online()?;
+    let strategy = strategy.unwrap_or_default();
+    info!("Sampling {} candidates for {}", n, request.id);
+
+    let candidates = join_all((1..=n).map(|index| {
+        let request = AnthropicRequest {
+            id: format!("{}:{}", request.id, index),
+            ..request.clone()
+        };
+        generate(app.clone(), request, index)
+    }))
+    .await;
+    let generated = candidates
+        .iter()
+        .filter(|candidate| candidate.text.is_some())
+        .count();
+    if generated == 0 {
+        let error = candidates
+            .iter()
+            .find_map(|candidate| candidate.error.clone())
+            .unwrap_or_else(|| "No candidate had any text".to_string());
+        return Err(MightyError::from(error));
+    }
+
+    let question = request
+        .messages
+        .iter()
+        .rev()
+        .find(|message| message.role == "user")
+        .map(|message| message.content.as_str())
+        .unwrap_or_default();
+    let best = match strategy {
+        FanoutStrategy::All => None,
+        // Nothing to choose between
+        FanoutStrategy::Judge if generated == 1 => candidates
+            .iter()
+            .find(|candidate| candidate.text.is_some())
+            .map(|candidate| BestPick {
+                index: candidate.index,
+                reason: "The only candidate generated".to_string(),
+            }),
+        FanoutStrategy::Judge => {
+            match judge(app, &request.id, &request.model, question, &candidates).await {
+                Ok(best) => Some(best),
+                Err(e) => {
+                    warn!(
+                        "Failed to pick the best candidate for {}: {}",
+                        request.id, e
+                    );
+                    None
+                }
+            }
+        }
+    };
+
+    Ok(FanoutResult {
+        id: request.id,
+        strategy,
+        candidates,
+        best,
+    })
+}
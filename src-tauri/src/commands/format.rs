@@ -0,0 +1,221 @@
+// src/commands/format.rs
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use tauri::command;
+use tracing::warn;
+
+use super::exec::{find_executable, run_tool, ExecError};
+use super::fs::{get_project_root, resolve_workspace_path};
+use super::trust::ensure_trusted;
+use crate::config::{FormatConfig, FormatterConfig};
+
+const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+static FORMAT_CONFIG: Lazy<Mutex<FormatConfig>> = Lazy::new(|| Mutex::new(FormatConfig::default()));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormattedDocument {
+    pub content: String,
+    pub language: String,
+    pub formatter: String,
+    pub changed: bool,
+}
+
+pub fn initialize_format(config: Option<&FormatConfig>) {
+    *FORMAT_CONFIG.lock() = config.cloned().unwrap_or_default();
+}
+
+/// Whether edits made by the assistant should be formatted before they are written.
+pub(crate) fn format_ai_edits() -> bool {
+    FORMAT_CONFIG.lock().format_ai_edits.unwrap_or(true)
+}
+
+fn language_for_path(path: &str) -> Option<&'static str> {
+    let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+    let language = match extension.as_str() {
+        "rs" => "rust",
+        "ts" | "tsx" | "mts" | "cts" => "typescript",
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "json" => "json",
+        "css" | "scss" | "less" => "css",
+        "html" | "vue" | "svelte" => "html",
+        "md" | "mdx" => "markdown",
+        "yaml" | "yml" => "yaml",
+        "py" | "pyi" => "python",
+        "go" => "go",
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// File name handed to formatters that pick a parser from the extension when
+/// only content was given.
+fn placeholder_path(language: &str) -> &'static str {
+    match language {
+        "rust" => "document.rs",
+        "typescript" => "document.ts",
+        "javascript" => "document.js",
+        "json" => "document.json",
+        "css" => "document.css",
+        "html" => "document.html",
+        "markdown" => "document.md",
+        "yaml" => "document.yaml",
+        "python" => "document.py",
+        "go" => "document.go",
+        _ => "document.txt",
+    }
+}
+
+fn default_formatter(language: &str) -> Option<FormatterConfig> {
+    let (command, args): (&str, &[&str]) = match language {
+        "rust" => ("rustfmt", &["--edition", "2021"]),
+        "typescript" | "javascript" | "json" | "css" | "html" | "markdown" | "yaml" => {
+            ("prettier", &["--stdin-filepath", "{path}"])
+        }
+        "python" => ("black", &["--quiet", "--stdin-filename", "{path}", "-"]),
+        "go" => ("gofmt", &[]),
+        _ => return None,
+    };
+    Some(FormatterConfig {
+        command: command.to_string(),
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+    })
+}
+
+/// Formats `content` with the formatter configured for `language` (or the
+/// default one), reading from stdin and returning stdout.
+pub(crate) async fn format_text(
+    content: &str,
+    language: &str,
+    path: Option<&str>,
+) -> Result<FormattedDocument, ExecError> {
+    // Formatters load the workspace's config and plugins
+    ensure_trusted("running tasks").map_err(|e| ExecError::new("WORKSPACE_UNTRUSTED", &e))?;
+    let (formatter, timeout_ms) = {
+        let config = FORMAT_CONFIG.lock();
+        (
+            config.formatters.get(language).cloned(),
+            config.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS),
+        )
+    };
+    let formatter = formatter
+        .or_else(|| default_formatter(language))
+        .ok_or_else(|| {
+            ExecError::new(
+                "UNSUPPORTED_LANGUAGE",
+                &format!("No formatter is configured for {}", language),
+            )
+        })?;
+
+    let program = find_executable(&formatter.command).ok_or_else(|| {
+        ExecError::new(
+            "FORMATTER_NOT_FOUND",
+            &format!("{} was not found on PATH", formatter.command),
+        )
+    })?;
+
+    let path = path.unwrap_or_else(|| placeholder_path(language));
+    let args: Vec<String> = formatter
+        .args
+        .iter()
+        .map(|arg| arg.replace("{path}", path))
+        .collect();
+
+    let output = run_tool(
+        &program,
+        &args,
+        &get_project_root(),
+        Some(content),
+        Duration::from_millis(timeout_ms),
+    )
+    .await?;
+
+    if !output.success() {
+        return Err(ExecError::with_details(
+            "FORMAT_FAILED",
+            &format!(
+                "{} exited with status {}",
+                formatter.command,
+                output
+                    .exit_code
+                    .map(|code| code.to_string())
+                    .unwrap_or_else(|| "unknown".to_string())
+            ),
+            output.stderr.trim(),
+        ));
+    }
+
+    Ok(FormattedDocument {
+        changed: output.stdout != content,
+        content: output.stdout,
+        language: language.to_string(),
+        formatter: formatter.command,
+    })
+}
+
+/// Formats a file or a piece of text. When only `path` is given the file is
+/// read from disk; the formatted text is returned, never written.
+#[command]
+pub async fn format_document(
+    path: Option<String>,
+    content: Option<String>,
+    language: Option<String>,
+) -> Result<FormattedDocument, ExecError> {
+    let language = match (&language, &path) {
+        (Some(language), _) => language.to_lowercase(),
+        (None, Some(path)) => language_for_path(path)
+            .ok_or_else(|| {
+                ExecError::new(
+                    "UNSUPPORTED_LANGUAGE",
+                    &format!("Cannot infer a language for {}", path),
+                )
+            })?
+            .to_string(),
+        (None, None) => {
+            return Err(ExecError::new(
+                "INVALID_ARGUMENT",
+                "Either a path or a language is required",
+            ))
+        }
+    };
+
+    let content = match (content, &path) {
+        (Some(content), _) => content,
+        (None, Some(path)) => {
+            let full_path = resolve_workspace_path(path)?;
+            tokio::fs::read_to_string(&full_path)
+                .await
+                .map_err(|e| ExecError::new("READ_ERROR", &format!("{}: {}", path, e)))?
+        }
+        (None, None) => {
+            return Err(ExecError::new(
+                "INVALID_ARGUMENT",
+                "Either a path or content is required",
+            ))
+        }
+    };
+
+    format_text(&content, &language, path.as_deref()).await
+}
+
+/// Formats assistant-written content for `path` if formatting is enabled and
+/// a formatter is available; otherwise returns the content unchanged.
+pub(crate) async fn format_for_write(path: &str, content: String) -> (String, bool) {
+    if !format_ai_edits() {
+        return (content, false);
+    }
+    let Some(language) = language_for_path(path) else {
+        return (content, false);
+    };
+    match format_text(&content, language, Some(path)).await {
+        Ok(formatted) => (formatted.content, formatted.changed),
+        Err(e) => {
+            warn!("Leaving {} unformatted: {}", path, e);
+            (content, false)
+        }
+    }
+}
@@ -1,18 +1,31 @@
-use notify::{Event, RecursiveMode, Watcher};
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::{HashMap, VecDeque};
 use std::env;
-use std::path::PathBuf;
+use std::path::{Component, PathBuf};
 use std::{fs, os::unix::fs::PermissionsExt, path::Path, sync::mpsc, time::SystemTime};
-use tauri::{command, Emitter, Manager, Runtime, WebviewWindow};
+use tauri::ipc::Request;
+use tauri::{command, AppHandle, Emitter, Manager, Runtime, WebviewWindow};
+use tracing::warn;
+
+use super::audit;
+use super::dry_run;
+use super::encoding::{convert_line_endings, decode_text, encode_text, DecodedText, LineEnding};
+use super::permissions::{authorize, Actor, Capability};
+use crate::config::FsConfig;
+use crate::error::MightyError;
+use crate::secrets;
+use crate::state::app_state;
 
 // File watcher configuration
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
-use std::sync::Arc;
+use uuid::Uuid;
 
-static FILE_WATCHER: Lazy<Arc<Mutex<Option<FileWatcher>>>> =
-    Lazy::new(|| Arc::new(Mutex::new(None)));
+// Directories outside the workspace that fs commands are explicitly allowed to touch
+static ALLOWED_ROOTS: Lazy<Mutex<Vec<PathBuf>>> = Lazy::new(|| Mutex::new(Vec::new()));
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileSystemNode {
@@ -21,7 +34,8 @@ pub struct FileSystemNode {
     #[serde(rename = "type")]
     node_type: String,
     path: String,
-    metadata: FileMetadata,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<FileMetadata>,
     #[serde(skip_serializing_if = "Option::is_none")]
     children: Option<Vec<FileSystemNode>>,
 }
@@ -34,7 +48,8 @@ pub struct FileMetadata {
     permissions: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(into = "MightyError")]
 pub struct FileSystemError {
     code: String,
     message: String,
@@ -42,7 +57,7 @@ pub struct FileSystemError {
 }
 
 impl FileSystemError {
-    fn new(code: &str, message: &str) -> Self {
+    pub(crate) fn new(code: &str, message: &str) -> Self {
         Self {
             code: code.to_string(),
             message: message.to_string(),
@@ -50,19 +65,37 @@ impl FileSystemError {
         }
     }
 
-    fn with_path(code: &str, message: &str, path: &Path) -> Self {
+    pub(crate) fn with_path(code: &str, message: &str, path: &Path) -> Self {
         Self {
             code: code.to_string(),
             message: message.to_string(),
             path: Some(path.to_string_lossy().to_string()),
         }
     }
+
+    pub(crate) fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub(crate) fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<FileSystemError> for MightyError {
+    fn from(e: FileSystemError) -> Self {
+        MightyError::FileSystem {
+            code: e.code,
+            message: e.message,
+            path: e.path,
+        }
+    }
 }
 
 // Enhanced file watcher configuration
 pub struct FileWatcher {
     watcher: notify::RecommendedWatcher,
-    _tx: mpsc::Sender<Event>,
+    _tx: Option<mpsc::Sender<Event>>,
 }
 
 impl FileWatcher {
@@ -82,19 +115,88 @@ impl FileWatcher {
             notify::Config::default(),
         )?;
 
-        Ok(Self { watcher, _tx: tx })
+        Ok(Self {
+            watcher,
+            _tx: Some(tx),
+        })
+    }
+
+    /// Creates a watcher that hands every non-ignored event to `handler`.
+    pub fn with_handler<F>(handler: F) -> notify::Result<Self>
+    where
+        F: Fn(Event) + Send + 'static,
+    {
+        let watcher = notify::RecommendedWatcher::new(
+            move |res: notify::Result<Event>| {
+                if let Ok(event) = res {
+                    if !should_ignore_event(&event) {
+                        handler(event);
+                    }
+                }
+            },
+            notify::Config::default(),
+        )?;
+
+        Ok(Self { watcher, _tx: None })
     }
 
     pub fn watch<P: AsRef<Path>>(&mut self, path: P) -> notify::Result<()> {
         self.watcher.watch(path.as_ref(), RecursiveMode::Recursive)
     }
+
+    pub fn watch_with_mode<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        recursive: bool,
+    ) -> notify::Result<()> {
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        self.watcher.watch(path.as_ref(), mode)
+    }
+}
+
+// A scoped watch requested by the frontend for a single file or directory
+struct WatchSubscription {
+    info: WatchSubscriptionInfo,
+    _watcher: FileWatcher,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchSubscriptionInfo {
+    id: String,
+    path: String,
+    recursive: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct FileWatchEvent {
+    subscription_id: String,
+    kind: String,
+    paths: Vec<String>,
+}
+
+static WATCH_SUBSCRIPTIONS: Lazy<Mutex<HashMap<String, WatchSubscription>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn event_kind_name(kind: &EventKind) -> Option<&'static str> {
+    match kind {
+        EventKind::Create(_) => Some("create"),
+        EventKind::Modify(ModifyKind::Name(_)) => Some("rename"),
+        EventKind::Modify(_) => Some("modify"),
+        EventKind::Remove(_) => Some("remove"),
+        EventKind::Access(_) => None,
+        EventKind::Any | EventKind::Other => Some("other"),
+    }
 }
 
 fn should_ignore_event(event: &Event) -> bool {
     event.paths.iter().any(|path| should_ignore_path(path))
 }
 
-fn should_ignore_path(path: &Path) -> bool {
+pub(crate) fn should_ignore_path(path: &Path) -> bool {
     let path_str = path.to_string_lossy();
     let ignore_patterns = [
         "__pycache__",
@@ -103,6 +205,7 @@ fn should_ignore_path(path: &Path) -> bool {
         "/.pytest_cache/",
         "/target/",
         "/.git/",
+        "/.mighty/history/",
         "/node_modules/",
         ".DS_Store",
         "/storage/",
@@ -123,12 +226,12 @@ pub fn initialize_watcher() -> Result<(), Box<dyn std::error::Error>> {
     let project_root = get_project_root();
     watcher.watch(project_root)?;
 
-    *FILE_WATCHER.lock() = Some(watcher);
+    *app_state().file_watcher.lock() = Some(watcher);
     Ok(())
 }
 
 // Function to get the project root directory
-fn get_project_root() -> PathBuf {
+pub(crate) fn get_project_root() -> PathBuf {
     let current_dir = env::current_dir().expect("Failed to get current directory");
 
     let mut dir = current_dir.as_path();
@@ -146,52 +249,201 @@ fn get_project_root() -> PathBuf {
     current_dir
 }
 
-// Helper function to get file metadata
-fn get_metadata(path: &Path) -> Result<FileMetadata, std::io::Error> {
-    let metadata = fs::metadata(path)?;
-    let created = metadata.created()?;
-    let modified = metadata.modified()?;
+// Resolves `.` and `..` components without touching the filesystem
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
 
-    Ok(FileMetadata {
-        created_at: created
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            .to_string(),
-        modified_at: modified
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            .to_string(),
-        size: metadata.len(),
-        permissions: format!("{:o}", metadata.permissions().mode()),
-    })
+// Links followed while resolving one path before giving up, as the OS does
+const MAX_SYMLINK_HOPS: usize = 40;
+
+// Resolves `path` the way opening it would: every component that is a
+// symlink is replaced by its target, even when the target doesn't exist
+// yet, so a dangling link can't be used to create a file outside the
+// workspace. Components that don't exist are appended as they are.
+fn canonicalize_existing_prefix(path: &Path) -> std::io::Result<PathBuf> {
+    let mut pending: VecDeque<PathBuf> = path
+        .components()
+        .map(|component| PathBuf::from(component.as_os_str()))
+        .collect();
+    let mut resolved = PathBuf::new();
+    let mut hops = 0;
+
+    while let Some(next) = pending.pop_front() {
+        match next.components().next() {
+            Some(Component::Prefix(_)) | Some(Component::RootDir) => resolved.push(&next),
+            Some(Component::CurDir) | None => {}
+            Some(Component::ParentDir) => {
+                resolved.pop();
+            }
+            Some(Component::Normal(name)) => {
+                let candidate = resolved.join(name);
+                match fs::symlink_metadata(&candidate) {
+                    Ok(metadata) if metadata.file_type().is_symlink() => {
+                        hops += 1;
+                        if hops > MAX_SYMLINK_HOPS {
+                            return Err(std::io::Error::new(
+                                std::io::ErrorKind::Other,
+                                "Too many levels of symbolic links",
+                            ));
+                        }
+                        let target = fs::read_link(&candidate)?;
+                        // An absolute target starts over from its root; a
+                        // relative one continues from the link's directory
+                        if target.is_absolute() {
+                            resolved = PathBuf::new();
+                        }
+                        for component in target.components().rev() {
+                            pending.push_front(PathBuf::from(component.as_os_str()));
+                        }
+                    }
+                    Ok(_) => resolved = candidate,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => resolved = candidate,
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+    }
+    Ok(resolved)
 }
 
-#[command]
-pub async fn read_directory(path: String) -> Result<Vec<FileSystemNode>, FileSystemError> {
+fn is_allowed_location(resolved: &Path, project_root: &Path) -> bool {
+    resolved.starts_with(project_root)
+        || ALLOWED_ROOTS
+            .lock()
+            .iter()
+            .any(|root| resolved.starts_with(root))
+}
+
+/// Resolves a path received from the frontend against the workspace root and
+/// rejects anything that escapes it, whether through `..` components or
+/// symlinks pointing elsewhere. The returned path is not canonicalized so
+/// operations on a symlink act on the link itself.
+pub(crate) fn resolve_workspace_path(path: &str) -> Result<PathBuf, FileSystemError> {
     let project_root = get_project_root();
-    let full_path = if Path::new(&path).is_absolute() {
+    let canonical_root = project_root
+        .canonicalize()
+        .unwrap_or_else(|_| project_root.clone());
+
+    let joined = if Path::new(path).is_absolute() {
         PathBuf::from(path)
     } else {
         project_root.join(path)
     };
+    let full_path = normalize_lexically(&joined);
 
-    if !full_path.exists() {
+    let resolved = canonicalize_existing_prefix(&full_path)
+        .map_err(|e| FileSystemError::with_path("ACCESS_DENIED", &e.to_string(), &full_path))?;
+
+    if !is_allowed_location(&resolved, &canonical_root) {
         return Err(FileSystemError::with_path(
-            "PATH_NOT_FOUND",
-            "Directory not found",
+            "ACCESS_DENIED",
+            "Path is outside of the workspace",
             &full_path,
         ));
     }
 
-    let mut nodes = Vec::new();
-    let entries = fs::read_dir(&full_path)
-        .map_err(|e| FileSystemError::with_path("READ_ERROR", &e.to_string(), &full_path))?;
+    Ok(full_path)
+}
+
+/// Returns `full_path` relative to the workspace root, or unchanged if it lives elsewhere.
+pub(crate) fn workspace_relative_path(full_path: &Path) -> String {
+    full_path
+        .strip_prefix(get_project_root())
+        .unwrap_or(full_path)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Replaces the set of directories outside the workspace that fs commands may access.
+pub fn set_allowed_roots(roots: &[String]) {
+    let resolved = roots
+        .iter()
+        .filter_map(|root| match Path::new(root).canonicalize() {
+            Ok(path) => Some(path),
+            Err(e) => {
+                warn!("Ignoring allowed root {}: {}", root, e);
+                None
+            }
+        })
+        .collect();
+    *ALLOWED_ROOTS.lock() = resolved;
+}
+
+// Entries are stat'ed in batches of this size across worker threads
+const METADATA_BATCH_SIZE: usize = 256;
+
+fn unix_secs(time: SystemTime) -> String {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+        .to_string()
+}
 
+// Helper function to get file metadata
+fn get_metadata(path: &Path) -> Result<FileMetadata, std::io::Error> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata.modified()?;
+    // Not every filesystem records creation time
+    let created = metadata.created().unwrap_or(modified);
+
+    Ok(FileMetadata {
+        created_at: unix_secs(created),
+        modified_at: unix_secs(modified),
+        size: metadata.len(),
+        permissions: format!("{:o}", metadata.permissions().mode()),
+    })
+}
+
+// Stats every path, spreading large directories over a few threads
+fn get_metadata_batched(paths: &[PathBuf]) -> Vec<Result<FileMetadata, std::io::Error>> {
+    if paths.len() <= METADATA_BATCH_SIZE {
+        return paths.iter().map(|p| get_metadata(p)).collect();
+    }
+
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4);
+    let batch_size = METADATA_BATCH_SIZE.max(paths.len().div_ceil(workers));
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = paths
+            .chunks(batch_size)
+            .map(|batch| {
+                scope.spawn(move || batch.iter().map(|p| get_metadata(p)).collect::<Vec<_>>())
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("metadata worker panicked"))
+            .collect()
+    })
+}
+
+fn list_directory(
+    full_path: &Path,
+    project_root: &Path,
+    include_metadata: bool,
+) -> Result<Vec<FileSystemNode>, FileSystemError> {
+    let entries = fs::read_dir(full_path)
+        .map_err(|e| FileSystemError::with_path("READ_ERROR", &e.to_string(), full_path))?;
+
+    let mut paths = Vec::new();
+    let mut is_dirs = Vec::new();
     for entry in entries {
         let entry = entry
-            .map_err(|e| FileSystemError::with_path("ENTRY_ERROR", &e.to_string(), &full_path))?;
+            .map_err(|e| FileSystemError::with_path("ENTRY_ERROR", &e.to_string(), full_path))?;
         let path = entry.path();
 
         // Skip ignored files and directories
@@ -199,32 +451,58 @@ pub async fn read_directory(path: String) -> Result<Vec<FileSystemNode>, FileSys
             continue;
         }
 
-        // Make path relative to project root for consistency
-        let relative_path = path
-            .strip_prefix(&project_root)
-            .unwrap_or(&path)
-            .to_string_lossy()
-            .to_string();
-
-        let metadata = get_metadata(&path)
-            .map_err(|e| FileSystemError::with_path("METADATA_ERROR", &e.to_string(), &path))?;
-
-        let node = FileSystemNode {
-            id: relative_path.clone(),
-            name: path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string(),
-            node_type: if path.is_dir() { "directory" } else { "file" }.to_string(),
-            path: relative_path,
-            metadata,
-            children: None,
+        // The file type comes from readdir itself; only symlinks need a stat
+        let is_dir = match entry.file_type() {
+            Ok(file_type) if file_type.is_symlink() => path.is_dir(),
+            Ok(file_type) => file_type.is_dir(),
+            Err(_) => path.is_dir(),
         };
 
-        nodes.push(node);
+        paths.push(path);
+        is_dirs.push(is_dir);
     }
 
+    let metadata: Vec<Option<FileMetadata>> = if include_metadata {
+        get_metadata_batched(&paths)
+            .into_iter()
+            .zip(&paths)
+            .map(|(result, path)| {
+                result
+                    .map(Some)
+                    .map_err(|e| FileSystemError::with_path("METADATA_ERROR", &e.to_string(), path))
+            })
+            .collect::<Result<_, _>>()?
+    } else {
+        vec![None; paths.len()]
+    };
+
+    let mut nodes: Vec<FileSystemNode> = paths
+        .into_iter()
+        .zip(is_dirs)
+        .zip(metadata)
+        .map(|((path, is_dir), metadata)| {
+            // Make path relative to project root for consistency
+            let relative_path = path
+                .strip_prefix(project_root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string();
+
+            FileSystemNode {
+                id: relative_path.clone(),
+                name: path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string(),
+                node_type: if is_dir { "directory" } else { "file" }.to_string(),
+                path: relative_path,
+                metadata,
+                children: None,
+            }
+        })
+        .collect();
+
     nodes.sort_by(|a, b| match (a.node_type.as_str(), b.node_type.as_str()) {
         ("directory", "file") => std::cmp::Ordering::Less,
         ("file", "directory") => std::cmp::Ordering::Greater,
@@ -234,10 +512,36 @@ pub async fn read_directory(path: String) -> Result<Vec<FileSystemNode>, FileSys
     Ok(nodes)
 }
 
+/// Lists a directory. Pass `include_metadata: false` to skip stat'ing every
+/// entry, which makes the first render of huge directories much faster.
 #[command]
-pub async fn read_file(path: String) -> Result<String, FileSystemError> {
+pub async fn read_directory(
+    path: String,
+    include_metadata: Option<bool>,
+) -> Result<Vec<FileSystemNode>, FileSystemError> {
     let project_root = get_project_root();
-    let full_path = project_root.join(path);
+    let full_path = resolve_workspace_path(&path)?;
+
+    if !tokio::fs::try_exists(&full_path).await.unwrap_or(false) {
+        return Err(FileSystemError::with_path(
+            "PATH_NOT_FOUND",
+            "Directory not found",
+            &full_path,
+        ));
+    }
+
+    let include_metadata = include_metadata.unwrap_or(true);
+    let listing_path = full_path.clone();
+    tokio::task::spawn_blocking(move || {
+        list_directory(&listing_path, &project_root, include_metadata)
+    })
+    .await
+    .map_err(|e| FileSystemError::with_path("READ_ERROR", &e.to_string(), &full_path))?
+}
+
+#[command]
+pub async fn read_file(path: String) -> Result<DecodedText, FileSystemError> {
+    let full_path = resolve_workspace_path(&path)?;
 
     if !full_path.exists() {
         return Err(FileSystemError::with_path(
@@ -247,14 +551,60 @@ pub async fn read_file(path: String) -> Result<String, FileSystemError> {
         ));
     }
 
-    fs::read_to_string(&full_path)
-        .map_err(|e| FileSystemError::with_path("READ_ERROR", &e.to_string(), &full_path))
+    let bytes = fs::read(&full_path)
+        .map_err(|e| FileSystemError::with_path("READ_ERROR", &e.to_string(), &full_path))?;
+
+    decode_text(&bytes).ok_or_else(|| {
+        FileSystemError::with_path("BINARY_FILE", "File does not contain text", &full_path)
+    })
 }
 
 #[command]
-pub async fn write_file(path: String, content: String) -> Result<(), FileSystemError> {
-    let project_root = get_project_root();
-    let full_path = project_root.join(path);
+pub async fn write_file(
+    app: AppHandle,
+    request: Request<'_>,
+    path: String,
+    content: String,
+    encoding: Option<String>,
+    line_ending: Option<LineEnding>,
+    with_bom: Option<bool>,
+) -> Result<(), FileSystemError> {
+    let actor = Actor::of(&request);
+    write_file_as(&app, actor, path, content, encoding, line_ending, with_bom).await
+}
+
+/// `write_file` on behalf of `actor`, for callers without an IPC request.
+pub(crate) async fn write_file_as(
+    app: &AppHandle,
+    actor: Actor,
+    path: String,
+    content: String,
+    encoding: Option<String>,
+    line_ending: Option<LineEnding>,
+    with_bom: Option<bool>,
+) -> Result<(), FileSystemError> {
+    let full_path = resolve_workspace_path(&path)?;
+    let existing = fs::read_to_string(&full_path).ok();
+    if dry_run::is_active(actor) {
+        let diff = dry_run::file_diff(&path, existing.as_deref(), Some(&content));
+        dry_run::plan_step("write_file", &path, Some(diff), json!(null));
+        return Ok(());
+    }
+    authorize(app, actor, Capability::WriteFile, &path)
+        .await
+        .map_err(|e| FileSystemError::with_path("PERMISSION_DENIED", &e, &full_path))?;
+    secrets::check_agent_write(app, actor, &path, existing.as_deref(), &content)
+        .map_err(|e| FileSystemError::with_path("SECRET_DETECTED", &e, &full_path))?;
+
+    let content = match line_ending {
+        Some(line_ending) => convert_line_endings(&content, line_ending),
+        None => content,
+    };
+    let bytes = match encoding {
+        Some(label) => encode_text(&content, &label, with_bom.unwrap_or(false))
+            .map_err(|e| FileSystemError::with_path("ENCODING_ERROR", &e, &full_path))?,
+        None => content.into_bytes(),
+    };
 
     // Ensure the parent directory exists
     if let Some(parent) = full_path.parent() {
@@ -262,23 +612,50 @@ pub async fn write_file(path: String, content: String) -> Result<(), FileSystemE
             .map_err(|e| FileSystemError::with_path("CREATE_DIR_ERROR", &e.to_string(), parent))?;
     }
 
-    fs::write(&full_path, content)
-        .map_err(|e| FileSystemError::with_path("WRITE_ERROR", &e.to_string(), &full_path))
+    let before = fs::read(&full_path).ok();
+    fs::write(&full_path, &bytes)
+        .map_err(|e| FileSystemError::with_path("WRITE_ERROR", &e.to_string(), &full_path))?;
+    audit::record_change(
+        actor,
+        "write_file",
+        &path,
+        before.as_deref(),
+        Some(&bytes),
+        json!(null),
+    );
+    Ok(())
 }
 
 #[command]
-pub async fn create_directory(path: String) -> Result<(), FileSystemError> {
-    let project_root = get_project_root();
-    let full_path = project_root.join(path);
+pub async fn create_directory(request: Request<'_>, path: String) -> Result<(), FileSystemError> {
+    let full_path = resolve_workspace_path(&path)?;
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        dry_run::plan_step("create_directory", &path, None, json!(null));
+        return Ok(());
+    }
 
     fs::create_dir_all(&full_path)
-        .map_err(|e| FileSystemError::with_path("CREATE_ERROR", &e.to_string(), &full_path))
+        .map_err(|e| FileSystemError::with_path("CREATE_ERROR", &e.to_string(), &full_path))?;
+    audit::record(actor, "create_directory", &path, json!(null));
+    Ok(())
 }
 
 #[command]
-pub async fn delete_path(path: String) -> Result<(), FileSystemError> {
-    let project_root = get_project_root();
-    let full_path = project_root.join(path);
+pub async fn delete_path(
+    app: AppHandle,
+    request: Request<'_>,
+    path: String,
+) -> Result<(), FileSystemError> {
+    let full_path = resolve_workspace_path(&path)?;
+
+    if normalize_lexically(&full_path) == normalize_lexically(&get_project_root()) {
+        return Err(FileSystemError::with_path(
+            "ACCESS_DENIED",
+            "Refusing to delete the workspace root",
+            &full_path,
+        ));
+    }
 
     if !full_path.exists() {
         return Err(FileSystemError::with_path(
@@ -288,19 +665,49 @@ pub async fn delete_path(path: String) -> Result<(), FileSystemError> {
         ));
     }
 
-    if full_path.is_dir() {
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        let diff = fs::read_to_string(&full_path)
+            .ok()
+            .map(|before| dry_run::file_diff(&path, Some(&before), None));
+        let directory = full_path.is_dir();
+        dry_run::plan_step("delete_path", &path, diff, json!({ "directory": directory }));
+        return Ok(());
+    }
+    authorize(&app, actor, Capability::DeletePath, &path)
+        .await
+        .map_err(|e| FileSystemError::with_path("PERMISSION_DENIED", &e, &full_path))?;
+
+    let before = if full_path.is_dir() {
         fs::remove_dir_all(&full_path)
+            .map_err(|e| FileSystemError::with_path("DELETE_ERROR", &e.to_string(), &full_path))?;
+        None
     } else {
+        let before = fs::read(&full_path).ok();
         fs::remove_file(&full_path)
-    }
-    .map_err(|e| FileSystemError::with_path("DELETE_ERROR", &e.to_string(), &full_path))
+            .map_err(|e| FileSystemError::with_path("DELETE_ERROR", &e.to_string(), &full_path))?;
+        before
+    };
+    audit::record_change(
+        actor,
+        "delete_path",
+        &path,
+        before.as_deref(),
+        None,
+        json!(null),
+    );
+    Ok(())
 }
 
 #[command]
-pub async fn rename_path(old_path: String, new_path: String) -> Result<(), FileSystemError> {
-    let project_root = get_project_root();
-    let old_full_path = project_root.join(old_path);
-    let new_full_path = project_root.join(new_path);
+pub async fn rename_path(
+    app: AppHandle,
+    request: Request<'_>,
+    old_path: String,
+    new_path: String,
+) -> Result<(), FileSystemError> {
+    let old_full_path = resolve_workspace_path(&old_path)?;
+    let new_full_path = resolve_workspace_path(&new_path)?;
 
     if !old_full_path.exists() {
         return Err(FileSystemError::with_path(
@@ -309,6 +716,19 @@ pub async fn rename_path(old_path: String, new_path: String) -> Result<(), FileS
             &old_full_path,
         ));
     }
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        let details = json!({ "from": old_path, "to": new_path });
+        dry_run::plan_step("rename_path", &old_path, None, details);
+        return Ok(());
+    }
+    // The source goes away and the destination may be overwritten
+    authorize(&app, actor, Capability::DeletePath, &old_path)
+        .await
+        .map_err(|e| FileSystemError::with_path("PERMISSION_DENIED", &e, &old_full_path))?;
+    authorize(&app, actor, Capability::WriteFile, &new_path)
+        .await
+        .map_err(|e| FileSystemError::with_path("PERMISSION_DENIED", &e, &new_full_path))?;
 
     // Ensure the parent directory of the new path exists
     if let Some(parent) = new_full_path.parent() {
@@ -317,18 +737,168 @@ pub async fn rename_path(old_path: String, new_path: String) -> Result<(), FileS
     }
 
     fs::rename(&old_full_path, &new_full_path)
-        .map_err(|e| FileSystemError::with_path("RENAME_ERROR", &e.to_string(), &old_full_path))
+        .map_err(|e| FileSystemError::with_path("RENAME_ERROR", &e.to_string(), &old_full_path))?;
+    audit::record(
+        actor,
+        "rename_path",
+        &old_path,
+        json!({ "from": old_path, "to": new_path }),
+    );
+    Ok(())
 }
 
 // Initialize function to be called at startup
-pub fn initialize_fs() -> Result<(), Box<dyn std::error::Error>> {
+pub fn initialize_fs(config: Option<&FsConfig>) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(config) = config {
+        set_allowed_roots(&config.allowed_roots);
+    }
     initialize_watcher()?;
     Ok(())
 }
 
+/// Subscribes to changes under a single file or directory. Events are emitted
+/// as `file-watch-event` tagged with the returned subscription id.
+#[command]
+pub async fn watch_path(
+    app: AppHandle,
+    path: String,
+    recursive: Option<bool>,
+) -> Result<WatchSubscriptionInfo, FileSystemError> {
+    let full_path = resolve_workspace_path(&path)?;
+    let recursive = recursive.unwrap_or(false);
+
+    if !full_path.exists() {
+        return Err(FileSystemError::with_path(
+            "PATH_NOT_FOUND",
+            "Path not found",
+            &full_path,
+        ));
+    }
+
+    let subscription_id = Uuid::new_v4().to_string();
+    let event_subscription_id = subscription_id.clone();
+
+    let mut watcher = FileWatcher::with_handler(move |event| {
+        let kind = match event_kind_name(&event.kind) {
+            Some(kind) => kind,
+            None => return,
+        };
+        let payload = FileWatchEvent {
+            subscription_id: event_subscription_id.clone(),
+            kind: kind.to_string(),
+            paths: event
+                .paths
+                .iter()
+                .map(|p| workspace_relative_path(p))
+                .collect(),
+        };
+        if let Err(e) = app.emit("file-watch-event", payload) {
+            warn!("Failed to emit file watch event: {}", e);
+        }
+    })
+    .map_err(|e| FileSystemError::with_path("WATCH_ERROR", &e.to_string(), &full_path))?;
+
+    watcher
+        .watch_with_mode(&full_path, recursive)
+        .map_err(|e| FileSystemError::with_path("WATCH_ERROR", &e.to_string(), &full_path))?;
+
+    let info = WatchSubscriptionInfo {
+        id: subscription_id.clone(),
+        path: workspace_relative_path(&full_path),
+        recursive,
+    };
+
+    WATCH_SUBSCRIPTIONS.lock().insert(
+        subscription_id,
+        WatchSubscription {
+            info: info.clone(),
+            _watcher: watcher,
+        },
+    );
+
+    Ok(info)
+}
+
+/// Cancels a subscription created by `watch_path`.
+#[command]
+pub async fn unwatch_path(id: String) -> Result<(), FileSystemError> {
+    match WATCH_SUBSCRIPTIONS.lock().remove(&id) {
+        Some(_) => Ok(()),
+        None => Err(FileSystemError::new(
+            "WATCH_NOT_FOUND",
+            &format!("No watch subscription with id {}", id),
+        )),
+    }
+}
+
+#[command]
+pub async fn list_watched_paths() -> Result<Vec<WatchSubscriptionInfo>, FileSystemError> {
+    Ok(WATCH_SUBSCRIPTIONS
+        .lock()
+        .values()
+        .map(|subscription| subscription.info.clone())
+        .collect())
+}
+
+/// Whether the project watcher is running, and how many `watch_path`
+/// subscriptions are active.
+pub(crate) fn watcher_status() -> (bool, usize) {
+    (
+        app_state().file_watcher.lock().is_some(),
+        WATCH_SUBSCRIPTIONS.lock().len(),
+    )
+}
+
 // Cleanup function to be called on shutdown
 pub fn cleanup_fs() {
-    if let Some(_watcher) = FILE_WATCHER.lock().take() {
+    WATCH_SUBSCRIPTIONS.lock().clear();
+
+    if let Some(_watcher) = app_state().file_watcher.lock().take() {
         // The watcher will be dropped here, cleaning up its resources
     }
 }
+
+// The tests make symlinks, which need `std::os::unix`
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("mighty-fs-{}-{}", name, Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn dangling_symlink_out_of_workspace_is_rejected() {
+        let workspace = scratch_dir("workspace");
+        let outside = scratch_dir("outside");
+        let target = outside.join("authorized_keys");
+        std::os::unix::fs::symlink(&target, workspace.join("x")).unwrap();
+
+        let resolved = canonicalize_existing_prefix(&workspace.join("x")).unwrap();
+        assert_eq!(resolved, target);
+        assert!(!is_allowed_location(&resolved, &workspace));
+
+        // A file that doesn't exist yet stays where it is
+        let missing = canonicalize_existing_prefix(&workspace.join("new/file.rs")).unwrap();
+        assert_eq!(missing, workspace.join("new/file.rs"));
+        assert!(is_allowed_location(&missing, &workspace));
+
+        fs::remove_dir_all(&workspace).unwrap();
+        fs::remove_dir_all(&outside).unwrap();
+    }
+
+    #[test]
+    fn relative_symlink_is_followed() {
+        let workspace = scratch_dir("workspace");
+        fs::create_dir_all(workspace.join("src")).unwrap();
+        std::os::unix::fs::symlink("../../escaped", workspace.join("src/link")).unwrap();
+
+        let resolved = canonicalize_existing_prefix(&workspace.join("src/link/file")).unwrap();
+        assert_eq!(resolved, workspace.parent().unwrap().join("escaped/file"));
+        assert!(!is_allowed_location(&resolved, &workspace));
+
+        fs::remove_dir_all(&workspace).unwrap();
+    }
+}
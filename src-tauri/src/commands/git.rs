@@ -0,0 +1,1687 @@
+// src/commands/git.rs
+
+use git2::build::CheckoutBuilder;
+use git2::{
+    AnnotatedCommit, AutotagOption, BlameOptions, BranchType, Commit, Cred, CredentialType, Delta,
+    Diff, DiffFormat, DiffOptions, ErrorClass, ErrorCode, FetchOptions, IndexAddOption, IndexEntry,
+    IndexTime, Oid, Patch, PushOptions, RemoteCallbacks, Repository, Sort, Status, StatusOptions,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::ipc::Request;
+use tauri::{command, AppHandle, Emitter, Manager, State};
+use tracing::warn;
+
+use super::audit;
+use super::auth::GitCredential;
+use super::checkpoint::create_checkpoint;
+use super::diff::{compute_diff, DiffHunk, DiffLineKind};
+use super::dry_run::{self, DRY_RUN_CODE};
+use super::fs::{get_project_root, resolve_workspace_path};
+use super::permissions::{authorize, Actor, Capability};
+use crate::error::MightyError;
+use crate::state::AppState;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "MightyError")]
+pub struct GitError {
+    code: String,
+    message: String,
+}
+
+impl GitError {
+    pub(crate) fn new(code: &str, message: &str) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for GitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for GitError {}
+
+impl From<GitError> for MightyError {
+    fn from(e: GitError) -> Self {
+        MightyError::Git {
+            code: e.code,
+            message: e.message,
+        }
+    }
+}
+
+impl From<git2::Error> for GitError {
+    fn from(e: git2::Error) -> Self {
+        let code = match e.code() {
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::Conflict | ErrorCode::MergeConflict => "CONFLICT",
+            ErrorCode::UnbornBranch => "UNBORN_BRANCH",
+            ErrorCode::Locked => "LOCKED",
+            ErrorCode::BareRepo => "BARE_REPOSITORY",
+            ErrorCode::Auth => "AUTH_ERROR",
+            _ => "GIT_ERROR",
+        };
+        Self::new(code, e.message())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitFileStatus {
+    pub path: String,
+    /// Change recorded in the index relative to HEAD, if any.
+    pub index_status: Option<String>,
+    /// Change in the working tree relative to the index, if any.
+    pub worktree_status: Option<String>,
+    pub conflicted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitStatus {
+    pub branch: Option<String>,
+    pub head: Option<String>,
+    pub detached: bool,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    pub files: Vec<GitFileStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitDiffFile {
+    pub path: String,
+    pub old_path: Option<String>,
+    pub status: String,
+    pub additions: usize,
+    pub deletions: usize,
+    pub binary: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitDiff {
+    pub patch: String,
+    pub files: Vec<GitDiffFile>,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitCommitInfo {
+    pub id: String,
+    pub short_id: String,
+    pub summary: String,
+    pub message: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Seconds since the Unix epoch.
+    pub time: i64,
+    pub parents: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitShowResult {
+    pub commit: GitCommitInfo,
+    pub diff: GitDiff,
+    /// Contents of `path` at this commit, when a path was requested.
+    pub content: Option<String>,
+}
+
+/// Opens the repository containing the current workspace.
+pub(crate) fn open_repository() -> Result<Repository, GitError> {
+    Repository::discover(get_project_root()).map_err(|e| match e.code() {
+        ErrorCode::NotFound => GitError::new(
+            "NOT_A_REPOSITORY",
+            "The workspace is not inside a git repository",
+        ),
+        _ => e.into(),
+    })
+}
+
+/// Runs blocking git work off the async runtime.
+pub(crate) async fn run_git<T, F>(f: F) -> Result<T, GitError>
+where
+    T: Send + 'static,
+    F: FnOnce(Repository) -> Result<T, GitError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || f(open_repository()?))
+        .await
+        .map_err(|e| GitError::new("TASK_FAILED", &e.to_string()))?
+}
+
+fn index_status_name(status: Status) -> Option<&'static str> {
+    if status.contains(Status::INDEX_NEW) {
+        Some("added")
+    } else if status.contains(Status::INDEX_MODIFIED) {
+        Some("modified")
+    } else if status.contains(Status::INDEX_DELETED) {
+        Some("deleted")
+    } else if status.contains(Status::INDEX_RENAMED) {
+        Some("renamed")
+    } else if status.contains(Status::INDEX_TYPECHANGE) {
+        Some("typechange")
+    } else {
+        None
+    }
+}
+
+fn worktree_status_name(status: Status) -> Option<&'static str> {
+    if status.contains(Status::WT_NEW) {
+        Some("untracked")
+    } else if status.contains(Status::WT_MODIFIED) {
+        Some("modified")
+    } else if status.contains(Status::WT_DELETED) {
+        Some("deleted")
+    } else if status.contains(Status::WT_RENAMED) {
+        Some("renamed")
+    } else if status.contains(Status::WT_TYPECHANGE) {
+        Some("typechange")
+    } else {
+        None
+    }
+}
+
+fn delta_name(delta: Delta) -> &'static str {
+    match delta {
+        Delta::Added | Delta::Untracked => "added",
+        Delta::Deleted => "deleted",
+        Delta::Modified => "modified",
+        Delta::Renamed => "renamed",
+        Delta::Copied => "copied",
+        Delta::Typechange => "typechange",
+        Delta::Conflicted => "conflicted",
+        Delta::Ignored => "ignored",
+        Delta::Unmodified | Delta::Unreadable => "unmodified",
+    }
+}
+
+pub(crate) fn commit_info(commit: &Commit) -> GitCommitInfo {
+    let id = commit.id().to_string();
+    let author = commit.author();
+    GitCommitInfo {
+        short_id: id.chars().take(7).collect(),
+        id,
+        summary: commit.summary().unwrap_or_default().to_string(),
+        message: commit.message().unwrap_or_default().to_string(),
+        author_name: author.name().unwrap_or_default().to_string(),
+        author_email: author.email().unwrap_or_default().to_string(),
+        time: commit.time().seconds(),
+        parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+    }
+}
+
+/// Flattens a git2 diff into patch text plus per-file statistics.
+pub(crate) fn summarize_diff(diff: &Diff) -> Result<GitDiff, GitError> {
+    let mut patch = String::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if let origin @ ('+' | '-' | ' ') = line.origin() {
+            patch.push(origin);
+        }
+        patch.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })?;
+
+    let mut files = Vec::new();
+    for (index, delta) in diff.deltas().enumerate() {
+        let (additions, deletions) = match Patch::from_diff(diff, index)? {
+            Some(file_patch) => {
+                let (_, additions, deletions) = file_patch.line_stats()?;
+                (additions, deletions)
+            }
+            None => (0, 0),
+        };
+
+        let new_path = delta
+            .new_file()
+            .path()
+            .map(|p| p.to_string_lossy().to_string());
+        let old_path = delta
+            .old_file()
+            .path()
+            .map(|p| p.to_string_lossy().to_string());
+        files.push(GitDiffFile {
+            path: new_path
+                .clone()
+                .or_else(|| old_path.clone())
+                .unwrap_or_default(),
+            old_path: if old_path != new_path { old_path } else { None },
+            status: delta_name(delta.status()).to_string(),
+            additions,
+            deletions,
+            binary: delta.flags().is_binary(),
+        });
+    }
+
+    let stats = diff.stats()?;
+    Ok(GitDiff {
+        patch,
+        files,
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+    })
+}
+
+fn diff_options(path: Option<&str>) -> DiffOptions {
+    let mut opts = DiffOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    if let Some(path) = path {
+        opts.pathspec(path);
+    }
+    opts
+}
+
+/// Whether `commit` changed anything under `path` relative to its first parent.
+fn commit_touches_path(repo: &Repository, commit: &Commit, path: &str) -> Result<bool, GitError> {
+    let tree = commit.tree()?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let mut opts = DiffOptions::new();
+    opts.pathspec(path);
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts))?;
+    Ok(diff.deltas().len() > 0)
+}
+
+pub(crate) fn read_status(repo: &Repository) -> Result<GitStatus, GitError> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true);
+
+    let files = repo
+        .statuses(Some(&mut opts))?
+        .iter()
+        .filter_map(|entry| {
+            let status = entry.status();
+            if status.contains(Status::IGNORED) {
+                return None;
+            }
+            Some(GitFileStatus {
+                path: entry.path()?.to_string(),
+                index_status: index_status_name(status).map(String::from),
+                worktree_status: worktree_status_name(status).map(String::from),
+                conflicted: status.contains(Status::CONFLICTED),
+            })
+        })
+        .collect();
+
+    let head = match repo.head() {
+        Ok(head) => Some(head),
+        Err(e) if e.code() == ErrorCode::UnbornBranch || e.code() == ErrorCode::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let detached = repo.head_detached().unwrap_or(false);
+    let mut status = GitStatus {
+        branch: None,
+        head: None,
+        detached,
+        upstream: None,
+        ahead: 0,
+        behind: 0,
+        files,
+    };
+
+    if let Some(head) = head {
+        status.head = head.target().map(|oid| oid.to_string());
+        if !detached {
+            status.branch = head.shorthand().map(String::from);
+            let branch = git2::Branch::wrap(head);
+            if let Ok(upstream) = branch.upstream() {
+                status.upstream = upstream.name().ok().flatten().map(String::from);
+                if let (Some(local), Some(remote)) =
+                    (branch.get().target(), upstream.get().target())
+                {
+                    let (ahead, behind) = repo.graph_ahead_behind(local, remote)?;
+                    status.ahead = ahead;
+                    status.behind = behind;
+                }
+            }
+        }
+    }
+
+    Ok(status)
+}
+
+/// Working tree and index status, plus branch/upstream tracking info.
+#[command]
+pub async fn git_status() -> Result<GitStatus, GitError> {
+    run_git(|repo| read_status(&repo)).await
+}
+
+/// Diff of the working tree against the index, or of the index against HEAD
+/// when `staged` is set. Optionally limited to a single path.
+#[command]
+pub async fn git_diff(path: Option<String>, staged: Option<bool>) -> Result<GitDiff, GitError> {
+    run_git(move |repo| {
+        let mut opts = diff_options(path.as_deref());
+        let diff = if staged.unwrap_or(false) {
+            let head_tree = match repo.head() {
+                Ok(head) => Some(head.peel_to_tree()?),
+                Err(_) => None,
+            };
+            repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut opts))?
+        } else {
+            repo.diff_index_to_workdir(None, Some(&mut opts))?
+        };
+        summarize_diff(&diff)
+    })
+    .await
+}
+
+/// Commit history starting at HEAD, optionally limited to commits touching `path`.
+#[command]
+pub async fn git_log(
+    path: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<GitCommitInfo>, GitError> {
+    let limit = limit.unwrap_or(50);
+    run_git(move |repo| {
+        let mut revwalk = repo.revwalk()?;
+        revwalk.set_sorting(Sort::TIME)?;
+        match revwalk.push_head() {
+            Ok(()) => {}
+            Err(e) if e.code() == ErrorCode::UnbornBranch || e.code() == ErrorCode::NotFound => {
+                return Ok(Vec::new())
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        let mut commits = Vec::new();
+        for oid in revwalk {
+            if commits.len() >= limit {
+                break;
+            }
+            let commit = repo.find_commit(oid?)?;
+            if let Some(path) = &path {
+                if !commit_touches_path(&repo, &commit, path)? {
+                    continue;
+                }
+            }
+            commits.push(commit_info(&commit));
+        }
+        Ok(commits)
+    })
+    .await
+}
+
+/// Details of a single commit: metadata, its diff against the first parent
+/// and, when `path` is given, that file's contents at the commit.
+#[command]
+pub async fn git_show(commit: String, path: Option<String>) -> Result<GitShowResult, GitError> {
+    run_git(move |repo| {
+        let target = repo.revparse_single(&commit)?.peel_to_commit()?;
+        let tree = target.tree()?;
+        let parent_tree = match target.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+
+        let mut opts = DiffOptions::new();
+        if let Some(path) = &path {
+            opts.pathspec(path);
+        }
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts))?;
+
+        let content = match &path {
+            Some(path) => {
+                let entry = tree.get_path(Path::new(path))?;
+                let blob = entry.to_object(&repo)?.peel_to_blob()?;
+                Some(String::from_utf8_lossy(blob.content()).to_string())
+            }
+            None => None,
+        };
+
+        Ok(GitShowResult {
+            commit: commit_info(&target),
+            diff: summarize_diff(&diff)?,
+            content,
+        })
+    })
+    .await
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitBranch {
+    pub name: String,
+    pub is_remote: bool,
+    pub is_head: bool,
+    pub upstream: Option<String>,
+    pub target: Option<String>,
+}
+
+fn head_commit(repo: &Repository) -> Result<Option<Commit<'_>>, GitError> {
+    match repo.head() {
+        Ok(head) => Ok(Some(head.peel_to_commit()?)),
+        Err(e) if e.code() == ErrorCode::UnbornBranch || e.code() == ErrorCode::NotFound => {
+            Ok(None)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn workdir(repo: &Repository) -> Result<&Path, GitError> {
+    repo.workdir()
+        .ok_or_else(|| GitError::new("BARE_REPOSITORY", "Repository has no working directory"))
+}
+
+/// Adds the given paths (relative to the repository root) to the index,
+/// recording deletions for paths that no longer exist.
+#[command]
+pub async fn git_stage(request: Request<'_>, paths: Vec<String>) -> Result<GitStatus, GitError> {
+    let target = paths.join(", ");
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        dry_run::plan_step("git_stage", &target, None, json!(null));
+        return run_git(|repo| read_status(&repo)).await;
+    }
+    let status = run_git(move |repo| {
+        let root = workdir(&repo)?.to_path_buf();
+        let mut index = repo.index()?;
+        for path in &paths {
+            if root.join(path).exists() {
+                index.add_all([path.as_str()], IndexAddOption::DEFAULT, None)?;
+            } else {
+                index.remove_all([path.as_str()], None)?;
+            }
+        }
+        index.write()?;
+        read_status(&repo)
+    })
+    .await?;
+    audit::record(actor, "git_stage", &target, json!(null));
+    Ok(status)
+}
+
+/// Resets the given paths in the index back to HEAD, keeping working tree changes.
+#[command]
+pub async fn git_unstage(request: Request<'_>, paths: Vec<String>) -> Result<GitStatus, GitError> {
+    let target = paths.join(", ");
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        dry_run::plan_step("git_unstage", &target, None, json!(null));
+        return run_git(|repo| read_status(&repo)).await;
+    }
+    let status = run_git(move |repo| {
+        match head_commit(&repo)? {
+            Some(head) => repo.reset_default(Some(head.as_object()), paths.iter())?,
+            None => {
+                // Nothing committed yet, so unstaging means dropping from the index
+                let mut index = repo.index()?;
+                for path in &paths {
+                    index.remove_all([path.as_str()], None)?;
+                }
+                index.write()?;
+            }
+        }
+        read_status(&repo)
+    })
+    .await?;
+    audit::record(actor, "git_unstage", &target, json!(null));
+    Ok(status)
+}
+
+/// Commits the index on the current branch, or rewrites HEAD when `amend` is set.
+#[command]
+pub async fn git_commit(
+    request: Request<'_>,
+    message: String,
+    amend: Option<bool>,
+) -> Result<GitCommitInfo, GitError> {
+    let amend = amend.unwrap_or(false);
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        let details = json!({ "message": message, "amend": amend });
+        dry_run::plan_step("git_commit", "HEAD", None, details);
+        return Err(GitError::new(
+            DRY_RUN_CODE,
+            &dry_run::planned_error("git_commit"),
+        ));
+    }
+    let info = run_git(move |repo| {
+        if message.trim().is_empty() {
+            return Err(GitError::new("EMPTY_MESSAGE", "Commit message is empty"));
+        }
+        if repo.head_detached().unwrap_or(false) {
+            return Err(GitError::new(
+                "DETACHED_HEAD",
+                "HEAD is detached; check out a branch before committing",
+            ));
+        }
+
+        let mut index = repo.index()?;
+        if index.has_conflicts() {
+            return Err(GitError::new(
+                "CONFLICT",
+                "Resolve merge conflicts before committing",
+            ));
+        }
+
+        let signature = repo.signature().map_err(|e| {
+            GitError::new(
+                "MISSING_IDENTITY",
+                &format!("Configure user.name and user.email first: {}", e.message()),
+            )
+        })?;
+        let tree = repo.find_tree(index.write_tree()?)?;
+        let head = head_commit(&repo)?;
+
+        let oid = if amend {
+            let head = head
+                .ok_or_else(|| GitError::new("UNBORN_BRANCH", "There is no commit to amend yet"))?;
+            head.amend(
+                Some("HEAD"),
+                None,
+                Some(&signature),
+                None,
+                Some(&message),
+                Some(&tree),
+            )?
+        } else {
+            let parents: Vec<&Commit> = head.iter().collect();
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                &message,
+                &tree,
+                &parents,
+            )?
+        };
+
+        Ok(commit_info(&repo.find_commit(oid)?))
+    })
+    .await?;
+    audit::record(
+        actor,
+        "git_commit",
+        &info.id,
+        json!({ "summary": info.summary, "amend": amend }),
+    );
+    Ok(info)
+}
+
+/// Lists local and remote-tracking branches.
+#[command]
+pub async fn git_branches() -> Result<Vec<GitBranch>, GitError> {
+    run_git(|repo| {
+        let mut branches = Vec::new();
+        for branch in repo.branches(None)? {
+            let (branch, branch_type) = branch?;
+            let name = match branch.name()? {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            // Skip the symbolic origin/HEAD pointer
+            if branch_type == BranchType::Remote && name.ends_with("/HEAD") {
+                continue;
+            }
+            let upstream = branch
+                .upstream()
+                .ok()
+                .and_then(|u| u.name().ok().flatten().map(String::from));
+            branches.push(GitBranch {
+                name,
+                is_remote: branch_type == BranchType::Remote,
+                is_head: branch.is_head(),
+                upstream,
+                target: branch.get().target().map(|oid| oid.to_string()),
+            });
+        }
+        Ok(branches)
+    })
+    .await
+}
+
+/// Switches to `branch`, creating it from HEAD when `create` is set. Checking
+/// out a remote branch creates a local tracking branch with the same name.
+#[command]
+pub async fn git_checkout(
+    request: Request<'_>,
+    branch: String,
+    create: Option<bool>,
+) -> Result<GitStatus, GitError> {
+    let create = create.unwrap_or(false);
+    let target = branch.clone();
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        dry_run::plan_step("git_checkout", &target, None, json!({ "create": create }));
+        return run_git(|repo| read_status(&repo)).await;
+    }
+    let status = run_git(move |repo| {
+        let local = if create {
+            let head = head_commit(&repo)?.ok_or_else(|| {
+                GitError::new("UNBORN_BRANCH", "Cannot branch before the first commit")
+            })?;
+            repo.branch(&branch, &head, false)?
+        } else {
+            match repo.find_branch(&branch, BranchType::Local) {
+                Ok(local) => local,
+                Err(e) if e.code() == ErrorCode::NotFound => {
+                    let remote = repo.find_branch(&branch, BranchType::Remote)?;
+                    let local_name = branch
+                        .split_once('/')
+                        .map(|(_, name)| name)
+                        .unwrap_or(&branch)
+                        .to_string();
+                    let mut local =
+                        repo.branch(&local_name, &remote.get().peel_to_commit()?, false)?;
+                    local.set_upstream(Some(&branch))?;
+                    local
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        let reference = local
+            .get()
+            .name()
+            .ok_or_else(|| GitError::new("GIT_ERROR", "Branch name is not valid UTF-8"))?
+            .to_string();
+        let target = local.get().peel_to_commit()?;
+
+        let mut checkout = CheckoutBuilder::new();
+        checkout.safe();
+        repo.checkout_tree(target.as_object(), Some(&mut checkout))
+            .map_err(|e| match e.code() {
+                ErrorCode::Conflict | ErrorCode::MergeConflict => GitError::new(
+                    "CONFLICT",
+                    &format!(
+                        "Local changes would be overwritten by checking out {}: {}",
+                        branch,
+                        e.message()
+                    ),
+                ),
+                _ => e.into(),
+            })?;
+        repo.set_head(&reference)?;
+
+        read_status(&repo)
+    })
+    .await?;
+    audit::record(actor, "git_checkout", &target, json!({ "create": create }));
+    Ok(status)
+}
+
+/// Throws away working tree changes for the given paths, which may be
+/// files or directories. Tracked files go back to their staged version and
+/// untracked ones are deleted; every changed file is checkpointed first.
+#[command]
+pub async fn git_discard(
+    app: AppHandle,
+    request: Request<'_>,
+    paths: Vec<String>,
+) -> Result<GitStatus, GitError> {
+    let target = paths.join(", ");
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        dry_run::plan_step("git_discard", &target, None, json!(null));
+        return run_git(|repo| read_status(&repo)).await;
+    }
+    // Changes are overwritten and untracked files deleted
+    for path in &paths {
+        authorize(&app, actor, Capability::DeletePath, path)
+            .await
+            .map_err(|e| GitError::new("PERMISSION_DENIED", &e))?;
+    }
+    let status = run_git(move |repo| {
+        let root = workdir(&repo)?.to_path_buf();
+        let index = repo.index()?;
+        let changes = worktree_changes(&repo)?;
+
+        let mut tracked_paths = Vec::new();
+        let mut untracked = Vec::new();
+        for path in &paths {
+            let full_path =
+                resolve_workspace_path(path).map_err(|e| GitError::new(e.code(), e.message()))?;
+            let relative = repo_relative_path(&repo, &full_path.to_string_lossy())?;
+
+            // A directory is tracked if any file under it is
+            let tracked = index.iter().any(|entry| {
+                Path::new(&*String::from_utf8_lossy(&entry.path)).starts_with(&relative)
+            });
+            if tracked {
+                tracked_paths.push(relative.clone());
+            }
+            for (changed, is_untracked) in &changes {
+                if !changed.starts_with(&relative) {
+                    continue;
+                }
+                let changed_path = root.join(changed);
+                create_checkpoint(&changed_path, "git_discard")
+                    .map_err(|e| GitError::new("CHECKPOINT_ERROR", &e.to_string()))?;
+                if *is_untracked && !untracked.contains(&changed_path) {
+                    untracked.push(changed_path);
+                }
+            }
+        }
+
+        if !tracked_paths.is_empty() {
+            let mut checkout = CheckoutBuilder::new();
+            checkout.force();
+            // The repository root means everything, which is no paths at all
+            if !tracked_paths.iter().any(|path| path.as_os_str().is_empty()) {
+                for path in &tracked_paths {
+                    checkout.path(path);
+                }
+            }
+            repo.checkout_index(None, Some(&mut checkout))?;
+        }
+        for full_path in untracked {
+            std::fs::remove_file(&full_path).map_err(|e| {
+                GitError::new(
+                    "DISCARD_ERROR",
+                    &format!("Failed to remove {}: {}", full_path.display(), e),
+                )
+            })?;
+            remove_empty_parents(&full_path, &root);
+        }
+        read_status(&repo)
+    })
+    .await?;
+    audit::record(actor, "git_discard", &target, json!(null));
+    Ok(status)
+}
+
+/// Files with working tree changes, relative to the repository root, and
+/// whether each is untracked.
+fn worktree_changes(repo: &Repository) -> Result<Vec<(PathBuf, bool)>, GitError> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true).recurse_untracked_dirs(true);
+    let changes = repo
+        .statuses(Some(&mut opts))?
+        .iter()
+        .filter_map(|entry| {
+            let status = entry.status();
+            let changed = status.intersects(
+                Status::WT_NEW | Status::WT_MODIFIED | Status::WT_TYPECHANGE | Status::WT_RENAMED,
+            );
+            if !changed || status.contains(Status::IGNORED) {
+                return None;
+            }
+            Some((
+                PathBuf::from(entry.path()?),
+                status.contains(Status::WT_NEW),
+            ))
+        })
+        .collect();
+    Ok(changes)
+}
+
+/// Removes the directories above `path` that are left empty, up to `root`.
+fn remove_empty_parents(path: &Path, root: &Path) {
+    for dir in path.ancestors().skip(1) {
+        if dir == root || !dir.starts_with(root) || std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitBlameHunk {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub commit_id: String,
+    pub author_name: String,
+    pub author_email: String,
+    /// Seconds since the Unix epoch.
+    pub time: i64,
+    pub summary: String,
+}
+
+/// Converts a workspace path (relative or absolute) into one relative to the
+/// repository root, as libgit2 expects.
+fn repo_relative_path(repo: &Repository, path: &str) -> Result<PathBuf, GitError> {
+    let root = workdir(repo)?;
+    let full_path = get_project_root().join(path);
+    let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let canonical_path = full_path.canonicalize().unwrap_or(full_path);
+    canonical_path
+        .strip_prefix(&canonical_root)
+        .map(Path::to_path_buf)
+        .map_err(|_| {
+            GitError::new(
+                "NOT_IN_REPOSITORY",
+                &format!("{} is outside the repository", path),
+            )
+        })
+}
+
+/// Blames `path` (optionally limited to a 1-based inclusive line range).
+pub(crate) fn blame_lines(
+    repo: &Repository,
+    path: &str,
+    start_line: Option<usize>,
+    end_line: Option<usize>,
+) -> Result<Vec<GitBlameHunk>, GitError> {
+    let relative = repo_relative_path(repo, path)?;
+    let mut opts = BlameOptions::new();
+    if let Some(start) = start_line {
+        opts.min_line(start.max(1));
+    }
+    if let Some(end) = end_line {
+        opts.max_line(end);
+    }
+
+    let blame = repo.blame_file(&relative, Some(&mut opts))?;
+    let mut hunks = Vec::with_capacity(blame.len());
+    for hunk in blame.iter() {
+        let signature = hunk.final_signature();
+        let summary = repo
+            .find_commit(hunk.final_commit_id())
+            .ok()
+            .and_then(|c| c.summary().map(String::from))
+            .unwrap_or_default();
+        let start = hunk.final_start_line();
+        hunks.push(GitBlameHunk {
+            start_line: start,
+            end_line: start + hunk.lines_in_hunk().saturating_sub(1),
+            commit_id: hunk.final_commit_id().to_string(),
+            author_name: signature.name().unwrap_or_default().to_string(),
+            author_email: signature.email().unwrap_or_default().to_string(),
+            time: signature.when().seconds(),
+            summary,
+        });
+    }
+    Ok(hunks)
+}
+
+/// Line-range blame for a file, returned as hunks of consecutive lines that
+/// share the same last commit.
+#[command]
+pub async fn git_blame(
+    path: String,
+    start_line: Option<usize>,
+    end_line: Option<usize>,
+) -> Result<Vec<GitBlameHunk>, GitError> {
+    run_git(move |repo| blame_lines(&repo, &path, start_line, end_line)).await
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitHunk {
+    /// Stable id derived from the hunk's position and contents; becomes
+    /// invalid as soon as the file changes underneath it.
+    pub id: String,
+    #[serde(flatten)]
+    pub hunk: DiffHunk,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitFileHunks {
+    pub path: String,
+    /// Set when either side is not UTF-8 text; no hunks are reported then.
+    pub binary: bool,
+    pub hunks: Vec<GitHunk>,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+/// The index and working tree versions of a single file.
+struct HunkSides {
+    relative: PathBuf,
+    entry: Option<IndexEntry>,
+    staged: Option<String>,
+    worktree: Option<String>,
+    binary: bool,
+}
+
+impl HunkSides {
+    fn load(repo: &Repository, path: &str) -> Result<Self, GitError> {
+        let relative = repo_relative_path(repo, path)?;
+        let full_path = workdir(repo)?.join(&relative);
+
+        let entry = repo.index()?.get_path(&relative, 0);
+        let staged_bytes = match &entry {
+            Some(entry) => Some(repo.find_blob(entry.id)?.content().to_vec()),
+            None => None,
+        };
+        let worktree_bytes = match std::fs::read(&full_path) {
+            Ok(bytes) => Some(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(GitError::new(
+                    "READ_ERROR",
+                    &format!("Failed to read {}: {}", path, e),
+                ))
+            }
+        };
+
+        let as_text = |bytes: Option<Vec<u8>>| match bytes {
+            Some(bytes) if bytes.contains(&0) => Err(()),
+            Some(bytes) => String::from_utf8(bytes).map(Some).map_err(|_| ()),
+            None => Ok(None),
+        };
+        let (staged, worktree, binary) = match (as_text(staged_bytes), as_text(worktree_bytes)) {
+            (Ok(staged), Ok(worktree)) => (staged, worktree, false),
+            _ => (None, None, true),
+        };
+
+        Ok(Self {
+            relative,
+            entry,
+            staged,
+            worktree,
+            binary,
+        })
+    }
+
+    fn staged_text(&self) -> &str {
+        self.staged.as_deref().unwrap_or_default()
+    }
+
+    fn worktree_text(&self) -> &str {
+        self.worktree.as_deref().unwrap_or_default()
+    }
+
+    fn hunks(&self) -> Vec<GitHunk> {
+        if self.binary {
+            return Vec::new();
+        }
+        compute_diff(self.staged_text(), self.worktree_text())
+            .hunks
+            .into_iter()
+            .map(|hunk| GitHunk {
+                id: hunk_id(&hunk),
+                hunk,
+            })
+            .collect()
+    }
+
+    fn find_hunk(&self, hunk_id: &str) -> Result<DiffHunk, GitError> {
+        if self.binary {
+            return Err(GitError::new(
+                "BINARY_FILE",
+                "Hunks are not available for binary files",
+            ));
+        }
+        self.hunks()
+            .into_iter()
+            .find(|h| h.id == hunk_id)
+            .map(|h| h.hunk)
+            .ok_or_else(|| {
+                GitError::new(
+                    "HUNK_NOT_FOUND",
+                    "Hunk no longer matches the file; refresh the diff and try again",
+                )
+            })
+    }
+
+    fn summary(&self, path: String) -> GitFileHunks {
+        let hunks = self.hunks();
+        let count = |kind: DiffLineKind| {
+            hunks
+                .iter()
+                .flat_map(|h| &h.hunk.lines)
+                .filter(|l| l.kind == kind)
+                .count()
+        };
+        GitFileHunks {
+            additions: count(DiffLineKind::Added),
+            deletions: count(DiffLineKind::Removed),
+            path,
+            binary: self.binary,
+            hunks,
+        }
+    }
+}
+
+fn hunk_id(hunk: &DiffHunk) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(hunk.header.as_bytes());
+    for line in &hunk.lines {
+        hasher.update([line.kind as u8]);
+        hasher.update(line.content.as_bytes());
+        hasher.update(b"\n");
+    }
+    hasher
+        .finalize()
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Zero-based (start, len) ranges of a hunk's old and new sides.
+fn hunk_ranges(hunk: &DiffHunk) -> ((usize, usize), (usize, usize)) {
+    // An empty side's start points at the line before the change
+    let old_start = if hunk.old_lines == 0 {
+        hunk.old_start
+    } else {
+        hunk.old_start - 1
+    };
+    let new_start = if hunk.new_lines == 0 {
+        hunk.new_start
+    } else {
+        hunk.new_start - 1
+    };
+    ((old_start, hunk.old_lines), (new_start, hunk.new_lines))
+}
+
+/// Replaces a line range of `target` with a line range of `source`, keeping
+/// each line's original terminator.
+fn splice_lines(
+    target: &str,
+    (start, len): (usize, usize),
+    source: &str,
+    (source_start, source_len): (usize, usize),
+) -> String {
+    let target: Vec<&str> = target.split_inclusive('\n').collect();
+    let source: Vec<&str> = source.split_inclusive('\n').collect();
+    target[..start]
+        .iter()
+        .chain(&source[source_start..source_start + source_len])
+        .chain(&target[start + len..])
+        .copied()
+        .collect()
+}
+
+/// Structured hunks of the working tree changes to `path` that are not yet staged.
+#[command]
+pub async fn git_diff_hunks(path: String) -> Result<GitFileHunks, GitError> {
+    run_git(move |repo| Ok(HunkSides::load(&repo, &path)?.summary(path))).await
+}
+
+/// Stages a single hunk returned by `git_diff_hunks`, leaving the rest of the
+/// file's working tree changes unstaged.
+#[command]
+pub async fn git_stage_hunk(
+    request: Request<'_>,
+    path: String,
+    hunk_id: String,
+) -> Result<GitFileHunks, GitError> {
+    let (target, hunk) = (path.clone(), hunk_id.clone());
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        dry_run::plan_step("git_stage_hunk", &target, None, json!({ "hunk_id": hunk }));
+        return run_git(move |repo| Ok(HunkSides::load(&repo, &path)?.summary(path))).await;
+    }
+    let hunks = run_git(move |repo| {
+        let mut sides = HunkSides::load(&repo, &path)?;
+        let hunk = sides.find_hunk(&hunk_id)?;
+        let (old_range, new_range) = hunk_ranges(&hunk);
+        let content = splice_lines(
+            sides.staged_text(),
+            old_range,
+            sides.worktree_text(),
+            new_range,
+        );
+
+        let mut index = repo.index()?;
+        if content.is_empty() && sides.worktree.is_none() {
+            // Staging the last hunk of a deleted file stages the deletion
+            index.remove_path(&sides.relative)?;
+        } else {
+            let entry = sides.entry.take().unwrap_or_else(|| IndexEntry {
+                ctime: IndexTime::new(0, 0),
+                mtime: IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode: 0o100644,
+                uid: 0,
+                gid: 0,
+                file_size: 0,
+                id: Oid::zero(),
+                flags: 0,
+                flags_extended: 0,
+                path: sides
+                    .relative
+                    .to_string_lossy()
+                    .replace('\\', "/")
+                    .into_bytes(),
+            });
+            index.add_frombuffer(&entry, content.as_bytes())?;
+        }
+        index.write()?;
+
+        Ok(HunkSides::load(&repo, &path)?.summary(path))
+    })
+    .await?;
+    audit::record(actor, "git_stage_hunk", &target, json!({ "hunk_id": hunk }));
+    Ok(hunks)
+}
+
+/// Reverts a single unstaged hunk in the working tree back to its staged
+/// version. The file is checkpointed first so the revert can be undone.
+#[command]
+pub async fn git_revert_hunk(
+    app: AppHandle,
+    request: Request<'_>,
+    path: String,
+    hunk_id: String,
+) -> Result<GitFileHunks, GitError> {
+    let (target, hunk) = (path.clone(), hunk_id.clone());
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        dry_run::plan_step("git_revert_hunk", &target, None, json!({ "hunk_id": hunk }));
+        return run_git(move |repo| Ok(HunkSides::load(&repo, &path)?.summary(path))).await;
+    }
+    authorize(&app, actor, Capability::WriteFile, &target)
+        .await
+        .map_err(|e| GitError::new("PERMISSION_DENIED", &e))?;
+    let hunks = run_git(move |repo| {
+        let sides = HunkSides::load(&repo, &path)?;
+        let hunk = sides.find_hunk(&hunk_id)?;
+        let (old_range, new_range) = hunk_ranges(&hunk);
+        let content = splice_lines(
+            sides.worktree_text(),
+            new_range,
+            sides.staged_text(),
+            old_range,
+        );
+
+        let full_path = workdir(&repo)?.join(&sides.relative);
+        create_checkpoint(&full_path, "git_revert_hunk")
+            .map_err(|e| GitError::new("CHECKPOINT_ERROR", &e.to_string()))?;
+
+        let written = if content.is_empty() && sides.entry.is_none() {
+            // Reverting the only hunk of an untracked file removes it
+            std::fs::remove_file(&full_path)
+        } else {
+            full_path
+                .parent()
+                .map(std::fs::create_dir_all)
+                .unwrap_or(Ok(()))
+                .and_then(|_| std::fs::write(&full_path, &content))
+        };
+        written.map_err(|e| {
+            GitError::new("WRITE_ERROR", &format!("Failed to write {}: {}", path, e))
+        })?;
+
+        Ok(HunkSides::load(&repo, &path)?.summary(path))
+    })
+    .await?;
+    audit::record(
+        actor,
+        "git_revert_hunk",
+        &target,
+        json!({ "hunk_id": hunk }),
+    );
+    Ok(hunks)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PullStrategy {
+    Merge,
+    Rebase,
+    FastForwardOnly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PullOutcome {
+    UpToDate,
+    FastForward,
+    Merged,
+    Rebased,
+    /// The merge stopped with conflicts that need resolving before committing.
+    Conflict,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitPullResult {
+    pub outcome: PullOutcome,
+    pub status: GitStatus,
+}
+
+/// Payload of the `git-progress` event emitted during fetch, pull and push.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GitProgress {
+    pub operation: String,
+    pub remote: String,
+    pub stage: String,
+    pub current: usize,
+    pub total: usize,
+    pub bytes: usize,
+}
+
+/// Maximum number of times libgit2 may ask for credentials before giving up;
+/// it keeps asking as long as the previous answer was rejected.
+const MAX_CREDENTIAL_ATTEMPTS: usize = 6;
+
+/// Extracts the host from an HTTPS or scp-style remote URL.
+fn url_host(url: &str) -> Option<&str> {
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let authority = rest.split(['/', ':']).next()?;
+    let host = authority.rsplit('@').next()?;
+    (!host.is_empty()).then_some(host)
+}
+
+/// Builds callbacks that answer credential requests (stored app credentials,
+/// then git's credential helpers / OS keychain, then ssh-agent and default
+/// keys) and report transfer progress as `git-progress` events.
+fn remote_callbacks<'a>(
+    repo: &'a Repository,
+    app: &'a AppHandle,
+    credentials: &'a HashMap<String, GitCredential>,
+    operation: &'a str,
+    remote: &'a str,
+) -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+
+    let mut attempts = 0;
+    let mut tried_stored = false;
+    let mut tried_helper = false;
+    let mut ssh_keys: Option<Vec<PathBuf>> = None;
+    callbacks.credentials(move |url, username_from_url, allowed| {
+        attempts += 1;
+        if attempts > MAX_CREDENTIAL_ATTEMPTS {
+            return Err(git2::Error::new(
+                ErrorCode::Auth,
+                ErrorClass::Net,
+                "Authentication failed",
+            ));
+        }
+
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if !tried_stored {
+                tried_stored = true;
+                if let Some(credential) = url_host(url).and_then(|host| credentials.get(host)) {
+                    return Cred::userpass_plaintext(&credential.username, &credential.password);
+                }
+            }
+            if !tried_helper {
+                tried_helper = true;
+                if let Ok(cred) = repo
+                    .config()
+                    .and_then(|config| Cred::credential_helper(&config, url, username_from_url))
+                {
+                    return Ok(cred);
+                }
+            }
+        }
+
+        if allowed.contains(CredentialType::SSH_KEY) {
+            let user = username_from_url.unwrap_or("git");
+            let keys = ssh_keys.get_or_insert_with(|| {
+                let ssh_dir = app.path().home_dir().unwrap_or_default().join(".ssh");
+                // Tried in reverse order, so ssh-agent goes first
+                let mut keys: Vec<PathBuf> = ["id_rsa", "id_ecdsa", "id_ed25519"]
+                    .iter()
+                    .map(|name| ssh_dir.join(name))
+                    .filter(|path| path.exists())
+                    .collect();
+                keys.push(PathBuf::new());
+                keys
+            });
+            if let Some(key) = keys.pop() {
+                return if key.as_os_str().is_empty() {
+                    Cred::ssh_key_from_agent(user)
+                } else {
+                    Cred::ssh_key(user, None, &key, None)
+                };
+            }
+        }
+
+        if allowed.contains(CredentialType::DEFAULT) {
+            return Cred::default();
+        }
+
+        Err(git2::Error::new(
+            ErrorCode::Auth,
+            ErrorClass::Net,
+            format!("No usable credentials for {}", url),
+        ))
+    });
+
+    let emit = move |stage: &str, current: usize, total: usize, bytes: usize| {
+        let progress = GitProgress {
+            operation: operation.to_string(),
+            remote: remote.to_string(),
+            stage: stage.to_string(),
+            current,
+            total,
+            bytes,
+        };
+        if let Err(e) = app.emit("git-progress", &progress) {
+            warn!("Failed to emit git progress: {}", e);
+        }
+    };
+
+    // Only report whole-percent changes to avoid flooding the frontend
+    let mut last_fetch_percent = None;
+    callbacks.transfer_progress(move |stats| {
+        let (stage, current) = if stats.received_objects() < stats.total_objects() {
+            ("receiving", stats.received_objects())
+        } else {
+            ("indexing", stats.indexed_deltas())
+        };
+        let total = if stage == "receiving" {
+            stats.total_objects()
+        } else {
+            stats.total_deltas()
+        };
+        let percent = (stage, (current * 100).checked_div(total).unwrap_or(100));
+        if last_fetch_percent != Some(percent) {
+            last_fetch_percent = Some(percent);
+            emit(stage, current, total, stats.received_bytes());
+        }
+        true
+    });
+
+    let mut last_push_percent = None;
+    callbacks.push_transfer_progress(move |current, total, bytes| {
+        let percent = (current * 100).checked_div(total).unwrap_or(100);
+        if last_push_percent != Some(percent) {
+            last_push_percent = Some(percent);
+            emit("pushing", current, total, bytes);
+        }
+    });
+
+    callbacks
+}
+
+/// Remote the current branch tracks, falling back to `origin`.
+fn default_remote(repo: &Repository) -> String {
+    repo.head()
+        .ok()
+        .and_then(|head| head.name().map(String::from))
+        .and_then(|name| repo.branch_upstream_remote(&name).ok())
+        .and_then(|remote| remote.as_str().map(String::from))
+        .unwrap_or_else(|| "origin".to_string())
+}
+
+/// Name of the checked out branch, failing for detached or unborn HEADs.
+fn current_branch(repo: &Repository) -> Result<String, GitError> {
+    if repo.head_detached().unwrap_or(false) {
+        return Err(GitError::new(
+            "DETACHED_HEAD",
+            "HEAD is detached; check out a branch first",
+        ));
+    }
+    let head = repo.head()?;
+    head.shorthand()
+        .map(String::from)
+        .ok_or_else(|| GitError::new("GIT_ERROR", "Branch name is not valid UTF-8"))
+}
+
+fn fetch_remote(
+    repo: &Repository,
+    app: &AppHandle,
+    credentials: &HashMap<String, GitCredential>,
+    operation: &str,
+    remote_name: &str,
+) -> Result<(), GitError> {
+    let mut remote = repo.find_remote(remote_name)?;
+    let mut fetch_options = FetchOptions::new();
+    fetch_options
+        .remote_callbacks(remote_callbacks(
+            repo,
+            app,
+            credentials,
+            operation,
+            remote_name,
+        ))
+        .download_tags(AutotagOption::Auto);
+    // An empty refspec list uses the remote's configured fetch refspecs
+    remote.fetch::<&str>(&[], Some(&mut fetch_options), None)?;
+    Ok(())
+}
+
+/// Fetches from `remote` (default: the current branch's remote, or `origin`).
+#[command]
+pub async fn git_fetch(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    remote: Option<String>,
+) -> Result<GitStatus, GitError> {
+    fetch(app, state.auth.git_credentials(), remote).await
+}
+
+/// `git_fetch` without the command state, for background fetch jobs.
+pub(crate) async fn fetch(
+    app: AppHandle,
+    credentials: HashMap<String, GitCredential>,
+    remote: Option<String>,
+) -> Result<GitStatus, GitError> {
+    run_git(move |repo| {
+        let remote = remote.unwrap_or_else(|| default_remote(&repo));
+        fetch_remote(&repo, &app, &credentials, "fetch", &remote)?;
+        read_status(&repo)
+    })
+    .await
+}
+
+fn merge_upstream(
+    repo: &Repository,
+    upstream: &AnnotatedCommit,
+    upstream_name: &str,
+) -> Result<PullOutcome, GitError> {
+    let signature = repo.signature().map_err(|e| {
+        GitError::new(
+            "MISSING_IDENTITY",
+            &format!("Configure user.name and user.email first: {}", e.message()),
+        )
+    })?;
+
+    let mut merge_checkout = CheckoutBuilder::new();
+    merge_checkout.safe();
+    repo.merge(&[upstream], None, Some(&mut merge_checkout))?;
+
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        // Leave the merge in progress so the conflicts can be resolved
+        return Ok(PullOutcome::Conflict);
+    }
+
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let head = repo.head()?.peel_to_commit()?;
+    let theirs = repo.find_commit(upstream.id())?;
+    let message = format!("Merge remote-tracking branch '{}'", upstream_name);
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &[&head, &theirs],
+    )?;
+    repo.cleanup_state()?;
+    Ok(PullOutcome::Merged)
+}
+
+fn rebase_onto_upstream(
+    repo: &Repository,
+    upstream: &AnnotatedCommit,
+) -> Result<PullOutcome, GitError> {
+    let signature = repo.signature().map_err(|e| {
+        GitError::new(
+            "MISSING_IDENTITY",
+            &format!("Configure user.name and user.email first: {}", e.message()),
+        )
+    })?;
+
+    let mut rebase = repo.rebase(None, Some(upstream), None, None)?;
+    while let Some(operation) = rebase.next() {
+        operation?;
+        if repo.index()?.has_conflicts() {
+            rebase.abort()?;
+            return Err(GitError::new(
+                "CONFLICT",
+                "Rebase stopped on conflicts and was aborted; pull with merge instead or rebase in the terminal",
+            ));
+        }
+        match rebase.commit(None, &signature, None) {
+            Ok(_) => {}
+            // Commits already present upstream become empty and are skipped
+            Err(e) if e.code() == ErrorCode::Applied => {}
+            Err(e) => {
+                rebase.abort()?;
+                return Err(e.into());
+            }
+        }
+    }
+    rebase.finish(Some(&signature))?;
+    Ok(PullOutcome::Rebased)
+}
+
+/// Fetches the current branch's upstream and integrates it using `strategy`
+/// (default: merge).
+#[command]
+pub async fn git_pull(
+    app: AppHandle,
+    request: Request<'_>,
+    state: State<'_, AppState>,
+    strategy: Option<PullStrategy>,
+) -> Result<GitPullResult, GitError> {
+    let strategy = strategy.unwrap_or(PullStrategy::Merge);
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        dry_run::plan_step("git_pull", "HEAD", None, json!({ "strategy": strategy }));
+        return Err(GitError::new(
+            DRY_RUN_CODE,
+            &dry_run::planned_error("git_pull"),
+        ));
+    }
+    let credentials = state.auth.git_credentials();
+    let result = run_git(move |repo| {
+        let branch_name = current_branch(&repo)?;
+        let branch = repo.find_branch(&branch_name, BranchType::Local)?;
+        let upstream = branch.upstream().map_err(|_| {
+            GitError::new(
+                "NO_UPSTREAM",
+                &format!("Branch {} has no upstream branch", branch_name),
+            )
+        })?;
+        let upstream_name = upstream
+            .name()?
+            .ok_or_else(|| GitError::new("GIT_ERROR", "Branch name is not valid UTF-8"))?
+            .to_string();
+
+        let remote = default_remote(&repo);
+        fetch_remote(&repo, &app, &credentials, "pull", &remote)?;
+
+        // Re-resolve after fetching, the upstream ref has moved
+        let upstream = repo.find_branch(&upstream_name, BranchType::Remote)?;
+        let upstream_commit = repo.reference_to_annotated_commit(upstream.get())?;
+        let (analysis, _) = repo.merge_analysis(&[&upstream_commit])?;
+
+        let outcome = if analysis.is_up_to_date() {
+            PullOutcome::UpToDate
+        } else if analysis.is_fast_forward() {
+            let target = repo.find_commit(upstream_commit.id())?;
+            let mut checkout = CheckoutBuilder::new();
+            checkout.safe();
+            repo.checkout_tree(target.as_object(), Some(&mut checkout))?;
+            branch.into_reference().set_target(
+                upstream_commit.id(),
+                &format!("pull: fast-forward to {}", upstream_name),
+            )?;
+            PullOutcome::FastForward
+        } else {
+            match strategy {
+                PullStrategy::FastForwardOnly => {
+                    return Err(GitError::new(
+                        "NOT_FAST_FORWARD",
+                        &format!(
+                            "{} has diverged from {}; pull with merge or rebase",
+                            branch_name, upstream_name
+                        ),
+                    ))
+                }
+                PullStrategy::Merge => merge_upstream(&repo, &upstream_commit, &upstream_name)?,
+                PullStrategy::Rebase => rebase_onto_upstream(&repo, &upstream_commit)?,
+            }
+        };
+
+        Ok(GitPullResult {
+            outcome,
+            status: read_status(&repo)?,
+        })
+    })
+    .await?;
+    audit::record(
+        actor,
+        "git_pull",
+        result.status.branch.as_deref().unwrap_or("HEAD"),
+        json!({ "strategy": strategy, "outcome": result.outcome }),
+    );
+    Ok(result)
+}
+
+/// Pushes the current branch to its remote. With `set_upstream`, the remote
+/// branch becomes the local branch's upstream.
+#[command]
+pub async fn git_push(
+    app: AppHandle,
+    request: Request<'_>,
+    state: State<'_, AppState>,
+    set_upstream: Option<bool>,
+) -> Result<GitStatus, GitError> {
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        let details = json!({ "set_upstream": set_upstream.unwrap_or(false) });
+        dry_run::plan_step("git_push", "HEAD", None, details);
+        return Err(GitError::new(
+            DRY_RUN_CODE,
+            &dry_run::planned_error("git_push"),
+        ));
+    }
+    if actor == Actor::Agent {
+        let target = run_git(|repo| {
+            Ok(format!(
+                "{} to {}",
+                current_branch(&repo)?,
+                default_remote(&repo)
+            ))
+        })
+        .await?;
+        authorize(&app, actor, Capability::GitPush, &target)
+            .await
+            .map_err(|e| GitError::new("PERMISSION_DENIED", &e))?;
+    }
+
+    let credentials = state.auth.git_credentials();
+    let (status, target) = run_git(move |repo| {
+        let branch_name = current_branch(&repo)?;
+        let remote_name = default_remote(&repo);
+        let mut remote = repo.find_remote(&remote_name)?;
+
+        let rejected = RefCell::new(Vec::new());
+        let mut callbacks = remote_callbacks(&repo, &app, &credentials, "push", &remote_name);
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(message) = status {
+                rejected
+                    .borrow_mut()
+                    .push(format!("{}: {}", refname, message));
+            }
+            Ok(())
+        });
+
+        let mut push_options = PushOptions::new();
+        push_options.remote_callbacks(callbacks);
+        let refspec = format!("refs/heads/{0}:refs/heads/{0}", branch_name);
+        remote.push(&[refspec.as_str()], Some(&mut push_options))?;
+
+        let rejected = rejected.take();
+        if !rejected.is_empty() {
+            return Err(GitError::new(
+                "PUSH_REJECTED",
+                &format!("Push was rejected: {}", rejected.join("; ")),
+            ));
+        }
+
+        if set_upstream.unwrap_or(false) {
+            let mut branch = repo.find_branch(&branch_name, BranchType::Local)?;
+            branch.set_upstream(Some(&format!("{}/{}", remote_name, branch_name)))?;
+        }
+
+        Ok((
+            read_status(&repo)?,
+            format!("{} to {}", branch_name, remote_name),
+        ))
+    })
+    .await?;
+    audit::record(actor, "git_push", &target, json!(null));
+    Ok(status)
+}
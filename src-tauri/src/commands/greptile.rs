@@ -1,12 +1,33 @@
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
-use tauri::command;
+use tauri::{command, State};
+use tokio::sync::Mutex;
+use tracing::warn;
+use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GreptileConfig {
+use super::auth::vault;
+use super::offline::{is_offline, offline_error};
+use super::redaction;
+use super::storage::{get_record, put_record};
+use crate::config::AppConfig;
+use crate::error::MightyError;
+use crate::http_client;
+use crate::secrets::Redactor;
+use crate::state::AppState;
+
+const DEFAULT_BASE_URL: &str = "https://api.greptile.com";
+/// Vault secret holding the key saved with `set_greptile_api_key`.
+const API_KEY_SECRET: &str = "greptile";
+
+/// Greptile settings resolved on the Rust side, so the API key never has to
+/// cross the IPC boundary.
+struct GreptileSettings {
+    client: reqwest::Client,
     api_key: String,
-    base_url: Option<String>,
+    base_url: String,
     max_results: Option<u32>,
+    github_token: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,18 +44,32 @@ pub struct SearchRequest {
     options: Option<SearchOptions>,
 }
 
+impl SearchRequest {
+    pub(crate) fn new(query: &str, max_results: u32) -> Self {
+        Self {
+            query: query.to_string(),
+            options: Some(SearchOptions {
+                case_sensitive: None,
+                use_regex: None,
+                include_tests: None,
+                max_results: Some(max_results),
+            }),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SearchResult {
-    file: String,
-    line_number: u32,
-    matched_text: String,
-    score: f64,
-    context: Vec<String>,
+    pub(crate) file: String,
+    pub(crate) line_number: u32,
+    pub(crate) matched_text: String,
+    pub(crate) score: f64,
+    pub(crate) context: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SearchResponse {
-    results: Vec<SearchResult>,
+    pub(crate) results: Vec<SearchResult>,
     metadata: SearchMetadata,
 }
 
@@ -43,29 +78,124 @@ pub struct SearchMetadata {
     total_results: usize,
     execution_time: u64,
     query: String,
+    /// For `preview_outgoing_payload`.
+    #[serde(default)]
+    request_id: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(into = "MightyError")]
 pub struct ErrorResponse {
     code: String,
     message: String,
     details: Option<String>,
 }
 
+impl ErrorResponse {
+    pub(crate) fn code(&self) -> &str {
+        &self.code
+    }
+}
+
+impl std::fmt::Display for ErrorResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+impl From<ErrorResponse> for MightyError {
+    fn from(e: ErrorResponse) -> Self {
+        MightyError::Service {
+            code: e.code,
+            message: e.message,
+            details: e.details,
+        }
+    }
+}
+
+fn vault_api_key() -> Option<String> {
+    vault().get(API_KEY_SECRET).unwrap_or_else(|e| {
+        warn!("{}", e);
+        None
+    })
+}
+
+/// Reads the API key from the credential vault, falling back to
+/// `AppConfig.greptile`, along with the other Greptile settings. Fails with
+/// the `OFFLINE` code in offline mode, since every caller goes on to reach
+/// Greptile.
+async fn greptile_settings(config: &Mutex<AppConfig>) -> Result<GreptileSettings, ErrorResponse> {
+    if is_offline() {
+        let e = offline_error();
+        return Err(ErrorResponse {
+            code: e.code().to_string(),
+            message: e.message().to_string(),
+            details: None,
+        });
+    }
+    let greptile = config.lock().await.greptile.clone();
+    let api_key = vault_api_key()
+        .or_else(|| greptile.as_ref().and_then(|g| g.api_key.clone()))
+        .filter(|key| !key.trim().is_empty())
+        .ok_or_else(|| ErrorResponse {
+            code: "NOT_CONFIGURED".to_string(),
+            message: "Greptile API key not configured".to_string(),
+            details: None,
+        })?;
+
+    let client = http_client::client("greptile").map_err(|e| ErrorResponse {
+        code: "HTTP_CLIENT_ERROR".to_string(),
+        message: "Failed to set up the HTTP client".to_string(),
+        details: Some(e),
+    })?;
+
+    Ok(GreptileSettings {
+        client,
+        api_key,
+        base_url: greptile
+            .as_ref()
+            .and_then(|g| g.base_url.clone())
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+        max_results: greptile.as_ref().and_then(|g| g.max_results),
+        github_token: greptile.and_then(|g| g.github_token),
+    })
+}
+
+/// Saves the Greptile API key to the credential vault, where it takes
+/// precedence over the key in the config file.
+#[command]
+pub async fn set_greptile_api_key(api_key: String) -> Result<(), ErrorResponse> {
+    let api_key = api_key.trim();
+    if api_key.is_empty() {
+        return Err(ErrorResponse {
+            code: "INVALID_API_KEY".to_string(),
+            message: "The API key is empty".to_string(),
+            details: None,
+        });
+    }
+    vault()
+        .set(API_KEY_SECRET, api_key)
+        .map_err(|e| ErrorResponse {
+            code: "KEYCHAIN_ERROR".to_string(),
+            message: "Failed to save the API key to the keychain".to_string(),
+            details: Some(e),
+        })
+}
+
 #[command]
 pub async fn greptile_search(
-    config: GreptileConfig,
     request: SearchRequest,
+    state: State<'_, AppState>,
 ) -> Result<SearchResponse, ErrorResponse> {
-    let client = reqwest::Client::new();
-    let base_url = config.base_url.unwrap_or_else(|| "https://api.greptile.com".to_string());
-    
+    let settings = greptile_settings(&state.config).await?;
+    let client = settings.client.clone();
+
     // Set up headers
     let mut headers = HeaderMap::new();
     headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
     headers.insert(
         AUTHORIZATION,
-        HeaderValue::from_str(&format!("Bearer {}", config.api_key))
+        HeaderValue::from_str(&format!("Bearer {}", settings.api_key))
             .map_err(|e| ErrorResponse {
                 code: "INVALID_API_KEY".to_string(),
                 message: "Invalid API key format".to_string(),
@@ -73,12 +203,13 @@ pub async fn greptile_search(
             })?
     );
 
-    // Prepare request body
+    // Prepare request body, redacted like everything else leaving the machine
+    let mut redactor = Redactor::new();
     let body = serde_json::json!({
-        "query": request.query,
+        "query": redactor.redact(&request.query),
         "maxResults": request.options.as_ref()
             .and_then(|opt| opt.max_results)
-            .or(config.max_results),
+            .or(settings.max_results),
         "options": {
             "caseSensitive": request.options.as_ref().and_then(|opt| opt.case_sensitive),
             "useRegex": request.options.as_ref().and_then(|opt| opt.use_regex),
@@ -86,10 +217,13 @@ pub async fn greptile_search(
         }
     });
 
+    let request_id = Uuid::new_v4().to_string();
+    redaction::remember(&request_id, "greptile", &body, redactor.redactions());
+
     // Make the request
     let start_time = std::time::Instant::now();
     let response = client
-        .post(format!("{}/search", base_url))
+        .post(format!("{}/search", settings.base_url))
         .headers(headers)
         .json(&body)
         .send()
@@ -120,21 +254,204 @@ pub async fn greptile_search(
             total_results: results.len(),
             execution_time: start_time.elapsed().as_millis() as u64,
             query: request.query,
+            request_id,
         },
     })
 }
 
+/// Storage key prefix for query sessions, keyed by Greptile session id.
+const SESSION_PREFIX: &str = "greptile_session:";
+
+fn default_remote() -> String {
+    "github".to_string()
+}
+
+fn default_branch() -> String {
+    "main".to_string()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueryRepository {
+    #[serde(default = "default_remote")]
+    remote: String,
+    /// `owner/name`
+    repository: String,
+    #[serde(default = "default_branch")]
+    branch: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueryMessage {
+    #[serde(default)]
+    id: Option<String>,
+    content: String,
+    /// "user" or "assistant"
+    role: String,
+}
+
+/// A piece of code Greptile based its answer on.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuerySource {
+    repository: String,
+    remote: String,
+    branch: String,
+    #[serde(alias = "filepath")]
+    file_path: String,
+    #[serde(alias = "linestart")]
+    line_start: Option<u32>,
+    #[serde(alias = "lineend")]
+    line_end: Option<u32>,
+    summary: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueryResponse {
+    /// Pass back with the next question to continue the conversation.
+    session_id: String,
+    message: String,
+    sources: Vec<QuerySource>,
+    /// For `preview_outgoing_payload`.
+    #[serde(default)]
+    request_id: String,
+}
+
+/// A conversation saved so follow-up questions only need the session id.
+#[derive(Debug, Serialize, Deserialize)]
+struct QuerySession {
+    repositories: Vec<QueryRepository>,
+    messages: Vec<QueryMessage>,
+    updated_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GreptileQueryReply {
+    message: String,
+    #[serde(default)]
+    sources: Vec<QuerySource>,
+}
+
+fn storage_error(e: impl std::fmt::Display) -> ErrorResponse {
+    ErrorResponse {
+        code: "STORAGE_ERROR".to_string(),
+        message: "Failed to access the saved Greptile session".to_string(),
+        details: Some(e.to_string()),
+    }
+}
+
+/// Asks Greptile a natural-language question about `repositories`. Passing
+/// the `session_id` of an earlier answer continues that conversation: its
+/// history and repositories are restored from storage, so only the new
+/// messages need to be sent.
+#[command]
+pub async fn greptile_query(
+    messages: Vec<QueryMessage>,
+    repositories: Option<Vec<QueryRepository>>,
+    session_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<QueryResponse, ErrorResponse> {
+    let settings = greptile_settings(&state.config).await?;
+    let session_id = session_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    let saved: Option<QuerySession> = get_record(&format!("{}{}", SESSION_PREFIX, session_id))
+        .map_err(storage_error)?
+        .and_then(|json| serde_json::from_str(&json).ok());
+
+    let (mut history, saved_repositories) = match saved {
+        Some(session) => (session.messages, session.repositories),
+        None => (Vec::new(), Vec::new()),
+    };
+    let repositories = repositories
+        .filter(|repositories| !repositories.is_empty())
+        .unwrap_or(saved_repositories);
+    if repositories.is_empty() {
+        return Err(ErrorResponse {
+            code: "NO_REPOSITORIES".to_string(),
+            message: "No repositories given for this Greptile session".to_string(),
+            details: None,
+        });
+    }
+    history.extend(messages.into_iter().map(|mut message| {
+        message.id.get_or_insert_with(|| Uuid::new_v4().to_string());
+        message
+    }));
+
+    // The saved history keeps what was typed; only the copy sent is redacted
+    let mut redactor = Redactor::new();
+    let mut sent_history = serde_json::to_value(&history).map_err(storage_error)?;
+    redactor.redact_json(&mut sent_history);
+    let body = serde_json::json!({
+        "messages": sent_history,
+        "repositories": repositories,
+        "sessionId": session_id,
+        "stream": false,
+    });
+    let request_id = Uuid::new_v4().to_string();
+    redaction::remember(&request_id, "greptile", &body, redactor.redactions());
+
+    let client = settings.client.clone();
+    let mut request = client
+        .post(format!("{}/v2/query", settings.base_url))
+        .bearer_auth(&settings.api_key)
+        .json(&body);
+    if let Some(token) = &settings.github_token {
+        request = request.header("X-GitHub-Token", token);
+    }
+
+    let response = request.send().await.map_err(|e| ErrorResponse {
+        code: "REQUEST_FAILED".to_string(),
+        message: "Failed to send request to Greptile API".to_string(),
+        details: Some(e.to_string()),
+    })?;
+
+    if !response.status().is_success() {
+        return Err(ErrorResponse {
+            code: "API_ERROR".to_string(),
+            message: format!("Greptile API error: {}", response.status()),
+            details: Some(response.text().await.unwrap_or_default()),
+        });
+    }
+
+    let reply: GreptileQueryReply = response.json().await.map_err(|e| ErrorResponse {
+        code: "PARSE_ERROR".to_string(),
+        message: "Failed to parse API response".to_string(),
+        details: Some(e.to_string()),
+    })?;
+
+    // Keep the answer in the history so the next question has its context
+    let message = redactor.restore(&reply.message);
+    history.push(QueryMessage {
+        id: Some(Uuid::new_v4().to_string()),
+        content: message.clone(),
+        role: "assistant".to_string(),
+    });
+    let session = QuerySession {
+        repositories,
+        messages: history,
+        updated_at: Utc::now().timestamp_millis(),
+    };
+    let json = serde_json::to_string(&session).map_err(storage_error)?;
+    put_record(&format!("{}{}", SESSION_PREFIX, session_id), &json).map_err(storage_error)?;
+
+    Ok(QueryResponse {
+        session_id,
+        message,
+        sources: reply.sources,
+        request_id,
+    })
+}
+
 // Test connection to Greptile API
 #[command]
-pub async fn test_greptile_connection(config: GreptileConfig) -> Result<bool, ErrorResponse> {
-    let client = reqwest::Client::new();
-    let base_url = config.base_url.unwrap_or_else(|| "https://api.greptile.com".to_string());
+pub async fn test_greptile_connection(
+    state: State<'_, AppState>,
+) -> Result<bool, ErrorResponse> {
+    let settings = greptile_settings(&state.config).await?;
+    let client = settings.client.clone();
 
     let response = client
-        .get(format!("{}/ping", base_url))
+        .get(format!("{}/ping", settings.base_url))
         .header(
             AUTHORIZATION,
-            format!("Bearer {}", config.api_key)
+            format!("Bearer {}", settings.api_key)
         )
         .send()
         .await
@@ -0,0 +1,711 @@
+// src/commands/jobs.rs
+
+use chrono::Utc;
+use futures::FutureExt;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+use tauri::async_runtime::JoinHandle;
+use tauri::{command, AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::fs::resolve_workspace_path;
+use super::git;
+use super::notifications::{notify, NotificationLevel};
+use super::storage::{
+    backup_storage, compact_storage, delete_record, put_record, records_with_prefix,
+};
+use crate::activity;
+use crate::config::{JobsConfig, APP_IDENTIFIER};
+use crate::context::context;
+use crate::context::filters::SkipReason;
+use crate::logging;
+use crate::state::AppState;
+
+const STORAGE_PREFIX: &str = "job:";
+const DEFAULT_MAX_CONCURRENT: usize = 2;
+/// Finished jobs kept for `list_jobs`; older ones are forgotten.
+const FINISHED_JOBS_KEPT: usize = 100;
+const BACKUP_DIR_NAME: &str = "backups";
+const BACKUP_PREFIX: &str = "storage-";
+
+static JOBS: Lazy<Mutex<HashMap<String, Job>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static MAX_CONCURRENT: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_CONCURRENT);
+/// Set once storage is up; jobs queued earlier wait for it.
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+/// The work a job does, with its parameters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobSpec {
+    /// Embeds files into the code context, relative to the workspace root.
+    IndexFiles { paths: Vec<String> },
+    /// Copies the database, by default into the app's data directory,
+    /// where the oldest backups beyond `keep` are then deleted.
+    BackupStorage {
+        destination: Option<String>,
+        #[serde(default)]
+        keep: Option<usize>,
+    },
+    /// Fetches a git remote, by default the current branch's.
+    GitFetch { remote: Option<String> },
+    /// Indexes a freshly opened workspace's files in order, skipping those
+    /// already indexed and pausing between files to keep CPU use near
+    /// `cpu_percent` of a core.
+    WarmUpContext { paths: Vec<String>, cpu_percent: u8 },
+    /// Compacts the database, reclaiming space from deleted records.
+    CompactStorage,
+    /// Compacts the code context's tables and updates their indices.
+    OptimizeIndex,
+    /// Deletes log files past their retention.
+    RotateLogs,
+}
+
+impl JobSpec {
+    fn kind(&self) -> &'static str {
+        match self {
+            JobSpec::IndexFiles { .. } => "index_files",
+            JobSpec::BackupStorage { .. } => "backup_storage",
+            JobSpec::GitFetch { .. } => "git_fetch",
+            JobSpec::WarmUpContext { .. } => "warm_up_context",
+            JobSpec::CompactStorage => "compact_storage",
+            JobSpec::OptimizeIndex => "optimize_index",
+            JobSpec::RotateLogs => "rotate_logs",
+        }
+    }
+
+    /// Backups and fetches work on one database or repository, so only one
+    /// of each runs at a time; so does warming up, which reopening a
+    /// workspace would otherwise start twice.
+    fn exclusive(&self) -> bool {
+        !matches!(self, JobSpec::IndexFiles { .. })
+    }
+
+    /// Housekeeping that runs on a schedule; finishing it isn't worth a
+    /// notification.
+    fn is_maintenance(&self) -> bool {
+        matches!(
+            self,
+            JobSpec::CompactStorage | JobSpec::OptimizeIndex | JobSpec::RotateLogs
+        )
+    }
+
+    fn label(&self) -> String {
+        match self {
+            JobSpec::IndexFiles { paths } if paths.len() == 1 => format!("Index {}", paths[0]),
+            JobSpec::IndexFiles { paths } => format!("Index {} files", paths.len()),
+            JobSpec::BackupStorage { .. } => "Back up storage".to_string(),
+            JobSpec::GitFetch {
+                remote: Some(remote),
+            } => format!("Fetch {}", remote),
+            JobSpec::GitFetch { remote: None } => "Fetch".to_string(),
+            JobSpec::WarmUpContext { paths, .. } => {
+                format!("Warm up code context ({} files)", paths.len())
+            }
+            JobSpec::CompactStorage => "Compact storage".to_string(),
+            JobSpec::OptimizeIndex => "Optimize the code index".to_string(),
+            JobSpec::RotateLogs => "Rotate logs".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub(crate) fn is_finished(self) -> bool {
+        !matches!(self, JobState::Queued | JobState::Running)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobProgress {
+    pub current: u64,
+    /// `None` while the amount of work isn't known.
+    pub total: Option<u64>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub id: String,
+    pub label: String,
+    pub spec: JobSpec,
+    pub priority: JobPriority,
+    pub state: JobState,
+    pub progress: JobProgress,
+    /// What the job produced, once completed.
+    pub result: Option<Value>,
+    pub error: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    pub created_at: i64,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ProgressEvent<'a> {
+    id: &'a str,
+    progress: &'a JobProgress,
+}
+
+struct Job {
+    info: JobInfo,
+    task: Option<JoinHandle<()>>,
+}
+
+/// Handed to a running job to report how far along it is.
+struct JobContext {
+    id: String,
+}
+
+impl JobContext {
+    /// Waits while the user is typing or a completion is in flight, showing
+    /// the job as paused meanwhile.
+    async fn yield_to_user(&self, current: u64, total: Option<u64>) {
+        if activity::is_active() {
+            self.progress(current, total, Some("Paused while you work".to_string()));
+            activity::wait_until_idle().await;
+        }
+    }
+
+    fn progress(&self, current: u64, total: Option<u64>, message: Option<String>) {
+        let mut jobs = JOBS.lock();
+        let Some(job) = jobs.get_mut(&self.id) else {
+            return;
+        };
+        job.info.progress = JobProgress {
+            current,
+            total,
+            message,
+        };
+        if let Some(app) = APP.get() {
+            let event = ProgressEvent {
+                id: &self.id,
+                progress: &job.info.progress,
+            };
+            if let Err(e) = app.emit("job-progress", &event) {
+                warn!("Failed to emit job-progress: {}", e);
+            }
+        }
+    }
+}
+
+fn storage_key(id: &str) -> String {
+    format!("{}{}", STORAGE_PREFIX, id)
+}
+
+/// Saves the job and tells the frontend its state changed.
+fn publish(info: &JobInfo) {
+    let result = serde_json::to_string(info)
+        .map_err(|e| e.to_string())
+        .and_then(|json| put_record(&storage_key(&info.id), &json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("Failed to save job {}: {}", info.id, e);
+    }
+    if let Some(app) = APP.get() {
+        if let Err(e) = app.emit("job-status", info) {
+            warn!("Failed to emit job-status: {}", e);
+        }
+    }
+}
+
+/// Forgets the oldest finished jobs beyond `FINISHED_JOBS_KEPT`.
+fn prune(jobs: &mut HashMap<String, Job>) {
+    let mut finished: Vec<(i64, String)> = jobs
+        .values()
+        .filter(|job| job.info.state.is_finished())
+        .map(|job| (job.info.finished_at.unwrap_or(0), job.info.id.clone()))
+        .collect();
+    if finished.len() <= FINISHED_JOBS_KEPT {
+        return;
+    }
+    finished.sort();
+    for (_, id) in &finished[..finished.len() - FINISHED_JOBS_KEPT] {
+        jobs.remove(id);
+        if let Err(e) = delete_record(&storage_key(id)) {
+            warn!("Failed to delete job {}: {}", id, e);
+        }
+    }
+}
+
+/// Starts queued jobs, highest priority and then oldest first, while there
+/// is room under the concurrency limit.
+fn schedule() {
+    if APP.get().is_none() {
+        return;
+    }
+    let mut jobs = JOBS.lock();
+    loop {
+        let running: Vec<&JobSpec> = jobs
+            .values()
+            .filter(|job| job.info.state == JobState::Running)
+            .map(|job| &job.info.spec)
+            .collect();
+        if running.len() >= MAX_CONCURRENT.load(Ordering::Relaxed) {
+            return;
+        }
+        let next = jobs
+            .values()
+            .filter(|job| job.info.state == JobState::Queued)
+            .filter(|job| {
+                !job.info.spec.exclusive()
+                    || !running
+                        .iter()
+                        .any(|spec| spec.kind() == job.info.spec.kind())
+            })
+            .min_by_key(|job| (Reverse(job.info.priority), job.info.created_at))
+            .map(|job| job.info.id.clone());
+        let Some(id) = next else {
+            return;
+        };
+
+        let job = jobs.get_mut(&id).expect("picked from the map");
+        job.info.state = JobState::Running;
+        job.info.started_at = Some(Utc::now().timestamp_millis());
+        publish(&job.info);
+        let context = JobContext { id };
+        let spec = job.info.spec.clone();
+        job.task = Some(tauri::async_runtime::spawn(run(context, spec)));
+    }
+}
+
+async fn run(context: JobContext, spec: JobSpec) {
+    let result = AssertUnwindSafe(execute(&context, spec))
+        .catch_unwind()
+        .await
+        .unwrap_or_else(|_| Err("The job panicked".to_string()));
+
+    let finished = {
+        let mut jobs = JOBS.lock();
+        // A cancelled job has already been settled
+        let finished = jobs
+            .get_mut(&context.id)
+            .filter(|job| job.info.state == JobState::Running)
+            .map(|job| {
+                job.task = None;
+                job.info.finished_at = Some(Utc::now().timestamp_millis());
+                match result {
+                    Ok(value) => {
+                        job.info.state = JobState::Completed;
+                        job.info.result = Some(value);
+                    }
+                    Err(e) => {
+                        warn!("Job {} ({}) failed: {}", context.id, job.info.label, e);
+                        job.info.state = JobState::Failed;
+                        job.info.error = Some(e);
+                    }
+                }
+                publish(&job.info);
+                job.info.clone()
+            });
+        prune(&mut jobs);
+        finished
+    };
+    if let Some(info) = finished {
+        notify_finished(&info);
+    }
+    schedule();
+}
+
+/// Lets the user know how a job ended; an indexing job that skipped files
+/// counts as a warning.
+fn notify_finished(info: &JobInfo) {
+    let Some(app) = APP.get() else {
+        return;
+    };
+    match info.state {
+        JobState::Completed if info.spec.is_maintenance() => {}
+        JobState::Completed => {
+            let skipped = info
+                .result
+                .as_ref()
+                .and_then(|result| result["failed"].as_array())
+                .map_or(0, Vec::len);
+            if skipped > 0 {
+                notify(
+                    app,
+                    NotificationLevel::Warning,
+                    "jobs",
+                    "Some files could not be indexed",
+                    Some(format!("{}: {} file(s) skipped", info.label, skipped)),
+                );
+            } else {
+                notify(
+                    app,
+                    NotificationLevel::Success,
+                    "jobs",
+                    "Job finished",
+                    Some(info.label.clone()),
+                );
+            }
+        }
+        JobState::Failed => {
+            let title = match info.spec {
+                JobSpec::IndexFiles { .. } | JobSpec::WarmUpContext { .. } => "Indexing failed",
+                _ => "Job failed",
+            };
+            let body = format!(
+                "{}: {}",
+                info.label,
+                info.error.as_deref().unwrap_or_default()
+            );
+            notify(app, NotificationLevel::Error, "jobs", title, Some(body));
+        }
+        _ => {}
+    }
+}
+
+async fn execute(context: &JobContext, spec: JobSpec) -> Result<Value, String> {
+    match spec {
+        JobSpec::IndexFiles { paths } => index_files(context, paths).await,
+        JobSpec::WarmUpContext { paths, cpu_percent } => warm_up(context, paths, cpu_percent).await,
+        JobSpec::BackupStorage { destination, keep } => backup(context, destination, keep).await,
+        JobSpec::CompactStorage => {
+            tokio::task::spawn_blocking(compact_storage)
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| e.to_string())?;
+            Ok(json!({}))
+        }
+        JobSpec::OptimizeIndex => {
+            context::optimize_index().await?;
+            Ok(json!({}))
+        }
+        JobSpec::RotateLogs => {
+            let pruned = logging::prune_log_files()?;
+            Ok(json!({ "deleted": pruned }))
+        }
+        JobSpec::GitFetch { remote } => {
+            context.yield_to_user(0, None).await;
+            let app = APP.get().expect("jobs only run once initialized").clone();
+            let credentials = app.state::<AppState>().auth.git_credentials();
+            // Transfer progress goes out as git-progress events
+            let status = git::fetch(app, credentials, remote)
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(json!({ "ahead": status.ahead, "behind": status.behind }))
+        }
+    }
+}
+
+/// Reads and indexes one file, returning why it was left out, if it was.
+async fn index_file(path: &str) -> Result<Option<SkipReason>, String> {
+    let full_path = resolve_workspace_path(path).map_err(|e| e.message().to_string())?;
+    let content = tokio::fs::read_to_string(&full_path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    context::index_file(path, &content).await
+}
+
+/// A file that can't be read or embedded is reported as failed, and one the
+/// content filters leave out as skipped; the job fails only when no file
+/// could be indexed.
+async fn index_files(context: &JobContext, paths: Vec<String>) -> Result<Value, String> {
+    let total = paths.len() as u64;
+    let mut indexed = 0;
+    let mut skipped = Vec::new();
+    let mut failed = Vec::new();
+    for (done, path) in paths.into_iter().enumerate() {
+        context.yield_to_user(done as u64, Some(total)).await;
+        context.progress(done as u64, Some(total), Some(path.clone()));
+        match index_file(&path).await {
+            Ok(None) => indexed += 1,
+            Ok(Some(reason)) => skipped.push(json!({ "path": path, "reason": reason })),
+            Err(e) => failed.push(json!({ "path": path, "error": e })),
+        }
+    }
+    context.progress(total, Some(total), None);
+
+    if indexed == 0 && !failed.is_empty() {
+        return Err(failed[0]["error"].as_str().unwrap_or_default().to_string());
+    }
+    Ok(json!({ "indexed": indexed, "skipped": skipped, "failed": failed }))
+}
+
+/// Like `index_files`, but files already in the context are skipped and
+/// nothing failing fails the job; it only runs to get ahead of the user.
+async fn warm_up(
+    context: &JobContext,
+    paths: Vec<String>,
+    cpu_percent: u8,
+) -> Result<Value, String> {
+    let total = paths.len() as u64;
+    let cpu_percent = u32::from(cpu_percent.clamp(1, 100));
+    let mut indexed = 0;
+    let mut already_indexed = 0;
+    let mut skipped = Vec::new();
+    let mut failed = Vec::new();
+    for (done, path) in paths.into_iter().enumerate() {
+        context.yield_to_user(done as u64, Some(total)).await;
+        context.progress(done as u64, Some(total), Some(path.clone()));
+        if context::is_file_in_context(path.clone()).await? {
+            already_indexed += 1;
+            continue;
+        }
+
+        let started = Instant::now();
+        match index_file(&path).await {
+            Ok(None) => indexed += 1,
+            Ok(Some(reason)) => skipped.push(json!({ "path": path, "reason": reason })),
+            Err(e) => failed.push(json!({ "path": path, "error": e })),
+        }
+        // Idle long enough that the time spent working is `cpu_percent` of the total
+        let pause = started.elapsed() * (100 - cpu_percent) / cpu_percent;
+        tokio::time::sleep(pause).await;
+    }
+    context.progress(total, Some(total), None);
+    Ok(json!({
+        "indexed": indexed,
+        "already_indexed": already_indexed,
+        "skipped": skipped,
+        "failed": failed,
+    }))
+}
+
+fn backup_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(APP_IDENTIFIER)
+        .join(BACKUP_DIR_NAME)
+}
+
+/// Deletes the oldest backups in the default directory beyond the newest
+/// `keep`, returning how many.
+fn prune_backups(keep: usize) -> Result<usize, String> {
+    let dir = backup_dir();
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with(BACKUP_PREFIX))
+        })
+        .collect();
+    if backups.len() <= keep {
+        return Ok(0);
+    }
+    // Names end in a sortable timestamp
+    backups.sort();
+    let expired = backups.len() - keep;
+    for path in &backups[..expired] {
+        std::fs::remove_dir_all(path)
+            .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+    }
+    Ok(expired)
+}
+
+async fn backup(
+    context: &JobContext,
+    destination: Option<String>,
+    keep: Option<usize>,
+) -> Result<Value, String> {
+    let default_destination = destination.is_none();
+    let destination = match destination {
+        Some(destination) => PathBuf::from(destination),
+        None => backup_dir().join(format!(
+            "{}{}",
+            BACKUP_PREFIX,
+            Utc::now().format("%Y%m%d-%H%M%S")
+        )),
+    };
+    context.progress(0, None, Some(destination.to_string_lossy().to_string()));
+    if let Some(parent) = destination.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let target = destination.clone();
+    tokio::task::spawn_blocking(move || backup_storage(&target))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    let pruned = match keep {
+        Some(keep) if default_destination => prune_backups(keep.max(1))?,
+        _ => 0,
+    };
+    Ok(json!({ "path": destination, "deleted": pruned }))
+}
+
+/// Applies the `[jobs]` settings; a lower limit lets running jobs finish.
+pub(crate) fn configure(config: Option<&JobsConfig>) {
+    let max_concurrent = config
+        .and_then(|jobs| jobs.max_concurrent)
+        .unwrap_or(DEFAULT_MAX_CONCURRENT)
+        .max(1);
+    if MAX_CONCURRENT.swap(max_concurrent, Ordering::Relaxed) < max_concurrent {
+        schedule();
+    }
+}
+
+/// Restores jobs saved by the previous run and starts the queue; called once
+/// storage is up. Jobs that were running when the app closed start over.
+pub(crate) fn initialize_jobs(app: AppHandle) {
+    let saved = match records_with_prefix(STORAGE_PREFIX) {
+        Ok(records) => records,
+        Err(e) => {
+            warn!("Failed to load saved jobs: {}", e);
+            Vec::new()
+        }
+    };
+
+    {
+        let mut jobs = JOBS.lock();
+        let mut restarted = 0;
+        for (key, json) in saved {
+            let Ok(mut info) = serde_json::from_str::<JobInfo>(&json) else {
+                warn!("Dropping unreadable job record {}", key);
+                let _ = delete_record(&key);
+                continue;
+            };
+            if info.state == JobState::Running {
+                info.state = JobState::Queued;
+                info.started_at = None;
+                info.progress = JobProgress::default();
+                restarted += 1;
+            }
+            jobs.entry(info.id.clone())
+                .or_insert(Job { info, task: None });
+        }
+        if restarted > 0 {
+            info!("Restarting {} interrupted job(s)", restarted);
+        }
+        prune(&mut jobs);
+    }
+
+    if APP.set(app).is_err() {
+        return;
+    }
+    // Jobs queued before storage was up haven't been saved yet
+    for job in JOBS.lock().values() {
+        publish(&job.info);
+    }
+    schedule();
+}
+
+/// Adds a job to the queue; progress and state changes arrive as
+/// `job-progress` and `job-status` events.
+pub(crate) fn enqueue(spec: JobSpec, priority: JobPriority) -> JobInfo {
+    let info = JobInfo {
+        id: Uuid::new_v4().to_string(),
+        label: spec.label(),
+        spec,
+        priority,
+        state: JobState::Queued,
+        progress: JobProgress::default(),
+        result: None,
+        error: None,
+        created_at: Utc::now().timestamp_millis(),
+        started_at: None,
+        finished_at: None,
+    };
+    if APP.get().is_some() {
+        publish(&info);
+    }
+    JOBS.lock().insert(
+        info.id.clone(),
+        Job {
+            info: info.clone(),
+            task: None,
+        },
+    );
+    schedule();
+    info
+}
+
+/// The state of job `id`, if it is still known.
+pub(crate) fn job_state(id: &str) -> Option<JobState> {
+    JOBS.lock().get(id).map(|job| job.info.state)
+}
+
+/// Number of jobs queued and running.
+pub(crate) fn pending_jobs() -> (usize, usize) {
+    let jobs = JOBS.lock();
+    let count = |state: JobState| jobs.values().filter(|job| job.info.state == state).count();
+    (count(JobState::Queued), count(JobState::Running))
+}
+
+#[command]
+pub async fn enqueue_job(spec: JobSpec, priority: Option<JobPriority>) -> Result<JobInfo, String> {
+    if let JobSpec::IndexFiles { paths } = &spec {
+        if paths.is_empty() {
+            return Err("No files to index".to_string());
+        }
+    }
+    Ok(enqueue(spec, priority.unwrap_or_default()))
+}
+
+/// Jobs in the order they will run or ran: running first, then queued by
+/// priority, then finished jobs newest first. Finished jobs are left out
+/// unless `include_finished` is set.
+#[command]
+pub async fn list_jobs(include_finished: Option<bool>) -> Result<Vec<JobInfo>, String> {
+    let include_finished = include_finished.unwrap_or(false);
+    let mut infos: Vec<JobInfo> = JOBS
+        .lock()
+        .values()
+        .filter(|job| include_finished || !job.info.state.is_finished())
+        .map(|job| job.info.clone())
+        .collect();
+    infos.sort_by_key(|info| match info.state {
+        JobState::Running => (0, Reverse(0), info.started_at.unwrap_or(0)),
+        JobState::Queued => (1, Reverse(info.priority as i64), info.created_at),
+        _ => (2, Reverse(0), -info.finished_at.unwrap_or(0)),
+    });
+    Ok(infos)
+}
+
+/// Cancels a queued or running job. A running job stops at its next await
+/// point; work already handed to a blocking thread (a fetch or backup in
+/// progress) completes, but its result is discarded.
+#[command]
+pub async fn cancel_job(id: String) -> Result<JobInfo, String> {
+    let info = {
+        let mut jobs = JOBS.lock();
+        let job = jobs
+            .get_mut(&id)
+            .ok_or_else(|| format!("No job with id {}", id))?;
+        if job.info.state.is_finished() {
+            return Err(format!("Job {} has already finished", id));
+        }
+        if let Some(task) = job.task.take() {
+            task.abort();
+        }
+        job.info.state = JobState::Cancelled;
+        job.info.finished_at = Some(Utc::now().timestamp_millis());
+        publish(&job.info);
+        let info = job.info.clone();
+        prune(&mut jobs);
+        info
+    };
+    schedule();
+    Ok(info)
+}
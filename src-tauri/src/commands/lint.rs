@@ -0,0 +1,334 @@
+// src/commands/lint.rs
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{command, AppHandle};
+
+use super::diagnostics::{publish_diagnostics, Diagnostic, DiagnosticSeverity};
+use super::exec::{find_executable, run_tool, ExecError, ExecOutput};
+use super::fs::{get_project_root, resolve_workspace_path, workspace_relative_path};
+use super::trust::ensure_trusted;
+
+/// Clippy may have to build the whole crate first, so it gets more time.
+const LINT_TIMEOUT: Duration = Duration::from_secs(120);
+const CLIPPY_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintTool {
+    Eslint,
+    Clippy,
+    Ruff,
+}
+
+impl LintTool {
+    fn name(self) -> &'static str {
+        match self {
+            LintTool::Eslint => "eslint",
+            LintTool::Clippy => "clippy",
+            LintTool::Ruff => "ruff",
+        }
+    }
+
+    fn for_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(LintTool::Clippy),
+            "py" | "pyi" => Some(LintTool::Ruff),
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" | "vue" => {
+                Some(LintTool::Eslint)
+            }
+            _ => None,
+        }
+    }
+
+    /// Picks a tool from the project files at the workspace root.
+    fn for_workspace(root: &Path) -> Option<Self> {
+        if root.join("Cargo.toml").exists() {
+            Some(LintTool::Clippy)
+        } else if root.join("package.json").exists() {
+            Some(LintTool::Eslint)
+        } else if ["pyproject.toml", "ruff.toml", ".ruff.toml", "setup.py"]
+            .iter()
+            .any(|file| root.join(file).exists())
+        {
+            Some(LintTool::Ruff)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintResult {
+    pub tool: LintTool,
+    pub scope: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub errors: usize,
+    pub warnings: usize,
+    pub duration_ms: u64,
+}
+
+fn required_executable(name: &str) -> Result<PathBuf, ExecError> {
+    find_executable(name)
+        .ok_or_else(|| ExecError::new("TOOL_NOT_FOUND", &format!("{} was not found on PATH", name)))
+}
+
+fn lint_failed(tool: LintTool, output: &ExecOutput) -> ExecError {
+    let details = if output.stderr.trim().is_empty() {
+        output.stdout.trim()
+    } else {
+        output.stderr.trim()
+    };
+    ExecError::with_details(
+        "LINT_FAILED",
+        &format!("{} could not lint the target", tool.name()),
+        details,
+    )
+}
+
+/// Makes a tool-reported path workspace-relative, resolving it against `base`.
+fn relative_to_workspace(base: &Path, reported: &str) -> String {
+    workspace_relative_path(&base.join(reported))
+}
+
+/// Cargo reports paths relative to its workspace root, which may be any
+/// ancestor of the crate directory.
+fn cargo_path(crate_dir: &Path, reported: &str) -> String {
+    crate_dir
+        .ancestors()
+        .map(|dir| dir.join(reported))
+        .find(|path| path.exists())
+        .map(|path| workspace_relative_path(&path))
+        .unwrap_or_else(|| reported.to_string())
+}
+
+fn usize_at(value: &Value, key: &str) -> Option<usize> {
+    value[key].as_u64().map(|n| n as usize)
+}
+
+fn parse_eslint(stdout: &str, base: &Path) -> Option<Vec<Diagnostic>> {
+    let files: Vec<Value> = serde_json::from_str(stdout).ok()?;
+    let mut diagnostics = Vec::new();
+    for file in &files {
+        let path = relative_to_workspace(base, file["filePath"].as_str().unwrap_or_default());
+        for message in file["messages"].as_array().into_iter().flatten() {
+            diagnostics.push(Diagnostic {
+                path: path.clone(),
+                line: usize_at(message, "line").unwrap_or(1),
+                column: usize_at(message, "column").unwrap_or(1),
+                end_line: usize_at(message, "endLine"),
+                end_column: usize_at(message, "endColumn"),
+                severity: if message["severity"].as_u64() == Some(2) {
+                    DiagnosticSeverity::Error
+                } else {
+                    DiagnosticSeverity::Warning
+                },
+                message: message["message"].as_str().unwrap_or_default().to_string(),
+                code: message["ruleId"].as_str().map(String::from),
+                source: "eslint".to_string(),
+            });
+        }
+    }
+    Some(diagnostics)
+}
+
+fn parse_clippy(stdout: &str, crate_dir: &Path) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for line in stdout.lines() {
+        let Ok(record) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if record["reason"] != "compiler-message" {
+            continue;
+        }
+        let message = &record["message"];
+        let severity = match message["level"].as_str() {
+            Some("error") | Some("error: internal compiler error") => DiagnosticSeverity::Error,
+            Some("warning") => DiagnosticSeverity::Warning,
+            Some("note") => DiagnosticSeverity::Info,
+            Some("help") => DiagnosticSeverity::Hint,
+            _ => continue,
+        };
+        // Summary lines like "aborting due to 2 previous errors" have no spans
+        let Some(span) = message["spans"]
+            .as_array()
+            .and_then(|spans| spans.iter().find(|s| s["is_primary"] == true))
+        else {
+            continue;
+        };
+
+        diagnostics.push(Diagnostic {
+            path: cargo_path(crate_dir, span["file_name"].as_str().unwrap_or_default()),
+            line: usize_at(span, "line_start").unwrap_or(1),
+            column: usize_at(span, "column_start").unwrap_or(1),
+            end_line: usize_at(span, "line_end"),
+            end_column: usize_at(span, "column_end"),
+            severity,
+            message: message["message"].as_str().unwrap_or_default().to_string(),
+            code: message["code"]["code"].as_str().map(String::from),
+            source: "clippy".to_string(),
+        });
+    }
+    diagnostics
+}
+
+fn parse_ruff(stdout: &str, base: &Path) -> Option<Vec<Diagnostic>> {
+    let items: Vec<Value> = serde_json::from_str(stdout).ok()?;
+    Some(
+        items
+            .iter()
+            .map(|item| {
+                let code = item["code"].as_str().map(String::from);
+                Diagnostic {
+                    path: relative_to_workspace(
+                        base,
+                        item["filename"].as_str().unwrap_or_default(),
+                    ),
+                    line: usize_at(&item["location"], "row").unwrap_or(1),
+                    column: usize_at(&item["location"], "column").unwrap_or(1),
+                    end_line: usize_at(&item["end_location"], "row"),
+                    end_column: usize_at(&item["end_location"], "column"),
+                    // Ruff has no severities; only syntax errors come without a rule code
+                    severity: if code.is_none() {
+                        DiagnosticSeverity::Error
+                    } else {
+                        DiagnosticSeverity::Warning
+                    },
+                    message: item["message"].as_str().unwrap_or_default().to_string(),
+                    code,
+                    source: "ruff".to_string(),
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Directory of the nearest Cargo.toml at or above `start`, within the workspace.
+fn cargo_dir(start: &Path, root: &Path) -> PathBuf {
+    start
+        .ancestors()
+        .take_while(|dir| dir.starts_with(root))
+        .find(|dir| dir.join("Cargo.toml").is_file())
+        .unwrap_or(root)
+        .to_path_buf()
+}
+
+async fn run_eslint(target: &Path, root: &Path) -> Result<Vec<Diagnostic>, ExecError> {
+    let program = required_executable("eslint")?;
+    let args = vec![
+        "--format".to_string(),
+        "json".to_string(),
+        target.to_string_lossy().to_string(),
+    ];
+    let output = run_tool(&program, &args, root, None, LINT_TIMEOUT).await?;
+    // Exit code 1 means lint errors were found; anything else is a failure
+    match output.exit_code {
+        Some(0) | Some(1) => {
+            parse_eslint(&output.stdout, root).ok_or_else(|| lint_failed(LintTool::Eslint, &output))
+        }
+        _ => Err(lint_failed(LintTool::Eslint, &output)),
+    }
+}
+
+async fn run_ruff(target: &Path, root: &Path) -> Result<Vec<Diagnostic>, ExecError> {
+    let program = required_executable("ruff")?;
+    let args = vec![
+        "check".to_string(),
+        "--output-format".to_string(),
+        "json".to_string(),
+        "--exit-zero".to_string(),
+        target.to_string_lossy().to_string(),
+    ];
+    let output = run_tool(&program, &args, root, None, LINT_TIMEOUT).await?;
+    if !output.success() {
+        return Err(lint_failed(LintTool::Ruff, &output));
+    }
+    parse_ruff(&output.stdout, root).ok_or_else(|| lint_failed(LintTool::Ruff, &output))
+}
+
+async fn run_clippy(target: &Path, root: &Path) -> Result<Vec<Diagnostic>, ExecError> {
+    let program = required_executable("cargo")?;
+    let start = if target.is_dir() {
+        target
+    } else {
+        target.parent().unwrap_or(root)
+    };
+    let crate_dir = cargo_dir(start, root);
+    let args = vec![
+        "clippy".to_string(),
+        "--message-format=json".to_string(),
+        "--quiet".to_string(),
+    ];
+    let output = run_tool(&program, &args, &crate_dir, None, CLIPPY_TIMEOUT).await?;
+
+    let mut diagnostics = parse_clippy(&output.stdout, &crate_dir);
+    if !output.success() && diagnostics.is_empty() {
+        return Err(lint_failed(LintTool::Clippy, &output));
+    }
+    // Clippy always checks the whole crate; narrow to the requested file
+    if target.is_file() {
+        let wanted = workspace_relative_path(target);
+        diagnostics.retain(|d| d.path == wanted);
+    }
+    Ok(diagnostics)
+}
+
+/// Lints a file or directory (or the whole workspace when `path` is omitted)
+/// with eslint, clippy or ruff, and emits the results as a
+/// `diagnostics-updated` event. The tool is inferred when not given.
+#[command]
+pub async fn run_linter(
+    app: AppHandle,
+    path: Option<String>,
+    tool: Option<LintTool>,
+) -> Result<LintResult, ExecError> {
+    // Linters load the workspace's plugins and build scripts
+    ensure_trusted("running tasks").map_err(|e| ExecError::new("WORKSPACE_UNTRUSTED", &e))?;
+    let root = get_project_root();
+    let target = match &path {
+        Some(path) => resolve_workspace_path(path)?,
+        None => root.clone(),
+    };
+
+    let tool = tool
+        .or_else(|| {
+            if target.is_file() {
+                LintTool::for_path(&target)
+            } else {
+                LintTool::for_workspace(&target)
+            }
+        })
+        .ok_or_else(|| {
+            ExecError::new(
+                "UNSUPPORTED_TARGET",
+                "No linter applies to this target; pass a tool explicitly",
+            )
+        })?;
+
+    let started = std::time::Instant::now();
+    let diagnostics = match tool {
+        LintTool::Eslint => run_eslint(&target, &root).await?,
+        LintTool::Clippy => run_clippy(&target, &root).await?,
+        LintTool::Ruff => run_ruff(&target, &root).await?,
+    };
+
+    let scope = path.as_ref().map(|_| workspace_relative_path(&target));
+    publish_diagnostics(&app, tool.name(), scope.as_deref(), &diagnostics);
+
+    let count = |severity| {
+        diagnostics
+            .iter()
+            .filter(|d| d.severity == severity)
+            .count()
+    };
+    Ok(LintResult {
+        tool,
+        errors: count(DiagnosticSeverity::Error),
+        warnings: count(DiagnosticSeverity::Warning),
+        scope,
+        diagnostics,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
@@ -0,0 +1,47 @@
+// src/commands/logs.rs
+
+use tauri::command;
+use tracing::level_filters::LevelFilter;
+use tracing::Level;
+
+use crate::logging::{self, LogEntry};
+
+const DEFAULT_RECENT_LIMIT: usize = 200;
+
+/// Returns the most recent log entries, oldest first: up to `limit` (200 by
+/// default) at `level` ("error", "warn", "info", "debug" or "trace") or more
+/// severe, `info` by default.
+#[command]
+pub async fn get_recent_logs(
+    level: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<LogEntry>, String> {
+    let level = match level {
+        Some(level) => level
+            .parse::<Level>()
+            .map_err(|_| format!("Unknown log level: {}", level))?,
+        None => Level::INFO,
+    };
+    Ok(logging::recent(
+        level,
+        limit.unwrap_or(DEFAULT_RECENT_LIMIT),
+    ))
+}
+
+/// Changes the log level ("off", "error", "warn", "info", "debug" or "trace")
+/// for `target`, a module path such as `mighty::commands::storage`, or the
+/// default level when no target is given. Lasts until the app exits.
+#[command]
+pub async fn set_log_level(target: Option<String>, level: String) -> Result<(), String> {
+    let level = level
+        .parse::<LevelFilter>()
+        .map_err(|_| format!("Unknown log level: {}", level))?;
+    let target = target.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    logging::set_level(target, level)?;
+    tracing::info!(
+        "Log level for {} set to {}",
+        target.unwrap_or("everything else"),
+        level
+    );
+    Ok(())
+}
@@ -0,0 +1,19 @@
+// src/commands/metrics.rs
+
+use tauri::command;
+
+use crate::telemetry::{self, MetricsReport};
+
+/// Returns per-command timings, failure counts and payload sizes, plus the
+/// timings of stages within commands (embeddings, vector search). Nothing is
+/// recorded unless `telemetry.enabled` is set in the config.
+#[command]
+pub async fn get_command_metrics() -> Result<MetricsReport, String> {
+    Ok(telemetry::report())
+}
+
+/// Clears the recorded metrics.
+#[command]
+pub async fn reset_command_metrics() -> Result<(), String> {
+    telemetry::reset()
+}
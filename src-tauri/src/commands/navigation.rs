@@ -0,0 +1,158 @@
+// src/commands/navigation.rs
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+use tracing::warn;
+
+use super::fs::{get_project_root, resolve_workspace_path};
+use super::session::CursorPosition;
+use super::storage::{get_record, put_record};
+
+const STORAGE_PREFIX: &str = "navigation:";
+/// Locations kept per workspace; the oldest are dropped.
+const MAX_LOCATIONS: usize = 100;
+
+/// The current workspace's history, loaded from storage on first use.
+static HISTORY: Lazy<Mutex<Option<(String, NavigationHistory)>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NavigationLocation {
+    /// Relative to the workspace root.
+    pub path: String,
+    pub cursor: CursorPosition,
+    /// What led here, e.g. "go to definition" or "search result".
+    pub reason: String,
+    /// Milliseconds since the Unix epoch.
+    #[serde(default)]
+    pub visited_at: i64,
+}
+
+/// Jump locations, oldest first, and where back and forward move from.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NavigationHistory {
+    pub locations: Vec<NavigationLocation>,
+    /// Index of the current location in `locations`.
+    pub current: usize,
+}
+
+impl NavigationHistory {
+    fn is_current(&self, path: &str, cursor: &CursorPosition) -> bool {
+        self.locations
+            .get(self.current)
+            .is_some_and(|location| location.path == path && location.cursor.line == cursor.line)
+    }
+
+    /// Adds a location after the current one, dropping any forward history.
+    fn push(&mut self, path: String, cursor: CursorPosition, reason: String) {
+        if self.is_current(&path, &cursor) {
+            return;
+        }
+        self.locations.truncate(self.current + 1);
+        self.locations.push(NavigationLocation {
+            path,
+            cursor,
+            reason,
+            visited_at: Utc::now().timestamp_millis(),
+        });
+        let excess = self.locations.len().saturating_sub(MAX_LOCATIONS);
+        self.locations.drain(..excess);
+        self.current = self.locations.len() - 1;
+    }
+
+    /// Moves `step` locations back (negative) or forward, passing over files
+    /// deleted since, and returns where it ended up.
+    fn step(&mut self, step: isize) -> Option<NavigationLocation> {
+        let mut index = self.current;
+        loop {
+            index = index.checked_add_signed(step)?;
+            let location = self.locations.get(index)?;
+            if resolve_workspace_path(&location.path).is_ok_and(|path| path.exists()) {
+                self.current = index;
+                return Some(location.clone());
+            }
+        }
+    }
+}
+
+fn workspace() -> String {
+    get_project_root().to_string_lossy().to_string()
+}
+
+fn storage_key(workspace: &str) -> String {
+    format!("{}{}", STORAGE_PREFIX, workspace)
+}
+
+/// Runs `f` on the current workspace's history, saving it afterwards when
+/// `save` is set. Switching workspaces loads that workspace's history.
+fn with_history<T>(save: bool, f: impl FnOnce(&mut NavigationHistory) -> T) -> Result<T, String> {
+    let workspace = workspace();
+    let mut history = HISTORY.lock();
+    if history
+        .as_ref()
+        .is_none_or(|(owner, _)| *owner != workspace)
+    {
+        let loaded = match get_record(&storage_key(&workspace)).map_err(|e| e.to_string())? {
+            Some(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!(
+                    "The navigation history for {} is unreadable: {}",
+                    workspace, e
+                );
+                NavigationHistory::default()
+            }),
+            None => NavigationHistory::default(),
+        };
+        *history = Some((workspace.clone(), loaded));
+    }
+
+    let (_, history) = history.as_mut().expect("loaded above");
+    let result = f(history);
+    if save {
+        let json = serde_json::to_string(history).map_err(|e| e.to_string())?;
+        put_record(&storage_key(&workspace), &json).map_err(|e| e.to_string())?;
+    }
+    Ok(result)
+}
+
+/// Records a jump to `path` at `cursor`. `from`, where the cursor was before
+/// the jump, is recorded first when it isn't the current location already,
+/// so back returns there. Jumping after going back drops the forward
+/// history, as in a browser.
+#[command]
+pub async fn push_location(
+    path: String,
+    cursor: CursorPosition,
+    reason: String,
+    from: Option<NavigationLocation>,
+) -> Result<NavigationHistory, String> {
+    resolve_workspace_path(&path).map_err(|e| e.message().to_string())?;
+    with_history(true, |history| {
+        if let Some(from) = from {
+            history.push(from.path, from.cursor, from.reason);
+        }
+        history.push(path, cursor, reason);
+        history.clone()
+    })
+}
+
+/// Moves to the previous location, or returns `None` at the oldest.
+#[command]
+pub async fn navigate_back() -> Result<Option<NavigationLocation>, String> {
+    with_history(true, |history| history.step(-1))
+}
+
+/// Moves to the next location after going back, or returns `None` at the
+/// newest.
+#[command]
+pub async fn navigate_forward() -> Result<Option<NavigationLocation>, String> {
+    with_history(true, |history| history.step(1))
+}
+
+/// The current workspace's history, e.g. to restore the back and forward
+/// buttons after a reload.
+#[command]
+pub async fn get_navigation_history() -> Result<NavigationHistory, String> {
+    with_history(false, |history| history.clone())
+}
@@ -0,0 +1,166 @@
+// src/commands/notifications.rs
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{command, AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::storage::{delete_record, put_record, records_with_prefix};
+use crate::config::NotificationsConfig;
+
+const STORAGE_PREFIX: &str = "notification:";
+/// Notifications kept in the feed; older ones are dropped.
+const NOTIFICATIONS_KEPT: usize = 200;
+
+static DESKTOP: AtomicBool = AtomicBool::new(true);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub id: String,
+    pub level: NotificationLevel,
+    /// What raised it, e.g. "jobs" or "providers", for grouping in the UI.
+    pub category: String,
+    pub title: String,
+    pub body: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    pub created_at: i64,
+    pub read: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationList {
+    pub notifications: Vec<Notification>,
+    /// Across the whole feed, for the badge.
+    pub unread: usize,
+}
+
+fn storage_key(id: &str) -> String {
+    format!("{}{}", STORAGE_PREFIX, id)
+}
+
+fn save(notification: &Notification) -> Result<(), String> {
+    let json = serde_json::to_string(notification).map_err(|e| e.to_string())?;
+    put_record(&storage_key(&notification.id), &json).map_err(|e| e.to_string())
+}
+
+/// The whole feed, newest first.
+fn load_feed() -> Result<Vec<Notification>, String> {
+    let mut feed: Vec<Notification> = records_with_prefix(STORAGE_PREFIX)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|(_, json)| serde_json::from_str(&json).ok())
+        .collect();
+    feed.sort_by_key(|notification| std::cmp::Reverse(notification.created_at));
+    Ok(feed)
+}
+
+fn prune() -> Result<(), String> {
+    for old in load_feed()?.iter().skip(NOTIFICATIONS_KEPT) {
+        delete_record(&storage_key(&old.id)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Applies the `[notifications]` settings.
+pub(crate) fn configure(config: Option<&NotificationsConfig>) {
+    let desktop = config
+        .and_then(|notifications| notifications.desktop)
+        .unwrap_or(true);
+    DESKTOP.store(desktop, Ordering::Relaxed);
+}
+
+/// Adds a notification to the feed and sends it to the frontend as a
+/// `notification` event. While the main window is in the background it is
+/// also shown as an OS notification, unless those are turned off.
+pub(crate) fn notify(
+    app: &AppHandle,
+    level: NotificationLevel,
+    category: &str,
+    title: impl Into<String>,
+    body: Option<String>,
+) {
+    let notification = Notification {
+        id: Uuid::new_v4().to_string(),
+        level,
+        category: category.to_string(),
+        title: title.into(),
+        body,
+        created_at: Utc::now().timestamp_millis(),
+        read: false,
+    };
+
+    if let Err(e) = save(&notification).and_then(|()| prune()) {
+        warn!("Failed to save notification: {}", e);
+    }
+    if let Err(e) = app.emit("notification", &notification) {
+        warn!("Failed to emit notification: {}", e);
+    }
+
+    let focused = app
+        .get_webview_window("main")
+        .and_then(|window| window.is_focused().ok())
+        .unwrap_or(false);
+    if DESKTOP.load(Ordering::Relaxed) && !focused {
+        let mut builder = app.notification().builder().title(&notification.title);
+        if let Some(body) = &notification.body {
+            builder = builder.body(body);
+        }
+        if let Err(e) = builder.show() {
+            warn!("Failed to show a desktop notification: {}", e);
+        }
+    }
+}
+
+/// The feed, newest first, optionally only unread notifications and at most
+/// `limit` of them.
+#[command]
+pub async fn list_notifications(
+    unread_only: Option<bool>,
+    limit: Option<usize>,
+) -> Result<NotificationList, String> {
+    let feed = load_feed()?;
+    let unread = feed
+        .iter()
+        .filter(|notification| !notification.read)
+        .count();
+    let notifications = feed
+        .into_iter()
+        .filter(|notification| !unread_only.unwrap_or(false) || !notification.read)
+        .take(limit.unwrap_or(NOTIFICATIONS_KEPT))
+        .collect();
+    Ok(NotificationList {
+        notifications,
+        unread,
+    })
+}
+
+/// Marks the given notifications read, or all of them when `ids` is omitted.
+/// Returns how many were unread before.
+#[command]
+pub async fn mark_read(ids: Option<Vec<String>>) -> Result<usize, String> {
+    let mut marked = 0;
+    for mut notification in load_feed()? {
+        let selected = match &ids {
+            Some(ids) => ids.contains(&notification.id),
+            None => true,
+        };
+        if selected && !notification.read {
+            notification.read = true;
+            save(&notification)?;
+            marked += 1;
+        }
+    }
+    Ok(marked)
+}
@@ -0,0 +1,674 @@
+// src/commands/oauth.rs
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter, State};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tracing::warn;
+use uuid::Uuid;
+
+use super::auth::vault;
+use super::offline::ensure_reachable;
+use crate::config::{AppConfig, OAuthFlow, OAuthProviderConfig};
+use crate::error::MightyError;
+use crate::http_client;
+use crate::state::AppState;
+
+/// How long a login may wait for the user to finish in the browser.
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+const CALLBACK_PATH: &str = "/callback";
+/// Device flow polling interval when the provider doesn't give one.
+const DEFAULT_POLL_INTERVAL: u64 = 5;
+/// How long a connection to the redirect listener may take to send its request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Seconds before expiry at which a token is refreshed, unless configured.
+const DEFAULT_REFRESH_SKEW: u64 = 60;
+/// Largest redirect request read from the browser.
+const MAX_REQUEST_BYTES: usize = 16 * 1024;
+
+/// Tokens from a completed login, saved in the credential vault.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct StoredTokens {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    /// Unix timestamp (seconds) the access token expires at, when known.
+    pub expires_at: Option<i64>,
+    pub token_type: Option<String>,
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoginState {
+    Idle,
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginStatus {
+    pub provider: String,
+    pub state: LoginState,
+    /// Whether tokens for the provider are stored, from this or an earlier run.
+    pub logged_in: bool,
+    pub error: Option<String>,
+    /// For the device flow: the code to enter at `verification_uri`.
+    pub user_code: Option<String>,
+    pub verification_uri: Option<String>,
+}
+
+struct LoginAttempt {
+    /// Lets a login task tell whether it has since been replaced.
+    id: String,
+    status: LoginStatus,
+    task: Option<AbortHandle>,
+}
+
+/// Held while refreshing, so concurrent callers don't each spend a refresh
+/// token that the provider rotates on use.
+static REFRESH_LOCK: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+static LOGINS: Lazy<Mutex<HashMap<String, LoginAttempt>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_in: Option<i64>,
+    token_type: Option<String>,
+    scope: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+impl TokenResponse {
+    fn error_message(&self) -> String {
+        match (&self.error, &self.error_description) {
+            (Some(error), Some(description)) => format!("{}: {}", error, description),
+            (Some(error), None) => error.clone(),
+            _ => "The token response had no access token".to_string(),
+        }
+    }
+
+    fn into_tokens(self) -> Result<StoredTokens, String> {
+        let Some(access_token) = self.access_token.clone() else {
+            return Err(self.error_message());
+        };
+        Ok(StoredTokens {
+            access_token,
+            refresh_token: self.refresh_token,
+            expires_at: self
+                .expires_in
+                .map(|seconds| Utc::now().timestamp() + seconds),
+            token_type: self.token_type,
+            scope: self.scope,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceAuthorization {
+    device_code: String,
+    user_code: String,
+    /// Google calls it `verification_url`
+    #[serde(alias = "verification_url")]
+    verification_uri: String,
+    verification_uri_complete: Option<String>,
+    expires_in: Option<u64>,
+    interval: Option<u64>,
+}
+
+/// Start of the vault secrets holding each provider's tokens.
+pub(crate) const TOKEN_SECRET_PREFIX: &str = "oauth.";
+
+/// Vault secret holding a provider's tokens.
+pub(crate) fn token_secret(provider: &str) -> String {
+    format!("{}{}", TOKEN_SECRET_PREFIX, provider)
+}
+
+pub(crate) fn load_tokens(provider: &str) -> Option<StoredTokens> {
+    match vault().get(&token_secret(provider)) {
+        Ok(json) => json.and_then(|json| serde_json::from_str(&json).ok()),
+        Err(e) => {
+            warn!("{}", e);
+            None
+        }
+    }
+}
+
+pub(crate) fn save_tokens(provider: &str, tokens: &StoredTokens) -> Result<(), String> {
+    let json = serde_json::to_string(tokens).map_err(|e| e.to_string())?;
+    vault().set(&token_secret(provider), &json)
+}
+
+/// Posts a form to a token endpoint. Errors come back in the body, so the
+/// status code isn't checked here.
+async fn request_token(
+    config: &OAuthProviderConfig,
+    params: &[(&str, &str)],
+) -> Result<TokenResponse, MightyError> {
+    ensure_reachable(&config.token_url)?;
+    let mut form: Vec<(&str, &str)> = params.to_vec();
+    form.push(("client_id", &config.client_id));
+    if let Some(secret) = &config.client_secret {
+        form.push(("client_secret", secret));
+    }
+    http_client::client("oauth")?
+        .post(&config.token_url)
+        // GitHub answers form-encoded unless asked for JSON
+        .header("Accept", "application/json")
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| {
+            MightyError::auth(
+                "TOKEN_REQUEST_FAILED",
+                &format!("Failed to reach the token endpoint: {}", e),
+            )
+        })?
+        .json::<TokenResponse>()
+        .await
+        .map_err(|e| {
+            MightyError::auth(
+                "INVALID_TOKEN_RESPONSE",
+                &format!("Failed to parse the token response: {}", e),
+            )
+        })
+}
+
+impl StoredTokens {
+    fn expires_within(&self, skew: u64) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at - (skew as i64) <= Utc::now().timestamp())
+    }
+}
+
+/// Returns an access token for `provider` that isn't about to expire,
+/// refreshing it first if needed. `None` means the user has to sign in
+/// again; an `oauth-relogin-required` event is emitted when that's newly so.
+pub(crate) async fn access_token(
+    app: &AppHandle,
+    provider: &str,
+    config: &OAuthProviderConfig,
+) -> Result<Option<String>, MightyError> {
+    let skew = config.refresh_skew_secs.unwrap_or(DEFAULT_REFRESH_SKEW);
+    match load_tokens(provider) {
+        None => return Ok(None),
+        Some(tokens) if !tokens.expires_within(skew) => return Ok(Some(tokens.access_token)),
+        Some(_) => {}
+    }
+
+    let _guard = REFRESH_LOCK.lock().await;
+    // Another caller may have refreshed while this one waited
+    let Some(tokens) = load_tokens(provider) else {
+        return Ok(None);
+    };
+    if !tokens.expires_within(skew) {
+        return Ok(Some(tokens.access_token));
+    }
+    let Some(refresh_token) = tokens.refresh_token.clone() else {
+        require_relogin(app, provider, "The access token expired");
+        return Ok(None);
+    };
+
+    // Transport errors are returned as-is; the stored tokens may still work
+    // once the provider is reachable again
+    let response = request_token(
+        config,
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &refresh_token),
+        ],
+    )
+    .await?;
+    if response.access_token.is_none() {
+        require_relogin(app, provider, &response.error_message());
+        return Ok(None);
+    }
+    let mut refreshed = response
+        .into_tokens()
+        .map_err(|e| MightyError::auth("TOKEN_ERROR", &e))?;
+    // Providers that don't rotate refresh tokens leave them out
+    if refreshed.refresh_token.is_none() {
+        refreshed.refresh_token = Some(refresh_token);
+    }
+    save_tokens(provider, &refreshed).map_err(|e| MightyError::auth("VAULT_ERROR", &e))?;
+    Ok(Some(refreshed.access_token))
+}
+
+/// Drops tokens that can no longer be used and tells the UI to sign in again.
+fn require_relogin(app: &AppHandle, provider: &str, reason: &str) {
+    if let Err(e) = vault().delete(&token_secret(provider)) {
+        warn!("{}", e);
+    }
+    let payload = json!({
+        "provider": provider,
+        "reason": reason,
+    });
+    if let Err(e) = app.emit("oauth-relogin-required", payload) {
+        warn!("Failed to emit relogin request: {}", e);
+    }
+}
+
+fn random_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+fn update_status(provider: &str, attempt: &str, update: impl FnOnce(&mut LoginStatus)) {
+    let mut logins = LOGINS.lock();
+    if let Some(login) = logins.get_mut(provider) {
+        if login.id == attempt {
+            update(&mut login.status);
+        }
+    }
+}
+
+/// Opens `url` in the system browser.
+fn open_browser(app: &AppHandle, url: &str) -> Result<(), String> {
+    use tauri_plugin_opener::OpenerExt;
+    app.opener()
+        .open_url(url, None::<&str>)
+        .map_err(|e| format!("Failed to open the browser: {}", e))
+}
+
+async fn bind_listener(port: u16) -> Result<TcpListener, String> {
+    // A listener from an attempt that was just cancelled may still hold the port
+    let mut last_error = None;
+    for _ in 0..10 {
+        match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => return Ok(listener),
+            Err(e) => last_error = Some(e),
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    Err(format!(
+        "Failed to listen for the login redirect: {}",
+        last_error.map(|e| e.to_string()).unwrap_or_default()
+    ))
+}
+
+/// Waits for the browser to be redirected back with the authorization code.
+/// Each connection is handled on its own task, since browsers open spare
+/// connections that may never carry a request.
+async fn wait_for_code(listener: TcpListener, state: &str) -> Result<String, String> {
+    let (sender, mut receiver) = mpsc::channel(1);
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, _) = accepted.map_err(|e| e.to_string())?;
+                let state = state.to_string();
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    if let Ok(Some(result)) =
+                        tokio::time::timeout(REQUEST_TIMEOUT, handle_redirect(stream, &state)).await
+                    {
+                        let _ = sender.send(result).await;
+                    }
+                });
+            }
+            Some(result) = receiver.recv() => return result,
+        }
+    }
+}
+
+/// Answers one request to the redirect listener, returning the login's
+/// outcome when it was the redirect.
+async fn handle_redirect(mut stream: TcpStream, state: &str) -> Option<Result<String, String>> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 2048];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_BYTES {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => request.extend_from_slice(&buffer[..n]),
+        }
+    }
+    let request = String::from_utf8_lossy(&request);
+    let target = request
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))?;
+    let url = Url::parse(&format!("http://localhost{}", target)).ok()?;
+    // Browsers also ask for things like /favicon.ico
+    if url.path() != CALLBACK_PATH {
+        let _ = stream
+            .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .await;
+        return None;
+    }
+
+    let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    let result = if params.get("state").map(String::as_str) != Some(state) {
+        Err("The login redirect didn't match this login attempt".to_string())
+    } else if let Some(code) = params.get("code") {
+        Ok(code.clone())
+    } else {
+        Err(params
+            .get("error_description")
+            .or_else(|| params.get("error"))
+            .cloned()
+            .unwrap_or_else(|| "The provider didn't return an authorization code".to_string()))
+    };
+
+    let message = match &result {
+        Ok(_) => "Signed in. You can close this tab and return to Mighty IDE.".to_string(),
+        Err(e) => format!("Sign-in failed: {}", e),
+    };
+    let body = format!(
+        "<!doctype html><html><body style=\"font-family: sans-serif\"><p>{}</p></body></html>",
+        message.replace('&', "&amp;").replace('<', "&lt;")
+    );
+    let response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    Some(result)
+}
+
+async fn pkce_login(
+    app: &AppHandle,
+    config: &OAuthProviderConfig,
+    authorization_url: &str,
+) -> Result<StoredTokens, MightyError> {
+    let listener = bind_listener(config.redirect_port.unwrap_or(0)).await?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    let redirect_uri = format!("http://127.0.0.1:{}{}", port, CALLBACK_PATH);
+
+    let verifier = random_token();
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    let state = Uuid::new_v4().simple().to_string();
+    let scope = config.scopes.join(" ");
+    let url = Url::parse_with_params(
+        authorization_url,
+        &[
+            ("response_type", "code"),
+            ("client_id", config.client_id.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("scope", scope.as_str()),
+            ("state", state.as_str()),
+            ("code_challenge", challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ],
+    )
+    .map_err(|e| format!("Invalid authorization URL: {}", e))?;
+    open_browser(app, url.as_str())?;
+
+    let code = wait_for_code(listener, &state).await?;
+    request_token(
+        config,
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("redirect_uri", &redirect_uri),
+            ("code_verifier", &verifier),
+        ],
+    )
+    .await?
+    .into_tokens()
+    .map_err(|e| MightyError::auth("TOKEN_ERROR", &e))
+}
+
+async fn device_login(
+    app: &AppHandle,
+    provider: &str,
+    attempt: &str,
+    config: &OAuthProviderConfig,
+    device_authorization_url: &str,
+) -> Result<StoredTokens, MightyError> {
+    ensure_reachable(device_authorization_url)?;
+    let mut form = vec![("client_id", config.client_id.clone())];
+    if !config.scopes.is_empty() {
+        form.push(("scope", config.scopes.join(" ")));
+    }
+    let response = http_client::client("oauth")?
+        .post(device_authorization_url)
+        .header("Accept", "application/json")
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| {
+            MightyError::auth(
+                "DEVICE_LOGIN_FAILED",
+                &format!("Failed to start device login: {}", e),
+            )
+        })?;
+    if !response.status().is_success() {
+        return Err(MightyError::auth(
+            "DEVICE_LOGIN_REFUSED",
+            &format!(
+                "Device login was refused: {}",
+                response.text().await.unwrap_or_default()
+            ),
+        ));
+    }
+    let device: DeviceAuthorization = response.json().await.map_err(|e| {
+        MightyError::auth(
+            "INVALID_DEVICE_RESPONSE",
+            &format!("Failed to parse the device login response: {}", e),
+        )
+    })?;
+
+    update_status(provider, attempt, |status| {
+        status.user_code = Some(device.user_code.clone());
+        status.verification_uri = Some(device.verification_uri.clone());
+    });
+    let payload = json!({
+        "provider": provider,
+        "user_code": device.user_code,
+        "verification_uri": device.verification_uri,
+    });
+    if let Err(e) = app.emit("oauth-device-code", payload) {
+        warn!("Failed to emit device code: {}", e);
+    }
+    // The code still has to be shown in the app, so a browser failure isn't fatal
+    let page = device
+        .verification_uri_complete
+        .as_deref()
+        .unwrap_or(&device.verification_uri);
+    if let Err(e) = open_browser(app, page) {
+        warn!("{}", e);
+    }
+
+    let deadline = tokio::time::Instant::now()
+        + device
+            .expires_in
+            .map(Duration::from_secs)
+            .unwrap_or(LOGIN_TIMEOUT);
+    let mut interval = device.interval.unwrap_or(DEFAULT_POLL_INTERVAL);
+    loop {
+        tokio::time::sleep(Duration::from_secs(interval)).await;
+        if tokio::time::Instant::now() >= deadline {
+            return Err(MightyError::auth(
+                "DEVICE_CODE_EXPIRED",
+                "The device code expired before the login was approved",
+            ));
+        }
+        let response = request_token(
+            config,
+            &[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+                ("device_code", &device.device_code),
+            ],
+        )
+        .await?;
+        match response.error.as_deref() {
+            Some("authorization_pending") => {}
+            Some("slow_down") => interval += 5,
+            _ => {
+                return response
+                    .into_tokens()
+                    .map_err(|e| MightyError::auth("TOKEN_ERROR", &e))
+            }
+        }
+    }
+}
+
+pub(crate) fn provider_config(
+    config: &AppConfig,
+    provider: &str,
+) -> Result<OAuthProviderConfig, String> {
+    config
+        .oauth
+        .get(provider)
+        .cloned()
+        .ok_or_else(|| format!("No OAuth provider named {} is configured", provider))
+}
+
+/// Starts signing in to `provider` from `AppConfig.oauth`, opening the
+/// system browser, and returns right away; the outcome arrives as an
+/// `oauth-login-completed` event and through `get_login_status`. Starting
+/// again cancels an unfinished attempt for the same provider.
+#[command]
+pub async fn begin_oauth_login(
+    app: AppHandle,
+    provider: String,
+    state: State<'_, AppState>,
+) -> Result<LoginStatus, MightyError> {
+    let provider_config = provider_config(&*state.config.lock().await, &provider)
+        .map_err(|e| MightyError::config("UNKNOWN_PROVIDER", &e))?;
+    let flow = provider_config
+        .flow
+        .unwrap_or(if provider_config.authorization_url.is_some() {
+            OAuthFlow::Pkce
+        } else {
+            OAuthFlow::Device
+        });
+    let endpoint = match flow {
+        OAuthFlow::Pkce => provider_config.authorization_url.clone(),
+        OAuthFlow::Device => provider_config.device_authorization_url.clone(),
+    }
+    .ok_or_else(|| {
+        MightyError::config(
+            "MISSING_ENDPOINT",
+            &format!(
+                "{} has no endpoint configured for the {:?} flow",
+                provider, flow
+            ),
+        )
+    })?;
+
+    let attempt = Uuid::new_v4().to_string();
+    let status = LoginStatus {
+        provider: provider.clone(),
+        state: LoginState::Pending,
+        logged_in: load_tokens(&provider).is_some(),
+        error: None,
+        user_code: None,
+        verification_uri: None,
+    };
+    {
+        let mut logins = LOGINS.lock();
+        if let Some(task) = logins
+            .get_mut(&provider)
+            .and_then(|login| login.task.take())
+        {
+            task.abort();
+        }
+        logins.insert(
+            provider.clone(),
+            LoginAttempt {
+                id: attempt.clone(),
+                status: status.clone(),
+                task: None,
+            },
+        );
+    }
+
+    let task_provider = provider.clone();
+    let task_attempt = attempt.clone();
+    let task = tokio::spawn(async move {
+        let login = async {
+            match flow {
+                OAuthFlow::Pkce => pkce_login(&app, &provider_config, &endpoint).await,
+                OAuthFlow::Device => {
+                    device_login(
+                        &app,
+                        &task_provider,
+                        &task_attempt,
+                        &provider_config,
+                        &endpoint,
+                    )
+                    .await
+                }
+            }
+        };
+        let result = match tokio::time::timeout(LOGIN_TIMEOUT, login).await {
+            Ok(result) => result,
+            Err(_) => Err(MightyError::auth("LOGIN_TIMEOUT", "The login timed out")),
+        }
+        .and_then(|tokens| {
+            save_tokens(&task_provider, &tokens).map_err(|e| MightyError::auth("VAULT_ERROR", &e))
+        });
+
+        let error = result.err();
+        update_status(&task_provider, &task_attempt, |status| {
+            status.state = if error.is_some() {
+                LoginState::Failed
+            } else {
+                LoginState::Succeeded
+            };
+            status.logged_in = error.is_none() || status.logged_in;
+            status.error = error.as_ref().map(ToString::to_string);
+        });
+        let payload = json!({
+            "provider": task_provider,
+            "success": error.is_none(),
+            "error": error,
+        });
+        if let Err(e) = app.emit("oauth-login-completed", payload) {
+            warn!("Failed to emit login result: {}", e);
+        }
+    });
+
+    if let Some(login) = LOGINS.lock().get_mut(&provider) {
+        if login.id == attempt {
+            login.task = Some(task.abort_handle());
+        }
+    }
+    Ok(status)
+}
+
+/// Reports each configured provider's login state: whether tokens are
+/// stored, and how the latest login attempt in this run went.
+#[command]
+pub async fn get_login_status(state: State<'_, AppState>) -> Result<Vec<LoginStatus>, MightyError> {
+    let mut providers: Vec<String> = state.config.lock().await.oauth.keys().cloned().collect();
+    providers.sort();
+
+    let logins = LOGINS.lock();
+    Ok(providers
+        .into_iter()
+        .map(|provider| {
+            let logged_in = load_tokens(&provider).is_some();
+            match logins.get(&provider) {
+                Some(login) => LoginStatus {
+                    logged_in,
+                    ..login.status.clone()
+                },
+                None => LoginStatus {
+                    provider,
+                    state: LoginState::Idle,
+                    logged_in,
+                    error: None,
+                    user_code: None,
+                    verification_uri: None,
+                },
+            }
+        })
+        .collect())
+}
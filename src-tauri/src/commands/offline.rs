@@ -0,0 +1,80 @@
+// src/commands/offline.rs
+
+use reqwest::Url;
+use serde::Serialize;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{command, AppHandle, Emitter};
+use tracing::{info, warn};
+
+use crate::error::MightyError;
+
+/// The error code every network path fails with while offline.
+pub(crate) const OFFLINE_CODE: &str = "OFFLINE";
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Payload of the `offline-changed` event.
+#[derive(Debug, Clone, Serialize)]
+struct OfflineEvent {
+    offline: bool,
+}
+
+pub(crate) fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+pub(crate) fn offline_error() -> MightyError {
+    MightyError::Service {
+        code: OFFLINE_CODE.to_string(),
+        message: "Offline mode is on; turn it off to use network features".to_string(),
+        details: None,
+    }
+}
+
+/// Fails straight away while offline mode is on, so callers don't wait out
+/// a network timeout.
+pub(crate) fn ensure_online() -> Result<(), MightyError> {
+    if is_offline() {
+        return Err(offline_error());
+    }
+    Ok(())
+}
+
+fn is_local(url: &str) -> bool {
+    let Ok(url) = Url::parse(url) else {
+        return false;
+    };
+    let host = url.host_str().unwrap_or_default();
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Like `ensure_online`, but lets requests to this machine through, such
+/// as to a local model server.
+pub(crate) fn ensure_reachable(url: &str) -> Result<(), MightyError> {
+    if is_local(url) {
+        return Ok(());
+    }
+    ensure_online()
+}
+
+/// Turns offline mode on or off. While on, commands that would reach the
+/// network fail at once with the `OFFLINE` code instead.
+#[command]
+pub async fn set_offline(app: AppHandle, offline: bool) -> Result<(), MightyError> {
+    if OFFLINE.swap(offline, Ordering::Relaxed) == offline {
+        return Ok(());
+    }
+    info!("Offline mode {}", if offline { "on" } else { "off" });
+    if let Err(e) = app.emit("offline-changed", &OfflineEvent { offline }) {
+        warn!("Failed to emit offline change: {}", e);
+    }
+    Ok(())
+}
+
+#[command]
+pub async fn get_offline() -> Result<bool, MightyError> {
+    Ok(is_offline())
+}
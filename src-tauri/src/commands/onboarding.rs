@@ -0,0 +1,242 @@
+// src/commands/onboarding.rs
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tauri::{command, State};
+
+use super::api::anthropic_api_key;
+use super::fs::get_project_root;
+use super::storage::{delete_record, get_record, put_record};
+use crate::bindings::python_runtime;
+use crate::context::{context, warmup};
+use crate::state::AppState;
+
+const STORAGE_KEY: &str = "onboarding";
+/// Python module that computes embeddings.
+const EMBEDDING_MODULE: &str = "bge_embed";
+/// Setting Python up for the first time installs packages, so it gets long.
+const PYTHON_CHECK_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Setup steps, in the order the wizard walks them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingStep {
+    ApiKey,
+    PythonRuntime,
+    Workspace,
+    Index,
+}
+
+impl OnboardingStep {
+    const ALL: [OnboardingStep; 4] = [
+        OnboardingStep::ApiKey,
+        OnboardingStep::PythonRuntime,
+        OnboardingStep::Workspace,
+        OnboardingStep::Index,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pending,
+    Done,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepState {
+    pub step: OnboardingStep,
+    pub status: StepStatus,
+    /// What's missing or what went wrong, for the wizard to show.
+    pub detail: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    pub completed_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingState {
+    pub steps: Vec<StepState>,
+    /// The first step neither done nor skipped.
+    pub current: Option<OnboardingStep>,
+    pub complete: bool,
+}
+
+/// What's saved between runs: steps confirmed by a check that can't be
+/// repeated cheaply at every launch, steps skipped, and failures.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Progress {
+    #[serde(default)]
+    completed: BTreeMap<OnboardingStep, i64>,
+    #[serde(default)]
+    skipped: BTreeMap<OnboardingStep, i64>,
+    #[serde(default)]
+    failed: BTreeMap<OnboardingStep, String>,
+    /// The workspace root confirmed at the workspace step.
+    workspace: Option<String>,
+}
+
+fn load_progress() -> Result<Progress, String> {
+    match get_record(STORAGE_KEY).map_err(|e| e.to_string())? {
+        Some(json) => serde_json::from_str(&json).map_err(|e| e.to_string()),
+        None => Ok(Progress::default()),
+    }
+}
+
+fn save_progress(progress: &Progress) -> Result<(), String> {
+    let json = serde_json::to_string(progress).map_err(|e| e.to_string())?;
+    put_record(STORAGE_KEY, &json).map_err(|e| e.to_string())
+}
+
+fn workspace_root() -> String {
+    get_project_root().to_string_lossy().to_string()
+}
+
+/// Checks a step against the running app. `Ok(None)` means it's met,
+/// otherwise the reason it isn't.
+async fn check(
+    step: OnboardingStep,
+    state: &AppState,
+    progress: &Progress,
+) -> Result<Option<String>, String> {
+    Ok(match step {
+        OnboardingStep::ApiKey => anthropic_api_key(state)
+            .await
+            .is_none()
+            .then(|| "Save an Anthropic API key".to_string()),
+        // Python starts on first use, so a check from an earlier run stands
+        OnboardingStep::PythonRuntime => (!python_runtime::is_initialized()
+            && !progress.completed.contains_key(&step))
+        .then(|| "Python hasn't been set up yet".to_string()),
+        OnboardingStep::Workspace => (progress.workspace.as_deref()
+            != Some(workspace_root().as_str()))
+        .then(|| format!("Confirm {} as the workspace", workspace_root())),
+        OnboardingStep::Index => match context::indexed_files().await {
+            Ok(files) if !files.is_empty() => None,
+            Ok(_) => Some("Nothing is indexed yet".to_string()),
+            Err(_) => Some("The code context isn't running yet".to_string()),
+        },
+    })
+}
+
+async fn onboarding_state(state: &AppState) -> Result<OnboardingState, String> {
+    let progress = load_progress()?;
+    let mut steps = Vec::new();
+    for step in OnboardingStep::ALL {
+        let missing = check(step, state, &progress).await?;
+        let (status, detail) = match missing {
+            None => (StepStatus::Done, None),
+            Some(_) if progress.skipped.contains_key(&step) => (StepStatus::Skipped, None),
+            Some(missing) => match progress.failed.get(&step) {
+                Some(error) => (StepStatus::Failed, Some(error.clone())),
+                None if step == OnboardingStep::Index && progress.completed.contains_key(&step) => {
+                    (
+                        StepStatus::Pending,
+                        Some("Indexing in the background".to_string()),
+                    )
+                }
+                None => (StepStatus::Pending, Some(missing)),
+            },
+        };
+        steps.push(StepState {
+            step,
+            status,
+            detail,
+            completed_at: match status {
+                StepStatus::Done => progress.completed.get(&step).copied(),
+                _ => None,
+            },
+        });
+    }
+
+    let current = steps
+        .iter()
+        .find(|step| matches!(step.status, StepStatus::Pending | StepStatus::Failed))
+        .map(|step| step.step);
+    Ok(OnboardingState {
+        steps,
+        current,
+        complete: current.is_none(),
+    })
+}
+
+/// Where first-run setup stands, each step checked against the running app
+/// rather than taken from what the wizard last recorded.
+#[command]
+pub async fn get_onboarding_state(state: State<'_, AppState>) -> Result<OnboardingState, String> {
+    onboarding_state(&state).await
+}
+
+/// Completes `step` by doing or verifying what it needs: Python is set up
+/// and its embedding module loaded, the current workspace is confirmed, and
+/// indexing is started in the background. A failed check is kept and shown
+/// until the step is completed again.
+#[command]
+pub async fn complete_onboarding_step(
+    step: OnboardingStep,
+    state: State<'_, AppState>,
+) -> Result<OnboardingState, String> {
+    let mut progress = load_progress()?;
+    progress.failed.remove(&step);
+    let outcome = match step {
+        OnboardingStep::ApiKey => check(step, &state, &progress).await?.map_or(Ok(()), Err),
+        OnboardingStep::PythonRuntime => {
+            let import = tokio::task::spawn_blocking(|| {
+                python_runtime::run_python(|py| py.import(EMBEDDING_MODULE).map(drop))
+            });
+            match tokio::time::timeout(PYTHON_CHECK_TIMEOUT, import).await {
+                Ok(Ok(Ok(()))) => Ok(()),
+                Ok(Ok(Err(e))) => Err(format!("Failed to load {}: {}", EMBEDDING_MODULE, e)),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err("Setting Python up timed out".to_string()),
+            }
+        }
+        OnboardingStep::Workspace => {
+            progress.workspace = Some(workspace_root());
+            Ok(())
+        }
+        OnboardingStep::Index => match check(step, &state, &progress).await? {
+            None => Ok(()),
+            // Done once the first files are indexed
+            Some(_) => warmup::open_workspace(state.clone()).await.map(drop),
+        },
+    };
+
+    match outcome {
+        Ok(()) => {
+            progress.skipped.remove(&step);
+            progress
+                .completed
+                .insert(step, Utc::now().timestamp_millis());
+        }
+        Err(e) => {
+            progress.completed.remove(&step);
+            progress.failed.insert(step, e);
+        }
+    }
+    save_progress(&progress)?;
+    onboarding_state(&state).await
+}
+
+/// Skips `step` for now; it's done anyway once its check passes.
+#[command]
+pub async fn skip_onboarding_step(
+    step: OnboardingStep,
+    state: State<'_, AppState>,
+) -> Result<OnboardingState, String> {
+    let mut progress = load_progress()?;
+    progress.failed.remove(&step);
+    progress.skipped.insert(step, Utc::now().timestamp_millis());
+    save_progress(&progress)?;
+    onboarding_state(&state).await
+}
+
+/// Forgets all progress, to run the wizard again.
+#[command]
+pub async fn reset_onboarding(state: State<'_, AppState>) -> Result<OnboardingState, String> {
+    delete_record(STORAGE_KEY).map_err(|e| e.to_string())?;
+    onboarding_state(&state).await
+}
@@ -0,0 +1,376 @@
+// src/commands/permissions.rs
+
+use chrono::Utc;
+use http::HeaderMap;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tauri::ipc::Request;
+use tauri::{command, AppHandle, Emitter};
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::fs::get_project_root;
+use super::notifications::{notify, NotificationLevel};
+use super::storage::{get_record, put_record};
+
+const STORAGE_PREFIX: &str = "permission_policy:";
+/// Header the agent sets on the calls it makes, e.g.
+/// `invoke("write_file", args, { headers: { "x-mighty-actor": "agent" } })`.
+const ACTOR_HEADER: &str = "x-mighty-actor";
+/// An unanswered request is denied after this long.
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(300);
+
+static PENDING: Lazy<Mutex<HashMap<String, PendingApproval>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Commands that can do damage when the agent calls them unchecked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    WriteFile,
+    DeletePath,
+    RunCommand,
+    GitPush,
+}
+
+impl Capability {
+    const ALL: [Capability; 4] = [
+        Capability::WriteFile,
+        Capability::DeletePath,
+        Capability::RunCommand,
+        Capability::GitPush,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decision {
+    Allow,
+    /// Ask the user each time.
+    #[default]
+    Ask,
+    Deny,
+}
+
+/// Who made an IPC call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Actor {
+    User,
+    Agent,
+}
+
+impl Actor {
+    /// Calls are the user's unless they carry the agent's actor header.
+    pub(crate) fn of(request: &Request<'_>) -> Self {
+        Self::from_headers(request.headers())
+    }
+
+    /// `of` for requests that didn't come over IPC, such as the local API
+    /// server's.
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        let agent = headers
+            .get(ACTOR_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.eq_ignore_ascii_case("agent"));
+        if agent {
+            Actor::Agent
+        } else {
+            Actor::User
+        }
+    }
+}
+
+/// Sent as a `permission-requested` event when the agent needs approval.
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionRequest {
+    pub id: String,
+    pub capability: Capability,
+    /// The file, command or branch the call acts on.
+    pub target: String,
+    pub workspace: String,
+    /// Milliseconds since the Unix epoch.
+    pub requested_at: i64,
+}
+
+struct PendingApproval {
+    request: PermissionRequest,
+    respond: oneshot::Sender<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionPolicy {
+    pub workspace: String,
+    pub rules: BTreeMap<Capability, Decision>,
+}
+
+fn workspace() -> String {
+    get_project_root().to_string_lossy().to_string()
+}
+
+fn storage_key(workspace: &str) -> String {
+    format!("{}{}", STORAGE_PREFIX, workspace)
+}
+
+/// The workspace's saved decisions; anything unsaved is `Ask`.
+fn load_rules(workspace: &str) -> Result<BTreeMap<Capability, Decision>, String> {
+    let saved: BTreeMap<Capability, Decision> = match get_record(&storage_key(workspace)) {
+        Ok(Some(json)) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            warn!(
+                "Ignoring the unreadable permission policy for {}: {}",
+                workspace, e
+            );
+            BTreeMap::new()
+        }),
+        Ok(None) => BTreeMap::new(),
+        Err(e) => return Err(e.to_string()),
+    };
+    Ok(Capability::ALL
+        .into_iter()
+        .map(|capability| {
+            let decision = saved.get(&capability).copied().unwrap_or_default();
+            (capability, decision)
+        })
+        .collect())
+}
+
+fn save_decision(
+    workspace: &str,
+    capability: Capability,
+    decision: Decision,
+) -> Result<(), String> {
+    let mut rules = load_rules(workspace)?;
+    rules.insert(capability, decision);
+    let json = serde_json::to_string(&rules).map_err(|e| e.to_string())?;
+    put_record(&storage_key(workspace), &json).map_err(|e| e.to_string())
+}
+
+/// Removes the pending request when the wait ends, however it ends.
+struct PendingGuard(String);
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        PENDING.lock().remove(&self.0);
+    }
+}
+
+/// Lets a call through unless the agent made it and the workspace's policy
+/// doesn't allow `capability`. With the `Ask` policy the user is asked
+/// through a `permission-requested` event and the call waits for
+/// `respond_to_permission`; no answer within a few minutes is a denial.
+pub(crate) async fn authorize(
+    app: &AppHandle,
+    actor: Actor,
+    capability: Capability,
+    target: &str,
+) -> Result<(), String> {
+    if actor == Actor::User {
+        return Ok(());
+    }
+    let workspace = workspace();
+    // A policy that can't be read falls back to asking
+    let decision = load_rules(&workspace)
+        .map(|rules| rules[&capability])
+        .unwrap_or_else(|e| {
+            warn!("Failed to read the permission policy: {}", e);
+            Decision::Ask
+        });
+    match decision {
+        Decision::Allow => return Ok(()),
+        Decision::Deny => {
+            return Err(format!(
+                "The agent is not allowed to {} in this workspace",
+                describe(capability)
+            ))
+        }
+        Decision::Ask => {}
+    }
+
+    let request = PermissionRequest {
+        id: Uuid::new_v4().to_string(),
+        capability,
+        target: target.to_string(),
+        workspace,
+        requested_at: Utc::now().timestamp_millis(),
+    };
+    let (respond, response) = oneshot::channel();
+    PENDING.lock().insert(
+        request.id.clone(),
+        PendingApproval {
+            request: request.clone(),
+            respond,
+        },
+    );
+    let _guard = PendingGuard(request.id.clone());
+
+    if let Err(e) = app.emit("permission-requested", &request) {
+        warn!("Failed to emit permission-requested: {}", e);
+    }
+    notify(
+        app,
+        NotificationLevel::Warning,
+        "permissions",
+        "The agent is waiting for approval",
+        Some(format!("{}: {}", describe(capability), target)),
+    );
+
+    match tokio::time::timeout(APPROVAL_TIMEOUT, response).await {
+        Ok(Ok(true)) => Ok(()),
+        Ok(Ok(false)) | Ok(Err(_)) => Err(format!(
+            "The user declined to let the agent {}",
+            describe(capability)
+        )),
+        Err(_) => Err(format!(
+            "No approval to {} arrived in time",
+            describe(capability)
+        )),
+    }
+}
+
+fn describe(capability: Capability) -> &'static str {
+    match capability {
+        Capability::WriteFile => "write files",
+        Capability::DeletePath => "delete files",
+        Capability::RunCommand => "run commands",
+        Capability::GitPush => "push to a remote",
+    }
+}
+
+/// Answers a `permission-requested` event. With `remember`, the answer
+/// becomes the workspace's policy for that capability.
+#[command]
+pub async fn respond_to_permission(
+    request: Request<'_>,
+    id: String,
+    approved: bool,
+    remember: Option<bool>,
+) -> Result<(), String> {
+    respond(
+        Actor::of(&request),
+        &id,
+        approved,
+        remember.unwrap_or(false),
+    )
+}
+
+/// Only the user may answer, or the agent could approve its own requests.
+fn respond(actor: Actor, id: &str, approved: bool, remember: bool) -> Result<(), String> {
+    if actor == Actor::Agent {
+        return Err("Only the user can answer permission requests".to_string());
+    }
+    let pending = PENDING
+        .lock()
+        .remove(id)
+        .ok_or_else(|| format!("No pending permission request with id {}", id))?;
+    if remember {
+        let decision = if approved {
+            Decision::Allow
+        } else {
+            Decision::Deny
+        };
+        let request = &pending.request;
+        save_decision(&request.workspace, request.capability, decision)?;
+        info!(
+            "Agent permission to {} in {} set to {:?}",
+            describe(request.capability),
+            request.workspace,
+            decision
+        );
+    }
+    // The caller may have given up waiting already
+    let _ = pending.respond.send(approved);
+    Ok(())
+}
+
+/// Requests still waiting for an answer, oldest first.
+#[command]
+pub async fn list_pending_permissions() -> Result<Vec<PermissionRequest>, String> {
+    let mut requests: Vec<PermissionRequest> = PENDING
+        .lock()
+        .values()
+        .map(|pending| pending.request.clone())
+        .collect();
+    requests.sort_by_key(|request| request.requested_at);
+    Ok(requests)
+}
+
+#[command]
+pub async fn get_permission_policy() -> Result<PermissionPolicy, String> {
+    let workspace = workspace();
+    let rules = load_rules(&workspace)?;
+    Ok(PermissionPolicy { workspace, rules })
+}
+
+/// Pre-grants, denies or resets to asking for one capability in the current
+/// workspace.
+#[command]
+pub async fn set_permission_policy(
+    request: Request<'_>,
+    capability: Capability,
+    decision: Decision,
+) -> Result<PermissionPolicy, String> {
+    set_policy(Actor::of(&request), capability, decision)
+}
+
+fn set_policy(
+    actor: Actor,
+    capability: Capability,
+    decision: Decision,
+) -> Result<PermissionPolicy, String> {
+    if actor == Actor::Agent {
+        return Err("Only the user can change the agent's permissions".to_string());
+    }
+    let workspace = workspace();
+    save_decision(&workspace, capability, decision)?;
+    let rules = load_rules(&workspace)?;
+    Ok(PermissionPolicy { workspace, rules })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn agent() -> Actor {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACTOR_HEADER, HeaderValue::from_static("agent"));
+        Actor::from_headers(&headers)
+    }
+
+    #[test]
+    fn agent_cannot_answer_its_own_request() {
+        let id = Uuid::new_v4().to_string();
+        let (respond_tx, mut response) = oneshot::channel();
+        let request = PermissionRequest {
+            id: id.clone(),
+            capability: Capability::RunCommand,
+            target: "rm -rf ~".to_string(),
+            workspace: "/workspace".to_string(),
+            requested_at: 0,
+        };
+        PENDING.lock().insert(
+            id.clone(),
+            PendingApproval {
+                request,
+                respond: respond_tx,
+            },
+        );
+
+        assert!(respond(agent(), &id, true, false).is_err());
+        // The request is still waiting for the user
+        assert!(PENDING.lock().contains_key(&id));
+        assert!(response.try_recv().is_err());
+
+        respond(Actor::User, &id, false, false).unwrap();
+        assert_eq!(response.try_recv(), Ok(false));
+    }
+
+    #[test]
+    fn agent_cannot_change_the_policy() {
+        let error = set_policy(agent(), Capability::GitPush, Decision::Allow).unwrap_err();
+        assert_eq!(error, "Only the user can change the agent's permissions");
+    }
+}
@@ -7,6 +7,7 @@ use std::path::Path;
 use std::process;
 use sysinfo::{ProcessesToUpdate, System};
 use tauri::command;
+use tracing::{debug, info, warn};
 
 /// Configuration options for initializing the ProcessManager.
 #[derive(Debug, Serialize, Deserialize)]
@@ -92,17 +93,17 @@ impl ProcessManager {
             let target_name = self.app_name.to_ascii_lowercase();
 
             if process_name.contains(&target_name) && pid.as_u32() != current_pid {
-                println!(
+                info!(
                     "Terminating other instance: PID {}, Name {:?}",
                     pid.as_u32(),
                     process.name()
                 );
 
                 if process.kill() {
-                    println!("Successfully terminated PID {}", pid.as_u32());
+                    info!("Successfully terminated PID {}", pid.as_u32());
                     terminated_count += 1;
                 } else {
-                    println!("Failed to terminate PID {}", pid.as_u32());
+                    warn!("Failed to terminate PID {}", pid.as_u32());
                 }
             }
         }
@@ -118,14 +119,14 @@ impl ProcessManager {
     /// * `Err(anyhow::Error)` if removal fails.
     fn cleanup_db_locks(&self) -> Result<()> {
         let lock_file = Path::new(&self.db_path).join("LOCK");
-        println!("Looking for lock file at {:?}", lock_file);
+        debug!("Looking for lock file at {:?}", lock_file);
 
         if lock_file.exists() {
             fs::remove_file(&lock_file)
                 .with_context(|| format!("Failed to remove lock file at {:?}", lock_file))?;
-            println!("Successfully removed lock file at {:?}", lock_file);
+            info!("Successfully removed lock file at {:?}", lock_file);
         } else {
-            println!("No lock file found at {:?}", lock_file);
+            debug!("No lock file found at {:?}", lock_file);
         }
 
         Ok(())
@@ -157,7 +158,7 @@ pub async fn initialize_process_manager(
     // Retrieve the database path from environment variables or use default
     let db_path = std::env::var("DB_PATH").unwrap_or_else(|_| "storage/storage.db".to_string());
 
-    println!("ProcessManager initializing with DB_PATH: {}", db_path);
+    info!("ProcessManager initializing with DB_PATH: {}", db_path);
 
     // Use provided options or default options
     let options = options.unwrap_or_default();
@@ -0,0 +1,343 @@
+// src/commands/proxy.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter, State};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::api::anthropic_api_key;
+use super::auth::vault;
+use super::offline::ensure_reachable;
+use super::recorder::{self, RecordedRequest};
+use super::redaction;
+use crate::config::{AppConfig, ProviderProfile};
+use crate::error::MightyError;
+use crate::http_client;
+use crate::secrets::Redactor;
+use crate::state::AppState;
+
+const ANTHROPIC: &str = "anthropic";
+/// Headers only the proxy sets; callers can't override them.
+const RESERVED_HEADERS: &[&str] = &["host", "content-length", "transfer-encoding", "connection"];
+
+/// The Anthropic profile used unless `providers.anthropic` overrides it.
+fn builtin_profile(provider: &str) -> Option<ProviderProfile> {
+    (provider == ANTHROPIC).then(|| ProviderProfile {
+        base_url: "https://api.anthropic.com".to_string(),
+        headers: HashMap::from([
+            ("x-api-key".to_string(), "{api_key}".to_string()),
+            ("anthropic-version".to_string(), "2023-06-01".to_string()),
+        ]),
+        secret: None,
+    })
+}
+
+fn profile(config: &AppConfig, provider: &str) -> Option<ProviderProfile> {
+    config
+        .providers
+        .get(provider)
+        .cloned()
+        .or_else(|| builtin_profile(provider))
+}
+
+fn host_of(url: &str) -> Option<String> {
+    reqwest::Url::parse(url)
+        .ok()?
+        .host_str()
+        .map(|host| host.to_lowercase())
+}
+
+/// Hosts requests may go to: every provider's and `proxy.allowed_hosts`.
+fn allowed_hosts(config: &AppConfig) -> Vec<String> {
+    let mut hosts: Vec<String> = config
+        .providers
+        .values()
+        .cloned()
+        .chain(builtin_profile(ANTHROPIC))
+        .filter_map(|profile| host_of(&profile.base_url))
+        .collect();
+    if let Some(proxy) = &config.proxy {
+        hosts.extend(proxy.allowed_hosts.iter().map(|host| host.to_lowercase()));
+    }
+    hosts
+}
+
+/// The key for `provider`, from the vault secret its profile names. The
+/// built-in Anthropic profile also falls back to `anthropic.api_key`.
+async fn provider_api_key(
+    state: &AppState,
+    provider: &str,
+    profile: &ProviderProfile,
+) -> Option<String> {
+    if profile.secret.is_none() && provider == ANTHROPIC {
+        return anthropic_api_key(state).await;
+    }
+    let secret = profile.secret.as_deref().unwrap_or(provider);
+    vault().get(secret).unwrap_or_else(|e| {
+        error!("{}", e);
+        None
+    })
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProxyRequest {
+    /// Streamed chunks carry this id; one is made up when not given.
+    pub id: Option<String>,
+    /// A provider profile name, e.g. "anthropic".
+    pub provider: String,
+    /// Appended to the provider's base URL, e.g. "/v1/messages".
+    pub path: String,
+    /// POST by default.
+    pub method: Option<String>,
+    /// Extra headers; the profile's headers win over these.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub body: Option<serde_json::Value>,
+    /// Passes the response body through as `proxy-chunk` events as it
+    /// arrives, ending with `proxy-done`, instead of returning it.
+    #[serde(default)]
+    pub stream: bool,
+    /// The recording being replayed, set by `replay_request`.
+    #[serde(skip)]
+    pub(crate) replay_of: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProxyResponse {
+    pub id: String,
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    /// `None` when the body is streamed.
+    pub body: Option<String>,
+}
+
+/// Payload of the `proxy-chunk` event.
+#[derive(Debug, Clone, Serialize)]
+struct ChunkEvent<'a> {
+    id: &'a str,
+    chunk: &'a str,
+}
+
+/// Payload of the `proxy-done` event.
+#[derive(Debug, Clone, Serialize)]
+struct DoneEvent<'a> {
+    id: &'a str,
+    error: Option<String>,
+}
+
+/// Takes the complete UTF-8 text off the front of `pending`, leaving a
+/// character split across chunks for the next one.
+fn take_text(pending: &mut Vec<u8>) -> String {
+    let complete = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => pending.len(),
+    };
+    let rest = pending.split_off(complete);
+    let text = String::from_utf8_lossy(pending).into_owned();
+    *pending = rest;
+    text
+}
+
+fn emit_chunk(app: &AppHandle, id: &str, chunk: &str) {
+    if let Err(e) = app.emit("proxy-chunk", &ChunkEvent { id, chunk }) {
+        warn!("Failed to emit proxy chunk: {}", e);
+    }
+}
+
+async fn stream_body(
+    app: AppHandle,
+    id: String,
+    mut response: reqwest::Response,
+    mut recording: RecordedRequest,
+    api_key: String,
+) {
+    let mut transcript = recorder::is_enabled().then(String::new);
+    let mut pending = Vec::new();
+    let error = loop {
+        match response.chunk().await {
+            Ok(Some(bytes)) => {
+                pending.extend_from_slice(&bytes);
+                let chunk = take_text(&mut pending);
+                if chunk.is_empty() {
+                    continue;
+                }
+                emit_chunk(&app, &id, &chunk);
+                if let Some(transcript) = transcript.as_mut() {
+                    transcript.push_str(&chunk);
+                }
+            }
+            Ok(None) => break None,
+            Err(e) => break Some(e.to_string()),
+        }
+    };
+    if !pending.is_empty() {
+        let chunk = String::from_utf8_lossy(&pending);
+        emit_chunk(&app, &id, &chunk);
+        if let Some(transcript) = transcript.as_mut() {
+            transcript.push_str(&chunk);
+        }
+    }
+
+    recording.response_body = transcript;
+    recording.error = error.clone();
+    recorder::record(recording, &[api_key.as_str()]);
+    if let Err(e) = app.emit("proxy-done", &DoneEvent { id: &id, error }) {
+        warn!("Failed to emit proxy completion: {}", e);
+    }
+}
+
+/// Sends `request` to its provider with the profile's headers and key, and
+/// records the exchange. Secrets in the body are redacted before it leaves
+/// and restored in a buffered reply; streamed replies keep the placeholders.
+pub(crate) async fn forward(
+    app: &AppHandle,
+    state: &AppState,
+    request: ProxyRequest,
+) -> Result<ProxyResponse, MightyError> {
+    let config = state.config.lock().await.clone();
+    let profile = profile(&config, &request.provider).ok_or_else(|| {
+        MightyError::config(
+            "UNKNOWN_PROVIDER",
+            &format!("No provider named {}", request.provider),
+        )
+    })?;
+
+    let url = format!(
+        "{}/{}",
+        profile.base_url.trim_end_matches('/'),
+        request.path.trim_start_matches('/')
+    );
+    let url = reqwest::Url::parse(&url)
+        .map_err(|e| MightyError::request("INVALID_URL", &format!("Invalid URL {}: {}", url, e)))?;
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    if !allowed_hosts(&config).contains(&host) {
+        warn!("Blocked a proxy request to {}", host);
+        return Err(MightyError::request(
+            "HOST_NOT_ALLOWED",
+            &format!("{} is not an allowed destination", host),
+        ));
+    }
+    ensure_reachable(url.as_str())?;
+
+    let api_key = provider_api_key(state, &request.provider, &profile).await;
+    let method = request.method.as_deref().unwrap_or("POST");
+    let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes()).map_err(|_| {
+        MightyError::request("INVALID_METHOD", &format!("Invalid method {}", method))
+    })?;
+
+    let mut headers = HashMap::new();
+    for (name, value) in &request.headers {
+        if !RESERVED_HEADERS.contains(&name.to_lowercase().as_str()) {
+            headers.insert(name.to_lowercase(), value.clone());
+        }
+    }
+    for (name, value) in &profile.headers {
+        let value = if value.contains("{api_key}") {
+            let key = api_key.as_deref().ok_or_else(|| {
+                MightyError::auth(
+                    "MISSING_API_KEY",
+                    &format!("API key for {} not configured.", request.provider),
+                )
+            })?;
+            value.replace("{api_key}", key)
+        } else {
+            value.clone()
+        };
+        headers.insert(name.to_lowercase(), value);
+    }
+
+    let client = http_client::client(&request.provider)?;
+    let mut builder = client.request(method.clone(), url.clone());
+    for (name, value) in &headers {
+        builder = builder.header(name, value);
+    }
+    let mut redactor = Redactor::new();
+    let mut body = request.body.clone();
+    if let Some(body) = &mut body {
+        redactor.redact_json(body);
+        builder = builder.json(body);
+    }
+
+    let mut recording = RecordedRequest::new(
+        &request.provider,
+        method.as_str(),
+        &request.path,
+        &headers,
+        body.as_ref(),
+    );
+    recording.streamed = request.stream;
+    recording.replay_of = request.replay_of;
+    let api_key = api_key.unwrap_or_default();
+
+    let id = request.id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    if let Some(body) = &body {
+        redaction::remember(&id, &request.provider, body, redactor.redactions());
+    }
+    info!(
+        "Proxying {} {} for {}",
+        method,
+        url.path(),
+        request.provider
+    );
+    let response = match builder.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Proxy request failed: {}", e);
+            recording.error = Some(e.to_string());
+            recorder::record(recording, &[api_key.as_str()]);
+            return Err(MightyError::Service {
+                code: "REQUEST_FAILED".to_string(),
+                message: format!("Request to {} failed", request.provider),
+                details: Some(e.to_string()),
+            });
+        }
+    };
+
+    let status = response.status().as_u16();
+    recording.status = Some(status);
+    let response_headers = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+
+    let body = if request.stream {
+        tauri::async_runtime::spawn(stream_body(
+            app.clone(),
+            id.clone(),
+            response,
+            recording,
+            api_key,
+        ));
+        None
+    } else {
+        let text = response.text().await;
+        match &text {
+            Ok(text) => recording.response_body = Some(text.clone()),
+            Err(e) => recording.error = Some(e.to_string()),
+        }
+        recorder::record(recording, &[api_key.as_str()]);
+        Some(redactor.restore_json(&text.map_err(|e| e.to_string())?))
+    };
+
+    Ok(ProxyResponse {
+        id,
+        status,
+        headers: response_headers,
+        body,
+    })
+}
+
+/// Forwards a request to an LLM provider, adding the headers and key its
+/// profile in `AppConfig.providers` calls for. Only hosts of configured
+/// providers and `proxy.allowed_hosts` can be reached.
+#[tauri::command]
+pub async fn proxy_request(
+    app: AppHandle,
+    request: ProxyRequest,
+    state: State<'_, AppState>,
+) -> Result<ProxyResponse, MightyError> {
+    forward(&app, &state, request).await
+}
@@ -0,0 +1,218 @@
+// src/commands/recent.rs
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::command;
+use tracing::warn;
+
+use super::fs::{get_project_root, resolve_workspace_path};
+use super::storage::{delete_record, get_record, put_record, records_with_prefix};
+
+const STORAGE_PREFIX: &str = "recent:";
+/// Items kept per kind and workspace; the lowest scored are dropped.
+const MAX_ITEMS_PER_KIND: usize = 200;
+/// Uses remembered per item for scoring.
+const USES_KEPT: usize = 10;
+const DEFAULT_LIMIT: usize = 20;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+/// How much a use counts by its age, as (younger than, weight).
+const AGE_WEIGHTS: [(i64, f64); 4] = [
+    (4 * DAY_MS, 100.0),
+    (14 * DAY_MS, 70.0),
+    (31 * DAY_MS, 50.0),
+    (90 * DAY_MS, 30.0),
+];
+const OLD_WEIGHT: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecentKind {
+    /// Workspace-relative paths of opened files.
+    File,
+    /// Tasks and dev servers started.
+    Task,
+    Search,
+    /// Command palette commands.
+    Command,
+}
+
+impl RecentKind {
+    const ALL: [RecentKind; 4] = [
+        RecentKind::File,
+        RecentKind::Task,
+        RecentKind::Search,
+        RecentKind::Command,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            RecentKind::File => "file",
+            RecentKind::Task => "task",
+            RecentKind::Search => "search",
+            RecentKind::Command => "command",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentItem {
+    pub kind: RecentKind,
+    pub value: String,
+    /// What to show instead of the value, e.g. a task's name.
+    pub label: Option<String>,
+    /// Uses ever recorded.
+    pub count: u64,
+    /// Milliseconds since the Unix epoch.
+    pub last_used: i64,
+    /// Most recent uses, newest last.
+    #[serde(default)]
+    uses: Vec<i64>,
+    /// Frecency: how often and how recently the item was used.
+    #[serde(default)]
+    pub score: f64,
+}
+
+impl RecentItem {
+    fn frecency(&self, now: i64) -> f64 {
+        if self.uses.is_empty() {
+            return 0.0;
+        }
+        let weights: f64 = self
+            .uses
+            .iter()
+            .map(|used| {
+                let age = now - used;
+                AGE_WEIGHTS
+                    .iter()
+                    .find(|(younger_than, _)| age < *younger_than)
+                    .map_or(OLD_WEIGHT, |(_, weight)| *weight)
+            })
+            .sum();
+        self.count as f64 * weights / self.uses.len() as f64
+    }
+}
+
+fn kind_prefix(kind: RecentKind) -> String {
+    format!(
+        "{}{}|{}|",
+        STORAGE_PREFIX,
+        get_project_root().to_string_lossy(),
+        kind.name()
+    )
+}
+
+/// The workspace's items of one kind, best first.
+fn load(kind: RecentKind) -> Result<Vec<(String, RecentItem)>, String> {
+    let now = Utc::now().timestamp_millis();
+    let mut items: Vec<(String, RecentItem)> = records_with_prefix(&kind_prefix(kind))
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|(key, json)| {
+            let mut item: RecentItem = serde_json::from_str(&json).ok()?;
+            item.score = item.frecency(now);
+            Some((key, item))
+        })
+        .collect();
+    items.sort_by(|(_, a), (_, b)| b.score.total_cmp(&a.score));
+    Ok(items)
+}
+
+fn record(kind: RecentKind, value: &str, label: Option<String>) -> Result<(), String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(());
+    }
+    let key = format!("{}{}", kind_prefix(kind), value);
+    let now = Utc::now().timestamp_millis();
+    let mut item = match get_record(&key).map_err(|e| e.to_string())? {
+        Some(json) => serde_json::from_str::<RecentItem>(&json).ok(),
+        None => None,
+    }
+    .unwrap_or_else(|| RecentItem {
+        kind,
+        value: value.to_string(),
+        label: None,
+        count: 0,
+        last_used: now,
+        uses: Vec::new(),
+        score: 0.0,
+    });
+    if label.is_some() {
+        item.label = label;
+    }
+    item.count += 1;
+    item.last_used = now;
+    item.uses.push(now);
+    let excess = item.uses.len().saturating_sub(USES_KEPT);
+    item.uses.drain(..excess);
+
+    let json = serde_json::to_string(&item).map_err(|e| e.to_string())?;
+    put_record(&key, &json).map_err(|e| e.to_string())?;
+
+    for (key, _) in load(kind)?.iter().skip(MAX_ITEMS_PER_KIND) {
+        delete_record(key).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Records a use from inside the backend, where failing to record
+/// shouldn't fail what was used.
+pub(crate) fn record_use(kind: RecentKind, value: &str, label: Option<String>) {
+    if let Err(e) = record(kind, value, label) {
+        warn!("Failed to record recent {} {}: {}", kind.name(), value, e);
+    }
+}
+
+/// Records that an item was used now. Searches and started tasks are
+/// recorded by the backend; the frontend records opened files and palette
+/// commands.
+#[command]
+pub async fn record_recent_item(
+    kind: RecentKind,
+    value: String,
+    label: Option<String>,
+) -> Result<(), String> {
+    record(kind, &value, label)
+}
+
+/// The workspace's recent items of `kind`, or of every kind, by frecency.
+/// Files deleted since are left out.
+#[command]
+pub async fn get_recent_items(
+    kind: Option<RecentKind>,
+    limit: Option<usize>,
+) -> Result<Vec<RecentItem>, String> {
+    let kinds = match kind {
+        Some(kind) => vec![kind],
+        None => RecentKind::ALL.to_vec(),
+    };
+    let mut items = Vec::new();
+    for kind in kinds {
+        items.extend(load(kind)?.into_iter().map(|(_, item)| item));
+    }
+    items.retain(|item| {
+        item.kind != RecentKind::File
+            || resolve_workspace_path(&item.value).is_ok_and(|path| path.exists())
+    });
+    items.sort_by(|a, b| b.score.total_cmp(&a.score));
+    items.truncate(limit.unwrap_or(DEFAULT_LIMIT));
+    Ok(items)
+}
+
+/// Forgets one item, or every item of `kind` when `value` is omitted.
+#[command]
+pub async fn remove_recent_items(kind: RecentKind, value: Option<String>) -> Result<usize, String> {
+    let prefix = kind_prefix(kind);
+    let keys: Vec<String> = match value {
+        Some(value) => vec![format!("{}{}", prefix, value.trim())],
+        None => load(kind)?.into_iter().map(|(key, _)| key).collect(),
+    };
+    let mut removed = 0;
+    for key in keys {
+        if get_record(&key).map_err(|e| e.to_string())?.is_some() {
+            delete_record(&key).map_err(|e| e.to_string())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
@@ -0,0 +1,243 @@
+// src/commands/recorder.rs
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tauri::{command, AppHandle, State};
+use tracing::warn;
+use uuid::Uuid;
+
+use super::proxy::{forward, ProxyRequest, ProxyResponse};
+use super::storage::{delete_record, get_record, put_record, records_with_prefix};
+use crate::config::RecorderConfig;
+use crate::error::MightyError;
+use crate::state::AppState;
+
+/// Keys sort by start time, so a prefix scan returns recordings oldest first.
+const STORAGE_PREFIX: &str = "recorded_request:";
+const DEFAULT_MAX_ENTRIES: usize = 500;
+/// Response bodies are cut off past this many bytes.
+const MAX_RESPONSE_BYTES: usize = 256 * 1024;
+const DEFAULT_LIMIT: usize = 50;
+const REDACTED: &str = "[REDACTED]";
+/// Header names containing any of these have their values redacted.
+const SENSITIVE_HEADERS: &[&str] = &["authorization", "cookie", "key", "token", "secret"];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static MAX_ENTRIES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_ENTRIES);
+
+/// An LLM request as sent, minus credentials, and how it went.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub id: String,
+    /// Provider profile name, e.g. "anthropic".
+    pub provider: String,
+    pub method: String,
+    /// Relative to the provider's base URL.
+    pub path: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<Value>,
+    pub status: Option<u16>,
+    pub response_body: Option<String>,
+    #[serde(default)]
+    pub response_truncated: bool,
+    /// Set when the request failed before a response arrived.
+    pub error: Option<String>,
+    #[serde(default)]
+    pub streamed: bool,
+    /// The recording this one replayed.
+    pub replay_of: Option<String>,
+    /// Milliseconds since the Unix epoch.
+    pub started_at: i64,
+    pub duration_ms: u64,
+}
+
+impl RecordedRequest {
+    /// Starts a recording of a request about to be sent.
+    pub(crate) fn new(
+        provider: &str,
+        method: &str,
+        path: &str,
+        headers: &HashMap<String, String>,
+        body: Option<&Value>,
+    ) -> Self {
+        Self {
+            id: String::new(),
+            provider: provider.to_string(),
+            method: method.to_string(),
+            path: path.to_string(),
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_lowercase(), value.clone()))
+                .collect(),
+            body: body.cloned(),
+            status: None,
+            response_body: None,
+            response_truncated: false,
+            error: None,
+            streamed: false,
+            replay_of: None,
+            started_at: Utc::now().timestamp_millis(),
+            duration_ms: 0,
+        }
+    }
+}
+
+/// Applies the `[recorder]` settings.
+pub(crate) fn configure(config: Option<&RecorderConfig>) {
+    ENABLED.store(
+        config.is_some_and(|recorder| recorder.enabled),
+        Ordering::Relaxed,
+    );
+    MAX_ENTRIES.store(
+        config
+            .and_then(|recorder| recorder.max_entries)
+            .unwrap_or(DEFAULT_MAX_ENTRIES),
+        Ordering::Relaxed,
+    );
+}
+
+pub(crate) fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn storage_key(id: &str) -> String {
+    format!("{}{}", STORAGE_PREFIX, id)
+}
+
+/// Replaces credentials in `entry`: sensitive headers and every occurrence
+/// of `secrets` anywhere in it.
+fn redact(entry: &mut RecordedRequest, secrets: &[&str]) {
+    for (name, value) in entry.headers.iter_mut() {
+        if SENSITIVE_HEADERS.iter().any(|word| name.contains(word)) {
+            *value = REDACTED.to_string();
+        }
+    }
+    let secrets: Vec<&str> = secrets
+        .iter()
+        .copied()
+        .filter(|secret| !secret.is_empty())
+        .collect();
+    if secrets.is_empty() {
+        return;
+    }
+    let scrub = |text: &str| {
+        secrets.iter().fold(text.to_string(), |text, secret| {
+            text.replace(secret, REDACTED)
+        })
+    };
+    for value in entry.headers.values_mut() {
+        *value = scrub(value);
+    }
+    if let Some(body) = &entry.body {
+        entry.body = serde_json::from_str(&scrub(&body.to_string())).ok();
+    }
+    entry.response_body = entry.response_body.as_deref().map(scrub);
+    entry.error = entry.error.as_deref().map(scrub);
+}
+
+/// Saves a finished request when recording is on, redacting credentials
+/// and `secrets` first. Failures are logged rather than returned, since a
+/// recording is never worth failing a request over.
+pub(crate) fn record(mut entry: RecordedRequest, secrets: &[&str]) {
+    if !is_enabled() {
+        return;
+    }
+    entry.id = format!("{:013}-{}", entry.started_at, Uuid::new_v4().simple());
+    entry.duration_ms = (Utc::now().timestamp_millis() - entry.started_at).max(0) as u64;
+    if let Some(body) = entry.response_body.as_mut() {
+        if body.len() > MAX_RESPONSE_BYTES {
+            let mut end = MAX_RESPONSE_BYTES;
+            while !body.is_char_boundary(end) {
+                end -= 1;
+            }
+            body.truncate(end);
+            entry.response_truncated = true;
+        }
+    }
+    redact(&mut entry, secrets);
+
+    let result = serde_json::to_string(&entry)
+        .map_err(|e| e.to_string())
+        .and_then(|json| put_record(&storage_key(&entry.id), &json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("Failed to record a request: {}", e);
+        return;
+    }
+    prune();
+}
+
+fn prune() {
+    let records = match records_with_prefix(STORAGE_PREFIX) {
+        Ok(records) => records,
+        Err(e) => {
+            warn!("Failed to load recorded requests: {}", e);
+            return;
+        }
+    };
+    let excess = records
+        .len()
+        .saturating_sub(MAX_ENTRIES.load(Ordering::Relaxed));
+    for (key, _) in records.into_iter().take(excess) {
+        if let Err(e) = delete_record(&key) {
+            warn!("Failed to prune recorded requests: {}", e);
+            return;
+        }
+    }
+}
+
+/// Recorded requests, newest first, optionally only those to `provider`
+/// or that failed.
+#[command]
+pub async fn list_recorded_requests(
+    provider: Option<String>,
+    failed_only: Option<bool>,
+    limit: Option<usize>,
+) -> Result<Vec<RecordedRequest>, MightyError> {
+    let records = records_with_prefix(STORAGE_PREFIX)?;
+    let failed_only = failed_only.unwrap_or(false);
+    Ok(records
+        .into_iter()
+        .rev()
+        .filter_map(|(_, json)| serde_json::from_str::<RecordedRequest>(&json).ok())
+        .filter(|entry| provider.as_ref().is_none_or(|name| &entry.provider == name))
+        .filter(|entry| {
+            !failed_only || entry.error.is_some() || entry.status.is_some_and(|s| s >= 400)
+        })
+        .take(limit.unwrap_or(DEFAULT_LIMIT))
+        .collect())
+}
+
+/// Sends a recorded request again through the proxy, with the provider's
+/// current credentials, and returns the new response. The replay is
+/// recorded too, pointing back at the original.
+#[command]
+pub async fn replay_request(
+    app: AppHandle,
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<ProxyResponse, MightyError> {
+    let json = get_record(&storage_key(&id))?
+        .ok_or_else(|| MightyError::request("NOT_FOUND", &format!("No recorded request {}", id)))?;
+    let entry: RecordedRequest = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+
+    // Redacted headers are left for the provider profile to fill in again
+    let headers = entry
+        .headers
+        .into_iter()
+        .filter(|(_, value)| !value.contains(REDACTED))
+        .collect();
+    let request = ProxyRequest {
+        id: None,
+        provider: entry.provider,
+        path: entry.path,
+        method: Some(entry.method),
+        headers,
+        body: entry.body,
+        stream: false,
+        replay_of: Some(id),
+    };
+    forward(&app, &state, request).await
+}
@@ -0,0 +1,199 @@
+// src/commands/redaction.rs
+
+use chrono::Utc;
+use glob::Pattern;
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use regex::{Captures, Regex};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, VecDeque};
+use std::env;
+use std::path::Path;
+use tauri::command;
+use tracing::debug;
+
+use super::fs::get_project_root;
+use crate::config::RedactionConfig;
+
+/// Outgoing payloads kept for `preview_outgoing_payload`, newest last.
+const MAX_PAYLOADS: usize = 50;
+/// Account names too common in code to replace, or too short to be worth it.
+const GENERIC_USERNAMES: &[&str] = &["root", "admin", "user", "runner", "ubuntu", "vagrant"];
+const MIN_USERNAME_LENGTH: usize = 3;
+
+/// How many of each kind of thing were redacted, e.g. `home_path` or
+/// `aws_access_key`.
+pub type Redactions = BTreeMap<String, usize>;
+
+struct Policy {
+    home: Option<Regex>,
+    username: Option<Regex>,
+    sensitive: Vec<Pattern>,
+}
+
+static POLICY: Lazy<RwLock<Policy>> = Lazy::new(|| RwLock::new(policy(&Default::default())));
+static OUTGOING: Lazy<Mutex<VecDeque<OutgoingPayload>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+
+/// Runs of path characters containing a separator.
+static PATH_TOKEN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[\w.~@+-]*(?:[/\\][\w.@+-]+)+").expect("the pattern is valid"));
+
+/// A request body as it left the machine.
+#[derive(Debug, Clone, Serialize)]
+pub struct OutgoingPayload {
+    pub request_id: String,
+    /// e.g. "anthropic" or "greptile".
+    pub destination: String,
+    pub payload: Value,
+    pub redactions: Redactions,
+    /// Milliseconds since the Unix epoch.
+    pub sent_at: i64,
+}
+
+fn username() -> Option<String> {
+    env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .ok()
+        .or_else(|| {
+            dirs::home_dir().and_then(|home| {
+                home.file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+            })
+        })
+        .filter(|name| {
+            name.len() >= MIN_USERNAME_LENGTH
+                && !GENERIC_USERNAMES.contains(&name.to_lowercase().as_str())
+        })
+}
+
+fn policy(config: &RedactionConfig) -> Policy {
+    let home = dirs::home_dir()
+        .filter(|_| config.home_paths.unwrap_or(true))
+        .map(|home| {
+            home.to_string_lossy()
+                .trim_end_matches(['/', '\\'])
+                .to_string()
+        })
+        .filter(|home| !home.is_empty())
+        .map(|home| Regex::new(&format!(r"{}\b", regex::escape(&home))).expect("escaped"));
+    let username = username()
+        .filter(|_| config.usernames.unwrap_or(true))
+        .map(|name| Regex::new(&format!(r"\b{}\b", regex::escape(&name))).expect("escaped"));
+    // Invalid globs are reported when the config is loaded
+    let sensitive = config
+        .sensitive_globs
+        .iter()
+        .filter_map(|glob| Pattern::new(glob).ok())
+        .collect();
+    Policy {
+        home,
+        username,
+        sensitive,
+    }
+}
+
+/// Applies the `[redaction]` settings.
+pub(crate) fn configure(config: Option<&RedactionConfig>) {
+    *POLICY.write() = policy(&config.cloned().unwrap_or_default());
+}
+
+fn is_sensitive(sensitive: &[Pattern], root: &Path, token: &str) -> bool {
+    let path = Path::new(token);
+    let relative = path
+        .strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/");
+    let relative = relative.trim_start_matches("./");
+    sensitive.iter().any(|pattern| pattern.matches(relative))
+}
+
+fn replace_counted(
+    text: &str,
+    regex: &Regex,
+    replacement: &str,
+    kind: &str,
+    redactions: &mut Redactions,
+) -> String {
+    let count = regex.find_iter(text).count();
+    if count == 0 {
+        return text.to_string();
+    }
+    *redactions.entry(kind.to_string()).or_default() += count;
+    regex.replace_all(text, replacement).into_owned()
+}
+
+/// `text` without mentions of sensitive files, with home directories
+/// shortened to `~` and the user name replaced, counting each into
+/// `redactions`.
+pub(crate) fn scrub(text: &str, redactions: &mut Redactions) -> String {
+    let policy = POLICY.read();
+    let mut text = text.to_string();
+
+    // Sensitive paths first, while absolute ones still match the workspace
+    if !policy.sensitive.is_empty() {
+        let root = get_project_root();
+        let mut masked = 0;
+        text = PATH_TOKEN
+            .replace_all(&text, |captures: &Captures| {
+                if is_sensitive(&policy.sensitive, &root, &captures[0]) {
+                    masked += 1;
+                    "[REDACTED:path]".to_string()
+                } else {
+                    captures[0].to_string()
+                }
+            })
+            .into_owned();
+        if masked > 0 {
+            *redactions.entry("sensitive_path".to_string()).or_default() += masked;
+        }
+    }
+    if let Some(home) = &policy.home {
+        text = replace_counted(&text, home, "~", "home_path", redactions);
+    }
+    if let Some(username) = &policy.username {
+        text = replace_counted(&text, username, "[REDACTED:user]", "username", redactions);
+    }
+    text
+}
+
+/// Keeps what was sent for `request_id`, for `preview_outgoing_payload`.
+pub(crate) fn remember(
+    request_id: &str,
+    destination: &str,
+    payload: &Value,
+    redactions: &Redactions,
+) {
+    let mut outgoing = OUTGOING.lock();
+    outgoing.retain(|sent| sent.request_id != request_id);
+    if outgoing.len() >= MAX_PAYLOADS {
+        outgoing.pop_front();
+    }
+    outgoing.push_back(OutgoingPayload {
+        request_id: request_id.to_string(),
+        destination: destination.to_string(),
+        payload: payload.clone(),
+        redactions: redactions.clone(),
+        sent_at: Utc::now().timestamp_millis(),
+    });
+    if !redactions.is_empty() {
+        debug!(
+            "Redacted {:?} from request {} to {}",
+            redactions, request_id, destination
+        );
+    }
+}
+
+/// The body of one of the last few requests to a model provider or
+/// Greptile exactly as it was sent, after redaction, with counts of what
+/// was taken out. Kept in memory only, whether or not the recorder is on.
+#[command]
+pub async fn preview_outgoing_payload(request_id: String) -> Result<OutgoingPayload, String> {
+    OUTGOING
+        .lock()
+        .iter()
+        .find(|sent| sent.request_id == request_id)
+        .cloned()
+        .ok_or_else(|| format!("No recent outgoing request {}", request_id))
+}
@@ -0,0 +1,446 @@
+// src/commands/scheduler.rs
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeDelta, TimeZone, Timelike, Utc};
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::time::Duration;
+use tauri::command;
+use tracing::{info, warn};
+
+use super::jobs::{self, JobInfo, JobPriority, JobSpec};
+use super::storage::{get_record, put_record};
+use crate::activity;
+use crate::config::SchedulerConfig;
+
+const STORAGE_PREFIX: &str = "scheduler:";
+const TICK: Duration = Duration::from_secs(60);
+const DEFAULT_IDLE_MINUTES: u64 = 5;
+const DEFAULT_KEEP_BACKUPS: usize = 5;
+/// A due job waits at most this long for the app to go idle, in
+/// milliseconds, so a busy app still gets maintained.
+const MAX_DEFER_MS: i64 = 6 * 60 * 60 * 1000;
+/// How far ahead the next run is looked for; far enough for a schedule
+/// that only matches on February 29.
+const MAX_LOOKAHEAD_DAYS: i64 = 366 * 8;
+/// The schedule value that turns a job off.
+pub(crate) const OFF: &str = "off";
+
+/// A maintenance job the scheduler knows how to run.
+struct MaintenanceJob {
+    name: &'static str,
+    description: &'static str,
+    default_schedule: &'static str,
+    spec: fn(&Settings) -> JobSpec,
+}
+
+static MAINTENANCE: &[MaintenanceJob] = &[
+    MaintenanceJob {
+        name: "backup_storage",
+        description: "Back up the database, keeping the newest backups",
+        default_schedule: "0 2 * * 0",
+        spec: |settings| JobSpec::BackupStorage {
+            destination: None,
+            keep: Some(settings.keep_backups),
+        },
+    },
+    MaintenanceJob {
+        name: "compact_storage",
+        description: "Compact the database to reclaim space from deleted records",
+        default_schedule: "30 3 * * *",
+        spec: |_| JobSpec::CompactStorage,
+    },
+    MaintenanceJob {
+        name: "optimize_index",
+        description: "Compact the code context and conversation tables and update their indices",
+        default_schedule: "0 4 * * *",
+        spec: |_| JobSpec::OptimizeIndex,
+    },
+    MaintenanceJob {
+        name: "rotate_logs",
+        description: "Delete log files past the retention period",
+        default_schedule: "0 5 * * *",
+        spec: |_| JobSpec::RotateLogs,
+    },
+];
+
+struct Settings {
+    enabled: bool,
+    idle_ms: i64,
+    keep_backups: usize,
+    /// Schedules set in the config, by job name; `None` turns a job off.
+    overrides: HashMap<String, Option<Schedule>>,
+}
+
+impl Settings {
+    fn schedule(&self, job: &MaintenanceJob) -> Option<Schedule> {
+        match self.overrides.get(job.name) {
+            Some(schedule) => schedule.clone(),
+            None => Schedule::parse(job.default_schedule).ok(),
+        }
+    }
+}
+
+static SETTINGS: Lazy<RwLock<Settings>> = Lazy::new(|| {
+    RwLock::new(Settings {
+        enabled: true,
+        idle_ms: DEFAULT_IDLE_MINUTES as i64 * 60_000,
+        keep_backups: DEFAULT_KEEP_BACKUPS,
+        overrides: HashMap::new(),
+    })
+});
+static STARTED: AtomicBool = AtomicBool::new(false);
+/// When jobs were last seen queued or running, in milliseconds.
+static BUSY_AT: AtomicI64 = AtomicI64::new(0);
+/// Serializes runs so a tick and a manual trigger can't both start a job.
+static RUNS: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// A standard five-field cron expression: minute, hour, day of month,
+/// month and day of week (0 or 7 is Sunday), each a `*`, a value, a range
+/// `a-b`, a step `*/n` or `a-b/n`, or a comma-separated list of them. The
+/// shorthands `@hourly`, `@daily`, `@weekly` and `@monthly` work too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Schedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Both day fields are restricted, so as in cron a day matching either
+    /// one matches.
+    either_day: bool,
+}
+
+fn bit(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Parses one field into a bit mask of the values it matches.
+fn parse_field(text: &str, name: &str, min: u32, max: u32) -> Result<u64, String> {
+    let number = |value: &str| {
+        value
+            .parse::<u32>()
+            .map_err(|_| format!("{} is not a number in the {} field", value, name))
+    };
+    let mut mask = 0;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match number(step)? {
+                0 => return Err(format!("The step in {} must be above 0", part)),
+                step => (range, step),
+            },
+            None => (part, 1),
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (number(start)?, number(end)?),
+            // `5/15` runs from 5 to the end of the range
+            None if part.contains('/') => (number(range)?, max),
+            None => (number(range)?, number(range)?),
+        };
+        if start < min || end > max || start > end {
+            return Err(format!(
+                "{} is outside {}-{} in the {} field",
+                part, min, max, name
+            ));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+impl Schedule {
+    pub(crate) fn parse(expression: &str) -> Result<Self, String> {
+        let expression = expression.trim();
+        let expanded = match expression {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "'{}' should have five fields: minute, hour, day of month, month and day of week",
+                expression
+            ));
+        };
+        let mut weekdays = parse_field(weekday, "day of week", 0, 7)?;
+        if bit(weekdays, 7) {
+            weekdays = (weekdays & !(1 << 7)) | 1;
+        }
+        Ok(Self {
+            expression: expression.to_string(),
+            minutes: parse_field(minute, "minute", 0, 59)?,
+            hours: parse_field(hour, "hour", 0, 23)?,
+            days: parse_field(day, "day of month", 1, 31)?,
+            months: parse_field(month, "month", 1, 12)?,
+            weekdays,
+            either_day: day != "*" && weekday != "*",
+        })
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day = bit(self.days, date.day());
+        let weekday = bit(self.weekdays, date.weekday().num_days_from_sunday());
+        if self.either_day {
+            day || weekday
+        } else {
+            day && weekday
+        }
+    }
+
+    /// The first minute after `after` that matches, in local time; `None`
+    /// if it never does, e.g. "0 0 31 2 *".
+    pub(crate) fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let mut time =
+            after.naive_local().with_second(0)?.with_nanosecond(0)? + TimeDelta::minutes(1);
+        let limit = time + TimeDelta::days(MAX_LOOKAHEAD_DAYS);
+        while time < limit {
+            let date = time.date();
+            if !bit(self.months, time.month()) {
+                let (year, month) = match time.month() {
+                    12 => (time.year() + 1, 1),
+                    month => (time.year(), month + 1),
+                };
+                time = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.matches_day(date) {
+                time = date.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if !bit(self.hours, time.hour()) {
+                time = time.with_minute(0)? + TimeDelta::hours(1);
+            } else if !bit(self.minutes, time.minute()) {
+                time += TimeDelta::minutes(1);
+            } else {
+                match Local.from_local_datetime(&time).earliest() {
+                    Some(next) => return Some(next),
+                    // Skipped by a daylight saving change
+                    None => time += TimeDelta::minutes(1),
+                }
+            }
+        }
+        None
+    }
+}
+
+/// What the scheduler remembers about a job between runs of the app.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct RunRecord {
+    /// When the scheduler last started the job, in milliseconds.
+    last_run: Option<i64>,
+    last_job_id: Option<String>,
+    /// When the scheduler first saw the job; its first run is the first
+    /// scheduled time after this.
+    first_seen: i64,
+}
+
+fn storage_key(name: &str) -> String {
+    format!("{}{}", STORAGE_PREFIX, name)
+}
+
+fn load(name: &str) -> RunRecord {
+    let record = match get_record(&storage_key(name)) {
+        Ok(json) => json.and_then(|json| serde_json::from_str(&json).ok()),
+        Err(e) => {
+            warn!("Failed to read the schedule of {}: {}", name, e);
+            None
+        }
+    };
+    record.unwrap_or_else(|| {
+        let record = RunRecord {
+            first_seen: Utc::now().timestamp_millis(),
+            ..RunRecord::default()
+        };
+        save(name, &record);
+        record
+    })
+}
+
+fn save(name: &str, record: &RunRecord) {
+    let result = serde_json::to_string(record)
+        .map_err(|e| e.to_string())
+        .and_then(|json| put_record(&storage_key(name), &json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("Failed to save the schedule of {}: {}", name, e);
+    }
+}
+
+/// When the job is next due, in milliseconds; a run missed while the app
+/// was closed is due at once, and only once.
+fn next_run(schedule: &Schedule, record: &RunRecord) -> Option<i64> {
+    let since = record.last_run.unwrap_or(record.first_seen);
+    let since = Local.timestamp_millis_opt(since).single()?;
+    schedule
+        .next_after(since)
+        .map(|next| next.timestamp_millis())
+}
+
+/// Whether the job's previous run is still queued or running.
+fn in_progress(record: &RunRecord) -> bool {
+    record
+        .last_job_id
+        .as_deref()
+        .and_then(jobs::job_state)
+        .is_some_and(|state| !state.is_finished())
+}
+
+fn run(job: &MaintenanceJob, settings: &Settings, priority: JobPriority) -> JobInfo {
+    let info = jobs::enqueue((job.spec)(settings), priority);
+    save(
+        job.name,
+        &RunRecord {
+            last_run: Some(Utc::now().timestamp_millis()),
+            last_job_id: Some(info.id.clone()),
+            first_seen: load(job.name).first_seen,
+        },
+    );
+    info
+}
+
+/// Starts the jobs that are due. They wait until no other job has been
+/// queued or running and the user hasn't been at work for
+/// `scheduler.idle_minutes`, unless they have already waited
+/// `MAX_DEFER_MS`.
+fn tick() {
+    let settings = SETTINGS.read();
+    if !settings.enabled {
+        return;
+    }
+    let _guard = RUNS.lock();
+    let now = Utc::now().timestamp_millis();
+    let (queued, running) = jobs::pending_jobs();
+    if queued + running > 0 || activity::completions() > 0 {
+        BUSY_AT.store(now, Ordering::Relaxed);
+    }
+    let busy_at = BUSY_AT
+        .load(Ordering::Relaxed)
+        .max(activity::last_activity().unwrap_or(0));
+    let idle = now - busy_at >= settings.idle_ms;
+
+    for job in MAINTENANCE {
+        let Some(schedule) = settings.schedule(job) else {
+            continue;
+        };
+        let record = load(job.name);
+        let Some(due) = next_run(&schedule, &record) else {
+            continue;
+        };
+        if due > now || in_progress(&record) || (!idle && now - due < MAX_DEFER_MS) {
+            continue;
+        }
+        let info = run(job, &settings, JobPriority::Low);
+        info!("Started scheduled job {} as {}", job.name, info.id);
+    }
+}
+
+/// Applies the `[scheduler]` settings. Invalid schedules were reported by
+/// validation and leave the job on its default.
+pub(crate) fn configure(config: Option<&SchedulerConfig>) {
+    let mut settings = SETTINGS.write();
+    settings.enabled = config.and_then(|c| c.enabled).unwrap_or(true);
+    settings.idle_ms = config
+        .and_then(|c| c.idle_minutes)
+        .unwrap_or(DEFAULT_IDLE_MINUTES) as i64
+        * 60_000;
+    settings.keep_backups = config
+        .and_then(|c| c.keep_backups)
+        .unwrap_or(DEFAULT_KEEP_BACKUPS)
+        .max(1);
+    settings.overrides = config
+        .map(|c| {
+            c.schedules
+                .iter()
+                .filter_map(|(name, expression)| match expression.trim() {
+                    OFF => Some((name.clone(), None)),
+                    expression => Schedule::parse(expression)
+                        .ok()
+                        .map(|schedule| (name.clone(), Some(schedule))),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+}
+
+/// Starts checking for due maintenance every minute; called once jobs are
+/// up. The app counts as busy at first, so nothing runs during startup.
+pub(crate) fn start() {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    BUSY_AT.store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    tauri::async_runtime::spawn(async {
+        loop {
+            tokio::time::sleep(TICK).await;
+            tick();
+        }
+    });
+}
+
+/// Whether `name` is a job the scheduler knows.
+pub(crate) fn is_scheduled_job(name: &str) -> bool {
+    MAINTENANCE.iter().any(|job| job.name == name)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledJobInfo {
+    pub name: String,
+    pub description: String,
+    /// The cron expression in effect, or `None` when the job is off.
+    pub schedule: Option<String>,
+    pub enabled: bool,
+    /// Milliseconds since the Unix epoch.
+    pub last_run: Option<i64>,
+    /// When it is next due; it may wait for the app to go idle.
+    pub next_run: Option<i64>,
+    /// The job queue entry of its last run, for `list_jobs`.
+    pub last_job_id: Option<String>,
+}
+
+/// The maintenance jobs with their schedules and when they last and next run.
+#[command]
+pub async fn list_scheduled_jobs() -> Result<Vec<ScheduledJobInfo>, String> {
+    let settings = SETTINGS.read();
+    Ok(MAINTENANCE
+        .iter()
+        .map(|job| {
+            let schedule = settings.schedule(job);
+            let record = load(job.name);
+            let enabled = settings.enabled && schedule.is_some();
+            ScheduledJobInfo {
+                name: job.name.to_string(),
+                description: job.description.to_string(),
+                next_run: schedule
+                    .as_ref()
+                    .filter(|_| enabled)
+                    .and_then(|schedule| next_run(schedule, &record)),
+                schedule: schedule.map(|schedule| schedule.expression),
+                enabled,
+                last_run: record.last_run,
+                last_job_id: record.last_job_id,
+            }
+        })
+        .collect())
+}
+
+/// Runs a maintenance job now, whether or not it is scheduled or the app is
+/// idle. Its next scheduled run counts from now.
+#[command]
+pub async fn run_scheduled_job(name: String) -> Result<JobInfo, String> {
+    let job = MAINTENANCE
+        .iter()
+        .find(|job| job.name == name)
+        .ok_or_else(|| format!("There is no scheduled job named {}", name))?;
+    let settings = SETTINGS.read();
+    let _guard = RUNS.lock();
+    if in_progress(&load(job.name)) {
+        return Err(format!("{} is already queued or running", name));
+    }
+    let info = run(job, &settings, JobPriority::Normal);
+    info!("Started {} as {} on request", name, info.id);
+    Ok(info)
+}
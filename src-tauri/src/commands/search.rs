@@ -0,0 +1,342 @@
+// src/commands/search.rs
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use tauri::{command, State};
+
+use super::exec::{find_system_executable, run_tool, ExecError};
+use super::fs::{get_project_root, workspace_relative_path};
+use super::greptile::{greptile_search, SearchRequest};
+use super::recent::{record_use, RecentKind};
+use crate::context::context::similar_chunks;
+use crate::state::AppState;
+
+const DEFAULT_LIMIT: usize = 20;
+const TEXT_SEARCH_TIMEOUT: Duration = Duration::from_secs(15);
+/// Matching lines read per file; files are ranked by how many they have.
+const TEXT_MATCHES_PER_FILE: usize = 20;
+/// Reciprocal rank fusion constant; larger values flatten the gap between
+/// a source's first and later hits.
+const RANK_CONSTANT: f64 = 60.0;
+const MAX_SNIPPET_LENGTH: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSource {
+    /// The context manager's vector index.
+    Semantic,
+    /// ripgrep over the workspace.
+    Text,
+    Greptile,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub source: SearchSource,
+    /// Workspace-relative where possible.
+    pub path: String,
+    /// 1-based and inclusive.
+    pub start_line: usize,
+    pub end_line: usize,
+    pub snippet: String,
+    /// One-line description of the code, for semantic hits when chunk
+    /// summaries are on.
+    #[serde(default)]
+    pub summary: Option<String>,
+    /// Fused rank across sources; only meaningful relative to other hits.
+    pub score: f64,
+}
+
+/// How one source fared, so the UI can tell "no matches" from "not run".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceReport {
+    pub source: SearchSource,
+    pub hits: usize,
+    pub duration_ms: u64,
+    /// True when the source isn't set up (no index, no API key, no ripgrep).
+    pub skipped: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub query: String,
+    pub hits: Vec<SearchHit>,
+    pub sources: Vec<SourceReport>,
+}
+
+/// A source's hits, best first.
+type SourceOutcome = Result<Vec<SearchHit>, SourceError>;
+
+enum SourceError {
+    Unavailable(String),
+    Failed(String),
+}
+
+fn hit(
+    source: SearchSource,
+    path: String,
+    start_line: usize,
+    end_line: usize,
+    snippet: &str,
+) -> SearchHit {
+    let mut end = snippet.len().min(MAX_SNIPPET_LENGTH);
+    while !snippet.is_char_boundary(end) {
+        end -= 1;
+    }
+    SearchHit {
+        source,
+        path,
+        start_line,
+        end_line: end_line.max(start_line),
+        snippet: snippet[..end].to_string(),
+        summary: None,
+        score: 0.0,
+    }
+}
+
+/// Paths from different sources are compared after making them
+/// workspace-relative.
+fn normalize_path(path: &str) -> String {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        workspace_relative_path(path)
+    } else {
+        path.to_string_lossy().trim_start_matches("./").to_string()
+    }
+}
+
+async fn semantic_hits(query: &str, limit: usize) -> SourceOutcome {
+    let chunks = similar_chunks(query, limit).await.map_err(|e| {
+        if e.contains("not initialized") {
+            SourceError::Unavailable(e)
+        } else {
+            SourceError::Failed(e)
+        }
+    })?;
+    Ok(chunks
+        .iter()
+        .map(|chunk| {
+            // Chunk lines are 0-based with an exclusive end
+            let hit = hit(
+                SearchSource::Semantic,
+                normalize_path(&chunk.file_path),
+                chunk.start_line + 1,
+                chunk.end_line,
+                &chunk.content,
+            );
+            SearchHit {
+                summary: chunk.summary.clone(),
+                ..hit
+            }
+        })
+        .collect())
+}
+
+/// Searches the workspace for `query` as literal text. Files are ranked by
+/// how many lines match, each represented by its first matching line.
+async fn text_hits(query: &str, limit: usize) -> SourceOutcome {
+    let program = find_system_executable("rg")
+        .ok_or_else(|| SourceError::Unavailable("rg was not found on PATH".to_string()))?;
+    let root = get_project_root();
+    let args = vec![
+        "--json".to_string(),
+        "--smart-case".to_string(),
+        "--fixed-strings".to_string(),
+        "--max-count".to_string(),
+        TEXT_MATCHES_PER_FILE.to_string(),
+        "--".to_string(),
+        query.to_string(),
+        ".".to_string(),
+    ];
+    let output = run_tool(&program, &args, &root, None, TEXT_SEARCH_TIMEOUT)
+        .await
+        .map_err(|e| SourceError::Failed(e.to_string()))?;
+    // Exit code 1 means nothing matched
+    if !matches!(output.exit_code, Some(0) | Some(1)) {
+        return Err(SourceError::Failed(output.stderr.trim().to_string()));
+    }
+
+    let mut files: Vec<(String, usize, SearchHit)> = Vec::new();
+    for line in output.stdout.lines() {
+        let Ok(record) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if record["type"] != "match" {
+            continue;
+        }
+        let data = &record["data"];
+        let path = normalize_path(data["path"]["text"].as_str().unwrap_or_default());
+        match files.iter_mut().find(|(file, _, _)| *file == path) {
+            Some((_, count, _)) => *count += 1,
+            None => {
+                let line_number = data["line_number"].as_u64().unwrap_or(1) as usize;
+                let text = data["lines"]["text"].as_str().unwrap_or_default().trim();
+                let first = hit(
+                    SearchSource::Text,
+                    path.clone(),
+                    line_number,
+                    line_number,
+                    text,
+                );
+                files.push((path, 1, first));
+            }
+        }
+    }
+
+    // Stable, so ties keep ripgrep's order
+    files.sort_by_key(|(_, count, _)| std::cmp::Reverse(*count));
+    Ok(files
+        .into_iter()
+        .take(limit)
+        .map(|(_, _, hit)| hit)
+        .collect())
+}
+
+async fn greptile_hits(query: &str, limit: usize, state: State<'_, AppState>) -> SourceOutcome {
+    let response = greptile_search(SearchRequest::new(query, limit as u32), state)
+        .await
+        .map_err(|e| {
+            if e.code() == "NOT_CONFIGURED" {
+                SourceError::Unavailable(e.to_string())
+            } else {
+                SourceError::Failed(e.to_string())
+            }
+        })?;
+
+    let mut results = response.results;
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(results
+        .iter()
+        .map(|result| {
+            let line = result.line_number.max(1) as usize;
+            let snippet = if result.context.is_empty() {
+                result.matched_text.clone()
+            } else {
+                result.context.join("\n")
+            };
+            hit(
+                SearchSource::Greptile,
+                normalize_path(&result.file),
+                line,
+                line,
+                &snippet,
+            )
+        })
+        .collect())
+}
+
+/// Scores each source's hits by reciprocal rank and folds hits on
+/// overlapping lines of the same file into the best-ranked one, which keeps
+/// its source and gains the others' scores.
+fn merge(outcomes: Vec<Vec<SearchHit>>) -> Vec<SearchHit> {
+    let mut merged: Vec<SearchHit> = Vec::new();
+    let mut by_path: HashMap<String, Vec<usize>> = HashMap::new();
+
+    let mut ranked: Vec<SearchHit> = outcomes
+        .into_iter()
+        .flat_map(|hits| {
+            hits.into_iter().enumerate().map(|(rank, mut hit)| {
+                hit.score = 1.0 / (RANK_CONSTANT + rank as f64 + 1.0);
+                hit
+            })
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    for hit in ranked {
+        let indices = by_path.entry(hit.path.clone()).or_default();
+        let overlapping = indices.iter().copied().find(|&i| {
+            let existing: &SearchHit = &merged[i];
+            existing.start_line <= hit.end_line && hit.start_line <= existing.end_line
+        });
+        match overlapping {
+            Some(i) => {
+                merged[i].score += hit.score;
+                if merged[i].summary.is_none() {
+                    merged[i].summary = hit.summary;
+                }
+            }
+            None => {
+                indices.push(merged.len());
+                merged.push(hit);
+            }
+        }
+    }
+
+    merged.sort_by(|a, b| b.score.total_cmp(&a.score));
+    merged
+}
+
+/// Runs `query` against the semantic index, a text search of the workspace
+/// and Greptile (when configured) at once, and returns their hits merged
+/// into one ranked list, each tagged with the source that found it. A source
+/// that fails or isn't set up is reported in `sources` rather than failing
+/// the search.
+#[command]
+pub async fn search(
+    query: String,
+    limit: Option<usize>,
+    sources: Option<Vec<SearchSource>>,
+    state: State<'_, AppState>,
+) -> Result<SearchResults, ExecError> {
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return Err(ExecError::new("INVALID_QUERY", "The search query is empty"));
+    }
+    record_use(RecentKind::Search, &query, None);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    let mut wanted = sources.unwrap_or_else(|| {
+        vec![
+            SearchSource::Semantic,
+            SearchSource::Text,
+            SearchSource::Greptile,
+        ]
+    });
+    let mut seen = HashSet::new();
+    wanted.retain(|source| seen.insert(*source));
+
+    let timed = |source: SearchSource| {
+        let query = query.as_str();
+        let state = state.clone();
+        async move {
+            let started = Instant::now();
+            let outcome = match source {
+                SearchSource::Semantic => semantic_hits(query, limit).await,
+                SearchSource::Text => text_hits(query, limit).await,
+                SearchSource::Greptile => greptile_hits(query, limit, state).await,
+            };
+            (source, outcome, started.elapsed().as_millis() as u64)
+        }
+    };
+    let outcomes = futures::future::join_all(wanted.into_iter().map(timed)).await;
+
+    let mut reports = Vec::new();
+    let mut found = Vec::new();
+    for (source, outcome, duration_ms) in outcomes {
+        let (hits, skipped, error) = match outcome {
+            Ok(hits) => (hits, false, None),
+            Err(SourceError::Unavailable(reason)) => (Vec::new(), true, Some(reason)),
+            Err(SourceError::Failed(reason)) => (Vec::new(), false, Some(reason)),
+        };
+        reports.push(SourceReport {
+            source,
+            hits: hits.len(),
+            duration_ms,
+            skipped,
+            error,
+        });
+        found.push(hits);
+    }
+
+    let mut hits = merge(found);
+    hits.truncate(limit);
+    Ok(SearchResults {
+        query,
+        hits,
+        sources: reports,
+    })
+}
@@ -0,0 +1,186 @@
+// src/commands/session.rs
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::command;
+use tracing::warn;
+
+use super::fs::{get_project_root, resolve_workspace_path};
+use super::storage::{flush_buffered_writes, get_record, put_record};
+use crate::config::SessionConfig;
+
+const STORAGE_PREFIX: &str = "session:";
+const DEFAULT_AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+/// Most recent context queries kept in a session.
+const CONTEXT_QUERIES_KEPT: usize = 20;
+
+static AUTOSAVE_INTERVAL: Mutex<Duration> = Mutex::new(DEFAULT_AUTOSAVE_INTERVAL);
+/// The latest state not yet written, with the workspace it belongs to.
+static UNSAVED: Lazy<Mutex<Option<(String, SessionState)>>> = Lazy::new(|| Mutex::new(None));
+/// Set once the autosave task is running.
+static AUTOSAVER: OnceLock<()> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CursorPosition {
+    /// Zero-based.
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenFile {
+    /// Relative to the workspace root.
+    pub path: String,
+    pub cursor: Option<CursorPosition>,
+    /// Scroll offset in pixels.
+    pub scroll_top: Option<f64>,
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalTab {
+    pub session_id: String,
+    pub title: Option<String>,
+}
+
+/// What the workspace looked like, restored when it is opened again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionState {
+    #[serde(default)]
+    pub open_files: Vec<OpenFile>,
+    pub active_file: Option<String>,
+    /// Panel sizes and visibility, in whatever shape the frontend uses.
+    #[serde(default)]
+    pub layout: Value,
+    #[serde(default)]
+    pub terminals: Vec<TerminalTab>,
+    pub active_terminal: Option<String>,
+    /// Oldest first.
+    #[serde(default)]
+    pub context_queries: Vec<String>,
+    /// Milliseconds since the Unix epoch.
+    #[serde(default)]
+    pub saved_at: i64,
+}
+
+fn workspace() -> String {
+    get_project_root().to_string_lossy().to_string()
+}
+
+fn storage_key(workspace: &str) -> String {
+    format!("{}{}", STORAGE_PREFIX, workspace)
+}
+
+fn write(workspace: &str, state: &SessionState) -> Result<(), String> {
+    let json = serde_json::to_string(state).map_err(|e| e.to_string())?;
+    put_record(&storage_key(workspace), &json).map_err(|e| e.to_string())
+}
+
+/// Writes the unsaved state, if any. Called by the autosave task and on exit.
+pub(crate) fn flush_session_state() {
+    let Some((workspace, state)) = UNSAVED.lock().take() else {
+        return;
+    };
+    if let Err(e) = write(&workspace, &state) {
+        warn!("Failed to save the session for {}: {}", workspace, e);
+        // Keep it for the next attempt unless something newer arrived
+        UNSAVED.lock().get_or_insert((workspace, state));
+    }
+}
+
+/// Applies the `[session]` settings and starts autosaving.
+pub(crate) fn configure(config: Option<&SessionConfig>) {
+    *AUTOSAVE_INTERVAL.lock() = config
+        .and_then(|session| session.autosave_interval_secs)
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_AUTOSAVE_INTERVAL);
+    start_autosaver();
+}
+
+fn start_autosaver() {
+    if AUTOSAVER.set(()).is_err() {
+        return;
+    }
+    tauri::async_runtime::spawn(async {
+        loop {
+            let interval = *AUTOSAVE_INTERVAL.lock();
+            tokio::time::sleep(interval).await;
+            flush_session_state();
+        }
+    });
+}
+
+/// Replaces the current workspace's session. It is written at the next
+/// autosave, or right away with `flush` (e.g. when the window closes).
+#[command]
+pub async fn save_session_state(
+    mut state: SessionState,
+    flush: Option<bool>,
+) -> Result<(), String> {
+    let excess = state
+        .context_queries
+        .len()
+        .saturating_sub(CONTEXT_QUERIES_KEPT);
+    state.context_queries.drain(..excess);
+    state.saved_at = Utc::now().timestamp_millis();
+
+    let workspace = workspace();
+    let flush = flush.unwrap_or(false);
+    let previous = if flush {
+        UNSAVED.lock().take()
+    } else {
+        UNSAVED.lock().replace((workspace.clone(), state.clone()))
+    };
+    // A session left unsaved for another workspace is written now rather
+    // than lost
+    if let Some((other, unsaved)) = previous.filter(|(other, _)| *other != workspace) {
+        write(&other, &unsaved)?;
+    }
+    if flush {
+        write(&workspace, &state)?;
+        // Session writes are buffered too; this one shouldn't wait
+        flush_buffered_writes().map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// The current workspace's last session, or `None` if it has none. Files
+/// deleted since are left out.
+#[command]
+pub async fn load_session_state() -> Result<Option<SessionState>, String> {
+    let workspace = workspace();
+    let unsaved = UNSAVED
+        .lock()
+        .as_ref()
+        .filter(|(owner, _)| *owner == workspace)
+        .map(|(_, state)| state.clone());
+    let state = match unsaved {
+        Some(state) => Some(state),
+        None => match get_record(&storage_key(&workspace)).map_err(|e| e.to_string())? {
+            Some(json) => Some(serde_json::from_str::<SessionState>(&json).map_err(|e| {
+                format!("The saved session for {} is unreadable: {}", workspace, e)
+            })?),
+            None => None,
+        },
+    };
+
+    Ok(state.map(|mut state| {
+        state
+            .open_files
+            .retain(|file| resolve_workspace_path(&file.path).is_ok_and(|path| path.exists()));
+        if state
+            .active_file
+            .as_ref()
+            .is_some_and(|active| !state.open_files.iter().any(|file| &file.path == active))
+        {
+            state.active_file = state.open_files.first().map(|file| file.path.clone());
+        }
+        state
+    }))
+}
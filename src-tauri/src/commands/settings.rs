@@ -0,0 +1,637 @@
+// src/commands/settings.rs
+
+use notify::EventKind;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::ipc::Request;
+use tauri::{command, AppHandle, Emitter, State};
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::warn;
+
+use super::fs::{get_project_root, FileWatcher};
+use super::permissions::Actor;
+use super::{
+    api_server, budget, extensions, format, fs, jobs, notifications, recorder, redaction,
+    scheduler, session, storage, trust,
+};
+use crate::activity;
+use crate::config::{AppConfig, ConfigIssue, IssueSeverity};
+use crate::context::{filters, pruning};
+use crate::error::MightyError;
+use crate::http_client;
+use crate::secrets;
+use crate::state::AppState;
+use crate::telemetry;
+
+/// Editors often write a file in several steps; reloading waits this long
+/// after the last change.
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(250);
+/// The workspace's own settings, relative to its root.
+const WORKSPACE_CONFIG_DIR: &str = ".mighty";
+const WORKSPACE_CONFIG_FILE: &str = "config.toml";
+/// Sections a workspace config may set. Credentials and the endpoints they
+/// are sent to stay global, so opening a repository can't redirect them.
+const WORKSPACE_SECTIONS: &[&str] = &["models", "workspace", "tasks", "context"];
+
+enum EnvValue {
+    Text,
+    /// Separated like `PATH`.
+    PathList,
+}
+
+/// Environment variables that override a config value, for CI, containers
+/// and switching between profiles. They take precedence over both config
+/// files and are never saved.
+const ENV_OVERRIDES: &[(&str, &str, EnvValue)] = &[
+    (
+        "MIGHTY_ANTHROPIC_API_KEY",
+        "anthropic.api_key",
+        EnvValue::Text,
+    ),
+    (
+        "MIGHTY_GREPTILE_API_KEY",
+        "greptile.api_key",
+        EnvValue::Text,
+    ),
+    (
+        "MIGHTY_GREPTILE_BASE_URL",
+        "greptile.base_url",
+        EnvValue::Text,
+    ),
+    (
+        "MIGHTY_GREPTILE_GITHUB_TOKEN",
+        "greptile.github_token",
+        EnvValue::Text,
+    ),
+    ("MIGHTY_CHAT_MODEL", "models.chat", EnvValue::Text),
+    (
+        "MIGHTY_COMPLETION_MODEL",
+        "models.completion",
+        EnvValue::Text,
+    ),
+    (
+        "MIGHTY_ALLOWED_ROOTS",
+        "fs.allowed_roots",
+        EnvValue::PathList,
+    ),
+];
+
+/// Where each part of the effective configuration comes from. The app
+/// state's `config` holds the merged result; this keeps the layers it was
+/// built from.
+struct ConfigLayers {
+    /// Exactly what the global config file holds, which is what gets saved.
+    global: AppConfig,
+    workspace_root: PathBuf,
+    /// The allowed sections of the workspace config, when there is one.
+    workspace: Option<Value>,
+    /// Sections of the workspace config that were left out.
+    ignored_sections: Vec<String>,
+}
+
+static LAYERS: Lazy<parking_lot::Mutex<ConfigLayers>> = Lazy::new(|| {
+    parking_lot::Mutex::new(ConfigLayers {
+        global: AppConfig::default(),
+        workspace_root: PathBuf::new(),
+        workspace: None,
+        ignored_sections: Vec::new(),
+    })
+});
+
+static CONFIG_WATCHER: Lazy<parking_lot::Mutex<Option<FileWatcher>>> =
+    Lazy::new(|| parking_lot::Mutex::new(None));
+/// The workspace watcher and whether it covers the workspace config
+/// directory, or only the root while waiting for that to be created.
+static WORKSPACE_WATCHER: Lazy<parking_lot::Mutex<Option<(FileWatcher, bool)>>> =
+    Lazy::new(|| parking_lot::Mutex::new(None));
+/// Bumped on every change to a config file, so only the last of a burst of
+/// changes reloads.
+static CONFIG_CHANGES: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigLayer {
+    Global,
+    Workspace,
+    Environment,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveConfig {
+    pub config: AppConfig,
+    pub workspace_root: String,
+    /// The workspace config file, when the workspace has one.
+    pub workspace_file: Option<String>,
+    /// The layer each set value came from, keyed by dotted path (e.g.
+    /// `models.chat`). Values that aren't listed are unset.
+    pub sources: BTreeMap<String, ConfigLayer>,
+    /// Sections of the workspace config that only the global config may set.
+    pub ignored_sections: Vec<String>,
+}
+
+fn workspace_config_path(root: &Path) -> PathBuf {
+    root.join(WORKSPACE_CONFIG_DIR).join(WORKSPACE_CONFIG_FILE)
+}
+
+/// Reads the workspace config under `root`, keeping only the sections a
+/// workspace may set. Returns the kept sections and the names of the rest.
+fn load_workspace_layer(root: &Path) -> Result<(Option<Value>, Vec<String>), String> {
+    let path = workspace_config_path(root);
+    if !path.is_file() {
+        return Ok((None, Vec::new()));
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let table: toml::Table = toml::from_str(&content)
+        .map_err(|e| format!("Invalid configuration in {}: {}", path.display(), e))?;
+
+    let mut ignored_sections = Vec::new();
+    let mut layer = serde_json::Map::new();
+    for (key, value) in table {
+        if WORKSPACE_SECTIONS.contains(&key.as_str()) {
+            layer.insert(key, serde_json::to_value(value).map_err(|e| e.to_string())?);
+        } else {
+            ignored_sections.push(key);
+        }
+    }
+    Ok((Some(Value::Object(layer)), ignored_sections))
+}
+
+/// Reads the config overrides set through environment variables.
+fn environment_layer() -> Option<Value> {
+    let mut layer = Value::Object(Default::default());
+    let mut found = false;
+    for (var, path, kind) in ENV_OVERRIDES {
+        let Some(raw) = env::var_os(var).filter(|raw| !raw.is_empty()) else {
+            continue;
+        };
+        let value = match kind {
+            EnvValue::Text => json!(raw.to_string_lossy()),
+            EnvValue::PathList => json!(env::split_paths(&raw)
+                .map(|path| path.to_string_lossy().to_string())
+                .collect::<Vec<_>>()),
+        };
+        let patch = path
+            .rsplit('.')
+            .fold(value, |value, key| json!({ key: value }));
+        merge_patch(&mut layer, &patch);
+        found = true;
+    }
+    found.then_some(layer)
+}
+
+/// Builds the effective configuration: `global`, then the workspace layer,
+/// then environment overrides.
+fn merge_layers(global: &AppConfig, workspace: Option<&Value>) -> serde_json::Result<AppConfig> {
+    let mut merged = serde_json::to_value(global)?;
+    for layer in [workspace.cloned(), environment_layer()]
+        .into_iter()
+        .flatten()
+    {
+        merge_patch(&mut merged, &layer);
+    }
+    serde_json::from_value(merged)
+}
+
+/// Loads the workspace layer for `root` over `global` and returns the
+/// layers with the merged configuration.
+fn resolve_layers(global: AppConfig, root: PathBuf) -> Result<(ConfigLayers, AppConfig), String> {
+    let (workspace, ignored_sections) = load_workspace_layer(&root)?;
+    let effective = merge_layers(&global, workspace.as_ref()).map_err(|e| {
+        format!(
+            "Invalid configuration in {}: {}",
+            workspace_config_path(&root).display(),
+            e
+        )
+    })?;
+    let layers = ConfigLayers {
+        global,
+        workspace_root: root,
+        workspace,
+        ignored_sections,
+    };
+    Ok((layers, effective))
+}
+
+/// Merges the current workspace's config and any environment overrides
+/// over `global` at startup, and returns the configuration to share. A
+/// broken workspace config is skipped so the app can still start.
+pub(crate) fn resolve_config(global: AppConfig) -> AppConfig {
+    let root = get_project_root();
+    let (layers, effective) = match resolve_layers(global.clone(), root.clone()) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            warn!("Ignoring the workspace config: {}", e);
+            let effective = merge_layers(&global, None).unwrap_or_else(|_| global.clone());
+            let layers = ConfigLayers {
+                global,
+                workspace_root: root,
+                workspace: None,
+                ignored_sections: Vec::new(),
+            };
+            (layers, effective)
+        }
+    };
+    *LAYERS.lock() = layers;
+    effective
+}
+
+/// Pushes settings that are cached outside the shared config into the
+/// modules that use them, so a changed config takes effect immediately.
+pub(crate) fn apply_config(config: &AppConfig) {
+    let allowed_roots = config
+        .fs
+        .as_ref()
+        .map(|fs| fs.allowed_roots.clone())
+        .unwrap_or_default();
+    fs::set_allowed_roots(&allowed_roots);
+    format::initialize_format(config.format.as_ref());
+    telemetry::configure(config.telemetry.as_ref());
+    jobs::configure(config.jobs.as_ref());
+    activity::configure(config.jobs.as_ref());
+    scheduler::configure(config.scheduler.as_ref());
+    budget::configure(config.budget.as_ref());
+    storage::configure(config.storage.as_ref());
+    notifications::configure(config.notifications.as_ref());
+    session::configure(config.session.as_ref());
+    extensions::configure(config.extensions.as_ref());
+    api_server::configure(config.api_server.as_ref());
+    http_client::configure(config.http.as_ref());
+    recorder::configure(config.recorder.as_ref());
+    secrets::configure(config.secrets.as_ref());
+    redaction::configure(config.redaction.as_ref());
+    trust::configure(config.trust.as_ref());
+    filters::configure(config.context.as_ref());
+    pruning::configure(config.workspace.as_ref());
+}
+
+/// Makes `layers` current and shares `effective`, applying and announcing
+/// it if it differs from what was shared before.
+fn install_config(
+    app: &AppHandle,
+    mut current: MutexGuard<'_, AppConfig>,
+    layers: ConfigLayers,
+    effective: AppConfig,
+) {
+    *LAYERS.lock() = layers;
+    // Also skips the change update_config's own write causes
+    if serde_json::to_value(&*current).ok() == serde_json::to_value(&effective).ok() {
+        return;
+    }
+    *current = effective.clone();
+    drop(current);
+
+    apply_config(&effective);
+    if let Err(e) = app.emit("config-updated", &effective) {
+        warn!("Failed to emit config update: {}", e);
+    }
+}
+
+fn report_config_error(app: &AppHandle, path: &Path, message: &str) {
+    let payload = json!({
+        "path": path.to_string_lossy(),
+        "message": message,
+    });
+    if let Err(e) = app.emit("config-error", payload) {
+        warn!("Failed to emit config error: {}", e);
+    }
+}
+
+/// Creates a watcher that reloads the config when any of `paths` changes.
+fn config_watcher(
+    app: AppHandle,
+    config: Arc<Mutex<AppConfig>>,
+    paths: Vec<PathBuf>,
+) -> notify::Result<FileWatcher> {
+    FileWatcher::with_handler(move |event| {
+        if matches!(event.kind, EventKind::Access(_))
+            || !event.paths.iter().any(|path| paths.contains(path))
+        {
+            return;
+        }
+        let change = CONFIG_CHANGES.fetch_add(1, Ordering::SeqCst) + 1;
+        let app = app.clone();
+        let config = config.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(RELOAD_DEBOUNCE).await;
+            if CONFIG_CHANGES.load(Ordering::SeqCst) == change {
+                reload_config(&app, &config).await;
+            }
+        });
+    })
+}
+
+/// Watches the global and workspace config files and reloads them when they
+/// change, so hand edits apply without a restart. A file that fails to
+/// parse leaves the current config in place and is reported as a
+/// `config-error` event.
+pub(crate) fn watch_config_file(
+    app: AppHandle,
+    config: Arc<Mutex<AppConfig>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = AppConfig::path();
+    // The directory is watched rather than the file, since saving by
+    // renaming over the file would end a watch on the file itself
+    let config_dir = config_path
+        .parent()
+        .ok_or("The config file has no parent directory")?
+        .to_path_buf();
+    std::fs::create_dir_all(&config_dir)?;
+
+    let mut watcher = config_watcher(app.clone(), config.clone(), vec![config_path])?;
+    watcher.watch_with_mode(&config_dir, false)?;
+    *CONFIG_WATCHER.lock() = Some(watcher);
+
+    let root = LAYERS.lock().workspace_root.clone();
+    watch_workspace_config(app, config, &root)?;
+    Ok(())
+}
+
+/// Whether config file changes are being picked up.
+pub(crate) fn config_watcher_active() -> bool {
+    CONFIG_WATCHER.lock().is_some()
+}
+
+/// Watches the workspace config directory, or the workspace root until that
+/// directory exists. Keeps the current watcher when nothing has changed.
+fn watch_workspace_config(
+    app: AppHandle,
+    config: Arc<Mutex<AppConfig>>,
+    root: &Path,
+) -> notify::Result<()> {
+    let config_dir = root.join(WORKSPACE_CONFIG_DIR);
+    let covers_dir = config_dir.is_dir();
+    let mut current = WORKSPACE_WATCHER.lock();
+    if current
+        .as_ref()
+        .is_some_and(|(_, covered)| *covered == covers_dir)
+        && LAYERS.lock().workspace_root == root
+    {
+        return Ok(());
+    }
+
+    let paths = vec![config_dir.clone(), workspace_config_path(root)];
+    let mut watcher = config_watcher(app, config, paths)?;
+    watcher.watch_with_mode(if covers_dir { &config_dir } else { root }, false)?;
+    *current = Some((watcher, covers_dir));
+    Ok(())
+}
+
+/// Rereads both config files, resolving the workspace layer against the
+/// current workspace.
+async fn reload_config(app: &AppHandle, config: &Arc<Mutex<AppConfig>>) {
+    let config_path = AppConfig::path();
+    // Deleting the global file keeps the settings already loaded
+    let global = if config_path.exists() {
+        match AppConfig::load_from(&config_path) {
+            Ok(global) => global,
+            Err(e) => {
+                report_config_error(app, &config_path, &e.to_string());
+                return;
+            }
+        }
+    } else {
+        LAYERS.lock().global.clone()
+    };
+
+    let root = get_project_root();
+    if let Err(e) = watch_workspace_config(app.clone(), config.clone(), &root) {
+        warn!("Failed to watch the workspace config: {}", e);
+    }
+    match resolve_layers(global, root.clone()) {
+        Ok((layers, effective)) => install_config(app, config.lock().await, layers, effective),
+        Err(e) => report_config_error(app, &workspace_config_path(&root), &e),
+    }
+}
+
+/// Applies a JSON merge patch (RFC 7386): objects are merged key by key, a
+/// `null` removes the key, and anything else replaces the target.
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// Records the layer of every set value under `prefix`. `overlays` are the
+/// layers above the global config, highest precedence first. Arrays count
+/// as one value, since a layer replaces them whole.
+fn collect_sources(
+    value: &Value,
+    overlays: &[(ConfigLayer, Option<&Value>)],
+    prefix: &str,
+    sources: &mut BTreeMap<String, ConfigLayer>,
+) {
+    match value {
+        Value::Null => {}
+        Value::Object(map) => {
+            for (key, child) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                let overlays: Vec<_> = overlays
+                    .iter()
+                    .map(|(layer, value)| (*layer, value.and_then(|value| value.get(key))))
+                    .collect();
+                collect_sources(child, &overlays, &path, sources);
+            }
+        }
+        _ => {
+            let layer = overlays
+                .iter()
+                .find(|(_, value)| value.is_some())
+                .map_or(ConfigLayer::Global, |(layer, _)| *layer);
+            sources.insert(prefix.to_string(), layer);
+        }
+    }
+}
+
+/// Maps every set value of `effective` to the layer it came from.
+fn value_sources(effective: &AppConfig) -> Result<BTreeMap<String, ConfigLayer>, String> {
+    let value = serde_json::to_value(effective).map_err(|e| e.to_string())?;
+    let environment = environment_layer();
+    let layers = LAYERS.lock();
+    let overlays = [
+        (ConfigLayer::Environment, environment.as_ref()),
+        (ConfigLayer::Workspace, layers.workspace.as_ref()),
+    ];
+    let mut sources = BTreeMap::new();
+    collect_sources(&value, &overlays, "", &mut sources);
+    Ok(sources)
+}
+
+/// Finds the layer that set `path` or, for a setting that is missing, the
+/// nearest enclosing one.
+fn issue_layer(sources: &BTreeMap<String, ConfigLayer>, path: &str) -> Option<ConfigLayer> {
+    let mut prefix = path;
+    loop {
+        let nested = format!("{}.", prefix);
+        let found = sources
+            .iter()
+            .find(|(key, _)| *key == prefix || key.starts_with(&nested));
+        if let Some((_, layer)) = found {
+            return Some(*layer);
+        }
+        prefix = &prefix[..prefix.rfind('.')?];
+    }
+}
+
+/// Returns the current configuration, with the workspace's settings and
+/// environment overrides merged over the global ones.
+#[command]
+pub async fn get_config(state: State<'_, AppState>) -> Result<AppConfig, MightyError> {
+    Ok(state.config.lock().await.clone())
+}
+
+/// Returns the configuration in effect for the current workspace along with
+/// the layer each value came from. The workspace is re-resolved first, so
+/// switching workspaces picks up the new one's config.
+#[command]
+pub async fn get_effective_config(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<EffectiveConfig, MightyError> {
+    if LAYERS.lock().workspace_root != get_project_root() {
+        reload_config(&app, &state.config).await;
+    }
+
+    let effective = state.config.lock().await.clone();
+    let sources = value_sources(&effective)?;
+    let layers = LAYERS.lock();
+    Ok(EffectiveConfig {
+        config: effective,
+        workspace_root: layers.workspace_root.to_string_lossy().to_string(),
+        workspace_file: layers.workspace.as_ref().map(|_| {
+            workspace_config_path(&layers.workspace_root)
+                .to_string_lossy()
+                .to_string()
+        }),
+        sources,
+        ignored_sections: layers.ignored_sections.clone(),
+    })
+}
+
+/// Merges `patch` into the global configuration (`null` clears a setting),
+/// checks the result, writes it to the config file and applies it without a
+/// restart. Returns the configuration now in effect, workspace settings and
+/// environment overrides included, which is also emitted as `config-updated`.
+/// Only the user can do this: the settings include trusted folders, the API
+/// server and the proxy's allowed hosts.
+#[command]
+pub async fn update_config(
+    app: AppHandle,
+    request: Request<'_>,
+    patch: Value,
+    state: State<'_, AppState>,
+) -> Result<AppConfig, MightyError> {
+    if Actor::of(&request) == Actor::Agent {
+        return Err(MightyError::config(
+            "AGENT_NOT_ALLOWED",
+            "Only the user can change settings",
+        ));
+    }
+    // Held throughout, so concurrent updates can't drop each other's changes
+    let current = state.config.lock().await;
+    // Saving over a file that doesn't parse would throw away what's in it
+    let config_path = AppConfig::path();
+    if config_path.exists() {
+        AppConfig::load_from(&config_path).map_err(|e| {
+            MightyError::config(
+                "UNREADABLE_CONFIG_FILE",
+                &format!("Fix the config file before changing settings: {}", e),
+            )
+        })?;
+    }
+    let (global, root) = {
+        let layers = LAYERS.lock();
+        (layers.global.clone(), layers.workspace_root.clone())
+    };
+    let mut value = serde_json::to_value(&global).map_err(|e| e.to_string())?;
+    merge_patch(&mut value, &patch);
+    let updated: AppConfig = serde_json::from_value(value).map_err(|e| {
+        MightyError::config("INVALID_CONFIG", &format!("Invalid configuration: {}", e))
+    })?;
+    let (layers, effective) = resolve_layers(updated.clone(), root)
+        .map_err(|e| MightyError::config("INVALID_CONFIG", &e))?;
+
+    let existing = current.validate();
+    let introduced: Vec<String> = effective
+        .validate()
+        .into_iter()
+        .filter(|issue| issue.severity == IssueSeverity::Error && !existing.contains(issue))
+        .map(|issue| format!("{}: {}", issue.path, issue.message))
+        .collect();
+    if !introduced.is_empty() {
+        return Err(MightyError::config(
+            "INVALID_CONFIG",
+            &format!("Invalid configuration: {}", introduced.join("; ")),
+        ));
+    }
+
+    updated.save().map_err(|e| {
+        MightyError::config(
+            "SAVE_ERROR",
+            &format!("Failed to save configuration: {}", e),
+        )
+    })?;
+    install_config(&app, current, layers, effective.clone());
+    Ok(effective)
+}
+
+/// Checks the configuration files and the settings in effect, returning
+/// each problem with the setting and file it concerns. Errors come first.
+/// A file that doesn't parse is reported as a single error for that file.
+#[command]
+pub async fn validate_config(state: State<'_, AppState>) -> Result<Vec<ConfigIssue>, MightyError> {
+    let global_path = AppConfig::path();
+    let root = get_project_root();
+    let workspace_path = workspace_config_path(&root);
+    let file_error = |path: &Path, message: String| ConfigIssue {
+        file: Some(path.to_string_lossy().to_string()),
+        ..ConfigIssue::error("", message)
+    };
+
+    let mut issues = Vec::new();
+    if global_path.exists() {
+        if let Err(e) = AppConfig::load_from(&global_path) {
+            issues.push(file_error(&global_path, e.to_string()));
+        }
+    }
+    // Type errors in the workspace file only show up once it is merged
+    let global = LAYERS.lock().global.clone();
+    if let Err(e) = resolve_layers(global, root) {
+        issues.push(file_error(&workspace_path, e));
+    }
+
+    let effective = state.config.lock().await.clone();
+    let sources = value_sources(&effective)?;
+    for mut issue in effective.validate() {
+        issue.file = match issue_layer(&sources, &issue.path) {
+            Some(ConfigLayer::Global) => Some(global_path.to_string_lossy().to_string()),
+            Some(ConfigLayer::Workspace) => Some(workspace_path.to_string_lossy().to_string()),
+            Some(ConfigLayer::Environment) | None => None,
+        };
+        issues.push(issue);
+    }
+    Ok(issues)
+}
@@ -0,0 +1,213 @@
+// src/commands/shell_integration.rs
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::config::APP_IDENTIFIER;
+
+const INTEGRATION_DIR_NAME: &str = "shell-integration";
+
+/// Loaded by bash in place of its usual startup file. It sources those
+/// itself, then reports each command line (OSC 633 E), its start (C) and its
+/// exit status (D) the way VS Code's shell integration does, along with the
+/// working directory at each prompt (OSC 7).
+const BASH_INIT: &str = r#"if [ -n "$MIGHTY_LOGIN_SHELL" ]; then
+    unset MIGHTY_LOGIN_SHELL
+    [ -r /etc/profile ] && . /etc/profile
+    for __mighty_rc in ~/.bash_profile ~/.bash_login ~/.profile; do
+        if [ -r "$__mighty_rc" ]; then
+            . "$__mighty_rc"
+            break
+        fi
+    done
+    unset __mighty_rc
+elif [ -r ~/.bashrc ]; then
+    . ~/.bashrc
+fi
+
+__mighty_escape() {
+    local value=${1//\\/\\\\}
+    value=${value//;/\\x3b}
+    value=${value//$'\n'/\\x0a}
+    value=${value//$'\a'/\\x07}
+    value=${value//$'\e'/\\x1b}
+    printf '%s' "$value"
+}
+
+# Runs from PS0 in a subshell; a history number that hasn't moved means the
+# line wasn't saved (e.g. HISTCONTROL=ignorespace), so it isn't reported
+__mighty_preexec() {
+    local entry
+    entry=$(HISTTIMEFORMAT= builtin history 1)
+    [[ $entry =~ ^[[:space:]]*([0-9]+)[*]?[[:space:]]+(.*)$ ]] || return
+    [[ ${BASH_REMATCH[1]} == "$__mighty_history_number" ]] && return
+    printf '\033]633;E;%s\007\033]633;C\007' "$(__mighty_escape "${BASH_REMATCH[2]}")"
+}
+
+__mighty_precmd() {
+    local code=$?
+    printf '\033]633;D;%s\007\033]7;file://%s%s\007' "$code" "$HOSTNAME" "$PWD"
+    [[ $(builtin history 1) =~ ^[[:space:]]*([0-9]+) ]] && __mighty_history_number=${BASH_REMATCH[1]}
+}
+
+PS0="${PS0}"'$(__mighty_preexec)'
+PROMPT_COMMAND="__mighty_precmd${PROMPT_COMMAND:+; $PROMPT_COMMAND}"
+"#;
+
+const ZSH_HOOKS: &str = r#"
+__mighty_escape() {
+    local value=${1//\\/\\\\}
+    value=${value//;/\\x3b}
+    value=${value//$'\n'/\\x0a}
+    value=${value//$'\a'/\\x07}
+    value=${value//$'\e'/\\x1b}
+    printf '%s' "$value"
+}
+
+__mighty_preexec() {
+    printf '\033]633;E;%s\007\033]633;C\007' "$(__mighty_escape "$1")"
+}
+
+__mighty_precmd() {
+    local code=$?
+    printf '\033]633;D;%s\007\033]7;file://%s%s\007' "$code" "$HOST" "$PWD"
+}
+
+preexec_functions+=(__mighty_preexec)
+precmd_functions=(__mighty_precmd $precmd_functions)
+"#;
+
+const FISH_INIT: &str = r#"function __mighty_preexec --on-event fish_preexec
+    set -l line (string split \n -- $argv[1] | string replace -a '\\' '\\\\' | string replace -a ';' '\\x3b')
+    printf '\e]633;E;%s\a\e]633;C\a' (string join '\\x0a' -- $line)
+end
+function __mighty_postexec --on-event fish_postexec
+    printf '\e]633;D;%s\a\e]7;file://%s%s\a' $status $hostname $PWD
+end"#;
+
+/// Extra arguments and environment that make a shell report the commands
+/// run in it.
+pub(crate) struct ShellIntegration {
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+}
+
+/// Sets up integration for bash, zsh and fish started with their default
+/// arguments. Shells given custom arguments are left as configured, since
+/// the integration has to take over how they load their startup files.
+pub(crate) fn prepare(
+    shell: &str,
+    args: &[String],
+    env: &HashMap<String, String>,
+) -> Option<ShellIntegration> {
+    if !args
+        .iter()
+        .all(|arg| matches!(arg.as_str(), "-l" | "--login" | "-i"))
+    {
+        return None;
+    }
+    let login = args.iter().any(|arg| arg == "-l" || arg == "--login");
+    let name = Path::new(shell)
+        .file_stem()?
+        .to_string_lossy()
+        .to_lowercase();
+
+    let result = match name.as_str() {
+        "bash" => write_script("bash-init.sh", BASH_INIT).map(|script| ShellIntegration {
+            args: vec![
+                "--init-file".to_string(),
+                script.to_string_lossy().to_string(),
+            ],
+            env: if login {
+                vec![("MIGHTY_LOGIN_SHELL".to_string(), "1".to_string())]
+            } else {
+                Vec::new()
+            },
+        }),
+        "zsh" => write_zsh_dotfiles().map(|dir| {
+            let user_dir = env
+                .get("ZDOTDIR")
+                .cloned()
+                .or_else(|| std::env::var("ZDOTDIR").ok())
+                .or_else(|| std::env::var("HOME").ok())
+                .unwrap_or_default();
+            ShellIntegration {
+                args: args.to_vec(),
+                env: vec![
+                    ("ZDOTDIR".to_string(), dir.to_string_lossy().to_string()),
+                    ("MIGHTY_USER_ZDOTDIR".to_string(), user_dir),
+                ],
+            }
+        }),
+        "fish" => {
+            let mut args = args.to_vec();
+            args.push("--init-command".to_string());
+            args.push(FISH_INIT.to_string());
+            Ok(ShellIntegration {
+                args,
+                env: Vec::new(),
+            })
+        }
+        _ => return None,
+    };
+
+    result
+        .map_err(|e| warn!("Failed to set up shell integration for {}: {}", shell, e))
+        .ok()
+}
+
+/// In the user's own cache directory: shells source these scripts, so no
+/// one else may be able to replace them.
+fn integration_dir() -> PathBuf {
+    dirs::cache_dir()
+        .or_else(dirs::data_dir)
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(APP_IDENTIFIER)
+        .join(INTEGRATION_DIR_NAME)
+}
+
+/// Writes a script into the integration directory, skipping the write when
+/// it is already up to date so a shell starting concurrently never reads a
+/// half-written file.
+fn write_script(name: &str, contents: &str) -> std::io::Result<PathBuf> {
+    let path = integration_dir().join(name);
+    if fs::read_to_string(&path).ok().as_deref() != Some(contents) {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        fs::create_dir_all(dir)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+        fs::write(&path, contents)?;
+    }
+    Ok(path)
+}
+
+/// zsh only reads startup files from `ZDOTDIR`, so each one there loads the
+/// user's own file with `ZDOTDIR` put back, and the last one to run leaves it
+/// that way.
+fn write_zsh_dotfiles() -> std::io::Result<PathBuf> {
+    for name in [".zshenv", ".zprofile", ".zshrc", ".zlogin"] {
+        let mut contents = format!(
+            r#"__mighty_zdotdir=$ZDOTDIR
+ZDOTDIR=${{MIGHTY_USER_ZDOTDIR:-$HOME}}
+[[ -r "$ZDOTDIR/{name}" ]] && . "$ZDOTDIR/{name}"
+MIGHTY_USER_ZDOTDIR=$ZDOTDIR
+ZDOTDIR=$__mighty_zdotdir
+"#
+        );
+        match name {
+            ".zshrc" => {
+                contents.push_str(ZSH_HOOKS);
+                contents.push_str("[[ -o login ]] || ZDOTDIR=$MIGHTY_USER_ZDOTDIR\n");
+            }
+            ".zlogin" => contents.push_str("ZDOTDIR=$MIGHTY_USER_ZDOTDIR\n"),
+            _ => {}
+        }
+        write_script(&format!("zsh/{}", name), &contents)?;
+    }
+    Ok(integration_dir().join("zsh"))
+}
@@ -0,0 +1,314 @@
+// src/commands/snippets.rs
+
+use chrono::{Local, Utc};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use tauri::ipc::Request;
+use tauri::{command, AppHandle};
+
+use super::fs::{get_project_root, resolve_workspace_path, write_file, FileSystemError};
+use super::storage::{delete_record, get_record, put_record, records_with_prefix};
+
+const STORAGE_PREFIX: &str = "snippet:";
+
+/// `${name}`, `${name|transform}`, `${name:default}` or both; `$$` is a
+/// literal `$`.
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\$\$|\$\{([A-Za-z_][A-Za-z0-9_]*)(?:\|([a-z]+))?(?::([^}]*))?\}").unwrap()
+});
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snippet {
+    pub name: String,
+    pub description: Option<String>,
+    /// Editor language id, e.g. "rust" or "typescriptreact".
+    pub language: Option<String>,
+    pub body: String,
+    /// Where `create_file_from_template` puts the file when no path is
+    /// given; may use the same placeholders as the body.
+    pub path: Option<String>,
+    /// Shipped with the app. Saving a snippet with the same name overrides
+    /// it, and deleting the override brings it back.
+    #[serde(default)]
+    pub builtin: bool,
+    /// Milliseconds since the Unix epoch.
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+fn builtin(name: &str, description: &str, language: &str, path: &str, body: &str) -> Snippet {
+    Snippet {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        language: Some(language.to_string()),
+        body: body.to_string(),
+        path: Some(path.to_string()),
+        builtin: true,
+        updated_at: 0,
+    }
+}
+
+fn builtin_snippets() -> Vec<Snippet> {
+    vec![
+        builtin(
+            "react-component",
+            "A React function component with a props interface",
+            "typescriptreact",
+            "src/components/${name|pascal}.tsx",
+            r#"export interface ${name|pascal}Props {
+  className?: string;
+}
+
+export function ${name|pascal}({ className }: ${name|pascal}Props) {
+  return <div className={className}></div>;
+}
+
+export default ${name|pascal};
+"#,
+        ),
+        builtin(
+            "rust-module",
+            "A Rust module with a struct and constructor",
+            "rust",
+            "src/${name|snake}.rs",
+            r#"#[derive(Debug, Default)]
+pub struct ${name|pascal} {}
+
+impl ${name|pascal} {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+"#,
+        ),
+    ]
+}
+
+fn storage_key(name: &str) -> String {
+    format!("{}{}", STORAGE_PREFIX, name)
+}
+
+/// Built-in snippets overlaid with the user's, by name.
+fn load_snippets() -> Result<BTreeMap<String, Snippet>, String> {
+    let mut snippets: BTreeMap<String, Snippet> = builtin_snippets()
+        .into_iter()
+        .map(|snippet| (snippet.name.clone(), snippet))
+        .collect();
+    for (_, json) in records_with_prefix(STORAGE_PREFIX).map_err(|e| e.to_string())? {
+        if let Ok(snippet) = serde_json::from_str::<Snippet>(&json) {
+            snippets.insert(snippet.name.clone(), snippet);
+        }
+    }
+    Ok(snippets)
+}
+
+fn find_snippet(name: &str) -> Result<Snippet, String> {
+    load_snippets()?
+        .remove(name)
+        .ok_or_else(|| format!("No snippet named {}", name))
+}
+
+/// Splits on separators and lower-to-upper case changes, so "myWidget",
+/// "my-widget" and "My Widget" all give ["my", "widget"].
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut previous_lower = false;
+    for c in text.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            previous_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && previous_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        previous_lower = c.is_lowercase() || c.is_numeric();
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn transform(value: &str, transform: &str) -> Result<String, String> {
+    let words = words(value);
+    Ok(match transform {
+        "pascal" => words.iter().map(|word| capitalize(word)).collect(),
+        "camel" => words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| {
+                if i == 0 {
+                    word.clone()
+                } else {
+                    capitalize(word)
+                }
+            })
+            .collect(),
+        "snake" => words.join("_"),
+        "kebab" => words.join("-"),
+        "constant" => words.join("_").to_uppercase(),
+        "upper" => value.to_uppercase(),
+        "lower" => value.to_lowercase(),
+        other => return Err(format!("Unknown transform {}", other)),
+    })
+}
+
+/// Expands the placeholders in `template`. Every placeholder without a
+/// default must have a value.
+fn expand(template: &str, vars: &HashMap<String, String>) -> Result<String, String> {
+    let mut missing = Vec::new();
+    let mut error = None;
+    let expanded = PLACEHOLDER.replace_all(template, |caps: &Captures| {
+        let Some(name) = caps.get(1) else {
+            return "$".to_string();
+        };
+        let value = match (vars.get(name.as_str()), caps.get(3)) {
+            (Some(value), _) => value.clone(),
+            (None, Some(default)) => default.as_str().to_string(),
+            (None, None) => {
+                let name = name.as_str().to_string();
+                if !missing.contains(&name) {
+                    missing.push(name);
+                }
+                return String::new();
+            }
+        };
+        match caps.get(2) {
+            Some(name) => transform(&value, name.as_str()).unwrap_or_else(|e| {
+                error.get_or_insert(e);
+                String::new()
+            }),
+            None => value,
+        }
+    });
+    if let Some(error) = error {
+        return Err(error);
+    }
+    if !missing.is_empty() {
+        return Err(format!("Missing values for {}", missing.join(", ")));
+    }
+    Ok(expanded.into_owned())
+}
+
+/// The caller's variables plus `date`, `year` and `workspace`, which they
+/// may override.
+fn with_builtin_vars(vars: Option<HashMap<String, String>>) -> HashMap<String, String> {
+    let now = Local::now();
+    let workspace = get_project_root()
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut all = HashMap::from([
+        ("date".to_string(), now.format("%Y-%m-%d").to_string()),
+        ("year".to_string(), now.format("%Y").to_string()),
+        ("workspace".to_string(), workspace),
+    ]);
+    all.extend(vars.unwrap_or_default());
+    all
+}
+
+/// Built-in and user snippets, by name.
+#[command]
+pub async fn list_snippets() -> Result<Vec<Snippet>, String> {
+    Ok(load_snippets()?.into_values().collect())
+}
+
+/// Adds or replaces a user snippet.
+#[command]
+pub async fn save_snippet(mut snippet: Snippet) -> Result<Snippet, String> {
+    let name = snippet.name.trim();
+    if name.is_empty() {
+        return Err("Snippet name is empty".to_string());
+    }
+    snippet.name = name.to_string();
+    snippet.builtin = false;
+    snippet.updated_at = Utc::now().timestamp_millis();
+    let json = serde_json::to_string(&snippet).map_err(|e| e.to_string())?;
+    put_record(&storage_key(&snippet.name), &json).map_err(|e| e.to_string())?;
+    Ok(snippet)
+}
+
+/// Deletes a user snippet. Built-in snippets can't be deleted, only
+/// overridden.
+#[command]
+pub async fn delete_snippet(name: String) -> Result<(), String> {
+    let key = storage_key(&name);
+    if get_record(&key).map_err(|e| e.to_string())?.is_none() {
+        return Err(format!("No user snippet named {}", name));
+    }
+    delete_record(&key).map_err(|e| e.to_string())
+}
+
+/// The snippet's body with its placeholders filled in from `vars`.
+#[command]
+pub async fn render_snippet(
+    name: String,
+    vars: Option<HashMap<String, String>>,
+) -> Result<String, String> {
+    let snippet = find_snippet(&name)?;
+    expand(&snippet.body, &with_builtin_vars(vars))
+}
+
+/// Creates a file from a snippet at `path`, or at the snippet's own path.
+/// The body may also use `file_path` and `file_name` (without extension).
+/// An existing file is only replaced with `overwrite`. Returns the path
+/// written.
+#[command]
+pub async fn create_file_from_template(
+    app: AppHandle,
+    request: Request<'_>,
+    name: String,
+    path: Option<String>,
+    vars: Option<HashMap<String, String>>,
+    overwrite: Option<bool>,
+) -> Result<String, FileSystemError> {
+    let template_error = |e: String| FileSystemError::new("TEMPLATE_ERROR", &e);
+    let snippet = find_snippet(&name).map_err(template_error)?;
+    let mut vars = with_builtin_vars(vars);
+
+    let path = match (path, &snippet.path) {
+        (Some(path), _) => path,
+        (None, Some(template)) => expand(template, &vars).map_err(template_error)?,
+        (None, None) => {
+            return Err(FileSystemError::new(
+                "PATH_REQUIRED",
+                &format!("Snippet {} has no default path; give one", name),
+            ))
+        }
+    };
+    let full_path = resolve_workspace_path(&path)?;
+    if full_path.exists() && !overwrite.unwrap_or(false) {
+        return Err(FileSystemError::with_path(
+            "ALREADY_EXISTS",
+            "File already exists",
+            &full_path,
+        ));
+    }
+
+    vars.insert("file_path".to_string(), path.clone());
+    let file_name = Path::new(&path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    vars.insert("file_name".to_string(), file_name);
+    let content = expand(&snippet.body, &vars).map_err(template_error)?;
+
+    write_file(app, request, path.clone(), content, None, None, None).await?;
+    Ok(path)
+}
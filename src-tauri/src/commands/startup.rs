@@ -0,0 +1,163 @@
+// src/commands/startup.rs
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::Instant;
+use tauri::{command, AppHandle, Emitter};
+use tracing::{error, info, warn};
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static SUBSYSTEMS: Lazy<Mutex<BTreeMap<Subsystem, SubsystemReadiness>>> = Lazy::new(|| {
+    Mutex::new(
+        Subsystem::ALL
+            .into_iter()
+            .map(|subsystem| (subsystem, SubsystemReadiness::new(subsystem)))
+            .collect(),
+    )
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Subsystem {
+    Storage,
+    Jobs,
+    ProcessManager,
+    FileSystem,
+    Format,
+    /// Started on first use rather than at startup.
+    Python,
+}
+
+impl Subsystem {
+    const ALL: [Subsystem; 6] = [
+        Subsystem::Storage,
+        Subsystem::Jobs,
+        Subsystem::ProcessManager,
+        Subsystem::FileSystem,
+        Subsystem::Format,
+        Subsystem::Python,
+    ];
+
+    fn lazy(self) -> bool {
+        self == Subsystem::Python
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessState {
+    /// Waiting for startup to reach it.
+    Pending,
+    /// Starts the first time something needs it.
+    Deferred,
+    Starting,
+    Ready,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubsystemReadiness {
+    pub subsystem: Subsystem,
+    pub state: ReadinessState,
+    pub error: Option<String>,
+    /// How long it took to start.
+    pub duration_ms: Option<u64>,
+    /// Milliseconds since the Unix epoch of the last change.
+    pub updated_at: i64,
+}
+
+impl SubsystemReadiness {
+    fn new(subsystem: Subsystem) -> Self {
+        Self {
+            subsystem,
+            state: if subsystem.lazy() {
+                ReadinessState::Deferred
+            } else {
+                ReadinessState::Pending
+            },
+            error: None,
+            duration_ms: None,
+            updated_at: Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// Remembers the handle readiness events go out on. Changes before this are
+/// still recorded for `get_startup_status`.
+pub(crate) fn begin(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+fn update(
+    subsystem: Subsystem,
+    state: ReadinessState,
+    error: Option<String>,
+    duration_ms: Option<u64>,
+) {
+    let readiness = SubsystemReadiness {
+        subsystem,
+        state,
+        error,
+        duration_ms,
+        updated_at: Utc::now().timestamp_millis(),
+    };
+    SUBSYSTEMS.lock().insert(subsystem, readiness.clone());
+
+    let event = match state {
+        ReadinessState::Ready => "subsystem-ready",
+        ReadinessState::Failed => "subsystem-failed",
+        _ => return,
+    };
+    if let Some(app) = APP.get() {
+        if let Err(e) = app.emit(event, &readiness) {
+            warn!("Failed to emit {}: {}", event, e);
+        }
+    }
+}
+
+pub(crate) fn mark_starting(subsystem: Subsystem) {
+    update(subsystem, ReadinessState::Starting, None, None);
+}
+
+pub(crate) fn mark_ready(subsystem: Subsystem, started: Instant) {
+    info!("{:?} ready", subsystem);
+    let duration_ms = started.elapsed().as_millis() as u64;
+    update(subsystem, ReadinessState::Ready, None, Some(duration_ms));
+}
+
+pub(crate) fn mark_failed(subsystem: Subsystem, error: String) {
+    error!("{:?} failed to start: {}", subsystem, error);
+    update(subsystem, ReadinessState::Failed, Some(error), None);
+}
+
+/// Starts `subsystem` with `init`, recording and announcing how it went.
+/// Returns whether it is ready.
+pub(crate) async fn start<F>(subsystem: Subsystem, init: F) -> bool
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let started = Instant::now();
+    mark_starting(subsystem);
+    match init.await {
+        Ok(()) => {
+            mark_ready(subsystem, started);
+            true
+        }
+        Err(e) => {
+            mark_failed(subsystem, e);
+            false
+        }
+    }
+}
+
+/// Where each subsystem is in starting up, for a UI that attached after
+/// the `subsystem-ready` and `subsystem-failed` events went out.
+#[command]
+pub async fn get_startup_status() -> Result<Vec<SubsystemReadiness>, String> {
+    Ok(SUBSYSTEMS.lock().values().cloned().collect())
+}
@@ -0,0 +1,291 @@
+// src/commands/status.rs
+
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tauri::{command, State};
+
+use super::auth::vault;
+use super::dev_server::{list_dev_servers, ProcessStatus};
+use super::jobs::pending_jobs;
+use super::{fs, oauth, settings, storage};
+use crate::bindings::python_runtime;
+use crate::config::{AppConfig, IssueSeverity};
+use crate::context::context;
+use crate::error::MightyError;
+use crate::state::AppState;
+
+/// Longest a single check may take before its subsystem is reported as not
+/// responding.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+/// Python module that computes embeddings.
+const EMBEDDING_MODULE: &str = "bge_embed";
+
+/// Ordered from best to worst, so the overall health is the maximum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Health {
+    Ok,
+    /// Not started or not configured, which is fine on its own.
+    Inactive,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubsystemStatus {
+    pub name: &'static str,
+    pub health: Health,
+    /// One line for the status bar tooltip.
+    pub summary: String,
+    /// Subsystem-specific facts for the drill-down view.
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+}
+
+impl SubsystemStatus {
+    fn new(name: &'static str, health: Health, summary: impl Into<String>) -> Self {
+        Self {
+            name,
+            health,
+            summary: summary.into(),
+            details: Value::Null,
+        }
+    }
+
+    fn with_details(mut self, details: Value) -> Self {
+        self.details = details;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemStatus {
+    /// The worst health of any subsystem; inactive ones count as ok.
+    pub health: Health,
+    /// Milliseconds since the Unix epoch.
+    pub checked_at: i64,
+    pub subsystems: Vec<SubsystemStatus>,
+}
+
+fn storage_status() -> SubsystemStatus {
+    match storage::storage_health() {
+        Ok(Some((path, records))) => SubsystemStatus::new("storage", Health::Ok, "Open")
+            .with_details(json!({ "path": path, "estimated_records": records })),
+        Ok(None) => SubsystemStatus::new("storage", Health::Warning, "Not open yet"),
+        Err(e) => SubsystemStatus::new("storage", Health::Error, e),
+    }
+}
+
+async fn context_status() -> SubsystemStatus {
+    match tokio::time::timeout(CHECK_TIMEOUT, context::context_health()).await {
+        Ok(Ok(Some(chunks))) => SubsystemStatus::new("context", Health::Ok, "LanceDB reachable")
+            .with_details(json!({ "chunks": chunks })),
+        Ok(Ok(None)) => SubsystemStatus::new("context", Health::Inactive, "Not initialized"),
+        Ok(Err(e)) => SubsystemStatus::new(
+            "context",
+            Health::Error,
+            format!("LanceDB unavailable: {}", e),
+        ),
+        Err(_) => SubsystemStatus::new("context", Health::Warning, "LanceDB is not responding"),
+    }
+}
+
+async fn python_status() -> SubsystemStatus {
+    if !python_runtime::is_initialized() {
+        // Python starts on first use, so this is normal until something needs it
+        return SubsystemStatus::new("python", Health::Inactive, "Not started yet");
+    }
+    // Importing needs the GIL, which a long embedding run may be holding
+    let import = tokio::task::spawn_blocking(|| {
+        python_runtime::run_python(|py| py.import(EMBEDDING_MODULE).map(drop))
+    });
+    match tokio::time::timeout(CHECK_TIMEOUT, import).await {
+        Ok(Ok(Ok(()))) => SubsystemStatus::new("python", Health::Ok, "Embeddings available"),
+        Ok(Ok(Err(e))) => SubsystemStatus::new(
+            "python",
+            Health::Error,
+            format!("Failed to load {}: {}", EMBEDDING_MODULE, e),
+        ),
+        Ok(Err(e)) => SubsystemStatus::new("python", Health::Error, e.to_string()),
+        Err(_) => SubsystemStatus::new("python", Health::Warning, "Busy; the check timed out"),
+    }
+}
+
+fn watcher_status() -> SubsystemStatus {
+    let (project_watcher, subscriptions) = fs::watcher_status();
+    let config_watcher = settings::config_watcher_active();
+    let details = json!({
+        "project": project_watcher,
+        "config": config_watcher,
+        "subscriptions": subscriptions,
+    });
+    let status = match (project_watcher, config_watcher) {
+        (true, true) => SubsystemStatus::new("watcher", Health::Ok, "Watching for changes"),
+        (false, _) => SubsystemStatus::new(
+            "watcher",
+            Health::Warning,
+            "Project files are not being watched",
+        ),
+        (true, false) => SubsystemStatus::new(
+            "watcher",
+            Health::Warning,
+            "Config file changes are not being picked up",
+        ),
+    };
+    status.with_details(details)
+}
+
+/// Whether each provider has a key (from the vault or the config file) and
+/// whether each OAuth provider is signed in.
+fn provider_status(config: &AppConfig) -> SubsystemStatus {
+    let keys = [
+        (
+            "anthropic",
+            config.anthropic.as_ref().and_then(|a| a.api_key.as_ref()),
+        ),
+        (
+            "greptile",
+            config.greptile.as_ref().and_then(|g| g.api_key.as_ref()),
+        ),
+    ];
+
+    let mut key_sources = serde_json::Map::new();
+    let mut missing = Vec::new();
+    for (provider, config_key) in keys {
+        let source = match vault().get(provider) {
+            Ok(Some(_)) => "vault",
+            Ok(None) if config_key.is_some() => "config",
+            Ok(None) => {
+                missing.push(provider);
+                "missing"
+            }
+            Err(e) => {
+                return SubsystemStatus::new("providers", Health::Error, e);
+            }
+        };
+        key_sources.insert(provider.to_string(), json!(source));
+    }
+    let signed_in: BTreeMap<&String, bool> = config
+        .oauth
+        .keys()
+        .map(|provider| (provider, oauth::load_tokens(provider).is_some()))
+        .collect();
+
+    let status = if missing.contains(&"anthropic") {
+        SubsystemStatus::new(
+            "providers",
+            Health::Warning,
+            "No Anthropic API key; AI features are unavailable",
+        )
+    } else if missing.is_empty() {
+        SubsystemStatus::new("providers", Health::Ok, "API keys configured")
+    } else {
+        SubsystemStatus::new(
+            "providers",
+            Health::Ok,
+            format!("No API key for {}", missing.join(", ")),
+        )
+    };
+    status.with_details(json!({ "keys": key_sources, "signed_in": signed_in }))
+}
+
+fn config_status(config: &AppConfig) -> SubsystemStatus {
+    let issues = config.validate();
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == IssueSeverity::Error)
+        .count();
+    let warnings = issues.len() - errors;
+    let details = json!({ "errors": errors, "warnings": warnings });
+    let status = if errors > 0 {
+        SubsystemStatus::new(
+            "config",
+            Health::Error,
+            format!("{} invalid setting(s)", errors),
+        )
+    } else if warnings > 0 {
+        SubsystemStatus::new(
+            "config",
+            Health::Warning,
+            format!("{} setting(s) to review", warnings),
+        )
+    } else {
+        SubsystemStatus::new("config", Health::Ok, "Valid")
+    };
+    status.with_details(details)
+}
+
+/// Long-running work the app is supervising in the background.
+async fn background_status() -> SubsystemStatus {
+    let servers = list_dev_servers().await.unwrap_or_default();
+    let count = |status: ProcessStatus| {
+        servers
+            .iter()
+            .filter(|server| server.status == status)
+            .count()
+    };
+    let running = count(ProcessStatus::Running) + count(ProcessStatus::Restarting);
+    let failed = count(ProcessStatus::Failed);
+    let (jobs_queued, jobs_running) = pending_jobs();
+    let details = json!({
+        "dev_servers_running": running,
+        "dev_servers_failed": failed,
+        "jobs_queued": jobs_queued,
+        "jobs_running": jobs_running,
+    });
+    let status = if failed > 0 {
+        SubsystemStatus::new(
+            "background",
+            Health::Warning,
+            format!("{} dev server(s) failed", failed),
+        )
+    } else if running > 0 || jobs_running > 0 {
+        let mut activity = Vec::new();
+        if running > 0 {
+            activity.push(format!("{} dev server(s) running", running));
+        }
+        if jobs_running > 0 {
+            activity.push(format!("{} job(s) running", jobs_running));
+        }
+        SubsystemStatus::new("background", Health::Ok, activity.join(", "))
+    } else {
+        SubsystemStatus::new("background", Health::Inactive, "Nothing running")
+    };
+    status.with_details(details)
+}
+
+/// Checks every subsystem and returns their health in one payload, for a
+/// single status bar indicator with a drill-down. Slow checks give up after
+/// a few seconds rather than holding up the rest.
+#[command]
+pub async fn get_system_status(state: State<'_, AppState>) -> Result<SystemStatus, MightyError> {
+    let config = state.config.lock().await.clone();
+    let (context, python, background) =
+        tokio::join!(context_status(), python_status(), background_status());
+    let subsystems = vec![
+        storage_status(),
+        context,
+        python,
+        watcher_status(),
+        provider_status(&config),
+        config_status(&config),
+        background,
+    ];
+
+    let health = subsystems
+        .iter()
+        .map(|subsystem| match subsystem.health {
+            Health::Inactive => Health::Ok,
+            health => health,
+        })
+        .max()
+        .unwrap_or(Health::Ok);
+    Ok(SystemStatus {
+        health,
+        checked_at: Utc::now().timestamp_millis(),
+        subsystems,
+    })
+}
@@ -1,17 +1,53 @@
 // src/commands/storage.rs
 
 use anyhow::{Context, Result};
-use once_cell::sync::OnceCell;
-use parking_lot::RwLock;
-use rocksdb::{DBWithThreadMode, MultiThreaded, Options};
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use rocksdb::checkpoint::Checkpoint;
+use rocksdb::{DBCompressionType, DBWithThreadMode, MultiThreaded, Options, WriteBatch};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tauri::ipc::Request;
+use tracing::{debug, error, info, warn};
+
+use super::audit;
+use super::budget;
+use super::permissions::Actor;
+use crate::config::StorageConfig;
+use crate::error::MightyError;
+use crate::state::app_state;
 
 type DB = DBWithThreadMode<MultiThreaded>;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The session, navigation history and terminal scrollback, which are
+/// rewritten whole on every autosave or jump.
+const DEFAULT_BUFFERED_PREFIXES: &[&str] = &["session:", "navigation:", "terminal_session:"];
+const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// Which keys are buffered, and how often they are flushed.
+struct WriteBehind {
+    prefixes: Vec<String>,
+    interval: Duration,
+}
+
+static WRITE_BEHIND: Lazy<RwLock<WriteBehind>> = Lazy::new(|| {
+    RwLock::new(WriteBehind {
+        prefixes: DEFAULT_BUFFERED_PREFIXES
+            .iter()
+            .map(|prefix| prefix.to_string())
+            .collect(),
+        interval: DEFAULT_FLUSH_INTERVAL,
+    })
+});
+static FLUSHER: OnceLock<()> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "MightyError")]
 pub struct StorageError {
     code: String,
     message: String,
@@ -25,21 +61,30 @@ impl std::fmt::Display for StorageError {
     }
 }
 
+impl From<StorageError> for MightyError {
+    fn from(e: StorageError) -> Self {
+        MightyError::Storage {
+            code: e.code,
+            message: e.message,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct StorageManager {
     db: Arc<DB>,
     db_path: PathBuf,
+    /// Latest values for buffered keys, not yet written.
+    pending: Arc<Mutex<HashMap<String, String>>>,
 }
 
-static STORAGE_MANAGER: OnceCell<RwLock<Option<StorageManager>>> = OnceCell::new();
-
 impl StorageManager {
     pub fn new(path: PathBuf) -> Result<Self, Box<dyn std::error::Error>> {
         // Create database directory if it doesn't exist
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)
                 .with_context(|| format!("Failed to create parent directory for {:?}", path))?;
-            println!("Created parent directory for {:?}", path);
+            debug!("Created parent directory for {:?}", path);
         }
 
         // Configure RocksDB options
@@ -49,46 +94,262 @@ impl StorageManager {
         opts.set_max_total_wal_size(536870912); // 512MB
         opts.set_write_buffer_size(67108864); // 64MB
         opts.set_max_open_files(32);
+        // zstd with a dictionary trained per file: values are mostly small JSON
+        // records that block compression alone barely shrinks. Files written
+        // before this are recompressed as compaction rewrites them.
+        opts.set_compression_type(DBCompressionType::Zstd);
+        opts.set_compression_options(-14, 3, 0, 16 * 1024); // 16KB dictionary
+        opts.set_zstd_max_train_bytes(100 * 16 * 1024);
 
         // Open database with multi-threaded mode
         match DB::open(&opts, &path) {
             Ok(db) => {
-                println!("Successfully opened RocksDB at {:?}", path);
+                info!("Successfully opened RocksDB at {:?}", path);
                 Ok(Self {
                     db: Arc::new(db),
                     db_path: path,
+                    pending: Arc::new(Mutex::new(HashMap::new())),
                 })
             }
             Err(e) => {
-                eprintln!("Failed to open RocksDB at {:?}: {}", path, e);
+                error!("Failed to open RocksDB at {:?}: {}", path, e);
                 Err(Box::new(e))
             }
         }
     }
 
     pub fn initialize(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-        // Initialize the OnceCell if not already done
-        let manager_lock = STORAGE_MANAGER.get_or_init(|| RwLock::new(None));
+        let manager_lock = &app_state().storage;
 
         // Check if StorageManager is already initialized
         if manager_lock.read().is_some() {
-            println!("StorageManager is already initialized.");
+            debug!("StorageManager is already initialized.");
             return Ok(());
         }
 
         // Initialize StorageManager
         let manager = Self::new(path.to_path_buf())?;
         *manager_lock.write() = Some(manager);
-        println!("StorageManager initialized and set in STORAGE_MANAGER.");
+        info!("StorageManager initialized and set in the app state.");
         Ok(())
     }
 
     pub fn shutdown(&self) -> Result<(), Box<dyn std::error::Error>> {
         // RocksDB handles its own cleanup upon drop, so manual removal is unnecessary
         // If you have additional cleanup, perform it here
-        println!("Shutting down StorageManager.");
+        info!("Shutting down StorageManager.");
+        self.flush()?;
         Ok(())
     }
+
+    /// Holds `value` until the next flush, replacing any value still waiting
+    /// for `key`.
+    fn buffer(&self, key: String, value: String) {
+        self.pending.lock().insert(key, value);
+    }
+
+    fn buffered(&self, key: &str) -> Option<String> {
+        self.pending.lock().get(key).cloned()
+    }
+
+    /// Buffers `value` if `key` is buffered, otherwise writes it now.
+    fn put(&self, key: &str, value: &str) -> Result<(), StorageError> {
+        if is_buffered(key) {
+            // Later writes to the key replace it; the latest reaches the
+            // database at the next flush
+            self.buffer(key.to_string(), value.to_string());
+            return Ok(());
+        }
+        // A value buffered before the prefixes changed would otherwise
+        // overwrite this one at the next flush
+        let mut pending = self.pending.lock();
+        pending.remove(key);
+        self.db
+            .put(key.as_bytes(), value.as_bytes())
+            .map_err(|e| StorageError {
+                code: "WRITE_ERROR".to_string(),
+                message: e.to_string(),
+            })
+    }
+
+    fn get(&self, key: &str) -> Result<Option<String>, StorageError> {
+        if let Some(value) = self.buffered(key) {
+            return Ok(Some(value));
+        }
+        self.db
+            .get(key.as_bytes())
+            .map(|value| value.map(|value| String::from_utf8_lossy(&value).to_string()))
+            .map_err(|e| StorageError {
+                code: "READ_ERROR".to_string(),
+                message: e.to_string(),
+            })
+    }
+
+    /// Writes the buffered values in one batch, returning how many.
+    fn flush(&self) -> Result<usize, StorageError> {
+        // Held while writing, so a delete can't be overtaken by the value it
+        // removes
+        let mut pending = self.pending.lock();
+        if pending.is_empty() {
+            return Ok(0);
+        }
+        let mut batch = WriteBatch::default();
+        for (key, value) in pending.iter() {
+            batch.put(key.as_bytes(), value.as_bytes());
+        }
+        self.db.write(batch).map_err(|e| StorageError {
+            code: "WRITE_ERROR".to_string(),
+            message: e.to_string(),
+        })?;
+        let flushed = pending.len();
+        pending.clear();
+        debug!("Flushed {} buffered writes", flushed);
+        Ok(flushed)
+    }
+}
+
+/// Whether writes to `key` are buffered.
+fn is_buffered(key: &str) -> bool {
+    WRITE_BEHIND
+        .read()
+        .prefixes
+        .iter()
+        .any(|prefix| key.starts_with(prefix.as_str()))
+}
+
+/// Writes buffered values now. Reads see them anyway; this is for anything
+/// that goes to the database files directly.
+pub(crate) fn flush_buffered_writes() -> Result<(), StorageError> {
+    match storage_manager() {
+        Ok(manager) => manager.flush().map(|_| ()),
+        // Nothing can have been buffered
+        Err(_) => Ok(()),
+    }
+}
+
+/// Applies the `[storage]` settings and starts flushing buffered writes.
+pub(crate) fn configure(config: Option<&StorageConfig>) {
+    *WRITE_BEHIND.write() = WriteBehind {
+        prefixes: config
+            .and_then(|c| c.buffered_prefixes.clone())
+            .unwrap_or_else(|| {
+                DEFAULT_BUFFERED_PREFIXES
+                    .iter()
+                    .map(|prefix| prefix.to_string())
+                    .collect()
+            }),
+        interval: config
+            .and_then(|c| c.flush_interval_ms)
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_FLUSH_INTERVAL),
+    };
+    if FLUSHER.set(()).is_err() {
+        return;
+    }
+    tauri::async_runtime::spawn(async {
+        loop {
+            let interval = WRITE_BEHIND.read().interval;
+            tokio::time::sleep(interval).await;
+            if let Err(e) = flush_buffered_writes() {
+                warn!("Failed to flush buffered writes: {}", e);
+            }
+        }
+    });
+}
+
+fn storage_manager() -> Result<StorageManager, StorageError> {
+    app_state()
+        .storage
+        .read()
+        .clone()
+        .ok_or_else(|| StorageError {
+            code: "NOT_INITIALIZED".to_string(),
+            message: "Storage manager not initialized".to_string(),
+        })
+}
+
+/// Where the open database lives and roughly how many records it holds, or
+/// `None` before storage is initialized. Reading the estimate also checks
+/// the database still responds.
+pub(crate) fn storage_health() -> Result<Option<(PathBuf, u64)>, String> {
+    let Ok(manager) = storage_manager() else {
+        return Ok(None);
+    };
+    let records = manager
+        .db
+        .property_int_value("rocksdb.estimate-num-keys")
+        .map_err(|e| e.to_string())?
+        .unwrap_or(0);
+    Ok(Some((manager.db_path, records)))
+}
+
+/// Where the open database lives, or `None` before storage is initialized.
+pub(crate) fn storage_dir() -> Option<PathBuf> {
+    storage_manager().ok().map(|manager| manager.db_path)
+}
+
+/// Stores a record for other backend modules. Unlike `store_value` this
+/// doesn't log the value, which may be large.
+pub(crate) fn put_record(key: &str, value: &str) -> Result<(), StorageError> {
+    storage_manager()?.put(key, value)
+}
+
+pub(crate) fn delete_record(key: &str) -> Result<(), StorageError> {
+    let manager = storage_manager()?;
+    manager.pending.lock().remove(key);
+    manager.db.delete(key.as_bytes()).map_err(|e| StorageError {
+        code: "DELETE_ERROR".to_string(),
+        message: e.to_string(),
+    })
+}
+
+pub(crate) fn get_record(key: &str) -> Result<Option<String>, StorageError> {
+    storage_manager()?.get(key)
+}
+
+/// All records whose key starts with `prefix`.
+pub(crate) fn records_with_prefix(prefix: &str) -> Result<Vec<(String, String)>, StorageError> {
+    let manager = storage_manager()?;
+    manager.flush()?;
+    let mut records = Vec::new();
+    for item in manager.db.prefix_iterator(prefix.as_bytes()) {
+        let (key, value) = item.map_err(|e| StorageError {
+            code: "SCAN_ERROR".to_string(),
+            message: e.to_string(),
+        })?;
+        // Without a prefix extractor the iterator runs past the prefix
+        if !key.starts_with(prefix.as_bytes()) {
+            break;
+        }
+        records.push((
+            String::from_utf8_lossy(&key).to_string(),
+            String::from_utf8_lossy(&value).to_string(),
+        ));
+    }
+    Ok(records)
+}
+
+/// Compacts the whole database, reclaiming the space of deleted and
+/// overwritten records. Blocks until it is done.
+pub(crate) fn compact_storage() -> Result<(), StorageError> {
+    let manager = storage_manager()?;
+    manager.flush()?;
+    manager.db.compact_range::<&[u8], &[u8]>(None, None);
+    Ok(())
+}
+
+/// Writes a consistent copy of the database to `destination`, which must not
+/// exist yet. Files are hard-linked where the filesystem allows, so this is
+/// quick even for a large database.
+pub(crate) fn backup_storage(destination: &Path) -> Result<(), StorageError> {
+    let manager = storage_manager()?;
+    manager.flush()?;
+    Checkpoint::new(&*manager.db)
+        .and_then(|checkpoint| checkpoint.create_checkpoint(destination))
+        .map_err(|e| StorageError {
+            code: "BACKUP_ERROR".to_string(),
+            message: e.to_string(),
+        })
 }
 
 #[derive(Debug, Serialize)]
@@ -99,7 +360,7 @@ pub struct StorageCleanupResult {
 
 #[tauri::command]
 pub async fn initialize_storage(db_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    println!(
+    debug!(
         "Attempting to initialize StorageManager at path: {}",
         db_path.display()
     );
@@ -107,52 +368,50 @@ pub async fn initialize_storage(db_path: &Path) -> Result<(), Box<dyn std::error
 }
 
 #[tauri::command]
-pub async fn store_value(key: String, value: String) -> Result<(), StorageError> {
-    let manager_lock = STORAGE_MANAGER.get().ok_or_else(|| StorageError {
-        code: "NOT_INITIALIZED".to_string(),
-        message: "Storage manager not initialized".to_string(),
-    })?;
+pub async fn store_value(
+    request: Request<'_>,
+    key: String,
+    value: String,
+) -> Result<(), StorageError> {
+    let manager = storage_manager()?;
+    if let Some(message) = budget::storage_refusal() {
+        return Err(StorageError {
+            code: "BUDGET_EXCEEDED".to_string(),
+            message,
+        });
+    }
 
-    let manager_read = manager_lock.read();
-    let manager = manager_read.as_ref().ok_or_else(|| StorageError {
-        code: "NOT_INITIALIZED".to_string(),
-        message: "Storage manager not initialized".to_string(),
-    })?;
+    debug!("Storing value for key: {}", key);
 
-    println!("Storing value: key={}, value={}", key, value);
+    let bytes = value.len();
+    manager.put(&key, &value)?;
 
-    manager
-        .db
-        .put(key.as_bytes(), value.as_bytes())
-        .map_err(|e| StorageError {
-            code: "WRITE_ERROR".to_string(),
-            message: e.to_string(),
-        })
+    // Only the agent's writes are audited; the UI's own use of storage would
+    // drown the log
+    let actor = Actor::of(&request);
+    if actor == Actor::Agent {
+        audit::record(actor, "store_value", &key, json!({ "bytes": bytes }));
+    }
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn get_value(key: String) -> Result<Option<String>, StorageError> {
-    let manager_lock = STORAGE_MANAGER.get().ok_or_else(|| StorageError {
-        code: "NOT_INITIALIZED".to_string(),
-        message: "Storage manager not initialized".to_string(),
-    })?;
+    let manager = storage_manager()?;
 
-    let manager_read = manager_lock.read();
-    let manager = manager_read.as_ref().ok_or_else(|| StorageError {
-        code: "NOT_INITIALIZED".to_string(),
-        message: "Storage manager not initialized".to_string(),
-    })?;
-
-    println!("Retrieving value for key: {}", key);
+    debug!("Retrieving value for key: {}", key);
 
+    if let Some(value) = manager.buffered(&key) {
+        return Ok(Some(value));
+    }
     match manager.db.get(key.as_bytes()) {
         Ok(Some(value)) => {
             let retrieved = String::from_utf8_lossy(&value).to_string();
-            println!("Retrieved value: {}", retrieved);
+            debug!("Retrieved value for key: {}", key);
             Ok(Some(retrieved))
         }
         Ok(None) => {
-            println!("No value found for key: {}", key);
+            debug!("No value found for key: {}", key);
             Ok(None)
         }
         Err(e) => Err(StorageError {
@@ -163,41 +422,31 @@ pub async fn get_value(key: String) -> Result<Option<String>, StorageError> {
 }
 
 #[tauri::command]
-pub async fn delete_value(key: String) -> Result<(), StorageError> {
-    let manager_lock = STORAGE_MANAGER.get().ok_or_else(|| StorageError {
-        code: "NOT_INITIALIZED".to_string(),
-        message: "Storage manager not initialized".to_string(),
-    })?;
-
-    let manager_read = manager_lock.read();
-    let manager = manager_read.as_ref().ok_or_else(|| StorageError {
-        code: "NOT_INITIALIZED".to_string(),
-        message: "Storage manager not initialized".to_string(),
-    })?;
+pub async fn delete_value(request: Request<'_>, key: String) -> Result<(), StorageError> {
+    let manager = storage_manager()?;
 
-    println!("Deleting value for key: {}", key);
+    debug!("Deleting value for key: {}", key);
 
+    manager.pending.lock().remove(&key);
     manager.db.delete(key.as_bytes()).map_err(|e| StorageError {
         code: "DELETE_ERROR".to_string(),
         message: e.to_string(),
-    })
+    })?;
+
+    let actor = Actor::of(&request);
+    if actor == Actor::Agent {
+        audit::record(actor, "delete_value", &key, json!(null));
+    }
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn scan_prefix(prefix: String) -> Result<Vec<(String, String)>, StorageError> {
-    let manager_lock = STORAGE_MANAGER.get().ok_or_else(|| StorageError {
-        code: "NOT_INITIALIZED".to_string(),
-        message: "Storage manager not initialized".to_string(),
-    })?;
-
-    let manager_read = manager_lock.read();
-    let manager = manager_read.as_ref().ok_or_else(|| StorageError {
-        code: "NOT_INITIALIZED".to_string(),
-        message: "Storage manager not initialized".to_string(),
-    })?;
+    let manager = storage_manager()?;
 
-    println!("Scanning for prefix: {}", prefix);
+    debug!("Scanning for prefix: {}", prefix);
 
+    manager.flush()?;
     let mut results = Vec::new();
     let iterator = manager.db.prefix_iterator(prefix.as_bytes());
 
@@ -208,12 +457,12 @@ pub async fn scan_prefix(prefix: String) -> Result<Vec<(String, String)>, Storag
                     String::from_utf8(key.to_vec()),
                     String::from_utf8(value.to_vec()),
                 ) {
-                    println!("Found key: {}, value: {}", k, v);
+                    debug!("Found key: {}", k);
                     results.push((k, v));
                 }
             }
             Err(e) => {
-                println!("Error scanning prefix: {}", e);
+                error!("Error scanning prefix: {}", e);
                 return Err(StorageError {
                     code: "SCAN_ERROR".to_string(),
                     message: e.to_string(),
@@ -227,20 +476,59 @@ pub async fn scan_prefix(prefix: String) -> Result<Vec<(String, String)>, Storag
 
 #[tauri::command]
 pub async fn cleanup_storage() -> Result<StorageCleanupResult, String> {
-    if let Some(manager_lock) = STORAGE_MANAGER.get() {
-        if let Some(manager) = manager_lock.write().take() {
-            if let Err(e) = manager.shutdown() {
-                return Err(format!("Failed to shutdown storage manager: {}", e));
-            }
-            return Ok(StorageCleanupResult {
-                cleaned_locks: true,
-                message: "Successfully shut down storage manager and cleaned up lock files."
-                    .to_string(),
-            });
+    if let Some(manager) = app_state().storage.write().take() {
+        if let Err(e) = manager.shutdown() {
+            return Err(format!("Failed to shutdown storage manager: {}", e));
         }
+        return Ok(StorageCleanupResult {
+            cleaned_locks: true,
+            message: "Successfully shut down storage manager and cleaned up lock files."
+                .to_string(),
+        });
     }
     Ok(StorageCleanupResult {
         cleaned_locks: false,
         message: "Storage manager was not initialized.".to_string(),
     })
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn scratch_manager() -> StorageManager {
+        let path = std::env::temp_dir().join(format!("mighty-storage-{}", Uuid::new_v4()));
+        StorageManager::new(path).unwrap()
+    }
+
+    #[test]
+    fn buffered_writes_are_read_back_before_flush() {
+        let manager = scratch_manager();
+        manager.put("session:/workspace", "first").unwrap();
+        manager.put("session:/workspace", "second").unwrap();
+        assert_eq!(manager.db.get(b"session:/workspace").unwrap(), None);
+        assert_eq!(
+            manager.get("session:/workspace").unwrap().as_deref(),
+            Some("second")
+        );
+
+        assert_eq!(manager.flush().unwrap(), 1);
+        assert_eq!(
+            manager.db.get(b"session:/workspace").unwrap().as_deref(),
+            Some(&b"second"[..])
+        );
+    }
+
+    #[test]
+    fn direct_write_is_not_overwritten_by_an_older_buffered_value() {
+        let manager = scratch_manager();
+        // Buffered while the key's prefix was still configured as buffered
+        manager.buffer("settings:theme".to_string(), "old".to_string());
+        manager.put("settings:theme", "new").unwrap();
+        manager.flush().unwrap();
+        assert_eq!(
+            manager.get("settings:theme").unwrap().as_deref(),
+            Some("new")
+        );
+    }
+}
@@ -1,84 +1,680 @@
+use chrono::Utc;
 use lazy_static::lazy_static;
-use nix::{
-    libc,
-    pty::{openpty, Winsize},
-    sys::termios::{self, InputFlags, LocalFlags, OutputFlags, SetArg, Termios},
+use portable_pty::{
+    native_pty_system, ChildKiller, CommandBuilder, ExitStatus, MasterPty, PtySize,
 };
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::{
-    collections::HashMap,
-    fs::File,
+    collections::{HashMap, VecDeque},
     io::{Read, Write},
-    os::unix::io::{AsRawFd, FromRawFd},
-    sync::{Arc, Mutex},
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex, Once},
     thread,
+    time::Duration,
 };
-use tauri::{command, Emitter, Window};
+use tauri::ipc::Request;
+use tauri::{command, AppHandle, Emitter, Window};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tracing::warn;
 use uuid::Uuid;
 
+use super::audit;
+use super::command_history::{record_command, CommandHistoryEntry, CommandTracker};
+use super::dry_run::{self, DRY_RUN_CODE};
+use super::exec::ExecError;
+use super::fs::get_project_root;
+use super::permissions::{authorize, Actor, Capability};
+use super::shell_integration;
+use super::storage::{delete_record, get_record, put_record, records_with_prefix};
+use super::trust::ensure_trusted;
+use crate::error::MightyError;
+use crate::state::app_state;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TerminalSession {
     pub id: String,
     pub pid: u32,
+    pub shell: String,
+    pub cwd: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TerminalConfig {
     pub shell: Option<String>,
     pub args: Option<Vec<String>>,
+    /// Passed to the shell only; the app's own environment is untouched.
     pub env: Option<HashMap<String, String>>,
+    /// Starting directory, absolute or relative to the workspace root
+    /// (the default).
+    pub cwd: Option<String>,
+}
+
+/// Terminal errors are reported like those of other processes.
+fn terminal_error(code: &str, message: &str) -> MightyError {
+    ExecError::new(code, message).into()
+}
+
+fn session_not_found() -> MightyError {
+    terminal_error("SESSION_NOT_FOUND", "Terminal session not found")
+}
+
+/// Lines of output kept per session for views that attach later.
+const SCROLLBACK_LINES: usize = 5000;
+/// Output without newlines (e.g. `\r` progress bars) is split at this size.
+const MAX_LINE_LENGTH: usize = 16 * 1024;
+
+/// Bounded ring buffer of terminal output, split into lines.
+#[derive(Default)]
+struct Scrollback {
+    lines: VecDeque<String>,
+    partial: String,
+}
+
+impl Scrollback {
+    fn push(&mut self, data: &str) {
+        for piece in data.split_inclusive('\n') {
+            self.partial.push_str(piece);
+            if piece.ends_with('\n') || self.partial.len() >= MAX_LINE_LENGTH {
+                if self.lines.len() == SCROLLBACK_LINES {
+                    self.lines.pop_front();
+                }
+                self.lines.push_back(std::mem::take(&mut self.partial));
+            }
+        }
+    }
+
+    /// The last `lines` lines, including the unfinished one (the prompt).
+    fn tail(&self, lines: Option<usize>) -> String {
+        let complete = match lines {
+            Some(lines) if !self.partial.is_empty() => lines.saturating_sub(1),
+            Some(lines) => lines,
+            None => self.lines.len(),
+        };
+        let skip = self.lines.len().saturating_sub(complete);
+        let mut output: String = self.lines.iter().skip(skip).map(String::as_str).collect();
+        if lines != Some(0) {
+            output.push_str(&self.partial);
+        }
+        output
+    }
 }
 
-struct TerminalInstance {
+/// Output is coalesced and emitted at most once per interval.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(16);
+/// Largest single `terminal-output` payload.
+const MAX_CHUNK_BYTES: usize = 64 * 1024;
+/// Unsent output beyond this means the UI can't keep up; the oldest part is
+/// dropped (it is still in the scrollback) and replaced by a marker.
+const MAX_PENDING_BYTES: usize = 1024 * 1024;
+
+/// Output read from the PTY that hasn't been emitted yet.
+#[derive(Default)]
+struct PendingOutput {
+    data: String,
+    dropped_bytes: usize,
+    closed: bool,
+    /// Set once everything has been emitted (or emitting gave up).
+    drained: bool,
+}
+
+#[derive(Default)]
+struct OutputQueue {
+    pending: Mutex<PendingOutput>,
+    ready: Condvar,
+    drained: Condvar,
+}
+
+impl OutputQueue {
+    fn push(&self, data: &str) {
+        let mut pending = self.pending.lock().unwrap();
+        pending.data.push_str(data);
+        if pending.data.len() > MAX_PENDING_BYTES {
+            let excess =
+                floor_char_boundary(&pending.data, pending.data.len() - MAX_PENDING_BYTES / 2);
+            pending.data.drain(..excess);
+            pending.dropped_bytes += excess;
+        }
+        self.ready.notify_one();
+    }
+
+    fn close(&self) {
+        self.pending.lock().unwrap().closed = true;
+        self.ready.notify_one();
+    }
+
+    fn finish(&self) {
+        self.pending.lock().unwrap().drained = true;
+        self.drained.notify_all();
+    }
+
+    /// Waits up to `timeout` for the remaining output to be emitted.
+    fn wait_drained(&self, timeout: Duration) {
+        let pending = self.pending.lock().unwrap();
+        let _ = self
+            .drained
+            .wait_timeout_while(pending, timeout, |pending| !pending.drained)
+            .unwrap();
+    }
+
+    /// Blocks until output is available, then returns the next chunk to emit,
+    /// or `None` once the queue is closed and drained.
+    fn next_chunk(&self) -> Option<String> {
+        let mut pending = self.pending.lock().unwrap();
+        while pending.data.is_empty() && !pending.closed {
+            pending = self.ready.wait(pending).unwrap();
+        }
+        if pending.data.is_empty() {
+            return None;
+        }
+        // Give the rest of a burst a moment to arrive so it goes out together
+        if !pending.closed {
+            drop(pending);
+            thread::sleep(FLUSH_INTERVAL);
+            pending = self.pending.lock().unwrap();
+        }
+
+        let end = floor_char_boundary(&pending.data, MAX_CHUNK_BYTES);
+        let mut chunk = String::new();
+        if pending.dropped_bytes > 0 {
+            chunk.push_str(&format!(
+                "\r\n\x1b[2m[output truncated: {} bytes skipped]\x1b[0m\r\n",
+                std::mem::take(&mut pending.dropped_bytes)
+            ));
+        }
+        chunk.extend(pending.data.drain(..end));
+        Some(chunk)
+    }
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    if index >= text.len() {
+        return text.len();
+    }
+    (0..=index)
+        .rev()
+        .find(|&i| text.is_char_boundary(i))
+        .unwrap_or(0)
+}
+
+/// Decodes as much of `bytes` as forms complete UTF-8, leaving a character
+/// split across reads in place for the next call.
+fn take_utf8(bytes: &mut Vec<u8>) -> String {
+    let valid = match std::str::from_utf8(bytes) {
+        Ok(_) => bytes.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => {
+            let text = String::from_utf8_lossy(bytes).to_string();
+            bytes.clear();
+            return text;
+        }
+    };
+    let text = String::from_utf8_lossy(&bytes[..valid]).to_string();
+    bytes.drain(..valid);
+    text
+}
+
+/// How long the shell gets to exit after being asked before it is killed.
+const KILL_GRACE: Duration = Duration::from_secs(2);
+const DEFAULT_RUN_TIMEOUT_MS: u64 = 120_000;
+/// How long an exit event waits for the shell's remaining output.
+const EXIT_OUTPUT_GRACE: Duration = Duration::from_secs(1);
+
+/// Filled in by the waiter thread once the shell has exited and been reaped.
+#[derive(Default)]
+struct ShellExit {
+    status: Mutex<Option<ExitStatus>>,
+    changed: Condvar,
+}
+
+impl ShellExit {
+    fn set(&self, status: ExitStatus) {
+        *self.status.lock().unwrap() = Some(status);
+        self.changed.notify_all();
+    }
+
+    /// Waits up to `timeout` for the shell to exit; true if it has.
+    fn wait(&self, timeout: Duration) -> bool {
+        let status = self.status.lock().unwrap();
+        let (status, _) = self
+            .changed
+            .wait_timeout_while(status, timeout, |status| status.is_none())
+            .unwrap();
+        status.is_some()
+    }
+}
+
+/// Longest OSC sequence kept while waiting for its terminator.
+const MAX_OSC_LENGTH: usize = 4096;
+
+/// Picks OSC sequences (`ESC ] code ; text BEL` or `... ESC \\`) out of the
+/// output stream, including ones split across reads.
+#[derive(Default)]
+struct OscParser {
+    partial: String,
+}
+
+impl OscParser {
+    fn feed(&mut self, data: &str) -> Vec<(u32, String)> {
+        let mut text = std::mem::take(&mut self.partial);
+        text.push_str(data);
+
+        let mut sequences = Vec::new();
+        let mut rest = text.as_str();
+        while let Some(start) = rest.find("\x1b]") {
+            let body = &rest[start + 2..];
+            let Some(end) = body.find(['\x07', '\x1b']) else {
+                if body.len() < MAX_OSC_LENGTH {
+                    self.partial = rest[start..].to_string();
+                }
+                break;
+            };
+            // An ESC that isn't followed by `\\` may just not have arrived yet
+            if body[end..].starts_with('\x1b') && body.len() == end + 1 {
+                self.partial = rest[start..].to_string();
+                break;
+            }
+            if let Some((code, value)) = body[..end].split_once(';') {
+                if let Ok(code) = code.parse() {
+                    sequences.push((code, value.to_string()));
+                }
+            }
+            rest = &body[end + 1..];
+        }
+        // Keep a trailing lone ESC, which may start the next sequence
+        if self.partial.is_empty() && text.ends_with('\x1b') {
+            self.partial = "\x1b".to_string();
+        }
+        sequences
+    }
+}
+
+/// Decodes the path of an OSC 7 `file://host/path` URL.
+fn path_from_file_url(url: &str) -> Option<String> {
+    let rest = url.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    let path = String::from_utf8(decoded).ok()?;
+    // file:///C:/Users/... on Windows
+    if cfg!(windows) {
+        return Some(path.trim_start_matches('/').to_string());
+    }
+    Some(path)
+}
+
+/// Handles the OSC sequences the UI cares about: 0 and 2 set the title,
+/// 7 reports the shell's working directory.
+fn apply_osc(
+    window: &Window,
+    session_id: &str,
+    metadata: &Mutex<SessionMetadata>,
+    code: u32,
+    text: &str,
+) {
+    match code {
+        0 | 2 => {
+            let mut metadata = metadata.lock().unwrap();
+            if metadata.title.as_deref() == Some(text) {
+                return;
+            }
+            metadata.title = Some(text.to_string());
+            drop(metadata);
+            let payload = json!({ "session_id": session_id, "title": text });
+            if let Err(e) = window.emit("terminal-title-changed", payload) {
+                warn!("Failed to emit terminal title: {}", e);
+            }
+        }
+        7 => {
+            let Some(cwd) = path_from_file_url(text) else {
+                return;
+            };
+            let mut metadata = metadata.lock().unwrap();
+            if metadata.cwd == cwd || !Path::new(&cwd).is_dir() {
+                return;
+            }
+            metadata.cwd = cwd.clone();
+            drop(metadata);
+            let payload = json!({ "session_id": session_id, "cwd": cwd });
+            if let Err(e) = window.emit("terminal-cwd-changed", payload) {
+                warn!("Failed to emit terminal cwd: {}", e);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// What a session was started with, kept so it can be saved and respawned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SessionMetadata {
+    shell: String,
+    args: Vec<String>,
+    cwd: String,
+    env: HashMap<String, String>,
+    title: Option<String>,
+    created_at: i64,
+}
+
+/// Each handle onto the PTY owns its own descriptor: the master keeps the
+/// original, while the writer and the reader thread's reader are duplicates
+/// handed out by portable-pty. Nothing is closed twice, and dropping the
+/// master when a session ends doesn't pull the descriptor out from under
+/// another session that happens to reuse the number.
+pub(crate) struct TerminalInstance {
     writer: Arc<Mutex<Box<dyn Write + Send>>>,
-    running: Arc<Mutex<bool>>,
-    raw_fd: i32,
+    master: Box<dyn MasterPty + Send>,
+    killer: Box<dyn ChildKiller + Send + Sync>,
+    scrollback: Arc<Mutex<Scrollback>>,
+    pid: u32,
+    exit: Arc<ShellExit>,
+    metadata: Arc<Mutex<SessionMetadata>>,
+    /// Receives a copy of all output while `run_in_terminal` is waiting on
+    /// a command.
+    watcher: Arc<Mutex<Option<UnboundedSender<String>>>>,
+}
+
+impl TerminalInstance {
+    /// Stops the shell and whatever it is running, the way closing a terminal
+    /// window does, escalating to SIGKILL if it doesn't exit in time. Returns
+    /// once the shell has been reaped.
+    fn shutdown(mut self) {
+        if self.exit.wait(Duration::ZERO) {
+            return;
+        }
+
+        #[cfg(unix)]
+        {
+            use nix::libc;
+
+            let foreground = self.master.process_group_leader();
+            unsafe {
+                if let Some(group) = foreground.filter(|&group| group as u32 != self.pid) {
+                    libc::kill(-group, libc::SIGTERM);
+                }
+            }
+            // portable-pty sends SIGHUP, which an interactive shell passes on
+            // to its jobs
+            if let Err(e) = self.killer.kill() {
+                warn!("Failed to signal terminal shell: {}", e);
+            }
+            if self.exit.wait(KILL_GRACE) {
+                return;
+            }
+            unsafe {
+                if let Some(group) = foreground {
+                    libc::kill(-group, libc::SIGKILL);
+                }
+                libc::kill(-(self.pid as i32), libc::SIGKILL);
+            }
+        }
+        #[cfg(not(unix))]
+        if let Err(e) = self.killer.kill() {
+            warn!("Failed to kill terminal shell: {}", e);
+        }
+
+        if !self.exit.wait(KILL_GRACE) {
+            warn!(
+                "Terminal shell {} did not exit after being killed",
+                self.pid
+            );
+        }
+    }
+}
+
+/// Storage key prefix for saved sessions.
+const STORAGE_PREFIX: &str = "terminal_session:";
+/// Lines of scrollback saved with a session.
+const PERSISTED_SCROLLBACK_LINES: usize = 500;
+const PERSIST_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Saved form of a session, restored by `reattach_terminal` after a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PersistedSession {
+    id: String,
+    #[serde(flatten)]
+    metadata: SessionMetadata,
+    scrollback: String,
+    updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TerminalSessionInfo {
+    pub id: String,
+    pub shell: String,
+    pub cwd: String,
+    pub title: Option<String>,
+    /// False for sessions saved by a previous run that haven't been
+    /// reattached yet.
+    pub running: bool,
+    pub pid: Option<u32>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReattachedTerminal {
+    pub session: TerminalSession,
+    /// Output to replay into the view before live output arrives.
+    pub scrollback: String,
+    /// True when the shell had to be started again.
+    pub respawned: bool,
 }
 
 lazy_static! {
-    static ref TERMINAL_SESSIONS: Arc<Mutex<HashMap<String, TerminalInstance>>> =
-        Arc::new(Mutex::new(HashMap::new()));
+    static ref ANSI_ESCAPE: Regex =
+        Regex::new(r"\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b\[[0-?]*[ -/]*[@-~]|\x1b[@-Z\\-_]")
+            .unwrap();
+    /// What the agent has typed into each session since its last Enter, so
+    /// a command sent in pieces is still approved as a whole.
+    static ref AGENT_LINES: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
 }
 
-fn configure_terminal(fd: &File) -> nix::Result<()> {
-    let mut termios = termios::tcgetattr(fd)?;
+/// Starts a shell for `session_id` and registers it. `history` seeds the
+/// scrollback when a saved session is respawned.
+fn spawn_session(
+    window: Window,
+    session_id: String,
+    metadata: SessionMetadata,
+    history: &str,
+) -> Result<TerminalSession, MightyError> {
+    let cwd = PathBuf::from(&metadata.cwd);
+    if !cwd.is_dir() {
+        return Err(terminal_error(
+            "INVALID_CWD",
+            &format!("{} is not a directory", cwd.display()),
+        ));
+    }
+
+    // Open a new PTY (ConPTY on Windows)
+    let pty = native_pty_system()
+        .openpty(PtySize {
+            rows: 24,
+            cols: 80,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(|e| terminal_error("PTY_ERROR", &e.to_string()))?;
+
+    let mut command = CommandBuilder::new(&metadata.shell);
+    command.cwd(&cwd);
+    command.env("TERM", "xterm-256color");
+    for (key, value) in &metadata.env {
+        command.env(key, value);
+    }
+    // Lets the shell report the commands typed into it for the history
+    match shell_integration::prepare(&metadata.shell, &metadata.args, &metadata.env) {
+        Some(integration) => {
+            command.args(&integration.args);
+            for (key, value) in &integration.env {
+                command.env(key, value);
+            }
+        }
+        None => command.args(&metadata.args),
+    }
+
+    let mut child = pty
+        .slave
+        .spawn_command(command)
+        .map_err(|e| terminal_error("SPAWN_FAILED", &format!("Failed to spawn shell: {}", e)))?;
+    // Only the shell should hold the slave side, so reads end when it exits
+    drop(pty.slave);
+
+    let pid = child.process_id().unwrap_or_default();
+    // Both are dups of the master descriptor; see TerminalInstance
+    let mut reader = pty
+        .master
+        .try_clone_reader()
+        .map_err(|e| terminal_error("PTY_ERROR", &e.to_string()))?;
+    let writer = pty
+        .master
+        .take_writer()
+        .map_err(|e| terminal_error("PTY_ERROR", &e.to_string()))?;
+
+    // Create terminal instance
+    let mut scrollback = Scrollback::default();
+    scrollback.push(history);
+    let scrollback = Arc::new(Mutex::new(scrollback));
+    let exit = Arc::new(ShellExit::default());
+    let watcher = Arc::new(Mutex::new(None::<UnboundedSender<String>>));
+    let session = TerminalSession {
+        id: session_id.clone(),
+        pid,
+        shell: metadata.shell.clone(),
+        cwd: metadata.cwd.clone(),
+    };
+    let metadata = Arc::new(Mutex::new(metadata));
+    let terminal = TerminalInstance {
+        writer: Arc::new(Mutex::new(writer)),
+        master: pty.master,
+        killer: child.clone_killer(),
+        scrollback: scrollback.clone(),
+        pid,
+        exit: exit.clone(),
+        metadata: metadata.clone(),
+        watcher: watcher.clone(),
+    };
+
+    // Store the session
+    app_state()
+        .terminals
+        .lock()
+        .unwrap()
+        .insert(session_id.clone(), terminal);
+    start_persistence();
+
+    // Set up output reader thread; it only buffers, emitting happens on the
+    // flusher thread so bursts don't flood the IPC bridge
+    let queue = Arc::new(OutputQueue::default());
+    let reader_queue = queue.clone();
+    let exit_queue = queue.clone();
+    let reader_window = window.clone();
+    let reader_session_id = session_id.clone();
+    thread::spawn(move || {
+        let mut buffer = [0u8; 8192];
+        let mut undecoded = Vec::new();
+        let mut osc = OscParser::default();
+        let mut commands = CommandTracker::default();
+
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => {
+                    undecoded.extend_from_slice(&buffer[..n]);
+                    let data = take_utf8(&mut undecoded);
+                    if data.is_empty() {
+                        continue;
+                    }
+                    scrollback.lock().unwrap().push(&data);
+                    for (code, text) in osc.feed(&data) {
+                        if code == 633 {
+                            let cwd = metadata.lock().unwrap().cwd.clone();
+                            if let Some(entry) = commands.observe(&reader_session_id, &cwd, &text) {
+                                record_command(&entry);
+                            }
+                        } else {
+                            apply_osc(&reader_window, &reader_session_id, &metadata, code, &text);
+                        }
+                    }
+                    let mut watcher = watcher.lock().unwrap();
+                    if let Some(sender) = watcher.as_ref() {
+                        if sender.send(data.clone()).is_err() {
+                            *watcher = None;
+                        }
+                    }
+                    drop(watcher);
+                    reader_queue.push(&data);
+                }
+                Err(_) => break,
+            }
+        }
+        // Let a waiting run_in_terminal know the shell is gone
+        watcher.lock().unwrap().take();
+        reader_queue.close();
+    });
+
+    let window_clone = window.clone();
+    let session_id_clone = session_id.clone();
+    thread::spawn(move || {
+        while let Some(data) = queue.next_chunk() {
+            let payload = json!({
+                "session_id": session_id_clone,
+                "data": data
+            });
+
+            if let Err(e) = window_clone.emit("terminal-output", payload) {
+                warn!("Failed to emit terminal output: {}", e);
+                break;
+            }
+        }
+        queue.finish();
+    });
 
-    // Disable both terminal echo and keyboard echo
-    termios.local_flags &=
-        !(LocalFlags::ECHO | LocalFlags::ECHOE | LocalFlags::ECHOK | LocalFlags::ECHONL);
+    // Reap the shell once it exits so it doesn't linger as a zombie, and
+    // report the exit after its last output has gone out. A background job
+    // can keep the PTY open, so that wait is bounded.
+    thread::spawn(move || {
+        let status = child
+            .wait()
+            .unwrap_or_else(|_| ExitStatus::with_exit_code(1));
+        exit.set(status.clone());
+        exit_queue.wait_drained(EXIT_OUTPUT_GRACE);
 
-    // Set the new attributes
-    termios::tcsetattr(fd, SetArg::TCSANOW, &termios)?;
+        // portable-pty only exposes the signal through Display
+        let description = status.to_string();
+        let signal = description.strip_prefix("Terminated by ").map(String::from);
+        let payload = json!({
+            "session_id": session_id,
+            "exit_code": if signal.is_some() { None } else { Some(status.exit_code()) },
+            "signal": signal
+        });
+        if let Err(e) = window.emit("terminal-exited", payload) {
+            warn!("Failed to emit terminal exit: {}", e);
+        }
+    });
 
-    Ok(())
+    Ok(session)
 }
 
 #[command]
 pub async fn create_terminal_session(
     window: Window,
     config: Option<TerminalConfig>,
-) -> Result<TerminalSession, String> {
-    // Open a new PTY
-    let pty = openpty(
-        Some(&Winsize {
-            ws_row: 24,
-            ws_col: 80,
-            ws_xpixel: 0,
-            ws_ypixel: 0,
-        }),
-        None,
-    )
-    .map_err(|e| e.to_string())?;
-
+) -> Result<TerminalSession, MightyError> {
     let session_id = Uuid::new_v4().to_string();
 
-    // Convert to File types for easier handling
-    let master_file = unsafe { File::from_raw_fd(pty.master.as_raw_fd()) };
-
-    // Configure the master side of the PTY
-    configure_terminal(&master_file).map_err(|e| e.to_string())?;
-
     // Get default shell configuration
     let (shell, default_args) = get_default_shell();
     let shell_path = if let Some(cfg) = &config {
@@ -93,124 +689,459 @@ pub async fn create_terminal_session(
         default_args
     };
 
-    // Set up environment variables if provided
-    if let Some(cfg) = &config {
-        if let Some(env_vars) = &cfg.env {
-            for (key, value) in env_vars {
-                std::env::set_var(key, value);
-            }
-        }
-    }
+    let cwd = match config.as_ref().and_then(|cfg| cfg.cwd.as_ref()) {
+        Some(dir) => get_project_root().join(dir),
+        None => get_project_root(),
+    };
 
-    // Create terminal instance
-    let raw_fd = pty.master.as_raw_fd();
-    let terminal = TerminalInstance {
-        writer: Arc::new(Mutex::new(Box::new(master_file))),
-        running: Arc::new(Mutex::new(true)),
-        raw_fd,
+    let metadata = SessionMetadata {
+        shell: shell_path,
+        args,
+        cwd: cwd.to_string_lossy().to_string(),
+        env: config.and_then(|cfg| cfg.env).unwrap_or_default(),
+        title: None,
+        created_at: Utc::now().timestamp_millis(),
     };
 
-    // Store the session
-    TERMINAL_SESSIONS
-        .lock()
-        .unwrap()
-        .insert(session_id.clone(), terminal);
+    let session = spawn_session(window, session_id, metadata, "")?;
+    persist_session(&session.id);
+    Ok(session)
+}
 
-    // Fork process using libc for better control
-    let pid = unsafe { libc::fork() };
+fn storage_key(session_id: &str) -> String {
+    format!("{}{}", STORAGE_PREFIX, session_id)
+}
 
-    match pid {
-        -1 => Err("Failed to fork process".to_string()),
-        0 => {
-            // Child process
-            let slave_file = unsafe { File::from_raw_fd(pty.slave.as_raw_fd()) };
-            configure_terminal(&slave_file).map_err(|e| e.to_string())?;
+/// Saves a live session's metadata and scrollback tail.
+fn persist_session(session_id: &str) {
+    let record = {
+        let sessions = app_state().terminals.lock().unwrap();
+        let Some(terminal) = sessions.get(session_id) else {
+            return;
+        };
+        let scrollback = terminal
+            .scrollback
+            .lock()
+            .unwrap()
+            .tail(Some(PERSISTED_SCROLLBACK_LINES));
+        let metadata = terminal.metadata.lock().unwrap().clone();
+        PersistedSession {
+            id: session_id.to_string(),
+            metadata,
+            scrollback,
+            updated_at: Utc::now().timestamp_millis(),
+        }
+    };
 
-            // Set up stdio
-            unsafe {
-                libc::dup2(pty.slave.as_raw_fd(), libc::STDIN_FILENO);
-                libc::dup2(pty.slave.as_raw_fd(), libc::STDOUT_FILENO);
-                libc::dup2(pty.slave.as_raw_fd(), libc::STDERR_FILENO);
-            }
+    let result = serde_json::to_string(&record)
+        .map_err(|e| e.to_string())
+        .and_then(|json| put_record(&storage_key(session_id), &json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        warn!("Failed to save terminal session {}: {}", session_id, e);
+    }
+}
 
-            // Execute shell
-            let error = unsafe {
-                let args_cstring: Vec<std::ffi::CString> = std::iter::once(shell_path.clone())
-                    .chain(args)
-                    .map(|s| std::ffi::CString::new(s).unwrap())
-                    .collect();
-                let mut args_ptr: Vec<*const libc::c_char> = args_cstring
-                    .iter()
-                    .map(|s| s.as_ptr())
-                    .chain(std::iter::once(std::ptr::null()))
-                    .collect();
-
-                let path = std::ffi::CString::new(shell_path).unwrap();
-                libc::execvp(path.as_ptr(), args_ptr.as_mut_ptr())
-            };
+/// Saves every live session; called periodically and when the app exits.
+pub fn persist_terminal_sessions() {
+    let ids: Vec<String> = app_state()
+        .terminals
+        .lock()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect();
+    for id in ids {
+        persist_session(&id);
+    }
+}
 
-            // If we get here, exec failed
-            std::process::exit(error);
-        }
-        n => {
-            // Parent process
-            let running = Arc::new(Mutex::new(true));
-            let running_clone = running.clone();
-            let window_clone = window.clone();
-            let session_id_clone = session_id.clone();
-
-            // Set up output reader thread
-            thread::spawn(move || {
-                let mut reader = unsafe { File::from_raw_fd(pty.master.as_raw_fd()) };
-                let mut buffer = [0u8; 1024];
-
-                while *running_clone.lock().unwrap() {
-                    match reader.read(&mut buffer) {
-                        Ok(0) => break,
-                        Ok(n) => {
-                            let data = String::from_utf8_lossy(&buffer[..n]).to_string();
-                            let payload = json!({
-                                "session_id": session_id_clone,
-                                "data": data
-                            });
-
-                            if let Err(e) = window_clone.emit("terminal-output", payload) {
-                                eprintln!("Failed to emit terminal output: {}", e);
-                                break;
-                            }
-                        }
-                        Err(_) => break,
-                    }
-                }
-            });
+/// Starts the thread that keeps saved scrollback reasonably fresh.
+fn start_persistence() {
+    static STARTED: Once = Once::new();
+    STARTED.call_once(|| {
+        thread::spawn(|| loop {
+            thread::sleep(PERSIST_INTERVAL);
+            persist_terminal_sessions();
+        });
+    });
+}
+
+fn saved_sessions() -> Vec<PersistedSession> {
+    match records_with_prefix(STORAGE_PREFIX) {
+        Ok(records) => records
+            .into_iter()
+            .filter_map(|(_, json)| serde_json::from_str(&json).ok())
+            .collect(),
+        Err(e) => {
+            warn!("Failed to load saved terminal sessions: {}", e);
+            Vec::new()
+        }
+    }
+}
 
-            Ok(TerminalSession {
-                id: session_id,
-                pid: n as u32,
+/// Lists live sessions along with sessions saved by a previous run, oldest
+/// first, so the UI can restore its terminal layout.
+#[command]
+pub async fn list_terminal_sessions() -> Result<Vec<TerminalSessionInfo>, MightyError> {
+    let mut infos: Vec<TerminalSessionInfo> = {
+        let sessions = app_state().terminals.lock().unwrap();
+        sessions
+            .iter()
+            .map(|(id, terminal)| {
+                let metadata = terminal.metadata.lock().unwrap();
+                TerminalSessionInfo {
+                    id: id.clone(),
+                    shell: metadata.shell.clone(),
+                    cwd: metadata.cwd.clone(),
+                    title: metadata.title.clone(),
+                    running: !terminal.exit.wait(Duration::ZERO),
+                    pid: Some(terminal.pid),
+                    created_at: metadata.created_at,
+                }
             })
+            .collect()
+    };
+
+    for saved in saved_sessions() {
+        if infos.iter().any(|info| info.id == saved.id) {
+            continue;
         }
+        infos.push(TerminalSessionInfo {
+            id: saved.id,
+            shell: saved.metadata.shell,
+            cwd: saved.metadata.cwd,
+            title: saved.metadata.title,
+            running: false,
+            pid: None,
+            created_at: saved.metadata.created_at,
+        });
     }
+
+    infos.sort_by_key(|info| info.created_at);
+    Ok(infos)
 }
 
+/// Reconnects a view to a session. A live session is returned as is with its
+/// scrollback; a session saved by a previous run gets a new shell in its old
+/// working directory, with the saved scrollback restored.
 #[command]
-pub async fn write_to_terminal(session_id: String, data: String) -> Result<(), String> {
-    let sessions = TERMINAL_SESSIONS.lock().unwrap();
+pub async fn reattach_terminal(
+    window: Window,
+    session_id: String,
+) -> Result<ReattachedTerminal, MightyError> {
+    if let Some(terminal) = app_state().terminals.lock().unwrap().get(&session_id) {
+        let metadata = terminal.metadata.lock().unwrap();
+        return Ok(ReattachedTerminal {
+            session: TerminalSession {
+                id: session_id.clone(),
+                pid: terminal.pid,
+                shell: metadata.shell.clone(),
+                cwd: metadata.cwd.clone(),
+            },
+            scrollback: terminal.scrollback.lock().unwrap().tail(None),
+            respawned: false,
+        });
+    }
+
+    let saved: PersistedSession = get_record(&storage_key(&session_id))?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .ok_or_else(session_not_found)?;
+
+    let mut metadata = saved.metadata;
+    // The directory may have gone away since the session was saved
+    if !Path::new(&metadata.cwd).is_dir() {
+        metadata.cwd = get_project_root().to_string_lossy().to_string();
+    }
+    // Start the new prompt on its own line below the old output
+    let mut history = saved.scrollback;
+    if !history.is_empty() && !history.ends_with('\n') {
+        history.push_str("\r\n");
+    }
+    let session = spawn_session(window, session_id, metadata, &history)?;
+    persist_session(&session.id);
+
+    Ok(ReattachedTerminal {
+        session,
+        scrollback: history,
+        respawned: true,
+    })
+}
+
+#[command]
+pub async fn write_to_terminal(
+    app: AppHandle,
+    request: Request<'_>,
+    session_id: String,
+    data: String,
+) -> Result<(), MightyError> {
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        dry_run::plan_step(
+            "write_to_terminal",
+            &session_id,
+            None,
+            json!({ "data": data }),
+        );
+        return Ok(());
+    }
+    // The user's own typing is never held back
+    if actor == Actor::Agent {
+        ensure_trusted("sending input to terminals")
+            .map_err(|e| terminal_error("WORKSPACE_UNTRUSTED", &e))?;
+        // Enter runs whatever is on the line, so it needs the same approval
+        // as `run_in_terminal`
+        let line = {
+            let mut lines = AGENT_LINES.lock().unwrap();
+            let line = lines.entry(session_id.clone()).or_default();
+            line.push_str(&data);
+            if data.contains(['\n', '\r']) {
+                lines.remove(&session_id)
+            } else {
+                None
+            }
+        };
+        if let Some(line) = line {
+            authorize(&app, actor, Capability::RunCommand, line.trim())
+                .await
+                .map_err(|e| terminal_error("PERMISSION_DENIED", &e))?;
+        }
+    }
+    let sessions = app_state().terminals.lock().unwrap();
     if let Some(terminal) = sessions.get(&session_id) {
         let mut writer = terminal.writer.lock().unwrap();
         writer
             .write_all(data.as_bytes())
-            .map_err(|e| e.to_string())?;
-        writer.flush().map_err(|e| e.to_string())?;
+            .and_then(|_| writer.flush())
+            .map_err(|e| terminal_error("WRITE_FAILED", &e.to_string()))?;
+        // Only the agent's input is audited; the user's keystrokes would
+        // drown the log
+        if actor == Actor::Agent {
+            audit::record(
+                actor,
+                "write_to_terminal",
+                &session_id,
+                json!({ "data": data }),
+            );
+        }
         Ok(())
     } else {
-        Err("Terminal session not found".to_string())
+        Err(session_not_found())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TerminalRunResult {
+    pub exit_code: Option<i32>,
+    /// What the command printed, with escape sequences removed.
+    pub output: String,
+    pub duration_ms: u64,
+}
+
+/// How a shell is told to report where a command's output starts and ends.
+enum ShellFlavor {
+    Posix,
+    Fish,
+    PowerShell,
+}
+
+impl ShellFlavor {
+    fn of(shell: &str) -> Option<Self> {
+        let name = Path::new(shell)
+            .file_stem()?
+            .to_string_lossy()
+            .to_lowercase();
+        match name.as_str() {
+            "pwsh" | "powershell" => Some(ShellFlavor::PowerShell),
+            "fish" => Some(ShellFlavor::Fish),
+            "cmd" => None,
+            _ => Some(ShellFlavor::Posix),
+        }
+    }
+
+    /// Wraps `command` so the shell prints OSC 633 style start and end
+    /// markers (with the exit status) around its output. The command is
+    /// grouped so the whole line is parsed before anything runs, which keeps
+    /// a command that reads stdin from swallowing the end marker.
+    fn wrap(&self, command: &str, token: &str) -> String {
+        match self {
+            ShellFlavor::Posix => format!(
+                " printf '\\033]633;A;{token}\\007'; {{ {command}\n}}; printf '\\033]633;D;{token};%s\\007' \"$?\"\n"
+            ),
+            ShellFlavor::Fish => format!(
+                " printf '\\e]633;A;{token}\\a'; begin; {command}\nend; printf '\\e]633;D;{token};%s\\a' $status\n"
+            ),
+            ShellFlavor::PowerShell => format!(
+                " Write-Host -NoNewline \"$([char]27)]633;A;{token}$([char]7)\"; & {{ {command}\n}}; $mightyExit = if ($?) {{ 0 }} elseif ($LASTEXITCODE) {{ $LASTEXITCODE }} else {{ 1 }}; Write-Host -NoNewline \"$([char]27)]633;D;{token};$mightyExit$([char]7)\"\r\n"
+            ),
+        }
+    }
+}
+
+/// Turns raw terminal output into plain text: escape sequences go, line
+/// endings become `\n`, and carriage-return overwrites keep the last text.
+fn plain_text(raw: &str) -> String {
+    let stripped = ANSI_ESCAPE.replace_all(raw, "");
+    stripped
+        .split('\n')
+        .map(|line| {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            line.rsplit('\r').next().unwrap_or(line)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Runs `command` in the session's shell as if typed, waits for it to finish
+/// and returns its exit status and output. Completion is detected with
+/// markers the shell prints, not by waiting for output to go quiet. A command
+/// that times out is left running.
+#[command]
+pub async fn run_in_terminal(
+    app: AppHandle,
+    request: Request<'_>,
+    session_id: String,
+    command: String,
+    timeout_ms: Option<u64>,
+) -> Result<TerminalRunResult, MightyError> {
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        let details = json!({ "session_id": session_id });
+        dry_run::plan_step("run_in_terminal", &command, None, details);
+        return Err(terminal_error(
+            DRY_RUN_CODE,
+            &dry_run::planned_error("run_in_terminal"),
+        ));
+    }
+    ensure_trusted("running commands in terminals")
+        .map_err(|e| terminal_error("WORKSPACE_UNTRUSTED", &e))?;
+    authorize(&app, actor, Capability::RunCommand, &command)
+        .await
+        .map_err(|e| terminal_error("PERMISSION_DENIED", &e))?;
+
+    let token = Uuid::new_v4().simple().to_string();
+    let (mut receiver, writer, input, cwd) = {
+        let sessions = app_state().terminals.lock().unwrap();
+        let terminal = sessions.get(&session_id).ok_or_else(session_not_found)?;
+        if terminal.exit.wait(Duration::ZERO) {
+            return Err(terminal_error(
+                "SESSION_EXITED",
+                "The terminal's shell has exited",
+            ));
+        }
+        let metadata = terminal.metadata.lock().unwrap().clone();
+        let flavor = ShellFlavor::of(&metadata.shell).ok_or_else(|| {
+            terminal_error(
+                "UNSUPPORTED_SHELL",
+                "run_in_terminal is not supported for cmd.exe",
+            )
+        })?;
+
+        let mut watcher = terminal.watcher.lock().unwrap();
+        if watcher.as_ref().is_some_and(|sender| !sender.is_closed()) {
+            return Err(terminal_error(
+                "SESSION_BUSY",
+                "Another command is already running in this terminal",
+            ));
+        }
+        let (sender, receiver) = unbounded_channel();
+        *watcher = Some(sender);
+        (
+            receiver,
+            terminal.writer.clone(),
+            flavor.wrap(&command, &token),
+            metadata.cwd,
+        )
+    };
+
+    {
+        let mut writer = writer.lock().unwrap();
+        writer
+            .write_all(input.as_bytes())
+            .and_then(|_| writer.flush())
+            .map_err(|e| terminal_error("WRITE_FAILED", &e.to_string()))?;
+    }
+    // Like write_to_terminal, only the agent's commands are audited
+    if actor == Actor::Agent {
+        audit::record(
+            actor,
+            "run_in_terminal",
+            &command,
+            json!({ "session_id": session_id }),
+        );
+    }
+
+    let start_marker = format!("\x1b]633;A;{}\x07", token);
+    let end_marker = Regex::new(&format!(r"\x1b\]633;D;{};(-?\d*)\x07", token)).unwrap();
+    let started = std::time::Instant::now();
+    let started_at = Utc::now().timestamp_millis();
+    let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_RUN_TIMEOUT_MS));
+
+    let mut captured = String::new();
+    let wait = async {
+        while let Some(data) = receiver.recv().await {
+            captured.push_str(&data);
+            if let Some(end) = end_marker.captures(&captured) {
+                let end_start = end.get(0).map_or(0, |m| m.start());
+                let output_start = captured[..end_start]
+                    .find(&start_marker)
+                    .map_or(0, |i| i + start_marker.len());
+                return Some((
+                    end[1].parse::<i32>().ok(),
+                    plain_text(&captured[output_start..end_start]),
+                ));
+            }
+        }
+        None
+    };
+
+    match tokio::time::timeout(timeout, wait).await {
+        Ok(Some((exit_code, output))) => {
+            let duration_ms = started.elapsed().as_millis() as u64;
+            record_command(&CommandHistoryEntry {
+                session_id,
+                command: command.trim().to_string(),
+                cwd,
+                exit_code,
+                started_at,
+                duration_ms,
+            });
+            Ok(TerminalRunResult {
+                exit_code,
+                output,
+                duration_ms,
+            })
+        }
+        Ok(None) => Err(terminal_error(
+            "SESSION_EXITED",
+            "The terminal closed before the command finished",
+        )),
+        Err(_) => Err(terminal_error(
+            "TIMEOUT",
+            &format!("Command did not finish within {} ms", timeout.as_millis()),
+        )),
+    }
+}
+
+/// Returns the last `lines` lines of a session's output (everything kept
+/// when omitted), so a view that mounts later can replay what it missed.
+#[command]
+pub async fn get_terminal_buffer(
+    session_id: String,
+    lines: Option<usize>,
+) -> Result<String, MightyError> {
+    let sessions = app_state().terminals.lock().unwrap();
+    if let Some(terminal) = sessions.get(&session_id) {
+        Ok(terminal.scrollback.lock().unwrap().tail(lines))
+    } else {
+        Err(session_not_found())
     }
 }
 
 fn get_default_shell() -> (String, Vec<String>) {
     #[cfg(target_os = "windows")]
     {
-        ("cmd.exe".to_string(), vec!["/C".to_string()])
+        let shell = std::env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_string());
+        (shell, Vec::new())
     }
     #[cfg(target_os = "macos")]
     {
@@ -234,39 +1165,46 @@ fn get_default_shell() -> (String, Vec<String>) {
 }
 
 #[command]
-pub async fn resize_terminal(session_id: String, cols: u16, rows: u16) -> Result<(), String> {
-    let sessions = TERMINAL_SESSIONS.lock().unwrap();
+pub async fn resize_terminal(session_id: String, cols: u16, rows: u16) -> Result<(), MightyError> {
+    let sessions = app_state().terminals.lock().unwrap();
     if let Some(terminal) = sessions.get(&session_id) {
-        let size = Winsize {
-            ws_row: rows,
-            ws_col: cols,
-            ws_xpixel: 0,
-            ws_ypixel: 0,
-        };
-
-        unsafe {
-            if libc::ioctl(terminal.raw_fd, libc::TIOCSWINSZ, &size) == -1 {
-                return Err("Failed to resize terminal".to_string());
-            }
-        }
-
-        Ok(())
+        terminal
+            .master
+            .resize(PtySize {
+                rows,
+                cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            })
+            .map_err(|e| terminal_error("PTY_ERROR", &format!("Failed to resize terminal: {}", e)))
     } else {
-        Err("Terminal session not found".to_string())
+        Err(session_not_found())
     }
 }
 
 #[command]
-pub async fn terminate_terminal_session(session_id: String) -> Result<(), String> {
-    let mut sessions = TERMINAL_SESSIONS.lock().unwrap();
-    if let Some(terminal) = sessions.remove(&session_id) {
-        // Set running flag to false
-        if let Ok(mut running) = terminal.running.lock() {
-            *running = false;
+pub async fn terminate_terminal_session(session_id: String) -> Result<(), MightyError> {
+    let terminal = app_state().terminals.lock().unwrap().remove(&session_id);
+    AGENT_LINES.lock().unwrap().remove(&session_id);
+    // A closed terminal shouldn't come back on the next start
+    let key = storage_key(&session_id);
+    let saved = matches!(get_record(&key), Ok(Some(_)));
+    if saved {
+        if let Err(e) = delete_record(&key) {
+            warn!("Failed to forget terminal session {}: {}", session_id, e);
         }
+    }
+
+    if let Some(terminal) = terminal {
+        // Dropping the master at the end closes the PTY and ends the reader
+        tokio::task::spawn_blocking(move || terminal.shutdown())
+            .await
+            .map_err(|e| e.to_string())?;
 
+        Ok(())
+    } else if saved {
         Ok(())
     } else {
-        Err("Terminal session not found".to_string())
+        Err(session_not_found())
     }
 }
@@ -0,0 +1,458 @@
+// src/commands/test_runner.rs
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+use tracing::warn;
+use uuid::Uuid;
+
+use super::exec::{find_executable, run_tool, run_tool_streaming, ExecError, ExecOutput};
+use super::fs::{get_project_root, workspace_relative_path};
+use super::trust::ensure_trusted;
+
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(300);
+const TEST_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestFramework {
+    Cargo,
+    Jest,
+    Pytest,
+}
+
+impl TestFramework {
+    fn executable(self) -> &'static str {
+        match self {
+            TestFramework::Cargo => "cargo",
+            TestFramework::Jest => "jest",
+            TestFramework::Pytest => "pytest",
+        }
+    }
+
+    /// Frameworks the workspace appears to use, based on its project files.
+    fn detect(root: &Path) -> Vec<Self> {
+        let mut frameworks = Vec::new();
+        if root.join("Cargo.toml").exists() {
+            frameworks.push(TestFramework::Cargo);
+        }
+        let package_json = std::fs::read_to_string(root.join("package.json")).unwrap_or_default();
+        if package_json.contains("\"jest\"")
+            || root.join("jest.config.js").exists()
+            || root.join("jest.config.ts").exists()
+        {
+            frameworks.push(TestFramework::Jest);
+        }
+        if [
+            "pytest.ini",
+            "pyproject.toml",
+            "setup.cfg",
+            "tox.ini",
+            "conftest.py",
+        ]
+        .iter()
+        .any(|file| root.join(file).exists())
+        {
+            frameworks.push(TestFramework::Pytest);
+        }
+        frameworks
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveredTest {
+    pub framework: TestFramework,
+    pub name: String,
+    /// File containing the test, when the framework reports it.
+    pub file: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// Outcome of a single test; also the payload of the `test-result` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestResult {
+    pub run_id: String,
+    pub name: String,
+    pub status: TestStatus,
+    pub duration_ms: Option<u64>,
+    /// Captured output or failure message.
+    pub output: Option<String>,
+}
+
+/// Payload of the `test-run-finished` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestRunSummary {
+    pub run_id: String,
+    pub framework: TestFramework,
+    pub filter: Option<String>,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub duration_ms: u64,
+    pub results: Vec<TestResult>,
+}
+
+fn test_tool(framework: TestFramework) -> Result<PathBuf, ExecError> {
+    let name = framework.executable();
+    find_executable(name)
+        .ok_or_else(|| ExecError::new("TOOL_NOT_FOUND", &format!("{} was not found on PATH", name)))
+}
+
+fn run_failed(framework: TestFramework, output: &ExecOutput) -> ExecError {
+    let details = if output.stderr.trim().is_empty() {
+        output.stdout.trim()
+    } else {
+        output.stderr.trim()
+    };
+    ExecError::with_details(
+        "TESTS_FAILED_TO_RUN",
+        &format!("{} could not run the tests", framework.executable()),
+        details,
+    )
+}
+
+fn emit_result(app: &AppHandle, result: &TestResult) {
+    if let Err(e) = app.emit("test-result", result) {
+        warn!("Failed to emit test result: {}", e);
+    }
+}
+
+async fn discover(framework: TestFramework, root: &Path) -> Result<Vec<DiscoveredTest>, ExecError> {
+    let program = test_tool(framework)?;
+    let args: Vec<String> = match framework {
+        TestFramework::Cargo => vec!["test", "--quiet", "--", "--list", "--format", "terse"],
+        TestFramework::Jest => vec!["--listTests"],
+        TestFramework::Pytest => vec!["--collect-only", "-q"],
+    }
+    .into_iter()
+    .map(String::from)
+    .collect();
+
+    let output = run_tool(&program, &args, root, None, DISCOVERY_TIMEOUT).await?;
+    if !output.success() {
+        return Err(run_failed(framework, &output));
+    }
+
+    let tests = output
+        .stdout
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let (name, file) = match framework {
+                TestFramework::Cargo => (line.strip_suffix(": test")?.to_string(), None),
+                TestFramework::Jest if !line.is_empty() => {
+                    let file = workspace_relative_path(Path::new(line));
+                    (file.clone(), Some(file))
+                }
+                TestFramework::Pytest if line.contains("::") => {
+                    let file = line.split("::").next().map(String::from);
+                    (line.to_string(), file)
+                }
+                _ => return None,
+            };
+            Some(DiscoveredTest {
+                framework,
+                name,
+                file,
+            })
+        })
+        .collect();
+    Ok(tests)
+}
+
+/// Lists the tests of every framework detected in the workspace.
+#[command]
+pub async fn discover_tests() -> Result<Vec<DiscoveredTest>, ExecError> {
+    // Listing tests builds and imports the workspace's code too
+    ensure_trusted("running tasks").map_err(|e| ExecError::new("WORKSPACE_UNTRUSTED", &e))?;
+    let root = get_project_root();
+    let frameworks = TestFramework::detect(&root);
+    if frameworks.is_empty() {
+        return Err(ExecError::new(
+            "NO_TEST_FRAMEWORK",
+            "No supported test framework was found in the workspace",
+        ));
+    }
+
+    let mut tests = Vec::new();
+    let mut last_error = None;
+    for framework in frameworks {
+        match discover(framework, &root).await {
+            Ok(found) => tests.extend(found),
+            Err(e) => {
+                warn!("Test discovery failed for {:?}: {}", framework, e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    match last_error {
+        Some(e) if tests.is_empty() => Err(e),
+        _ => Ok(tests),
+    }
+}
+
+/// Parses one line of libtest's JSON output into a finished test, if it is one.
+fn parse_libtest_line(line: &str, run_id: &str) -> Option<TestResult> {
+    let event: Value = serde_json::from_str(line).ok()?;
+    if event["type"] != "test" {
+        return None;
+    }
+    let status = match event["event"].as_str()? {
+        "ok" => TestStatus::Passed,
+        "failed" => TestStatus::Failed,
+        "ignored" => TestStatus::Skipped,
+        _ => return None,
+    };
+    Some(TestResult {
+        run_id: run_id.to_string(),
+        name: event["name"].as_str()?.to_string(),
+        status,
+        duration_ms: event["exec_time"]
+            .as_f64()
+            .map(|secs| (secs * 1000.0) as u64),
+        output: event["stdout"]
+            .as_str()
+            .filter(|s| !s.is_empty())
+            .map(String::from),
+    })
+}
+
+async fn run_cargo(
+    app: &AppHandle,
+    root: &Path,
+    run_id: &str,
+    filter: Option<&str>,
+) -> Result<Vec<TestResult>, ExecError> {
+    let program = test_tool(TestFramework::Cargo)?;
+    let mut args = vec!["test".to_string()];
+    args.extend(filter.map(String::from));
+    args.extend(
+        [
+            "--",
+            "-Z",
+            "unstable-options",
+            "--format",
+            "json",
+            "--report-time",
+        ]
+        .iter()
+        .map(|arg| arg.to_string()),
+    );
+
+    let mut results = Vec::new();
+    // libtest's JSON output is unstable; RUSTC_BOOTSTRAP unlocks it on stable toolchains
+    let output = run_tool_streaming(
+        &program,
+        &args,
+        root,
+        &[("RUSTC_BOOTSTRAP", "1")],
+        TEST_TIMEOUT,
+        |line| {
+            if let Some(result) = parse_libtest_line(line, run_id) {
+                emit_result(app, &result);
+                results.push(result);
+            }
+        },
+    )
+    .await?;
+
+    // Test failures exit non-zero too; only a run without results is an error
+    if !output.success() && results.is_empty() {
+        return Err(run_failed(TestFramework::Cargo, &output));
+    }
+    Ok(results)
+}
+
+async fn run_jest(
+    root: &Path,
+    run_id: &str,
+    filter: Option<&str>,
+) -> Result<Vec<TestResult>, ExecError> {
+    let program = test_tool(TestFramework::Jest)?;
+    let mut args = vec!["--json".to_string()];
+    if let Some(filter) = filter {
+        args.push("--testNamePattern".to_string());
+        args.push(filter.to_string());
+    }
+
+    let output = run_tool(&program, &args, root, None, TEST_TIMEOUT).await?;
+    let report: Value = serde_json::from_str(&output.stdout)
+        .map_err(|_| run_failed(TestFramework::Jest, &output))?;
+
+    let mut results = Vec::new();
+    for suite in report["testResults"].as_array().into_iter().flatten() {
+        for assertion in suite["assertionResults"].as_array().into_iter().flatten() {
+            let status = match assertion["status"].as_str() {
+                Some("passed") => TestStatus::Passed,
+                Some("failed") => TestStatus::Failed,
+                _ => TestStatus::Skipped,
+            };
+            let failures: Vec<&str> = assertion["failureMessages"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            results.push(TestResult {
+                run_id: run_id.to_string(),
+                name: assertion["fullName"]
+                    .as_str()
+                    .or_else(|| assertion["title"].as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                status,
+                duration_ms: assertion["duration"].as_u64(),
+                output: (!failures.is_empty()).then(|| failures.join("\n")),
+            });
+        }
+    }
+    Ok(results)
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#10;", "\n")
+        .replace("&amp;", "&")
+}
+
+/// Extracts test cases from a JUnit XML report.
+fn parse_junit(xml: &str, run_id: &str) -> Vec<TestResult> {
+    let testcase = Regex::new(r#"(?s)<testcase\s([^>]*?)(?:/>|>(.*?)</testcase>)"#).unwrap();
+    let attribute = Regex::new(r#"(\w+)="([^"]*)""#).unwrap();
+    let body_text =
+        Regex::new(r#"(?s)<(failure|error)[^>]*?(?:/>|>(.*?)</(?:failure|error)>)"#).unwrap();
+
+    testcase
+        .captures_iter(xml)
+        .map(|case| {
+            let attrs: Vec<(String, String)> = attribute
+                .captures_iter(&case[1])
+                .map(|a| (a[1].to_string(), unescape_xml(&a[2])))
+                .collect();
+            let attr = |name: &str| {
+                attrs
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.as_str())
+            };
+            let body = case.get(2).map(|m| m.as_str()).unwrap_or_default();
+
+            let (status, output) = if let Some(failure) = body_text.captures(body) {
+                let text = failure
+                    .get(2)
+                    .map(|m| unescape_xml(m.as_str()))
+                    .unwrap_or_default();
+                (TestStatus::Failed, Some(text))
+            } else if body.contains("<skipped") {
+                (TestStatus::Skipped, None)
+            } else {
+                (TestStatus::Passed, None)
+            };
+
+            let name = match (attr("classname"), attr("name")) {
+                (Some(class), Some(name)) if !class.is_empty() => format!("{}::{}", class, name),
+                (_, name) => name.unwrap_or_default().to_string(),
+            };
+            TestResult {
+                run_id: run_id.to_string(),
+                name,
+                status,
+                duration_ms: attr("time")
+                    .and_then(|t| t.parse::<f64>().ok())
+                    .map(|secs| (secs * 1000.0) as u64),
+                output,
+            }
+        })
+        .collect()
+}
+
+async fn run_pytest(
+    root: &Path,
+    run_id: &str,
+    filter: Option<&str>,
+) -> Result<Vec<TestResult>, ExecError> {
+    let program = test_tool(TestFramework::Pytest)?;
+    let report = std::env::temp_dir().join(format!("mighty-pytest-{}.xml", run_id));
+    let mut args = vec!["-q".to_string(), format!("--junitxml={}", report.display())];
+    if let Some(filter) = filter {
+        args.push("-k".to_string());
+        args.push(filter.to_string());
+    }
+
+    let output = run_tool(&program, &args, root, None, TEST_TIMEOUT).await?;
+    let xml = tokio::fs::read_to_string(&report).await;
+    let _ = tokio::fs::remove_file(&report).await;
+
+    // Exit codes 0 (passed), 1 (failures) and 5 (nothing collected) are normal runs
+    match (output.exit_code, xml) {
+        (Some(0) | Some(1) | Some(5), Ok(xml)) => Ok(parse_junit(&xml, run_id)),
+        _ => Err(run_failed(TestFramework::Pytest, &output)),
+    }
+}
+
+/// Runs the workspace's tests (optionally only those matching `filter`),
+/// emitting a `test-result` event per test and `test-run-finished` at the end.
+/// Cargo results stream while the run is in progress; jest and pytest report
+/// once their run completes.
+#[command]
+pub async fn run_tests(
+    app: AppHandle,
+    filter: Option<String>,
+    framework: Option<TestFramework>,
+) -> Result<TestRunSummary, ExecError> {
+    // Test runs build and execute the workspace's code
+    ensure_trusted("running tasks").map_err(|e| ExecError::new("WORKSPACE_UNTRUSTED", &e))?;
+    let root = get_project_root();
+    let framework = framework
+        .or_else(|| TestFramework::detect(&root).into_iter().next())
+        .ok_or_else(|| {
+            ExecError::new(
+                "NO_TEST_FRAMEWORK",
+                "No supported test framework was found in the workspace",
+            )
+        })?;
+
+    let run_id = Uuid::new_v4().to_string();
+    let started = std::time::Instant::now();
+    let results = match framework {
+        TestFramework::Cargo => run_cargo(&app, &root, &run_id, filter.as_deref()).await?,
+        TestFramework::Jest => run_jest(&root, &run_id, filter.as_deref()).await?,
+        TestFramework::Pytest => run_pytest(&root, &run_id, filter.as_deref()).await?,
+    };
+    if framework != TestFramework::Cargo {
+        for result in &results {
+            emit_result(&app, result);
+        }
+    }
+
+    let count = |status| results.iter().filter(|r| r.status == status).count();
+    let summary = TestRunSummary {
+        run_id,
+        framework,
+        filter,
+        passed: count(TestStatus::Passed),
+        failed: count(TestStatus::Failed),
+        skipped: count(TestStatus::Skipped),
+        duration_ms: started.elapsed().as_millis() as u64,
+        results,
+    };
+
+    if let Err(e) = app.emit("test-run-finished", &summary) {
+        warn!("Failed to emit test summary: {}", e);
+    }
+    Ok(summary)
+}
@@ -0,0 +1,190 @@
+// src/commands/trust.rs
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use tauri::ipc::Request;
+use tauri::{command, AppHandle, Emitter};
+use tracing::{info, warn};
+
+use super::audit;
+use super::dev_server::stop_all_dev_servers;
+use super::extensions::stop_workspace_extensions;
+use super::fs::get_project_root;
+use super::permissions::Actor;
+use super::storage::{get_record, put_record};
+use crate::config::TrustConfig;
+use crate::error::MightyError;
+
+const STORAGE_PREFIX: &str = "workspace_trust:";
+
+struct Settings {
+    enabled: bool,
+    trusted_folders: Vec<PathBuf>,
+}
+
+static SETTINGS: Lazy<RwLock<Settings>> = Lazy::new(|| {
+    RwLock::new(Settings {
+        enabled: true,
+        trusted_folders: Vec::new(),
+    })
+});
+
+/// Why a workspace is or isn't trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrustSource {
+    /// `trust.enabled` is off.
+    Disabled,
+    /// It is inside one of `trust.trusted_folders`.
+    TrustedFolder,
+    /// The user trusted or distrusted it.
+    Decision,
+    /// Nobody has decided yet, so it isn't trusted.
+    Default,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceTrust {
+    pub workspace: String,
+    pub trusted: bool,
+    pub source: TrustSource,
+    /// Milliseconds since the Unix epoch, when the user decided.
+    pub decided_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Decision {
+    trusted: bool,
+    decided_at: i64,
+}
+
+/// Applies the `[trust]` settings.
+pub(crate) fn configure(config: Option<&TrustConfig>) {
+    let config = config.cloned().unwrap_or_default();
+    *SETTINGS.write() = Settings {
+        enabled: config.enabled.unwrap_or(true),
+        trusted_folders: config
+            .trusted_folders
+            .iter()
+            .map(|folder| match folder.strip_prefix("~/") {
+                Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+                None => PathBuf::from(folder),
+            })
+            .collect(),
+    };
+}
+
+fn storage_key(workspace: &str) -> String {
+    format!("{}{}", STORAGE_PREFIX, workspace)
+}
+
+fn trust_of(root: &Path) -> WorkspaceTrust {
+    let workspace = root.to_string_lossy().to_string();
+    let settings = SETTINGS.read();
+    let trust = |trusted, source, decided_at| WorkspaceTrust {
+        workspace: workspace.clone(),
+        trusted,
+        source,
+        decided_at,
+    };
+    if !settings.enabled {
+        return trust(true, TrustSource::Disabled, None);
+    }
+    if settings
+        .trusted_folders
+        .iter()
+        .any(|folder| root.starts_with(folder))
+    {
+        return trust(true, TrustSource::TrustedFolder, None);
+    }
+    // A decision that can't be read leaves the workspace untrusted
+    let decision = match get_record(&storage_key(&workspace)) {
+        Ok(json) => json.and_then(|json| serde_json::from_str::<Decision>(&json).ok()),
+        Err(e) => {
+            warn!("Failed to read the trust of {}: {}", workspace, e);
+            None
+        }
+    };
+    match decision {
+        Some(decision) => trust(
+            decision.trusted,
+            TrustSource::Decision,
+            Some(decision.decided_at),
+        ),
+        None => trust(false, TrustSource::Default, None),
+    }
+}
+
+pub(crate) fn is_trusted() -> bool {
+    trust_of(&get_project_root()).trusted
+}
+
+/// Fails unless the workspace is trusted; `feature` names what is off,
+/// e.g. "running tasks".
+pub(crate) fn ensure_trusted(feature: &str) -> Result<(), String> {
+    if is_trusted() {
+        return Ok(());
+    }
+    Err(format!(
+        "This workspace isn't trusted, so {} is turned off. Trust the folder to turn it on",
+        feature
+    ))
+}
+
+/// Whether the open workspace is trusted, and why.
+#[command]
+pub async fn get_workspace_trust() -> Result<WorkspaceTrust, MightyError> {
+    Ok(trust_of(&get_project_root()))
+}
+
+/// Trusts or distrusts the open workspace, remembered per folder.
+/// Distrusting stops its tasks and extensions. Only the user can do this,
+/// and a workspace config can't: `[trust]` is global only.
+#[command]
+pub async fn set_workspace_trust(
+    app: AppHandle,
+    request: Request<'_>,
+    trusted: bool,
+) -> Result<WorkspaceTrust, MightyError> {
+    let actor = Actor::of(&request);
+    if actor == Actor::Agent {
+        return Err(MightyError::auth(
+            "AGENT_NOT_ALLOWED",
+            "Only the user can change whether a workspace is trusted",
+        ));
+    }
+    let root = get_project_root();
+    let workspace = root.to_string_lossy().to_string();
+    let decision = Decision {
+        trusted,
+        decided_at: Utc::now().timestamp_millis(),
+    };
+    let json = serde_json::to_string(&decision).map_err(|e| e.to_string())?;
+    put_record(&storage_key(&workspace), &json)?;
+
+    if !trusted {
+        stop_all_dev_servers();
+        stop_workspace_extensions();
+    }
+    info!(
+        "Workspace {} {}",
+        workspace,
+        if trusted { "trusted" } else { "distrusted" }
+    );
+    audit::record(
+        actor,
+        "set_workspace_trust",
+        &workspace,
+        json!({ "trusted": trusted }),
+    );
+
+    let trust = trust_of(&root);
+    if let Err(e) = app.emit("workspace-trust-changed", &trust) {
+        warn!("Failed to emit workspace-trust-changed: {}", e);
+    }
+    Ok(trust)
+}
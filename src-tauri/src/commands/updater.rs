@@ -0,0 +1,212 @@
+// src/commands/updater.rs
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use reqwest::Url;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+use tauri_plugin_updater::{Update, UpdaterExt};
+use tracing::{error, info, warn};
+
+use super::notifications::{notify, NotificationLevel};
+use super::offline::{ensure_online, is_offline};
+use crate::config::{UpdateChannel, UpdatesConfig};
+use crate::error::MightyError;
+use crate::state::app_state;
+
+const DEFAULT_CHECK_INTERVAL_HOURS: u64 = 24;
+/// Leaves startup alone before the first background check.
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(60);
+
+/// The update the last check found, kept for `install_update`.
+static AVAILABLE: Lazy<Mutex<Option<Update>>> = Lazy::new(|| Mutex::new(None));
+/// Set while an update downloads and installs.
+static INSTALLING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub channel: UpdateChannel,
+    /// Release date as the manifest gives it.
+    pub date: Option<String>,
+    /// Release notes.
+    pub notes: Option<String>,
+}
+
+/// Payload of the `update-progress` event.
+#[derive(Debug, Clone, Serialize)]
+struct ProgressEvent {
+    downloaded: u64,
+    total: Option<u64>,
+    /// Set once the download is complete and cleanup is running.
+    installing: bool,
+}
+
+async fn updates_config() -> UpdatesConfig {
+    app_state()
+        .config
+        .lock()
+        .await
+        .updates
+        .clone()
+        .unwrap_or_default()
+}
+
+async fn check(app: &AppHandle) -> Result<Option<UpdateInfo>, MightyError> {
+    ensure_online()?;
+    let config = updates_config().await;
+    let channel = config.channel.unwrap_or_default();
+
+    let mut builder = app.updater_builder();
+    match config.endpoints.get(channel.as_str()) {
+        Some(endpoint) => {
+            let endpoint = Url::parse(endpoint).map_err(|e| e.to_string())?;
+            builder = builder
+                .endpoints(vec![endpoint])
+                .map_err(|e| e.to_string())?;
+        }
+        // Stable comes from the endpoints in tauri.conf.json
+        None if channel == UpdateChannel::Stable => {}
+        None => {
+            return Err(MightyError::config(
+                "MISSING_ENDPOINT",
+                &format!(
+                    "No update endpoint for the {} channel; set updates.endpoints.{}",
+                    channel.as_str(),
+                    channel.as_str()
+                ),
+            ))
+        }
+    }
+    let update = builder
+        .build()
+        .map_err(|e| e.to_string())?
+        .check()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let info = update.as_ref().map(|update| UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        channel,
+        date: update.date.map(|date| date.to_string()),
+        notes: update.body.clone(),
+    });
+    *AVAILABLE.lock() = update;
+    Ok(info)
+}
+
+/// Checks the configured channel for a newer version, returning it with
+/// its release notes.
+#[command]
+pub async fn check_for_updates(app: AppHandle) -> Result<Option<UpdateInfo>, MightyError> {
+    check(&app).await
+}
+
+/// Downloads the update found by the last check, runs every cleanup hook,
+/// then installs it and restarts. Download progress is sent as
+/// `update-progress` events.
+#[command]
+pub async fn install_update(app: AppHandle) -> Result<(), MightyError> {
+    if INSTALLING.swap(true, Ordering::SeqCst) {
+        return Err("An update is already being installed".into());
+    }
+    let result = download_and_install(&app).await;
+    INSTALLING.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn download_and_install(app: &AppHandle) -> Result<(), MightyError> {
+    let available = AVAILABLE.lock().clone();
+    let update = match available {
+        Some(update) => update,
+        None => {
+            check(app).await?;
+            AVAILABLE
+                .lock()
+                .clone()
+                .ok_or_else(|| "No update is available".to_string())?
+        }
+    };
+
+    info!("Downloading update {}", update.version);
+    let mut downloaded = 0u64;
+    let mut reported = None;
+    let bytes = update
+        .download(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                // Only whole-percent steps, or every chunk would be an event
+                let percent = total.map(|total| downloaded * 100 / total.max(1));
+                if percent.is_some() && percent == reported {
+                    return;
+                }
+                reported = percent;
+                let _ = app.emit(
+                    "update-progress",
+                    &ProgressEvent {
+                        downloaded,
+                        total,
+                        installing: false,
+                    },
+                );
+            },
+            || {},
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let _ = app.emit(
+        "update-progress",
+        &ProgressEvent {
+            downloaded: bytes.len() as u64,
+            total: Some(bytes.len() as u64),
+            installing: true,
+        },
+    );
+    // The installer replaces the app, so nothing may still be running
+    crate::cleanup_before_restart().await;
+    info!("Installing update {}", update.version);
+    if let Err(e) = update.install(bytes) {
+        // Cleanup has shut storage down, so carry on with the old version
+        error!("Failed to install update {}: {}", update.version, e);
+    }
+    app.restart();
+}
+
+/// Checks for updates in the background on the configured interval,
+/// announcing a new version with a notification and `update-available`.
+pub(crate) fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(FIRST_CHECK_DELAY).await;
+        loop {
+            let hours = updates_config()
+                .await
+                .check_interval_hours
+                .unwrap_or(DEFAULT_CHECK_INTERVAL_HOURS);
+            if hours > 0 && !is_offline() && !INSTALLING.load(Ordering::SeqCst) {
+                match check(&app).await {
+                    Ok(Some(info)) => {
+                        notify(
+                            &app,
+                            NotificationLevel::Info,
+                            "updates",
+                            format!("Mighty IDE {} is available", info.version),
+                            info.notes.clone(),
+                        );
+                        if let Err(e) = app.emit("update-available", &info) {
+                            warn!("Failed to emit available update: {}", e);
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => warn!("Update check failed: {}", e),
+                }
+            }
+            // Rechecks the setting hourly while checks are off
+            tokio::time::sleep(Duration::from_secs(3600 * hours.max(1))).await;
+        }
+    });
+}
@@ -1,11 +1,21 @@
 // src-tauri/src/config.rs
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::commands::scheduler;
+
+/// Matches the bundle identifier, so the config sits beside the app's other data.
+pub(crate) const APP_IDENTIFIER: &str = "com.mighty.ide";
+const CONFIG_FILE_NAME: &str = "config.toml";
+/// Set from `--config` or `MIGHTY_CONFIG` at startup.
+static CONFIG_PATH: OnceLock<PathBuf> = OnceLock::new();
 
 /// Configuration specific to Bedrock.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BedrockConfig {
     pub endpoint_url: String,
     pub region: String,
@@ -14,44 +24,1093 @@ pub struct BedrockConfig {
 }
 
 /// Configuration specific to Anthropic API.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnthropicConfig {
-    pub api_key: String,
+    /// Used when no "anthropic" secret has been saved to the credential vault.
+    pub api_key: Option<String>,
 }
 
 /// Configuration specific to Greptile API.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GreptileConfig {
-    pub api_key: String,
+    /// Used when no "greptile" secret has been saved to the credential vault.
+    pub api_key: Option<String>,
+    pub base_url: Option<String>,
+    pub max_results: Option<u32>,
+    /// Lets Greptile read the repositories being queried; required by the
+    /// query API for GitHub repositories.
+    pub github_token: Option<String>,
+}
+
+/// How to sign in to an OAuth provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OAuthFlow {
+    /// Authorization code with PKCE, redirected to a localhost listener.
+    Pkce,
+    /// Device authorization grant: the user enters a code in the browser.
+    Device,
+}
+
+/// An OAuth provider the app can sign in to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthProviderConfig {
+    pub client_id: String,
+    /// Only for providers that insist on one even for native apps.
+    pub client_secret: Option<String>,
+    pub authorization_url: Option<String>,
+    pub device_authorization_url: Option<String>,
+    pub token_url: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Fixed port for the redirect listener, for providers that require an
+    /// exact redirect URI; any free port otherwise.
+    pub redirect_port: Option<u16>,
+    /// Defaults to PKCE when `authorization_url` is set.
+    pub flow: Option<OAuthFlow>,
+    /// Seconds before expiry at which an access token is refreshed rather
+    /// than handed out; 60 by default.
+    pub refresh_skew_secs: Option<u64>,
+}
+
+/// Configuration for filesystem access.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FsConfig {
+    /// Directories outside the workspace that fs commands may read and write.
+    #[serde(default)]
+    pub allowed_roots: Vec<String>,
+}
+
+/// An external formatter invoked with the document on stdin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatterConfig {
+    pub command: String,
+    /// Arguments; `{path}` is replaced with the document's path.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+/// Configuration for code formatting.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FormatConfig {
+    /// Formatter overrides keyed by language (e.g. "rust", "typescript", "python").
+    #[serde(default)]
+    pub formatters: HashMap<String, FormatterConfig>,
+    pub timeout_ms: Option<u64>,
+    /// Format assistant edits before writing them (defaults to true).
+    pub format_ai_edits: Option<bool>,
+}
+
+/// Models used for assistant requests, by model id.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelsConfig {
+    pub chat: Option<String>,
+    pub completion: Option<String>,
+    /// Summarizes the start of conversations that outgrow the chat model's
+    /// context window; a small, cheap model is enough.
+    pub summary: Option<String>,
+}
+
+/// Settings for the workspace being edited.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceConfig {
+    /// Globs, relative to the workspace root, for paths left out of
+    /// indexing, search and watching.
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    /// Copy files dropped onto the window from outside the workspace into
+    /// `drop_dir` before indexing them.
+    #[serde(default)]
+    pub copy_dropped_files: bool,
+    /// Where dropped files are copied, relative to the workspace root;
+    /// `dropped` by default.
+    pub drop_dir: Option<String>,
+}
+
+/// A named command the workspace can run (e.g. "build", "test").
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskConfig {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Relative to the workspace root; the root itself by default.
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+/// Configuration for code context retrieval.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContextConfig {
+    /// Lines per indexed chunk.
+    pub chunk_size: Option<usize>,
+    /// Chunks returned per query.
+    pub max_results: Option<usize>,
+    /// Asks a model for a one-line summary of each chunk while indexing,
+    /// embedded with the code and shown in search results. Off by default,
+    /// since every indexed file costs a request.
+    pub summarize_chunks: Option<bool>,
+    /// Model writing the summaries; Claude Haiku by default.
+    pub summary_model: Option<String>,
+    /// An OpenAI-compatible endpoint to summarize with instead of
+    /// Anthropic, such as a local model at `http://localhost:11434/v1`.
+    pub summary_endpoint: Option<String>,
+    /// Files indexed in the background when a workspace is opened, entry
+    /// points and recently modified files first; 500 by default, 0 to turn
+    /// warm-up off.
+    pub warmup_max_files: Option<usize>,
+    /// Share of a CPU core warm-up may keep busy, in percent; it pauses
+    /// between files to stay under it. 50 by default.
+    pub warmup_cpu_percent: Option<u8>,
+    /// Globs, relative to the workspace root, of files never indexed, such
+    /// as lockfiles and minified bundles; replaces the built-in list.
+    pub skip_patterns: Option<Vec<String>>,
+    /// Globs of files indexed even when a skip pattern, the size limit or
+    /// the minified check would leave them out.
+    #[serde(default)]
+    pub include_patterns: Vec<String>,
+    /// Larger files aren't indexed; 1 MiB by default.
+    pub max_file_bytes: Option<u64>,
+    /// Skips files that look minified, going by their line lengths. On by
+    /// default.
+    pub skip_minified: Option<bool>,
+    /// Embeds the turns of chats that have a conversation id so later chats
+    /// can recall related discussions. On by default.
+    pub remember_conversations: Option<bool>,
+}
+
+/// Opt-in recording of command timings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    /// Nothing is recorded unless this is set.
+    #[serde(default)]
+    pub enabled: bool,
+    /// OTLP/HTTP collector to export metrics to (e.g. `http://localhost:4318`).
+    pub otlp_endpoint: Option<String>,
+    /// Seconds between saving and exporting metrics; 60 by default.
+    pub export_interval_secs: Option<u64>,
+}
+
+/// Limits for the background job queue.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JobsConfig {
+    /// Jobs run at once; 2 by default.
+    pub max_concurrent: Option<usize>,
+    /// Pause indexing, embedding and git fetches while the user types or a
+    /// completion is in flight; on by default.
+    pub pause_while_active: Option<bool>,
+    /// Milliseconds without typing before paused work resumes; 2000 by
+    /// default.
+    pub idle_threshold_ms: Option<u64>,
+}
+
+/// Limits on what the app keeps on disk and in memory, in megabytes; none
+/// by default. Over a budget the app frees space first and then refuses to
+/// store more, telling the user.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetConfig {
+    /// The code index and conversation memory (LanceDB). Files changed
+    /// least recently are evicted to stay under it.
+    pub max_index_mb: Option<u64>,
+    /// The database (RocksDB). It is compacted to stay under it.
+    pub max_storage_mb: Option<u64>,
+    /// File contents cached in memory for the code context.
+    pub max_cache_mb: Option<u64>,
+}
+
+/// When maintenance (backups, compaction, index optimization, log
+/// rotation) runs in the background.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchedulerConfig {
+    /// On by default.
+    pub enabled: Option<bool>,
+    /// Minutes without other jobs or user activity before due maintenance
+    /// starts; 5 by default.
+    pub idle_minutes: Option<u64>,
+    /// Cron expressions by job name (see `list_scheduled_jobs`), replacing
+    /// the defaults; "off" turns a job off.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub schedules: HashMap<String, String>,
+    /// Scheduled backups kept; 5 by default.
+    pub keep_backups: Option<usize>,
+}
+
+/// Writes `store_value` holds back and coalesces: values for these keys
+/// change many times a second (editor state, cursor positions), so only
+/// the latest is written, in one batch per flush.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// Key prefixes to buffer; `session:`, `navigation:` and
+    /// `terminal_session:` by default.
+    pub buffered_prefixes: Option<Vec<String>>,
+    /// Milliseconds between flushes of buffered writes; 1000 by default.
+    /// They are also flushed on exit.
+    pub flush_interval_ms: Option<u64>,
+}
+
+/// How notifications reach the user.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotificationsConfig {
+    /// Also show OS notifications while the app is in the background; on by
+    /// default.
+    pub desktop: Option<bool>,
+}
+
+/// How the workspace session (open files, layout, terminals) is saved.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionConfig {
+    /// Seconds between autosaves of session changes; 30 by default.
+    pub autosave_interval_secs: Option<u64>,
+}
+
+/// How author-defined extensions are run.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExtensionsConfig {
+    /// WASI runtime for WASM extensions, taking wasmtime's `run` arguments;
+    /// `wasmtime` by default.
+    pub wasm_runner: Option<String>,
+}
+
+/// The localhost API server that lets other editors use this backend.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiServerConfig {
+    /// Off unless set.
+    pub enabled: Option<bool>,
+    /// 7331 by default.
+    pub port: Option<u16>,
+}
+
+/// An LLM provider `proxy_request` can forward to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderProfile {
+    /// Request paths are appended to this (e.g. `https://api.openai.com/v1`).
+    pub base_url: String,
+    /// Sent with every request; `{api_key}` is replaced with the provider's
+    /// key (e.g. `Authorization = "Bearer {api_key}"`).
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Vault secret holding the key; the provider's name by default.
+    pub secret: Option<String>,
+}
+
+/// Where `proxy_request` may send requests.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    /// Hosts allowed besides those of the providers' base URLs.
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+}
+
+/// Opt-in recording of LLM requests and their responses, for debugging
+/// prompts and replaying provider errors.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecorderConfig {
+    /// Nothing is recorded unless this is set.
+    #[serde(default)]
+    pub enabled: bool,
+    /// Recordings kept; older ones are dropped. 500 by default.
+    pub max_entries: Option<usize>,
+}
+
+/// How outgoing HTTP requests are made.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HttpConfig {
+    /// Proxy for every request (e.g. `http://proxy.corp:8080`);
+    /// `HTTPS_PROXY`, `HTTP_PROXY` and `NO_PROXY` are used when unset.
+    pub proxy: Option<String>,
+    /// PEM file of extra CA certificates to trust, such as a corporate
+    /// proxy's.
+    pub ca_bundle: Option<String>,
+    /// Seconds to wait for a connection; 10 by default.
+    pub connect_timeout_secs: Option<u64>,
+    /// Seconds a request may go without receiving data; 120 by default.
+    pub timeout_secs: Option<u64>,
+    /// `timeout_secs` overrides keyed by provider (e.g. "anthropic",
+    /// "greptile").
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub provider_timeouts: HashMap<String, u64>,
+    /// Requests to one provider in flight at once, e.g. when several
+    /// completions are sampled together; 4 by default.
+    pub max_concurrent_requests: Option<usize>,
+}
+
+/// Release channel updates come from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+impl UpdateChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Beta => "beta",
+        }
+    }
+}
+
+/// How the app updates itself.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdatesConfig {
+    /// Stable by default.
+    pub channel: Option<UpdateChannel>,
+    /// Hours between background checks; 24 by default, 0 to check only
+    /// when asked.
+    pub check_interval_hours: Option<u64>,
+    /// Update manifest URLs keyed by channel, replacing the endpoints in
+    /// tauri.conf.json; `{{target}}`, `{{arch}}` and `{{current_version}}`
+    /// are filled in.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub endpoints: HashMap<String, String>,
+}
+
+/// What happens when the agent writes something that looks like a secret.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretPolicy {
+    /// Write it, but tell the user.
+    #[default]
+    Warn,
+    Block,
+}
+
+/// Detection of credentials such as API keys, tokens and private keys.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SecretsConfig {
+    /// Replaces secrets with placeholders in indexed code and in requests
+    /// to model providers. On by default.
+    pub redact: Option<bool>,
+    /// Warn by default.
+    pub agent_writes: Option<SecretPolicy>,
+    /// Regexes for values that look like secrets but aren't, such as test
+    /// fixtures.
+    #[serde(default)]
+    pub allow: Vec<String>,
+}
+
+/// What is stripped from everything sent off the machine: model requests
+/// and Greptile queries.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    /// Rewrites absolute paths under the home directory to start with `~`.
+    /// On by default.
+    pub home_paths: Option<bool>,
+    /// Replaces the local user name. On by default.
+    pub usernames: Option<bool>,
+    /// Globs, relative to the workspace root, of files whose paths are
+    /// masked wherever they're mentioned.
+    #[serde(default)]
+    pub sensitive_globs: Vec<String>,
+}
+
+/// Workspace trust: until a folder is trusted, tasks, commands sent into
+/// terminals and the workspace's own extensions are turned off.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrustConfig {
+    /// On by default; off trusts every folder.
+    pub enabled: Option<bool>,
+    /// Folders whose subfolders are all trusted, such as where your own
+    /// projects live.
+    #[serde(default)]
+    pub trusted_folders: Vec<String>,
 }
 
 /// Main application configuration.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AppConfig {
     pub anthropic: Option<AnthropicConfig>,
     pub greptile: Option<GreptileConfig>,
+    pub fs: Option<FsConfig>,
+    pub format: Option<FormatConfig>,
+    /// OAuth providers keyed by name (e.g. "github").
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub oauth: HashMap<String, OAuthProviderConfig>,
+    pub models: Option<ModelsConfig>,
+    pub workspace: Option<WorkspaceConfig>,
+    /// Tasks keyed by name.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tasks: HashMap<String, TaskConfig>,
+    pub context: Option<ContextConfig>,
+    pub telemetry: Option<TelemetryConfig>,
+    pub jobs: Option<JobsConfig>,
+    pub scheduler: Option<SchedulerConfig>,
+    pub budget: Option<BudgetConfig>,
+    pub storage: Option<StorageConfig>,
+    pub notifications: Option<NotificationsConfig>,
+    pub session: Option<SessionConfig>,
+    pub extensions: Option<ExtensionsConfig>,
+    pub api_server: Option<ApiServerConfig>,
+    /// Provider profiles keyed by name; "anthropic" is built in and can be
+    /// overridden here.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub providers: HashMap<String, ProviderProfile>,
+    pub proxy: Option<ProxyConfig>,
+    pub recorder: Option<RecorderConfig>,
+    pub http: Option<HttpConfig>,
+    pub updates: Option<UpdatesConfig>,
+    pub secrets: Option<SecretsConfig>,
+    pub redaction: Option<RedactionConfig>,
+    pub trust: Option<TrustConfig>,
 }
 
 impl AppConfig {
-    /// Loads configuration from `config.toml`.
+    /// Where the config file lives: the path set with `set_path`, else
+    /// `config.toml` in the platform config directory (e.g.
+    /// `~/.config/com.mighty.ide` on Linux).
+    pub fn path() -> PathBuf {
+        CONFIG_PATH.get().cloned().unwrap_or_else(|| {
+            dirs::config_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(APP_IDENTIFIER)
+                .join(CONFIG_FILE_NAME)
+        })
+    }
+
+    /// Uses `path` as the config file from now on. Only the first call has
+    /// an effect.
+    pub fn set_path(path: PathBuf) {
+        let _ = CONFIG_PATH.set(path);
+    }
+
+    /// Loads configuration from the config file, or the defaults when there
+    /// isn't one yet. Without an explicit path, a `config.toml` in the
+    /// working directory, where it used to be read from, is copied into
+    /// place first.
     pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
-        // Define the path to config.toml
-        let config_path = Path::new("config.toml");
-        
-        // Check if config.toml exists
+        let config_path = Self::path();
+        let legacy_path = Path::new(CONFIG_FILE_NAME);
+        if CONFIG_PATH.get().is_none() && !config_path.exists() && legacy_path.is_file() {
+            if let Some(dir) = config_path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::copy(legacy_path, &config_path)?;
+        }
+
         if !config_path.exists() {
-            return Err(format!(
-                "Configuration file not found at path: {}",
-                config_path.display()
-            ).into());
-        }
-        
-        // Read the contents of config.toml
-        let config_content = fs::read_to_string(config_path)?;
-        
-        // Parse the TOML content into AppConfig
-        let config: AppConfig = toml::from_str(&config_content)?;
-        
+            return Ok(Self::default());
+        }
+        Self::load_from(&config_path)
+    }
+
+    /// Parses the config file at `path`.
+    pub fn load_from(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let config_content = fs::read_to_string(path)?;
+        let config: AppConfig = toml::from_str(&config_content)
+            .map_err(|e| format!("Invalid configuration in {}: {}", path.display(), e))?;
         Ok(config)
     }
-}
\ No newline at end of file
+
+    /// Writes the configuration to the config file. The file is replaced in
+    /// one step, so a crash mid-write can't leave it truncated.
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let config_path = Self::path();
+        if let Some(dir) = config_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp_path = config_path.with_extension("toml.tmp");
+        fs::write(&temp_path, toml::to_string_pretty(self)?)?;
+        fs::rename(&temp_path, &config_path)?;
+        Ok(())
+    }
+}
+
+/// Model families the app knows how to talk to. Newer ids in a family
+/// (dated snapshots, `-latest` aliases) match by prefix.
+const KNOWN_MODEL_FAMILIES: &[&str] = &[
+    "claude-3-haiku",
+    "claude-3-sonnet",
+    "claude-3-opus",
+    "claude-3-5-haiku",
+    "claude-3-5-sonnet",
+    "claude-3-7-sonnet",
+    "claude-sonnet-4",
+    "claude-opus-4",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSeverity {
+    /// The setting can't work as given.
+    Error,
+    /// The setting works but is probably not what was meant.
+    Warning,
+}
+
+/// A problem found in the configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigIssue {
+    pub severity: IssueSeverity,
+    /// Dotted path of the setting (e.g. `greptile.base_url`); empty for
+    /// problems with a whole file.
+    pub path: String,
+    pub message: String,
+    /// The config file the setting came from, when it came from one.
+    pub file: Option<String>,
+}
+
+impl ConfigIssue {
+    pub fn error(path: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity: IssueSeverity::Error,
+            path: path.to_string(),
+            message: message.into(),
+            file: None,
+        }
+    }
+
+    pub fn warning(path: &str, message: impl Into<String>) -> Self {
+        Self {
+            severity: IssueSeverity::Warning,
+            path: path.to_string(),
+            message: message.into(),
+            file: None,
+        }
+    }
+}
+
+fn check_url(issues: &mut Vec<ConfigIssue>, path: &str, url: &str) {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if parsed.scheme() != "http" && parsed.scheme() != "https" => {
+            issues.push(ConfigIssue::error(
+                path,
+                format!("{} is not an http(s) URL", url),
+            ));
+        }
+        Ok(parsed) => {
+            let local = matches!(parsed.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+            if parsed.scheme() == "http" && !local {
+                issues.push(ConfigIssue::warning(
+                    path,
+                    format!("{} is not encrypted; use https", url),
+                ));
+            }
+        }
+        Err(e) => issues.push(ConfigIssue::error(
+            path,
+            format!("{} is not a valid URL: {}", url, e),
+        )),
+    }
+}
+
+fn check_range(issues: &mut Vec<ConfigIssue>, path: &str, value: u64, min: u64, max: u64) {
+    if value < min || value > max {
+        issues.push(ConfigIssue::error(
+            path,
+            format!("{} is out of range; use {} to {}", value, min, max),
+        ));
+    }
+}
+
+fn check_model(issues: &mut Vec<ConfigIssue>, path: &str, model: &str) {
+    if model.trim().is_empty() {
+        issues.push(ConfigIssue::error(path, "The model id is empty"));
+    } else if !KNOWN_MODEL_FAMILIES
+        .iter()
+        .any(|family| model.starts_with(family))
+    {
+        issues.push(ConfigIssue::warning(
+            path,
+            format!("{} is not a known model; check the id for typos", model),
+        ));
+    }
+}
+
+impl AppConfig {
+    /// Checks settings that parse but can't work or look mistaken: URLs,
+    /// model ids, numeric ranges and required values. Errors come first.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
+        if let Some(anthropic) = &self.anthropic {
+            if anthropic.api_key.is_some() {
+                issues.push(ConfigIssue::warning(
+                    "anthropic.api_key",
+                    "The key is stored in plain text; save it as the \"anthropic\" secret instead",
+                ));
+            }
+        }
+
+        if let Some(greptile) = &self.greptile {
+            if let Some(base_url) = &greptile.base_url {
+                check_url(&mut issues, "greptile.base_url", base_url);
+            }
+            if let Some(max_results) = greptile.max_results {
+                check_range(
+                    &mut issues,
+                    "greptile.max_results",
+                    max_results.into(),
+                    1,
+                    100,
+                );
+            }
+            if greptile.api_key.is_some() {
+                issues.push(ConfigIssue::warning(
+                    "greptile.api_key",
+                    "The key is stored in plain text; save it as the \"greptile\" secret instead",
+                ));
+            }
+        }
+
+        for (name, provider) in &self.oauth {
+            let path = format!("oauth.{}", name);
+            if provider.client_id.trim().is_empty() {
+                issues.push(ConfigIssue::error(
+                    &format!("{}.client_id", path),
+                    "The client id is empty",
+                ));
+            }
+            check_url(
+                &mut issues,
+                &format!("{}.token_url", path),
+                &provider.token_url,
+            );
+            for (field, url) in [
+                ("authorization_url", &provider.authorization_url),
+                (
+                    "device_authorization_url",
+                    &provider.device_authorization_url,
+                ),
+            ] {
+                if let Some(url) = url {
+                    check_url(&mut issues, &format!("{}.{}", path, field), url);
+                }
+            }
+            let missing = match provider.flow {
+                Some(OAuthFlow::Pkce) => provider
+                    .authorization_url
+                    .is_none()
+                    .then_some("authorization_url"),
+                Some(OAuthFlow::Device) => provider
+                    .device_authorization_url
+                    .is_none()
+                    .then_some("device_authorization_url"),
+                None => (provider.authorization_url.is_none()
+                    && provider.device_authorization_url.is_none())
+                .then_some("authorization_url"),
+            };
+            if let Some(field) = missing {
+                issues.push(ConfigIssue::error(
+                    &format!("{}.{}", path, field),
+                    "Required to sign in with this provider",
+                ));
+            }
+        }
+
+        if let Some(fs_config) = &self.fs {
+            for (i, root) in fs_config.allowed_roots.iter().enumerate() {
+                let path = format!("fs.allowed_roots.{}", i);
+                if !Path::new(root).is_absolute() {
+                    issues.push(ConfigIssue::error(
+                        &path,
+                        format!("{} is not an absolute path", root),
+                    ));
+                } else if !Path::new(root).is_dir() {
+                    issues.push(ConfigIssue::warning(
+                        &path,
+                        format!("{} is not a directory", root),
+                    ));
+                }
+            }
+        }
+
+        if let Some(format) = &self.format {
+            if let Some(timeout_ms) = format.timeout_ms {
+                check_range(&mut issues, "format.timeout_ms", timeout_ms, 100, 600_000);
+            }
+            for (language, formatter) in &format.formatters {
+                if formatter.command.trim().is_empty() {
+                    issues.push(ConfigIssue::error(
+                        &format!("format.formatters.{}.command", language),
+                        "The command is empty",
+                    ));
+                }
+            }
+        }
+
+        if let Some(models) = &self.models {
+            for (field, model) in [
+                ("chat", &models.chat),
+                ("completion", &models.completion),
+                ("summary", &models.summary),
+            ] {
+                if let Some(model) = model {
+                    check_model(&mut issues, &format!("models.{}", field), model);
+                }
+            }
+        }
+
+        if let Some(workspace) = &self.workspace {
+            for (i, pattern) in workspace.ignore.iter().enumerate() {
+                if let Err(e) = glob::Pattern::new(pattern) {
+                    issues.push(ConfigIssue::error(
+                        &format!("workspace.ignore.{}", i),
+                        format!("{} is not a valid glob: {}", pattern, e),
+                    ));
+                }
+            }
+            if let Some(drop_dir) = &workspace.drop_dir {
+                let path = Path::new(drop_dir);
+                if drop_dir.trim().is_empty()
+                    || path.is_absolute()
+                    || path
+                        .components()
+                        .any(|component| matches!(component, Component::ParentDir))
+                {
+                    issues.push(ConfigIssue::error(
+                        "workspace.drop_dir",
+                        format!("{:?} is not a directory inside the workspace", drop_dir),
+                    ));
+                }
+            }
+        }
+
+        for (name, task) in &self.tasks {
+            if task.command.trim().is_empty() {
+                issues.push(ConfigIssue::error(
+                    &format!("tasks.{}.command", name),
+                    "The command is empty",
+                ));
+            }
+        }
+
+        if let Some(context) = &self.context {
+            if let Some(chunk_size) = context.chunk_size {
+                check_range(
+                    &mut issues,
+                    "context.chunk_size",
+                    chunk_size as u64,
+                    5,
+                    1000,
+                );
+            }
+            if let Some(max_results) = context.max_results {
+                check_range(
+                    &mut issues,
+                    "context.max_results",
+                    max_results as u64,
+                    1,
+                    200,
+                );
+            }
+            if let Some(max_files) = context.warmup_max_files {
+                check_range(
+                    &mut issues,
+                    "context.warmup_max_files",
+                    max_files as u64,
+                    0,
+                    100_000,
+                );
+            }
+            if let Some(cpu_percent) = context.warmup_cpu_percent {
+                check_range(
+                    &mut issues,
+                    "context.warmup_cpu_percent",
+                    cpu_percent as u64,
+                    1,
+                    100,
+                );
+            }
+            for (field, patterns) in [
+                (
+                    "skip_patterns",
+                    context.skip_patterns.as_deref().unwrap_or_default(),
+                ),
+                ("include_patterns", context.include_patterns.as_slice()),
+            ] {
+                for (i, pattern) in patterns.iter().enumerate() {
+                    if let Err(e) = glob::Pattern::new(pattern) {
+                        issues.push(ConfigIssue::error(
+                            &format!("context.{}.{}", field, i),
+                            format!("{} is not a valid glob: {}", pattern, e),
+                        ));
+                    }
+                }
+            }
+            if let Some(max_file_bytes) = context.max_file_bytes {
+                check_range(
+                    &mut issues,
+                    "context.max_file_bytes",
+                    max_file_bytes,
+                    1024,
+                    100 * 1024 * 1024,
+                );
+            }
+            if let Some(endpoint) = &context.summary_endpoint {
+                check_url(&mut issues, "context.summary_endpoint", endpoint);
+                if context.summary_model.is_none() {
+                    issues.push(ConfigIssue::error(
+                        "context.summary_model",
+                        "Needed to summarize with context.summary_endpoint",
+                    ));
+                }
+                if context.summarize_chunks != Some(true) {
+                    issues.push(ConfigIssue::warning(
+                        "context.summary_endpoint",
+                        "Nothing is summarized while context.summarize_chunks is off",
+                    ));
+                }
+            }
+        }
+
+        if let Some(telemetry) = &self.telemetry {
+            if let Some(endpoint) = &telemetry.otlp_endpoint {
+                check_url(&mut issues, "telemetry.otlp_endpoint", endpoint);
+                if !telemetry.enabled {
+                    issues.push(ConfigIssue::warning(
+                        "telemetry.otlp_endpoint",
+                        "Nothing is exported while telemetry.enabled is off",
+                    ));
+                }
+            }
+            if let Some(interval) = telemetry.export_interval_secs {
+                check_range(
+                    &mut issues,
+                    "telemetry.export_interval_secs",
+                    interval,
+                    5,
+                    3600,
+                );
+            }
+        }
+
+        if let Some(max_concurrent) = self.jobs.as_ref().and_then(|jobs| jobs.max_concurrent) {
+            check_range(
+                &mut issues,
+                "jobs.max_concurrent",
+                max_concurrent as u64,
+                1,
+                16,
+            );
+        }
+
+        if let Some(threshold) = self.jobs.as_ref().and_then(|jobs| jobs.idle_threshold_ms) {
+            check_range(
+                &mut issues,
+                "jobs.idle_threshold_ms",
+                threshold,
+                100,
+                60_000,
+            );
+        }
+
+        if let Some(budget) = &self.budget {
+            for (path, value, min) in [
+                ("budget.max_index_mb", budget.max_index_mb, 64),
+                ("budget.max_storage_mb", budget.max_storage_mb, 64),
+                ("budget.max_cache_mb", budget.max_cache_mb, 1),
+            ] {
+                if let Some(value) = value {
+                    check_range(&mut issues, path, value, min, 1 << 20);
+                }
+            }
+        }
+
+        if let Some(storage) = &self.storage {
+            if let Some(interval) = storage.flush_interval_ms {
+                check_range(
+                    &mut issues,
+                    "storage.flush_interval_ms",
+                    interval,
+                    50,
+                    60_000,
+                );
+            }
+            let mut prefixes = storage.buffered_prefixes.iter().flatten();
+            if prefixes.any(|prefix| prefix.is_empty()) {
+                issues.push(ConfigIssue::error(
+                    "storage.buffered_prefixes",
+                    "An empty prefix would buffer every write",
+                ));
+            }
+        }
+
+        if let Some(scheduler) = &self.scheduler {
+            if let Some(idle_minutes) = scheduler.idle_minutes {
+                check_range(
+                    &mut issues,
+                    "scheduler.idle_minutes",
+                    idle_minutes,
+                    1,
+                    24 * 60,
+                );
+            }
+            if let Some(keep_backups) = scheduler.keep_backups {
+                check_range(
+                    &mut issues,
+                    "scheduler.keep_backups",
+                    keep_backups as u64,
+                    1,
+                    100,
+                );
+            }
+            for (name, expression) in &scheduler.schedules {
+                let path = format!("scheduler.schedules.{}", name);
+                if !scheduler::is_scheduled_job(name) {
+                    issues.push(ConfigIssue::error(
+                        &path,
+                        format!("There is no scheduled job named {}", name),
+                    ));
+                } else if expression.trim() != scheduler::OFF {
+                    if let Err(e) = scheduler::Schedule::parse(expression) {
+                        issues.push(ConfigIssue::error(&path, e));
+                    }
+                }
+            }
+        }
+
+        if let Some(interval) = self
+            .session
+            .as_ref()
+            .and_then(|session| session.autosave_interval_secs)
+        {
+            check_range(
+                &mut issues,
+                "session.autosave_interval_secs",
+                interval,
+                1,
+                3600,
+            );
+        }
+
+        if let Some(port) = self.api_server.as_ref().and_then(|server| server.port) {
+            check_range(&mut issues, "api_server.port", port as u64, 1024, 65535);
+        }
+
+        for (name, provider) in &self.providers {
+            check_url(
+                &mut issues,
+                &format!("providers.{}.base_url", name),
+                &provider.base_url,
+            );
+        }
+
+        if let Some(proxy) = &self.proxy {
+            for (i, host) in proxy.allowed_hosts.iter().enumerate() {
+                if host.trim().is_empty() || host.contains(['/', ':']) {
+                    issues.push(ConfigIssue::error(
+                        &format!("proxy.allowed_hosts.{}", i),
+                        format!("{:?} is not a host name", host),
+                    ));
+                }
+            }
+        }
+
+        if let Some(max_entries) = self
+            .recorder
+            .as_ref()
+            .and_then(|recorder| recorder.max_entries)
+        {
+            check_range(
+                &mut issues,
+                "recorder.max_entries",
+                max_entries as u64,
+                1,
+                100_000,
+            );
+        }
+
+        if let Some(http) = &self.http {
+            if let Some(proxy) = &http.proxy {
+                match reqwest::Url::parse(proxy) {
+                    Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+                    Ok(_) => issues.push(ConfigIssue::error(
+                        "http.proxy",
+                        format!("{} is not an http(s) URL", proxy),
+                    )),
+                    Err(e) => issues.push(ConfigIssue::error(
+                        "http.proxy",
+                        format!("{} is not a valid URL: {}", proxy, e),
+                    )),
+                }
+            }
+            if let Some(ca_bundle) = &http.ca_bundle {
+                if !Path::new(ca_bundle).is_file() {
+                    issues.push(ConfigIssue::error(
+                        "http.ca_bundle",
+                        format!("{} is not a file", ca_bundle),
+                    ));
+                }
+            }
+            if let Some(timeout) = http.connect_timeout_secs {
+                check_range(&mut issues, "http.connect_timeout_secs", timeout, 1, 300);
+            }
+            let timeouts = http
+                .timeout_secs
+                .map(|timeout| ("http.timeout_secs".to_string(), timeout))
+                .into_iter()
+                .chain(http.provider_timeouts.iter().map(|(provider, timeout)| {
+                    (format!("http.provider_timeouts.{}", provider), *timeout)
+                }));
+            for (path, timeout) in timeouts {
+                check_range(&mut issues, &path, timeout, 1, 3600);
+            }
+            if let Some(max) = http.max_concurrent_requests {
+                check_range(
+                    &mut issues,
+                    "http.max_concurrent_requests",
+                    max as u64,
+                    1,
+                    64,
+                );
+            }
+        }
+
+        if let Some(updates) = &self.updates {
+            if let Some(hours) = updates.check_interval_hours {
+                check_range(
+                    &mut issues,
+                    "updates.check_interval_hours",
+                    hours,
+                    0,
+                    24 * 30,
+                );
+            }
+            for (channel, endpoint) in &updates.endpoints {
+                let path = format!("updates.endpoints.{}", channel);
+                if channel != "stable" && channel != "beta" {
+                    issues.push(ConfigIssue::warning(
+                        &path,
+                        format!("{} is not a channel; use stable or beta", channel),
+                    ));
+                }
+                check_url(&mut issues, &path, endpoint);
+            }
+            let channel = updates.channel.unwrap_or_default();
+            if channel == UpdateChannel::Beta && !updates.endpoints.contains_key("beta") {
+                issues.push(ConfigIssue::error(
+                    "updates.endpoints.beta",
+                    "Required to update from the beta channel",
+                ));
+            }
+        }
+
+        if let Some(secrets) = &self.secrets {
+            for (i, pattern) in secrets.allow.iter().enumerate() {
+                if let Err(e) = regex::Regex::new(pattern) {
+                    issues.push(ConfigIssue::error(
+                        &format!("secrets.allow.{}", i),
+                        format!("{} is not a valid regex: {}", pattern, e),
+                    ));
+                }
+            }
+        }
+
+        if let Some(redaction) = &self.redaction {
+            for (i, pattern) in redaction.sensitive_globs.iter().enumerate() {
+                if let Err(e) = glob::Pattern::new(pattern) {
+                    issues.push(ConfigIssue::error(
+                        &format!("redaction.sensitive_globs.{}", i),
+                        format!("{} is not a valid glob: {}", pattern, e),
+                    ));
+                }
+            }
+        }
+
+        // Stable, so issues keep their order within a severity
+        issues.sort_by_key(|issue| issue.severity != IssueSeverity::Error);
+        issues
+    }
+}
@@ -0,0 +1,138 @@
+// src/context/ai_edit.rs
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use super::explain::{complete, gather_context, prompt, selection_range};
+use crate::commands::diff::{compute_diff, TextDiffResult};
+use crate::commands::edit_transaction::{
+    begin_edit_transaction, rollback_edit_transaction, stage_edit,
+};
+use crate::commands::fs::{read_file, resolve_workspace_path, workspace_relative_path};
+use crate::error::MightyError;
+use crate::state::AppState;
+
+const MAX_TOKENS: i32 = 8_192;
+const INSTRUCTIONS: &str = "Rewrite the selected code as the instruction below asks. Reply \
+with only the code replacing the selection, in one fenced code block, keeping its indentation \
+and leaving the code around it alone.";
+
+/// Lines of a file, 1-based and inclusive.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LineRange {
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// A model's rewrite of a selection, staged for review.
+#[derive(Debug, Clone, Serialize)]
+pub struct AiEditProposal {
+    /// The edit transaction holding the change; commit it to apply the edit
+    /// or roll it back to drop it. `None` when the model changed nothing.
+    pub transaction_id: Option<String>,
+    pub path: String,
+    pub range: LineRange,
+    pub replacement: String,
+    pub diff: TextDiffResult,
+    pub model: String,
+}
+
+/// The code in a reply: the first fenced block if there is one, otherwise
+/// the whole reply.
+pub(super) fn code_in_reply(reply: &str) -> String {
+    let Some(open) = reply.find("```") else {
+        return reply.trim_matches('\n').to_string();
+    };
+    // Skip the language tag on the opening fence
+    let body = &reply[open + 3..];
+    let body = body.split_once('\n').map_or("", |(_, rest)| rest);
+    let code = match body.find("\n```") {
+        Some(close) => &body[..close],
+        None => body.strip_suffix("```").unwrap_or(body),
+    };
+    code.to_string()
+}
+
+/// `content` with lines `start` to `end` (0-based, inclusive) replaced by
+/// `replacement`, keeping the file's line endings.
+pub(super) fn splice(content: &str, start: usize, end: usize, replacement: &str) -> String {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let line_ending = if lines[end].ends_with("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    let mut spliced: String = lines[..start].concat();
+    let replacement = replacement.replace("\r\n", "\n");
+    let replacement = replacement.strip_suffix('\n').unwrap_or(&replacement);
+    if !replacement.is_empty() {
+        spliced.push_str(&replacement.replace('\n', line_ending));
+        // The last selected line may be the file's last, without an ending
+        if lines[end].ends_with('\n') {
+            spliced.push_str(line_ending);
+        }
+    }
+    spliced.push_str(&lines[end + 1..].concat());
+    spliced
+}
+
+/// Asks the chat model to rewrite `range` of `path` following `instruction`,
+/// with the code around it, the file's imports and definitions of the names
+/// the selection uses as context. The change isn't written: it is staged in
+/// a new edit transaction and returned as a diff, to be applied with
+/// `commit_edit_transaction` once approved.
+#[tauri::command]
+pub async fn ai_edit_range(
+    app: AppHandle,
+    path: String,
+    range: LineRange,
+    instruction: String,
+    state: State<'_, AppState>,
+) -> Result<AiEditProposal, MightyError> {
+    if instruction.trim().is_empty() {
+        return Err(MightyError::request(
+            "EMPTY_INSTRUCTION",
+            "Describe the change to make",
+        ));
+    }
+    let content = read_file(path.clone()).await?.content;
+    let lines: Vec<&str> = content.lines().collect();
+    let (start, end) = selection_range(&path, range.start_line, range.end_line, lines.len())?;
+
+    let relative = workspace_relative_path(&resolve_workspace_path(&path)?);
+    let context = gather_context(&path, &relative, &lines, start, end)?;
+    let instructions = format!("{}\n\nInstruction: {}", INSTRUCTIONS, instruction.trim());
+    let prompt = prompt(&instructions, &relative, &lines, start, end, &context);
+    let (reply, model) = complete(app.clone(), state, prompt, MAX_TOKENS).await?;
+
+    let replacement = code_in_reply(&reply);
+    let edited = splice(&content, start, end, &replacement);
+    let diff = compute_diff(&content, &edited);
+
+    let transaction_id = if diff.hunks.is_empty() {
+        None
+    } else {
+        let transaction_id = begin_edit_transaction().await?;
+        let staged = stage_edit(
+            transaction_id.clone(),
+            path.clone(),
+            diff.unified_diff.clone(),
+        )
+        .await;
+        if let Err(e) = staged {
+            rollback_edit_transaction(app, transaction_id).await?;
+            return Err(e.into());
+        }
+        Some(transaction_id)
+    };
+
+    Ok(AiEditProposal {
+        transaction_id,
+        path,
+        range,
+        replacement,
+        diff,
+        model,
+    })
+}
@@ -0,0 +1,891 @@
+// src/context/auto_fix.rs
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::ipc::Request;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::oneshot;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::ai_edit::{code_in_reply, splice};
+use super::explain::{complete, gather_context};
+use super::stack_trace;
+use super::symbol_index::{self, IndexedSymbol};
+use crate::commands::diagnostics::{publish_diagnostics, Diagnostic, DiagnosticSeverity};
+use crate::commands::diff::{compute_diff, TextDiffResult};
+use crate::commands::dry_run::{self, DRY_RUN_CODE};
+use crate::commands::edit_transaction::{
+    begin_edit_transaction, commit_transaction, rollback_edit_transaction, stage_edit,
+    TransactionStatus, TransactionSummary,
+};
+use crate::commands::exec::{find_executable, run_tool_streaming, ExecError, ExecOutput};
+use crate::commands::fs::{get_project_root, resolve_workspace_path, workspace_relative_path};
+use crate::commands::notifications::{notify, NotificationLevel};
+use crate::commands::permissions::{authorize, Actor, Capability};
+use crate::commands::trust::ensure_trusted;
+use crate::config::TaskConfig;
+use crate::error::MightyError;
+use crate::state::AppState;
+
+const DEFAULT_MAX_ITERATIONS: usize = 3;
+const MAX_ITERATIONS_LIMIT: usize = 10;
+/// Builds and test suites can take a while; a task running longer fails
+/// the run.
+const TASK_TIMEOUT: Duration = Duration::from_secs(600);
+/// An unreviewed fix is declined after this long.
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(600);
+const MAX_TOKENS: i32 = 8_192;
+/// Errors shown to the model in one iteration, first reported first.
+const MAX_ERRORS: usize = 20;
+const MAX_REGIONS: usize = 8;
+/// Lines shown on each side of an error outside a symbol small enough to
+/// show whole.
+const REGION_CONTEXT: usize = 15;
+const MAX_REGION_LINES: usize = 120;
+const MAX_DEFINITIONS: usize = 15;
+/// Lines of the task's output included in the prompt, from its end.
+const OUTPUT_TAIL_LINES: usize = 60;
+const INSTRUCTIONS: &str = "A build or test task failed with the errors below. Fix their cause \
+rather than silencing them, and only change a test when the test itself is wrong. The code \
+around the errors is shown as numbered regions. Start with a sentence or two on the fix, then, \
+for each region that needs a change, write a line `Region <n>` followed by one fenced code \
+block holding the whole region as it should be, keeping its indentation. Leave out regions \
+that need no change.";
+
+static ANSI_ESCAPE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").expect("the pattern is valid"));
+/// `error[E0308]: mismatched types`, located by the ` --> file:line:col`
+/// line after it.
+static RUSTC_HEADER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?P<severity>error|warning)(?:\[(?P<code>[A-Z]+\d+)\])?: (?P<message>.+)$")
+        .expect("the pattern is valid")
+});
+static RUSTC_LOCATION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*--> (?P<file>.+?):(?P<line>\d+):(?P<column>\d+)$")
+        .expect("the pattern is valid")
+});
+/// `src/a.ts(3,7): error TS2322: ...` and `src/a.ts:3:7 - error TS2322: ...`
+static TSC: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(?P<file>[^\s(:]+)(?:\((?P<line>\d+),(?P<column>\d+)\):|:(?P<line2>\d+):(?P<column2>\d+) -) (?P<severity>error|warning) (?P<code>TS\d+): (?P<message>.+)$",
+    )
+    .expect("the pattern is valid")
+});
+/// `file:line[:col]: [severity[code]:] message`, as gcc, Go, mypy, ruff and
+/// pytest print it.
+static GENERIC: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(?P<file>[^\s:()]+\.[A-Za-z0-9]+):(?P<line>\d+):(?:(?P<column>\d+):?)?\s*(?:(?P<severity>fatal error|error|warning|note)(?:\[(?P<code>[^\]]+)\])?:\s*)?(?P<message>\S.*)$",
+    )
+    .expect("the pattern is valid")
+});
+/// A `Region <n>` heading in the model's reply.
+static REGION_HEADING: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^[#*\s]*Region (?P<n>\d+)\b.*$").expect("the pattern is valid"));
+
+static RUNS: Lazy<Mutex<HashMap<String, Run>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Default)]
+struct Run {
+    cancelled: bool,
+    /// Set while a proposed fix waits for `respond_to_auto_fix`.
+    approval: Option<oneshot::Sender<bool>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoFixOutcome {
+    /// The task passed.
+    Fixed,
+    /// The task still failed after the last allowed fix.
+    Exhausted,
+    /// The failure gave nothing to fix, or the model proposed no change.
+    Stuck,
+    Declined,
+    Cancelled,
+    Failed,
+}
+
+/// A file a proposed fix changes.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProposedFile {
+    /// Workspace-relative.
+    pub path: String,
+    pub diff: TextDiffResult,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum AutoFixStep {
+    Running {
+        command: String,
+    },
+    TaskFinished {
+        success: bool,
+        exit_code: Option<i32>,
+        duration_ms: u64,
+        /// Every problem found in the output, warnings included; only
+        /// errors are sent to the model.
+        diagnostics: Vec<Diagnostic>,
+    },
+    /// A fix staged in an edit transaction. Unless the run approves fixes
+    /// itself, it waits for `respond_to_auto_fix`.
+    Proposed {
+        transaction_id: String,
+        explanation: String,
+        files: Vec<ProposedFile>,
+        model: String,
+        awaiting_approval: bool,
+    },
+    Applied {
+        summary: TransactionSummary,
+    },
+    Finished {
+        outcome: AutoFixOutcome,
+        message: Option<String>,
+    },
+}
+
+/// Payload of the `auto-fix-step` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoFixEvent {
+    pub run_id: String,
+    /// 1-based; each iteration runs the task once.
+    pub iteration: usize,
+    #[serde(flatten)]
+    pub step: AutoFixStep,
+}
+
+/// Payload of the `auto-fix-output` event, one per line the task prints.
+#[derive(Debug, Clone, Serialize)]
+struct OutputEvent<'a> {
+    run_id: &'a str,
+    line: &'a str,
+}
+
+struct FixRun {
+    id: String,
+    actor: Actor,
+    task_name: String,
+    task: TaskConfig,
+    max_iterations: usize,
+    auto_approve: bool,
+}
+
+impl FixRun {
+    fn emit(&self, app: &AppHandle, iteration: usize, step: AutoFixStep) {
+        let event = AutoFixEvent {
+            run_id: self.id.clone(),
+            iteration,
+            step,
+        };
+        if let Err(e) = app.emit("auto-fix-step", &event) {
+            warn!("Failed to emit auto-fix-step: {}", e);
+        }
+    }
+
+    fn command_line(&self) -> String {
+        std::iter::once(self.task.command.as_str())
+            .chain(self.task.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn cancelled(&self) -> bool {
+        RUNS.lock().get(&self.id).is_none_or(|run| run.cancelled)
+    }
+}
+
+/// Lines of a file that a fix may rewrite, 0-based and inclusive.
+#[derive(Debug, Clone)]
+struct Region {
+    path: String,
+    start: usize,
+    end: usize,
+}
+
+struct Proposal {
+    transaction_id: String,
+    explanation: String,
+    files: Vec<ProposedFile>,
+    model: String,
+}
+
+fn task_program(task: &TaskConfig) -> Result<PathBuf, ExecError> {
+    find_executable(&task.command)
+        .or_else(|| {
+            let path = resolve_workspace_path(&task.command).ok()?;
+            path.is_file().then_some(path)
+        })
+        .ok_or_else(|| {
+            ExecError::new(
+                "TOOL_NOT_FOUND",
+                &format!("{} was not found on PATH", task.command),
+            )
+        })
+}
+
+fn task_cwd(task: &TaskConfig) -> Result<PathBuf, ExecError> {
+    match &task.cwd {
+        Some(cwd) => Ok(resolve_workspace_path(cwd)?),
+        None => Ok(get_project_root()),
+    }
+}
+
+/// A path from the task's output as a file in the workspace. Tools report
+/// paths relative to where they ran or to an ancestor of it, such as a
+/// Cargo workspace root.
+fn resolve_reported(file: &str, cwd: &Path, root: &Path) -> Option<String> {
+    cwd.ancestors()
+        .take_while(|dir| dir.starts_with(root))
+        .map(|dir| dir.join(file))
+        .find(|path| path.is_file())
+        .or_else(|| stack_trace::resolve_path(file, root))
+        .map(|path| workspace_relative_path(&path))
+}
+
+fn severity(name: Option<&str>) -> Option<DiagnosticSeverity> {
+    match name {
+        Some("warning") => Some(DiagnosticSeverity::Warning),
+        Some("note") => None,
+        _ => Some(DiagnosticSeverity::Error),
+    }
+}
+
+/// The problems a task's output reports in workspace files: rustc and tsc
+/// messages, `file:line:col: message` lines, and failing the rest, where a
+/// panic or exception was raised.
+fn parse_diagnostics(output: &str, cwd: &Path, source: &str) -> Vec<Diagnostic> {
+    let root = get_project_root();
+    let output = ANSI_ESCAPE.replace_all(output, "");
+    let lines: Vec<&str> = output.lines().collect();
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut push = |diagnostic: Diagnostic| {
+        let seen = diagnostics.iter().any(|seen| {
+            seen.path == diagnostic.path
+                && seen.line == diagnostic.line
+                && seen.message == diagnostic.message
+        });
+        if !seen {
+            diagnostics.push(diagnostic);
+        }
+    };
+
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.trim_end();
+        let found = if let Some(header) = RUSTC_HEADER.captures(line) {
+            let Some(location) = lines
+                .get(i + 1)
+                .and_then(|next| RUSTC_LOCATION.captures(next))
+            else {
+                continue;
+            };
+            (
+                location["file"].to_string(),
+                location["line"].parse().unwrap_or(1),
+                location["column"].parse().ok(),
+                severity(Some(&header["severity"])),
+                header.name("code").map(|code| code.as_str().to_string()),
+                header["message"].to_string(),
+            )
+        } else if let Some(captures) = TSC.captures(line) {
+            let number = |name: &str, fallback: &str| {
+                captures
+                    .name(name)
+                    .or_else(|| captures.name(fallback))
+                    .and_then(|n| n.as_str().parse().ok())
+            };
+            (
+                captures["file"].to_string(),
+                number("line", "line2").unwrap_or(1),
+                number("column", "column2"),
+                severity(Some(&captures["severity"])),
+                Some(captures["code"].to_string()),
+                captures["message"].to_string(),
+            )
+        } else if let Some(captures) = GENERIC.captures(line) {
+            (
+                captures["file"].to_string(),
+                captures["line"].parse().unwrap_or(1),
+                captures
+                    .name("column")
+                    .and_then(|n| n.as_str().parse().ok()),
+                severity(captures.name("severity").map(|s| s.as_str())),
+                captures.name("code").map(|code| code.as_str().to_string()),
+                captures["message"].to_string(),
+            )
+        } else {
+            continue;
+        };
+
+        let (file, line, column, severity, code, message) = found;
+        let (Some(severity), Some(path)) = (severity, resolve_reported(&file, cwd, &root)) else {
+            continue;
+        };
+        push(Diagnostic {
+            path,
+            line,
+            column: column.unwrap_or(1),
+            end_line: None,
+            end_column: None,
+            severity,
+            message,
+            code,
+            source: source.to_string(),
+        });
+    }
+
+    // Failing tests often report nothing but a panic or an exception
+    let has_errors = diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error);
+    if !has_errors {
+        let (_, message, frames) = stack_trace::parse(&output);
+        let located = frames
+            .iter()
+            .find_map(|frame| stack_trace::locate(frame, &root));
+        if let Some(location) = located {
+            diagnostics.push(Diagnostic {
+                path: location.path,
+                line: location.line,
+                column: location.column.unwrap_or(1),
+                end_line: None,
+                end_column: None,
+                severity: DiagnosticSeverity::Error,
+                message: message.unwrap_or_else(|| "The task failed here".to_string()),
+                code: None,
+                source: source.to_string(),
+            });
+        }
+    }
+    diagnostics
+}
+
+/// The code shown around each error: the innermost symbol holding it when
+/// that's small enough to show whole, otherwise the lines around it.
+/// Overlapping regions of a file are merged.
+fn regions_for(errors: &[&Diagnostic], files: &HashMap<String, Vec<String>>) -> Vec<Region> {
+    let mut regions: Vec<Region> = Vec::new();
+    for error in errors {
+        let Some(lines) = files.get(&error.path) else {
+            continue;
+        };
+        if lines.is_empty() {
+            continue;
+        }
+        let line = error.line.clamp(1, lines.len()) - 1;
+        let symbols = symbol_index::symbols_in_file(&[&error.path]).unwrap_or_default();
+        let enclosing = symbols
+            .iter()
+            .filter(|symbol| symbol.start_line <= line && line <= symbol.end_line)
+            .filter(|symbol| symbol.end_line.saturating_sub(symbol.start_line) < MAX_REGION_LINES)
+            .max_by_key(|symbol| symbol.start_line);
+        let (start, end) = match enclosing {
+            Some(symbol) => (symbol.start_line, symbol.end_line.min(lines.len() - 1)),
+            None => (
+                line.saturating_sub(REGION_CONTEXT),
+                (line + REGION_CONTEXT).min(lines.len() - 1),
+            ),
+        };
+
+        let overlapping = regions.iter_mut().find(|region| {
+            region.path == error.path && start <= region.end + 1 && region.start <= end + 1
+        });
+        match overlapping {
+            Some(region) => {
+                region.start = region.start.min(start);
+                region.end = region.end.max(end);
+            }
+            None if regions.len() < MAX_REGIONS => regions.push(Region {
+                path: error.path.clone(),
+                start,
+                end,
+            }),
+            None => {}
+        }
+    }
+    regions
+}
+
+fn output_tail(output: &ExecOutput) -> String {
+    let combined = format!("{}\n{}", output.stdout, output.stderr);
+    let combined = ANSI_ESCAPE.replace_all(&combined, "");
+    let lines: Vec<&str> = combined
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    lines[lines.len().saturating_sub(OUTPUT_TAIL_LINES)..].join("\n")
+}
+
+fn prompt(
+    run: &FixRun,
+    output: &ExecOutput,
+    errors: &[&Diagnostic],
+    regions: &[Region],
+    files: &HashMap<String, Vec<String>>,
+) -> String {
+    let mut prompt = format!(
+        "{}\n\nTask `{}` (`{}`) failed with exit code {}.\n\nErrors:\n",
+        INSTRUCTIONS,
+        run.task_name,
+        run.command_line(),
+        output
+            .exit_code
+            .map_or_else(|| "unknown".to_string(), |code| code.to_string())
+    );
+    for error in errors {
+        let code = error
+            .code
+            .as_ref()
+            .map(|code| format!(" [{}]", code))
+            .unwrap_or_default();
+        prompt.push_str(&format!(
+            "- {}:{}:{}{}: {}\n",
+            error.path, error.line, error.column, code, error.message
+        ));
+    }
+    prompt.push_str(&format!(
+        "\nEnd of the task's output:\n```\n{}\n```\n",
+        output_tail(output)
+    ));
+
+    let mut imports_shown = HashSet::new();
+    let mut definitions: Vec<IndexedSymbol> = Vec::new();
+    for (n, region) in regions.iter().enumerate() {
+        let lines: Vec<&str> = files[&region.path].iter().map(String::as_str).collect();
+        match gather_context(&region.path, &region.path, &lines, region.start, region.end) {
+            Ok(context) => {
+                if !context.imports.is_empty() && imports_shown.insert(region.path.clone()) {
+                    prompt.push_str(&format!(
+                        "\nImports of {}:\n{}\n",
+                        region.path,
+                        context.imports.join("\n")
+                    ));
+                }
+                for symbol in context.definitions {
+                    let seen = definitions.iter().any(|seen| {
+                        seen.name == symbol.name
+                            && seen.file == symbol.file
+                            && seen.start_line == symbol.start_line
+                    });
+                    if !seen {
+                        definitions.push(symbol);
+                    }
+                }
+            }
+            Err(e) => debug!("No context for {}: {}", region.path, e),
+        }
+        prompt.push_str(&format!(
+            "\nRegion {}: {} lines {}-{}\n```\n{}\n```\n",
+            n + 1,
+            region.path,
+            region.start + 1,
+            region.end + 1,
+            lines[region.start..=region.end].join("\n")
+        ));
+    }
+
+    if !definitions.is_empty() {
+        prompt.push_str("\nDefinitions of names used in the regions:\n");
+        for symbol in definitions.iter().take(MAX_DEFINITIONS) {
+            prompt.push_str(&format!(
+                "- {:?} {} ({}:{})",
+                symbol.kind,
+                symbol.name,
+                symbol.file,
+                symbol.start_line + 1
+            ));
+            if let Some(docs) = &symbol.docs {
+                prompt.push_str(&format!(": {}", docs.replace('\n', " ")));
+            }
+            prompt.push('\n');
+        }
+    }
+    prompt
+}
+
+/// The explanation before the first region of a reply, and the new code
+/// for each region it rewrites, by 0-based region index.
+fn parse_reply(reply: &str, regions: usize) -> (String, Vec<(usize, String)>) {
+    let headings: Vec<_> = REGION_HEADING.captures_iter(reply).collect();
+    let explanation = match headings.first() {
+        Some(first) => reply[..first.get(0).expect("the whole match").start()].trim(),
+        None => reply.trim(),
+    };
+    let mut rewrites: Vec<(usize, String)> = Vec::new();
+    for (i, heading) in headings.iter().enumerate() {
+        let whole = heading.get(0).expect("the whole match");
+        let end = headings.get(i + 1).map_or(reply.len(), |next| {
+            next.get(0).expect("the whole match").start()
+        });
+        let body = &reply[whole.end()..end];
+        let Some(n) = heading["n"]
+            .parse::<usize>()
+            .ok()
+            .filter(|n| (1..=regions).contains(n))
+        else {
+            continue;
+        };
+        if body.contains("```") && !rewrites.iter().any(|(index, _)| *index == n - 1) {
+            rewrites.push((n - 1, code_in_reply(body)));
+        }
+    }
+    (explanation.to_string(), rewrites)
+}
+
+/// Asks the chat model to fix `errors` and stages its rewrites in a new edit
+/// transaction. `None` when it changed nothing.
+async fn propose(
+    app: &AppHandle,
+    run: &FixRun,
+    output: &ExecOutput,
+    errors: &[&Diagnostic],
+) -> Result<Option<Proposal>, MightyError> {
+    let mut contents: HashMap<String, String> = HashMap::new();
+    for error in errors {
+        if contents.contains_key(&error.path) {
+            continue;
+        }
+        let full_path = resolve_workspace_path(&error.path)?;
+        match tokio::fs::read_to_string(&full_path).await {
+            Ok(content) => {
+                contents.insert(error.path.clone(), content);
+            }
+            Err(e) => debug!("Not showing {}: {}", error.path, e),
+        }
+    }
+    let files: HashMap<String, Vec<String>> = contents
+        .iter()
+        .map(|(path, content)| (path.clone(), content.lines().map(String::from).collect()))
+        .collect();
+
+    let regions = regions_for(errors, &files);
+    if regions.is_empty() {
+        return Ok(None);
+    }
+    let prompt = prompt(run, output, errors, &regions, &files);
+    let (reply, model) = complete(app.clone(), app.state::<AppState>(), prompt, MAX_TOKENS).await?;
+    let (explanation, mut rewrites) = parse_reply(&reply, regions.len());
+
+    // Later regions first, so splicing one doesn't move the lines of another
+    rewrites.sort_by_key(|(index, _)| std::cmp::Reverse(regions[*index].start));
+    let mut edited: Vec<(String, String)> = Vec::new();
+    for (index, replacement) in rewrites {
+        let region = &regions[index];
+        let position = match edited.iter().position(|(path, _)| *path == region.path) {
+            Some(position) => position,
+            None => {
+                edited.push((region.path.clone(), contents[&region.path].clone()));
+                edited.len() - 1
+            }
+        };
+        let content = &mut edited[position].1;
+        *content = splice(content, region.start, region.end, &replacement);
+    }
+
+    let files: Vec<ProposedFile> = edited
+        .into_iter()
+        .map(|(path, content)| ProposedFile {
+            diff: compute_diff(&contents[&path], &content),
+            path,
+        })
+        .filter(|file| !file.diff.hunks.is_empty())
+        .collect();
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    let transaction_id = begin_edit_transaction().await?;
+    for file in &files {
+        let staged = stage_edit(
+            transaction_id.clone(),
+            file.path.clone(),
+            file.diff.unified_diff.clone(),
+        )
+        .await;
+        if let Err(e) = staged {
+            rollback_edit_transaction(app.clone(), transaction_id).await?;
+            return Err(e.into());
+        }
+    }
+    Ok(Some(Proposal {
+        transaction_id,
+        explanation,
+        files,
+        model,
+    }))
+}
+
+/// Waits for `respond_to_auto_fix`; no answer in time is a refusal.
+async fn await_approval(app: &AppHandle, run: &FixRun) -> bool {
+    let (respond, response) = oneshot::channel();
+    match RUNS.lock().get_mut(&run.id) {
+        Some(pending) if !pending.cancelled => pending.approval = Some(respond),
+        _ => return false,
+    }
+    notify(
+        app,
+        NotificationLevel::Info,
+        "auto_fix",
+        "A fix is waiting for review",
+        Some(format!("Task {}", run.task_name)),
+    );
+    matches!(
+        tokio::time::timeout(APPROVAL_TIMEOUT, response).await,
+        Ok(Ok(true))
+    )
+}
+
+async fn discard(app: &AppHandle, transaction_id: String) {
+    if let Err(e) = rollback_edit_transaction(app.clone(), transaction_id).await {
+        warn!("Failed to discard a proposed fix: {}", e);
+    }
+}
+
+/// Runs the task and fixes what it reports until it passes or the run
+/// ends otherwise. Returns how it ended and the iteration it ended in.
+async fn fix_loop(app: &AppHandle, run: &FixRun) -> (AutoFixOutcome, Option<String>, usize) {
+    let mut iteration = 0;
+    loop {
+        iteration += 1;
+        if run.cancelled() {
+            return (AutoFixOutcome::Cancelled, None, iteration);
+        }
+
+        let (program, cwd) = match task_program(&run.task)
+            .and_then(|program| task_cwd(&run.task).map(|cwd| (program, cwd)))
+        {
+            Ok(found) => found,
+            Err(e) => return (AutoFixOutcome::Failed, Some(e.to_string()), iteration),
+        };
+        run.emit(
+            app,
+            iteration,
+            AutoFixStep::Running {
+                command: run.command_line(),
+            },
+        );
+        let envs: Vec<(&str, &str)> = run
+            .task
+            .env
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        let result = run_tool_streaming(
+            &program,
+            &run.task.args,
+            &cwd,
+            &envs,
+            TASK_TIMEOUT,
+            |line| {
+                let event = OutputEvent {
+                    run_id: &run.id,
+                    line,
+                };
+                if let Err(e) = app.emit("auto-fix-output", &event) {
+                    warn!("Failed to emit auto-fix-output: {}", e);
+                }
+            },
+        )
+        .await;
+        let output = match result {
+            Ok(output) => output,
+            Err(e) => return (AutoFixOutcome::Failed, Some(e.to_string()), iteration),
+        };
+
+        let diagnostics = if output.success() {
+            Vec::new()
+        } else {
+            let text = format!("{}\n{}", output.stdout, output.stderr);
+            let source = run.task_name.clone();
+            tokio::task::spawn_blocking(move || parse_diagnostics(&text, &cwd, &source))
+                .await
+                .unwrap_or_default()
+        };
+        publish_diagnostics(app, &run.task_name, None, &diagnostics);
+        run.emit(
+            app,
+            iteration,
+            AutoFixStep::TaskFinished {
+                success: output.success(),
+                exit_code: output.exit_code,
+                duration_ms: output.duration.as_millis() as u64,
+                diagnostics: diagnostics.clone(),
+            },
+        );
+
+        if output.success() {
+            return (AutoFixOutcome::Fixed, None, iteration);
+        }
+        if iteration > run.max_iterations {
+            let message = format!("Still failing after {} fixes", run.max_iterations);
+            return (AutoFixOutcome::Exhausted, Some(message), iteration);
+        }
+        let errors: Vec<&Diagnostic> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+            .take(MAX_ERRORS)
+            .collect();
+        if errors.is_empty() {
+            let message = "The task failed without pointing at code in the workspace";
+            return (AutoFixOutcome::Stuck, Some(message.to_string()), iteration);
+        }
+
+        let proposal = match propose(app, run, &output, &errors).await {
+            Ok(Some(proposal)) => proposal,
+            Ok(None) => {
+                let message = "The model proposed no change";
+                return (AutoFixOutcome::Stuck, Some(message.to_string()), iteration);
+            }
+            Err(e) => return (AutoFixOutcome::Failed, Some(e.to_string()), iteration),
+        };
+        let transaction_id = proposal.transaction_id.clone();
+        let paths = proposal
+            .files
+            .iter()
+            .map(|file| file.path.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        run.emit(
+            app,
+            iteration,
+            AutoFixStep::Proposed {
+                transaction_id: proposal.transaction_id,
+                explanation: proposal.explanation,
+                files: proposal.files,
+                model: proposal.model,
+                awaiting_approval: !run.auto_approve,
+            },
+        );
+
+        // The agent's writes still answer to the workspace's policy
+        let approved = if run.auto_approve {
+            match authorize(app, run.actor, Capability::WriteFile, &paths).await {
+                Ok(()) => true,
+                Err(e) => {
+                    discard(app, transaction_id).await;
+                    return (AutoFixOutcome::Declined, Some(e), iteration);
+                }
+            }
+        } else {
+            await_approval(app, run).await
+        };
+        if run.cancelled() {
+            discard(app, transaction_id).await;
+            return (AutoFixOutcome::Cancelled, None, iteration);
+        }
+        if !approved {
+            discard(app, transaction_id).await;
+            return (AutoFixOutcome::Declined, None, iteration);
+        }
+
+        let summary = match commit_transaction(app, run.actor, transaction_id).await {
+            Ok(summary) => summary,
+            Err(e) => return (AutoFixOutcome::Failed, Some(e.to_string()), iteration),
+        };
+        let error = summary.error.clone();
+        let committed = summary.status == TransactionStatus::Committed;
+        run.emit(app, iteration, AutoFixStep::Applied { summary });
+        if !committed {
+            return (AutoFixOutcome::Failed, error, iteration);
+        }
+    }
+}
+
+/// Runs the workspace task `task_name`, such as `build` or `test`, and
+/// while it fails, asks the chat model to fix the errors its output points
+/// at, applies the fix and runs it again, at most `max_iterations` times
+/// (3 by default). Each fix is staged in an edit transaction and waits for
+/// `respond_to_auto_fix` unless `auto_approve` is set. Progress arrives as
+/// `auto-fix-step` events and the task's output as `auto-fix-output`;
+/// returns the run's id.
+#[tauri::command]
+pub async fn auto_fix(
+    app: AppHandle,
+    request: Request<'_>,
+    task_name: String,
+    max_iterations: Option<usize>,
+    auto_approve: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<String, MightyError> {
+    let task = state.config.lock().await.tasks.get(&task_name).cloned();
+    let Some(task) = task else {
+        return Err(MightyError::request(
+            "UNKNOWN_TASK",
+            &format!("No task named {}", task_name),
+        ));
+    };
+    let actor = Actor::of(&request);
+    if dry_run::is_active(actor) {
+        dry_run::plan_step(
+            "auto_fix",
+            &task_name,
+            None,
+            json!({ "command": task.command }),
+        );
+        return Err(MightyError::request(
+            DRY_RUN_CODE,
+            &dry_run::planned_error("auto_fix"),
+        ));
+    }
+    ensure_trusted("running tasks").map_err(|e| MightyError::request("WORKSPACE_UNTRUSTED", &e))?;
+    authorize(&app, actor, Capability::RunCommand, &task_name).await?;
+
+    let run = FixRun {
+        id: Uuid::new_v4().to_string(),
+        actor,
+        task_name,
+        task,
+        max_iterations: max_iterations
+            .unwrap_or(DEFAULT_MAX_ITERATIONS)
+            .clamp(1, MAX_ITERATIONS_LIMIT),
+        auto_approve: auto_approve.unwrap_or(false),
+    };
+    let run_id = run.id.clone();
+    RUNS.lock().insert(run_id.clone(), Run::default());
+    tauri::async_runtime::spawn(async move {
+        let (outcome, message, iteration) = fix_loop(&app, &run).await;
+        RUNS.lock().remove(&run.id);
+        info!(
+            "Auto-fix of {} ended {:?} after {} runs",
+            run.task_name, outcome, iteration
+        );
+        run.emit(&app, iteration, AutoFixStep::Finished { outcome, message });
+    });
+    Ok(run_id)
+}
+
+/// Applies or discards the fix an auto-fix run proposed.
+#[tauri::command]
+pub async fn respond_to_auto_fix(run_id: String, approved: bool) -> Result<(), String> {
+    let respond = RUNS
+        .lock()
+        .get_mut(&run_id)
+        .and_then(|run| run.approval.take())
+        .ok_or_else(|| format!("No fix from run {} is waiting for review", run_id))?;
+    // The run may have given up waiting already
+    let _ = respond.send(approved);
+    Ok(())
+}
+
+/// Stops an auto-fix run once the task it's running finishes, discarding
+/// any fix waiting for review.
+#[tauri::command]
+pub async fn cancel_auto_fix(run_id: String) -> Result<(), String> {
+    let mut runs = RUNS.lock();
+    let run = runs
+        .get_mut(&run_id)
+        .ok_or_else(|| format!("No auto-fix run with id {}", run_id))?;
+    run.cancelled = true;
+    if let Some(respond) = run.approval.take() {
+        let _ = respond.send(false);
+    }
+    Ok(())
+}
@@ -0,0 +1,353 @@
+// src/context/chat_memory.rs
+
+use ::arrow::array::{
+    Array, FixedSizeListArray, Float32Array, Int32Array, Int64Array, RecordBatch,
+    RecordBatchIterator, StringArray,
+};
+use ::arrow::datatypes::DataType;
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use futures::TryStreamExt;
+use lancedb::arrow::arrow_schema::{Field, Schema};
+use lancedb::arrow::SendableRecordBatchStream;
+use lancedb::query::{ExecutableQuery, QueryBase};
+use lancedb::table::Table;
+use lancedb::{Connection, DistanceType};
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+use super::context::context_manager;
+use super::context_manager::{has_columns, EMBEDDING_DIM};
+use crate::commands::api::AnthropicMessage;
+use crate::commands::budget;
+use crate::commands::fs::get_project_root;
+use crate::secrets;
+use crate::state::app_state;
+
+const TABLE_NAME: &str = "conversation_memory";
+const DEFAULT_LIMIT: usize = 5;
+const MAX_LIMIT: usize = 20;
+/// Turns fetched per conversation asked for, since several usually come
+/// from the same one.
+const TURNS_PER_RESULT: usize = 4;
+/// Characters of a turn kept and embedded; the start says most about it.
+const MAX_TURN_CHARS: usize = 4_000;
+
+fn memory_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("conversation_id", DataType::Utf8, false),
+        Field::new("workspace", DataType::Utf8, false),
+        Field::new("turn_index", DataType::Int32, false),
+        Field::new("role", DataType::Utf8, false),
+        Field::new("content", DataType::Utf8, false),
+        Field::new(
+            "embedding",
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, false)),
+                EMBEDDING_DIM,
+            ),
+            false,
+        ),
+        Field::new("created_at", DataType::Int64, false),
+    ]))
+}
+
+/// Opens the conversation table next to the chunk table, creating it, or
+/// rebuilding it if its columns changed.
+pub(crate) async fn open_table(db: &Connection) -> Result<Table> {
+    let schema = memory_schema();
+    match db.open_table(TABLE_NAME).execute().await {
+        Ok(table) if has_columns(&table.schema().await?, &schema) => Ok(table),
+        Ok(_) => {
+            warn!("Schema of table '{}' is outdated, rebuilding", TABLE_NAME);
+            db.drop_table(TABLE_NAME).await?;
+            Ok(db.create_empty_table(TABLE_NAME, schema).execute().await?)
+        }
+        Err(_) => {
+            info!("Creating new table '{}'", TABLE_NAME);
+            Ok(db.create_empty_table(TABLE_NAME, schema).execute().await?)
+        }
+    }
+}
+
+/// A turn of an earlier conversation.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecalledTurn {
+    /// Position in its conversation, from 0.
+    pub turn_index: usize,
+    pub role: String,
+    pub content: String,
+    /// Cosine distance to the query; lower is closer.
+    pub distance: f32,
+    /// Milliseconds since the Unix epoch.
+    pub created_at: i64,
+}
+
+/// An earlier conversation and its turns closest to the query.
+#[derive(Debug, Clone, Serialize)]
+pub struct RelatedConversation {
+    pub conversation_id: String,
+    pub workspace: String,
+    /// The distance of its closest turn.
+    pub distance: f32,
+    /// In conversation order.
+    pub turns: Vec<RecalledTurn>,
+}
+
+fn quoted(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn truncate(text: &str) -> &str {
+    match text.char_indices().nth(MAX_TURN_CHARS) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+async fn enabled() -> bool {
+    app_state()
+        .config
+        .lock()
+        .await
+        .context
+        .as_ref()
+        .and_then(|context| context.remember_conversations)
+        .unwrap_or(true)
+}
+
+/// Embeds and stores `messages` as turns `first_turn` onwards of
+/// `conversation_id`, replacing what was stored for those turns, e.g. a
+/// regenerated reply. Secrets are redacted first. Returns how many turns
+/// were stored: none while `context.remember_conversations` is off.
+pub(crate) async fn remember_turns(
+    conversation_id: &str,
+    first_turn: usize,
+    messages: &[AnthropicMessage],
+) -> Result<usize, String> {
+    if !enabled().await {
+        return Ok(0);
+    }
+    budget::check_index_write()?;
+    let manager = context_manager().await?;
+    let turns: Vec<(usize, &AnthropicMessage)> = messages
+        .iter()
+        .enumerate()
+        .map(|(i, message)| (first_turn + i, message))
+        .filter(|(_, message)| !message.content.trim().is_empty())
+        .collect();
+    if turns.is_empty() {
+        return Ok(0);
+    }
+
+    let contents: Vec<String> = turns
+        .iter()
+        .map(|(_, message)| secrets::redact(truncate(&message.content)))
+        .collect();
+    let embeddings = manager
+        .generate_embeddings(contents.clone())
+        .await
+        .map_err(|e| e.to_string())?;
+    if embeddings.len() != turns.len()
+        || embeddings
+            .iter()
+            .any(|embedding| embedding.len() != EMBEDDING_DIM as usize)
+    {
+        return Err("The embedding model returned unexpected embeddings".to_string());
+    }
+
+    let workspace = get_project_root().to_string_lossy().to_string();
+    let created_at = Utc::now().timestamp_millis();
+    let indices: Vec<i32> = turns.iter().map(|(index, _)| *index as i32).collect();
+    let columns: Vec<Arc<dyn Array>> = vec![
+        Arc::new(StringArray::from_iter_values(
+            indices
+                .iter()
+                .map(|index| format!("{}:{}", conversation_id, index)),
+        )),
+        Arc::new(StringArray::from(vec![conversation_id; turns.len()])),
+        Arc::new(StringArray::from(vec![workspace.as_str(); turns.len()])),
+        Arc::new(Int32Array::from(indices.clone())),
+        Arc::new(StringArray::from_iter_values(
+            turns.iter().map(|(_, message)| message.role.as_str()),
+        )),
+        Arc::new(StringArray::from(contents)),
+        Arc::new(
+            FixedSizeListArray::try_new(
+                Arc::new(Field::new("item", DataType::Float32, false)),
+                EMBEDDING_DIM,
+                Arc::new(Float32Array::from(
+                    embeddings.into_iter().flatten().collect::<Vec<f32>>(),
+                )),
+                None,
+            )
+            .map_err(|e| e.to_string())?,
+        ),
+        Arc::new(Int64Array::from(vec![created_at; turns.len()])),
+    ];
+    let batch = RecordBatch::try_new(memory_schema(), columns).map_err(|e| e.to_string())?;
+
+    let table = manager.memory_table();
+    let replaced = format!(
+        "conversation_id = {} AND turn_index IN ({})",
+        quoted(conversation_id),
+        indices
+            .iter()
+            .map(|index| index.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    table.delete(&replaced).await.map_err(|e| e.to_string())?;
+    let batches = RecordBatchIterator::new(vec![Ok(batch)], memory_schema());
+    table
+        .add(batches)
+        .execute()
+        .await
+        .map_err(|e| e.to_string())?;
+    debug!(
+        "Remembered {} turns of conversation {}",
+        turns.len(),
+        conversation_id
+    );
+    Ok(turns.len())
+}
+
+/// Reads query results as (conversation id, workspace, turn).
+async fn read_turns(
+    mut stream: SendableRecordBatchStream,
+) -> Result<Vec<(String, String, RecalledTurn)>> {
+    let mut turns = Vec::new();
+    while let Some(batch) = stream.try_next().await? {
+        let strings = |name: &str| {
+            batch
+                .column_by_name(name)
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                .ok_or_else(|| anyhow!("{} column not found in record batch", name))
+        };
+        let conversation_id = strings("conversation_id")?;
+        let workspace = strings("workspace")?;
+        let role = strings("role")?;
+        let content = strings("content")?;
+        let turn_index = batch
+            .column_by_name("turn_index")
+            .and_then(|c| c.as_any().downcast_ref::<Int32Array>())
+            .ok_or_else(|| anyhow!("turn_index column not found in record batch"))?;
+        let created_at = batch
+            .column_by_name("created_at")
+            .and_then(|c| c.as_any().downcast_ref::<Int64Array>())
+            .ok_or_else(|| anyhow!("created_at column not found in record batch"))?;
+        let distance = batch
+            .column_by_name("_distance")
+            .and_then(|c| c.as_any().downcast_ref::<Float32Array>());
+
+        for i in 0..batch.num_rows() {
+            turns.push((
+                conversation_id.value(i).to_string(),
+                workspace.value(i).to_string(),
+                RecalledTurn {
+                    turn_index: turn_index.value(i) as usize,
+                    role: role.value(i).to_string(),
+                    content: content.value(i).to_string(),
+                    distance: distance.map_or(0.0, |d| d.value(i)),
+                    created_at: created_at.value(i),
+                },
+            ));
+        }
+    }
+    Ok(turns)
+}
+
+/// Groups turns by conversation, closest conversation first, keeping at
+/// most `limit` conversations.
+fn group(turns: Vec<(String, String, RecalledTurn)>, limit: usize) -> Vec<RelatedConversation> {
+    let mut conversations: Vec<RelatedConversation> = Vec::new();
+    for (conversation_id, workspace, turn) in turns {
+        match conversations
+            .iter_mut()
+            .find(|c| c.conversation_id == conversation_id)
+        {
+            Some(conversation) => {
+                conversation.distance = conversation.distance.min(turn.distance);
+                conversation.turns.push(turn);
+            }
+            None => conversations.push(RelatedConversation {
+                conversation_id,
+                workspace,
+                distance: turn.distance,
+                turns: vec![turn],
+            }),
+        }
+    }
+    conversations.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    conversations.truncate(limit);
+    for conversation in &mut conversations {
+        conversation.turns.sort_by_key(|turn| turn.turn_index);
+    }
+    conversations
+}
+
+/// Earlier conversations about the same thing as `query`, closest first,
+/// each with its turns closest to it, for seeding a new chat with what was
+/// already discussed. Only this workspace's conversations unless
+/// `all_workspaces`; `exclude_conversation_id` leaves out the current chat.
+#[tauri::command]
+pub async fn recall_related_conversations(
+    query: String,
+    limit: Option<usize>,
+    exclude_conversation_id: Option<String>,
+    all_workspaces: Option<bool>,
+) -> Result<Vec<RelatedConversation>, String> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let manager = context_manager().await?;
+    let embedding = manager
+        .generate_embedding(&query)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut filters = Vec::new();
+    if !all_workspaces.unwrap_or(false) {
+        let workspace = get_project_root().to_string_lossy().to_string();
+        filters.push(format!("workspace = {}", quoted(&workspace)));
+    }
+    if let Some(exclude) = &exclude_conversation_id {
+        filters.push(format!("conversation_id != {}", quoted(exclude)));
+    }
+
+    let mut search = manager
+        .memory_table()
+        .vector_search(embedding)
+        .map_err(|e| e.to_string())?
+        .distance_type(DistanceType::Cosine)
+        .limit(limit * TURNS_PER_RESULT);
+    if !filters.is_empty() {
+        search = search.only_if(filters.join(" AND "));
+    }
+    let stream = search.execute().await.map_err(|e| e.to_string())?;
+    let turns = read_turns(stream).await.map_err(|e| e.to_string())?;
+    Ok(group(turns, limit))
+}
+
+/// Stores turns of a conversation for `recall_related_conversations`, e.g.
+/// chats from before it existed. Turns sent through `anthropic_completion`
+/// with a conversation id are stored as they happen.
+#[tauri::command]
+pub async fn remember_conversation(
+    conversation_id: String,
+    messages: Vec<AnthropicMessage>,
+    first_turn: Option<usize>,
+) -> Result<usize, String> {
+    remember_turns(&conversation_id, first_turn.unwrap_or(0), &messages).await
+}
+
+/// Forgets every stored turn of a conversation, e.g. when it is deleted.
+#[tauri::command]
+pub async fn forget_conversation(conversation_id: String) -> Result<(), String> {
+    let manager = context_manager().await?;
+    manager
+        .memory_table()
+        .delete(&format!("conversation_id = {}", quoted(&conversation_id)))
+        .await
+        .map_err(|e| e.to_string())
+}
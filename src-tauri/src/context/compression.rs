@@ -0,0 +1,160 @@
+// src/context/compression.rs
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tracing::{info, warn};
+use zstd::dict::{DecoderDictionary, EncoderDictionary};
+use zstd::zstd_safe;
+
+use crate::commands::storage::{get_record, put_record};
+
+const LEVEL: i32 = 3;
+const DICTIONARY_KEY_PREFIX: &str = "compression:dictionary:";
+/// Id of the dictionary new chunks are compressed with.
+const CURRENT_DICTIONARY_KEY: &str = "compression:current";
+const DICTIONARY_SIZE: usize = 64 * 1024;
+/// Chunk text gathered before a dictionary is trained; zstd wants samples
+/// worth many times the dictionary size.
+const TRAINING_BYTES: usize = 16 * DICTIONARY_SIZE;
+
+#[derive(Default)]
+struct Samples {
+    chunks: Vec<String>,
+    bytes: usize,
+    /// Set once training starts, so it happens at most once per run.
+    done: bool,
+}
+
+static ENCODER: Lazy<RwLock<Option<Arc<EncoderDictionary<'static>>>>> =
+    Lazy::new(|| RwLock::new(None));
+/// Whether the current dictionary has been looked up in storage.
+static LOADED: AtomicBool = AtomicBool::new(false);
+static DECODERS: Lazy<RwLock<HashMap<u32, Arc<DecoderDictionary<'static>>>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+static SAMPLES: Lazy<Mutex<Samples>> = Lazy::new(|| Mutex::new(Samples::default()));
+
+fn load_dictionary(id: u32) -> Option<Vec<u8>> {
+    let encoded = get_record(&format!("{}{}", DICTIONARY_KEY_PREFIX, id)).ok()??;
+    STANDARD.decode(encoded).ok()
+}
+
+/// The dictionary new chunks are compressed with, read from storage the
+/// first time it is needed.
+fn encoder() -> Option<Arc<EncoderDictionary<'static>>> {
+    if let Some(encoder) = ENCODER.read().clone() {
+        return Some(encoder);
+    }
+    if LOADED.load(Ordering::Relaxed) {
+        return None;
+    }
+    // Storage isn't up yet; look again on the next chunk
+    let current = get_record(CURRENT_DICTIONARY_KEY).ok()?;
+    LOADED.store(true, Ordering::Relaxed);
+    let id = current?.parse().ok()?;
+    let dictionary = load_dictionary(id)?;
+    let encoder = Arc::new(EncoderDictionary::copy(&dictionary, LEVEL));
+    *ENCODER.write() = Some(encoder.clone());
+    Some(encoder)
+}
+
+fn decoder(id: u32) -> Option<Arc<DecoderDictionary<'static>>> {
+    if let Some(dictionary) = DECODERS.read().get(&id) {
+        return Some(dictionary.clone());
+    }
+    let dictionary = Arc::new(DecoderDictionary::copy(&load_dictionary(id)?));
+    DECODERS.write().insert(id, dictionary.clone());
+    Some(dictionary)
+}
+
+/// Trains a dictionary on the chunks gathered so far and makes it current.
+fn train(chunks: Vec<String>) {
+    let dictionary = match zstd::dict::from_samples(&chunks, DICTIONARY_SIZE) {
+        Ok(dictionary) => dictionary,
+        Err(e) => {
+            warn!("Failed to train a compression dictionary: {}", e);
+            return;
+        }
+    };
+    let Some(id) = zstd_safe::get_dict_id_from_dict(&dictionary) else {
+        warn!("Trained compression dictionary has no id");
+        return;
+    };
+    let id = id.get();
+    let stored = put_record(
+        &format!("{}{}", DICTIONARY_KEY_PREFIX, id),
+        &STANDARD.encode(&dictionary),
+    )
+    .and_then(|_| put_record(CURRENT_DICTIONARY_KEY, &id.to_string()));
+    if let Err(e) = stored {
+        // Chunks compressed with it could not be read after a restart
+        warn!("Failed to store the compression dictionary: {}", e);
+        return;
+    }
+    DECODERS
+        .write()
+        .insert(id, Arc::new(DecoderDictionary::copy(&dictionary)));
+    *ENCODER.write() = Some(Arc::new(EncoderDictionary::copy(&dictionary, LEVEL)));
+    info!(
+        "Trained compression dictionary {} on {} chunks",
+        id,
+        chunks.len()
+    );
+}
+
+/// Keeps `text` for training until there is enough, then trains in the
+/// background.
+fn sample(text: &str) {
+    let mut samples = SAMPLES.lock();
+    if samples.done {
+        return;
+    }
+    samples.bytes += text.len();
+    samples.chunks.push(text.to_string());
+    if samples.bytes >= TRAINING_BYTES {
+        samples.done = true;
+        let chunks = std::mem::take(&mut samples.chunks);
+        tokio::task::spawn_blocking(move || train(chunks));
+    }
+}
+
+/// Compresses chunk text for the index, with the project's dictionary once
+/// one has been trained. Each frame records the dictionary it used, so text
+/// compressed before or without one stays readable.
+pub fn compress(text: &str) -> io::Result<Vec<u8>> {
+    match encoder() {
+        Some(encoder) => {
+            zstd::bulk::Compressor::with_prepared_dictionary(&encoder)?.compress(text.as_bytes())
+        }
+        None => {
+            sample(text);
+            zstd::bulk::compress(text.as_bytes(), LEVEL)
+        }
+    }
+}
+
+/// Reverses `compress`.
+pub fn decompress(bytes: &[u8]) -> io::Result<String> {
+    let mut text = Vec::new();
+    match zstd_safe::get_dict_id_from_frame(bytes) {
+        Some(id) => {
+            let dictionary = decoder(id.get()).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Compression dictionary {} is missing", id),
+                )
+            })?;
+            zstd::stream::read::Decoder::with_prepared_dictionary(bytes, &dictionary)?
+                .read_to_end(&mut text)?;
+        }
+        None => {
+            zstd::stream::read::Decoder::new(bytes)?.read_to_end(&mut text)?;
+        }
+    }
+    String::from_utf8(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
@@ -1,23 +1,32 @@
 use anyhow::Result;
 use chrono::Utc;
-use once_cell::sync::OnceCell;
 use serde::Deserialize;
 use std::path::PathBuf;
 use std::sync::Arc;
+use tauri::AppHandle;
 use tokio::sync::Mutex;
+use tracing::{debug, info};
 
 use super::context_manager::{
-    ChunkInfo, ContextConfig, ContextStats, QueryContext, QueryMetadata, SmartContextManager
+    ChunkInfo, ChunkRecord, ContextConfig, ContextStats, EmbeddedChunk, FileContext, QueryContext,
+    QueryMetadata, SmartContextManager,
 };
+use super::filters::{self, SkipReason};
+use super::pruning;
+use crate::commands::budget;
+use crate::commands::fs::{resolve_workspace_path, FileSystemError};
+use crate::error::MightyError;
+use crate::state::app_state;
 
-/// Thread-safe global state using tokio::sync::Mutex for async safety
-struct GlobalState {
+/// The context manager's slot in the app state, using tokio::sync::Mutex
+/// for async safety
+pub(crate) struct ContextState {
     manager: Arc<Mutex<Option<Arc<SmartContextManager>>>>,
     init_lock: Arc<Mutex<()>>,
 }
 
-impl GlobalState {
-    fn new() -> Self {
+impl ContextState {
+    pub(crate) fn new() -> Self {
         Self {
             manager: Arc::new(Mutex::new(None)),
             init_lock: Arc::new(Mutex::new(())),
@@ -40,23 +49,38 @@ impl GlobalState {
     }
 }
 
-// Thread-safe singleton instance
-static GLOBAL_STATE: OnceCell<GlobalState> = OnceCell::new();
+fn context_state() -> &'static ContextState {
+    &app_state().context
+}
+
+/// The context manager, for modules keeping their own tables in its
+/// database.
+pub(crate) async fn context_manager() -> Result<Arc<SmartContextManager>, String> {
+    context_state().get_manager().await
+}
 
-fn get_global_state() -> &'static GlobalState {
-    GLOBAL_STATE.get_or_init(|| GlobalState::new())
+/// Number of indexed chunks, which also checks that LanceDB answers; `None`
+/// until the context manager is initialized.
+pub(crate) async fn context_health() -> Result<Option<usize>, String> {
+    let manager = context_state().manager.lock().await.clone();
+    match manager {
+        Some(manager) => manager.chunk_count().await.map(Some).map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
 }
 
 #[tauri::command]
 pub async fn init_context_manager(
+    app: AppHandle,
     db_path: String,
     max_files: usize,
     max_embeddings: usize,
     watch_files: Option<bool>,
     chunk_size: Option<usize>,
     min_chunk_overlap: Option<usize>,
+    max_cache_bytes: Option<usize>,
 ) -> Result<(), String> {
-    println!("=== Rust Context Manager Initialization ===");
+    info!("Initializing the context manager");
 
     let context_config = ContextConfig {
         max_files,
@@ -65,42 +89,45 @@ pub async fn init_context_manager(
         watch_files: Some(watch_files.unwrap_or(false)),
         chunk_size: Some(chunk_size.unwrap_or(512)),
         min_chunk_overlap: Some(min_chunk_overlap.unwrap_or(32)),
+        max_cache_bytes,
     };
 
-    let state = get_global_state();
+    let state = context_state();
     let _init_guard = state.init_lock.lock().await;
 
     let mut manager_guard = state.manager.lock().await;
     if manager_guard.is_some() {
-        println!("ContextManager is already initialized.");
+        debug!("ContextManager is already initialized.");
         return Ok(());
     }
 
     let manager = SmartContextManager::new(context_config)
         .await
         .map_err(|e| format!("Failed to create SmartContextManager: {}", e))?;
+    manager.limit_cache(budget::cache_limit());
 
     *manager_guard = Some(Arc::new(manager));
-    println!("=== Context Manager Initialization Complete ===");
+    info!("Context manager initialized");
+    pruning::start(app);
     Ok(())
 }
 
 #[tauri::command]
 pub async fn reset_context_manager() -> Result<(), String> {
-    let state = get_global_state();
+    let state = context_state();
     state.reset().await
 }
 
 #[tauri::command]
 pub async fn get_context(query: String) -> Result<QueryContext, String> {
-    let state = get_global_state();
+    let state = context_state();
     let manager = state.get_manager().await?;
     manager.get_context(&query).await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn generate_embeddings(text: String) -> Result<Vec<f32>, String> {
-    let state = get_global_state();
+    let state = context_state();
     let manager = state.get_manager().await?;
     manager
         .generate_embedding(&text)
@@ -108,36 +135,148 @@ pub async fn generate_embeddings(text: String) -> Result<Vec<f32>, String> {
         .map_err(|e| e.to_string())
 }
 
+/// Reads a workspace file; paths outside it are refused with
+/// `ACCESS_DENIED`, as by `read_file`.
 #[tauri::command]
-pub async fn read_context_file(path: String) -> Result<String, String> {
-    tokio::fs::read_to_string(&path)
+pub async fn read_context_file(path: String) -> Result<String, MightyError> {
+    let full_path = resolve_workspace_path(&path)?;
+    tokio::fs::read_to_string(&full_path)
+        .await
+        .map_err(|e| FileSystemError::with_path("READ_ERROR", &e.to_string(), &full_path).into())
+}
+
+/// Indexes a file unless the content filters leave it out, in which case
+/// any chunks it already has are dropped and the reason is returned.
+pub(crate) async fn index_file(path: &str, content: &str) -> Result<Option<SkipReason>, String> {
+    budget::check_index_write()?;
+    let state = context_state();
+    let manager = state.get_manager().await?;
+    if let Some(reason) = filters::content_skip_reason(path, content) {
+        debug!("Not indexing {}: {:?}", path, reason);
+        filters::record_skipped(path, reason.clone());
+        manager.remove_file(path).await.map_err(|e| e.to_string())?;
+        return Ok(Some(reason));
+    }
+    manager
+        .add_file(path, content)
         .await
-        .map_err(|e| format!("Failed to read file {}: {}", path, e))
+        .map_err(|e| e.to_string())?;
+    filters::clear_skipped(path);
+    Ok(None)
 }
 
+/// Indexes a file; files the content filters leave out are skipped and
+/// listed by `get_skipped_files`.
 #[tauri::command]
 pub async fn add_to_context(path: String, content: String) -> Result<(), String> {
-    let state = get_global_state();
+    index_file(&path, &content).await.map(|_| ())
+}
+
+/// Chunks most similar to `query`, best first.
+pub(crate) async fn similar_chunks(query: &str, limit: usize) -> Result<Vec<ChunkInfo>, String> {
+    let state = context_state();
     let manager = state.get_manager().await?;
     manager
-        .add_file(&path, &content)
+        .search_similar(query, limit)
         .await
-        .map(|_| ())
         .map_err(|e| e.to_string())
 }
 
+/// Every indexed chunk with its embedding.
+pub(crate) async fn embedded_chunks() -> Result<Vec<EmbeddedChunk>, String> {
+    let state = context_state();
+    let manager = state.get_manager().await?;
+    manager.embedded_chunks().await.map_err(|e| e.to_string())
+}
+
+/// Creates the vector index if there isn't one yet; true when it did.
+pub(crate) async fn ensure_vector_index() -> Result<bool, String> {
+    let state = context_state();
+    let manager = state.get_manager().await?;
+    manager
+        .ensure_vector_index()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// The `limit` chunks the vector index finds nearest to `embedding`, with
+/// their distances.
+pub(crate) async fn nearest_chunks(
+    embedding: &[f32],
+    limit: usize,
+) -> Result<Vec<(EmbeddedChunk, f32)>, String> {
+    let state = context_state();
+    let manager = state.get_manager().await?;
+    manager
+        .nearest_chunks(embedding, limit)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// The indexed chunks of `path` with their embeddings.
+pub(crate) async fn file_chunks(path: &str) -> Result<Vec<EmbeddedChunk>, String> {
+    let state = context_state();
+    let manager = state.get_manager().await?;
+    manager.file_chunks(path).await.map_err(|e| e.to_string())
+}
+
+/// Compacts the index's tables; see `SmartContextManager::optimize`.
+pub(crate) async fn optimize_index() -> Result<(), String> {
+    let state = context_state();
+    let manager = state.get_manager().await?;
+    manager.optimize().await.map_err(|e| e.to_string())
+}
+
+/// Compacts the index and deletes its old versions at once; see
+/// `SmartContextManager::reclaim_space`.
+pub(crate) async fn reclaim_index_space() -> Result<(), String> {
+    let state = context_state();
+    let manager = state.get_manager().await?;
+    manager.reclaim_space().await.map_err(|e| e.to_string())
+}
+
+/// Where the index is stored and the bytes its file cache holds, or `None`
+/// until the context manager is initialized.
+pub(crate) async fn index_usage() -> Option<(PathBuf, usize)> {
+    let manager = context_state().manager.lock().await.clone()?;
+    Some((manager.index_dir(), manager.cache_bytes()))
+}
+
+/// Caps the file cache at `limit` bytes. Until the context manager is
+/// initialized this does nothing; it applies the budget when it starts.
+pub(crate) async fn limit_cache(limit: Option<usize>) {
+    if let Some(manager) = context_state().manager.lock().await.clone() {
+        manager.limit_cache(limit);
+    }
+}
+
+/// Paths of every indexed file.
+pub(crate) async fn indexed_files() -> Result<Vec<String>, String> {
+    let state = context_state();
+    let manager = state.get_manager().await?;
+    manager.indexed_files().await.map_err(|e| e.to_string())
+}
+
+/// Every indexed chunk's id, file and embedding dimension.
+pub(crate) async fn chunk_records() -> Result<Vec<ChunkRecord>, String> {
+    let state = context_state();
+    let manager = state.get_manager().await?;
+    manager.chunk_records().await.map_err(|e| e.to_string())
+}
+
+/// Drops an indexed file, returning how many chunks it had.
+pub(crate) async fn remove_from_context(path: &str) -> Result<usize, String> {
+    let state = context_state();
+    let manager = state.get_manager().await?;
+    manager.remove_file(path).await.map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn search_similar_code(
     query: String,
     limit: Option<usize>,
 ) -> Result<QueryContext, String> {
-    let state = get_global_state();
-    let manager = state.get_manager().await?;
-
-    let chunks = manager
-        .search_similar(&query, limit.unwrap_or(5))
-        .await
-        .map_err(|e| e.to_string())?;
+    let chunks = similar_chunks(&query, limit.unwrap_or(5)).await?;
 
     Ok(QueryContext {
         chunks: chunks.clone(),
@@ -153,21 +292,34 @@ pub async fn search_similar_code(
 
 #[tauri::command]
 pub async fn get_file_context(path: String) -> Result<QueryContext, String> {
-    let state = get_global_state();
+    let state = context_state();
     let manager = state.get_manager().await?;
     manager.get_context(&path).await.map_err(|e| e.to_string())
 }
 
+/// An indexed file's content, symbols and imports, read back from disk and
+/// the symbol index if the cache let it go.
+#[tauri::command]
+pub async fn get_cached_file_context(path: String) -> Result<FileContext, String> {
+    let state = context_state();
+    let manager = state.get_manager().await?;
+    manager
+        .file_context(&path)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("{} is not in context", path))
+}
+
 #[tauri::command]
 pub async fn is_file_in_context(path: String) -> Result<bool, String> {
-    let state = get_global_state();
+    let state = context_state();
     let manager = state.get_manager().await?;
     manager.has_file(&path).await.map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub async fn get_context_stats() -> Result<ContextStats, String> {
-    let state = get_global_state();
+    let state = context_state();
     let manager = state.get_manager().await?;
     manager.get_stats().await.map_err(|e| e.to_string())
 }
@@ -1,8 +1,8 @@
 // src/commands/context_manager.rs
 
 use ::arrow::array::{
-    self, Array, FixedSizeListArray, Float32Array, Int32Array, RecordBatch, RecordBatchIterator,
-    StringArray,
+    self, Array, BinaryArray, FixedSizeListArray, Float32Array, Int32Array, Int64Array,
+    RecordBatch, RecordBatchIterator, StringArray,
 };
 use ::arrow::datatypes::DataType;
 use ::arrow::error::ArrowError;
@@ -10,26 +10,50 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use futures::TryStreamExt;
 use lancedb::arrow::arrow_schema::Schema;
+use lancedb::arrow::SendableRecordBatchStream;
 use lancedb::index::vector::IvfPqIndexBuilder;
 use lancedb::index::{Index, IndexConfig};
 use once_cell::sync::OnceCell;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::num::NonZeroUsize;
-use std::path::{Path, PathBuf};
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use lancedb::query::ExecutableQuery;
+use lancedb::query::{ExecutableQuery, QueryBase, Select};
+use lancedb::table::OptimizeAction;
 use lancedb::{arrow, connect, table::Table, Connection};
-use lru::LruCache;
 use parking_lot::Mutex;
 use pyo3::prelude::*; // For Python embedding calls
 
+use super::chat_memory;
+use super::compression;
+use super::docs;
+use super::file_cache::FileCache;
+use super::ingest::{self, IngestQueue};
+use super::lsp_symbols::{self, LspSymbol};
+use super::summaries;
+use super::symbol_index;
+use crate::commands::fs::resolve_workspace_path;
+use crate::commands::git::{blame_lines, open_repository, GitBlameHunk};
+use crate::secrets;
+use crate::telemetry;
+
 // Constants for the embedding size
-const EMBEDDING_DIM: i32 = 1024; // Adjust as per your model
+pub(crate) const EMBEDDING_DIM: i32 = 1024; // Adjust as per your model
+pub(crate) const VECTOR_INDEX_PARTITIONS: u32 = 64;
+pub(crate) const VECTOR_INDEX_SUB_VECTORS: u32 = 16;
+const DEFAULT_CACHE_BYTES: usize = 64 * 1024 * 1024;
+/// The LanceDB directory, inside the configured `db_path`.
+const INDEX_DIR_NAME: &str = "context.lancedb";
+/// Table `time_scratch_table` writes to, next to the real ones.
+const SCRATCH_TABLE: &str = "benchmark_chunks";
+const SCRATCH_SEARCH_LIMIT: usize = 10;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeLocation {
     pub file: String,
     pub start_line: usize,
@@ -43,17 +67,25 @@ pub struct ContextStats {
     pub totalFiles: usize,
     pub activeFiles: usize,
     pub totalSize: usize, // in bytes
+    /// Estimated bytes held by the file cache.
+    pub cacheBytes: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CodeSymbol {
     pub name: String,
     pub kind: SymbolKind,
     pub location: CodeLocation,
     pub related_symbols: Vec<String>,
+    /// Enclosing symbol (e.g. the impl or class), when known.
+    #[serde(default)]
+    pub container_name: Option<String>,
+    /// Doc comment or docstring, when the symbol has one.
+    #[serde(default)]
+    pub docs: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileContext {
     pub content: String,
     pub symbols: Vec<CodeSymbol>,
@@ -68,6 +100,8 @@ pub struct ContextConfig {
     pub watch_files: Option<bool>,
     pub chunk_size: Option<usize>,
     pub min_chunk_overlap: Option<usize>,
+    /// Budget for cached file contents; 64 MiB by default.
+    pub max_cache_bytes: Option<usize>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -86,13 +120,18 @@ pub struct QueryMetadata {
     pub total_chunks_searched: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SymbolKind {
     File,
+    Module,
     Class,
+    Struct,
+    Enum,
     Interface,
     Function,
     Method,
+    Property,
+    Constant,
     Variable,
     Import,
 }
@@ -104,6 +143,51 @@ pub struct ChunkInfo {
     pub end_line: usize,
     pub file_path: String,
     pub symbol_kind: Option<SymbolKind>,
+    /// Symbol the chunk belongs to, when a language server reported one.
+    #[serde(default)]
+    pub symbol_name: Option<String>,
+    #[serde(default)]
+    pub container_name: Option<String>,
+    /// Full extent of `symbol_name` (0-based, inclusive), which may reach
+    /// beyond this chunk.
+    #[serde(default)]
+    pub symbol_start_line: Option<usize>,
+    #[serde(default)]
+    pub symbol_end_line: Option<usize>,
+    /// Most recent commit touching any line of the chunk, from git blame.
+    #[serde(default)]
+    pub last_commit: Option<String>,
+    #[serde(default)]
+    pub last_author: Option<String>,
+    /// Unix timestamp (seconds) of `last_commit`.
+    #[serde(default)]
+    pub last_modified: Option<i64>,
+    /// Doc comment or docstring of `symbol_name`.
+    #[serde(default)]
+    pub docs: Option<String>,
+    /// One-line description written by a model, when chunk summaries are on.
+    #[serde(default)]
+    pub summary: Option<String>,
+}
+
+/// A chunk with its embedding, for comparing chunks with each other.
+#[derive(Debug, Clone)]
+pub struct EmbeddedChunk {
+    pub file_path: String,
+    /// 0-based, end-exclusive, as in `ChunkInfo`.
+    pub start_line: usize,
+    pub end_line: usize,
+    pub content: String,
+    pub embedding: Vec<f32>,
+}
+
+/// A chunk's identity and the shape of its embedding, for checking the
+/// table's health.
+#[derive(Debug, Clone)]
+pub struct ChunkRecord {
+    pub id: String,
+    pub file_path: String,
+    pub dimension: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -113,12 +197,284 @@ pub struct FileMetadata {
     pub last_updated: i64,
 }
 
+/// Arrow schema of the chunk table. Keep in sync with the columns written in `add_file`.
+fn chunk_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        arrow::arrow_schema::Field::new("id", DataType::Utf8, false),
+        arrow::arrow_schema::Field::new("file_path", DataType::Utf8, false),
+        // Chunk text, zstd-compressed; see `compression`
+        arrow::arrow_schema::Field::new("compressed_content", DataType::Binary, false),
+        arrow::arrow_schema::Field::new(
+            "embedding",
+            DataType::FixedSizeList(
+                Arc::new(arrow::arrow_schema::Field::new(
+                    "item",
+                    DataType::Float32,
+                    false,
+                )),
+                EMBEDDING_DIM,
+            ),
+            false,
+        ),
+        arrow::arrow_schema::Field::new("start_line", DataType::Int32, false),
+        arrow::arrow_schema::Field::new("end_line", DataType::Int32, false),
+        arrow::arrow_schema::Field::new("symbol_kind", DataType::Utf8, true),
+        arrow::arrow_schema::Field::new("symbol_name", DataType::Utf8, true),
+        arrow::arrow_schema::Field::new("container_name", DataType::Utf8, true),
+        arrow::arrow_schema::Field::new("symbol_start_line", DataType::Int32, true),
+        arrow::arrow_schema::Field::new("symbol_end_line", DataType::Int32, true),
+        arrow::arrow_schema::Field::new("last_commit", DataType::Utf8, true),
+        arrow::arrow_schema::Field::new("last_author", DataType::Utf8, true),
+        arrow::arrow_schema::Field::new("last_modified", DataType::Int64, true),
+        arrow::arrow_schema::Field::new("docs", DataType::Utf8, true),
+        arrow::arrow_schema::Field::new("summary", DataType::Utf8, true),
+    ]))
+}
+
+/// One row per chunk in the layout of `chunk_schema`, with its embedding.
+fn chunk_batch(
+    schema: Arc<Schema>,
+    chunks: &[ChunkInfo],
+    embeddings: &[Vec<f32>],
+) -> Result<RecordBatch> {
+    // Build up a vector of arrays (one row per chunk)
+    let mut ids = Vec::new();
+    let mut file_paths = Vec::new();
+    let mut contents = Vec::new();
+    let mut embedding_arrays = Vec::new();
+    let mut start_lines = Vec::new();
+    let mut end_lines = Vec::new();
+    let mut symbol_kinds = Vec::new();
+    let mut symbol_names = Vec::new();
+    let mut container_names = Vec::new();
+    let mut symbol_start_lines = Vec::new();
+    let mut symbol_end_lines = Vec::new();
+    let mut last_commits = Vec::new();
+    let mut last_authors = Vec::new();
+    let mut last_modifieds = Vec::new();
+    let mut docs = Vec::new();
+    let mut summaries = Vec::new();
+
+    for (chunk, emb) in chunks.iter().zip(embeddings.iter()) {
+        ids.push(Uuid::new_v4().to_string());
+        file_paths.push(chunk.file_path.clone());
+        contents.push(compression::compress(&chunk.content)?);
+        start_lines.push(chunk.start_line as i32);
+        end_lines.push(chunk.end_line as i32);
+        // SymbolKind as a string or None
+        let sk_str = chunk
+            .symbol_kind
+            .as_ref()
+            .map(|k| format!("{:?}", k))
+            .unwrap_or_default();
+        symbol_kinds.push(sk_str);
+        symbol_names.push(chunk.symbol_name.clone());
+        container_names.push(chunk.container_name.clone());
+        symbol_start_lines.push(chunk.symbol_start_line.map(|l| l as i32));
+        symbol_end_lines.push(chunk.symbol_end_line.map(|l| l as i32));
+        last_commits.push(chunk.last_commit.clone());
+        last_authors.push(chunk.last_author.clone());
+        last_modifieds.push(chunk.last_modified);
+        docs.push(chunk.docs.clone());
+        summaries.push(chunk.summary.clone());
+        embedding_arrays.push(emb.clone()); // store the Vec<f32>
+    }
+
+    // Now convert them to Arrow arrays
+    let id_array = Arc::new(StringArray::from(ids)) as Arc<dyn Array>;
+    let path_array = Arc::new(StringArray::from(file_paths)) as Arc<dyn Array>;
+    let content_array = Arc::new(BinaryArray::from_iter_values(contents)) as Arc<dyn Array>;
+    let symbol_kind_array = Arc::new(StringArray::from(symbol_kinds)) as Arc<dyn Array>;
+    let start_line_array = Arc::new(Int32Array::from(start_lines)) as Arc<dyn Array>;
+    let end_line_array = Arc::new(Int32Array::from(end_lines)) as Arc<dyn Array>;
+    let symbol_name_array = Arc::new(StringArray::from(symbol_names)) as Arc<dyn Array>;
+    let container_name_array = Arc::new(StringArray::from(container_names)) as Arc<dyn Array>;
+    let symbol_start_line_array = Arc::new(Int32Array::from(symbol_start_lines)) as Arc<dyn Array>;
+    let symbol_end_line_array = Arc::new(Int32Array::from(symbol_end_lines)) as Arc<dyn Array>;
+    let last_commit_array = Arc::new(StringArray::from(last_commits)) as Arc<dyn Array>;
+    let last_author_array = Arc::new(StringArray::from(last_authors)) as Arc<dyn Array>;
+    let last_modified_array = Arc::new(Int64Array::from(last_modifieds)) as Arc<dyn Array>;
+    let docs_array = Arc::new(StringArray::from(docs)) as Arc<dyn Array>;
+    let summary_array = Arc::new(StringArray::from(summaries)) as Arc<dyn Array>;
+
+    let item_field = Arc::new(arrow::arrow_schema::Field::new(
+        "item",
+        DataType::Float32,
+        false,
+    ));
+
+    // For embeddings, build a Float32Array for each row, then wrap in FixedSizeList
+    // Flatten all embeddings into one big Float32Array:
+    let flat_embeddings: Vec<f32> = embedding_arrays.into_iter().flatten().collect();
+    let float32_arr: Arc<dyn Array> = Arc::new(Float32Array::from(flat_embeddings.clone()));
+
+    // Each embedding is EMBEDDING_DIM in length, so total length = num_rows * EMBEDDING_DIM
+    let embedding_list_array = Arc::new(FixedSizeListArray::try_new(
+        item_field.clone(),  // Arc<Field> with a descriptive name
+        EMBEDDING_DIM,       // list size
+        float32_arr.clone(), // values array
+        None,                // Option<NullBuffer>
+    )?) as Arc<dyn Array>;
+
+    assert_eq!(
+        flat_embeddings.len(),
+        (start_line_array.len() as usize) * (EMBEDDING_DIM as usize),
+        "Mismatch between number of embeddings and embedding dimensions"
+    );
+
+    // We need to ensure the table's schema matches the order of fields we specified above
+    Ok(RecordBatch::try_new(
+        schema,
+        vec![
+            id_array,
+            path_array,
+            content_array,
+            embedding_list_array,
+            start_line_array,
+            end_line_array,
+            symbol_kind_array,
+            symbol_name_array,
+            container_name_array,
+            symbol_start_line_array,
+            symbol_end_line_array,
+            last_commit_array,
+            last_author_array,
+            last_modified_array,
+            docs_array,
+            summary_array,
+        ],
+    )?)
+}
+
+/// Whether `actual` contains every column of `expected`.
+pub(super) fn has_columns(actual: &Schema, expected: &Schema) -> bool {
+    expected
+        .fields()
+        .iter()
+        .all(|field| actual.field_with_name(field.name()).is_ok())
+}
+
+/// The decompressed chunk text in a batch of chunk rows.
+fn chunk_contents(batch: &RecordBatch) -> Result<Vec<String>> {
+    let column = batch
+        .column_by_name("compressed_content")
+        .and_then(|c| c.as_any().downcast_ref::<BinaryArray>())
+        .ok_or_else(|| anyhow::anyhow!("compressed_content column not found in record batch"))?;
+    (0..column.len())
+        .map(|i| Ok(compression::decompress(column.value(i))?))
+        .collect()
+}
+
+/// Reads the chunks in a query's results with their embeddings, and the
+/// distance to the query vector when it was a vector search.
+async fn read_embedded_chunks(
+    mut stream: SendableRecordBatchStream,
+) -> Result<Vec<(EmbeddedChunk, f32)>> {
+    let mut chunks = Vec::new();
+    while let Some(batch) = stream.try_next().await? {
+        let strings = |name: &str| {
+            batch
+                .column_by_name(name)
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                .ok_or_else(|| anyhow::anyhow!("{} column not found in record batch", name))
+        };
+        let lines = |name: &str| {
+            batch
+                .column_by_name(name)
+                .and_then(|c| c.as_any().downcast_ref::<Int32Array>())
+                .ok_or_else(|| anyhow::anyhow!("{} column not found in record batch", name))
+        };
+        let file_path = strings("file_path")?;
+        let mut content = chunk_contents(&batch)?;
+        let start_line = lines("start_line")?;
+        let end_line = lines("end_line")?;
+        let embedding = batch
+            .column_by_name("embedding")
+            .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>())
+            .ok_or_else(|| anyhow::anyhow!("embedding column not found in record batch"))?;
+        let distance = batch
+            .column_by_name("_distance")
+            .and_then(|c| c.as_any().downcast_ref::<Float32Array>());
+
+        for i in 0..batch.num_rows() {
+            let values = embedding.value(i);
+            let Some(values) = values.as_any().downcast_ref::<Float32Array>() else {
+                continue;
+            };
+            let chunk = EmbeddedChunk {
+                file_path: file_path.value(i).to_string(),
+                start_line: start_line.value(i) as usize,
+                end_line: end_line.value(i) as usize,
+                content: std::mem::take(&mut content[i]),
+                embedding: values.values().to_vec(),
+            };
+            chunks.push((chunk, distance.map_or(0.0, |d| d.value(i))));
+        }
+    }
+
+    Ok(chunks)
+}
+
+/// Finds, for each chunk, the most recent blame hunk overlapping its lines.
+/// Files outside a git repository simply get no attribution.
+async fn attribute_chunks(path: &str, chunks: &[ChunkInfo]) -> Vec<Option<GitBlameHunk>> {
+    let path = path.to_string();
+    // Chunk ranges are 0-based and end-exclusive; blame lines are 1-based
+    let ranges: Vec<(usize, usize)> = chunks
+        .iter()
+        .map(|c| (c.start_line + 1, c.end_line))
+        .collect();
+    let count = ranges.len();
+
+    tokio::task::spawn_blocking(move || {
+        let hunks = open_repository()
+            .and_then(|repo| blame_lines(&repo, &path, None, None))
+            .unwrap_or_default();
+        ranges
+            .iter()
+            .map(|(start, end)| {
+                hunks
+                    .iter()
+                    .filter(|h| h.start_line <= *end && h.end_line >= *start)
+                    .max_by_key(|h| h.time)
+                    .cloned()
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_else(|_| vec![None; count])
+}
+
+/// Picks the symbol covering most of the chunk, preferring the narrower one
+/// on ties so a method wins over its enclosing impl or class.
+fn dominant_symbol<'a>(symbols: &'a [LspSymbol], chunk: &ChunkInfo) -> Option<&'a LspSymbol> {
+    symbols
+        .iter()
+        .filter_map(|symbol| {
+            let start = symbol.start_line.max(chunk.start_line);
+            let end = (symbol.end_line + 1).min(chunk.end_line);
+            (end > start).then(|| (symbol, end - start))
+        })
+        .max_by(|(a, a_overlap), (b, b_overlap)| {
+            a_overlap.cmp(b_overlap).then_with(|| {
+                let a_span = a.end_line - a.start_line;
+                let b_span = b.end_line - b.start_line;
+                b_span.cmp(&a_span)
+            })
+        })
+        .map(|(symbol, _)| symbol)
+}
+
 /// Main context manager implementation using LanceDB for vector storage
 pub struct SmartContextManager {
     db: Connection, // The LanceDB connection
     table: Table,   // The table storing code chunks
-    file_cache: Arc<Mutex<LruCache<String, FileContext>>>,
+    /// Past conversation turns, for `chat_memory`.
+    memory: Table,
+    file_cache: Arc<Mutex<FileCache>>,
     base_path: PathBuf,
+    /// Every write to `table` goes through here.
+    ingest: IngestQueue,
 }
 
 impl SmartContextManager {
@@ -126,13 +482,14 @@ impl SmartContextManager {
         // Clear the cache
         self.file_cache.lock().clear();
         // Any other cleanup needed for LanceDB connections
+        lsp_symbols::shutdown_servers().await;
         Ok(())
     }
 
     /// Create a new instance of the manager with given config.
     pub async fn new(config: ContextConfig) -> Result<Self> {
         // 1) Build a path for the LanceDB directory.
-        let uri = format!("{}/context.lancedb", config.db_path.to_string_lossy());
+        let uri = format!("{}/{}", config.db_path.to_string_lossy(), INDEX_DIR_NAME);
         let uri_str = uri.as_str();
 
         // 2) Connect to the LanceDB database (creates if not exists)
@@ -142,45 +499,38 @@ impl SmartContextManager {
         let table_name = "context_chunks";
 
         // 4) Define an Arrow schema for storing your data
-        let schema = Arc::new(Schema::new(vec![
-            arrow::arrow_schema::Field::new("id", DataType::Utf8, false),
-            arrow::arrow_schema::Field::new("file_path", DataType::Utf8, false),
-            arrow::arrow_schema::Field::new("content", DataType::Utf8, false),
-            arrow::arrow_schema::Field::new(
-                "embedding",
-                DataType::FixedSizeList(
-                    Arc::new(arrow::arrow_schema::Field::new(
-                        "item",
-                        DataType::Float32,
-                        false,
-                    )),
-                    EMBEDDING_DIM,
-                ),
-                false,
-            ),
-            arrow::arrow_schema::Field::new("start_line", DataType::Int32, false),
-            arrow::arrow_schema::Field::new("end_line", DataType::Int32, false),
-            arrow::arrow_schema::Field::new("symbol_kind", DataType::Utf8, true),
-        ]));
+        let schema = chunk_schema();
 
-        // 5) Try to open existing table first, create if it doesn't exist
+        // 5) Try to open existing table first, create if it doesn't exist.
+        // Tables written with an older column layout are rebuilt; the index
+        // is derived data and files get re-added as they are opened.
         let table = match db.open_table(table_name).execute().await {
-            Ok(table) => {
-                println!("Successfully opened existing table '{}'", table_name);
+            Ok(table) if has_columns(&table.schema().await?, &schema) => {
+                info!("Successfully opened existing table '{}'", table_name);
                 table
             }
+            Ok(_) => {
+                warn!("Schema of table '{}' is outdated, rebuilding", table_name);
+                db.drop_table(table_name).await?;
+                db.create_empty_table(table_name, schema).execute().await?
+            }
             Err(_) => {
-                println!("Creating new table '{}'", table_name);
+                info!("Creating new table '{}'", table_name);
                 db.create_empty_table(table_name, schema).execute().await?
             }
         };
 
+        let memory = chat_memory::open_table(&db).await?;
+
         // 6) Build up the manager
         Ok(Self {
             db,
+            ingest: IngestQueue::start(table.clone()),
             table,
-            file_cache: Arc::new(Mutex::new(LruCache::new(
-                NonZeroUsize::new(config.max_files).unwrap(),
+            memory,
+            file_cache: Arc::new(Mutex::new(FileCache::new(
+                config.max_files,
+                config.max_cache_bytes.unwrap_or(DEFAULT_CACHE_BYTES),
             ))),
             base_path: config.db_path.into(),
         })
@@ -193,89 +543,44 @@ impl SmartContextManager {
             return Err(anyhow::anyhow!("File {} is already in context", path));
         }
 
-        // Parse file into chunks and symbols
-        let (chunks, symbols) = self.process_file(path, content)?;
+        // Parse file into chunks and symbols, preferring the language server's view
+        let lsp_symbols = lsp_symbols::document_symbols(path, content).await;
+        let (mut chunks, symbols) = self.process_file(path, content, lsp_symbols)?;
+        symbol_index::update_file(path, &symbols);
 
-        // Generate embeddings for chunks
-        let embeddings = self.generate_embeddings_for_chunks(&chunks).await?;
-
-        // Build up a vector of arrays (one row per chunk)
-        let mut ids = Vec::new();
-        let mut file_paths = Vec::new();
-        let mut contents = Vec::new();
-        let mut embedding_arrays = Vec::new();
-        let mut start_lines = Vec::new();
-        let mut end_lines = Vec::new();
-        let mut symbol_kinds = Vec::new();
-
-        for (chunk, emb) in chunks.iter().zip(embeddings.iter()) {
-            ids.push(Uuid::new_v4().to_string());
-            file_paths.push(chunk.file_path.clone());
-            contents.push(chunk.content.clone());
-            start_lines.push(chunk.start_line as i32);
-            end_lines.push(chunk.end_line as i32);
-            // SymbolKind as a string or None
-            let sk_str = chunk
-                .symbol_kind
-                .as_ref()
-                .map(|k| format!("{:?}", k))
-                .unwrap_or_default();
-            symbol_kinds.push(sk_str);
-            embedding_arrays.push(emb.clone()); // store the Vec<f32>
+        // Secrets never reach the embedding model, the index or prompts built from it
+        for chunk in chunks.iter_mut() {
+            chunk.content = secrets::redact(&chunk.content);
         }
 
-        // Now convert them to Arrow arrays
-        let id_array = Arc::new(StringArray::from(ids)) as Arc<dyn Array>;
-        let path_array = Arc::new(StringArray::from(file_paths)) as Arc<dyn Array>;
-        let content_array = Arc::new(StringArray::from(contents)) as Arc<dyn Array>;
-        let symbol_kind_array = Arc::new(StringArray::from(symbol_kinds)) as Arc<dyn Array>;
-        let start_line_array = Arc::new(Int32Array::from(start_lines)) as Arc<dyn Array>;
-        let end_line_array = Arc::new(Int32Array::from(end_lines)) as Arc<dyn Array>;
-
-        let item_field = Arc::new(arrow::arrow_schema::Field::new(
-            "item",
-            DataType::Float32,
-            false,
-        ));
-
-        // For embeddings, build a Float32Array for each row, then wrap in FixedSizeList
-        // Flatten all embeddings into one big Float32Array:
-        let flat_embeddings: Vec<f32> = embedding_arrays.into_iter().flatten().collect();
-        let float32_arr: Arc<dyn Array> = Arc::new(Float32Array::from(flat_embeddings.clone()));
-
-        // Each embedding is EMBEDDING_DIM in length, so total length = num_rows * EMBEDDING_DIM
-        let embedding_list_array = Arc::new(FixedSizeListArray::try_new(
-            item_field.clone(),  // Arc<Field> with a descriptive name
-            EMBEDDING_DIM,       // list size
-            float32_arr.clone(), // values array
-            None,                // Option<NullBuffer>
-        )?) as Arc<dyn Array>;
-
-        assert_eq!(
-            flat_embeddings.len(),
-            (start_line_array.len() as usize) * (EMBEDDING_DIM as usize),
-            "Mismatch between number of embeddings and embedding dimensions"
-        );
+        // Record who last touched each chunk
+        for (chunk, blame) in chunks.iter_mut().zip(attribute_chunks(path, &chunks).await) {
+            if let Some(blame) = blame {
+                chunk.last_commit = Some(blame.commit_id);
+                chunk.last_author = Some(blame.author_name);
+                chunk.last_modified = Some(blame.time);
+            }
+        }
+
+        // Describe each chunk in a line, if turned on, for high-level queries
+        let summaries = {
+            let _timer = telemetry::stage("summaries");
+            summaries::summarize_chunks(path, &chunks).await
+        };
+        for (chunk, summary) in chunks.iter_mut().zip(summaries) {
+            chunk.summary = summary;
+        }
+
+        // Generate embeddings for chunks
+        let embeddings = {
+            let _timer = telemetry::stage("embeddings");
+            self.generate_embeddings_for_chunks(&chunks).await?
+        };
+
+        let batch = chunk_batch(self.table.schema().await?, &chunks, &embeddings)?;
 
-        // We need to ensure the table's schema matches the order of fields we specified above
-        let batch = RecordBatch::try_new(
-            self.table.schema().await?.clone(),
-            vec![
-                id_array,
-                path_array,
-                content_array,
-                embedding_list_array,
-                start_line_array,
-                end_line_array,
-                symbol_kind_array,
-            ],
-        )?;
-
-        let iter_batch =
-            RecordBatchIterator::new(vec![Ok(batch)].into_iter(), self.table.schema().await?);
-
-        // Insert the record batch into LanceDB
-        self.table.add(iter_batch);
+        // Insert the record batch into LanceDB, after any writes queued before it
+        self.ingest.add(path, batch).await?;
 
         // Cache the file context
         let file_context = FileContext {
@@ -295,65 +600,162 @@ impl SmartContextManager {
     }
 
     pub async fn has_file(&self, path: &str) -> Result<bool> {
-        // Implement a query to check if the file exists
-        let mut stream = self.table.query().execute().await?;
+        ingest::contains(&self.table, path).await
+    }
 
-        while let Some(_batch) = stream.try_next().await? {
-            // If any batch returns, the file exists
-            return Ok(true);
+    /// Removes a file's chunks from the table, the cache and the symbol
+    /// index, returning how many chunks it had.
+    pub async fn remove_file(&self, path: &str) -> Result<usize> {
+        let chunks = self.ingest.delete(path).await?;
+        self.file_cache.lock().pop(path);
+        if let Err(e) = symbol_index::remove_file(path) {
+            warn!("Failed to remove {} from the symbol index: {}", path, e);
         }
+        Ok(chunks)
+    }
+
+    /// Paths of the files with chunks in the table, each once.
+    pub async fn indexed_files(&self) -> Result<Vec<String>> {
+        let mut files = HashSet::new();
+        let mut stream = self
+            .table
+            .query()
+            .select(Select::Columns(vec!["file_path".to_string()]))
+            .execute()
+            .await?;
 
-        Ok(false)
+        while let Some(batch) = stream.try_next().await? {
+            let file_path = batch
+                .column_by_name("file_path")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                .ok_or_else(|| anyhow::anyhow!("file_path column not found in record batch"))?;
+            for i in 0..batch.num_rows() {
+                files.insert(file_path.value(i).to_string());
+            }
+        }
+
+        let mut files: Vec<String> = files.into_iter().collect();
+        files.sort();
+        Ok(files)
     }
 
-    /// Search for semantically similar code chunks
-    pub async fn search_similar(&self, query: &str, limit: usize) -> Result<Vec<ChunkInfo>> {
-        // Generate embedding for query using BGE (Python)
-        let query_embedding: Vec<f32> = self.generate_embedding(query).await?;
+    /// Every chunk's id, file and embedding dimension.
+    pub async fn chunk_records(&self) -> Result<Vec<ChunkRecord>> {
+        let mut records = Vec::new();
+        let mut stream = self
+            .table
+            .query()
+            .select(Select::Columns(vec![
+                "id".to_string(),
+                "file_path".to_string(),
+                "embedding".to_string(),
+            ]))
+            .execute()
+            .await?;
 
-        // Record search start time for metrics
-        let start_time = std::time::Instant::now();
+        while let Some(batch) = stream.try_next().await? {
+            let strings = |name: &str| {
+                batch
+                    .column_by_name(name)
+                    .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+                    .ok_or_else(|| anyhow::anyhow!("{} column not found in record batch", name))
+            };
+            let id = strings("id")?;
+            let file_path = strings("file_path")?;
+            let embedding = batch
+                .column_by_name("embedding")
+                .and_then(|c| c.as_any().downcast_ref::<FixedSizeListArray>())
+                .ok_or_else(|| anyhow::anyhow!("embedding column not found in record batch"))?;
+
+            for i in 0..batch.num_rows() {
+                records.push(ChunkRecord {
+                    id: id.value(i).to_string(),
+                    file_path: file_path.value(i).to_string(),
+                    dimension: if embedding.is_null(i) {
+                        0
+                    } else {
+                        embedding.value(i).len()
+                    },
+                });
+            }
+        }
 
-        // Check if index exists and create if needed
+        Ok(records)
+    }
+
+    /// Creates the embedding index if the table doesn't have one yet, and
+    /// returns whether it did.
+    pub async fn ensure_vector_index(&self) -> Result<bool> {
         let indices = self.table.list_indices().await?;
-        if !indices
+        if indices
             .iter()
             .any(|idx| idx.columns.contains(&"embedding".to_string()))
         {
-            self.table
-                .create_index(
-                    &["embedding"],
-                    Index::IvfPq(
-                        IvfPqIndexBuilder::default()
-                            .distance_type(lancedb::DistanceType::Cosine)
-                            .num_partitions(64)
-                            .num_sub_vectors(16),
-                    ),
-                )
-                .execute()
-                .await?;
+            return Ok(false);
         }
+        self.table
+            .create_index(
+                &["embedding"],
+                Index::IvfPq(
+                    IvfPqIndexBuilder::default()
+                        .distance_type(lancedb::DistanceType::Cosine)
+                        .num_partitions(VECTOR_INDEX_PARTITIONS)
+                        .num_sub_vectors(VECTOR_INDEX_SUB_VECTORS),
+                ),
+            )
+            .execute()
+            .await?;
+        Ok(true)
+    }
+
+    /// The `limit` chunks nearest to `embedding` as the vector index finds
+    /// them, nearest first, with the distance it reports.
+    pub async fn nearest_chunks(
+        &self,
+        embedding: &[f32],
+        limit: usize,
+    ) -> Result<Vec<(EmbeddedChunk, f32)>> {
+        let stream = self
+            .table
+            .vector_search(embedding.to_vec())?
+            .limit(limit)
+            .execute()
+            .await?;
+        read_embedded_chunks(stream).await
+    }
+
+    /// Search for semantically similar code chunks
+    pub async fn search_similar(&self, query: &str, limit: usize) -> Result<Vec<ChunkInfo>> {
+        // Generate embedding for query using BGE (Python)
+        let query_embedding: Vec<f32> = {
+            let _timer = telemetry::stage("embeddings");
+            self.generate_embedding(query).await?
+        };
+
+        // Record search start time for metrics
+        let start_time = std::time::Instant::now();
+        let _search_timer = telemetry::stage("vector_search");
+
+        self.ensure_vector_index().await?;
 
         // Perform vector search
-        let plan = self.table.vector_search(query_embedding.clone());
+        let plan = self
+            .table
+            .vector_search(query_embedding.clone())?
+            .limit(limit);
 
         // Log search latency
-        println!(
+        debug!(
             "Vector search completed in {:?}ms",
             start_time.elapsed().as_millis()
         );
 
         let mut chunks = Vec::new();
-        let copy = plan?.clone();
+        let mut stream = plan.execute().await?;
         // Process results from the stream
-        while let Some(batch) = copy.execute().await?.try_next().await? {
+        while let Some(batch) = stream.try_next().await? {
             // Extract columns from the batch
-            let content = batch
-                .column_by_name("content")
-                .expect("content column not found in record batch")
-                .as_any()
-                .downcast_ref::<StringArray>()
-                .unwrap();
+            let mut content = chunk_contents(&batch)?;
 
             let file_path = batch
                 .column_by_name("file_path")
@@ -383,30 +785,89 @@ impl SmartContextManager {
                 .downcast_ref::<StringArray>()
                 .unwrap();
 
+            let symbol_name = batch
+                .column_by_name("symbol_name")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+            let container_name = batch
+                .column_by_name("container_name")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+            let symbol_start_line = batch
+                .column_by_name("symbol_start_line")
+                .and_then(|c| c.as_any().downcast_ref::<Int32Array>());
+            let symbol_end_line = batch
+                .column_by_name("symbol_end_line")
+                .and_then(|c| c.as_any().downcast_ref::<Int32Array>());
+            let last_commit = batch
+                .column_by_name("last_commit")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+            let last_author = batch
+                .column_by_name("last_author")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+            let last_modified = batch
+                .column_by_name("last_modified")
+                .and_then(|c| c.as_any().downcast_ref::<Int64Array>());
+            let docs = batch
+                .column_by_name("docs")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+            let summary = batch
+                .column_by_name("summary")
+                .and_then(|c| c.as_any().downcast_ref::<StringArray>());
+
             // Process each row in the batch
             for i in 0..batch.num_rows() {
                 chunks.push(ChunkInfo {
-                    content: content.value(i).to_string(),
+                    content: std::mem::take(&mut content[i]),
                     file_path: file_path.value(i).to_string(),
                     start_line: start_line.value(i) as usize,
                     end_line: end_line.value(i) as usize,
                     symbol_kind: if symbol_kind.is_valid(i) {
                         match symbol_kind.value(i).to_lowercase().as_str() {
                             "file" => Some(SymbolKind::File),
+                            "module" | "mod" => Some(SymbolKind::Module),
                             "class" => Some(SymbolKind::Class),
+                            "struct" => Some(SymbolKind::Struct),
+                            "enum" => Some(SymbolKind::Enum),
                             "interface" => Some(SymbolKind::Interface),
                             "function" | "fn" => Some(SymbolKind::Function),
                             "method" => Some(SymbolKind::Method),
+                            "property" | "field" => Some(SymbolKind::Property),
+                            "constant" | "const" => Some(SymbolKind::Constant),
                             "variable" | "var" => Some(SymbolKind::Variable),
                             "import" | "use" => Some(SymbolKind::Import),
+                            "" => None,
                             _ => {
-                                println!("Unknown symbol kind: {}", symbol_kind.value(i));
+                                warn!("Unknown symbol kind: {}", symbol_kind.value(i));
                                 None
                             }
                         }
                     } else {
                         None
                     },
+                    symbol_name: symbol_name
+                        .filter(|c| c.is_valid(i))
+                        .map(|c| c.value(i).to_string()),
+                    container_name: container_name
+                        .filter(|c| c.is_valid(i))
+                        .map(|c| c.value(i).to_string()),
+                    symbol_start_line: symbol_start_line
+                        .filter(|c| c.is_valid(i))
+                        .map(|c| c.value(i) as usize),
+                    symbol_end_line: symbol_end_line
+                        .filter(|c| c.is_valid(i))
+                        .map(|c| c.value(i) as usize),
+                    last_commit: last_commit
+                        .filter(|c| c.is_valid(i))
+                        .map(|c| c.value(i).to_string()),
+                    last_author: last_author
+                        .filter(|c| c.is_valid(i))
+                        .map(|c| c.value(i).to_string()),
+                    last_modified: last_modified.filter(|c| c.is_valid(i)).map(|c| c.value(i)),
+                    docs: docs
+                        .filter(|c| c.is_valid(i))
+                        .map(|c| c.value(i).to_string()),
+                    summary: summary
+                        .filter(|c| c.is_valid(i))
+                        .map(|c| c.value(i).to_string()),
                 });
             }
         }
@@ -414,10 +875,16 @@ impl SmartContextManager {
         Ok(chunks)
     }
 
-    /// Process a file into chunks and extract symbols
-    fn process_file(&self, path: &str, content: &str) -> Result<(Vec<ChunkInfo>, Vec<CodeSymbol>)> {
+    /// Process a file into chunks and extract symbols. Symbols reported by a
+    /// language server are used when available; otherwise a few regexes
+    /// provide a rough approximation.
+    fn process_file(
+        &self,
+        path: &str,
+        content: &str,
+        lsp_symbols: Option<Vec<LspSymbol>>,
+    ) -> Result<(Vec<ChunkInfo>, Vec<CodeSymbol>)> {
         let mut chunks = Vec::new();
-        let mut symbols = Vec::new();
 
         // Simple chunking logic; can be enhanced based on requirements
         let lines: Vec<&str> = content.lines().collect();
@@ -433,36 +900,98 @@ impl SmartContextManager {
                 end_line,
                 file_path: path.to_string(),
                 symbol_kind: None,
+                symbol_name: None,
+                container_name: None,
+                symbol_start_line: None,
+                symbol_end_line: None,
+                last_commit: None,
+                last_author: None,
+                last_modified: None,
+                docs: None,
+                summary: None,
             });
         }
 
+        let symbols = match lsp_symbols {
+            Some(lsp_symbols) => {
+                let symbol_docs: Vec<Option<String>> = lsp_symbols
+                    .iter()
+                    .map(|symbol| docs::doc_comment(path, &lines, symbol.start_line))
+                    .collect();
+                for chunk in chunks.iter_mut() {
+                    if let Some(symbol) = dominant_symbol(&lsp_symbols, chunk) {
+                        chunk.symbol_kind = Some(symbol.kind.clone());
+                        chunk.symbol_name = Some(symbol.name.clone());
+                        chunk.container_name = symbol.container_name.clone();
+                        chunk.symbol_start_line = Some(symbol.start_line);
+                        chunk.symbol_end_line = Some(symbol.end_line);
+                        chunk.docs = docs::doc_comment(path, &lines, symbol.start_line);
+                    }
+                }
+
+                lsp_symbols
+                    .into_iter()
+                    .zip(symbol_docs)
+                    .map(|(symbol, docs)| CodeSymbol {
+                        name: symbol.name,
+                        kind: symbol.kind,
+                        location: CodeLocation {
+                            file: path.to_string(),
+                            start_line: symbol.start_line,
+                            end_line: symbol.end_line,
+                            start_col: symbol.start_col,
+                            end_col: symbol.end_col,
+                        },
+                        related_symbols: Vec::new(),
+                        container_name: symbol.container_name,
+                        docs,
+                    })
+                    .collect()
+            }
+            None => self.extract_symbols_with_regex(path, content)?,
+        };
+
+        Ok((chunks, symbols))
+    }
+
+    /// Fallback symbol extraction used when no language server is available
+    fn extract_symbols_with_regex(&self, path: &str, content: &str) -> Result<Vec<CodeSymbol>> {
+        let mut symbols = Vec::new();
+        let lines: Vec<&str> = content.lines().collect();
+
         // Basic symbol extraction with Regex
         let patterns = [
             (Regex::new(r"class\s+(\w+)")?, SymbolKind::Class),
             (Regex::new(r"fn\s+(\w+)")?, SymbolKind::Function),
-            (Regex::new(r"struct\s+(\w+)")?, SymbolKind::Class),
+            (Regex::new(r"struct\s+(\w+)")?, SymbolKind::Struct),
             // Add more patterns as needed
         ];
 
         for (re, kind) in patterns.iter() {
             for cap in re.captures_iter(content) {
-                let name = cap[1].to_string();
+                let name = cap.get(1).expect("patterns capture the name");
+                let line_start = content[..name.start()].rfind('\n').map_or(0, |i| i + 1);
+                let line = content[..name.start()].matches('\n').count();
+                let start_col = content[line_start..name.start()].chars().count();
                 symbols.push(CodeSymbol {
-                    name,
+                    name: name.as_str().to_string(),
                     kind: kind.clone(),
+                    // Only the name's position; the regexes don't find where bodies end
                     location: CodeLocation {
                         file: path.to_string(),
-                        start_line: 0, // Can be enhanced to capture actual locations
-                        end_line: 0,
-                        start_col: 0,
-                        end_col: 0,
+                        start_line: line,
+                        end_line: line,
+                        start_col,
+                        end_col: start_col + name.as_str().chars().count(),
                     },
                     related_symbols: Vec::new(),
+                    container_name: None,
+                    docs: docs::doc_comment(path, &lines, line),
                 });
             }
         }
 
-        Ok((chunks, symbols))
+        Ok(symbols)
     }
 
     /// Extract imports from content
@@ -491,8 +1020,19 @@ impl SmartContextManager {
         &self,
         chunks: &[ChunkInfo],
     ) -> Result<Vec<Vec<f32>>> {
-        let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
+        // A summary is embedded along with its code
+        let texts: Vec<String> = chunks
+            .iter()
+            .map(|c| match &c.summary {
+                Some(summary) => format!("{}\n\n{}", summary, c.content),
+                None => c.content.clone(),
+            })
+            .collect();
+        self.generate_embeddings(texts).await
+    }
 
+    /// Generate embeddings for several texts in one call
+    pub async fn generate_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
         Python::with_gil(|py| {
             let embed_module = py.import("bge_embed")?;
             let embed_batch_func = embed_module.getattr("embed_text_batch")?;
@@ -505,8 +1045,19 @@ impl SmartContextManager {
     pub async fn get_context(&self, query: &str) -> Result<QueryContext> {
         let start_time = std::time::Instant::now();
 
-        // Search for similar chunks
-        let chunks = self.search_similar(query, 5).await?;
+        // Search for similar chunks. Usage questions are better answered by
+        // documentation, so for those documented chunks are moved up from a
+        // wider pool.
+        let chunks = if docs::is_usage_query(query) {
+            let mut chunks = self
+                .search_similar(query, 5 * docs::USAGE_CANDIDATE_FACTOR)
+                .await?;
+            docs::boost_documented(&mut chunks, query);
+            chunks.truncate(5);
+            chunks
+        } else {
+            self.search_similar(query, 5).await?
+        };
 
         // Build query metadata
         let metadata = QueryMetadata {
@@ -529,20 +1080,184 @@ impl SmartContextManager {
         })
     }
 
+    /// A file's content, symbols and imports: from the cache, or read back
+    /// from disk and the symbol index if it was evicted. `None` when the file
+    /// isn't indexed.
+    pub async fn file_context(&self, path: &str) -> Result<Option<FileContext>> {
+        if let Some(context) = self.file_cache.lock().get(path) {
+            return Ok(Some(context.clone()));
+        }
+        if !self.has_file(path).await? {
+            return Ok(None);
+        }
+
+        let full_path =
+            resolve_workspace_path(path).map_err(|e| anyhow::anyhow!("{}", e.message()))?;
+        let content = tokio::fs::read_to_string(&full_path).await?;
+        let symbols = symbol_index::symbols_in_file(&[path])
+            .map_err(|e| anyhow::anyhow!(e))?
+            .into_iter()
+            .map(|symbol| CodeSymbol {
+                location: CodeLocation {
+                    file: symbol.file,
+                    start_line: symbol.start_line,
+                    end_line: symbol.end_line,
+                    start_col: symbol.start_col,
+                    end_col: symbol.end_col,
+                },
+                name: symbol.name,
+                kind: symbol.kind,
+                related_symbols: Vec::new(),
+                container_name: symbol.container_name,
+                docs: symbol.docs,
+            })
+            .collect();
+        let context = FileContext {
+            imports: self.extract_imports(&content),
+            content,
+            symbols,
+        };
+        self.file_cache
+            .lock()
+            .put(path.to_string(), context.clone());
+        Ok(Some(context))
+    }
+
     /// Retrieve context statistics
     pub async fn get_stats(&self) -> Result<ContextStats> {
         // Implement logic to calculate stats
         let total_files = self.table.count_rows(None).await? as usize;
-        let active_files = self.file_cache.lock().len();
+        let (active_files, cache_bytes) = {
+            let cache = self.file_cache.lock();
+            (cache.len(), cache.bytes())
+        };
         let total_size = self.calculate_total_size().await?;
 
         Ok(ContextStats {
             totalFiles: total_files,
             activeFiles: active_files,
             totalSize: total_size,
+            cacheBytes: cache_bytes,
         })
     }
 
+    /// Every chunk in the table with its embedding.
+    pub async fn embedded_chunks(&self) -> Result<Vec<EmbeddedChunk>> {
+        let stream = self.table.query().execute().await?;
+        let chunks = read_embedded_chunks(stream).await?;
+        Ok(chunks.into_iter().map(|(chunk, _)| chunk).collect())
+    }
+
+    /// The chunks of one file with their embeddings.
+    pub async fn file_chunks(&self, path: &str) -> Result<Vec<EmbeddedChunk>> {
+        let filter = format!("file_path = '{}'", path.replace('\'', "''"));
+        let stream = self.table.query().only_if(filter).execute().await?;
+        let chunks = read_embedded_chunks(stream).await?;
+        Ok(chunks.into_iter().map(|(chunk, _)| chunk).collect())
+    }
+
+    /// Merges the small data files that many separate writes leave behind
+    /// in the chunk and conversation tables, drops deleted rows and old
+    /// versions, and brings indices up to date with new rows. LanceDB
+    /// reconciles this with writes made meanwhile.
+    pub async fn optimize(&self) -> Result<()> {
+        for table in [&self.table, &self.memory] {
+            table.optimize(OptimizeAction::All).await?;
+        }
+        Ok(())
+    }
+
+    /// Like `optimize`, but also deletes every old version of the tables
+    /// right away rather than after a week, so space freed by removed rows
+    /// shows on disk.
+    pub async fn reclaim_space(&self) -> Result<()> {
+        self.optimize().await?;
+        for table in [&self.table, &self.memory] {
+            table
+                .optimize(OptimizeAction::Prune {
+                    older_than: Some(chrono::Duration::zero()),
+                    delete_unverified: Some(false),
+                    error_if_tagged_old_versions: Some(false),
+                })
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Where the tables are stored.
+    pub(crate) fn index_dir(&self) -> PathBuf {
+        self.base_path.join(INDEX_DIR_NAME)
+    }
+
+    /// Bytes of file contents cached in memory.
+    pub(crate) fn cache_bytes(&self) -> usize {
+        self.file_cache.lock().bytes()
+    }
+
+    /// Caps the file cache at `limit` bytes; see `FileCache::set_limit`.
+    pub(crate) fn limit_cache(&self, limit: Option<usize>) {
+        self.file_cache.lock().set_limit(limit);
+    }
+
+    /// The table of past conversation turns.
+    /// Splits `content` into chunks without indexing it, with symbols from
+    /// the regex fallback.
+    pub(crate) fn chunk_file(&self, path: &str, content: &str) -> Result<Vec<ChunkInfo>> {
+        Ok(self.process_file(path, content, None)?.0)
+    }
+
+    /// Writes `chunks` to a scratch table laid out like the chunk table and
+    /// runs a vector search for each of `queries`, returning how long the
+    /// write and each search took. The table is dropped afterwards.
+    pub(crate) async fn time_scratch_table(
+        &self,
+        chunks: &[ChunkInfo],
+        embeddings: &[Vec<f32>],
+        queries: &[Vec<f32>],
+    ) -> Result<(Duration, Vec<Duration>)> {
+        let schema = chunk_schema();
+        // Left behind if an earlier run failed halfway
+        let _ = self.db.drop_table(SCRATCH_TABLE).await;
+        let table = self
+            .db
+            .create_empty_table(SCRATCH_TABLE, schema.clone())
+            .execute()
+            .await?;
+        let timings = async {
+            let batch = chunk_batch(schema.clone(), chunks, embeddings)?;
+            let started = Instant::now();
+            table
+                .add(RecordBatchIterator::new(vec![Ok(batch)], schema.clone()))
+                .execute()
+                .await?;
+            let write = started.elapsed();
+            let mut searches = Vec::new();
+            for query in queries {
+                let started = Instant::now();
+                let stream = table
+                    .vector_search(query.clone())?
+                    .limit(SCRATCH_SEARCH_LIMIT)
+                    .execute()
+                    .await?;
+                read_embedded_chunks(stream).await?;
+                searches.push(started.elapsed());
+            }
+            Ok::<_, anyhow::Error>((write, searches))
+        }
+        .await;
+        self.db.drop_table(SCRATCH_TABLE).await?;
+        timings
+    }
+
+    pub(crate) fn memory_table(&self) -> &Table {
+        &self.memory
+    }
+
+    /// Number of chunks in the table.
+    pub async fn chunk_count(&self) -> Result<usize> {
+        Ok(self.table.count_rows(None).await?)
+    }
+
     /// Calculate total size of all files in context
     async fn calculate_total_size(&self) -> Result<usize> {
         // Implement logic to calculate total size
@@ -551,16 +1266,10 @@ impl SmartContextManager {
         let mut stream = self.table.query().execute().await?;
 
         while let Some(batch) = stream.try_next().await? {
-            let content = batch
-                .column_by_name("content")
-                .expect("content column not found")
-                .as_any()
-                .downcast_ref::<StringArray>()
-                .unwrap();
-
-            for i in 0..batch.num_rows() {
-                total += content.value(i).len();
-            }
+            total += chunk_contents(&batch)?
+                .iter()
+                .map(|content| content.len())
+                .sum::<usize>();
         }
 
         Ok(total)
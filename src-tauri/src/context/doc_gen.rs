@@ -0,0 +1,422 @@
+// src/context/doc_gen.rs
+
+use serde::Serialize;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+use super::context_manager::SymbolKind;
+use super::docs::{doc_comment, is_comment_or_attribute};
+use super::explain::{complete, gather_context, prompt};
+use super::lsp_symbols::document_symbols;
+use super::symbol_index;
+use crate::commands::diff::{compute_diff, TextDiffResult};
+use crate::commands::edit_transaction::{
+    begin_edit_transaction, rollback_edit_transaction, stage_edit,
+};
+use crate::commands::fs::{read_file, resolve_workspace_path, workspace_relative_path};
+use crate::error::MightyError;
+use crate::state::AppState;
+
+const MAX_TOKENS: i32 = 1_024;
+/// Lines of a long symbol shown to the model; the signature and start say
+/// enough to document it.
+const MAX_SYMBOL_LINES: usize = 300;
+/// Lines searched for the `:` ending a Python signature.
+const MAX_SIGNATURE_LINES: usize = 50;
+const INSTRUCTIONS: &str = "Reply with only the comment's text, without comment markers, \
+quotes or a code fence, and don't repeat the code. Describe what it does and why a caller \
+would use it rather than how it works line by line.";
+
+/// How a language writes doc comments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocStyle {
+    /// `///` lines.
+    Rustdoc,
+    /// `/** */` with `@param` and `@returns`.
+    JsDoc,
+    /// `/** */` with `@param`, `@return` and `@throws`, also read by
+    /// Doxygen and KDoc.
+    Javadoc,
+    /// `"""` opening a Python body.
+    Docstring,
+    /// `//` lines starting with the name.
+    Godoc,
+    /// `///` lines of XML elements.
+    CSharpXml,
+}
+
+impl DocStyle {
+    fn for_path(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+        Some(match extension.as_str() {
+            "rs" => Self::Rustdoc,
+            "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs" => Self::JsDoc,
+            "java" | "kt" | "scala" | "php" | "c" | "h" | "cc" | "cpp" | "hpp" => Self::Javadoc,
+            "py" | "pyi" => Self::Docstring,
+            "go" => Self::Godoc,
+            "cs" => Self::CSharpXml,
+            _ => return None,
+        })
+    }
+
+    fn instructions(self) -> &'static str {
+        match self {
+            Self::Rustdoc => {
+                "Write a rustdoc comment: a one-line summary, then more only where it helps, \
+                 in Markdown, with `# Errors` or `# Panics` sections where they apply."
+            }
+            Self::JsDoc => {
+                "Write a JSDoc comment: a summary, then `@param` for each parameter and \
+                 `@returns` when it returns something. Leave types out where the signature \
+                 has them."
+            }
+            Self::Javadoc => {
+                "Write a Javadoc comment: a summary, then `@param`, `@return` and `@throws` \
+                 tags where they apply."
+            }
+            Self::Docstring => {
+                "Write a Python docstring in the style the file already uses, or Google style \
+                 if it has none, with Args, Returns and Raises sections where they apply."
+            }
+            Self::Godoc => {
+                "Write a Go doc comment in full sentences, the first starting with the \
+                 symbol's name."
+            }
+            Self::CSharpXml => {
+                "Write C# XML documentation with `<summary>`, `<param>` and `<returns>` \
+                 elements where they apply."
+            }
+        }
+    }
+
+    /// `text` as a comment, each line starting with `indent`.
+    fn format(self, text: &str, indent: &str) -> Vec<String> {
+        let lines: Vec<&str> = text.lines().collect();
+        let prefixed = |marker: &str| -> Vec<String> {
+            lines
+                .iter()
+                .map(|line| {
+                    if line.is_empty() {
+                        format!("{}{}", indent, marker)
+                    } else {
+                        format!("{}{} {}", indent, marker, line)
+                    }
+                })
+                .collect()
+        };
+        match self {
+            Self::Rustdoc | Self::CSharpXml => prefixed("///"),
+            Self::Godoc => prefixed("//"),
+            Self::JsDoc | Self::Javadoc => {
+                let mut comment = vec![format!("{}/**", indent)];
+                comment.extend(lines.iter().map(|line| {
+                    if line.is_empty() {
+                        format!("{} *", indent)
+                    } else {
+                        format!("{} * {}", indent, line)
+                    }
+                }));
+                comment.push(format!("{} */", indent));
+                comment
+            }
+            Self::Docstring => {
+                if let [line] = lines.as_slice() {
+                    return vec![format!("{}\"\"\"{}\"\"\"", indent, line)];
+                }
+                let mut comment = vec![format!("{}\"\"\"{}", indent, lines[0])];
+                comment.extend(lines[1..].iter().map(|line| {
+                    if line.is_empty() {
+                        String::new()
+                    } else {
+                        format!("{}{}", indent, line)
+                    }
+                }));
+                comment.push(format!("{}\"\"\"", indent));
+                comment
+            }
+        }
+    }
+}
+
+/// A doc comment proposed for a symbol, staged for review.
+#[derive(Debug, Clone, Serialize)]
+pub struct DocProposal {
+    /// The edit transaction holding the insertion; commit it to apply the
+    /// comment or roll it back to drop it.
+    pub transaction_id: String,
+    pub path: String,
+    pub symbol: String,
+    pub kind: SymbolKind,
+    pub style: DocStyle,
+    /// The comment as inserted, markers and indentation included.
+    pub comment: String,
+    /// 1-based line the comment starts at once inserted.
+    pub line: usize,
+    pub diff: TextDiffResult,
+    pub model: String,
+}
+
+/// A symbol's place in the file; lines are 0-based and inclusive.
+struct Target {
+    name: String,
+    kind: SymbolKind,
+    start_line: usize,
+    end_line: usize,
+}
+
+/// Finds `symbol`, optionally qualified by its container (`Type::name` or
+/// `Type.name`), asking the language server first and falling back to the
+/// symbol index. The first in the file wins when several match.
+async fn find_symbol(
+    path: &str,
+    relative: &str,
+    content: &str,
+    symbol: &str,
+) -> Result<Target, MightyError> {
+    let (container, name) = match symbol.rsplit_once("::").or_else(|| symbol.rsplit_once('.')) {
+        Some((container, name)) => (Some(container), name),
+        None => (None, symbol),
+    };
+    let matches = |candidate: &str, kind: &SymbolKind, candidate_container: Option<&str>| {
+        candidate == name
+            && *kind != SymbolKind::Import
+            && container.map_or(true, |container| {
+                candidate_container.is_some_and(|candidate| {
+                    candidate == container || candidate.ends_with(&format!("::{}", container))
+                })
+            })
+    };
+
+    let mut targets: Vec<Target> = match document_symbols(relative, content).await {
+        Some(symbols) => symbols
+            .into_iter()
+            .filter(|s| matches(&s.name, &s.kind, s.container_name.as_deref()))
+            .map(|s| Target {
+                name: s.name,
+                kind: s.kind,
+                start_line: s.start_line,
+                end_line: s.end_line,
+            })
+            .collect(),
+        None => symbol_index::symbols_in_file(&[path, relative])?
+            .into_iter()
+            .filter(|s| matches(&s.name, &s.kind, s.container_name.as_deref()))
+            .map(|s| Target {
+                name: s.name,
+                kind: s.kind,
+                start_line: s.start_line,
+                end_line: s.end_line,
+            })
+            .collect(),
+    };
+    targets.sort_by_key(|target| target.start_line);
+    targets.into_iter().next().ok_or_else(|| {
+        MightyError::request(
+            "SYMBOL_NOT_FOUND",
+            &format!("No symbol named {} in {}", symbol, path),
+        )
+    })
+}
+
+fn indentation(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Where the comment goes (a 0-based line to insert before) and its
+/// indentation: above the declaration and any attributes or decorators on
+/// it, or for Python as the first line of the body.
+fn insertion_point(
+    style: DocStyle,
+    lines: &[&str],
+    target: &Target,
+) -> Result<(usize, String), MightyError> {
+    let last = target.end_line.min(lines.len().saturating_sub(1));
+    // Some servers start a symbol's range at its attributes
+    let mut declaration = target.start_line;
+    while declaration < last && is_comment_or_attribute(lines[declaration].trim()) {
+        declaration += 1;
+    }
+    let indent = indentation(lines[declaration]).to_string();
+
+    if style != DocStyle::Docstring {
+        let mut line = declaration;
+        while line > 0 {
+            let above = lines[line - 1].trim();
+            if !above.starts_with("#[") && !above.starts_with('@') {
+                break;
+            }
+            line -= 1;
+        }
+        return Ok((line, indent));
+    }
+
+    let signature_end = (declaration..=last.min(declaration + MAX_SIGNATURE_LINES))
+        .find(|&line| {
+            let code = lines[line].split(" #").next().unwrap_or_default();
+            code.trim_end().ends_with(':')
+        })
+        .ok_or_else(|| {
+            MightyError::request(
+                "UNSUPPORTED_SYMBOL",
+                &format!("{} has no body to put a docstring in", target.name),
+            )
+        })?;
+    let body_indent = lines[signature_end + 1..]
+        .iter()
+        .find(|line| !line.trim().is_empty())
+        .map(|line| indentation(line))
+        .filter(|body| body.len() > indent.len())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{}    ", indent));
+    Ok((signature_end + 1, body_indent))
+}
+
+/// The model's reply without the fences or markers it was asked to leave
+/// out.
+fn comment_text(reply: &str) -> String {
+    let reply = reply.trim();
+    let reply = match reply.strip_prefix("```") {
+        Some(fenced) => {
+            let body = fenced.split_once('\n').map_or("", |(_, rest)| rest);
+            body.trim_end().trim_end_matches("```")
+        }
+        None => reply,
+    };
+    let reply = reply.trim();
+    // Leading `*`s are Markdown bullets unless the reply is a block comment
+    let block = reply.starts_with("/**");
+    let reply = reply
+        .trim_start_matches("/**")
+        .trim_end_matches("*/")
+        .trim_matches('"');
+    reply
+        .lines()
+        .map(|line| {
+            let line = line.trim_end();
+            let stripped = line.trim_start();
+            let marker = ["///", "//"]
+                .into_iter()
+                .chain(block.then_some("*"))
+                .find(|marker| stripped.starts_with(marker));
+            match marker {
+                Some(marker) => {
+                    let rest = &stripped[marker.len()..];
+                    rest.strip_prefix(' ').unwrap_or(rest).to_string()
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// `content` with `inserted` placed before line `at` (0-based), keeping the
+/// file's line endings.
+fn insert_lines(content: &str, at: usize, inserted: &[String]) -> String {
+    let line_ending = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut edited: String = lines[..at.min(lines.len())].concat();
+    if !edited.is_empty() && !edited.ends_with('\n') {
+        edited.push_str(line_ending);
+    }
+    for line in inserted {
+        edited.push_str(line);
+        edited.push_str(line_ending);
+    }
+    edited.push_str(&lines[at.min(lines.len())..].concat());
+    edited
+}
+
+/// Asks the chat model to document `symbol` in `path` in the language's
+/// doc comment style (rustdoc, JSDoc, Javadoc, docstrings, Go or C# XML
+/// docs), given its code, the code around it and definitions of the names
+/// it uses. The comment isn't written: the insertion is staged in a new
+/// edit transaction and returned as a diff, to be applied with
+/// `commit_edit_transaction` once approved.
+#[tauri::command]
+pub async fn generate_docs(
+    app: AppHandle,
+    path: String,
+    symbol: String,
+    state: State<'_, AppState>,
+) -> Result<DocProposal, MightyError> {
+    let style = DocStyle::for_path(&path).ok_or_else(|| {
+        MightyError::request(
+            "UNSUPPORTED_LANGUAGE",
+            &format!("Doc comments aren't supported for {}", path),
+        )
+    })?;
+    let content = read_file(path.clone()).await?.content;
+    let lines: Vec<&str> = content.lines().collect();
+    let relative = workspace_relative_path(&resolve_workspace_path(&path)?);
+
+    let target = find_symbol(&path, &relative, &content, symbol.trim()).await?;
+    if target.start_line >= lines.len() {
+        return Err(MightyError::request(
+            "SYMBOL_NOT_FOUND",
+            &format!("{} is out of date in the symbol index", symbol),
+        ));
+    }
+    if doc_comment(&path, &lines, target.start_line).is_some() {
+        return Err(MightyError::request(
+            "ALREADY_DOCUMENTED",
+            &format!("{} already has a doc comment", target.name),
+        ));
+    }
+    let (at, indent) = insertion_point(style, &lines, &target)?;
+
+    let start = target.start_line;
+    let end = target
+        .end_line
+        .min(start + MAX_SYMBOL_LINES)
+        .min(lines.len() - 1);
+    let context = gather_context(&path, &relative, &lines, start, end)?;
+    let instructions = format!(
+        "{} {}\n\nDocument the {:?} `{}`, the selected code below.",
+        style.instructions(),
+        INSTRUCTIONS,
+        target.kind,
+        target.name
+    );
+    let prompt = prompt(&instructions, &relative, &lines, start, end, &context);
+    let (reply, model) = complete(app.clone(), state, prompt, MAX_TOKENS).await?;
+
+    let text = comment_text(&reply);
+    if text.is_empty() {
+        return Err(MightyError::from("The model replied without a comment"));
+    }
+    let comment = style.format(&text, &indent);
+    let edited = insert_lines(&content, at, &comment);
+    let diff = compute_diff(&content, &edited);
+
+    let transaction_id = begin_edit_transaction().await?;
+    let staged = stage_edit(
+        transaction_id.clone(),
+        path.clone(),
+        diff.unified_diff.clone(),
+    )
+    .await;
+    if let Err(e) = staged {
+        rollback_edit_transaction(app, transaction_id).await?;
+        return Err(e.into());
+    }
+
+    Ok(DocProposal {
+        transaction_id,
+        path,
+        symbol: target.name,
+        kind: target.kind,
+        style,
+        comment: comment.join("\n"),
+        line: at + 1,
+        diff,
+        model,
+    })
+}
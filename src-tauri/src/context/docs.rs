@@ -0,0 +1,169 @@
+// src/context/docs.rs
+
+use std::path::Path;
+
+use super::context_manager::ChunkInfo;
+
+/// How many more candidates to fetch for a usage question, so documented
+/// chunks further down the similarity ranking can be moved up.
+pub(crate) const USAGE_CANDIDATE_FACTOR: usize = 3;
+
+/// Phrases marking a question about how to use something rather than how it
+/// is implemented.
+const USAGE_PHRASES: [&str; 10] = [
+    "how do i",
+    "how do you",
+    "how can i",
+    "how to",
+    "how does",
+    "what does",
+    "usage",
+    "example",
+    "documentation",
+    "docs for",
+];
+/// Words in a usage question that don't name anything in the code.
+const STOP_WORDS: [&str; 16] = [
+    "how", "can", "you", "use", "the", "and", "for", "does", "what", "with", "this", "that",
+    "usage", "example", "examples", "docs",
+];
+
+/// The doc comment or docstring of the symbol starting at `start_line`
+/// (0-based): the comment lines directly above it, skipping attributes and
+/// decorators, or for Python the docstring opening its body.
+pub(crate) fn doc_comment(path: &str, lines: &[&str], start_line: usize) -> Option<String> {
+    // Some servers, rust-analyzer among them, start a symbol's range at its
+    // doc comment or attributes rather than at the declaration
+    let mut declaration = start_line;
+    while lines
+        .get(declaration)
+        .is_some_and(|line| is_comment_or_attribute(line.trim()))
+    {
+        declaration += 1;
+    }
+
+    let mut docs = Vec::new();
+    let mut line = declaration.min(lines.len());
+    let mut in_block = false;
+    while line > 0 {
+        line -= 1;
+        let text = lines[line].trim();
+        if in_block || text.ends_with("*/") {
+            docs.push(
+                text.trim_start_matches("/*")
+                    .trim_end_matches("*/")
+                    .trim_start_matches('*')
+                    .trim(),
+            );
+            if text.starts_with("/*") {
+                break;
+            }
+            in_block = true;
+            continue;
+        }
+        if text.starts_with("#[") || text.starts_with('@') {
+            if docs.is_empty() {
+                continue;
+            }
+            break;
+        }
+        // `#` only with a space after, so `#include` and the like aren't comments
+        let comment = text
+            .strip_prefix("//")
+            .map(|comment| comment.trim_start_matches(['/', '!']))
+            .or_else(|| (text == "#" || text.starts_with("# ")).then(|| &text[1..]));
+        match comment {
+            Some(comment) => docs.push(comment.trim()),
+            None => break,
+        }
+    }
+    docs.reverse();
+
+    let is_python = Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension == "py");
+    if docs.iter().all(|line| line.is_empty()) && is_python {
+        docs = docstring(lines, declaration);
+    }
+
+    let text = docs.join("\n").trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+pub(super) fn is_comment_or_attribute(text: &str) -> bool {
+    ["//", "/*", "*", "#[", "@", "# "]
+        .iter()
+        .any(|marker| text.starts_with(marker))
+        || text == "#"
+}
+
+/// The lines of a `"""` or `'''` docstring within the few lines after a
+/// Python `def` or `class`, without the quotes.
+fn docstring<'a>(lines: &[&'a str], start_line: usize) -> Vec<&'a str> {
+    let body = lines.iter().skip(start_line + 1).take(3);
+    let Some((offset, opening)) = body
+        .enumerate()
+        .find(|(_, line)| !line.trim().is_empty())
+        .map(|(offset, line)| (offset, line.trim()))
+    else {
+        return Vec::new();
+    };
+    let Some(quote) = ["\"\"\"", "'''"]
+        .into_iter()
+        .find(|quote| opening.starts_with(quote))
+    else {
+        return Vec::new();
+    };
+
+    let first = &opening[quote.len()..];
+    if let Some(end) = first.find(quote) {
+        return vec![first[..end].trim()];
+    }
+    let mut docs = vec![first.trim()];
+    for line in lines.iter().skip(start_line + offset + 2) {
+        let text = line.trim();
+        if let Some(end) = text.find(quote) {
+            docs.push(text[..end].trim());
+            break;
+        }
+        docs.push(text);
+    }
+    docs
+}
+
+/// Whether `query` asks how to use something, e.g. "how do I use
+/// SmartContextManager", where documentation answers better than code.
+pub(crate) fn is_usage_query(query: &str) -> bool {
+    let query = query.to_lowercase();
+    USAGE_PHRASES.iter().any(|phrase| query.contains(phrase))
+}
+
+/// Reorders `chunks`, ranked by similarity, so those whose symbol's
+/// documentation mentions a name from `query` come first, then other
+/// documented ones. Similarity decides within each group.
+pub(crate) fn boost_documented(chunks: &mut [ChunkInfo], query: &str) {
+    let names: Vec<String> = query
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| word.len() >= 3)
+        .map(str::to_lowercase)
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+        .collect();
+
+    // Stable, so similarity order holds among equal boosts
+    chunks.sort_by_key(|chunk| {
+        let Some(docs) = &chunk.docs else {
+            return 2;
+        };
+        let documented = format!(
+            "{} {}",
+            chunk.symbol_name.as_deref().unwrap_or_default(),
+            docs
+        )
+        .to_lowercase();
+        if names.iter().any(|name| documented.contains(name)) {
+            0
+        } else {
+            1
+        }
+    });
+}
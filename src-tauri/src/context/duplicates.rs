@@ -0,0 +1,179 @@
+// src/context/duplicates.rs
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use super::context::embedded_chunks;
+use super::context_manager::EmbeddedChunk;
+
+const DEFAULT_THRESHOLD: f32 = 0.95;
+/// Chunks with fewer non-blank lines are skipped; short runs of braces and
+/// imports look alike everywhere.
+const DEFAULT_MIN_LINES: usize = 5;
+const PREVIEW_LENGTH: usize = 120;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateLocation {
+    pub file_path: String,
+    /// 1-based and inclusive.
+    pub start_line: usize,
+    pub end_line: usize,
+    /// First non-blank line of the chunk.
+    pub preview: String,
+}
+
+/// Chunks that are near-identical to one another, directly or through
+/// other members of the group.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    pub locations: Vec<DuplicateLocation>,
+    /// Cosine similarity of the least similar pair that joined the group.
+    pub similarity: f32,
+    /// Non-blank lines in the largest member, a rough measure of how much
+    /// a refactoring would save.
+    pub lines: usize,
+}
+
+fn normalized(embedding: &[f32]) -> Option<Vec<f32>> {
+    let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+    (norm > 0.0).then(|| embedding.iter().map(|v| v / norm).collect())
+}
+
+fn non_blank_lines(content: &str) -> usize {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .count()
+}
+
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Links every pair of chunks at least `threshold` similar and returns the
+/// connected groups, largest and most similar first.
+fn group_duplicates(
+    chunks: Vec<EmbeddedChunk>,
+    threshold: f32,
+    min_lines: usize,
+) -> Vec<DuplicateGroup> {
+    // A file indexed more than once has its chunks in the table repeatedly
+    let mut seen = HashSet::new();
+    let (chunks, vectors): (Vec<EmbeddedChunk>, Vec<Vec<f32>>) = chunks
+        .into_iter()
+        .filter(|chunk| seen.insert((chunk.file_path.clone(), chunk.start_line, chunk.end_line)))
+        .filter(|chunk| non_blank_lines(&chunk.content) >= min_lines)
+        .filter_map(|chunk| {
+            let vector = normalized(&chunk.embedding)?;
+            Some((chunk, vector))
+        })
+        .unzip();
+
+    let mut parents: Vec<usize> = (0..chunks.len()).collect();
+    let mut links = Vec::new();
+    for i in 0..chunks.len() {
+        for j in i + 1..chunks.len() {
+            let (a, b) = (&chunks[i], &chunks[j]);
+            // Overlapping chunks of one file aren't duplicates of each other
+            if a.file_path == b.file_path && a.start_line < b.end_line && b.start_line < a.end_line
+            {
+                continue;
+            }
+            let similarity: f32 = vectors[i].iter().zip(&vectors[j]).map(|(x, y)| x * y).sum();
+            if similarity >= threshold {
+                let (root_i, root_j) = (find(&mut parents, i), find(&mut parents, j));
+                parents[root_j] = root_i;
+                links.push((i, similarity));
+            }
+        }
+    }
+
+    let mut weakest: HashMap<usize, f32> = HashMap::new();
+    for (i, similarity) in links {
+        let root = find(&mut parents, i);
+        let entry = weakest.entry(root).or_insert(similarity);
+        *entry = entry.min(similarity);
+    }
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..chunks.len() {
+        let root = find(&mut parents, i);
+        if weakest.contains_key(&root) {
+            members.entry(root).or_default().push(i);
+        }
+    }
+
+    let mut groups: Vec<DuplicateGroup> = members
+        .into_iter()
+        .map(|(root, indices)| {
+            let mut locations: Vec<DuplicateLocation> = indices
+                .iter()
+                .map(|&i| {
+                    let chunk = &chunks[i];
+                    let preview: String = chunk
+                        .content
+                        .lines()
+                        .map(str::trim)
+                        .find(|line| !line.is_empty())
+                        .unwrap_or_default()
+                        .chars()
+                        .take(PREVIEW_LENGTH)
+                        .collect();
+                    DuplicateLocation {
+                        file_path: chunk.file_path.clone(),
+                        // Chunk lines are 0-based with an exclusive end
+                        start_line: chunk.start_line + 1,
+                        end_line: chunk.end_line,
+                        preview,
+                    }
+                })
+                .collect();
+            locations.sort_by(|a, b| {
+                a.file_path
+                    .cmp(&b.file_path)
+                    .then_with(|| a.start_line.cmp(&b.start_line))
+            });
+            DuplicateGroup {
+                locations,
+                similarity: weakest[&root],
+                lines: indices
+                    .iter()
+                    .map(|&i| non_blank_lines(&chunks[i].content))
+                    .max()
+                    .unwrap_or_default(),
+            }
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.locations
+            .len()
+            .cmp(&a.locations.len())
+            .then_with(|| b.similarity.total_cmp(&a.similarity))
+            .then_with(|| b.lines.cmp(&a.lines))
+    });
+    groups
+}
+
+/// Groups of near-identical indexed chunks, found by comparing their
+/// embeddings. `threshold` is the cosine similarity two chunks need to count
+/// as duplicates, 0.95 by default.
+#[tauri::command]
+pub async fn find_duplicate_code(
+    threshold: Option<f32>,
+    min_lines: Option<usize>,
+) -> Result<Vec<DuplicateGroup>, String> {
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD);
+    if !(threshold > 0.0 && threshold <= 1.0) {
+        return Err("The threshold must be above 0 and at most 1".to_string());
+    }
+    let min_lines = min_lines.unwrap_or(DEFAULT_MIN_LINES);
+    let chunks = embedded_chunks().await?;
+
+    // Comparing every pair takes a while on a large index
+    tokio::task::spawn_blocking(move || group_duplicates(chunks, threshold, min_lines))
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,307 @@
+// src/context/explain.rs
+
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+use tauri::{AppHandle, State};
+use tracing::debug;
+use uuid::Uuid;
+
+use super::context::similar_chunks;
+use super::context_manager::ChunkInfo;
+use super::symbol_index::{self, IndexedSymbol};
+use crate::commands::api::{anthropic_completion, AnthropicMessage, AnthropicRequest};
+use crate::commands::bookmarks::{bookmarks_for_file, Bookmark};
+use crate::commands::fs::{read_file, resolve_workspace_path, workspace_relative_path};
+use crate::error::MightyError;
+use crate::state::AppState;
+
+const DEFAULT_MODEL: &str = "claude-3-5-sonnet-20241022";
+const MAX_TOKENS: i32 = 2_048;
+/// Definitions of names used in the selection included in the prompt.
+const MAX_DEFINITIONS: usize = 10;
+const MAX_RELATED_CHUNKS: usize = 3;
+const MAX_IMPORTS: usize = 30;
+/// Lines shown around the selection when it isn't inside a known symbol.
+const SURROUNDING_LINES: usize = 20;
+const INSTRUCTIONS: &str = "Explain what the selected code does, how it fits into the code \
+around it and anything surprising about it. Refer to the context below where it helps, and \
+don't restate the code line by line.";
+
+/// What the model was shown besides the selection.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExplanationContext {
+    /// Symbols of the file that contain the selection, outermost first.
+    pub enclosing: Vec<IndexedSymbol>,
+    /// Definitions elsewhere of names the selection uses.
+    pub definitions: Vec<IndexedSymbol>,
+    pub imports: Vec<String>,
+    /// Indexed chunks most similar to the selection, from other places.
+    pub related_chunks: Vec<ChunkInfo>,
+    /// The user's notes on the file.
+    pub annotations: Vec<Bookmark>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Explanation {
+    pub explanation: String,
+    pub model: String,
+    pub context: ExplanationContext,
+}
+
+fn is_import(line: &str) -> bool {
+    ["use ", "import ", "from ", "#include", "require("]
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+        || line.contains("= require(")
+}
+
+/// Gathers what the selection, lines `start` to `end` (0-based, inclusive)
+/// of `path`, depends on: the symbols around it, definitions of the names it
+/// uses, the file's imports and the user's notes on it. `relative` is the
+/// path relative to the workspace.
+pub(super) fn gather_context(
+    path: &str,
+    relative: &str,
+    lines: &[&str],
+    start: usize,
+    end: usize,
+) -> Result<ExplanationContext, String> {
+    // Files are indexed under whatever path they were added with
+    let file_symbols = symbol_index::symbols_in_file(&[path, relative])?;
+    let mut enclosing: Vec<IndexedSymbol> = file_symbols
+        .iter()
+        .filter(|symbol| symbol.start_line <= start && symbol.end_line >= end)
+        .cloned()
+        .collect();
+    enclosing.sort_by_key(|symbol| symbol.start_line);
+
+    let selection = lines[start..=end].join("\n");
+    let identifier = Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").expect("the pattern is valid");
+    let local: HashSet<&str> = file_symbols
+        .iter()
+        .filter(|symbol| symbol.start_line >= start && symbol.end_line <= end)
+        .map(|symbol| symbol.name.as_str())
+        .collect();
+    let names: HashSet<&str> = identifier
+        .find_iter(&selection)
+        .map(|name| name.as_str())
+        .filter(|name| name.len() >= 3 && !local.contains(name))
+        .collect();
+    let mut definitions = symbol_index::definitions_named(&names)?;
+    // Documented definitions say the most in the fewest lines
+    definitions.sort_by(|a, b| {
+        b.docs
+            .is_some()
+            .cmp(&a.docs.is_some())
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.file.cmp(&b.file))
+    });
+    definitions.truncate(MAX_DEFINITIONS);
+
+    let imports = lines
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| is_import(line))
+        .take(MAX_IMPORTS)
+        .map(str::to_string)
+        .collect();
+
+    let annotations = bookmarks_for_file(relative, &lines.join("\n"))
+        .into_iter()
+        .filter(|bookmark| !bookmark.note.is_empty() && !bookmark.orphaned)
+        .collect();
+
+    Ok(ExplanationContext {
+        enclosing,
+        definitions,
+        imports,
+        related_chunks: Vec::new(),
+        annotations,
+    })
+}
+
+/// The prompt for a request about the selection, lines `start` to `end`
+/// (0-based, inclusive), shown with the code around it and `context`.
+pub(super) fn prompt(
+    instructions: &str,
+    path: &str,
+    lines: &[&str],
+    start: usize,
+    end: usize,
+    context: &ExplanationContext,
+) -> String {
+    let mut prompt = format!("{}\n\nFile: {}\n", instructions, path);
+
+    if !context.imports.is_empty() {
+        prompt.push_str(&format!("\nImports:\n{}\n", context.imports.join("\n")));
+    }
+
+    // The innermost enclosing symbol, or a window around the selection
+    let (outer_start, outer_end) = match context.enclosing.last() {
+        Some(symbol) => (symbol.start_line, symbol.end_line.min(lines.len() - 1)),
+        None => (
+            start.saturating_sub(SURROUNDING_LINES),
+            (end + SURROUNDING_LINES).min(lines.len() - 1),
+        ),
+    };
+    if (outer_start, outer_end) != (start, end) {
+        let label = match context.enclosing.last() {
+            Some(symbol) => format!("{:?} {}", symbol.kind, symbol.name),
+            None => "Surrounding code".to_string(),
+        };
+        prompt.push_str(&format!(
+            "\n{} (lines {}-{}):\n```\n{}\n```\n",
+            label,
+            outer_start + 1,
+            outer_end + 1,
+            lines[outer_start..=outer_end].join("\n")
+        ));
+    }
+
+    prompt.push_str(&format!(
+        "\nSelected code (lines {}-{}):\n```\n{}\n```\n",
+        start + 1,
+        end + 1,
+        lines[start..=end].join("\n")
+    ));
+
+    if !context.definitions.is_empty() {
+        prompt.push_str("\nDefinitions of names used in the selection:\n");
+        for symbol in &context.definitions {
+            let name = match &symbol.container_name {
+                Some(container) => format!("{}::{}", container, symbol.name),
+                None => symbol.name.clone(),
+            };
+            prompt.push_str(&format!(
+                "- {:?} {} ({}:{})",
+                symbol.kind,
+                name,
+                symbol.file,
+                symbol.start_line + 1
+            ));
+            if let Some(docs) = &symbol.docs {
+                prompt.push_str(&format!(": {}", docs.replace('\n', " ")));
+            }
+            prompt.push('\n');
+        }
+    }
+
+    if !context.annotations.is_empty() {
+        prompt.push_str("\nNotes the user left on this file:\n");
+        for bookmark in &context.annotations {
+            prompt.push_str(&format!(
+                "- Lines {}-{}: {}\n",
+                bookmark.start_line, bookmark.end_line, bookmark.note
+            ));
+        }
+    }
+
+    for chunk in &context.related_chunks {
+        prompt.push_str(&format!(
+            "\nRelated code from {} (lines {}-{}):\n```\n{}\n```\n",
+            chunk.file_path,
+            chunk.start_line + 1,
+            chunk.end_line,
+            chunk.content
+        ));
+    }
+    prompt
+}
+
+/// Sends `prompt` to the chat model and returns its reply and the model used.
+pub(super) async fn complete(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    prompt: String,
+    max_tokens: i32,
+) -> Result<(String, String), MightyError> {
+    let model = state
+        .config
+        .lock()
+        .await
+        .models
+        .as_ref()
+        .and_then(|models| models.chat.clone())
+        .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+    let request = AnthropicRequest {
+        id: Uuid::new_v4().to_string(),
+        model: model.clone(),
+        max_tokens,
+        messages: vec![AnthropicMessage {
+            role: "user".to_string(),
+            content: prompt,
+        }],
+        conversation_id: None,
+    };
+    let response = anthropic_completion(app, request, state).await?;
+    let text = serde_json::from_str::<serde_json::Value>(&response)
+        .ok()
+        .and_then(|response| response["text"].as_str().map(str::to_string))
+        .ok_or_else(|| MightyError::from("The completion response had no text"))?;
+    Ok((text, model))
+}
+
+/// Checks that lines `start_line` to `end_line` (1-based, inclusive) exist in
+/// `path`'s `line_count` lines and returns them 0-based.
+pub(super) fn selection_range(
+    path: &str,
+    start_line: usize,
+    end_line: usize,
+    line_count: usize,
+) -> Result<(usize, usize), MightyError> {
+    if start_line == 0 || start_line > end_line || end_line > line_count {
+        return Err(MightyError::request(
+            "INVALID_RANGE",
+            &format!(
+                "Lines {}-{} are outside of {}, which has {} lines",
+                start_line, end_line, path, line_count
+            ),
+        ));
+    }
+    Ok((start_line - 1, end_line - 1))
+}
+
+/// Explains lines `start_line` to `end_line` (1-based, inclusive) of `path`
+/// in one call: gathers the enclosing symbols, definitions of the names the
+/// selection uses, the file's imports and similar indexed code, and asks the
+/// chat model about it all. Returns the context used with the explanation.
+#[tauri::command]
+pub async fn explain_range(
+    app: AppHandle,
+    path: String,
+    start_line: usize,
+    end_line: usize,
+    state: State<'_, AppState>,
+) -> Result<Explanation, MightyError> {
+    let content = read_file(path.clone()).await?.content;
+    let lines: Vec<&str> = content.lines().collect();
+    let (start, end) = selection_range(&path, start_line, end_line, lines.len())?;
+
+    let relative = workspace_relative_path(&resolve_workspace_path(&path)?);
+    let mut context = gather_context(&path, &relative, &lines, start, end)?;
+
+    let selection = lines[start..=end].join("\n");
+    match similar_chunks(&selection, MAX_RELATED_CHUNKS + 1).await {
+        Ok(chunks) => {
+            context.related_chunks = chunks
+                .into_iter()
+                .filter(|chunk| {
+                    let same_file = chunk.file_path == path || chunk.file_path == relative;
+                    !(same_file && chunk.start_line <= end && start < chunk.end_line)
+                })
+                .take(MAX_RELATED_CHUNKS)
+                .collect();
+        }
+        Err(e) => debug!("Explaining without related chunks: {}", e),
+    }
+
+    let prompt = prompt(INSTRUCTIONS, &relative, &lines, start, end, &context);
+    let (explanation, model) = complete(app, state, prompt, MAX_TOKENS).await?;
+
+    Ok(Explanation {
+        explanation,
+        model,
+        context,
+    })
+}
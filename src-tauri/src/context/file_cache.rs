@@ -0,0 +1,118 @@
+// src/context/file_cache.rs
+
+use lru::LruCache;
+
+use super::context_manager::FileContext;
+
+/// Bytes a symbol takes besides its strings, roughly.
+const SYMBOL_OVERHEAD: usize = 96;
+
+/// Roughly how much memory a cached file takes.
+fn size_of(context: &FileContext) -> usize {
+    let symbols: usize = context
+        .symbols
+        .iter()
+        .map(|symbol| {
+            SYMBOL_OVERHEAD
+                + symbol.name.len()
+                + symbol.location.file.len()
+                + symbol.container_name.as_ref().map_or(0, String::len)
+                + symbol.docs.as_ref().map_or(0, String::len)
+                + symbol
+                    .related_symbols
+                    .iter()
+                    .map(String::len)
+                    .sum::<usize>()
+        })
+        .sum();
+    let imports: usize = context.imports.iter().map(String::len).sum();
+    context.content.len() + symbols + imports
+}
+
+/// Least recently used file contexts, bounded by both a file count and a
+/// byte budget so a few huge files can't crowd out memory. Files evicted
+/// here are read back from disk when needed again.
+pub struct FileCache {
+    entries: LruCache<String, (FileContext, usize)>,
+    bytes: usize,
+    max_files: usize,
+    max_bytes: usize,
+    /// The memory budget, when it is below `max_bytes`.
+    limit: Option<usize>,
+}
+
+impl FileCache {
+    pub fn new(max_files: usize, max_bytes: usize) -> Self {
+        Self {
+            entries: LruCache::unbounded(),
+            bytes: 0,
+            max_files: max_files.max(1),
+            max_bytes,
+            limit: None,
+        }
+    }
+
+    fn budget(&self) -> usize {
+        self.limit
+            .map_or(self.max_bytes, |limit| limit.min(self.max_bytes))
+    }
+
+    /// Caps the cache at `limit` bytes on top of its own budget, evicting
+    /// the least recently used files at once to fit.
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+        while self.bytes > self.budget() {
+            match self.entries.pop_lru() {
+                Some((_, (_, evicted))) => self.bytes -= evicted,
+                None => break,
+            }
+        }
+    }
+
+    /// Caches `context`, evicting the least recently used files until both
+    /// limits hold. A file larger than the whole budget isn't kept.
+    pub fn put(&mut self, path: String, context: FileContext) {
+        self.pop(&path);
+        let size = size_of(&context);
+        let budget = self.budget();
+        if size > budget {
+            return;
+        }
+        while self.entries.len() >= self.max_files || self.bytes + size > budget {
+            match self.entries.pop_lru() {
+                Some((_, (_, evicted))) => self.bytes -= evicted,
+                None => break,
+            }
+        }
+        self.bytes += size;
+        self.entries.put(path, (context, size));
+    }
+
+    pub fn get(&mut self, path: &str) -> Option<&FileContext> {
+        self.entries.get(path).map(|(context, _)| context)
+    }
+
+    pub fn pop(&mut self, path: &str) -> Option<FileContext> {
+        let (context, size) = self.entries.pop(path)?;
+        self.bytes -= size;
+        Some(context)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.bytes = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Estimated bytes held.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
@@ -0,0 +1,286 @@
+// src/context/file_drop.rs
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, Emitter, State};
+use tracing::{info, warn};
+
+use super::context::index_file;
+use super::filters;
+use super::warmup::{is_source_file, walk_workspace, workspace_ignore};
+use crate::commands::fs::{get_project_root, workspace_relative_path};
+use crate::state::{app_state, AppState};
+
+const DEFAULT_DROP_DIR: &str = "dropped";
+/// Files taken from one drop; a dropped home directory stops here.
+const MAX_DROPPED_FILES: usize = 2000;
+
+const DOC_EXTENSIONS: &[&str] = &["md", "markdown", "txt", "rst", "adoc", "org", "pdf"];
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "svg", "bmp", "ico"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DroppedKind {
+    Code,
+    Doc,
+    Image,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DroppedFile {
+    /// Where the file ended up: relative to the workspace root when inside
+    /// it, absolute otherwise.
+    pub path: String,
+    pub kind: DroppedKind,
+    /// The original location, when the file was copied into the workspace.
+    pub copied_from: Option<String>,
+    pub indexed: bool,
+    /// Why an eligible file wasn't indexed.
+    pub error: Option<String>,
+}
+
+/// Payload of the `files-dropped` event.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DropSummary {
+    pub files: Vec<DroppedFile>,
+    pub code: usize,
+    pub docs: usize,
+    pub images: usize,
+    pub other: usize,
+    pub copied: usize,
+    pub indexed: usize,
+    /// Whether files were left out after `MAX_DROPPED_FILES`.
+    pub truncated: bool,
+}
+
+fn classify(path: &Path) -> DroppedKind {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+    // Markdown counts as source for warm-up, but it's read as a doc
+    if DOC_EXTENSIONS.contains(&extension.as_str()) {
+        DroppedKind::Doc
+    } else if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+        DroppedKind::Image
+    } else if is_source_file(path) {
+        DroppedKind::Code
+    } else {
+        DroppedKind::Other
+    }
+}
+
+/// Code and text docs go into the context index; images, PDFs and
+/// anything else are only reported.
+fn is_indexable(path: &Path, kind: DroppedKind) -> bool {
+    match kind {
+        DroppedKind::Code => true,
+        DroppedKind::Doc => !path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("pdf")),
+        DroppedKind::Image | DroppedKind::Other => false,
+    }
+}
+
+/// `dir/name`, or `dir/name (2)` and so on when that exists or another
+/// dropped path already took it.
+fn unique_target(dir: &Path, name: &str, taken: &HashSet<PathBuf>) -> PathBuf {
+    let free = |target: &PathBuf| !target.exists() && !taken.contains(target);
+    let target = dir.join(name);
+    if free(&target) {
+        return target;
+    }
+    let path = Path::new(name);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    (2..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, extension)))
+        .find(free)
+        .expect("an unused name")
+}
+
+/// The files under each dropped path, each with where it should be copied
+/// to when it's copied at all.
+fn collect(
+    paths: &[PathBuf],
+    copy_dir: Option<&Path>,
+    ignore: &[glob::Pattern],
+) -> (Vec<(PathBuf, Option<PathBuf>)>, bool) {
+    let root = get_project_root();
+    let mut files = Vec::new();
+    let mut truncated = false;
+    let mut taken = HashSet::new();
+    for path in paths {
+        let path = path.canonicalize().unwrap_or_else(|_| path.clone());
+        // Files already in the workspace are indexed where they are
+        let target = match copy_dir {
+            Some(dir) if !path.starts_with(&root) => {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let target = unique_target(dir, &name, &taken);
+                taken.insert(target.clone());
+                Some(target)
+            }
+            _ => None,
+        };
+        if path.is_dir() {
+            walk_workspace(&path, ignore, |file, _, _| {
+                if files.len() >= MAX_DROPPED_FILES {
+                    truncated = true;
+                    return;
+                }
+                let copy_to = target
+                    .as_ref()
+                    .map(|target| target.join(file.strip_prefix(&path).unwrap_or(file)));
+                files.push((file.to_path_buf(), copy_to));
+            });
+        } else if path.is_file() {
+            if files.len() >= MAX_DROPPED_FILES {
+                truncated = true;
+                continue;
+            }
+            files.push((path, target));
+        }
+    }
+    (files, truncated)
+}
+
+fn copy_file(from: &Path, to: &Path) -> Result<(), String> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::copy(from, to)
+        .map(drop)
+        .map_err(|e| format!("Failed to copy {}: {}", from.display(), e))
+}
+
+async fn index_dropped(path: &Path, relative: &str) -> Result<bool, String> {
+    let bytes = tokio::fs::metadata(path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    if filters::skip_reason(relative, bytes).is_some() {
+        return Ok(false);
+    }
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(index_file(relative, &content).await?.is_none())
+}
+
+async fn ingest(state: &AppState, paths: Vec<PathBuf>, copy: Option<bool>) -> DropSummary {
+    let workspace = state
+        .config
+        .lock()
+        .await
+        .workspace
+        .clone()
+        .unwrap_or_default();
+    let copy_dir = copy.unwrap_or(workspace.copy_dropped_files).then(|| {
+        get_project_root().join(workspace.drop_dir.as_deref().unwrap_or(DEFAULT_DROP_DIR))
+    });
+    let ignore = workspace_ignore(state).await;
+
+    let (files, truncated) =
+        match tokio::task::spawn_blocking(move || collect(&paths, copy_dir.as_deref(), &ignore))
+            .await
+        {
+            Ok(collected) => collected,
+            Err(e) => {
+                warn!("Failed to read dropped files: {}", e);
+                return DropSummary::default();
+            }
+        };
+
+    let mut summary = DropSummary {
+        truncated,
+        ..DropSummary::default()
+    };
+    for (source, copy_to) in files {
+        let kind = classify(&source);
+        match kind {
+            DroppedKind::Code => summary.code += 1,
+            DroppedKind::Doc => summary.docs += 1,
+            DroppedKind::Image => summary.images += 1,
+            DroppedKind::Other => summary.other += 1,
+        }
+
+        let mut error = None;
+        let mut copied_from = None;
+        let mut path = source.clone();
+        if let Some(target) = copy_to {
+            match copy_file(&source, &target) {
+                Ok(()) => {
+                    summary.copied += 1;
+                    copied_from = Some(source.to_string_lossy().to_string());
+                    path = target;
+                }
+                Err(e) => error = Some(e),
+            }
+        }
+
+        let relative = workspace_relative_path(&path);
+        let mut indexed = false;
+        if error.is_none() && is_indexable(&path, kind) {
+            match index_dropped(&path, &relative).await {
+                Ok(added) => indexed = added,
+                Err(e) => error = Some(e),
+            }
+        }
+        if indexed {
+            summary.indexed += 1;
+        }
+        summary.files.push(DroppedFile {
+            path: relative,
+            kind,
+            copied_from,
+            indexed,
+            error,
+        });
+    }
+    summary
+}
+
+/// Resolves, classifies and indexes files dropped onto the window, then
+/// emits `files-dropped` with what happened to them.
+pub(crate) fn handle_drop(app: AppHandle, paths: Vec<PathBuf>) {
+    if paths.is_empty() {
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        let summary = ingest(app_state(), paths, None).await;
+        info!(
+            "Dropped {} files: {} copied, {} indexed",
+            summary.files.len(),
+            summary.copied,
+            summary.indexed
+        );
+        if let Err(e) = app.emit("files-dropped", &summary) {
+            warn!("Failed to emit dropped files: {}", e);
+        }
+    });
+}
+
+/// Ingests `paths` as if they were dropped onto the window. `copy`
+/// overrides `workspace.copy_dropped_files`.
+#[command]
+pub async fn ingest_dropped_files(
+    app: AppHandle,
+    paths: Vec<String>,
+    copy: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<DropSummary, String> {
+    let paths = paths.into_iter().map(PathBuf::from).collect();
+    let summary = ingest(&state, paths, copy).await;
+    if let Err(e) = app.emit("files-dropped", &summary) {
+        warn!("Failed to emit dropped files: {}", e);
+    }
+    Ok(summary)
+}
@@ -0,0 +1,206 @@
+// src/context/filters.rs
+
+use chrono::Utc;
+use glob::Pattern;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::pruning;
+use crate::commands::fs::{resolve_workspace_path, workspace_relative_path};
+use crate::config::ContextConfig;
+
+/// Generated files whose embeddings are noise: lockfiles, minified and
+/// bundled code, source maps.
+const DEFAULT_SKIP_PATTERNS: &[&str] = &[
+    "**/package-lock.json",
+    "**/npm-shrinkwrap.json",
+    "**/yarn.lock",
+    "**/pnpm-lock.yaml",
+    "**/bun.lockb",
+    "**/Cargo.lock",
+    "**/poetry.lock",
+    "**/Pipfile.lock",
+    "**/uv.lock",
+    "**/Gemfile.lock",
+    "**/composer.lock",
+    "**/go.sum",
+    "**/*.min.js",
+    "**/*.min.css",
+    "**/*.bundle.js",
+    "**/*.map",
+];
+const DEFAULT_MAX_FILE_BYTES: u64 = 1024 * 1024;
+/// A file with a line this long, or lines this long on average, is taken
+/// to be minified.
+const MINIFIED_MAX_LINE: usize = 5000;
+const MINIFIED_AVERAGE_LINE: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SkipReason {
+    Pattern { pattern: String },
+    TooLarge { bytes: u64, limit: u64 },
+    Minified,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedFile {
+    /// Relative to the workspace root.
+    pub path: String,
+    pub reason: SkipReason,
+    /// Milliseconds since the Unix epoch.
+    pub skipped_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct FilterSettings {
+    skip_patterns: Vec<String>,
+    include_patterns: Vec<String>,
+    max_file_bytes: u64,
+    skip_minified: bool,
+}
+
+struct ContentFilters {
+    settings: FilterSettings,
+    skip: Vec<Pattern>,
+    include: Vec<Pattern>,
+}
+
+impl ContentFilters {
+    fn new(settings: FilterSettings) -> Self {
+        // Invalid globs are reported when the config is loaded
+        let compile = |globs: &[String]| {
+            globs
+                .iter()
+                .filter_map(|glob| Pattern::new(glob).ok())
+                .collect()
+        };
+        Self {
+            skip: compile(&settings.skip_patterns),
+            include: compile(&settings.include_patterns),
+            settings,
+        }
+    }
+}
+
+static FILTERS: Lazy<Mutex<ContentFilters>> =
+    Lazy::new(|| Mutex::new(ContentFilters::new(settings_from(None))));
+/// Files left out since the app started, by path.
+static SKIPPED: Lazy<Mutex<BTreeMap<String, SkippedFile>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+fn settings_from(config: Option<&ContextConfig>) -> FilterSettings {
+    FilterSettings {
+        skip_patterns: config
+            .and_then(|context| context.skip_patterns.clone())
+            .unwrap_or_else(|| {
+                DEFAULT_SKIP_PATTERNS
+                    .iter()
+                    .map(|pattern| pattern.to_string())
+                    .collect()
+            }),
+        include_patterns: config
+            .map(|context| context.include_patterns.clone())
+            .unwrap_or_default(),
+        max_file_bytes: config
+            .and_then(|context| context.max_file_bytes)
+            .unwrap_or(DEFAULT_MAX_FILE_BYTES),
+        skip_minified: config
+            .and_then(|context| context.skip_minified)
+            .unwrap_or(true),
+    }
+}
+
+/// Applies the `[context]` filter settings. Indexed files the new settings
+/// leave out are pruned.
+pub(crate) fn configure(config: Option<&ContextConfig>) {
+    let settings = settings_from(config);
+    let mut filters = FILTERS.lock();
+    if filters.settings == settings {
+        return;
+    }
+    *filters = ContentFilters::new(settings);
+    drop(filters);
+    pruning::recheck();
+}
+
+fn relative_path(path: &str) -> String {
+    resolve_workspace_path(path)
+        .map(|full_path| workspace_relative_path(&full_path))
+        .unwrap_or_else(|_| path.to_string())
+}
+
+fn looks_minified(content: &str) -> bool {
+    let (mut lines, mut longest) = (0, 0);
+    for line in content.lines() {
+        lines += 1;
+        longest = longest.max(line.len());
+    }
+    lines > 0 && (longest > MINIFIED_MAX_LINE || content.len() / lines > MINIFIED_AVERAGE_LINE)
+}
+
+/// Why a file of `bytes` at `path` is left out of the index, going by its
+/// path and size alone, or `None` to index it.
+pub(crate) fn skip_reason(path: &str, bytes: u64) -> Option<SkipReason> {
+    let relative = relative_path(path);
+    let filters = FILTERS.lock();
+    if filters
+        .include
+        .iter()
+        .any(|pattern| pattern.matches(&relative))
+    {
+        return None;
+    }
+    if let Some(pattern) = filters
+        .skip
+        .iter()
+        .find(|pattern| pattern.matches(&relative))
+    {
+        return Some(SkipReason::Pattern {
+            pattern: pattern.as_str().to_string(),
+        });
+    }
+    let limit = filters.settings.max_file_bytes;
+    (bytes > limit).then_some(SkipReason::TooLarge { bytes, limit })
+}
+
+/// Like `skip_reason`, also checking whether `content` looks minified.
+pub(crate) fn content_skip_reason(path: &str, content: &str) -> Option<SkipReason> {
+    if let Some(reason) = skip_reason(path, content.len() as u64) {
+        return Some(reason);
+    }
+    let filters = FILTERS.lock();
+    let included = filters
+        .include
+        .iter()
+        .any(|pattern| pattern.matches(&relative_path(path)));
+    (filters.settings.skip_minified && !included && looks_minified(content))
+        .then_some(SkipReason::Minified)
+}
+
+/// Notes that `path` was left out, for `get_skipped_files`.
+pub(crate) fn record_skipped(path: &str, reason: SkipReason) {
+    let path = relative_path(path);
+    SKIPPED.lock().insert(
+        path.clone(),
+        SkippedFile {
+            path,
+            reason,
+            skipped_at: Utc::now().timestamp_millis(),
+        },
+    );
+}
+
+/// Forgets that `path` was left out, once it's indexed after all.
+pub(crate) fn clear_skipped(path: &str) {
+    SKIPPED.lock().remove(&relative_path(path));
+}
+
+/// Files the content filters kept out of the index since the app started,
+/// and why.
+#[tauri::command]
+pub async fn get_skipped_files() -> Result<Vec<SkippedFile>, String> {
+    Ok(SKIPPED.lock().values().cloned().collect())
+}
@@ -0,0 +1,195 @@
+// src/context/health.rs
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::context::{add_to_context, chunk_records, remove_from_context};
+use super::context_manager::EMBEDDING_DIM;
+use super::pruning::full_path;
+use crate::commands::fs::resolve_workspace_path;
+
+/// An indexed file that is no longer on disk.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanedFile {
+    pub path: String,
+    pub chunks: usize,
+}
+
+/// A chunk whose embedding doesn't have the model's dimension, so it can't
+/// be compared with queries.
+#[derive(Debug, Clone, Serialize)]
+pub struct DimensionMismatch {
+    pub id: String,
+    pub path: String,
+    pub dimension: usize,
+}
+
+/// An id shared by several chunks, as left by an interrupted write.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateId {
+    pub id: String,
+    pub count: usize,
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexHealthReport {
+    pub healthy: bool,
+    pub total_chunks: usize,
+    pub total_files: usize,
+    pub expected_dimension: usize,
+    pub orphaned_files: Vec<OrphanedFile>,
+    pub dimension_mismatches: Vec<DimensionMismatch>,
+    pub duplicate_ids: Vec<DuplicateId>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RepairFailure {
+    pub path: String,
+    pub error: String,
+}
+
+/// What `repair_context_index` did, or with `dry_run` would do.
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexRepairReport {
+    pub dry_run: bool,
+    /// The problems found before repairing.
+    pub report: IndexHealthReport,
+    /// Orphaned files whose chunks were dropped.
+    pub removed_files: Vec<String>,
+    /// Files with broken chunks that were dropped and indexed again from disk.
+    pub reindexed_files: Vec<String>,
+    pub failed: Vec<RepairFailure>,
+}
+
+async fn check_index() -> Result<IndexHealthReport, String> {
+    let records = chunk_records().await?;
+    let expected_dimension = EMBEDDING_DIM as usize;
+
+    let mut chunks_by_file: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut paths_by_id: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    let mut dimension_mismatches = Vec::new();
+    for record in &records {
+        *chunks_by_file.entry(&record.file_path).or_default() += 1;
+        paths_by_id
+            .entry(&record.id)
+            .or_default()
+            .push(&record.file_path);
+        if record.dimension != expected_dimension {
+            dimension_mismatches.push(DimensionMismatch {
+                id: record.id.clone(),
+                path: record.file_path.clone(),
+                dimension: record.dimension,
+            });
+        }
+    }
+
+    let orphaned_files: Vec<OrphanedFile> = chunks_by_file
+        .iter()
+        .filter(|(path, _)| !full_path(path).exists())
+        .map(|(path, chunks)| OrphanedFile {
+            path: path.to_string(),
+            chunks: *chunks,
+        })
+        .collect();
+    let duplicate_ids: Vec<DuplicateId> = paths_by_id
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|(id, mut paths)| {
+            let count = paths.len();
+            paths.sort();
+            paths.dedup();
+            DuplicateId {
+                id: id.to_string(),
+                count,
+                paths: paths.into_iter().map(str::to_string).collect(),
+            }
+        })
+        .collect();
+
+    Ok(IndexHealthReport {
+        healthy: orphaned_files.is_empty()
+            && dimension_mismatches.is_empty()
+            && duplicate_ids.is_empty(),
+        total_chunks: records.len(),
+        total_files: chunks_by_file.len(),
+        expected_dimension,
+        orphaned_files,
+        dimension_mismatches,
+        duplicate_ids,
+    })
+}
+
+/// Checks the context index for chunks of files no longer on disk,
+/// embeddings of the wrong dimension and ids used more than once. Nothing
+/// is changed; `repair_context_index` fixes what this finds.
+#[tauri::command]
+pub async fn verify_context_index() -> Result<IndexHealthReport, String> {
+    check_index().await
+}
+
+/// Fixes what `verify_context_index` finds: orphaned files are dropped from
+/// the index, and files with broken chunks are dropped and indexed again
+/// from disk. With `dry_run`, only reports what would be done.
+#[tauri::command]
+pub async fn repair_context_index(dry_run: Option<bool>) -> Result<IndexRepairReport, String> {
+    let dry_run = dry_run.unwrap_or(false);
+    let report = check_index().await?;
+
+    let removed: BTreeSet<String> = report
+        .orphaned_files
+        .iter()
+        .map(|file| file.path.clone())
+        .collect();
+    let broken: BTreeSet<String> = report
+        .dimension_mismatches
+        .iter()
+        .map(|chunk| chunk.path.clone())
+        .chain(
+            report
+                .duplicate_ids
+                .iter()
+                .flat_map(|duplicate| duplicate.paths.iter().cloned()),
+        )
+        .filter(|path| !removed.contains(path))
+        .collect();
+
+    let mut removed_files = Vec::new();
+    let mut reindexed_files = Vec::new();
+    let mut failed = Vec::new();
+    if dry_run {
+        removed_files.extend(removed);
+        reindexed_files.extend(broken);
+    } else {
+        for path in removed {
+            match remove_from_context(&path).await {
+                Ok(_) => removed_files.push(path),
+                Err(error) => failed.push(RepairFailure { path, error }),
+            }
+        }
+        for path in broken {
+            let result = async {
+                let full_path =
+                    resolve_workspace_path(&path).map_err(|e| e.message().to_string())?;
+                let content = tokio::fs::read_to_string(&full_path)
+                    .await
+                    .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+                remove_from_context(&path).await?;
+                add_to_context(path.clone(), content).await
+            }
+            .await;
+            match result {
+                Ok(()) => reindexed_files.push(path),
+                Err(error) => failed.push(RepairFailure { path, error }),
+            }
+        }
+    }
+
+    Ok(IndexRepairReport {
+        dry_run,
+        report,
+        removed_files,
+        reindexed_files,
+        failed,
+    })
+}
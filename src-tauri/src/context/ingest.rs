@@ -0,0 +1,191 @@
+// src/context/ingest.rs
+
+use ::arrow::array::{RecordBatch, RecordBatchIterator};
+use anyhow::{anyhow, Result};
+use futures::TryStreamExt;
+use lancedb::query::{ExecutableQuery, QueryBase};
+use lancedb::table::Table;
+use std::collections::HashSet;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, warn};
+
+/// Writes taken off the queue at once; consecutive inserts among them go
+/// to LanceDB as one.
+const MAX_BATCH: usize = 32;
+
+struct PendingAdd {
+    path: String,
+    batch: RecordBatch,
+    reply: oneshot::Sender<Result<(), String>>,
+}
+
+enum IngestOp {
+    Add(PendingAdd),
+    Delete {
+        path: String,
+        reply: oneshot::Sender<Result<usize, String>>,
+    },
+}
+
+/// Serializes every write to the chunk table through one task, so
+/// concurrent indexing can't interleave a file's check and insert or race
+/// a delete. Each caller waits for its own write to land.
+pub struct IngestQueue {
+    tx: mpsc::UnboundedSender<IngestOp>,
+}
+
+impl IngestQueue {
+    pub fn start(table: Table) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tauri::async_runtime::spawn(run(table, rx));
+        Self { tx }
+    }
+
+    /// Inserts a file's chunks, failing if the file is already in the table.
+    pub async fn add(&self, path: &str, batch: RecordBatch) -> Result<()> {
+        let (reply, response) = oneshot::channel();
+        let op = IngestOp::Add(PendingAdd {
+            path: path.to_string(),
+            batch,
+            reply,
+        });
+        self.tx
+            .send(op)
+            .map_err(|_| anyhow!("The ingest queue has stopped"))?;
+        response
+            .await
+            .map_err(|_| anyhow!("The ingest queue has stopped"))?
+            .map_err(|e| anyhow!(e))
+    }
+
+    /// Deletes a file's chunks, returning how many there were.
+    pub async fn delete(&self, path: &str) -> Result<usize> {
+        let (reply, response) = oneshot::channel();
+        let op = IngestOp::Delete {
+            path: path.to_string(),
+            reply,
+        };
+        self.tx
+            .send(op)
+            .map_err(|_| anyhow!("The ingest queue has stopped"))?;
+        response
+            .await
+            .map_err(|_| anyhow!("The ingest queue has stopped"))?
+            .map_err(|e| anyhow!(e))
+    }
+}
+
+fn path_filter(path: &str) -> String {
+    format!("file_path = '{}'", path.replace('\'', "''"))
+}
+
+/// Whether the table has chunks of `path`.
+pub async fn contains(table: &Table, path: &str) -> Result<bool> {
+    let mut stream = table
+        .query()
+        .only_if(path_filter(path))
+        .limit(1)
+        .execute()
+        .await?;
+
+    while let Some(batch) = stream.try_next().await? {
+        if batch.num_rows() > 0 {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+async fn run(table: Table, mut rx: mpsc::UnboundedReceiver<IngestOp>) {
+    while let Some(op) = rx.recv().await {
+        let mut ops = vec![op];
+        while ops.len() < MAX_BATCH {
+            match rx.try_recv() {
+                Ok(op) => ops.push(op),
+                Err(_) => break,
+            }
+        }
+
+        // Deletes keep their place between the inserts around them
+        let mut adds = Vec::new();
+        for op in ops {
+            match op {
+                IngestOp::Add(add) => adds.push(add),
+                IngestOp::Delete { path, reply } => {
+                    write_adds(&table, std::mem::take(&mut adds)).await;
+                    let _ = reply.send(delete(&table, &path).await.map_err(|e| e.to_string()));
+                }
+            }
+        }
+        write_adds(&table, adds).await;
+    }
+    debug!("The ingest queue stopped");
+}
+
+async fn delete(table: &Table, path: &str) -> Result<usize> {
+    let filter = path_filter(path);
+    let chunks = table.count_rows(Some(filter.clone())).await?;
+    if chunks > 0 {
+        table.delete(&filter).await?;
+    }
+    Ok(chunks)
+}
+
+async fn insert(table: &Table, batches: Vec<RecordBatch>) -> Result<()> {
+    let Some(schema) = batches.first().map(|batch| batch.schema()) else {
+        return Ok(());
+    };
+    let batches = RecordBatchIterator::new(batches.into_iter().map(Ok), schema);
+    table.add(batches).execute().await?;
+    Ok(())
+}
+
+/// Inserts `adds` together, after turning away files already in the table.
+/// If the combined insert fails, each file is retried alone so only the
+/// ones at fault fail.
+async fn write_adds(table: &Table, adds: Vec<PendingAdd>) {
+    let mut accepted = Vec::new();
+    let mut seen = HashSet::new();
+    for add in adds {
+        let result = match contains(table, &add.path).await {
+            Ok(false) if seen.insert(add.path.clone()) => {
+                accepted.push(add);
+                continue;
+            }
+            Ok(_) => Err(format!("File {} is already in context", add.path)),
+            Err(e) => Err(e.to_string()),
+        };
+        let _ = add.reply.send(result);
+    }
+    if accepted.is_empty() {
+        return;
+    }
+
+    let batches = accepted.iter().map(|add| add.batch.clone()).collect();
+    match insert(table, batches).await {
+        Ok(()) => {
+            for add in accepted {
+                let _ = add.reply.send(Ok(()));
+            }
+        }
+        Err(e) if accepted.len() > 1 => {
+            warn!(
+                "Inserting {} files at once failed, retrying one by one: {}",
+                accepted.len(),
+                e
+            );
+            for add in accepted {
+                let result = insert(table, vec![add.batch])
+                    .await
+                    .map_err(|e| e.to_string());
+                let _ = add.reply.send(result);
+            }
+        }
+        Err(e) => {
+            for add in accepted {
+                let _ = add.reply.send(Err(e.to_string()));
+            }
+        }
+    }
+}
@@ -0,0 +1,520 @@
+// src/context/lsp_symbols.rs
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::{oneshot, Mutex};
+use tracing::{info, warn};
+
+use super::context_manager::SymbolKind;
+use crate::commands::exec::find_executable;
+use crate::commands::fs::get_project_root;
+
+/// How long to wait for a server to answer a single request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Running servers keyed by language id. `None` marks a language whose server
+/// is missing or failed to start, so it isn't retried for every file.
+static SERVERS: Lazy<Mutex<ServerMap>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+type ServerMap = HashMap<&'static str, Option<Arc<LspServer>>>;
+
+/// A symbol reported by `textDocument/documentSymbol`, flattened out of the
+/// server's hierarchy. Lines and columns are 0-based and the end is inclusive.
+#[derive(Debug, Clone)]
+pub struct LspSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub container_name: Option<String>,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub start_col: usize,
+    pub end_col: usize,
+}
+
+/// One edit of a `WorkspaceEdit`. Lines are 0-based and columns count
+/// UTF-16 code units, as in the protocol.
+#[derive(Debug, Clone)]
+pub struct LspTextEdit {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub start_col: usize,
+    pub end_col: usize,
+    pub new_text: String,
+}
+
+struct ServerSpec {
+    language_id: &'static str,
+    command: &'static str,
+    args: &'static [&'static str],
+}
+
+fn server_for(path: &str) -> Option<ServerSpec> {
+    let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+    let (language_id, command, args): (_, _, &'static [&'static str]) = match extension.as_str() {
+        "rs" => ("rust", "rust-analyzer", &[]),
+        "ts" => ("typescript", "typescript-language-server", &["--stdio"]),
+        "tsx" => (
+            "typescriptreact",
+            "typescript-language-server",
+            &["--stdio"],
+        ),
+        "js" | "mjs" | "cjs" => ("javascript", "typescript-language-server", &["--stdio"]),
+        "jsx" => (
+            "javascriptreact",
+            "typescript-language-server",
+            &["--stdio"],
+        ),
+        "py" => ("python", "pylsp", &[]),
+        "go" => ("go", "gopls", &[]),
+        _ => return None,
+    };
+    Some(ServerSpec {
+        language_id,
+        command,
+        args,
+    })
+}
+
+fn file_uri(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    } else {
+        format!("file:///{}", path)
+    }
+}
+
+/// The path of a `file://` URI, undoing the percent-encoding servers apply.
+fn path_from_uri(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut rest = encoded.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = (byte == b'%')
+            .then(|| tail.get(..2))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match decoded {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // `file:///C:/...` on Windows
+    let path = match path.strip_prefix('/') {
+        Some(windows) if windows.get(1..2) == Some(":") => windows.to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+fn map_kind(kind: u64) -> SymbolKind {
+    // Numbering from the LSP specification
+    match kind {
+        1 => SymbolKind::File,
+        2..=4 => SymbolKind::Module,
+        5 | 19 => SymbolKind::Class,
+        6 | 9 => SymbolKind::Method,
+        7 | 8 => SymbolKind::Property,
+        10 => SymbolKind::Enum,
+        11 => SymbolKind::Interface,
+        12 => SymbolKind::Function,
+        14 | 22 => SymbolKind::Constant,
+        23 => SymbolKind::Struct,
+        _ => SymbolKind::Variable,
+    }
+}
+
+/// Returns (start_line, end_line, start_col, end_col) of an LSP `Range`.
+fn range_of(range: &Value) -> (usize, usize, usize, usize) {
+    let field = |value: &Value, key: &str| value[key].as_u64().unwrap_or(0) as usize;
+    (
+        field(&range["start"], "line"),
+        field(&range["end"], "line"),
+        field(&range["start"], "character"),
+        field(&range["end"], "character"),
+    )
+}
+
+/// The text edits of a `WorkspaceEdit` by file, from either its `changes` or
+/// its `documentChanges`. File operations are ignored.
+fn workspace_edits(edit: &Value) -> HashMap<PathBuf, Vec<LspTextEdit>> {
+    let mut by_file: HashMap<PathBuf, Vec<LspTextEdit>> = HashMap::new();
+    let mut add = |uri: &str, edits: &Value| {
+        let Some(path) = path_from_uri(uri) else {
+            return;
+        };
+        let edits = edits.as_array().into_iter().flatten().map(|edit| {
+            let (start_line, end_line, start_col, end_col) = range_of(&edit["range"]);
+            LspTextEdit {
+                start_line,
+                end_line,
+                start_col,
+                end_col,
+                new_text: edit["newText"].as_str().unwrap_or_default().to_string(),
+            }
+        });
+        by_file.entry(path).or_default().extend(edits);
+    };
+
+    if let Some(changes) = edit["documentChanges"].as_array() {
+        for change in changes.iter().filter(|change| change.get("kind").is_none()) {
+            if let Some(uri) = change["textDocument"]["uri"].as_str() {
+                add(uri, &change["edits"]);
+            }
+        }
+    } else if let Some(changes) = edit["changes"].as_object() {
+        for (uri, edits) in changes {
+            add(uri, edits);
+        }
+    }
+    by_file
+}
+
+/// Flattens either `DocumentSymbol[]` (hierarchical) or `SymbolInformation[]`.
+fn flatten_symbols(value: &Value, container: Option<&str>, out: &mut Vec<LspSymbol>) {
+    let Some(items) = value.as_array() else {
+        return;
+    };
+    for item in items {
+        let Some(name) = item["name"].as_str() else {
+            continue;
+        };
+        let range = if item["range"].is_object() {
+            &item["range"]
+        } else {
+            &item["location"]["range"]
+        };
+        let (start_line, end_line, start_col, end_col) = range_of(range);
+        let container_name = item["containerName"]
+            .as_str()
+            .or(container)
+            .filter(|c| !c.is_empty())
+            .map(String::from);
+
+        out.push(LspSymbol {
+            name: name.to_string(),
+            kind: map_kind(item["kind"].as_u64().unwrap_or(0)),
+            container_name,
+            start_line,
+            end_line,
+            start_col,
+            end_col,
+        });
+        flatten_symbols(&item["children"], Some(name), out);
+    }
+}
+
+struct LspServer {
+    child: Mutex<Child>,
+    stdin: Mutex<ChildStdin>,
+    pending: Arc<parking_lot::Mutex<HashMap<i64, oneshot::Sender<Value>>>>,
+    next_id: AtomicI64,
+}
+
+async fn write_message(stdin: &mut ChildStdin, message: &Value) -> Result<()> {
+    let body = message.to_string();
+    stdin
+        .write_all(format!("Content-Length: {}\r\n\r\n{}", body.len(), body).as_bytes())
+        .await?;
+    stdin.flush().await?;
+    Ok(())
+}
+
+async fn read_message(reader: &mut BufReader<ChildStdout>) -> Result<Value> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await? == 0 {
+            return Err(anyhow!("Language server closed its output"));
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = Some(value.trim().parse::<usize>()?);
+        }
+    }
+    let mut body = vec![0; length.ok_or_else(|| anyhow!("Missing Content-Length header"))?];
+    reader.read_exact(&mut body).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+impl LspServer {
+    async fn start(spec: &ServerSpec) -> Result<Arc<Self>> {
+        let language_id = spec.language_id;
+        let program = find_executable(spec.command)
+            .ok_or_else(|| anyhow!("{} not found on PATH", spec.command))?;
+        let root = get_project_root();
+        let mut child = Command::new(program)
+            .args(spec.args)
+            .current_dir(&root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        let stdin = child.stdin.take().ok_or_else(|| anyhow!("No stdin"))?;
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("No stdout"))?;
+        let server = Arc::new(Self {
+            child: Mutex::new(child),
+            stdin: Mutex::new(stdin),
+            pending: Arc::new(parking_lot::Mutex::new(HashMap::new())),
+            next_id: AtomicI64::new(1),
+        });
+
+        let reader_server = Arc::downgrade(&server);
+        let pending = server.pending.clone();
+        tokio::spawn(async move {
+            let mut reader = BufReader::new(stdout);
+            while let Ok(message) = read_message(&mut reader).await {
+                let Some(server) = reader_server.upgrade() else {
+                    break;
+                };
+                match (message.get("id"), message.get("method")) {
+                    // Request from the server; answer so it doesn't block on us
+                    (Some(id), Some(method)) => {
+                        let result = if method == "workspace/configuration" {
+                            let items = message["params"]["items"].as_array().map_or(0, Vec::len);
+                            Value::Array(vec![Value::Null; items])
+                        } else {
+                            Value::Null
+                        };
+                        let reply = json!({ "jsonrpc": "2.0", "id": id, "result": result });
+                        let _ = write_message(&mut *server.stdin.lock().await, &reply).await;
+                    }
+                    (Some(id), None) => {
+                        if let Some(sender) = id.as_i64().and_then(|id| pending.lock().remove(&id))
+                        {
+                            let _ = sender.send(message);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            // The server is gone: fail outstanding requests right away and let
+            // the next lookup start a fresh one
+            pending.lock().clear();
+            let mut servers = SERVERS.lock().await;
+            if let Some(Some(current)) = servers.get(language_id) {
+                if std::ptr::eq(Arc::as_ptr(current), reader_server.as_ptr()) {
+                    servers.remove(language_id);
+                }
+            }
+        });
+
+        server
+            .request(
+                "initialize",
+                json!({
+                    "processId": std::process::id(),
+                    "rootUri": file_uri(&root),
+                    "capabilities": {
+                        "textDocument": {
+                            "documentSymbol": { "hierarchicalDocumentSymbolSupport": true },
+                            "rename": {}
+                        },
+                        "workspace": {
+                            "workspaceEdit": { "documentChanges": true }
+                        }
+                    }
+                }),
+            )
+            .await?;
+        server.notify("initialized", json!({})).await?;
+        Ok(server)
+    }
+
+    async fn notify(&self, method: &str, params: Value) -> Result<()> {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        write_message(&mut *self.stdin.lock().await, &message).await
+    }
+
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().insert(id, sender);
+
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        write_message(&mut *self.stdin.lock().await, &message).await?;
+
+        let response = match tokio::time::timeout(REQUEST_TIMEOUT, receiver).await {
+            Ok(response) => response?,
+            Err(_) => {
+                self.pending.lock().remove(&id);
+                return Err(anyhow!("{} timed out", method));
+            }
+        };
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("{} failed: {}", method, error));
+        }
+        Ok(response["result"].clone())
+    }
+}
+
+async fn server_for_language(spec: &ServerSpec) -> Option<Arc<LspServer>> {
+    let mut servers = SERVERS.lock().await;
+    if let Some(server) = servers.get(spec.language_id) {
+        return server.clone();
+    }
+
+    let server = match LspServer::start(spec).await {
+        Ok(server) => Some(server),
+        Err(e) => {
+            info!(
+                "No language server for {} ({}); using regex symbols",
+                spec.language_id, e
+            );
+            None
+        }
+    };
+    servers.insert(spec.language_id, server.clone());
+    server
+}
+
+/// Asks the language server for `path` for its document symbols. Returns
+/// `None` when no server is available or it fails, so callers can fall back
+/// to regex extraction.
+pub async fn document_symbols(path: &str, content: &str) -> Option<Vec<LspSymbol>> {
+    let spec = server_for(path)?;
+    let server = server_for_language(&spec).await?;
+    let uri = file_uri(&get_project_root().join(path));
+
+    let opened = server
+        .notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": uri,
+                    "languageId": spec.language_id,
+                    "version": 1,
+                    "text": content
+                }
+            }),
+        )
+        .await;
+    if let Err(e) = opened {
+        warn!("Failed to open {} in language server: {}", path, e);
+        return None;
+    }
+
+    let result = server
+        .request(
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": uri } }),
+        )
+        .await;
+    let _ = server
+        .notify(
+            "textDocument/didClose",
+            json!({ "textDocument": { "uri": uri } }),
+        )
+        .await;
+
+    match result {
+        Ok(value) => {
+            let mut symbols = Vec::new();
+            flatten_symbols(&value, None, &mut symbols);
+            Some(symbols)
+        }
+        Err(e) => {
+            warn!("documentSymbol failed for {}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Asks the language server for `path` to rename the symbol at `line` and
+/// `character` (0-based, UTF-16) to `new_name`. Returns the edits by
+/// absolute path, or `None` when no server is available or it can't rename
+/// there, so callers can fall back to text replacement.
+pub async fn rename(
+    path: &str,
+    content: &str,
+    line: usize,
+    character: usize,
+    new_name: &str,
+) -> Option<HashMap<PathBuf, Vec<LspTextEdit>>> {
+    let spec = server_for(path)?;
+    let server = server_for_language(&spec).await?;
+    let uri = file_uri(&get_project_root().join(path));
+
+    let opened = server
+        .notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": uri,
+                    "languageId": spec.language_id,
+                    "version": 1,
+                    "text": content
+                }
+            }),
+        )
+        .await;
+    if let Err(e) = opened {
+        warn!("Failed to open {} in language server: {}", path, e);
+        return None;
+    }
+
+    let result = server
+        .request(
+            "textDocument/rename",
+            json!({
+                "textDocument": { "uri": uri },
+                "position": { "line": line, "character": character },
+                "newName": new_name
+            }),
+        )
+        .await;
+    let _ = server
+        .notify(
+            "textDocument/didClose",
+            json!({ "textDocument": { "uri": uri } }),
+        )
+        .await;
+
+    match result {
+        Ok(Value::Null) => None,
+        Ok(edit) => {
+            let edits = workspace_edits(&edit);
+            (!edits.is_empty()).then_some(edits)
+        }
+        Err(e) => {
+            warn!("rename failed for {}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// Stops every running language server.
+pub async fn shutdown_servers() {
+    let servers: Vec<Arc<LspServer>> = SERVERS
+        .lock()
+        .await
+        .drain()
+        .filter_map(|(_, s)| s)
+        .collect();
+    for server in servers {
+        let _ = server.request("shutdown", Value::Null).await;
+        let _ = server.notify("exit", Value::Null).await;
+        let _ = server.child.lock().await.kill().await;
+    }
+}
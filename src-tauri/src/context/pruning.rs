@@ -0,0 +1,237 @@
+// src/context/pruning.rs
+
+use glob::Pattern;
+use notify::event::ModifyKind;
+use notify::EventKind;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter};
+use tracing::{debug, info, warn};
+
+use super::context::{indexed_files, remove_from_context};
+use super::filters;
+use crate::commands::fs::{
+    get_project_root, resolve_workspace_path, workspace_relative_path, FileWatcher,
+};
+use crate::config::WorkspaceConfig;
+
+/// How long deletions are collected before pruning, so removing a directory
+/// scans the index once rather than once per file.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static WATCHER: Lazy<Mutex<Option<FileWatcher>>> = Lazy::new(|| Mutex::new(None));
+/// The `workspace.ignore` globs last applied.
+static IGNORE: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+/// Deleted paths waiting to be pruned; `None` when no prune is scheduled.
+static PENDING: Lazy<Mutex<Option<HashSet<PathBuf>>>> = Lazy::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum PruneReason {
+    Deleted,
+    /// Ignored by `workspace.ignore` or the content filters, or outside the
+    /// workspace.
+    Ignored,
+    /// Evicted to bring the index back under `budget.max_index_mb`.
+    OverBudget,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ContextPrunedEvent {
+    reason: PruneReason,
+    /// As they were indexed.
+    paths: Vec<String>,
+    chunks: usize,
+}
+
+/// Where an indexed path is on disk; paths are indexed as given, relative
+/// to the workspace or absolute.
+pub(super) fn full_path(indexed: &str) -> PathBuf {
+    if Path::new(indexed).is_absolute() {
+        PathBuf::from(indexed)
+    } else {
+        get_project_root().join(indexed)
+    }
+}
+
+/// Removes the indexed files `should_prune` picks and tells the frontend
+/// which, returning how many.
+async fn prune(reason: PruneReason, should_prune: impl Fn(&str) -> bool) -> usize {
+    let files = match indexed_files().await {
+        Ok(files) => files,
+        Err(e) => {
+            debug!("Not pruning the context: {}", e);
+            return 0;
+        }
+    };
+
+    let mut paths = Vec::new();
+    let mut chunks = 0;
+    for file in files.into_iter().filter(|file| should_prune(file)) {
+        match remove_from_context(&file).await {
+            Ok(removed) => {
+                chunks += removed;
+                paths.push(file);
+            }
+            Err(e) => warn!("Failed to remove {} from the context: {}", file, e),
+        }
+    }
+    if paths.is_empty() {
+        return 0;
+    }
+
+    info!(
+        "Pruned {} files ({} chunks) from the context: {:?}",
+        paths.len(),
+        chunks,
+        reason
+    );
+    let pruned = paths.len();
+    let Some(app) = APP.get() else {
+        return pruned;
+    };
+    let event = ContextPrunedEvent {
+        reason,
+        paths,
+        chunks,
+    };
+    if let Err(e) = app.emit("context-pruned", &event) {
+        warn!("Failed to emit context-pruned: {}", e);
+    }
+    pruned
+}
+
+/// Drops indexed files at or under `removed` that are gone from disk.
+async fn prune_deleted(removed: HashSet<PathBuf>) {
+    prune(PruneReason::Deleted, |file| {
+        let full_path = full_path(file);
+        !full_path.exists() && removed.iter().any(|path| full_path.starts_with(path))
+    })
+    .await;
+}
+
+/// Drops indexed files the workspace no longer covers: those now ignored
+/// or left out by the content filters, and those outside of it.
+async fn prune_ignored() {
+    // Invalid globs are reported when the config is loaded
+    let patterns: Vec<Pattern> = IGNORE
+        .lock()
+        .iter()
+        .filter_map(|glob| Pattern::new(glob).ok())
+        .collect();
+    prune(PruneReason::Ignored, |file| {
+        let Ok(full_path) = resolve_workspace_path(file) else {
+            return true;
+        };
+        let relative = workspace_relative_path(&full_path);
+        let bytes = full_path.metadata().map_or(0, |metadata| metadata.len());
+        patterns.iter().any(|pattern| pattern.matches(&relative))
+            || filters::skip_reason(&relative, bytes).is_some()
+    })
+    .await;
+}
+
+/// Evicts the `count` indexed files changed least recently on disk, those
+/// gone from it first, to make room in the index. They are indexed again
+/// when next opened. Returns how many were evicted.
+pub(crate) async fn evict_coldest(count: usize) -> Result<usize, String> {
+    let mut files: Vec<(Option<SystemTime>, String)> = indexed_files()
+        .await?
+        .into_iter()
+        .map(|file| {
+            let modified = full_path(&file)
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            (modified, file)
+        })
+        .collect();
+    // Files without a modification time, i.e. gone, sort first
+    files.sort();
+    let coldest: HashSet<String> = files
+        .into_iter()
+        .take(count)
+        .map(|(_, file)| file)
+        .collect();
+    Ok(prune(PruneReason::OverBudget, |file| coldest.contains(file)).await)
+}
+
+/// Queues paths reported deleted or renamed away, pruning them together
+/// once the changes settle.
+fn queue_deleted(paths: Vec<PathBuf>) {
+    let mut pending = PENDING.lock();
+    if let Some(pending) = pending.as_mut() {
+        pending.extend(paths);
+        return;
+    }
+    *pending = Some(paths.into_iter().collect());
+    tauri::async_runtime::spawn(async {
+        tokio::time::sleep(DEBOUNCE).await;
+        let removed = PENDING.lock().take().unwrap_or_default();
+        prune_deleted(removed).await;
+    });
+}
+
+/// Watches the workspace so files deleted from it, or renamed away, leave
+/// the index too and search never returns code that no longer exists. Files
+/// ignored since the last run are pruned right away. Each prune is announced
+/// with `context-pruned`.
+pub(crate) fn start(app: AppHandle) {
+    if APP.set(app).is_err() {
+        return;
+    }
+    let watcher = FileWatcher::with_handler(|event| {
+        if !matches!(
+            event.kind,
+            EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_))
+        ) {
+            return;
+        }
+        let gone: Vec<PathBuf> = event
+            .paths
+            .into_iter()
+            .filter(|path| !path.exists())
+            .collect();
+        if !gone.is_empty() {
+            queue_deleted(gone);
+        }
+    });
+    let result = watcher.and_then(|mut watcher| {
+        watcher.watch(get_project_root())?;
+        Ok(watcher)
+    });
+    match result {
+        Ok(watcher) => *WATCHER.lock() = Some(watcher),
+        Err(e) => warn!("Failed to watch the workspace for deletions: {}", e),
+    }
+    tauri::async_runtime::spawn(prune_ignored());
+}
+
+/// Applies `workspace.ignore`. Indexed files the new globs leave out of the
+/// workspace are pruned once the context is running.
+pub(crate) fn configure(config: Option<&WorkspaceConfig>) {
+    let globs = config
+        .map(|workspace| workspace.ignore.clone())
+        .unwrap_or_default();
+    let mut current = IGNORE.lock();
+    if *current == globs {
+        return;
+    }
+    *current = globs;
+    drop(current);
+    recheck();
+}
+
+/// Prunes files left out of the workspace by changed settings, once the
+/// context is running.
+pub(crate) fn recheck() {
+    if APP.get().is_some() {
+        tauri::async_runtime::spawn(prune_ignored());
+    }
+}
@@ -0,0 +1,367 @@
+// src/context/refactor.rs
+
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tauri::ipc::Request;
+use tauri::{AppHandle, State};
+use tracing::debug;
+use uuid::Uuid;
+
+use super::context::similar_chunks;
+use super::explain::complete;
+use super::symbol_index;
+use crate::commands::bookmarks::bookmarks_for_file;
+use crate::commands::diff::{compute_diff, TextDiffResult};
+use crate::commands::edit_transaction::{
+    begin_edit_transaction, commit_edit_transaction, rollback_edit_transaction, stage_edit,
+    TransactionSummary,
+};
+use crate::commands::fs::{read_file, resolve_workspace_path, workspace_relative_path};
+use crate::error::MightyError;
+use crate::state::AppState;
+
+const MAX_TOKENS: i32 = 16_384;
+/// Files shown to the model, best candidates first.
+const MAX_FILES: usize = 8;
+/// Larger files are left out rather than cut, since edits must quote them
+/// exactly.
+const MAX_FILE_CHARS: usize = 40_000;
+const CANDIDATE_CHUNKS: usize = 20;
+/// Plans kept for execution; the oldest is dropped past this.
+const MAX_PLANS: usize = 20;
+const INSTRUCTIONS: &str = "Plan the refactoring below across the files shown. Reply with only \
+a JSON object of the form {\"summary\": string, \"steps\": [{\"path\": string, \"description\": \
+string, \"edits\": [{\"find\": string, \"replace\": string}]}]}. Steps run in order, one file \
+each. Each `find` must quote a part of the file exactly, as it is after the earlier edits, and \
+match only once there; an empty `find` creates a new file with `replace` as its content. Leave \
+out files that need no change.";
+
+static PLANS: Lazy<Mutex<HashMap<String, RefactorPlan>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// One file's part of a plan.
+#[derive(Debug, Clone, Serialize)]
+pub struct RefactorStep {
+    /// Workspace-relative.
+    pub path: String,
+    pub description: String,
+    pub created: bool,
+    pub diff: TextDiffResult,
+    /// Why the step's edits couldn't be turned into a patch; a plan with a
+    /// failed step can't be executed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RefactorPlan {
+    pub plan_id: String,
+    pub instruction: String,
+    pub summary: String,
+    pub steps: Vec<RefactorStep>,
+    /// The files the model was shown.
+    pub files: Vec<String>,
+    pub model: String,
+    pub created_at: i64,
+}
+
+#[derive(Deserialize)]
+struct PlanReply {
+    #[serde(default)]
+    summary: String,
+    steps: Vec<StepReply>,
+}
+
+#[derive(Deserialize)]
+struct StepReply {
+    path: String,
+    #[serde(default)]
+    description: String,
+    edits: Vec<EditReply>,
+}
+
+#[derive(Deserialize)]
+struct EditReply {
+    find: String,
+    replace: String,
+}
+
+/// Workspace-relative paths of the files the instruction most likely
+/// touches: those defining names it mentions, then those holding the
+/// indexed code most similar to it.
+async fn candidate_files(instruction: &str) -> Result<Vec<String>, String> {
+    let identifier = Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").expect("the pattern is valid");
+    let names: HashSet<&str> = identifier
+        .find_iter(instruction)
+        .map(|name| name.as_str())
+        .filter(|name| name.len() >= 3)
+        .collect();
+    let mut files: Vec<String> = symbol_index::definitions_named(&names)?
+        .into_iter()
+        .map(|symbol| symbol.file)
+        .collect();
+
+    match similar_chunks(instruction, CANDIDATE_CHUNKS).await {
+        Ok(chunks) => files.extend(chunks.into_iter().map(|chunk| chunk.file_path)),
+        Err(e) => debug!("Planning without similar chunks: {}", e),
+    }
+
+    let mut seen = HashSet::new();
+    Ok(files
+        .into_iter()
+        .filter_map(|file| {
+            let full_path = resolve_workspace_path(&file).ok()?;
+            full_path
+                .is_file()
+                .then(|| workspace_relative_path(&full_path))
+        })
+        .filter(|file| seen.insert(file.clone()))
+        .collect())
+}
+
+/// The JSON object in a reply, fenced or not.
+fn parse_reply(reply: &str) -> Result<PlanReply, String> {
+    let (Some(start), Some(end)) = (reply.find('{'), reply.rfind('}')) else {
+        return Err("The model's reply has no plan in it".to_string());
+    };
+    serde_json::from_str(&reply[start..=end])
+        .map_err(|e| format!("The model's plan could not be read: {}", e))
+}
+
+/// Applies `edits` to `content` in order.
+fn apply_edits(content: &str, edits: &[EditReply]) -> Result<String, String> {
+    // Edits are written with `\n`; match the file's endings
+    let crlf = content.contains("\r\n");
+    let mut edited = content.to_string();
+    for edit in edits {
+        let (find, replace) = if crlf {
+            (
+                edit.find.replace("\r\n", "\n").replace('\n', "\r\n"),
+                edit.replace.replace("\r\n", "\n").replace('\n', "\r\n"),
+            )
+        } else {
+            (edit.find.clone(), edit.replace.clone())
+        };
+        if find.is_empty() {
+            return Err("An edit to an existing file has nothing to find".to_string());
+        }
+        match edited.matches(find.as_str()).count() {
+            1 => edited = edited.replacen(find.as_str(), &replace, 1),
+            0 => {
+                return Err(format!(
+                    "Text to replace was not found: {}",
+                    preview(&edit.find)
+                ))
+            }
+            n => {
+                return Err(format!(
+                    "Text to replace appears {} times: {}",
+                    n,
+                    preview(&edit.find)
+                ))
+            }
+        }
+    }
+    Ok(edited)
+}
+
+fn preview(text: &str) -> String {
+    text.lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or_default()
+        .trim()
+        .chars()
+        .take(80)
+        .collect()
+}
+
+/// Turns the model's steps into patches against `originals`, the files as
+/// they are now, following each file through the steps before it.
+async fn build_steps(
+    steps: Vec<StepReply>,
+    originals: &HashMap<String, String>,
+) -> Vec<RefactorStep> {
+    let mut current: HashMap<String, String> = HashMap::new();
+    let mut built = Vec::new();
+    for step in steps {
+        let (path, exists) = match resolve_workspace_path(&step.path) {
+            Ok(full_path) => (workspace_relative_path(&full_path), full_path.exists()),
+            Err(e) => {
+                built.push(RefactorStep {
+                    path: step.path,
+                    description: step.description,
+                    created: false,
+                    diff: compute_diff("", ""),
+                    error: Some(e.message().to_string()),
+                });
+                continue;
+            }
+        };
+
+        let before = match current.get(&path).or_else(|| originals.get(&path)) {
+            Some(content) => Ok(Some(content.clone())),
+            None if exists => read_file(path.clone())
+                .await
+                .map(|text| Some(text.content))
+                .map_err(|e| e.message().to_string()),
+            None => Ok(None),
+        };
+        let created = matches!(before, Ok(None));
+        let edited = match &before {
+            Ok(Some(content)) => apply_edits(content, &step.edits),
+            Ok(None) => match step.edits.as_slice() {
+                [edit] if edit.find.is_empty() => Ok(edit.replace.clone()),
+                _ => Err(format!("{} doesn't exist", path)),
+            },
+            Err(e) => Err(e.clone()),
+        };
+
+        let before = before.ok().flatten().unwrap_or_default();
+        let (diff, error) = match edited {
+            Ok(edited) => {
+                let diff = compute_diff(&before, &edited);
+                current.insert(path.clone(), edited);
+                (diff, None)
+            }
+            Err(e) => (compute_diff(&before, &before), Some(e)),
+        };
+        built.push(RefactorStep {
+            path,
+            description: step.description,
+            created,
+            diff,
+            error,
+        });
+    }
+    built
+}
+
+/// Plans a refactoring across the workspace: finds the files `instruction`
+/// likely touches through the symbol index and semantic search, asks the
+/// chat model for ordered per-file edits and turns them into patches to
+/// preview. Nothing is written until `execute_refactor` runs the plan.
+#[tauri::command]
+pub async fn plan_refactor(
+    app: AppHandle,
+    instruction: String,
+    state: State<'_, AppState>,
+) -> Result<RefactorPlan, MightyError> {
+    if instruction.trim().is_empty() {
+        return Err(MightyError::request(
+            "EMPTY_INSTRUCTION",
+            "Describe the refactoring to plan",
+        ));
+    }
+
+    let mut originals = HashMap::new();
+    let mut files = Vec::new();
+    for file in candidate_files(&instruction).await? {
+        if files.len() == MAX_FILES {
+            break;
+        }
+        let Ok(text) = read_file(file.clone()).await else {
+            continue;
+        };
+        if text.content.len() > MAX_FILE_CHARS {
+            debug!(
+                "Leaving {} out of the refactoring plan; it is too large",
+                file
+            );
+            continue;
+        }
+        originals.insert(file.clone(), text.content);
+        files.push(file);
+    }
+    if files.is_empty() {
+        return Err(MightyError::request(
+            "NO_CANDIDATE_FILES",
+            "No indexed files look related to the refactoring; index the workspace first",
+        ));
+    }
+
+    let mut prompt = format!("{}\n\nRefactoring: {}\n", INSTRUCTIONS, instruction.trim());
+    for file in &files {
+        prompt.push_str(&format!(
+            "\nFile: {}\n```\n{}\n```\n",
+            file, originals[file]
+        ));
+        for bookmark in bookmarks_for_file(file, &originals[file]) {
+            if !bookmark.note.is_empty() && !bookmark.orphaned {
+                prompt.push_str(&format!(
+                    "Note on lines {}-{}: {}\n",
+                    bookmark.start_line, bookmark.end_line, bookmark.note
+                ));
+            }
+        }
+    }
+    let (reply, model) = complete(app, state, prompt, MAX_TOKENS).await?;
+    let reply = parse_reply(&reply)?;
+
+    let plan = RefactorPlan {
+        plan_id: Uuid::new_v4().to_string(),
+        instruction,
+        summary: reply.summary,
+        steps: build_steps(reply.steps, &originals).await,
+        files,
+        model,
+        created_at: Utc::now().timestamp_millis(),
+    };
+
+    let mut plans = PLANS.lock();
+    if plans.len() >= MAX_PLANS {
+        let oldest = plans
+            .values()
+            .min_by_key(|plan| plan.created_at)
+            .map(|plan| plan.plan_id.clone());
+        if let Some(oldest) = oldest {
+            plans.remove(&oldest);
+        }
+    }
+    plans.insert(plan.plan_id.clone(), plan.clone());
+    Ok(plan)
+}
+
+/// Applies a plan from `plan_refactor` as one edit transaction: every patch
+/// is staged in order and then committed, so either all files change or,
+/// if any patch no longer applies or a write fails, none do.
+#[tauri::command]
+pub async fn execute_refactor(
+    app: AppHandle,
+    request: Request<'_>,
+    plan_id: String,
+) -> Result<TransactionSummary, MightyError> {
+    let plan = PLANS.lock().remove(&plan_id).ok_or_else(|| {
+        MightyError::request(
+            "PLAN_NOT_FOUND",
+            &format!("Refactor plan {} not found", plan_id),
+        )
+    })?;
+    if let Some(step) = plan.steps.iter().find(|step| step.error.is_some()) {
+        return Err(MightyError::request(
+            "PLAN_INCOMPLETE",
+            &format!(
+                "The step for {} failed and the plan can't be executed: {}",
+                step.path,
+                step.error.as_deref().unwrap_or_default()
+            ),
+        ));
+    }
+
+    let transaction_id = begin_edit_transaction().await?;
+    for step in plan.steps.iter().filter(|step| !step.diff.hunks.is_empty()) {
+        let staged = stage_edit(
+            transaction_id.clone(),
+            step.path.clone(),
+            step.diff.unified_diff.clone(),
+        )
+        .await;
+        if let Err(e) = staged {
+            rollback_edit_transaction(app, transaction_id).await?;
+            return Err(e.into());
+        }
+    }
+    Ok(commit_edit_transaction(app, request, transaction_id).await?)
+}
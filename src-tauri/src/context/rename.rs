@@ -0,0 +1,300 @@
+// src/context/rename.rs
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use tauri::AppHandle;
+use tracing::{debug, warn};
+
+use super::lsp_symbols::{self, LspTextEdit};
+use crate::commands::diff::{compute_diff, TextDiffResult};
+use crate::commands::edit_transaction::{
+    begin_edit_transaction, rollback_edit_transaction, stage_edit,
+};
+use crate::commands::exec::{find_system_executable, run_tool};
+use crate::commands::fs::{
+    get_project_root, read_file, resolve_workspace_path, workspace_relative_path,
+};
+use crate::error::MightyError;
+
+const TEXT_SEARCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A place in a file. Both are 1-based; the column counts characters.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenameMethod {
+    /// The language server worked out every reference.
+    Lsp,
+    /// Whole-word replacement in files of the same language; may touch
+    /// unrelated symbols of the same name.
+    Text,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenameFileEdit {
+    /// Workspace-relative.
+    pub path: String,
+    pub occurrences: usize,
+    pub diff: TextDiffResult,
+}
+
+/// A rename staged for review.
+#[derive(Debug, Clone, Serialize)]
+pub struct RenamePreview {
+    /// The edit transaction holding the changes; commit it to rename or roll
+    /// it back to drop it.
+    pub transaction_id: String,
+    pub old_name: String,
+    pub new_name: String,
+    pub method: RenameMethod,
+    pub files: Vec<RenameFileEdit>,
+    /// Files the language server wanted to change outside the workspace,
+    /// such as dependencies' sources; they are left alone.
+    pub skipped: Vec<String>,
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// The identifier covering character `column` (0-based) of `line` and the
+/// character index it starts at.
+fn identifier_at(line: &str, column: usize) -> Option<(String, usize)> {
+    let chars: Vec<char> = line.chars().collect();
+    // A cursor just after the name still points at it
+    let column = if chars.get(column).is_some_and(|c| is_identifier_char(*c)) {
+        column
+    } else {
+        column.checked_sub(1)?
+    };
+    if !chars.get(column).is_some_and(|c| is_identifier_char(*c)) {
+        return None;
+    }
+    let start = chars[..column]
+        .iter()
+        .rposition(|c| !is_identifier_char(*c))
+        .map_or(0, |i| i + 1);
+    let end = chars[column..]
+        .iter()
+        .position(|c| !is_identifier_char(*c))
+        .map_or(chars.len(), |i| column + i);
+    Some((chars[start..end].iter().collect(), start))
+}
+
+/// The byte offset of `line` and UTF-16 column `character` in `content`,
+/// clamped to the line's end as the protocol asks.
+fn offset_of(content: &str, line: usize, character: usize) -> usize {
+    let line_start: usize = content.split_inclusive('\n').take(line).map(str::len).sum();
+    let text = content[line_start..]
+        .split_inclusive('\n')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(['\n', '\r']);
+    let mut units = 0;
+    for (offset, c) in text.char_indices() {
+        if units >= character {
+            return line_start + offset;
+        }
+        units += c.len_utf16();
+    }
+    line_start + text.len()
+}
+
+/// `content` with the language server's `edits` applied.
+fn apply_lsp_edits(content: &str, edits: &[LspTextEdit]) -> String {
+    let mut ranges: Vec<(usize, usize, &str)> = edits
+        .iter()
+        .map(|edit| {
+            (
+                offset_of(content, edit.start_line, edit.start_col),
+                offset_of(content, edit.end_line, edit.end_col),
+                edit.new_text.as_str(),
+            )
+        })
+        .collect();
+    // From the end, so earlier offsets stay valid
+    ranges.sort_by(|a, b| b.0.cmp(&a.0));
+    let mut edited = content.to_string();
+    for (start, end, text) in ranges {
+        edited.replace_range(start..end.max(start), text);
+    }
+    edited
+}
+
+/// `content` with every whole-word `old_name` replaced, and how many there
+/// were.
+fn replace_word(content: &str, old_name: &str, new_name: &str) -> (String, usize) {
+    let mut edited = String::with_capacity(content.len());
+    let mut count = 0;
+    let mut last = 0;
+    for (offset, _) in content.match_indices(old_name) {
+        let before = content[..offset].chars().next_back();
+        let after = content[offset + old_name.len()..].chars().next();
+        if before.is_some_and(is_identifier_char) || after.is_some_and(is_identifier_char) {
+            continue;
+        }
+        edited.push_str(&content[last..offset]);
+        edited.push_str(new_name);
+        last = offset + old_name.len();
+        count += 1;
+    }
+    edited.push_str(&content[last..]);
+    (edited, count)
+}
+
+/// Workspace files of the same kind as `path` mentioning `name` as a whole
+/// word, found with ripgrep. Only `path` itself without ripgrep.
+async fn files_mentioning(path: &str, name: &str) -> Vec<String> {
+    let mut files = vec![path.to_string()];
+    let Some(program) = find_system_executable("rg") else {
+        debug!("rg was not found on PATH; renaming only in {}", path);
+        return files;
+    };
+    let mut args = vec![
+        "--files-with-matches".to_string(),
+        "--word-regexp".to_string(),
+        "--fixed-strings".to_string(),
+        "--case-sensitive".to_string(),
+    ];
+    if let Some(extension) = Path::new(path).extension() {
+        args.push("--glob".to_string());
+        args.push(format!("*.{}", extension.to_string_lossy()));
+    }
+    args.extend(["--".to_string(), name.to_string(), ".".to_string()]);
+
+    let root = get_project_root();
+    match run_tool(&program, &args, &root, None, TEXT_SEARCH_TIMEOUT).await {
+        Ok(output) => files.extend(
+            output
+                .stdout
+                .lines()
+                .map(|file| file.trim_start_matches("./").to_string())
+                .filter(|file| file != path),
+        ),
+        Err(e) => warn!("Failed to search for {}: {}", name, e),
+    }
+    files
+}
+
+/// Renames the symbol at `position` in `path` to `new_name`. The language
+/// server for the file does it when one is available, so only real
+/// references change; otherwise every whole-word occurrence in files of the
+/// same language is replaced. Nothing is written: the changes are staged in
+/// a new edit transaction and returned for review, to be applied with
+/// `commit_edit_transaction`.
+#[tauri::command]
+pub async fn rename_symbol(
+    app: AppHandle,
+    path: String,
+    position: Position,
+    new_name: String,
+) -> Result<RenamePreview, MightyError> {
+    let new_name = new_name.trim().to_string();
+    if new_name.is_empty() || !new_name.chars().all(is_identifier_char) {
+        return Err(MightyError::request(
+            "INVALID_NAME",
+            &format!("{} is not a valid name", new_name),
+        ));
+    }
+    let path = workspace_relative_path(&resolve_workspace_path(&path)?);
+    let content = read_file(path.clone()).await?.content;
+    let line = position
+        .line
+        .checked_sub(1)
+        .and_then(|line| content.lines().nth(line))
+        .unwrap_or_default();
+    let Some((old_name, start)) = identifier_at(line, position.column.saturating_sub(1)) else {
+        return Err(MightyError::request(
+            "NO_SYMBOL",
+            &format!(
+                "There is no symbol at line {}, column {} of {}",
+                position.line, position.column, path
+            ),
+        ));
+    };
+    if old_name == new_name {
+        return Err(MightyError::request(
+            "INVALID_NAME",
+            "The new name is the same as the old one",
+        ));
+    }
+
+    let character: usize = line.chars().take(start).map(char::len_utf16).sum();
+    let lsp_edits =
+        lsp_symbols::rename(&path, &content, position.line - 1, character, &new_name).await;
+    let method = if lsp_edits.is_some() {
+        RenameMethod::Lsp
+    } else {
+        RenameMethod::Text
+    };
+
+    let mut edited: Vec<(String, String, String, usize)> = Vec::new();
+    let mut skipped = Vec::new();
+    match lsp_edits {
+        Some(by_file) => {
+            let mut by_file: Vec<_> = by_file.into_iter().collect();
+            by_file.sort_by(|a, b| a.0.cmp(&b.0));
+            for (full_path, edits) in by_file {
+                let file = full_path.to_string_lossy().to_string();
+                let Ok(resolved) = resolve_workspace_path(&file) else {
+                    skipped.push(file);
+                    continue;
+                };
+                let file = workspace_relative_path(&resolved);
+                let before = read_file(file.clone()).await?.content;
+                let after = apply_lsp_edits(&before, &edits);
+                edited.push((file, before, after, edits.len()));
+            }
+        }
+        None => {
+            for file in files_mentioning(&path, &old_name).await {
+                let Ok(text) = read_file(file.clone()).await else {
+                    continue;
+                };
+                let (after, count) = replace_word(&text.content, &old_name, &new_name);
+                if count > 0 {
+                    edited.push((file, text.content, after, count));
+                }
+            }
+        }
+    }
+
+    let transaction_id = begin_edit_transaction().await?;
+    let mut files = Vec::new();
+    for (file, before, after, occurrences) in edited {
+        let diff = compute_diff(&before, &after);
+        if diff.hunks.is_empty() {
+            continue;
+        }
+        let staged = stage_edit(
+            transaction_id.clone(),
+            file.clone(),
+            diff.unified_diff.clone(),
+        )
+        .await;
+        if let Err(e) = staged {
+            rollback_edit_transaction(app, transaction_id).await?;
+            return Err(e.into());
+        }
+        files.push(RenameFileEdit {
+            path: file,
+            occurrences,
+            diff,
+        });
+    }
+
+    Ok(RenamePreview {
+        transaction_id,
+        old_name,
+        new_name,
+        method,
+        files,
+        skipped,
+    })
+}
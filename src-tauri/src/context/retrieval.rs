@@ -0,0 +1,270 @@
+// src/context/retrieval.rs
+
+use serde::Serialize;
+use std::time::Instant;
+
+use super::context::{
+    ensure_vector_index, file_chunks, generate_embeddings, indexed_files, nearest_chunks,
+};
+use super::context_manager::{
+    EmbeddedChunk, EMBEDDING_DIM, VECTOR_INDEX_PARTITIONS, VECTOR_INDEX_SUB_VECTORS,
+};
+use crate::commands::fs::{resolve_workspace_path, workspace_relative_path};
+
+const DEFAULT_LIMIT: usize = 5;
+/// Candidates fetched per returned result, to show what just missed.
+const CANDIDATE_FACTOR: usize = 4;
+const PREVIEW_LENGTH: usize = 120;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetrievalCandidate {
+    pub file_path: String,
+    /// 1-based and inclusive.
+    pub start_line: usize,
+    pub end_line: usize,
+    /// First non-blank line of the chunk.
+    pub preview: String,
+    /// Position in the vector index's results, from 1.
+    pub search_rank: usize,
+    /// Cosine distance as the index reports it; approximate, since vectors
+    /// are quantized.
+    pub distance: f32,
+    /// Exact cosine similarity to the query, computed from the stored
+    /// embedding.
+    pub similarity: f32,
+    /// Position when the candidates are reranked by `similarity`, from 1.
+    pub rerank_rank: usize,
+    /// Whether `search_similar_code` with the same limit returns it.
+    pub returned: bool,
+}
+
+/// How a file the user expected to be retrieved fared.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpectedFile {
+    pub path: String,
+    pub indexed: bool,
+    pub chunks: usize,
+    /// Exact similarity of the file's best chunk to the query.
+    pub best_similarity: Option<f32>,
+    /// Lines of that chunk, 1-based and inclusive.
+    pub best_lines: Option<(usize, usize)>,
+    /// Whether any of its chunks was among the candidates.
+    pub among_candidates: bool,
+    /// Candidates more similar than its best chunk.
+    pub outranked_by: usize,
+    pub verdict: String,
+}
+
+/// Milliseconds spent in each stage.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetrievalTimings {
+    pub embedding_ms: u128,
+    pub index_ms: u128,
+    pub search_ms: u128,
+    pub rerank_ms: u128,
+    pub total_ms: u128,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetrievalExplanation {
+    pub query: String,
+    pub limit: usize,
+    pub embedding_dimension: usize,
+    pub expected_dimension: usize,
+    /// L2 norm of the query embedding; far from 1 suggests the embedding
+    /// model isn't normalizing.
+    pub embedding_norm: f32,
+    /// What narrows the search, in the order applied.
+    pub filters: Vec<String>,
+    /// The index was missing and created for this query.
+    pub index_created: bool,
+    /// Nearest first, as the index ranks them.
+    pub candidates: Vec<RetrievalCandidate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_file: Option<ExpectedFile>,
+    pub timings: RetrievalTimings,
+}
+
+fn norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|v| v * v).sum::<f32>().sqrt()
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let (norm_a, norm_b) = (norm(a), norm(b));
+    if a.len() != b.len() || norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>() / (norm_a * norm_b)
+}
+
+fn preview(chunk: &EmbeddedChunk) -> String {
+    chunk
+        .content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .chars()
+        .take(PREVIEW_LENGTH)
+        .collect()
+}
+
+/// Compares `path`'s chunks with the candidates to say why it was or wasn't
+/// retrieved.
+async fn explain_expected_file(
+    path: &str,
+    query_embedding: &[f32],
+    candidates: &[RetrievalCandidate],
+    limit: usize,
+) -> Result<ExpectedFile, String> {
+    // Files are indexed under the path they were added with
+    let relative = resolve_workspace_path(path)
+        .map(|full_path| workspace_relative_path(&full_path))
+        .unwrap_or_else(|_| path.to_string());
+    let indexed = indexed_files()
+        .await?
+        .into_iter()
+        .find(|file| *file == relative || *file == path);
+    let Some(indexed_path) = indexed else {
+        return Ok(ExpectedFile {
+            path: relative,
+            indexed: false,
+            chunks: 0,
+            best_similarity: None,
+            best_lines: None,
+            among_candidates: false,
+            outranked_by: 0,
+            verdict: "The file isn't indexed; add it to the context first".to_string(),
+        });
+    };
+
+    let chunks = file_chunks(&indexed_path).await?;
+    let best = chunks
+        .iter()
+        .map(|chunk| (chunk, cosine_similarity(query_embedding, &chunk.embedding)))
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+    let among_candidates = candidates
+        .iter()
+        .any(|candidate| candidate.file_path == indexed_path);
+    let returned = candidates
+        .iter()
+        .any(|candidate| candidate.file_path == indexed_path && candidate.returned);
+    let outranked_by = best.map_or(0, |(_, similarity)| {
+        candidates
+            .iter()
+            .filter(|candidate| candidate.file_path != indexed_path)
+            .filter(|candidate| candidate.similarity > similarity)
+            .count()
+    });
+
+    let verdict = if returned {
+        "The file is among the results".to_string()
+    } else if best.is_none() {
+        "The file has no chunks with embeddings; repair the index".to_string()
+    } else if outranked_by >= limit {
+        format!(
+            "{} chunks of other files are more similar than its best one; raise the limit or \
+             reword the query",
+            outranked_by
+        )
+    } else {
+        "Its best chunk is similar enough but the approximate index ranked it lower; the \
+         quantized distances differ from the exact ones"
+            .to_string()
+    };
+
+    Ok(ExpectedFile {
+        path: indexed_path,
+        indexed: true,
+        chunks: chunks.len(),
+        best_similarity: best.map(|(_, similarity)| similarity),
+        best_lines: best.map(|(chunk, _)| (chunk.start_line + 1, chunk.end_line)),
+        among_candidates,
+        outranked_by,
+        verdict,
+    })
+}
+
+/// Runs `query` the way `search_similar_code` does and reports every step:
+/// the query embedding, the index settings and limit applied, the
+/// candidates with the index's raw distances and their exact similarity
+/// reranked, and how long each stage took. With `expected_file`, also says
+/// why that file was or wasn't retrieved.
+#[tauri::command]
+pub async fn explain_retrieval(
+    query: String,
+    limit: Option<usize>,
+    expected_file: Option<String>,
+) -> Result<RetrievalExplanation, String> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).max(1);
+    let started = Instant::now();
+    let mut timings = RetrievalTimings::default();
+
+    let stage = Instant::now();
+    let query_embedding = generate_embeddings(query.clone()).await?;
+    timings.embedding_ms = stage.elapsed().as_millis();
+
+    let stage = Instant::now();
+    let index_created = ensure_vector_index().await?;
+    timings.index_ms = stage.elapsed().as_millis();
+
+    let stage = Instant::now();
+    let nearest = nearest_chunks(&query_embedding, limit * CANDIDATE_FACTOR).await?;
+    timings.search_ms = stage.elapsed().as_millis();
+
+    let stage = Instant::now();
+    let mut candidates: Vec<RetrievalCandidate> = nearest
+        .iter()
+        .enumerate()
+        .map(|(i, (chunk, distance))| RetrievalCandidate {
+            file_path: chunk.file_path.clone(),
+            // Chunk lines are 0-based with an exclusive end
+            start_line: chunk.start_line + 1,
+            end_line: chunk.end_line,
+            preview: preview(chunk),
+            search_rank: i + 1,
+            distance: *distance,
+            similarity: cosine_similarity(&query_embedding, &chunk.embedding),
+            rerank_rank: 0,
+            returned: i < limit,
+        })
+        .collect();
+    let mut by_similarity: Vec<usize> = (0..candidates.len()).collect();
+    by_similarity.sort_by(|&a, &b| {
+        candidates[b]
+            .similarity
+            .total_cmp(&candidates[a].similarity)
+    });
+    for (rank, index) in by_similarity.into_iter().enumerate() {
+        candidates[index].rerank_rank = rank + 1;
+    }
+    timings.rerank_ms = stage.elapsed().as_millis();
+
+    let expected_file = match expected_file {
+        Some(path) => {
+            Some(explain_expected_file(&path, &query_embedding, &candidates, limit).await?)
+        }
+        None => None,
+    };
+    timings.total_ms = started.elapsed().as_millis();
+
+    Ok(RetrievalExplanation {
+        query,
+        limit,
+        embedding_dimension: query_embedding.len(),
+        expected_dimension: EMBEDDING_DIM as usize,
+        embedding_norm: norm(&query_embedding),
+        filters: vec![
+            format!(
+                "Vector index: IVF-PQ over cosine distance, {} partitions, {} sub-vectors \
+                 (approximate)",
+                VECTOR_INDEX_PARTITIONS, VECTOR_INDEX_SUB_VECTORS
+            ),
+            format!("Limit: the {} nearest chunks are returned", limit),
+        ],
+        index_created,
+        candidates,
+        expected_file,
+        timings,
+    })
+}
@@ -0,0 +1,438 @@
+// src/context/stack_trace.rs
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tracing::debug;
+
+use super::context::file_chunks;
+use crate::commands::fs::{
+    get_project_root, resolve_workspace_path, should_ignore_path, workspace_relative_path,
+};
+
+/// Lines shown on each side of a frame's line.
+const SNIPPET_LINES: usize = 3;
+/// Frames, innermost first, whose indexed chunks are returned.
+const MAX_CHUNK_FRAMES: usize = 5;
+/// Parts of the paths of toolchain and dependency sources.
+const EXTERNAL_MARKERS: &[&str] = &[
+    "/rustc/",
+    "/.cargo/registry/",
+    "/.cargo/git/",
+    "/.rustup/",
+    "node_modules/",
+    "site-packages/",
+    "dist-packages/",
+    "/lib/python",
+];
+
+static RUST_PANIC: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"panicked at (?:'(?P<message>.*)', )?(?P<file>[^\s:]+):(?P<line>\d+):(?P<column>\d+)",
+    )
+    .expect("the pattern is valid")
+});
+static RUST_FUNCTION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*\d+:\s+(?P<function>\S.*)$").expect("the pattern is valid"));
+static RUST_AT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s+at (?P<file>.+?):(?P<line>\d+):(?P<column>\d+)\s*$")
+        .expect("the pattern is valid")
+});
+static NODE_FRAME: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*at (?:(?P<function>.+?) \()?(?P<file>[^()\s]+):(?P<line>\d+):(?P<column>\d+)\)?\s*$",
+    )
+    .expect("the pattern is valid")
+});
+static PYTHON_FRAME: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^\s*File "(?P<file>[^"]+)", line (?P<line>\d+)(?:, in (?P<function>.+))?$"#)
+        .expect("the pattern is valid")
+});
+static ERROR_LINE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:[\w.]+\.)?\w*(?:Error|Exception|Warning|Interrupt|Exit)\b.*$")
+        .expect("the pattern is valid")
+});
+static SOURCE_MAPPING_URL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"//[#@] sourceMappingURL=(?P<url>\S+)").expect("the pattern is valid")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceLanguage {
+    Rust,
+    Node,
+    Python,
+}
+
+/// Where a frame points in the workspace.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceLocation {
+    /// Relative to the workspace root.
+    pub path: String,
+    /// 1-based.
+    pub line: usize,
+    pub column: Option<usize>,
+    /// Whether a source map led here from the compiled file in the trace.
+    pub source_mapped: bool,
+    /// The lines around `line`, starting at `snippet_start` (1-based).
+    pub snippet: String,
+    pub snippet_start: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StackFrame {
+    pub function: Option<String>,
+    /// As written in the trace.
+    pub file: String,
+    /// 1-based.
+    pub line: usize,
+    pub column: Option<usize>,
+    /// `None` for frames outside the workspace, such as the standard
+    /// library's or dependencies'.
+    pub location: Option<SourceLocation>,
+}
+
+/// An indexed chunk containing a frame's line.
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceChunk {
+    pub path: String,
+    /// 0-based, end-exclusive, as in `ChunkInfo`.
+    pub start_line: usize,
+    pub end_line: usize,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedTrace {
+    pub language: Option<TraceLanguage>,
+    /// The panic message or the exception line.
+    pub message: Option<String>,
+    /// Innermost first.
+    pub frames: Vec<StackFrame>,
+    pub chunks: Vec<TraceChunk>,
+}
+
+fn number(captures: &regex::Captures, name: &str) -> Option<usize> {
+    captures.name(name)?.as_str().parse().ok()
+}
+
+fn frame(captures: &regex::Captures, function: Option<String>) -> Option<StackFrame> {
+    Some(StackFrame {
+        function: function.or_else(|| {
+            captures
+                .name("function")
+                .map(|function| function.as_str().trim().to_string())
+        }),
+        file: captures.name("file")?.as_str().to_string(),
+        line: number(captures, "line")?,
+        column: number(captures, "column"),
+        location: None,
+    })
+}
+
+/// A Rust backtrace's `at file:line:column` line, which Node's `at file:line:column` is
+/// told apart from by the numbered function line above it.
+fn rust_frame<'a>(lines: &[&'a str], i: usize) -> Option<(regex::Captures<'a>, String)> {
+    let captures = RUST_AT.captures(lines[i])?;
+    let function = RUST_FUNCTION.captures(lines[i.checked_sub(1)?])?;
+    Some((captures, function["function"].trim().to_string()))
+}
+
+/// Reads the frames of a Rust panic and backtrace, a Node.js stack or a
+/// Python traceback out of `text`, which may have other output around it.
+pub(super) fn parse(text: &str) -> (Option<TraceLanguage>, Option<String>, Vec<StackFrame>) {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut language = None;
+    let mut message = None;
+    let mut frames = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(captures) = RUST_PANIC.captures(line) {
+            language = Some(TraceLanguage::Rust);
+            // Since Rust 1.73 the message follows on the next line
+            message = captures
+                .name("message")
+                .map(|message| message.as_str().to_string())
+                .or_else(|| lines.get(i + 1).map(|next| next.trim().to_string()));
+            frames.extend(frame(&captures, None));
+        } else if let Some((captures, function)) = rust_frame(&lines, i) {
+            language.get_or_insert(TraceLanguage::Rust);
+            frames.extend(frame(&captures, Some(function)));
+        } else if let Some(captures) = NODE_FRAME.captures(line) {
+            language.get_or_insert(TraceLanguage::Node);
+            frames.extend(frame(&captures, None));
+        } else if let Some(captures) = PYTHON_FRAME.captures(line) {
+            language.get_or_insert(TraceLanguage::Python);
+            frames.extend(frame(&captures, None));
+        } else if message.is_none() || language == Some(TraceLanguage::Python) {
+            // Node prints the error before its frames, Python after them
+            if ERROR_LINE.is_match(line.trim()) && !line.starts_with(char::is_whitespace) {
+                message = Some(line.trim().to_string());
+            }
+        }
+    }
+
+    // Python lists the innermost call last
+    if language == Some(TraceLanguage::Python) {
+        frames.reverse();
+    }
+    (language, message, frames)
+}
+
+/// `file` from a trace as a file in the workspace. Paths from another
+/// machine or a container (`/app/src/x.py`) are matched by their longest
+/// tail that exists under the root.
+pub(super) fn resolve_path(file: &str, root: &Path) -> Option<PathBuf> {
+    let file = file.strip_prefix("file://").unwrap_or(file);
+    // A dependency's `src/lib.rs` would otherwise match the workspace's
+    if EXTERNAL_MARKERS.iter().any(|marker| file.contains(marker)) {
+        return None;
+    }
+    let path = Path::new(file);
+    let direct = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        root.join(path)
+    };
+    let found = if direct.is_file() && direct.starts_with(root) {
+        Some(direct)
+    } else {
+        let parts: Vec<&str> = path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(part) => part.to_str(),
+                _ => None,
+            })
+            .collect();
+        (0..parts.len())
+            .map(|skip| root.join(parts[skip..].join("/")))
+            .find(|candidate| candidate.is_file())
+    }?;
+    // `..` or a symlink in the trace could still lead out of the workspace
+    resolve_workspace_path(&found.to_string_lossy()).ok()?;
+    (!should_ignore_path(&found)).then_some(found)
+}
+
+fn snippet(path: &Path, line: usize) -> Option<(String, usize)> {
+    let content = fs::read_to_string(path).ok()?;
+    let lines: Vec<&str> = content.lines().collect();
+    if line == 0 || line > lines.len() {
+        return None;
+    }
+    let start = line.saturating_sub(SNIPPET_LINES + 1);
+    let end = (line + SNIPPET_LINES).min(lines.len());
+    Some((lines[start..end].join("\n"), start + 1))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SourceMap {
+    #[serde(default)]
+    source_root: Option<String>,
+    sources: Vec<String>,
+    mappings: String,
+}
+
+/// The source map of a compiled JavaScript file: from its
+/// `sourceMappingURL`, inline or a file next to it, or `<file>.map`.
+fn source_map(path: &Path) -> Option<(SourceMap, PathBuf)> {
+    let content = fs::read_to_string(path).ok()?;
+    let url = SOURCE_MAPPING_URL
+        .captures_iter(&content)
+        .last()
+        .map(|captures| captures["url"].to_string());
+    let dir = path.parent()?.to_path_buf();
+    let json = match url {
+        Some(url) if url.starts_with("data:") => {
+            let (_, data) = url.split_once(";base64,")?;
+            String::from_utf8(STANDARD.decode(data).ok()?).ok()?
+        }
+        Some(url) => fs::read_to_string(dir.join(url)).ok()?,
+        None => {
+            let mut map = path.as_os_str().to_os_string();
+            map.push(".map");
+            fs::read_to_string(map).ok()?
+        }
+    };
+    match serde_json::from_str(&json) {
+        Ok(map) => Some((map, dir)),
+        Err(e) => {
+            debug!("Ignoring the source map of {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Decodes one base64 VLQ value from `chars`.
+fn vlq(chars: &mut std::str::Chars) -> Option<i64> {
+    const ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut value = 0i64;
+    let mut shift = 0;
+    loop {
+        let digit = ALPHABET.find(chars.next()?)? as i64;
+        value |= (digit & 31) << shift;
+        if digit & 32 == 0 {
+            break;
+        }
+        shift += 5;
+    }
+    Some(if value & 1 == 1 {
+        -(value >> 1)
+    } else {
+        value >> 1
+    })
+}
+
+/// The original source, line and column (0-based) of generated `line` and
+/// `column` (0-based): the last mapping at or before the column.
+fn original_position(
+    map: &SourceMap,
+    line: usize,
+    column: usize,
+) -> Option<(String, usize, usize)> {
+    // Source fields are relative to the previous mapping in the whole file
+    let (mut source, mut source_line, mut source_column) = (0i64, 0i64, 0i64);
+    let mut found = None;
+    for (index, groups) in map.mappings.split(';').enumerate() {
+        if index > line {
+            break;
+        }
+        let mut generated_column = 0i64;
+        for segment in groups.split(',').filter(|segment| !segment.is_empty()) {
+            let mut chars = segment.chars();
+            generated_column += vlq(&mut chars)?;
+            let Some(source_delta) = vlq(&mut chars) else {
+                continue;
+            };
+            source += source_delta;
+            source_line += vlq(&mut chars)?;
+            source_column += vlq(&mut chars)?;
+            if index == line && generated_column <= column as i64 {
+                found = Some((source, source_line, source_column));
+            }
+        }
+    }
+    let (source, source_line, source_column) = found?;
+    let name = map.sources.get(usize::try_from(source).ok()?)?;
+    let name = match map.source_root.as_deref().filter(|root| !root.is_empty()) {
+        Some(root) => format!("{}/{}", root.trim_end_matches('/'), name),
+        None => name.clone(),
+    };
+    Some((
+        name,
+        usize::try_from(source_line).ok()?,
+        usize::try_from(source_column).ok()?,
+    ))
+}
+
+/// Follows a compiled JavaScript frame to its original source.
+fn map_frame(
+    path: &Path,
+    line: usize,
+    column: Option<usize>,
+    root: &Path,
+) -> Option<(PathBuf, usize, Option<usize>)> {
+    let (map, dir) = source_map(path)?;
+    let (source, line, column) = original_position(
+        &map,
+        line.checked_sub(1)?,
+        column.unwrap_or(1).saturating_sub(1),
+    )?;
+    // Bundlers name sources like `webpack:///./src/app.ts`
+    let source = match source.split_once("://") {
+        Some((_, rest)) => rest.trim_start_matches('/').to_string(),
+        None => source,
+    };
+    let source = source.trim_start_matches("./");
+    let resolved = if Path::new(source).is_absolute() {
+        resolve_path(source, root)
+    } else {
+        resolve_path(&dir.join(source).to_string_lossy(), root)
+            .or_else(|| resolve_path(source, root))
+    }?;
+    Some((resolved, line + 1, Some(column + 1)))
+}
+
+pub(super) fn locate(frame: &StackFrame, root: &Path) -> Option<SourceLocation> {
+    let path = resolve_path(&frame.file, root)?;
+    let compiled = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| matches!(extension, "js" | "mjs" | "cjs"));
+    let mapped = compiled
+        .then(|| map_frame(&path, frame.line, frame.column, root))
+        .flatten();
+    let source_mapped = mapped.is_some();
+    let (path, line, column) = mapped.unwrap_or((path, frame.line, frame.column));
+    let (snippet, snippet_start) = snippet(&path, line)?;
+    Some(SourceLocation {
+        path: workspace_relative_path(&path),
+        line,
+        column,
+        source_mapped,
+        snippet,
+        snippet_start,
+    })
+}
+
+/// Resolves a stack trace pasted from a terminal or log: Rust panics and
+/// backtraces, Node.js stacks and Python tracebacks. Frames are mapped to
+/// files in the workspace, through source maps for compiled JavaScript,
+/// each with the lines around it; the indexed chunks holding the innermost
+/// workspace frames are returned too, for asking the model about the error.
+#[tauri::command]
+pub async fn resolve_stack_trace(text: String) -> Result<ResolvedTrace, String> {
+    let (language, message, frames) = tokio::task::spawn_blocking(move || {
+        let root = get_project_root();
+        let (language, message, mut frames) = parse(&text);
+        for frame in &mut frames {
+            frame.location = locate(frame, &root);
+        }
+        (language, message, frames)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut chunks: Vec<TraceChunk> = Vec::new();
+    let located = frames
+        .iter()
+        .filter_map(|frame| frame.location.as_ref())
+        .take(MAX_CHUNK_FRAMES);
+    for location in located {
+        let line = location.line - 1;
+        let indexed = match file_chunks(&location.path).await {
+            Ok(indexed) => indexed,
+            Err(e) => {
+                debug!("No indexed chunks for {}: {}", location.path, e);
+                continue;
+            }
+        };
+        let chunk = indexed
+            .into_iter()
+            .find(|chunk| chunk.start_line <= line && line < chunk.end_line);
+        if let Some(chunk) = chunk {
+            let seen = chunks
+                .iter()
+                .any(|seen| seen.path == chunk.file_path && seen.start_line == chunk.start_line);
+            if !seen {
+                chunks.push(TraceChunk {
+                    path: chunk.file_path,
+                    start_line: chunk.start_line,
+                    end_line: chunk.end_line,
+                    content: chunk.content,
+                });
+            }
+        }
+    }
+
+    Ok(ResolvedTrace {
+        language,
+        message,
+        frames,
+        chunks,
+    })
+}
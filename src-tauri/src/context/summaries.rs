@@ -0,0 +1,175 @@
+// src/context/summaries.rs
+
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::warn;
+
+use super::context_manager::ChunkInfo;
+use crate::commands::api::anthropic_api_key;
+use crate::commands::offline::ensure_reachable;
+use crate::http_client;
+use crate::state::app_state;
+
+const ANTHROPIC_URL: &str = "https://api.anthropic.com/v1/messages";
+const DEFAULT_MODEL: &str = "claude-3-5-haiku-latest";
+/// Chunks summarized per request.
+const BATCH_SIZE: usize = 20;
+/// Code sent per chunk; the start of a chunk says most about it.
+const MAX_CHUNK_CHARS: usize = 4_000;
+const MAX_TOKENS: u32 = 1_024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const INSTRUCTIONS: &str = "Summarize what each numbered code chunk below does in one line of \
+at most 20 words, for someone searching the codebase. Answer with one line per chunk in the form \
+`<number>: <summary>` and nothing else.";
+
+/// Where and with what model summaries are written.
+struct Summarizer {
+    model: String,
+    /// An OpenAI-compatible endpoint; Anthropic when `None`.
+    endpoint: Option<String>,
+    api_key: Option<String>,
+}
+
+/// The summarizer `[context]` asks for, or `None` when summaries are off or
+/// can't be written.
+async fn summarizer() -> Option<Summarizer> {
+    let state = app_state();
+    let context = state.config.lock().await.context.clone()?;
+    if context.summarize_chunks != Some(true) {
+        return None;
+    }
+    match context.summary_endpoint {
+        Some(endpoint) => {
+            let Some(model) = context.summary_model else {
+                warn!("Chunk summaries need context.summary_model with a summary endpoint");
+                return None;
+            };
+            Some(Summarizer {
+                model,
+                endpoint: Some(endpoint),
+                api_key: None,
+            })
+        }
+        None => {
+            let Some(api_key) = anthropic_api_key(state).await else {
+                warn!("Chunk summaries are on but no Anthropic API key is configured");
+                return None;
+            };
+            Some(Summarizer {
+                model: context
+                    .summary_model
+                    .unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+                endpoint: None,
+                api_key: Some(api_key),
+            })
+        }
+    }
+}
+
+impl Summarizer {
+    async fn complete(&self, prompt: String) -> Result<String, String> {
+        let client = http_client::client(match self.endpoint {
+            Some(_) => "summaries",
+            None => "anthropic",
+        })?;
+        let messages = json!([{ "role": "user", "content": prompt }]);
+        let request = match &self.endpoint {
+            Some(endpoint) => client
+                .post(format!(
+                    "{}/chat/completions",
+                    endpoint.trim_end_matches('/')
+                ))
+                .json(&json!({
+                    "model": self.model,
+                    "max_tokens": MAX_TOKENS,
+                    "messages": messages,
+                })),
+            None => client
+                .post(ANTHROPIC_URL)
+                .header("x-api-key", self.api_key.as_deref().unwrap_or_default())
+                .header("anthropic-version", "2023-06-01")
+                .json(&json!({
+                    "model": self.model,
+                    "max_tokens": MAX_TOKENS,
+                    "messages": messages,
+                })),
+        };
+
+        let response = request
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| e.to_string())?;
+        if !status.is_success() {
+            return Err(format!("Request failed with status {}: {}", status, text));
+        }
+        let body: Value = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+        let summary = match self.endpoint {
+            Some(_) => body["choices"][0]["message"]["content"].as_str(),
+            None => body["content"][0]["text"].as_str(),
+        };
+        summary
+            .map(str::to_string)
+            .ok_or_else(|| "The response had no text".to_string())
+    }
+}
+
+/// One-line summaries of `chunks`, in order, when `context.summarize_chunks`
+/// is on. Chunks that couldn't be summarized get `None`; indexing goes on
+/// without their summaries.
+pub(crate) async fn summarize_chunks(path: &str, chunks: &[ChunkInfo]) -> Vec<Option<String>> {
+    let mut summaries = vec![None; chunks.len()];
+    let Some(summarizer) = summarizer().await else {
+        return summaries;
+    };
+    // Offline, files are indexed without summaries unless the model is local
+    if ensure_reachable(summarizer.endpoint.as_deref().unwrap_or(ANTHROPIC_URL)).is_err() {
+        return summaries;
+    }
+
+    for (batch_index, batch) in chunks.chunks(BATCH_SIZE).enumerate() {
+        match summarizer.complete(prompt(path, batch)).await {
+            Ok(text) => {
+                let offset = batch_index * BATCH_SIZE;
+                for (i, summary) in parse_summaries(&text, batch.len()).into_iter().enumerate() {
+                    summaries[offset + i] = summary;
+                }
+            }
+            Err(e) => {
+                warn!("Failed to summarize chunks of {}: {}", path, e);
+                break;
+            }
+        }
+    }
+    summaries
+}
+
+fn prompt(path: &str, chunks: &[ChunkInfo]) -> String {
+    let mut prompt = format!("{}\n\nFile: {}\n", INSTRUCTIONS, path);
+    for (i, chunk) in chunks.iter().enumerate() {
+        let code: String = chunk.content.chars().take(MAX_CHUNK_CHARS).collect();
+        prompt.push_str(&format!("\n{}:\n```\n{}\n```\n", i + 1, code));
+    }
+    prompt
+}
+
+/// Reads the `<number>: <summary>` lines of a reply, ignoring anything else
+/// the model added.
+fn parse_summaries(text: &str, count: usize) -> Vec<Option<String>> {
+    let mut summaries = vec![None; count];
+    for line in text.lines() {
+        let Some((number, summary)) = line.split_once(':') else {
+            continue;
+        };
+        let Ok(number) = number.trim().parse::<usize>() else {
+            continue;
+        };
+        let summary = summary.trim();
+        if (1..=count).contains(&number) && !summary.is_empty() {
+            summaries[number - 1] = Some(summary.to_string());
+        }
+    }
+    summaries
+}
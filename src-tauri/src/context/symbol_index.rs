@@ -0,0 +1,229 @@
+// src/context/symbol_index.rs
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tracing::warn;
+
+use super::context_manager::{CodeSymbol, SymbolKind};
+use crate::commands::command_history::fuzzy_score;
+use crate::commands::fs::get_project_root;
+use crate::commands::storage::{delete_record, put_record, records_with_prefix};
+
+const STORAGE_PREFIX: &str = "symbols:";
+const DEFAULT_LIMIT: usize = 50;
+/// Added to a name matching the query exactly, ignoring case, so it beats
+/// longer names the query is merely a prefix of.
+const EXACT_MATCH_BONUS: i64 = 1_000;
+
+/// The workspace's symbols by file, loaded from storage on first use and
+/// kept in step with it afterwards.
+static INDEX: Lazy<Mutex<Option<HashMap<String, Vec<IndexedSymbol>>>>> =
+    Lazy::new(|| Mutex::new(None));
+
+/// A symbol in the workspace table. Lines and columns are 0-based and the
+/// end is inclusive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexedSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub container_name: Option<String>,
+    /// As given when the file was indexed, usually workspace-relative.
+    pub file: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub start_col: usize,
+    pub end_col: usize,
+    /// Doc comment or docstring, when the symbol has one.
+    #[serde(default)]
+    pub docs: Option<String>,
+    /// How well the name matched the query; higher is better.
+    #[serde(default)]
+    pub score: i64,
+}
+
+fn workspace_prefix() -> String {
+    format!(
+        "{}{}|",
+        STORAGE_PREFIX,
+        get_project_root().to_string_lossy()
+    )
+}
+
+fn loaded<T>(f: impl FnOnce(&mut HashMap<String, Vec<IndexedSymbol>>) -> T) -> Result<T, String> {
+    let mut index = INDEX.lock();
+    if index.is_none() {
+        let prefix = workspace_prefix();
+        let files = records_with_prefix(&prefix)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter_map(|(key, json)| {
+                let symbols = serde_json::from_str(&json).ok()?;
+                Some((key[prefix.len()..].to_string(), symbols))
+            })
+            .collect();
+        *index = Some(files);
+    }
+    Ok(f(index.as_mut().expect("loaded above")))
+}
+
+/// Replaces the symbols recorded for `file` with those just extracted from
+/// it. Imports are left out; they name symbols defined elsewhere.
+pub(crate) fn update_file(file: &str, symbols: &[CodeSymbol]) {
+    let symbols: Vec<IndexedSymbol> = symbols
+        .iter()
+        .filter(|symbol| !matches!(symbol.kind, SymbolKind::Import))
+        .map(|symbol| IndexedSymbol {
+            name: symbol.name.clone(),
+            kind: symbol.kind.clone(),
+            container_name: symbol.container_name.clone(),
+            file: file.to_string(),
+            start_line: symbol.location.start_line,
+            end_line: symbol.location.end_line,
+            start_col: symbol.location.start_col,
+            end_col: symbol.location.end_col,
+            docs: symbol.docs.clone(),
+            score: 0,
+        })
+        .collect();
+    let key = format!("{}{}", workspace_prefix(), file);
+    let stored = serde_json::to_string(&symbols)
+        .map_err(|e| e.to_string())
+        .and_then(|json| put_record(&key, &json).map_err(|e| e.to_string()))
+        .and_then(|_| {
+            loaded(|files| {
+                files.insert(file.to_string(), symbols);
+            })
+        });
+    if let Err(e) = stored {
+        warn!("Failed to index symbols of {}: {}", file, e);
+    }
+}
+
+/// Forgets the symbols of `file`.
+pub(crate) fn remove_file(file: &str) -> Result<(), String> {
+    delete_record(&format!("{}{}", workspace_prefix(), file)).map_err(|e| e.to_string())?;
+    loaded(|files| {
+        files.remove(file);
+    })
+}
+
+/// The symbols recorded for `file`, under any of the names it may have been
+/// indexed by.
+pub(crate) fn symbols_in_file(files: &[&str]) -> Result<Vec<IndexedSymbol>, String> {
+    loaded(|indexed| {
+        files
+            .iter()
+            .find_map(|file| indexed.get(*file))
+            .cloned()
+            .unwrap_or_default()
+    })
+}
+
+/// Every definition whose name is one of `names`, matching case exactly.
+pub(crate) fn definitions_named(names: &HashSet<&str>) -> Result<Vec<IndexedSymbol>, String> {
+    loaded(|files| {
+        files
+            .values()
+            .flatten()
+            .filter(|symbol| names.contains(symbol.name.as_str()))
+            .cloned()
+            .collect()
+    })
+}
+
+/// Symbols across the workspace whose names fuzzily match `query`, best
+/// first, optionally only of the given `kinds`. Files deleted since they
+/// were indexed are dropped from the table.
+#[tauri::command]
+pub async fn search_symbols(
+    query: String,
+    kinds: Option<Vec<SymbolKind>>,
+    limit: Option<usize>,
+) -> Result<Vec<IndexedSymbol>, String> {
+    let root = get_project_root();
+    let (mut matches, missing) = loaded(|files| {
+        let mut matches = Vec::new();
+        let mut missing = Vec::new();
+        for (file, symbols) in files.iter() {
+            if !root.join(file).exists() {
+                missing.push(file.clone());
+                continue;
+            }
+            for symbol in symbols {
+                if kinds
+                    .as_ref()
+                    .is_some_and(|kinds| !kinds.contains(&symbol.kind))
+                {
+                    continue;
+                }
+                if let Some(score) = fuzzy_score(&query, &symbol.name) {
+                    let exact = symbol.name.eq_ignore_ascii_case(query.trim());
+                    let score = score + if exact { EXACT_MATCH_BONUS } else { 0 };
+                    matches.push(IndexedSymbol {
+                        score,
+                        ..symbol.clone()
+                    });
+                }
+            }
+        }
+        (matches, missing)
+    })?;
+    for file in missing {
+        remove_file(&file)?;
+    }
+
+    // Shorter names first among equal scores, then by place for stable output
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then_with(|| a.name.len().cmp(&b.name.len()))
+            .then_with(|| a.file.cmp(&b.file))
+            .then_with(|| a.start_line.cmp(&b.start_line))
+    });
+    matches.truncate(limit.unwrap_or(DEFAULT_LIMIT));
+    Ok(matches)
+}
+
+/// The documentation of every definition named `name`. A container may be
+/// given as in `Config::load` or `Config.load`; exact-case matches are
+/// preferred over ones differing only in case. Definitions without docs
+/// are left out.
+#[tauri::command]
+pub async fn get_symbol_docs(name: String) -> Result<Vec<IndexedSymbol>, String> {
+    let name = name.trim();
+    let (container, name) = match name.rsplit_once("::").or_else(|| name.rsplit_once('.')) {
+        Some((container, name)) => (Some(container), name),
+        None => (None, name),
+    };
+
+    let documented = loaded(|files| {
+        files
+            .values()
+            .flatten()
+            .filter(|symbol| symbol.docs.is_some() && symbol.name.eq_ignore_ascii_case(name))
+            .filter(|symbol| {
+                container.is_none_or(|container| {
+                    symbol.container_name.as_deref().is_some_and(|c| {
+                        c.eq_ignore_ascii_case(container) || c.ends_with(container)
+                    })
+                })
+            })
+            .cloned()
+            .collect::<Vec<_>>()
+    })?;
+
+    let exact: Vec<IndexedSymbol> = documented
+        .iter()
+        .filter(|symbol| symbol.name == name)
+        .cloned()
+        .collect();
+    let mut symbols = if exact.is_empty() { documented } else { exact };
+    symbols.sort_by(|a, b| {
+        a.file
+            .cmp(&b.file)
+            .then_with(|| a.start_line.cmp(&b.start_line))
+    });
+    Ok(symbols)
+}
@@ -0,0 +1,211 @@
+// src/context/warmup.rs
+
+use glob::Pattern;
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::State;
+use tracing::{debug, warn};
+
+use super::filters;
+use crate::commands::fs::{get_project_root, should_ignore_path, workspace_relative_path};
+use crate::commands::jobs::{enqueue, JobInfo, JobPriority, JobSpec};
+use crate::state::AppState;
+
+const DEFAULT_MAX_FILES: usize = 500;
+const DEFAULT_CPU_PERCENT: u8 = 50;
+/// Files looked at before giving up on the rest of a huge workspace.
+const MAX_SCANNED_FILES: usize = 50_000;
+
+const MANIFESTS: &[&str] = &[
+    "package.json",
+    "tsconfig.json",
+    "Cargo.toml",
+    "pyproject.toml",
+    "setup.py",
+    "requirements.txt",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "Gemfile",
+    "composer.json",
+    "CMakeLists.txt",
+    "Makefile",
+];
+const ENTRY_POINTS: &[&str] = &["main", "index", "lib", "app", "mod", "__init__", "server"];
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "rs", "ts", "tsx", "js", "jsx", "mjs", "cjs", "py", "pyi", "go", "java", "kt", "c", "h", "cc",
+    "cpp", "hpp", "cs", "rb", "php", "swift", "scala", "vue", "svelte", "md", "toml", "json",
+    "yaml", "yml",
+];
+
+struct Candidate {
+    relative: String,
+    depth: usize,
+    modified: SystemTime,
+}
+
+impl Candidate {
+    /// Manifests say what the project is, entry points where it starts;
+    /// everything else goes by how recently it was worked on.
+    fn tier(&self) -> u8 {
+        let path = Path::new(&self.relative);
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        if MANIFESTS.contains(&name.as_ref()) {
+            0
+        } else if ENTRY_POINTS.contains(&stem.as_ref()) {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+/// Whether `path` is code or project metadata worth indexing.
+pub(super) fn is_source_file(path: &Path) -> bool {
+    if MANIFESTS.iter().any(|name| path.ends_with(name)) {
+        return true;
+    }
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| SOURCE_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// The `workspace.ignore` globs; invalid ones are reported when the config
+/// is loaded.
+pub(super) async fn workspace_ignore(state: &AppState) -> Vec<Pattern> {
+    let config = state.config.lock().await;
+    config
+        .workspace
+        .as_ref()
+        .map(|workspace| workspace.ignore.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|glob| Pattern::new(glob).ok())
+        .collect()
+}
+
+/// Calls `visit` with every file under `root`, its workspace-relative path
+/// and metadata, skipping ignored and hidden paths and those matching
+/// `ignore`. Stops after `MAX_SCANNED_FILES`.
+pub(super) fn walk_workspace(
+    root: &Path,
+    ignore: &[Pattern],
+    mut visit: impl FnMut(&Path, String, &fs::Metadata),
+) {
+    let mut scanned = 0;
+    let mut pending: Vec<PathBuf> = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                debug!("Skipping {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            let relative = workspace_relative_path(&path);
+            // The ignore list matches directories by their trailing separator
+            let checked = if metadata.is_dir() {
+                path.join("")
+            } else {
+                path.clone()
+            };
+            if hidden
+                || should_ignore_path(&checked)
+                || ignore.iter().any(|pattern| pattern.matches(&relative))
+            {
+                continue;
+            }
+            if metadata.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            scanned += 1;
+            if scanned > MAX_SCANNED_FILES {
+                warn!(
+                    "Stopped walking {} after {} files",
+                    root.display(),
+                    MAX_SCANNED_FILES
+                );
+                return;
+            }
+            visit(&path, relative, &metadata);
+        }
+    }
+}
+
+/// The workspace's source files worth indexing.
+fn collect_candidates(root: &Path, ignore: &[Pattern]) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    walk_workspace(root, ignore, |path, relative, metadata| {
+        if metadata.len() == 0
+            || !is_source_file(path)
+            || filters::skip_reason(&relative, metadata.len()).is_some()
+        {
+            return;
+        }
+        candidates.push(Candidate {
+            depth: relative.matches('/').count(),
+            relative,
+            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        });
+    });
+    candidates
+}
+
+/// Starts indexing the workspace in the background after it is opened, so
+/// search and the model's context are ready when first needed. Manifests,
+/// then entry points, then the most recently modified files go first, up to
+/// `context.warmup_max_files`; files already indexed are skipped, so
+/// reopening a workspace only indexes what's new. Returns the low-priority
+/// job doing it, or `None` when warm-up is off or there's nothing to index.
+#[tauri::command]
+pub async fn open_workspace(state: State<'_, AppState>) -> Result<Option<JobInfo>, String> {
+    let context_config = state
+        .config
+        .lock()
+        .await
+        .context
+        .clone()
+        .unwrap_or_default();
+    let max_files = context_config.warmup_max_files.unwrap_or(DEFAULT_MAX_FILES);
+    if max_files == 0 {
+        return Ok(None);
+    }
+    let cpu_percent = context_config
+        .warmup_cpu_percent
+        .unwrap_or(DEFAULT_CPU_PERCENT);
+    let ignore = workspace_ignore(&state).await;
+
+    let root = get_project_root();
+    let mut candidates = tokio::task::spawn_blocking(move || collect_candidates(&root, &ignore))
+        .await
+        .map_err(|e| e.to_string())?;
+    candidates.sort_by(|a, b| {
+        a.tier().cmp(&b.tier()).then_with(|| match a.tier() {
+            2 => Reverse(a.modified).cmp(&Reverse(b.modified)),
+            _ => a.depth.cmp(&b.depth),
+        })
+    });
+    candidates.truncate(max_files);
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+
+    let paths = candidates
+        .into_iter()
+        .map(|candidate| candidate.relative)
+        .collect();
+    Ok(Some(enqueue(
+        JobSpec::WarmUpContext { paths, cpu_percent },
+        JobPriority::Low,
+    )))
+}
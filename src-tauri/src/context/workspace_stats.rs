@@ -0,0 +1,220 @@
+// src/context/workspace_stats.rs
+
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use tauri::State;
+use tracing::debug;
+
+use super::context::indexed_files;
+use super::filters;
+use super::warmup::{is_source_file, walk_workspace, workspace_ignore};
+use crate::commands::fs::get_project_root;
+use crate::state::AppState;
+
+const LARGEST_FILES: usize = 10;
+/// Larger files are counted by size only; reading them for lines would
+/// make the walk crawl.
+const MAX_COUNTED_BYTES: u64 = 4 * 1024 * 1024;
+/// Bytes checked for a NUL to tell binary files apart.
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LanguageStats {
+    pub language: String,
+    pub files: usize,
+    /// Non-blank lines.
+    pub lines: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LargeFile {
+    /// Relative to the workspace root.
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceStats {
+    pub root: String,
+    pub files: usize,
+    pub bytes: u64,
+    /// Non-blank lines in text files.
+    pub lines: usize,
+    pub binary_files: usize,
+    /// Most lines first.
+    pub languages: Vec<LanguageStats>,
+    /// Largest first.
+    pub largest_files: Vec<LargeFile>,
+    /// Files indexing would take, after the content filters.
+    pub indexable_files: usize,
+    pub indexable_bytes: u64,
+    /// Of the indexable files, those indexed.
+    pub indexed_files: usize,
+    /// `indexed_files` as a percentage of `indexable_files`; `None` when the
+    /// context isn't running.
+    pub index_coverage: Option<f64>,
+}
+
+fn language_of(path: &Path) -> &'static str {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    if name == "Dockerfile" {
+        return "Dockerfile";
+    }
+    if name == "Makefile" {
+        return "Makefile";
+    }
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    match extension.as_str() {
+        "rs" => "Rust",
+        "ts" | "tsx" | "mts" | "cts" => "TypeScript",
+        "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
+        "py" | "pyi" => "Python",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" => "C++",
+        "cs" => "C#",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "swift" => "Swift",
+        "scala" => "Scala",
+        "vue" => "Vue",
+        "svelte" => "Svelte",
+        "html" | "htm" => "HTML",
+        "css" | "scss" | "sass" | "less" => "CSS",
+        "md" | "mdx" => "Markdown",
+        "json" => "JSON",
+        "toml" => "TOML",
+        "yaml" | "yml" => "YAML",
+        "sql" => "SQL",
+        "sh" | "bash" | "zsh" => "Shell",
+        _ => "Other",
+    }
+}
+
+/// Non-blank lines of a text file, or `None` for a binary one.
+fn count_lines(path: &Path) -> Option<usize> {
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let sniffed = reader.fill_buf().ok()?;
+    if sniffed[..sniffed.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return None;
+    }
+    let mut content = Vec::new();
+    reader.read_to_end(&mut content).ok()?;
+    Some(
+        content
+            .split(|byte| *byte == b'\n')
+            .filter(|line| line.iter().any(|byte| !byte.is_ascii_whitespace()))
+            .count(),
+    )
+}
+
+/// Counts the workspace's files, lines per language and largest files,
+/// skipping what the workspace ignores, and how much of what indexing
+/// would take is indexed already. Feeds the dashboard and sizes indexing
+/// jobs before they start.
+#[tauri::command]
+pub async fn get_workspace_stats(state: State<'_, AppState>) -> Result<WorkspaceStats, String> {
+    let ignore = workspace_ignore(&state).await;
+    let root = get_project_root();
+    let walk_root = root.clone();
+    let (mut stats, indexable) = tokio::task::spawn_blocking(move || {
+        let mut stats = WorkspaceStats {
+            root: walk_root.to_string_lossy().to_string(),
+            files: 0,
+            bytes: 0,
+            lines: 0,
+            binary_files: 0,
+            languages: Vec::new(),
+            largest_files: Vec::new(),
+            indexable_files: 0,
+            indexable_bytes: 0,
+            indexed_files: 0,
+            index_coverage: None,
+        };
+        let mut languages: HashMap<&str, LanguageStats> = HashMap::new();
+        let mut indexable = HashSet::new();
+        walk_workspace(&walk_root, &ignore, |path, relative, metadata| {
+            let bytes = metadata.len();
+            stats.files += 1;
+            stats.bytes += bytes;
+            stats.largest_files.push(LargeFile {
+                path: relative.clone(),
+                bytes,
+            });
+
+            let lines = if bytes > MAX_COUNTED_BYTES {
+                Some(0)
+            } else {
+                count_lines(path)
+            };
+            let Some(lines) = lines else {
+                stats.binary_files += 1;
+                return;
+            };
+            stats.lines += lines;
+            let language = language_of(path);
+            let entry = languages.entry(language).or_default();
+            entry.files += 1;
+            entry.lines += lines;
+            entry.bytes += bytes;
+
+            if bytes > 0 && is_source_file(path) && filters::skip_reason(&relative, bytes).is_none()
+            {
+                stats.indexable_bytes += bytes;
+                indexable.insert(relative);
+            }
+        });
+
+        stats.largest_files.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+        stats.largest_files.truncate(LARGEST_FILES);
+        stats.languages = languages
+            .into_iter()
+            .map(|(language, mut entry)| {
+                entry.language = language.to_string();
+                entry
+            })
+            .collect();
+        stats
+            .languages
+            .sort_by(|a, b| b.lines.cmp(&a.lines).then(b.bytes.cmp(&a.bytes)));
+        stats.indexable_files = indexable.len();
+        (stats, indexable)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    match indexed_files().await {
+        Ok(indexed) => {
+            // Files are indexed as given, relative or absolute
+            stats.indexed_files = indexed
+                .iter()
+                .map(|file| {
+                    Path::new(file)
+                        .strip_prefix(&root)
+                        .map_or(file.clone(), |relative| {
+                            relative.to_string_lossy().to_string()
+                        })
+                })
+                .filter(|file| indexable.contains(file))
+                .collect::<HashSet<_>>()
+                .len();
+            stats.index_coverage = Some(if stats.indexable_files == 0 {
+                100.0
+            } else {
+                stats.indexed_files as f64 * 100.0 / stats.indexable_files as f64
+            });
+        }
+        Err(e) => debug!("Workspace stats without index coverage: {}", e),
+    }
+    Ok(stats)
+}
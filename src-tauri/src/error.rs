@@ -0,0 +1,137 @@
+// src/error.rs
+
+use serde::Serialize;
+use std::fmt;
+
+/// The one error shape every command sends over IPC. The variant is
+/// serialized as `kind`, so the frontend sees
+/// `{ kind, code, message, path?, details? }` whichever subsystem failed,
+/// and can branch on `kind` and `code` rather than matching on messages.
+/// Each subsystem's own error type converts into this and serializes
+/// through it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MightyError {
+    FileSystem {
+        code: String,
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+    },
+    Git {
+        code: String,
+        message: String,
+    },
+    /// External tools and processes.
+    Exec {
+        code: String,
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        details: Option<String>,
+    },
+    Storage {
+        code: String,
+        message: String,
+    },
+    /// Greptile and the other remote services.
+    Service {
+        code: String,
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        details: Option<String>,
+    },
+    /// Tokens, secrets and credentials.
+    Auth {
+        code: String,
+        message: String,
+    },
+    Config {
+        code: String,
+        message: String,
+    },
+    /// A request the local API server couldn't accept: unauthorized,
+    /// malformed or for an unknown method.
+    Request {
+        code: String,
+        message: String,
+    },
+    /// Anything reported only as a message.
+    Internal {
+        code: String,
+        message: String,
+    },
+}
+
+impl MightyError {
+    pub(crate) fn auth(code: &str, message: &str) -> Self {
+        Self::Auth {
+            code: code.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn config(code: &str, message: &str) -> Self {
+        Self::Config {
+            code: code.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn request(code: &str, message: &str) -> Self {
+        Self::Request {
+            code: code.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        match self {
+            Self::FileSystem { code, .. }
+            | Self::Git { code, .. }
+            | Self::Exec { code, .. }
+            | Self::Storage { code, .. }
+            | Self::Service { code, .. }
+            | Self::Auth { code, .. }
+            | Self::Config { code, .. }
+            | Self::Request { code, .. }
+            | Self::Internal { code, .. } => code,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::FileSystem { message, .. }
+            | Self::Git { message, .. }
+            | Self::Exec { message, .. }
+            | Self::Storage { message, .. }
+            | Self::Service { message, .. }
+            | Self::Auth { message, .. }
+            | Self::Config { message, .. }
+            | Self::Request { message, .. }
+            | Self::Internal { message, .. } => message,
+        }
+    }
+}
+
+impl fmt::Display for MightyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
+
+impl std::error::Error for MightyError {}
+
+impl From<String> for MightyError {
+    fn from(message: String) -> Self {
+        Self::Internal {
+            code: "INTERNAL".to_string(),
+            message,
+        }
+    }
+}
+
+impl From<&str> for MightyError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
@@ -0,0 +1,109 @@
+// src/http_client.rs
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::info;
+
+use crate::config::HttpConfig;
+
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_TIMEOUT_SECS: u64 = 120;
+const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 4;
+
+static SETTINGS: Lazy<Mutex<HttpConfig>> = Lazy::new(|| Mutex::new(HttpConfig::default()));
+/// Clients by provider, so connections are pooled across requests.
+static CLIENTS: Lazy<Mutex<HashMap<String, Client>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+/// Requests each provider may have in flight at once.
+static LIMITS: Lazy<Mutex<HashMap<String, Arc<Semaphore>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Applies the `[http]` settings. Clients built under the old ones are
+/// dropped and rebuilt on next use.
+pub(crate) fn configure(config: Option<&HttpConfig>) {
+    let config = config.cloned().unwrap_or_default();
+    let mut settings = SETTINGS.lock();
+    if *settings == config {
+        return;
+    }
+    *settings = config;
+    CLIENTS.lock().clear();
+    // Requests already waiting finish under the old limit
+    LIMITS.lock().clear();
+}
+
+fn build(settings: &HttpConfig, provider: &str) -> Result<Client, String> {
+    let timeout = settings
+        .provider_timeouts
+        .get(provider)
+        .copied()
+        .or(settings.timeout_secs)
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+    // A read timeout rather than a total one, so long streamed responses
+    // aren't cut off while data keeps arriving
+    let mut builder = Client::builder()
+        .connect_timeout(Duration::from_secs(
+            settings
+                .connect_timeout_secs
+                .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS),
+        ))
+        .read_timeout(Duration::from_secs(timeout));
+
+    // Without an explicit proxy, reqwest reads the proxy environment variables
+    if let Some(proxy) = &settings.proxy {
+        let proxy = Proxy::all(proxy)
+            .map_err(|e| format!("Invalid proxy {}: {}", proxy, e))?
+            .no_proxy(NoProxy::from_env());
+        builder = builder.proxy(proxy);
+    }
+    if let Some(path) = &settings.ca_bundle {
+        let pem =
+            fs::read(path).map_err(|e| format!("Failed to read CA bundle {}: {}", path, e))?;
+        let certificates = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("Invalid CA bundle {}: {}", path, e))?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+/// The HTTP client for requests to `provider` (e.g. "anthropic",
+/// "greptile"), with the configured proxy, CA certificates and timeouts.
+pub(crate) fn client(provider: &str) -> Result<Client, String> {
+    let settings = SETTINGS.lock().clone();
+    let mut clients = CLIENTS.lock();
+    if let Some(client) = clients.get(provider) {
+        return Ok(client.clone());
+    }
+    let client = build(&settings, provider)?;
+    info!("Built the HTTP client for {}", provider);
+    clients.insert(provider.to_string(), client.clone());
+    Ok(client)
+}
+
+/// Waits for a turn to send a request to `provider`, so no more than
+/// `http.max_concurrent_requests` are in flight at once. The turn ends
+/// when the permit is dropped.
+pub(crate) async fn acquire(provider: &str) -> OwnedSemaphorePermit {
+    let limit = {
+        let max = SETTINGS
+            .lock()
+            .max_concurrent_requests
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_REQUESTS);
+        LIMITS
+            .lock()
+            .entry(provider.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(max)))
+            .clone()
+    };
+    limit
+        .acquire_owned()
+        .await
+        .expect("the semaphore is never closed")
+}
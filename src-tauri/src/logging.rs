@@ -0,0 +1,308 @@
+// src-tauri/src/logging.rs
+
+use chrono::Utc;
+use parking_lot::Mutex;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt as format_layer, reload, EnvFilter, Layer, Registry};
+
+use crate::activity;
+use crate::config::APP_IDENTIFIER;
+use crate::telemetry;
+
+const LOG_DIR_NAME: &str = "logs";
+const LOG_FILE_PREFIX: &str = "mighty";
+/// Daily files, so this is about a week of logs.
+const MAX_LOG_FILES: usize = 7;
+/// Entries kept in memory for `get_recent_logs`.
+const RECENT_LOG_CAPACITY: usize = 2000;
+
+/// One log event as returned by `get_recent_logs`.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: i64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    /// Structured fields recorded with the event, other than the message.
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub fields: Map<String, Value>,
+    #[serde(skip)]
+    severity: Level,
+}
+
+/// The levels in effect: a default plus any per-target overrides, turned into
+/// an `EnvFilter` whenever they change.
+struct LogLevels {
+    default: LevelFilter,
+    targets: BTreeMap<String, LevelFilter>,
+}
+
+impl LogLevels {
+    /// Reads `RUST_LOG`-style directives (`info`, `mighty::commands::storage=debug`);
+    /// anything fancier is ignored.
+    fn parse(directives: &str) -> Self {
+        let mut levels = Self {
+            default: LevelFilter::INFO,
+            targets: BTreeMap::new(),
+        };
+        for directive in directives.split(',').map(str::trim) {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    if let Ok(level) = level.parse() {
+                        levels.targets.insert(target.to_string(), level);
+                    }
+                }
+                None => {
+                    if let Ok(level) = directive.parse() {
+                        levels.default = level;
+                    }
+                }
+            }
+        }
+        levels
+    }
+
+    fn filter(&self) -> EnvFilter {
+        let directives = std::iter::once(self.default.to_string())
+            .chain(
+                self.targets
+                    .iter()
+                    .map(|(target, level)| format!("{}={}", target, level)),
+            )
+            .collect::<Vec<_>>()
+            .join(",");
+        EnvFilter::builder()
+            .parse(&directives)
+            .unwrap_or_else(|_| EnvFilter::new(self.default.to_string()))
+    }
+}
+
+static LEVELS: OnceLock<Mutex<LogLevels>> = OnceLock::new();
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+static RECENT: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+/// Where the rotating JSON log files are written.
+pub fn log_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(APP_IDENTIFIER)
+        .join(LOG_DIR_NAME)
+}
+
+/// Sets up logging for the whole app: human-readable output on stderr, JSON
+/// lines in daily log files and an in-memory buffer of recent entries.
+/// Messages from the `log` crate are included. Levels come from `RUST_LOG`,
+/// defaulting to `info`. Keep the returned guard alive until exit so the
+/// last lines reach the file.
+pub fn init() -> Option<WorkerGuard> {
+    let levels = LogLevels::parse(&std::env::var("RUST_LOG").unwrap_or_default());
+    let (filter, handle) = reload::Layer::new(levels.filter());
+    let _ = LEVELS.set(Mutex::new(levels));
+    let _ = FILTER_HANDLE.set(handle);
+
+    let (file_layer, guard) = match file_appender() {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = format_layer::layer()
+                .json()
+                .with_current_span(false)
+                .with_span_list(false)
+                .with_writer(writer);
+            (Some(layer), Some(guard))
+        }
+        Err(e) => {
+            eprintln!("Logging to stderr only: {}", e);
+            (None, None)
+        }
+    };
+
+    // The level filter applies to the log outputs only; command telemetry
+    // needs Tauri's trace-level IPC spans whatever the log level
+    let outputs = format_layer::layer()
+        .with_writer(std::io::stderr)
+        .and_then(file_layer)
+        .and_then(RecentLogs)
+        .with_filter(filter);
+    let result = tracing_subscriber::registry()
+        .with(outputs)
+        .with(telemetry::layer())
+        .with(activity::layer())
+        .try_init();
+    if let Err(e) = result {
+        eprintln!("Failed to initialize logging: {}", e);
+    }
+    guard
+}
+
+fn file_appender() -> Result<RollingFileAppender, String> {
+    let dir = log_dir();
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir)
+        .map_err(|e| format!("Failed to open a log file in {}: {}", dir.display(), e))
+}
+
+/// Deletes log files older than `MAX_LOG_FILES` days, which the appender
+/// only does when it rolls over to a new file, returning how many.
+pub fn prune_log_files() -> Result<usize, String> {
+    let dir = log_dir();
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(format!("Failed to read {}: {}", dir.display(), e)),
+    };
+    let max_age = Duration::from_secs(MAX_LOG_FILES as u64 * 24 * 60 * 60);
+    let mut pruned = 0;
+    for entry in entries.flatten() {
+        if !entry
+            .file_name()
+            .to_string_lossy()
+            .starts_with(LOG_FILE_PREFIX)
+        {
+            continue;
+        }
+        let expired = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > max_age);
+        if expired {
+            std::fs::remove_file(entry.path())
+                .map_err(|e| format!("Failed to delete {}: {}", entry.path().display(), e))?;
+            pruned += 1;
+        }
+    }
+    Ok(pruned)
+}
+
+/// Sets the level for `target` (a module path such as `mighty::commands::storage`,
+/// or a dependency's crate name), or the default level when `target` is `None`.
+pub fn set_level(target: Option<&str>, level: LevelFilter) -> Result<(), String> {
+    let (Some(levels), Some(handle)) = (LEVELS.get(), FILTER_HANDLE.get()) else {
+        return Err("Logging is not initialized".to_string());
+    };
+    let mut levels = levels.lock();
+    match target {
+        Some(target) => {
+            levels.targets.insert(target.to_string(), level);
+        }
+        None => levels.default = level,
+    }
+    handle
+        .reload(levels.filter())
+        .map_err(|e| format!("Failed to change the log level: {}", e))
+}
+
+/// The most recent `limit` entries at `level` or more severe, oldest first.
+pub fn recent(level: Level, limit: usize) -> Vec<LogEntry> {
+    let recent = RECENT.lock();
+    let mut entries: Vec<LogEntry> = recent
+        .iter()
+        .rev()
+        .filter(|entry| entry.severity <= level)
+        .take(limit)
+        .cloned()
+        .collect();
+    entries.reverse();
+    entries
+}
+
+/// Keeps the latest events in memory so they can be read back from the UI.
+struct RecentLogs;
+
+impl<S: Subscriber> Layer<S> for RecentLogs {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        let entry = LogEntry {
+            timestamp: Utc::now().timestamp_millis(),
+            level: metadata.level().to_string(),
+            // Messages bridged from the `log` crate all share the target "log"
+            target: visitor
+                .log_target
+                .unwrap_or_else(|| metadata.target().to_string()),
+            message: visitor.message,
+            fields: visitor.fields,
+            severity: *metadata.level(),
+        };
+
+        let mut recent = RECENT.lock();
+        if recent.len() == RECENT_LOG_CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(entry);
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    log_target: Option<String>,
+    fields: Map<String, Value>,
+}
+
+impl FieldVisitor {
+    fn insert(&mut self, field: &Field, value: Value) {
+        match field.name() {
+            "message" => {
+                self.message = match value {
+                    Value::String(message) => message,
+                    other => other.to_string(),
+                }
+            }
+            "log.target" => {
+                if let Value::String(target) = value {
+                    self.log_target = Some(target);
+                }
+            }
+            // Location details added to messages bridged from the `log` crate
+            name if name.starts_with("log.") => {}
+            name => {
+                self.fields.insert(name.to_string(), value);
+            }
+        }
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, format!("{:?}", value).into());
+    }
+}
@@ -1,15 +1,66 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod commands {
+    pub mod actions;
+    pub mod activity;
     pub mod api;
+    pub mod api_server;
+    pub mod audit;
     pub mod auth;
+    pub mod benchmark;
+    pub mod bookmarks;
+    pub mod budget;
+    pub mod checkpoint;
+    pub mod clipboard;
+    pub mod command_history;
+    pub mod conversation;
+    pub mod deep_link;
+    pub mod deps;
+    pub mod dev_server;
+    pub mod diagnostics;
+    pub mod diff;
+    pub mod dry_run;
+    pub mod edit_transaction;
+    pub mod encoding;
+    pub mod environment;
+    pub mod exec;
+    pub mod extensions;
+    pub mod fanout;
+    pub mod format;
     pub mod fs;
+    pub mod git;
     pub mod greptile;
+    pub mod jobs;
+    pub mod lint;
+    pub mod logs;
+    pub mod metrics;
+    pub mod navigation;
+    pub mod notifications;
+    pub mod oauth;
+    pub mod offline;
+    pub mod onboarding;
+    pub mod permissions;
     pub mod process_manager;
+    pub mod proxy;
+    pub mod recorder;
+    pub mod recent;
+    pub mod redaction;
+    pub mod scheduler;
+    pub mod search;
+    pub mod session;
+    pub mod settings;
+    pub mod shell_integration;
+    pub mod snippets;
+    pub mod startup;
+    pub mod status;
     pub mod storage;
     pub mod terminal;
+    pub mod test_runner;
+    pub mod trust;
+    pub mod updater;
 }
 
+mod activity;
 mod bindings {
     pub mod embed;
     pub mod python_runtime;
@@ -17,112 +68,311 @@ mod bindings {
 
 mod config;
 mod context {
+    pub mod ai_edit;
+    pub mod auto_fix;
+    pub mod chat_memory;
+    pub mod compression;
     pub mod context;
     pub mod context_manager;
+    pub mod doc_gen;
+    pub mod docs;
+    pub mod duplicates;
+    pub mod explain;
+    pub mod file_cache;
+    pub mod file_drop;
+    pub mod filters;
+    pub mod health;
+    pub mod ingest;
+    pub mod lsp_symbols;
+    pub mod pruning;
+    pub mod refactor;
+    pub mod rename;
+    pub mod retrieval;
+    pub mod stack_trace;
+    pub mod summaries;
+    pub mod symbol_index;
+    pub mod warmup;
+    pub mod workspace_stats;
 }
+mod error;
+mod http_client;
+mod logging;
+mod secrets;
+mod state;
+mod telemetry;
 
 use std::fs::create_dir_all;
-use auth::AppState;
-use bindings::{embed, python_runtime};
+use bindings::embed;
 use commands::*;
 use config::AppConfig;
-use log::info;
-use std::{env, path::PathBuf, sync::Arc};
-use tauri::{Listener, Manager};
+use startup::Subsystem;
+use state::AppState;
+use tracing::{error, info, warn};
+use std::{env, path::{Path, PathBuf}, sync::Arc};
+use tauri::{AppHandle, Listener, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
 use tokio::{self, sync::Mutex};
 
-async fn initialize_systems(shared_config: Arc<Mutex<AppConfig>>) -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize Python runtime
-    python_runtime::initialize_python_runtime().await?;
-
+/// Where the database lives: `MIGHTY_DB_PATH`, or next to the executable.
+fn storage_path() -> Result<PathBuf, String> {
     // Setup storage paths
-    let app_dir = std::env::current_exe()?
+    let app_dir = std::env::current_exe()
+        .map_err(|e| e.to_string())?
         .parent()
         .map(|p| p.join("storage"))
         .unwrap_or(PathBuf::from("storage"));
 
     info!("Initializing Storage Directory at: {}", app_dir.display());
 
-    create_dir_all(&app_dir)?;
-    let db_path = app_dir.join("storage.db");
+    create_dir_all(&app_dir).map_err(|e| e.to_string())?;
+    // MIGHTY_DB_PATH moves the database, e.g. onto a volume in a container
+    let db_path = match env::var_os("MIGHTY_DB_PATH").filter(|path| !path.is_empty()) {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            if let Some(parent) = path.parent() {
+                create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            path
+        }
+        None => app_dir.join("storage.db"),
+    };
 
     info!("Database Path: {}", db_path.display());
+    Ok(db_path)
+}
+
+/// Starts each subsystem, announcing it with `subsystem-ready` or
+/// `subsystem-failed`. A failure is left for the UI to show rather than
+/// stopping the app; only what depends on the failed subsystem is skipped.
+/// Python isn't started here but on first use.
+async fn initialize_systems(app: AppHandle, shared_config: Arc<Mutex<AppConfig>>) {
+    startup::begin(app.clone());
+
+    // Independent of storage, so these go first
+    let fs_config = shared_config.lock().await.fs.clone();
+    startup::start(Subsystem::FileSystem, async {
+        commands::fs::initialize_fs(fs_config.as_ref()).map_err(|e| e.to_string())
+    })
+    .await;
 
-    // Set DB_PATH environment variable to ensure consistency
-    env::set_var("DB_PATH", db_path.to_str().unwrap());
-    info!("Set DB_PATH to: {}", env::var("DB_PATH").unwrap());
+    let format_config = shared_config.lock().await.format.clone();
+    startup::start(Subsystem::Format, async {
+        commands::format::initialize_format(format_config.as_ref());
+        Ok(())
+    })
+    .await;
 
     // Initialize storage system **before** ProcessManager
-    commands::storage::initialize_storage(&db_path).await?;
+    let storage_ready = startup::start(Subsystem::Storage, async {
+        let db_path = storage_path()?;
+        // Set DB_PATH environment variable to ensure consistency
+        env::set_var("DB_PATH", &db_path);
+        commands::storage::initialize_storage(&db_path)
+            .await
+            .map_err(|e| e.to_string())
+    })
+    .await;
+    if !storage_ready {
+        let reason = "Storage is unavailable".to_string();
+        startup::mark_failed(Subsystem::Jobs, reason.clone());
+        startup::mark_failed(Subsystem::ProcessManager, reason);
+        return;
+    }
+
+    // Resume background jobs saved by the previous run
+    startup::start(Subsystem::Jobs, async {
+        commands::jobs::initialize_jobs(app.clone());
+        commands::scheduler::start();
+        commands::budget::start(app);
+        Ok(())
+    })
+    .await;
 
     // Force cleanup any stale locks first
     if let Err(e) = commands::process_manager::force_cleanup_locks().await {
-        eprintln!("Warning: Failed to cleanup stale locks: {}", e);
+        warn!("Failed to cleanup stale locks: {}", e);
     }
 
     // Initialize Process Manager with default options
-    let process_manager_options = None; // Will use default options
-    commands::process_manager::initialize_process_manager(process_manager_options).await?;
+    startup::start(
+        Subsystem::ProcessManager,
+        commands::process_manager::initialize_process_manager(None),
+    )
+    .await;
+}
 
-    // Initialize filesystem service
-    commands::fs::initialize_fs()?;
+/// Stops the processes and servers the app started.
+fn stop_services() {
+    commands::dev_server::stop_all_dev_servers();
+    commands::extensions::stop_all_extensions();
+    commands::api_server::stop_api_server();
+    telemetry::save();
+}
 
-    Ok(())
+/// Saves state and releases storage and locks.
+async fn release_resources() {
+    // Save terminals and the session before storage shuts down
+    commands::terminal::persist_terminal_sessions();
+    commands::session::flush_session_state();
+
+    if let Err(e) = commands::process_manager::force_cleanup_locks().await {
+        error!("Failed to cleanup locks: {}", e);
+    }
+
+    if let Err(e) = commands::storage::cleanup_storage().await {
+        error!("Failed to cleanup storage: {}", e);
+    }
+
+    if let Err(e) = commands::process_manager::cleanup_process_manager().await {
+        error!("Failed to cleanup process manager: {}", e);
+    }
 }
 
 /// Cleans up resources when the application exits.
 fn cleanup_on_exit() {
-    tauri::async_runtime::spawn(async {
-        if let Err(e) = commands::process_manager::force_cleanup_locks().await {
-            eprintln!("Failed to cleanup locks: {}", e);
-        }
+    stop_services();
+    tauri::async_runtime::spawn(release_resources());
+}
 
-        if let Err(e) = commands::storage::cleanup_storage().await {
-            eprintln!("Failed to cleanup storage: {}", e);
-        }
+/// Runs every cleanup hook and waits for them to finish, before the app
+/// is replaced by an update.
+pub(crate) async fn cleanup_before_restart() {
+    if let Err(e) = bindings::python_runtime::cleanup_all_systems().await {
+        error!("Error during cleanup: {}", e);
+    }
+    stop_services();
+    release_resources().await;
+}
 
-        if let Err(e) = commands::process_manager::cleanup_process_manager().await {
-            eprintln!("Failed to cleanup process manager: {}", e);
-        }
-    });
+/// Applies `--config <path>` and `--workspace <path>`, or the
+/// `MIGHTY_CONFIG` and `MIGHTY_WORKSPACE` environment variables; a folder
+/// given on its own, as in `mighty .`, is the workspace too. Relative
+/// paths are taken from the directory the app was started in.
+fn apply_command_line() -> Result<(), Box<dyn std::error::Error>> {
+    let start_dir = env::current_dir()?;
+    let mut config_path = env::var_os("MIGHTY_CONFIG").filter(|path| !path.is_empty()).map(PathBuf::from);
+    let mut workspace = env::var_os("MIGHTY_WORKSPACE").filter(|path| !path.is_empty()).map(PathBuf::from);
+
+    // Anything else is left alone; platforms pass arguments of their own
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        let target = match flag.as_str() {
+            "--config" => &mut config_path,
+            "--workspace" => &mut workspace,
+            _ => {
+                if workspace.is_none() && !flag.starts_with('-') && start_dir.join(&flag).is_dir() {
+                    workspace = Some(PathBuf::from(&flag));
+                }
+                continue;
+            }
+        };
+        let value = value.or_else(|| args.next()).ok_or_else(|| format!("{} needs a path", flag))?;
+        *target = Some(PathBuf::from(value));
+    }
+
+    if let Some(config_path) = config_path {
+        AppConfig::set_path(start_dir.join(config_path));
+    }
+    // The workspace is wherever the app runs from
+    if let Some(workspace) = workspace {
+        env::set_current_dir(start_dir.join(&workspace))
+            .map_err(|e| format!("Failed to open workspace {}: {}", workspace.display(), e))?;
+    }
+    // Handed to the UI once it's ready to open them
+    let args: Vec<String> = env::args().skip(1).collect();
+    deep_link::defer_open(deep_link::parse_args(&args, &start_dir));
+    Ok(())
 }
 
 fn main() {
-    // Initialize logging
-    env_logger::init();
+    // Initialize logging; the guard flushes the log file on exit
+    let _log_guard = logging::init();
+
+    if let Err(e) = apply_command_line() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
 
     // Load configuration
+    // A broken config file shouldn't keep the app from starting; validate_config
+    // reports it, and fixing the file reloads it
     let config = match AppConfig::load() {
-        Ok(cfg) => cfg,
+        Ok(cfg) => {
+            info!("Configuration loaded from {}", AppConfig::path().display());
+            cfg
+        }
         Err(e) => {
-            eprintln!("Failed to load configuration: {}", e);
-            std::process::exit(1);
+            warn!("Failed to load configuration, using defaults: {}", e);
+            AppConfig::default()
         }
     };
 
-    info!("Configuration loaded successfully.");
+    // Merge the workspace's own settings over the global ones
+    let config = settings::resolve_config(config);
+    for issue in config.validate() {
+        warn!("Configuration {:?} at {}: {}", issue.severity, issue.path, issue.message);
+    }
+    telemetry::configure(config.telemetry.as_ref());
+    jobs::configure(config.jobs.as_ref());
+    activity::configure(config.jobs.as_ref());
+    scheduler::configure(config.scheduler.as_ref());
+    budget::configure(config.budget.as_ref());
+    storage::configure(config.storage.as_ref());
+    notifications::configure(config.notifications.as_ref());
+    session::configure(config.session.as_ref());
+    extensions::configure(config.extensions.as_ref());
+    api_server::configure(config.api_server.as_ref());
+    http_client::configure(config.http.as_ref());
+    recorder::configure(config.recorder.as_ref());
+    secrets::configure(config.secrets.as_ref());
+    redaction::configure(config.redaction.as_ref());
+    trust::configure(config.trust.as_ref());
+    context::filters::configure(config.context.as_ref());
+    context::pruning::configure(config.workspace.as_ref());
+    actions::register_builtin();
 
-    // Wrap config in Arc<Mutex<_>> for async access
-    let shared_config = Arc::new(Mutex::new(config));
+    // Everything the backend keeps, config included, lives in one state
+    let app_state = AppState::init(config);
+    let shared_config = app_state.config.clone();
 
     // Initialize and run the Tauri application
     tauri::Builder::default()
+        // Launching again, e.g. `mighty .` from a shell, hands the command
+        // line to this instance and exits; it must come before other plugins
+        .plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
+            let args = args.get(1..).unwrap_or_default();
+            deep_link::request_open(app, deep_link::parse_args(args, Path::new(&cwd)));
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         // Register necessary plugins
         .plugin(tauri_plugin_store::Builder::new().build())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_dialog::init())
-        // Manage other app states
-        .manage(AppState::new())
-        // Manage shared_config
-        .manage(shared_config.clone())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        // Manage the app state
+        .manage(app_state)
         // Register command handlers
         .invoke_handler(tauri::generate_handler![
             // Auth commands
             auth::get_auth_token,
             auth::store_auth_token,
             auth::has_auth_token,
+            auth::store_git_credentials,
+            auth::clear_git_credentials,
+            auth::set_secret,
+            auth::get_secret,
+            auth::delete_secret,
+            auth::list_secret_names,
+            oauth::begin_oauth_login,
+            oauth::get_login_status,
             // Storage commands
             storage::store_value,
             storage::get_value,
@@ -135,13 +385,148 @@ fn main() {
             fs::create_directory,
             fs::delete_path,
             fs::rename_path,
+            fs::watch_path,
+            fs::unwatch_path,
+            fs::list_watched_paths,
+            // Diff commands
+            diff::diff_text,
+            diff::apply_patch,
+            // Checkpoint commands
+            checkpoint::list_file_checkpoints,
+            checkpoint::restore_checkpoint,
+            // Edit transaction commands
+            edit_transaction::begin_edit_transaction,
+            edit_transaction::stage_edit,
+            edit_transaction::commit_edit_transaction,
+            edit_transaction::rollback_edit_transaction,
+            // Format commands
+            format::format_document,
+            // Lint commands
+            lint::run_linter,
+            // Log commands
+            logs::get_recent_logs,
+            logs::set_log_level,
+            // Startup commands
+            startup::get_startup_status,
+            // Metrics commands
+            metrics::get_command_metrics,
+            metrics::reset_command_metrics,
+            // Notification commands
+            notifications::list_notifications,
+            notifications::mark_read,
+            // Permission commands
+            permissions::respond_to_permission,
+            permissions::list_pending_permissions,
+            permissions::get_permission_policy,
+            permissions::set_permission_policy,
+            trust::get_workspace_trust,
+            trust::set_workspace_trust,
+            dry_run::set_agent_dry_run,
+            dry_run::get_agent_plan,
+            dry_run::clear_agent_plan,
+            // Extension commands
+            extensions::list_extensions,
+            extensions::enable_extension,
+            extensions::list_extension_tools,
+            extensions::call_extension_tool,
+            // Clipboard commands
+            clipboard::read_clipboard,
+            clipboard::paste_as_file,
+            clipboard::paste_image,
+            // Snippet commands
+            snippets::list_snippets,
+            snippets::save_snippet,
+            snippets::delete_snippet,
+            snippets::render_snippet,
+            snippets::create_file_from_template,
+            // Session commands
+            session::save_session_state,
+            session::load_session_state,
+            // Navigation commands
+            navigation::push_location,
+            navigation::navigate_back,
+            navigation::navigate_forward,
+            navigation::get_navigation_history,
+            // Bookmark commands
+            bookmarks::add_bookmark,
+            bookmarks::list_bookmarks,
+            bookmarks::update_bookmark,
+            bookmarks::delete_bookmark,
+            // API server commands
+            api_server::get_api_server_status,
+            api_server::regenerate_api_server_token,
+            // Recent item commands
+            recent::record_recent_item,
+            recent::get_recent_items,
+            recent::remove_recent_items,
+            // Audit commands
+            audit::get_audit_log,
+            audit::export_audit_log,
+            audit::verify_audit_log,
+            // Test commands
+            test_runner::discover_tests,
+            test_runner::run_tests,
+            // Environment commands
+            environment::detect_environment,
+            // Dev server commands
+            dev_server::start_dev_server,
+            dev_server::stop_dev_server,
+            dev_server::remove_dev_server,
+            dev_server::list_dev_servers,
+            dev_server::get_process_logs,
+            // Git commands
+            git::git_status,
+            git::git_diff,
+            git::git_log,
+            git::git_show,
+            git::git_stage,
+            git::git_unstage,
+            git::git_commit,
+            git::git_branches,
+            git::git_checkout,
+            git::git_discard,
+            git::git_blame,
+            git::git_diff_hunks,
+            git::git_stage_hunk,
+            git::git_revert_hunk,
+            git::git_fetch,
+            git::git_pull,
+            git::git_push,
+            // Job commands
+            jobs::enqueue_job,
+            jobs::list_jobs,
+            jobs::cancel_job,
+            // Activity commands
+            commands::activity::report_activity,
+            commands::activity::get_activity_status,
+            // Budget commands
+            budget::get_budget_usage,
+            // Benchmark commands
+            benchmark::run_benchmark,
+            // Maintenance scheduler commands
+            scheduler::list_scheduled_jobs,
+            scheduler::run_scheduled_job,
             // Terminal commands
             terminal::create_terminal_session,
             terminal::write_to_terminal,
             terminal::resize_terminal,
             terminal::terminate_terminal_session,
+            terminal::get_terminal_buffer,
+            terminal::list_terminal_sessions,
+            terminal::reattach_terminal,
+            terminal::run_in_terminal,
+            command_history::get_command_history,
             // AI commands
             api::anthropic_completion,
+            conversation::get_conversation_summary,
+            conversation::clear_conversation_summary,
+            fanout::completion_fanout,
+            offline::set_offline,
+            offline::get_offline,
+            proxy::proxy_request,
+            recorder::list_recorded_requests,
+            recorder::replay_request,
+            redaction::preview_outgoing_payload,
             // Context commands
             context::context::init_context_manager,
             context::context::get_context,
@@ -150,8 +535,32 @@ fn main() {
             context::context::add_to_context,
             context::context::search_similar_code,
             context::context::get_file_context,
+            context::context::get_cached_file_context,
             context::context::is_file_in_context,
             context::context::get_context_stats,
+            context::chat_memory::recall_related_conversations,
+            context::chat_memory::remember_conversation,
+            context::chat_memory::forget_conversation,
+            context::ai_edit::ai_edit_range,
+            context::duplicates::find_duplicate_code,
+            context::explain::explain_range,
+            context::filters::get_skipped_files,
+            context::health::verify_context_index,
+            context::health::repair_context_index,
+            context::refactor::plan_refactor,
+            context::refactor::execute_refactor,
+            context::rename::rename_symbol,
+            context::doc_gen::generate_docs,
+            context::stack_trace::resolve_stack_trace,
+            context::auto_fix::auto_fix,
+            context::auto_fix::respond_to_auto_fix,
+            context::auto_fix::cancel_auto_fix,
+            context::retrieval::explain_retrieval,
+            context::symbol_index::search_symbols,
+            context::symbol_index::get_symbol_docs,
+            context::warmup::open_workspace,
+            context::file_drop::ingest_dropped_files,
+            context::workspace_stats::get_workspace_stats,
             // Process Manager commands
             process_manager::kill_other_instances,
             process_manager::force_cleanup_locks,
@@ -159,9 +568,37 @@ fn main() {
             embed::embed_sentence,
             // Greptile commands
             greptile::greptile_search,
+            greptile::greptile_query,
             greptile::test_greptile_connection,
+            greptile::set_greptile_api_key,
+            // Search commands
+            search::search,
+            // Settings commands
+            settings::get_config,
+            settings::get_effective_config,
+            settings::update_config,
+            settings::validate_config,
+            // Action commands
+            actions::list_actions,
+            actions::invoke_action,
+            actions::list_agent_tools,
+            actions::call_agent_tool,
+            // Status commands
+            status::get_system_status,
+            // Deep link commands
+            deep_link::take_open_requests,
+            // Onboarding commands
+            onboarding::get_onboarding_state,
+            onboarding::complete_onboarding_step,
+            onboarding::skip_onboarding_step,
+            onboarding::reset_onboarding,
             // Storage cleanup
             storage::cleanup_storage,
+            // Update commands
+            updater::check_for_updates,
+            updater::install_update,
+            // Dependency commands
+            deps::get_dependency_report,
         ])
         // Setup window event handlers
         .setup(move |app| {
@@ -173,13 +610,23 @@ fn main() {
             let main_window = app.get_webview_window("main").unwrap();
 
             // Handle window close event with proper cleanup
+            let drop_handle = app_handle.clone();
             main_window.on_window_event(move |event| {
+                // Coming back to the window counts as activity, like typing
+                if let tauri::WindowEvent::Focused(true) = event {
+                    activity::note();
+                }
+                // Files dragged onto the window from the OS
+                if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                    context::file_drop::handle_drop(drop_handle.clone(), paths.clone());
+                    return;
+                }
                 let event = event.clone();
                 tauri::async_runtime::spawn(async move {
                     if let tauri::WindowEvent::CloseRequested { .. } = event {
                         // Cleanup all systems
                         if let Err(e) = bindings::python_runtime::cleanup_all_systems().await {
-                            eprintln!("Error during cleanup: {}", e);
+                            error!("Error during cleanup: {}", e);
                         }
 
                         // Additional cleanup if necessary
@@ -188,18 +635,45 @@ fn main() {
                 });
             });
 
-            // Initialize systems asynchronously
-            tauri::async_runtime::spawn(async move {
-                if let Err(e) = initialize_systems(shared_config.clone()).await {
-                    eprintln!("Failed to initialize systems: {}", e);
-                    // Optionally, you can terminate the application or notify the user
-                    // For example, you might want to exit the process:
-                    std::process::exit(1);
-                }
+            // Pick up hand edits to the config file
+            if let Err(e) = settings::watch_config_file(app_handle.clone(), shared_config.clone()) {
+                warn!("Failed to watch the config file: {}", e);
+            }
+
+            // `mighty://open?path=...` links, registered with the OS where
+            // that's done at runtime rather than by the installer
+            #[cfg(any(windows, target_os = "linux"))]
+            if let Err(e) = app.deep_link().register_all() {
+                warn!("Failed to register the {}:// scheme: {}", deep_link::SCHEME, e);
+            }
+            let link_handle = app_handle.clone();
+            app.deep_link().on_open_url(move |event| {
+                let requests = event.urls().iter().filter_map(|url| deep_link::parse_link(url.as_str())).collect();
+                deep_link::request_open(&link_handle, requests);
             });
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                deep_link::defer_open(urls.iter().filter_map(|url| deep_link::parse_link(url.as_str())).collect());
+            }
+
+            // Lets external editors drive the backend, when enabled
+            api_server::initialize_api_server(app_handle.clone());
+
+            // Looks for new versions in the background
+            updater::start(app_handle.clone());
+
+            // Initialize systems asynchronously
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(initialize_systems(app_handle, shared_config.clone()));
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, _event| {
+            // macOS opens files from Finder with an event, not arguments
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = &_event {
+                deep_link::request_open(_app, deep_link::parse_file_urls(urls));
+            }
+        });
 }
\ No newline at end of file
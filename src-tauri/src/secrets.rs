@@ -0,0 +1,431 @@
+// src/secrets.rs
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::ops::Range;
+use tauri::{AppHandle, Emitter};
+use tracing::warn;
+
+use crate::commands::notifications::{notify, NotificationLevel};
+use crate::commands::permissions::Actor;
+use crate::commands::redaction::{self, Redactions};
+use crate::config::{SecretPolicy, SecretsConfig};
+
+/// Values this short are too weak to be keys, or too common to flag.
+const MIN_GENERIC_LENGTH: usize = 12;
+/// Bits per character a value assigned to a secret-sounding name needs to
+/// count as a secret; random keys sit well above, words and identifiers
+/// below.
+const GENERIC_ENTROPY: f64 = 3.5;
+
+struct Settings {
+    redact: bool,
+    agent_writes: SecretPolicy,
+    allow: Vec<Regex>,
+}
+
+static SETTINGS: Lazy<RwLock<Settings>> = Lazy::new(|| {
+    RwLock::new(Settings {
+        redact: true,
+        agent_writes: SecretPolicy::Warn,
+        allow: Vec::new(),
+    })
+});
+
+/// A kind of secret and how to find it: the `secret` group of `pattern`,
+/// or the whole match. Rules matching on a name rather than a known key
+/// format also need the value to be long and random enough, and not read
+/// as a placeholder.
+struct Rule {
+    kind: &'static str,
+    pattern: Regex,
+    min_length: usize,
+    min_entropy: f64,
+}
+
+fn rule(kind: &'static str, pattern: &str) -> Rule {
+    heuristic(kind, pattern, 0, 0.0)
+}
+
+fn heuristic(kind: &'static str, pattern: &str, min_length: usize, min_entropy: f64) -> Rule {
+    Rule {
+        kind,
+        pattern: Regex::new(pattern).expect("the pattern is valid"),
+        min_length,
+        min_entropy,
+    }
+}
+
+/// Most specific first, so a match is reported as the narrowest kind.
+static RULES: Lazy<Vec<Rule>> = Lazy::new(|| {
+    vec![
+        rule(
+            "private_key",
+            r"-----BEGIN (?:[A-Z]+ )*PRIVATE KEY-----[\s\S]*?-----END (?:[A-Z]+ )*PRIVATE KEY-----",
+        ),
+        rule(
+            "aws_access_key",
+            r"\b(?P<secret>(?:AKIA|ASIA|AGPA|AIDA|AROA|ANPA|ANVA|AIPA)[0-9A-Z]{16})\b",
+        ),
+        heuristic(
+            "aws_secret_key",
+            r#"(?i)aws_?secret_?(?:access_?)?key['"]?\s*[:=]\s*['"]?(?P<secret>[A-Za-z0-9/+=]{40})\b"#,
+            40,
+            4.0,
+        ),
+        rule(
+            "github_token",
+            r"\b(?P<secret>(?:ghp|gho|ghu|ghs|ghr)_[A-Za-z0-9]{36,255}|github_pat_[A-Za-z0-9_]{22,255})\b",
+        ),
+        rule("gitlab_token", r"\b(?P<secret>glpat-[A-Za-z0-9_-]{20,})"),
+        rule("slack_token", r"\b(?P<secret>xox[abprs]-[A-Za-z0-9-]{10,})"),
+        rule(
+            "stripe_key",
+            r"\b(?P<secret>(?:sk|rk)_live_[A-Za-z0-9]{20,})\b",
+        ),
+        rule("anthropic_key", r"\b(?P<secret>sk-ant-[A-Za-z0-9_-]{20,})"),
+        rule(
+            "openai_key",
+            r"\b(?P<secret>sk-(?:proj-|svcacct-)?[A-Za-z0-9_-]{32,})",
+        ),
+        rule("google_api_key", r"\b(?P<secret>AIza[0-9A-Za-z_-]{35})"),
+        rule(
+            "jwt",
+            r"\b(?P<secret>eyJ[A-Za-z0-9_-]{10,}\.eyJ[A-Za-z0-9_-]{10,}\.[A-Za-z0-9_-]{10,})",
+        ),
+        heuristic(
+            "url_password",
+            r"\b[a-z][a-z0-9+.-]*://[^:/\s@]+:(?P<secret>[^@/\s]{6,})@",
+            8,
+            2.5,
+        ),
+        heuristic(
+            "generic_secret",
+            r#"(?i)[\w.-]*(?:secret|token|passw(?:or)?d|pwd|api_?key|apikey|access_?key|auth_?key|credentials?)[\w.-]*['"]?\s*(?::|=|:=|=>)\s*['"](?P<secret>[^'"\s]+)['"]"#,
+            MIN_GENERIC_LENGTH,
+            GENERIC_ENTROPY,
+        ),
+    ]
+});
+
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)^(?:x+|\*+|\.+)$|example|sample|dummy|changeme|placeholder|your[_-]|redacted|\$\{|\{\{|^<.*>$|^%.*%$|^process\.env|^os\.environ",
+    )
+    .expect("the pattern is valid")
+});
+
+/// A secret found in some text.
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretFinding {
+    /// e.g. `aws_access_key` or `generic_secret`.
+    pub kind: &'static str,
+    /// 1-based.
+    pub line: usize,
+    /// The value with all but its first and last few characters masked.
+    pub preview: String,
+    #[serde(skip)]
+    range: Range<usize>,
+}
+
+/// Payload of the `secrets-detected` event.
+#[derive(Debug, Clone, Serialize)]
+struct SecretsDetected<'a> {
+    path: &'a str,
+    findings: &'a [SecretFinding],
+    blocked: bool,
+}
+
+/// Applies the `[secrets]` settings.
+pub(crate) fn configure(config: Option<&SecretsConfig>) {
+    let config = config.cloned().unwrap_or_default();
+    let allow = config
+        .allow
+        .iter()
+        .filter_map(|pattern| match Regex::new(pattern) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                warn!("Ignoring the secrets.allow pattern {}: {}", pattern, e);
+                None
+            }
+        })
+        .collect();
+    *SETTINGS.write() = Settings {
+        redact: config.redact.unwrap_or(true),
+        agent_writes: config.agent_writes.unwrap_or_default(),
+        allow,
+    };
+}
+
+/// Shannon entropy in bits per character.
+fn entropy(value: &str) -> f64 {
+    let length = value.chars().count() as f64;
+    if length == 0.0 {
+        return 0.0;
+    }
+    let mut counts = std::collections::HashMap::new();
+    for c in value.chars() {
+        *counts.entry(c).or_insert(0usize) += 1;
+    }
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / length;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn preview(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() <= 12 {
+        return "*".repeat(chars.len());
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+/// Finds API keys, tokens, private keys and high-entropy values assigned
+/// to secret-sounding names, in the order they appear.
+pub(crate) fn scan(text: &str) -> Vec<SecretFinding> {
+    let settings = SETTINGS.read();
+    let mut candidates: Vec<(&'static str, Range<usize>)> = Vec::new();
+    for rule in RULES.iter() {
+        for captures in rule.pattern.captures_iter(text) {
+            let found = captures
+                .name("secret")
+                .or_else(|| captures.get(0))
+                .expect("the whole match");
+            let value = found.as_str();
+            if rule.min_entropy > 0.0
+                && (value.len() < rule.min_length
+                    || PLACEHOLDER.is_match(value)
+                    || entropy(value) < rule.min_entropy)
+            {
+                continue;
+            }
+            if settings.allow.iter().any(|allow| allow.is_match(value)) {
+                continue;
+            }
+            candidates.push((rule.kind, found.range()));
+        }
+    }
+
+    // Earlier rules are more specific, so they win ties over the same text
+    candidates.sort_by_key(|(_, range)| range.start);
+    let mut findings: Vec<SecretFinding> = Vec::new();
+    for (kind, range) in candidates {
+        if findings
+            .last()
+            .is_some_and(|last| range.start < last.range.end)
+        {
+            continue;
+        }
+        findings.push(SecretFinding {
+            kind,
+            line: text[..range.start].matches('\n').count() + 1,
+            preview: preview(&text[range.clone()]),
+            range,
+        });
+    }
+    findings
+}
+
+fn redaction_enabled() -> bool {
+    SETTINGS.read().redact
+}
+
+/// `text` with each secret replaced by `[REDACTED:<kind>]`, padded with the
+/// newlines it spanned so line numbers stay put. For text that is kept,
+/// such as indexed chunks; `Redactor` is for text sent and answered.
+pub(crate) fn redact(text: &str) -> String {
+    if !redaction_enabled() {
+        return text.to_string();
+    }
+    let findings = scan(text);
+    if findings.is_empty() {
+        return text.to_string();
+    }
+    let mut redacted = String::with_capacity(text.len());
+    let mut last = 0;
+    for finding in findings {
+        let secret = &text[finding.range.clone()];
+        redacted.push_str(&text[last..finding.range.start]);
+        redacted.push_str(&format!("[REDACTED:{}]", finding.kind));
+        redacted.push_str(&"\n".repeat(secret.matches('\n').count()));
+        last = finding.range.end;
+    }
+    redacted.push_str(&text[last..]);
+    redacted
+}
+
+/// Swaps secrets for numbered placeholders in what goes to a model
+/// provider, and back in its reply, so code it echoes or edits keeps the
+/// real values without the provider seeing them. The `[redaction]` policy
+/// is applied on top, one way.
+#[derive(Debug, Default)]
+pub(crate) struct Redactor {
+    /// Placeholder and secret pairs.
+    secrets: Vec<(String, String)>,
+    redactions: Redactions,
+}
+
+impl Redactor {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn redact(&mut self, text: &str) -> String {
+        let text = self.replace_secrets(text);
+        redaction::scrub(&text, &mut self.redactions)
+    }
+
+    fn replace_secrets(&mut self, text: &str) -> String {
+        if !redaction_enabled() {
+            return text.to_string();
+        }
+        let findings = scan(text);
+        if findings.is_empty() {
+            return text.to_string();
+        }
+        let mut redacted = String::with_capacity(text.len());
+        let mut last = 0;
+        for finding in findings {
+            let secret = &text[finding.range.clone()];
+            let known = self.secrets.iter().find(|(_, known)| known == secret);
+            let placeholder = match known {
+                Some((placeholder, _)) => placeholder.clone(),
+                None => {
+                    let placeholder =
+                        format!("[REDACTED:{}:{}]", finding.kind, self.secrets.len() + 1);
+                    self.secrets.push((placeholder.clone(), secret.to_string()));
+                    placeholder
+                }
+            };
+            *self.redactions.entry(finding.kind.to_string()).or_default() += 1;
+            redacted.push_str(&text[last..finding.range.start]);
+            redacted.push_str(&placeholder);
+            last = finding.range.end;
+        }
+        redacted.push_str(&text[last..]);
+        redacted
+    }
+
+    /// Redacts every string in a JSON request body.
+    pub(crate) fn redact_json(&mut self, value: &mut Value) {
+        match value {
+            Value::String(text) => *text = self.redact(text),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_json(item)),
+            Value::Object(fields) => fields
+                .values_mut()
+                .for_each(|field| self.redact_json(field)),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn restore(&self, text: &str) -> String {
+        self.secrets
+            .iter()
+            .fold(text.to_string(), |text, (placeholder, secret)| {
+                text.replace(placeholder, secret)
+            })
+    }
+
+    /// `restore` for a JSON response body, escaping what's put back.
+    pub(crate) fn restore_json(&self, text: &str) -> String {
+        self.secrets
+            .iter()
+            .fold(text.to_string(), |text, (placeholder, secret)| {
+                let escaped = serde_json::to_string(secret).unwrap_or_default();
+                text.replace(placeholder, escaped.trim_matches('"'))
+            })
+    }
+
+    /// What has been redacted so far, by kind.
+    pub(crate) fn redactions(&self) -> &Redactions {
+        &self.redactions
+    }
+}
+
+/// Checks what the agent is about to write to `path` for secrets `before`
+/// didn't have. New ones are announced through a `secrets-detected` event
+/// and a notification, and fail the write when `secrets.agent_writes` is
+/// `block`. The user's own writes aren't checked.
+pub(crate) fn check_agent_write(
+    app: &AppHandle,
+    actor: Actor,
+    path: &str,
+    before: Option<&str>,
+    after: &str,
+) -> Result<(), String> {
+    if actor != Actor::Agent {
+        return Ok(());
+    }
+    let existing: HashSet<&str> = before
+        .map(|before| {
+            scan(before)
+                .into_iter()
+                .map(|finding| &before[finding.range])
+                .collect()
+        })
+        .unwrap_or_default();
+    let added: Vec<SecretFinding> = scan(after)
+        .into_iter()
+        .filter(|finding| !existing.contains(&after[finding.range.clone()]))
+        .collect();
+    if added.is_empty() {
+        return Ok(());
+    }
+
+    let blocked = SETTINGS.read().agent_writes == SecretPolicy::Block;
+    let event = SecretsDetected {
+        path,
+        findings: &added,
+        blocked,
+    };
+    if let Err(e) = app.emit("secrets-detected", &event) {
+        warn!("Failed to emit secrets-detected: {}", e);
+    }
+    let mut kinds: Vec<&str> = added.iter().map(|finding| finding.kind).collect();
+    kinds.sort_unstable();
+    kinds.dedup();
+    let description = format!(
+        "{} secret(s) ({}) in {}",
+        added.len(),
+        kinds.join(", "),
+        path
+    );
+    warn!(
+        "The agent {} {}",
+        if blocked {
+            "was stopped writing"
+        } else {
+            "wrote"
+        },
+        description
+    );
+    notify(
+        app,
+        NotificationLevel::Warning,
+        "secrets",
+        if blocked {
+            "Blocked the agent from writing a secret"
+        } else {
+            "The agent wrote what looks like a secret"
+        },
+        Some(description.clone()),
+    );
+    if blocked {
+        return Err(format!(
+            "Not writing {}: keep secrets in the environment or the vault, or list them \
+             under secrets.allow",
+            description
+        ));
+    }
+    Ok(())
+}
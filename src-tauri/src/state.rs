@@ -0,0 +1,72 @@
+// src/state.rs
+
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Arc, OnceLock};
+
+use crate::bindings::python_runtime::PythonRuntime;
+use crate::commands::auth::AuthState;
+use crate::commands::fs::FileWatcher;
+use crate::commands::storage::StorageManager;
+use crate::commands::terminal::TerminalInstance;
+use crate::config::AppConfig;
+use crate::context::context::ContextState;
+
+static APP_STATE: OnceLock<AppState> = OnceLock::new();
+
+/// Everything the backend holds for the life of the app. Tauri manages it,
+/// so commands take `State<'_, AppState>`; code that runs without a handle,
+/// such as background tasks and storage helpers, reaches the same state
+/// through `app_state()`. Clones share one set of state.
+#[derive(Clone)]
+pub struct AppState(Arc<SharedState>);
+
+pub struct SharedState {
+    /// The merged configuration in effect, replaced on reload.
+    pub config: Arc<tokio::sync::Mutex<AppConfig>>,
+    pub auth: AuthState,
+    /// `None` until `initialize_systems` opens the database, and again once
+    /// it has been shut down.
+    pub(crate) storage: parking_lot::RwLock<Option<StorageManager>>,
+    /// Running shells by session id.
+    pub(crate) terminals: std::sync::Mutex<HashMap<String, TerminalInstance>>,
+    /// Watches the workspace root.
+    pub(crate) file_watcher: parking_lot::Mutex<Option<FileWatcher>>,
+    pub(crate) context: ContextState,
+    pub(crate) python: parking_lot::Mutex<Option<PythonRuntime>>,
+}
+
+impl Deref for AppState {
+    type Target = SharedState;
+
+    fn deref(&self) -> &SharedState {
+        &self.0
+    }
+}
+
+impl AppState {
+    /// Creates the app's state around `config`. Called once, in `main`,
+    /// before anything reads it.
+    pub fn init(config: AppConfig) -> AppState {
+        APP_STATE
+            .get_or_init(|| {
+                AppState(Arc::new(SharedState {
+                    config: Arc::new(tokio::sync::Mutex::new(config)),
+                    auth: AuthState::new(),
+                    storage: parking_lot::RwLock::new(None),
+                    terminals: std::sync::Mutex::new(HashMap::new()),
+                    file_watcher: parking_lot::Mutex::new(None),
+                    context: ContextState::new(),
+                    python: parking_lot::Mutex::new(None),
+                }))
+            })
+            .clone()
+    }
+}
+
+/// The state `main` created, for code that has no `State` or `AppHandle`.
+pub fn app_state() -> &'static AppState {
+    APP_STATE
+        .get()
+        .expect("app state is created in main before use")
+}
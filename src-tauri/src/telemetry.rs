@@ -0,0 +1,575 @@
+// src-tauri/src/telemetry.rs
+
+use chrono::Utc;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{info, warn, Subscriber};
+use tracing_subscriber::filter::dynamic_filter_fn;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::commands::offline::ensure_reachable;
+use crate::config::{TelemetryConfig, APP_IDENTIFIER};
+use crate::http_client;
+
+const METRICS_FILE_NAME: &str = "metrics.json";
+const DEFAULT_EXPORT_INTERVAL: Duration = Duration::from_secs(60);
+/// Upper bounds of the duration histogram buckets, in milliseconds.
+const BUCKET_BOUNDS_MS: [f64; 13] = [
+    1.0, 2.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0,
+];
+/// Durations kept per command for percentiles.
+const RECENT_SAMPLES: usize = 500;
+/// Tauri's IPC spans (from its `tracing` feature) bracket every command.
+const IPC_TARGET: &str = "tauri::ipc";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static EXPORT: Mutex<ExportSettings> = Mutex::new(ExportSettings {
+    endpoint: None,
+    interval: DEFAULT_EXPORT_INTERVAL,
+});
+static METRICS: Mutex<Metrics> = Mutex::new(Metrics {
+    since: 0,
+    commands: BTreeMap::new(),
+    stages: BTreeMap::new(),
+});
+/// Set once the task that saves and exports metrics is running.
+static FLUSHER: OnceLock<()> = OnceLock::new();
+
+struct ExportSettings {
+    endpoint: Option<String>,
+    interval: Duration,
+}
+
+/// Everything recorded since `since`, as saved to the metrics file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Metrics {
+    /// Milliseconds since the Unix epoch.
+    since: i64,
+    commands: BTreeMap<String, TimingStats>,
+    stages: BTreeMap<String, TimingStats>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TimingStats {
+    calls: u64,
+    failures: u64,
+    total_ms: f64,
+    max_ms: f64,
+    /// Calls per `BUCKET_BOUNDS_MS` bucket, then the calls slower than all of them.
+    buckets: Vec<u64>,
+    request_bytes: u64,
+    response_bytes: u64,
+    recent_ms: VecDeque<f64>,
+}
+
+impl TimingStats {
+    fn record(&mut self, elapsed: Duration, failed: bool, request_bytes: u64, response_bytes: u64) {
+        let ms = elapsed.as_secs_f64() * 1000.0;
+        self.calls += 1;
+        self.failures += u64::from(failed);
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+        self.buckets.resize(BUCKET_BOUNDS_MS.len() + 1, 0);
+        let bucket = BUCKET_BOUNDS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.buckets[bucket] += 1;
+        self.request_bytes += request_bytes;
+        self.response_bytes += response_bytes;
+        if self.recent_ms.len() == RECENT_SAMPLES {
+            self.recent_ms.pop_front();
+        }
+        self.recent_ms.push_back(ms);
+    }
+
+    fn summary(&self, name: &str) -> TimingSummary {
+        let mut recent: Vec<f64> = self.recent_ms.iter().copied().collect();
+        recent.sort_by(f64::total_cmp);
+        let percentile = |q: f64| {
+            let last = recent.len().saturating_sub(1);
+            recent
+                .get((last as f64 * q).round() as usize)
+                .copied()
+                .unwrap_or(0.0)
+        };
+        TimingSummary {
+            name: name.to_string(),
+            calls: self.calls,
+            failures: self.failures,
+            total_ms: self.total_ms,
+            mean_ms: if self.calls == 0 {
+                0.0
+            } else {
+                self.total_ms / self.calls as f64
+            },
+            p50_ms: percentile(0.5),
+            p95_ms: percentile(0.95),
+            max_ms: self.max_ms,
+            request_bytes: self.request_bytes,
+            response_bytes: self.response_bytes,
+        }
+    }
+}
+
+/// Timings for one command or stage. Percentiles cover the most recent calls.
+#[derive(Debug, Clone, Serialize)]
+pub struct TimingSummary {
+    pub name: String,
+    pub calls: u64,
+    pub failures: u64,
+    pub total_ms: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+    /// Payload sizes; approximate for binary responses.
+    pub request_bytes: u64,
+    pub response_bytes: u64,
+}
+
+/// What `get_command_metrics` returns: commands and stages, slowest in total first.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsReport {
+    pub enabled: bool,
+    /// When recording started, in milliseconds since the Unix epoch.
+    pub since: i64,
+    pub commands: Vec<TimingSummary>,
+    /// Named parts of command handling, such as embeddings or vector search.
+    pub stages: Vec<TimingSummary>,
+}
+
+fn metrics_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(APP_IDENTIFIER)
+        .join(METRICS_FILE_NAME)
+}
+
+/// Turns recording on or off and updates the export settings. The first time
+/// recording is turned on, earlier metrics are loaded from disk and a task is
+/// started that saves them (and exports them, with an OTLP endpoint) every
+/// export interval.
+pub fn configure(config: Option<&TelemetryConfig>) {
+    let config = config.cloned().unwrap_or_default();
+    *EXPORT.lock() = ExportSettings {
+        endpoint: config.otlp_endpoint.filter(|e| !e.trim().is_empty()),
+        interval: config
+            .export_interval_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_EXPORT_INTERVAL),
+    };
+    if config.enabled {
+        start_flusher();
+    }
+    if ENABLED.swap(config.enabled, Ordering::Relaxed) != config.enabled {
+        info!(
+            "Command telemetry {}",
+            if config.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+    }
+}
+
+fn start_flusher() {
+    if FLUSHER.set(()).is_err() {
+        return;
+    }
+    load();
+    tauri::async_runtime::spawn(async {
+        loop {
+            let interval = EXPORT.lock().interval;
+            tokio::time::sleep(interval).await;
+            if !ENABLED.load(Ordering::Relaxed) {
+                continue;
+            }
+            save();
+            let endpoint = EXPORT.lock().endpoint.clone();
+            // Offline, metrics wait for the next export after going back online
+            if let Some(endpoint) = endpoint.filter(|endpoint| ensure_reachable(endpoint).is_ok()) {
+                if let Err(e) = export(&endpoint).await {
+                    warn!("Failed to export metrics to {}: {}", endpoint, e);
+                }
+            }
+        }
+    });
+}
+
+fn load() {
+    let path = metrics_path();
+    let loaded = fs::read_to_string(&path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<Metrics>(&json).ok());
+    let mut metrics = METRICS.lock();
+    match loaded {
+        Some(loaded) => *metrics = loaded,
+        None if metrics.since == 0 => metrics.since = Utc::now().timestamp_millis(),
+        None => {}
+    }
+}
+
+/// Writes the metrics to the app data dir, if any were recorded.
+pub fn save() {
+    let json = {
+        let metrics = METRICS.lock();
+        if metrics.commands.is_empty() && metrics.stages.is_empty() {
+            return;
+        }
+        serde_json::to_string(&*metrics)
+    };
+    let path = metrics_path();
+    let result = json.map_err(|e| e.to_string()).and_then(|json| {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, json).map_err(|e| e.to_string())?;
+        fs::rename(&temp, &path).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        warn!("Failed to save metrics to {}: {}", path.display(), e);
+    }
+}
+
+/// Forgets everything recorded so far, on disk too.
+pub fn reset() -> Result<(), String> {
+    *METRICS.lock() = Metrics {
+        since: Utc::now().timestamp_millis(),
+        ..Metrics::default()
+    };
+    match fs::remove_file(metrics_path()) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to delete the metrics file: {}", e)),
+    }
+}
+
+pub fn report() -> MetricsReport {
+    let metrics = METRICS.lock();
+    let summarize = |stats: &BTreeMap<String, TimingStats>| {
+        let mut summaries: Vec<TimingSummary> = stats
+            .iter()
+            .map(|(name, stats)| stats.summary(name))
+            .collect();
+        summaries.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+        summaries
+    };
+    MetricsReport {
+        enabled: ENABLED.load(Ordering::Relaxed),
+        since: metrics.since,
+        commands: summarize(&metrics.commands),
+        stages: summarize(&metrics.stages),
+    }
+}
+
+/// Times part of handling a command until dropped, e.g. `stage("embeddings")`.
+pub fn stage(name: &'static str) -> StageTimer {
+    StageTimer {
+        name,
+        started: Instant::now(),
+    }
+}
+
+pub struct StageTimer {
+    name: &'static str,
+    started: Instant,
+}
+
+impl Drop for StageTimer {
+    fn drop(&mut self) {
+        if ENABLED.load(Ordering::Relaxed) {
+            METRICS
+                .lock()
+                .stages
+                .entry(self.name.to_string())
+                .or_default()
+                .record(self.started.elapsed(), false, 0, 0);
+        }
+    }
+}
+
+/// Times every command from Tauri's IPC spans: from when a request is handed
+/// to its command until the response is sent back.
+pub fn layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    // Interest is cached per callsite, so only the target is checked there;
+    // whether recording is on is checked for every span
+    let filter = dynamic_filter_fn(|metadata, _| {
+        ENABLED.load(Ordering::Relaxed) && metadata.target().starts_with(IPC_TARGET)
+    })
+    .with_callsite_filter(|metadata| {
+        if metadata.target().starts_with(IPC_TARGET) {
+            Interest::sometimes()
+        } else {
+            Interest::never()
+        }
+    });
+    IpcMetrics.with_filter(filter)
+}
+
+/// Request size, kept on Tauri's `ipc::request` span.
+struct RequestSize(u64);
+
+/// A command being handled, kept on Tauri's `ipc::request::handle` span.
+struct PendingCommand {
+    command: String,
+    started: Instant,
+    request_bytes: u64,
+    elapsed: Option<Duration>,
+    response_bytes: u64,
+    failed: bool,
+}
+
+struct IpcMetrics;
+
+impl<S> Layer<S> for IpcMetrics
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = IpcFields::default();
+        attrs.record(&mut fields);
+
+        match attrs.metadata().name() {
+            "ipc::request" => {
+                span.extensions_mut()
+                    .insert(RequestSize(fields.request_bytes.unwrap_or(0)));
+            }
+            "ipc::request::handle" => {
+                let request_bytes = span
+                    .parent()
+                    .and_then(|parent| parent.extensions().get::<RequestSize>().map(|s| s.0))
+                    .unwrap_or(0);
+                span.extensions_mut().insert(PendingCommand {
+                    command: fields.command.unwrap_or_default(),
+                    started: Instant::now(),
+                    request_bytes,
+                    elapsed: None,
+                    response_bytes: 0,
+                    failed: false,
+                });
+            }
+            // Created under the handle span once the command has responded
+            "ipc::request::respond" => {
+                if let Some(handle) = span.parent() {
+                    let mut extensions = handle.extensions_mut();
+                    if let Some(pending) = extensions.get_mut::<PendingCommand>() {
+                        pending.elapsed = Some(pending.started.elapsed());
+                    }
+                }
+            }
+            // Created inside the respond span, carrying the response itself
+            "ipc::request::response" => {
+                let Some(handle) = span.parent().and_then(|respond| respond.parent()) else {
+                    return;
+                };
+                let mut extensions = handle.extensions_mut();
+                if let Some(pending) = extensions.get_mut::<PendingCommand>() {
+                    pending.response_bytes = fields.response_bytes.unwrap_or(0);
+                    pending.failed = fields.failed;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if span.name() != "ipc::request" {
+            return;
+        }
+        let mut fields = IpcFields::default();
+        values.record(&mut fields);
+        if let Some(bytes) = fields.request_bytes {
+            span.extensions_mut().replace(RequestSize(bytes));
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        if span.name() != "ipc::request::handle" {
+            return;
+        }
+        let Some(pending) = span.extensions_mut().remove::<PendingCommand>() else {
+            return;
+        };
+        // Requests dropped without a response aren't timed
+        let Some(elapsed) = pending.elapsed else {
+            return;
+        };
+        METRICS
+            .lock()
+            .commands
+            .entry(pending.command)
+            .or_default()
+            .record(
+                elapsed,
+                pending.failed,
+                pending.request_bytes,
+                pending.response_bytes,
+            );
+    }
+}
+
+#[derive(Default)]
+struct IpcFields {
+    command: Option<String>,
+    request_bytes: Option<u64>,
+    response_bytes: Option<u64>,
+    failed: bool,
+}
+
+impl Visit for IpcFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "cmd" => self.command = Some(value.to_string()),
+            "request" => self.request_bytes = Some(value.len() as u64),
+            "response" => {
+                self.response_bytes = Some(value.len() as u64);
+                // The post-message transport reports errors as a debug-printed
+                // `InvokeError` in this field rather than in `error`
+                self.failed = value.starts_with("InvokeError(");
+            }
+            "error" => {
+                self.response_bytes = Some(value.len() as u64);
+                self.failed = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record_str(field, &format!("{:?}", value));
+    }
+}
+
+/// Sends the cumulative metrics to an OTLP/HTTP collector as JSON.
+async fn export(endpoint: &str) -> Result<(), String> {
+    let body = {
+        let metrics = METRICS.lock();
+        let start = (metrics.since * 1_000_000).to_string();
+        let now = Utc::now()
+            .timestamp_nanos_opt()
+            .unwrap_or_default()
+            .to_string();
+        let point = |key: &str, name: &str, value: Value| {
+            let mut point = json!({
+                "attributes": [{ "key": key, "value": { "stringValue": name } }],
+                "startTimeUnixNano": start,
+                "timeUnixNano": now,
+            });
+            if let (Value::Object(point), Value::Object(value)) = (&mut point, value) {
+                point.extend(value);
+            }
+            point
+        };
+        let histogram = |key: &str, stats: &BTreeMap<String, TimingStats>| {
+            let points: Vec<Value> = stats
+                .iter()
+                .map(|(name, stats)| {
+                    point(
+                        key,
+                        name,
+                        json!({
+                            "count": stats.calls.to_string(),
+                            "sum": stats.total_ms,
+                            "max": stats.max_ms,
+                            "bucketCounts": stats.buckets.iter().map(u64::to_string).collect::<Vec<_>>(),
+                            "explicitBounds": BUCKET_BOUNDS_MS,
+                        }),
+                    )
+                })
+                .collect();
+            json!({ "aggregationTemporality": 2, "dataPoints": points })
+        };
+        let counter = |value: fn(&TimingStats) -> u64| {
+            let points: Vec<Value> = metrics
+                .commands
+                .iter()
+                .map(|(name, stats)| {
+                    point(
+                        "command",
+                        name,
+                        json!({ "asInt": value(stats).to_string() }),
+                    )
+                })
+                .collect();
+            json!({ "aggregationTemporality": 2, "isMonotonic": true, "dataPoints": points })
+        };
+
+        json!({
+            "resourceMetrics": [{
+                "resource": {
+                    "attributes": [{ "key": "service.name", "value": { "stringValue": "mighty" } }]
+                },
+                "scopeMetrics": [{
+                    "scope": { "name": "mighty.telemetry" },
+                    "metrics": [
+                        {
+                            "name": "mighty.command.duration",
+                            "unit": "ms",
+                            "histogram": histogram("command", &metrics.commands),
+                        },
+                        {
+                            "name": "mighty.command.failures",
+                            "unit": "1",
+                            "sum": counter(|stats| stats.failures),
+                        },
+                        {
+                            "name": "mighty.command.request.size",
+                            "unit": "By",
+                            "sum": counter(|stats| stats.request_bytes),
+                        },
+                        {
+                            "name": "mighty.command.response.size",
+                            "unit": "By",
+                            "sum": counter(|stats| stats.response_bytes),
+                        },
+                        {
+                            "name": "mighty.stage.duration",
+                            "unit": "ms",
+                            "histogram": histogram("stage", &metrics.stages),
+                        },
+                    ],
+                }],
+            }],
+        })
+    };
+
+    let url = format!("{}/v1/metrics", endpoint.trim_end_matches('/'));
+    let response = http_client::client("telemetry")?
+        .post(&url)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("{} returned {}", url, response.status()));
+    }
+    Ok(())
+}
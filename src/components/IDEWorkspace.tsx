@@ -39,9 +39,9 @@ const IDEWorkspace: React.FC = () => {
   const handleFileSelect = useCallback(async (node: FileSystemNode) => {
     if (node.type === 'file') {
       try {
-        const content = await invokeWithAuth('read_file', { path: node.path}, auth0);
+        const file = await invokeWithAuth('read_file', { path: node.path}, auth0);
         if (isMounted.current) {
-          setFileContent(content);
+          setFileContent(file.content);
           setCurrentFile(node);
         }
       } catch (error) {
@@ -171,7 +171,14 @@ const TerminalManager: React.FC<TerminalManagerProps> = ({
       }
       return session;
     } catch (error) {
-      throw new Error(error instanceof Error ? error.message : String(error));
+      // Commands reject with `{ kind, code, message }`
+      const message =
+        error instanceof Error
+          ? error.message
+          : typeof error === "object" && error !== null && "message" in error
+            ? String(error.message)
+            : String(error);
+      throw new Error(message);
     }
   }, []);
 
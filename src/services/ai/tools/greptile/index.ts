@@ -1,7 +1,6 @@
 import { AITool, ToolMetadata, ToolResponse } from "@/types/tools/base";
 import { EventBusAdapter } from "@/types/events";
 import { eventSystem } from "../../../../classes/events/manager";
-import { GreptileConfig } from "@/types/tools/greptile";
 import { invokeWithAuth } from "../../../../lib/auth";
 import { Auth0Context, Auth0ContextInterface } from "@auth0/auth0-react";
 
@@ -194,13 +193,12 @@ export class GreptileService
     //     return this.agent;
     // }
 
+    // The API key lives in the backend's vault, so the connection test is
+    // what tells whether one is configured
     async validateConfig(
-        config: GreptileConfig,
+        _config: GreptileServiceConfig,
         auth0: Auth0ContextInterface,
     ): Promise<boolean> {
-        if (!config.apiKey) {
-            return false;
-        }
         return this.testConnection(auth0);
     }
 
@@ -210,9 +208,8 @@ export class GreptileService
         }
 
         try {
-            return await invokeWithAuth("test_greptile_connection", {
-                config: this.config,
-            }, auth0);
+            // The API key is read on the Rust side
+            return await invokeWithAuth("test_greptile_connection", {}, auth0);
         } catch (error) {
             await this.handleError("CONNECTION_TEST_FAILED", error);
             return false;
@@ -25,7 +25,8 @@ class TauriFileOperations implements FileOperationsService {
     auth0: Auth0ContextInterface,
   ): Promise<string> {
     try {
-      return await invokeWithAuth("read_file", { path }, auth0);
+      const file = await invokeWithAuth("read_file", { path }, auth0);
+      return file.content;
     } catch (error) {
       console.error("Error reading file:", error);
       throw new Error(`Failed to read file: ${error}`);